@@ -40,6 +40,10 @@ pub enum SecurityEvent {
     /// Consensus received an equivocating vote
     ConsensusEquivocatingVote,
 
+    /// Consensus received a vote signed by its own key that it did not send, which means that
+    /// another instance is signing with the same key
+    ConsensusConflictingOwnVote,
+
     /// Consensus received an invalid proposal
     InvalidConsensusProposal,
 
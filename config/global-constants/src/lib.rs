@@ -10,6 +10,7 @@
 
 /// Definitions of global cryptographic keys (e.g., as held in secure storage)
 pub const CONSENSUS_KEY: &str = "consensus";
+pub const CONSENSUS_BLS_KEY: &str = "consensus_bls";
 pub const EXECUTION_KEY: &str = "execution";
 pub const FULLNODE_NETWORK_KEY: &str = "fullnode_network";
 pub const DIEM_ROOT_KEY: &str = "diem_root";
@@ -23,5 +24,6 @@ pub const VALIDATOR_NETWORK_KEY: &str = "validator_network";
 
 /// Definitions of global data items (e.g., as held in secure storage)
 pub const SAFETY_DATA: &str = "safety_data";
+pub const SAFETY_RULES_LEASE: &str = "safety_rules_lease";
 pub const WAYPOINT: &str = "waypoint";
 pub const GENESIS_WAYPOINT: &str = "genesis-waypoint";
@@ -16,6 +16,10 @@ pub enum Command {
     InsertWaypoint(diem_management::waypoint::InsertWaypoint),
     #[structopt(about = "Submits an Ed25519PublicKey for the diem root")]
     DiemRootKey(crate::key::DiemRootKey),
+    #[structopt(
+        about = "Produces genesis and node configs for a local network from a layout file"
+    )]
+    LocalGenesis(crate::local_genesis::LocalGenesis),
     #[structopt(about = "Submits an Ed25519PublicKey for the operator")]
     OperatorKey(crate::key::OperatorKey),
     #[structopt(about = "Submits an Ed25519PublicKey for the owner")]
@@ -38,6 +42,7 @@ pub enum CommandName {
     Genesis,
     InsertWaypoint,
     DiemRootKey,
+    LocalGenesis,
     OperatorKey,
     OwnerKey,
     SetLayout,
@@ -54,6 +59,7 @@ impl From<&Command> for CommandName {
             Command::Genesis(_) => CommandName::Genesis,
             Command::InsertWaypoint(_) => CommandName::InsertWaypoint,
             Command::DiemRootKey(_) => CommandName::DiemRootKey,
+            Command::LocalGenesis(_) => CommandName::LocalGenesis,
             Command::OperatorKey(_) => CommandName::OperatorKey,
             Command::OwnerKey(_) => CommandName::OwnerKey,
             Command::SetLayout(_) => CommandName::SetLayout,
@@ -72,6 +78,7 @@ impl std::fmt::Display for CommandName {
             CommandName::Genesis => "genesis",
             CommandName::InsertWaypoint => "insert-waypoint",
             CommandName::DiemRootKey => "diem-root-key",
+            CommandName::LocalGenesis => "local-genesis",
             CommandName::OperatorKey => "operator-key",
             CommandName::OwnerKey => "owner-key",
             CommandName::SetLayout => "set-layout",
@@ -93,6 +100,7 @@ impl Command {
             Command::Genesis(_) => self.genesis().map(|_| "Success!".to_string()),
             Command::InsertWaypoint(_) => self.insert_waypoint().map(|_| "Success!".to_string()),
             Command::DiemRootKey(_) => self.diem_root_key().map(|_| "Success!".to_string()),
+            Command::LocalGenesis(_) => self.local_genesis().map(|report| report.to_string()),
             Command::OperatorKey(_) => self.operator_key().map(|_| "Success!".to_string()),
             Command::OwnerKey(_) => self.owner_key().map(|_| "Success!".to_string()),
             Command::SetLayout(_) => self.set_layout().map(|_| "Success!".to_string()),
@@ -121,6 +129,10 @@ impl Command {
         execute_command!(self, Command::DiemRootKey, CommandName::DiemRootKey)
     }
 
+    pub fn local_genesis(self) -> Result<crate::local_genesis::LocalGenesisReport, Error> {
+        execute_command!(self, Command::LocalGenesis, CommandName::LocalGenesis)
+    }
+
     pub fn operator_key(self) -> Result<Ed25519PublicKey, Error> {
         execute_command!(self, Command::OperatorKey, CommandName::OperatorKey)
     }
@@ -0,0 +1,749 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Builds genesis for a local network from a single layout file and a directory of key files,
+//! instead of collecting each participant's keys and validator config in a shared storage. Every
+//! input is validated before anything is written, and `--dry-run` only reports the validator set.
+
+use consensus_types::safety_data::SafetyData;
+use diem_config::{
+    config::{
+        Identity, NodeConfig, OnDiskStorageConfig, SafetyRulesService, SecureBackend,
+        WaypointConfig, HANDSHAKE_VERSION,
+    },
+    utils::validator_owner_account_from_name,
+};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    x25519, PrivateKey,
+};
+use diem_global_constants::{
+    CONSENSUS_KEY, EXECUTION_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY,
+    OWNER_ACCOUNT, OWNER_KEY, SAFETY_DATA, VALIDATOR_NETWORK_KEY, WAYPOINT,
+};
+use diem_management::{error::Error, storage::to_x25519, validator_config::validate_address};
+use diem_network_address_encryption::Encryptor;
+use diem_secure_storage::{CryptoStorage, KVStorage, OnDiskStorage, Storage};
+use diem_transaction_builder::stdlib as transaction_builder;
+use diem_types::{
+    account_address::{self, AccountAddress},
+    chain_id::{self, ChainId},
+    network_address::{self, NetworkAddress},
+    transaction::{ScriptFunction, Transaction},
+    waypoint::Waypoint,
+};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+use vm_genesis::{OperatorAssignment, OperatorRegistration};
+
+const GENESIS_FILE: &str = "genesis.blob";
+const WAYPOINT_FILE: &str = "waypoint.txt";
+const NODE_CONFIG_FILE: &str = "node.yaml";
+const SECURE_STORAGE_FILE: &str = "secure_storage.json";
+
+/// The layout of a local network. Keys are read from the key directory, where the diem root and
+/// treasury compliance keys are `<name>.key`, each owner has `<owner>/owner.key` and each operator
+/// has `<operator>/{operator,consensus,execution,validator_network,fullnode_network}.key`, all
+/// in the format written by `generate-key`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalLayout {
+    #[serde(deserialize_with = "chain_id::deserialize_config_chain_id")]
+    pub chain_id: ChainId,
+    pub diem_root: String,
+    /// Defaults to the diem root key
+    pub treasury_compliance: Option<String>,
+    pub validators: Vec<LocalValidator>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalValidator {
+    pub owner: String,
+    pub operator: String,
+    pub validator_address: NetworkAddress,
+    pub fullnode_address: NetworkAddress,
+}
+
+impl LocalLayout {
+    pub fn from_disk(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::UnableToReadFile(path.display().to_string(), e.to_string()))?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::UnableToParseFile(path.display().to_string(), e.to_string()))
+    }
+}
+
+struct ValidatorKeys {
+    owner: Ed25519PrivateKey,
+    operator: Ed25519PrivateKey,
+    consensus: Ed25519PrivateKey,
+    execution: Ed25519PrivateKey,
+    validator_network: Ed25519PrivateKey,
+    fullnode_network: Ed25519PrivateKey,
+}
+
+impl ValidatorKeys {
+    fn load(key_dir: &Path, validator: &LocalValidator) -> Result<Self, Error> {
+        let owner_dir = key_dir.join(&validator.owner);
+        let operator_dir = key_dir.join(&validator.operator);
+        Ok(Self {
+            owner: load_key(&owner_dir.join("owner.key"))?,
+            operator: load_key(&operator_dir.join("operator.key"))?,
+            consensus: load_key(&operator_dir.join("consensus.key"))?,
+            execution: load_key(&operator_dir.join("execution.key"))?,
+            validator_network: load_key(&operator_dir.join("validator_network.key"))?,
+            fullnode_network: load_key(&operator_dir.join("fullnode_network.key"))?,
+        })
+    }
+
+    fn public_keys(&self) -> Vec<(&'static str, Ed25519PublicKey)> {
+        vec![
+            (OWNER_KEY, self.owner.public_key()),
+            (OPERATOR_KEY, self.operator.public_key()),
+            (CONSENSUS_KEY, self.consensus.public_key()),
+            (EXECUTION_KEY, self.execution.public_key()),
+            (VALIDATOR_NETWORK_KEY, self.validator_network.public_key()),
+            (FULLNODE_NETWORK_KEY, self.fullnode_network.public_key()),
+        ]
+    }
+
+    fn into_private_keys(self) -> Vec<(&'static str, Ed25519PrivateKey)> {
+        vec![
+            (OWNER_KEY, self.owner),
+            (OPERATOR_KEY, self.operator),
+            (CONSENSUS_KEY, self.consensus),
+            (EXECUTION_KEY, self.execution),
+            (VALIDATOR_NETWORK_KEY, self.validator_network),
+            (FULLNODE_NETWORK_KEY, self.fullnode_network),
+        ]
+    }
+}
+
+fn load_key(path: &Path) -> Result<Ed25519PrivateKey, Error> {
+    let bytes = fs::read(path)
+        .map_err(|e| Error::UnableToReadFile(path.display().to_string(), e.to_string()))?;
+    bcs::from_bytes(&bytes)
+        .map_err(|e| Error::UnableToParseFile(path.display().to_string(), e.to_string()))
+}
+
+/// A validator as it is registered in genesis.
+#[derive(Debug)]
+pub struct ValidatorReport {
+    pub owner: String,
+    pub owner_account: AccountAddress,
+    pub operator: String,
+    pub operator_account: AccountAddress,
+    pub consensus_key: Ed25519PublicKey,
+    pub validator_network_key: x25519::PublicKey,
+    pub validator_address: NetworkAddress,
+    pub fullnode_address: NetworkAddress,
+}
+
+/// The validator set of the generated genesis.
+#[derive(Debug)]
+pub struct LocalGenesisReport {
+    pub chain_id: ChainId,
+    pub waypoint: Waypoint,
+    pub validators: Vec<ValidatorReport>,
+}
+
+impl fmt::Display for LocalGenesisReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Chain id: {}", self.chain_id)?;
+        writeln!(f, "Waypoint: {}", self.waypoint)?;
+        writeln!(f, "Validators: {}", self.validators.len())?;
+        for validator in &self.validators {
+            writeln!(
+                f,
+                "  {} (owner account {})",
+                validator.owner, validator.owner_account
+            )?;
+            writeln!(
+                f,
+                "    operator: {} (account {})",
+                validator.operator, validator.operator_account
+            )?;
+            writeln!(f, "    consensus key: {}", validator.consensus_key)?;
+            writeln!(
+                f,
+                "    validator network key: {}",
+                validator.validator_network_key
+            )?;
+            writeln!(f, "    validator address: {}", validator.validator_address)?;
+            writeln!(f, "    fullnode address: {}", validator.fullnode_address)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct LocalGenesis {
+    /// Path to the layout of the local network
+    #[structopt(long)]
+    layout_path: PathBuf,
+    /// Directory containing the keys named in the layout
+    #[structopt(long)]
+    key_dir: PathBuf,
+    /// Directory for genesis, the waypoint, and the secure storage and node config of each
+    /// operator
+    #[structopt(long, required_unless("dry-run"))]
+    output_dir: Option<PathBuf>,
+    /// Validates the inputs and reports the validator set without writing anything
+    #[structopt(long)]
+    dry_run: bool,
+    /// Skip checking that the addresses resolve
+    #[structopt(long)]
+    disable_address_validation: bool,
+}
+
+impl LocalGenesis {
+    pub fn execute(self) -> Result<LocalGenesisReport, Error> {
+        let layout = LocalLayout::from_disk(&self.layout_path)?;
+        check_names(&layout)?;
+
+        let diem_root_key = load_key(&self.key_dir.join(format!("{}.key", layout.diem_root)))?;
+        let treasury_compliance_key = layout
+            .treasury_compliance
+            .as_ref()
+            .map(|name| load_key(&self.key_dir.join(format!("{}.key", name))))
+            .transpose()?;
+        let validator_keys = layout
+            .validators
+            .iter()
+            .map(|validator| ValidatorKeys::load(&self.key_dir, validator))
+            .collect::<Result<Vec<_>, Error>>()?;
+        check_keys(
+            &layout,
+            &diem_root_key,
+            treasury_compliance_key.as_ref(),
+            &validator_keys,
+        )?;
+        self.check_addresses(&layout)?;
+
+        let mut operator_assignments: Vec<OperatorAssignment> = Vec::new();
+        let mut operator_registrations: Vec<OperatorRegistration> = Vec::new();
+        let mut validators = Vec::new();
+        for (validator, keys) in layout.validators.iter().zip(&validator_keys) {
+            let (report, registration) = register(validator, keys)?;
+            operator_assignments.push((
+                Some(keys.owner.public_key()),
+                validator.owner.as_bytes().to_vec(),
+                transaction_builder::encode_set_validator_operator_script_function(
+                    validator.operator.as_bytes().to_vec(),
+                    report.operator_account,
+                )
+                .into_script_function(),
+            ));
+            operator_registrations.push((
+                keys.operator.public_key(),
+                validator.operator.as_bytes().to_vec(),
+                registration,
+            ));
+            validators.push(report);
+        }
+
+        let diem_root_public_key = diem_root_key.public_key();
+        let genesis = vm_genesis::encode_genesis_transaction(
+            diem_root_public_key.clone(),
+            treasury_compliance_key
+                .map(|key| key.public_key())
+                .unwrap_or(diem_root_public_key),
+            &operator_assignments,
+            &operator_registrations,
+            // Only have an allowlist of stdlib scripts
+            None,
+            layout.chain_id,
+        );
+        let waypoint = crate::waypoint::generate_waypoint(&genesis)?;
+
+        if !self.dry_run {
+            // Guaranteed by structopt
+            let output_dir = self.output_dir.as_ref().unwrap();
+            write_outputs(output_dir, &layout, validator_keys, &genesis, waypoint)?;
+        }
+
+        Ok(LocalGenesisReport {
+            chain_id: layout.chain_id,
+            waypoint,
+            validators,
+        })
+    }
+
+    fn check_addresses(&self, layout: &LocalLayout) -> Result<(), Error> {
+        let mut addresses = HashMap::new();
+        for validator in &layout.validators {
+            for (name, address) in vec![
+                ("validator address", &validator.validator_address),
+                ("fullnode address", &validator.fullnode_address),
+            ] {
+                if !self.disable_address_validation {
+                    validate_address(name, address)?;
+                }
+                if let Some(other) = addresses.insert(address.clone(), &validator.operator) {
+                    return Err(Error::CommandArgumentError(format!(
+                        "{} is used by both {} and {}",
+                        address, other, validator.operator
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_names(layout: &LocalLayout) -> Result<(), Error> {
+    if layout.validators.is_empty() {
+        return Err(Error::CommandArgumentError(
+            "The layout has no validators".into(),
+        ));
+    }
+
+    let mut names = HashSet::new();
+    let roles = layout.validators.iter().flat_map(|validator| {
+        vec![
+            ("owner", &validator.owner),
+            ("operator", &validator.operator),
+        ]
+    });
+    for (role, name) in roles {
+        if name.is_empty() || name.contains(std::path::is_separator) {
+            return Err(Error::CommandArgumentError(format!(
+                "Invalid {} name '{}'",
+                role, name
+            )));
+        }
+        if !names.insert(name) {
+            return Err(Error::CommandArgumentError(format!(
+                "The name '{}' is used by more than one owner or operator",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Ensures that no key is used for more than one purpose, except for the treasury compliance key
+/// defaulting to the diem root key.
+fn check_keys(
+    layout: &LocalLayout,
+    diem_root_key: &Ed25519PrivateKey,
+    treasury_compliance_key: Option<&Ed25519PrivateKey>,
+    validator_keys: &[ValidatorKeys],
+) -> Result<(), Error> {
+    let mut keys = vec![(layout.diem_root.clone(), diem_root_key.public_key())];
+    if let (Some(name), Some(key)) = (&layout.treasury_compliance, treasury_compliance_key) {
+        keys.push((name.clone(), key.public_key()));
+    }
+    for (validator, validator_keys) in layout.validators.iter().zip(validator_keys) {
+        for (key_name, key) in validator_keys.public_keys() {
+            let owner = if key_name == OWNER_KEY {
+                &validator.owner
+            } else {
+                &validator.operator
+            };
+            keys.push((format!("{}/{}", owner, key_name), key));
+        }
+    }
+
+    let mut public_keys = HashMap::new();
+    for (name, key) in keys {
+        if let Some(other) = public_keys.insert(key.to_bytes(), name.clone()) {
+            return Err(Error::CommandArgumentError(format!(
+                "{} and {} are the same key",
+                other, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the validator config registration of the validator, checking that its encrypted
+/// validator address decrypts to the original address.
+fn register(
+    validator: &LocalValidator,
+    keys: &ValidatorKeys,
+) -> Result<(ValidatorReport, ScriptFunction), Error> {
+    let owner_account = validator_owner_account_from_name(validator.owner.as_bytes());
+    let operator_account = account_address::from_public_key(&keys.operator.public_key());
+    let consensus_key = keys.consensus.public_key();
+    let validator_network_key = to_x25519(keys.validator_network.public_key())?;
+    let fullnode_network_key = to_x25519(keys.fullnode_network.public_key())?;
+
+    let validator_address = validator
+        .validator_address
+        .clone()
+        .append_prod_protos(validator_network_key, HANDSHAKE_VERSION);
+    let fullnode_address = validator
+        .fullnode_address
+        .clone()
+        .append_prod_protos(fullnode_network_key, HANDSHAKE_VERSION);
+
+    // Node storages are initialized with the same key, see `initialize_storage`
+    let encryptor = Encryptor::for_testing();
+    let encrypted_addresses = encryptor
+        .encrypt(&[validator_address.clone()], owner_account, 0)
+        .map_err(|e| {
+            Error::UnexpectedError(format!(
+                "Error encrypting the address of {}: {}",
+                validator.operator, e
+            ))
+        })?;
+    let decrypted_addresses = encryptor
+        .decrypt(&encrypted_addresses, owner_account)
+        .map_err(|e| Error::NetworkAddressDecodeError(e.to_string()))?;
+    if decrypted_addresses != vec![validator_address.clone()] {
+        return Err(Error::NetworkAddressDecodeError(format!(
+            "The address of {} decrypts to {:?}",
+            validator.operator, decrypted_addresses
+        )));
+    }
+
+    let registration = transaction_builder::encode_register_validator_config_script_function(
+        owner_account,
+        consensus_key.to_bytes().to_vec(),
+        encrypted_addresses,
+        bcs::to_bytes(&vec![fullnode_address.clone()])
+            .map_err(|e| Error::BCS("fullnode address".into(), e))?,
+    )
+    .into_script_function();
+
+    let report = ValidatorReport {
+        owner: validator.owner.clone(),
+        owner_account,
+        operator: validator.operator.clone(),
+        operator_account,
+        consensus_key,
+        validator_network_key,
+        validator_address,
+        fullnode_address,
+    };
+    Ok((report, registration))
+}
+
+fn write_outputs(
+    output_dir: &Path,
+    layout: &LocalLayout,
+    validator_keys: Vec<ValidatorKeys>,
+    genesis: &Transaction,
+    waypoint: Waypoint,
+) -> Result<(), Error> {
+    let io_error = |path: &Path, e| Error::IO(path.display().to_string(), e);
+    // Check for existing secure storages before writing anything, so that a rejected run leaves
+    // the output directory untouched
+    for validator in &layout.validators {
+        let storage_path = output_dir
+            .join(&validator.operator)
+            .join(SECURE_STORAGE_FILE);
+        if storage_path.exists() {
+            return Err(Error::CommandArgumentError(format!(
+                "Refusing to overwrite the existing secure storage {}",
+                storage_path.display()
+            )));
+        }
+    }
+
+    fs::create_dir_all(output_dir).map_err(|e| io_error(output_dir, e))?;
+    // Secure storage paths in node configs are absolute, so that the configs may be moved
+    let output_dir = output_dir
+        .canonicalize()
+        .map_err(|e| io_error(output_dir, e))?;
+
+    let genesis_path = output_dir.join(GENESIS_FILE);
+    let genesis_bytes = bcs::to_bytes(genesis).map_err(|e| Error::BCS(GENESIS_FILE.into(), e))?;
+    fs::write(&genesis_path, genesis_bytes).map_err(|e| io_error(&genesis_path, e))?;
+    let waypoint_path = output_dir.join(WAYPOINT_FILE);
+    fs::write(&waypoint_path, waypoint.to_string()).map_err(|e| io_error(&waypoint_path, e))?;
+
+    for (validator, keys) in layout.validators.iter().zip(validator_keys) {
+        let node_dir = output_dir.join(&validator.operator);
+        fs::create_dir_all(&node_dir).map_err(|e| io_error(&node_dir, e))?;
+
+        let storage_path = node_dir.join(SECURE_STORAGE_FILE);
+        initialize_storage(
+            Storage::from(OnDiskStorage::new(storage_path.clone())),
+            validator,
+            keys,
+            waypoint,
+        )?;
+
+        let mut storage_config = OnDiskStorageConfig::default();
+        storage_config.path = storage_path;
+        storage_config.set_data_dir(node_dir.clone());
+        let backend = SecureBackend::OnDiskStorage(storage_config);
+
+        let mut config = node_config(validator, &node_dir, backend, genesis);
+        let config_path = node_dir.join(NODE_CONFIG_FILE);
+        config
+            .save(&config_path)
+            .map_err(|e| Error::UnexpectedError(format!("{}: {}", config_path.display(), e)))?;
+    }
+    Ok(())
+}
+
+/// Initializes the secure storage of a validator, like `StorageHelper::initialize` does for tests
+/// but with the keys of the layout.
+fn initialize_storage(
+    mut storage: Storage,
+    validator: &LocalValidator,
+    keys: ValidatorKeys,
+    waypoint: Waypoint,
+) -> Result<(), Error> {
+    let write_error = |name: &'static str, e: diem_secure_storage::Error| {
+        Error::StorageWriteError("validator", name, e.to_string())
+    };
+    let operator_account = account_address::from_public_key(&keys.operator.public_key());
+    for (name, key) in keys.into_private_keys() {
+        storage
+            .import_private_key(name, key)
+            .map_err(|e| write_error(name, e))?;
+    }
+    storage
+        .set(
+            OWNER_ACCOUNT,
+            validator_owner_account_from_name(validator.owner.as_bytes()),
+        )
+        .map_err(|e| write_error(OWNER_ACCOUNT, e))?;
+    storage
+        .set(OPERATOR_ACCOUNT, operator_account)
+        .map_err(|e| write_error(OPERATOR_ACCOUNT, e))?;
+    storage
+        .set(SAFETY_DATA, SafetyData::new(0, 0, 0, None))
+        .map_err(|e| write_error(SAFETY_DATA, e))?;
+    storage
+        .set(WAYPOINT, waypoint)
+        .map_err(|e| write_error(WAYPOINT, e))?;
+
+    let mut encryptor = Encryptor::new(storage);
+    encryptor
+        .initialize()
+        .and_then(|()| {
+            encryptor.add_key(
+                network_address::encrypted::TEST_SHARED_VAL_NETADDR_KEY_VERSION,
+                network_address::encrypted::TEST_SHARED_VAL_NETADDR_KEY,
+            )
+        })
+        .map_err(|e| Error::UnexpectedError(format!("Unable to initialize encryptor: {}", e)))
+}
+
+fn node_config(
+    validator: &LocalValidator,
+    node_dir: &Path,
+    backend: SecureBackend,
+    genesis: &Transaction,
+) -> NodeConfig {
+    let mut config = NodeConfig::default_for_validator();
+    // Avoid port conflicts between the nodes of the local network
+    config.randomize_ports();
+    config.set_data_dir(node_dir.to_path_buf());
+
+    let validator_network = config.validator_network.as_mut().unwrap();
+    validator_network.listen_address = validator.validator_address.clone();
+    let validator_identity = validator_network.identity_from_storage();
+    validator_network.identity = Identity::from_storage(
+        validator_identity.key_name,
+        validator_identity.peer_id_name,
+        backend.clone(),
+    );
+    validator_network.network_address_key_backend = Some(backend.clone());
+
+    let fullnode_network = &mut config.full_node_networks[0];
+    fullnode_network.listen_address = validator.fullnode_address.clone();
+    let fullnode_identity = fullnode_network.identity_from_storage();
+    fullnode_network.identity = Identity::from_storage(
+        fullnode_identity.key_name,
+        fullnode_identity.peer_id_name,
+        backend.clone(),
+    );
+
+    config.consensus.safety_rules.service = SafetyRulesService::Thread;
+    config.consensus.safety_rules.backend = backend.clone();
+    config.execution.backend = backend.clone();
+    config.base.waypoint = WaypointConfig::FromStorage(backend);
+    config.execution.genesis = Some(genesis.clone());
+    config.execution.genesis_file_location = PathBuf::from(GENESIS_FILE);
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+    use std::collections::BTreeMap;
+
+    const LAYOUT: &str = "\
+        chain_id = \"TESTING\"\n\
+        diem_root = \"diem_root\"\n\
+        \n\
+        [[validators]]\n\
+        owner = \"alice\"\n\
+        operator = \"alice_operator\"\n\
+        validator_address = \"/ip4/127.0.0.1/tcp/6180\"\n\
+        fullnode_address = \"/ip4/127.0.0.1/tcp/6181\"\n\
+        \n\
+        [[validators]]\n\
+        owner = \"bob\"\n\
+        operator = \"bob_operator\"\n\
+        validator_address = \"/ip4/127.0.0.1/tcp/6182\"\n\
+        fullnode_address = \"/ip4/127.0.0.1/tcp/6183\"\n\
+    ";
+
+    struct TestInputs {
+        layout_path: TempPath,
+        key_dir: TempPath,
+    }
+
+    impl TestInputs {
+        fn new(layout: &str) -> Self {
+            let layout_path = TempPath::new();
+            fs::write(layout_path.path(), layout).unwrap();
+
+            let key_dir = TempPath::new();
+            key_dir.create_as_dir().unwrap();
+            let dir = key_dir.path();
+            generate_key::generate_and_save_key(dir.join("diem_root.key"));
+            for name in &["alice", "bob"] {
+                fs::create_dir(dir.join(name)).unwrap();
+                generate_key::generate_and_save_key(dir.join(name).join("owner.key"));
+                let operator_dir = dir.join(format!("{}_operator", name));
+                fs::create_dir(&operator_dir).unwrap();
+                for key in &[
+                    "operator",
+                    "consensus",
+                    "execution",
+                    "validator_network",
+                    "fullnode_network",
+                ] {
+                    generate_key::generate_and_save_key(operator_dir.join(format!("{}.key", key)));
+                }
+            }
+            Self {
+                layout_path,
+                key_dir,
+            }
+        }
+
+        fn local_genesis(&self, output_dir: Option<&Path>) -> LocalGenesis {
+            LocalGenesis {
+                layout_path: self.layout_path.path().to_path_buf(),
+                key_dir: self.key_dir.path().to_path_buf(),
+                output_dir: output_dir.map(Path::to_path_buf),
+                dry_run: output_dir.is_none(),
+                disable_address_validation: false,
+            }
+        }
+    }
+
+    #[test]
+    fn test_local_genesis() {
+        let inputs = TestInputs::new(LAYOUT);
+        let output_dir = TempPath::new();
+
+        // A dry run reports the validator set without writing anything
+        let report = inputs.local_genesis(None).execute().unwrap();
+        assert!(!output_dir.path().exists());
+        assert_eq!(report.chain_id, ChainId::test());
+        let owners: Vec<_> = report.validators.iter().map(|v| v.owner.as_str()).collect();
+        assert_eq!(owners, vec!["alice", "bob"]);
+        let alice_consensus_key =
+            load_key(&inputs.key_dir.path().join("alice_operator/consensus.key")).unwrap();
+        assert_eq!(
+            report.validators[0].consensus_key,
+            alice_consensus_key.public_key()
+        );
+        assert!(report.to_string().contains("alice_operator"));
+
+        let written_report = inputs
+            .local_genesis(Some(output_dir.path()))
+            .execute()
+            .unwrap();
+        assert_eq!(written_report.waypoint, report.waypoint);
+        let waypoint = fs::read_to_string(output_dir.path().join(WAYPOINT_FILE)).unwrap();
+        assert_eq!(waypoint, report.waypoint.to_string());
+
+        for validator in &report.validators {
+            let node_dir = output_dir.path().join(&validator.operator);
+            let config = NodeConfig::load(node_dir.join(NODE_CONFIG_FILE)).unwrap();
+            assert!(config.execution.genesis.is_some());
+
+            let storage = Storage::from(OnDiskStorage::new(node_dir.join(SECURE_STORAGE_FILE)));
+            assert_eq!(
+                storage.get_public_key(CONSENSUS_KEY).unwrap().public_key,
+                validator.consensus_key
+            );
+            assert_eq!(
+                storage.get::<Waypoint>(WAYPOINT).unwrap().value,
+                report.waypoint
+            );
+            assert_eq!(
+                storage.get::<AccountAddress>(OWNER_ACCOUNT).unwrap().value,
+                validator.owner_account
+            );
+        }
+
+        // The secure storages already exist, so a rerun fails without touching any output
+        let outputs = read_dir_recursive(output_dir.path());
+        inputs
+            .local_genesis(Some(output_dir.path()))
+            .execute()
+            .unwrap_err();
+        assert_eq!(read_dir_recursive(output_dir.path()), outputs);
+
+        // Only one of the secure storages exists, and the other one is not created either
+        let alice_storage = output_dir
+            .path()
+            .join("alice_operator")
+            .join(SECURE_STORAGE_FILE);
+        fs::remove_file(&alice_storage).unwrap();
+        inputs
+            .local_genesis(Some(output_dir.path()))
+            .execute()
+            .unwrap_err();
+        assert!(!alice_storage.exists());
+    }
+
+    /// Returns the contents of every file under `dir`, keyed by path.
+    fn read_dir_recursive(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(read_dir_recursive(&path));
+            } else {
+                files.insert(path.clone(), fs::read(&path).unwrap());
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn test_invalid_layouts() {
+        // The same key used for two purposes
+        let inputs = TestInputs::new(LAYOUT);
+        let key_dir = inputs.key_dir.path();
+        fs::copy(
+            key_dir.join("alice_operator/consensus.key"),
+            key_dir.join("bob_operator/consensus.key"),
+        )
+        .unwrap();
+        inputs.local_genesis(None).execute().unwrap_err();
+
+        // The same address used by two validators
+        let inputs = TestInputs::new(&LAYOUT.replace("6182", "6180"));
+        inputs.local_genesis(None).execute().unwrap_err();
+
+        // The same name used for an owner and an operator
+        let inputs =
+            TestInputs::new(&LAYOUT.replace("owner = \"bob\"", "owner = \"alice_operator\""));
+        inputs.local_genesis(None).execute().unwrap_err();
+
+        // An address without a port
+        let inputs = TestInputs::new(&LAYOUT.replace("/tcp/6183", ""));
+        inputs.local_genesis(None).execute().unwrap_err();
+    }
+}
@@ -4,7 +4,7 @@
 use diem_config::config::RocksdbConfig;
 use diem_management::{config::ConfigPath, error::Error, secure_backend::SharedBackend};
 use diem_temppath::TempPath;
-use diem_types::{chain_id::ChainId, waypoint::Waypoint};
+use diem_types::{chain_id::ChainId, transaction::Transaction, waypoint::Waypoint};
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
 use executor::db_bootstrapper;
@@ -33,13 +33,17 @@ impl CreateWaypoint {
         };
 
         let genesis = genesis_helper.execute()?;
+        generate_waypoint(&genesis)
+    }
+}
 
-        let path = TempPath::new();
-        let diemdb = DiemDB::open(&path, false, None, RocksdbConfig::default())
-            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
-        let db_rw = DbReaderWriter::new(diemdb);
+/// Computes the waypoint of the genesis transaction by executing it against an empty database.
+pub fn generate_waypoint(genesis: &Transaction) -> Result<Waypoint, Error> {
+    let path = TempPath::new();
+    let diemdb = DiemDB::open(&path, false, None, RocksdbConfig::default())
+        .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    let db_rw = DbReaderWriter::new(diemdb);
 
-        db_bootstrapper::generate_waypoint::<DiemVM>(&db_rw, &genesis)
-            .map_err(|e| Error::UnexpectedError(e.to_string()))
-    }
+    db_bootstrapper::generate_waypoint::<DiemVM>(&db_rw, genesis)
+        .map_err(|e| Error::UnexpectedError(e.to_string()))
 }
@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account_resource::SimplifiedAccountResource, validator_config::DecryptedValidatorConfig,
+    account_resource::SimplifiedAccountResource, proposal::ProposalReview,
+    safety_data::SafetyDataSnapshot, validator_config::DecryptedValidatorConfig,
     validator_set::DecryptedValidatorInfo, TransactionContext,
 };
 use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
 use diem_management::{error::Error, execute_command};
+use diem_secure_storage::ReplicaStatus;
 use diem_types::{account_address::AccountAddress, waypoint::Waypoint};
+use safety_rules::audit_log::AuditLogSummary;
 use serde::Serialize;
 use structopt::StructOpt;
 
@@ -26,10 +29,14 @@ pub enum Command {
     CreateValidator(crate::governance::CreateValidator),
     #[structopt(about = "Create a new validator operator account")]
     CreateValidatorOperator(crate::governance::CreateValidatorOperator),
+    #[structopt(about = "Export the safety rules data from the validator storage")]
+    ExportSafetyData(crate::safety_data::ExportSafetyData),
     #[structopt(about = "Extract a private key from the validator storage")]
     ExtractPrivateKey(crate::keys::ExtractPrivateKey),
     #[structopt(about = "Extract a public key from the validator storage")]
     ExtractPublicKey(crate::keys::ExtractPublicKey),
+    #[structopt(about = "Import safety rules data into the validator storage")]
+    ImportSafetyData(crate::safety_data::ImportSafetyData),
     #[structopt(about = "Set the waypoint in the validator storage")]
     InsertWaypoint(diem_management::waypoint::InsertWaypoint),
     #[structopt(about = "Prints an account from the validator storage")]
@@ -42,8 +49,14 @@ pub enum Command {
     PrintXKey(crate::print::PrintXKey),
     #[structopt(about = "Prints a waypoint from the validator storage")]
     PrintWaypoint(crate::print::PrintWaypoint),
+    #[structopt(about = "Builds a batch of Diem Root transactions to be reviewed and signed")]
+    ProposeGovernanceActions(crate::proposal::ProposeGovernanceActions),
     #[structopt(about = "Remove a validator from ValidatorSet")]
     RemoveValidator(crate::governance::RemoveValidator),
+    #[structopt(about = "Re-syncs the replicas of a replicated storage")]
+    RepairStorage(crate::replicated_storage::RepairStorage),
+    #[structopt(about = "Displays the transactions and validator set change of a proposal")]
+    ReviewProposal(crate::proposal::ReviewProposal),
     #[structopt(about = "Rotates the consensus key for a validator")]
     RotateConsensusKey(crate::validator_config::RotateConsensusKey),
     #[structopt(about = "Rotates a full node network key")]
@@ -56,12 +69,18 @@ pub enum Command {
     SetValidatorConfig(crate::validator_config::SetValidatorConfig),
     #[structopt(about = "Sets the validator operator")]
     SetValidatorOperator(crate::owner::SetValidatorOperator),
+    #[structopt(about = "Signs the transactions of a proposal with the Diem Root key")]
+    SignProposal(crate::proposal::SignProposal),
+    #[structopt(about = "Submits the transactions of a signed proposal")]
+    SubmitProposal(crate::proposal::SubmitProposal),
     #[structopt(about = "Validates a transaction")]
     ValidateTransaction(crate::validate_transaction::ValidateTransaction),
     #[structopt(about = "Displays the current validator config registered on the blockchain")]
     ValidatorConfig(crate::validator_config::ValidatorConfig),
     #[structopt(about = "Displays the current validator set infos registered on the blockchain")]
     ValidatorSet(crate::validator_set::ValidatorSet),
+    #[structopt(about = "Verifies the hash chain of a safety rules audit log")]
+    VerifySafetyAuditLog(crate::safety_data::VerifySafetyAuditLog),
 }
 
 #[derive(Debug, PartialEq)]
@@ -72,23 +91,31 @@ pub enum CommandName {
     CheckValidatorSetEndpoints,
     CreateValidator,
     CreateValidatorOperator,
+    ExportSafetyData,
     ExtractPrivateKey,
     ExtractPublicKey,
+    ImportSafetyData,
     InsertWaypoint,
     PrintAccount,
     PrintKey,
     PrintXKey,
     PrintWaypoint,
+    ProposeGovernanceActions,
     RemoveValidator,
+    RepairStorage,
+    ReviewProposal,
     RotateConsensusKey,
     RotateOperatorKey,
     RotateFullNodeNetworkKey,
     RotateValidatorNetworkKey,
     SetValidatorConfig,
     SetValidatorOperator,
+    SignProposal,
+    SubmitProposal,
     ValidateTransaction,
     ValidatorConfig,
     ValidatorSet,
+    VerifySafetyAuditLog,
 }
 
 impl From<&Command> for CommandName {
@@ -100,23 +127,31 @@ impl From<&Command> for CommandName {
             Command::CheckValidatorSetEndpoints(_) => CommandName::CheckValidatorSetEndpoints,
             Command::CreateValidator(_) => CommandName::CreateValidator,
             Command::CreateValidatorOperator(_) => CommandName::CreateValidatorOperator,
+            Command::ExportSafetyData(_) => CommandName::ExportSafetyData,
             Command::ExtractPrivateKey(_) => CommandName::ExtractPrivateKey,
             Command::ExtractPublicKey(_) => CommandName::ExtractPublicKey,
+            Command::ImportSafetyData(_) => CommandName::ImportSafetyData,
             Command::InsertWaypoint(_) => CommandName::InsertWaypoint,
             Command::PrintAccount(_) => CommandName::PrintAccount,
             Command::PrintKey(_) => CommandName::PrintKey,
             Command::PrintXKey(_) => CommandName::PrintXKey,
             Command::PrintWaypoint(_) => CommandName::PrintWaypoint,
+            Command::ProposeGovernanceActions(_) => CommandName::ProposeGovernanceActions,
             Command::RemoveValidator(_) => CommandName::RemoveValidator,
+            Command::RepairStorage(_) => CommandName::RepairStorage,
+            Command::ReviewProposal(_) => CommandName::ReviewProposal,
             Command::RotateConsensusKey(_) => CommandName::RotateConsensusKey,
             Command::RotateOperatorKey(_) => CommandName::RotateOperatorKey,
             Command::RotateFullNodeNetworkKey(_) => CommandName::RotateFullNodeNetworkKey,
             Command::RotateValidatorNetworkKey(_) => CommandName::RotateValidatorNetworkKey,
             Command::SetValidatorConfig(_) => CommandName::SetValidatorConfig,
             Command::SetValidatorOperator(_) => CommandName::SetValidatorOperator,
+            Command::SignProposal(_) => CommandName::SignProposal,
+            Command::SubmitProposal(_) => CommandName::SubmitProposal,
             Command::ValidateTransaction(_) => CommandName::ValidateTransaction,
             Command::ValidatorConfig(_) => CommandName::ValidatorConfig,
             Command::ValidatorSet(_) => CommandName::ValidatorSet,
+            Command::VerifySafetyAuditLog(_) => CommandName::VerifySafetyAuditLog,
         }
     }
 }
@@ -130,23 +165,31 @@ impl std::fmt::Display for CommandName {
             CommandName::CheckValidatorSetEndpoints => "check-validator-set-endpoints",
             CommandName::CreateValidator => "create-validator",
             CommandName::CreateValidatorOperator => "create-validator-operator",
+            CommandName::ExportSafetyData => "export-safety-data",
             CommandName::ExtractPrivateKey => "extract-private-key",
             CommandName::ExtractPublicKey => "extract-public-key",
+            CommandName::ImportSafetyData => "import-safety-data",
             CommandName::InsertWaypoint => "insert-waypoint",
             CommandName::PrintAccount => "print-account",
             CommandName::PrintKey => "print-key",
             CommandName::PrintXKey => "print-x-key",
             CommandName::PrintWaypoint => "print-waypoint",
+            CommandName::ProposeGovernanceActions => "propose-governance-actions",
             CommandName::RemoveValidator => "remove-validator",
+            CommandName::RepairStorage => "repair-storage",
+            CommandName::ReviewProposal => "review-proposal",
             CommandName::RotateConsensusKey => "rotate-consensus-key",
             CommandName::RotateOperatorKey => "rotate-operator-key",
             CommandName::RotateFullNodeNetworkKey => "rotate-full-node-network-key",
             CommandName::RotateValidatorNetworkKey => "rotate-validator-network-key",
             CommandName::SetValidatorConfig => "set-validator-config",
             CommandName::SetValidatorOperator => "set-validator-operator",
+            CommandName::SignProposal => "sign-proposal",
+            CommandName::SubmitProposal => "submit-proposal",
             CommandName::ValidateTransaction => "validate-transaction",
             CommandName::ValidatorConfig => "validator-config",
             CommandName::ValidatorSet => "validator-set",
+            CommandName::VerifySafetyAuditLog => "verify-safety-audit-log",
         };
         write!(f, "{}", name)
     }
@@ -165,6 +208,8 @@ impl Command {
             Command::CreateValidatorOperator(cmd) => {
                 Self::print_transaction_context(cmd.execute().map(|(txn_ctx, _)| txn_ctx))
             }
+            Command::ExportSafetyData(cmd) => Self::pretty_print(cmd.execute()),
+            Command::ImportSafetyData(cmd) => Self::pretty_print(cmd.execute()),
             Command::InsertWaypoint(cmd) => Self::print_success(cmd.execute()),
             Command::ExtractPrivateKey(cmd) => Self::print_success(cmd.execute()),
             Command::ExtractPublicKey(cmd) => Self::print_success(cmd.execute()),
@@ -172,7 +217,10 @@ impl Command {
             Command::PrintKey(cmd) => Self::pretty_print(cmd.execute()),
             Command::PrintXKey(cmd) => Self::pretty_print(cmd.execute()),
             Command::PrintWaypoint(cmd) => Self::pretty_print(cmd.execute()),
+            Command::ProposeGovernanceActions(cmd) => Self::pretty_print(cmd.execute()),
             Command::RemoveValidator(cmd) => Self::print_transaction_context(cmd.execute()),
+            Command::RepairStorage(cmd) => Self::pretty_print(cmd.execute()),
+            Command::ReviewProposal(cmd) => Self::pretty_print(cmd.execute()),
             Command::RotateConsensusKey(cmd) => {
                 Self::print_transaction_context(cmd.execute().map(|(txn_ctx, _)| txn_ctx))
             }
@@ -187,9 +235,12 @@ impl Command {
             }
             Command::SetValidatorConfig(cmd) => Self::print_transaction_context(cmd.execute()),
             Command::SetValidatorOperator(cmd) => Self::print_transaction_context(cmd.execute()),
+            Command::SignProposal(cmd) => Self::pretty_print(cmd.execute()),
+            Command::SubmitProposal(cmd) => Self::pretty_print(cmd.execute()),
             Command::ValidateTransaction(cmd) => Self::print_transaction_context(cmd.execute()),
             Command::ValidatorConfig(cmd) => Self::pretty_print(cmd.execute()),
             Command::ValidatorSet(cmd) => Self::pretty_print(cmd.execute()),
+            Command::VerifySafetyAuditLog(cmd) => Self::pretty_print(cmd.execute()),
         }
     }
 
@@ -259,6 +310,14 @@ impl Command {
         )
     }
 
+    pub fn export_safety_data(self) -> Result<SafetyDataSnapshot, Error> {
+        execute_command!(
+            self,
+            Command::ExportSafetyData,
+            CommandName::ExportSafetyData
+        )
+    }
+
     pub fn extract_private_key(self) -> Result<(), Error> {
         execute_command!(
             self,
@@ -275,6 +334,14 @@ impl Command {
         )
     }
 
+    pub fn import_safety_data(self) -> Result<SafetyDataSnapshot, Error> {
+        execute_command!(
+            self,
+            Command::ImportSafetyData,
+            CommandName::ImportSafetyData
+        )
+    }
+
     pub fn insert_waypoint(self) -> Result<(), Error> {
         execute_command!(self, Command::InsertWaypoint, CommandName::InsertWaypoint)
     }
@@ -295,10 +362,26 @@ impl Command {
         execute_command!(self, Command::PrintWaypoint, CommandName::PrintWaypoint)
     }
 
+    pub fn propose_governance_actions(self) -> Result<ProposalReview, Error> {
+        execute_command!(
+            self,
+            Command::ProposeGovernanceActions,
+            CommandName::ProposeGovernanceActions
+        )
+    }
+
     pub fn remove_validator(self) -> Result<TransactionContext, Error> {
         execute_command!(self, Command::RemoveValidator, CommandName::RemoveValidator)
     }
 
+    pub fn repair_storage(self) -> Result<Vec<ReplicaStatus>, Error> {
+        execute_command!(self, Command::RepairStorage, CommandName::RepairStorage)
+    }
+
+    pub fn review_proposal(self) -> Result<ProposalReview, Error> {
+        execute_command!(self, Command::ReviewProposal, CommandName::ReviewProposal)
+    }
+
     pub fn rotate_consensus_key(self) -> Result<(TransactionContext, Ed25519PublicKey), Error> {
         execute_command!(
             self,
@@ -351,6 +434,14 @@ impl Command {
         )
     }
 
+    pub fn sign_proposal(self) -> Result<ProposalReview, Error> {
+        execute_command!(self, Command::SignProposal, CommandName::SignProposal)
+    }
+
+    pub fn submit_proposal(self) -> Result<Vec<TransactionContext>, Error> {
+        execute_command!(self, Command::SubmitProposal, CommandName::SubmitProposal)
+    }
+
     pub fn validate_transaction(self) -> Result<TransactionContext, Error> {
         execute_command!(
             self,
@@ -366,6 +457,14 @@ impl Command {
     pub fn validator_set(self) -> Result<Vec<DecryptedValidatorInfo>, Error> {
         execute_command!(self, Command::ValidatorSet, CommandName::ValidatorSet)
     }
+
+    pub fn verify_safety_audit_log(self) -> Result<AuditLogSummary, Error> {
+        execute_command!(
+            self,
+            Command::VerifySafetyAuditLog,
+            CommandName::VerifySafetyAuditLog
+        )
+    }
 }
 
 /// A result wrapper for displaying either a correct execution result or an error.
@@ -11,6 +11,9 @@ pub mod json_rpc;
 mod keys;
 mod owner;
 mod print;
+mod proposal;
+mod replicated_storage;
+mod safety_data;
 mod validate_transaction;
 mod validator_config;
 mod validator_set;
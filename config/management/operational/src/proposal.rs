@@ -0,0 +1,647 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Diem Root governance actions that are reviewed before submission. A proposal holds a batch of
+//! unsigned transactions along with the validator set they were built against. Reviewers inspect
+//! the resulting validator set change, sign the transactions offline, potentially as members of a
+//! multi-ed25519 key, and the proposal is submitted once enough signatures were collected.
+
+use crate::{
+    json_rpc::JsonRpcClientWrapper, validator_config::DecryptedValidatorConfig, TransactionContext,
+};
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    traits::Signature,
+};
+use diem_global_constants::DIEM_ROOT_KEY;
+use diem_management::{
+    config::ConfigPath, error::Error, secure_backend::ValidatorBackend,
+    transaction::build_raw_transaction_with_expiration,
+};
+use diem_transaction_builder::stdlib::{self as transaction_builder, ScriptFunctionCall};
+use diem_types::{
+    account_address::AccountAddress,
+    account_config::diem_root_address,
+    chain_id::ChainId,
+    transaction::{
+        authenticator::{AuthenticationKey, TransactionAuthenticator},
+        RawTransaction, ScriptFunction, SignedTransaction,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+/// A Diem Root action, as listed in the actions file of `propose-governance-actions`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum GovernanceAction {
+    AddValidator { account_address: AccountAddress },
+    RemoveValidator { account_address: AccountAddress },
+    CreateValidator { name: String, path_to_key: PathBuf },
+    CreateValidatorOperator { name: String, path_to_key: PathBuf },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GovernanceActions {
+    actions: Vec<GovernanceAction>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ValidatorSetMember {
+    pub name: String,
+    pub account_address: AccountAddress,
+}
+
+/// The signatures of one signer, one per transaction of the proposal.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProposalSignatures {
+    pub public_key: Ed25519PublicKey,
+    pub signatures: Vec<Ed25519Signature>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Proposal {
+    /// The keys that may sign. A single key produces ed25519 transactions, while multiple keys
+    /// produce multi-ed25519 transactions requiring `threshold` signatures.
+    pub public_keys: Vec<Ed25519PublicKey>,
+    pub threshold: u8,
+    /// The validator set at the time of the proposal
+    pub validator_set: Vec<ValidatorSetMember>,
+    pub transactions: Vec<RawTransaction>,
+    pub signatures: Vec<ProposalSignatures>,
+}
+
+impl Proposal {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::UnableToReadFile(path.display().to_string(), e.to_string()))?;
+        let proposal: Proposal = serde_json::from_str(&contents)
+            .map_err(|e| Error::UnableToParseFile(path.display().to_string(), e.to_string()))?;
+        proposal.multi_public_key()?;
+        Ok(proposal)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        fs::write(path, contents).map_err(|e| Error::IO(path.display().to_string(), e))
+    }
+
+    fn multi_public_key(&self) -> Result<MultiEd25519PublicKey, Error> {
+        MultiEd25519PublicKey::new(self.public_keys.clone(), self.threshold).map_err(|e| {
+            Error::CommandArgumentError(format!(
+                "Invalid threshold {} for {} keys: {}",
+                self.threshold,
+                self.public_keys.len(),
+                e
+            ))
+        })
+    }
+
+    /// Returns the signers whose signatures are valid for every transaction, with the index of
+    /// their key.
+    fn valid_signatures(&self) -> Vec<(u8, &ProposalSignatures)> {
+        let mut valid = Vec::new();
+        for (index, public_key) in self.public_keys.iter().enumerate() {
+            let signer = self.signatures.iter().find(|s| &s.public_key == public_key);
+            if let Some(signer) = signer {
+                let verified = signer.signatures.len() == self.transactions.len()
+                    && self
+                        .transactions
+                        .iter()
+                        .zip(&signer.signatures)
+                        .all(|(txn, signature)| signature.verify(txn, public_key).is_ok());
+                if verified {
+                    valid.push((index as u8, signer));
+                }
+            }
+        }
+        valid
+    }
+
+    /// Adds or replaces the signatures of the given signer.
+    fn add_signatures(&mut self, signatures: ProposalSignatures) -> Result<(), Error> {
+        if !self.public_keys.contains(&signatures.public_key) {
+            return Err(Error::CommandArgumentError(format!(
+                "{} is not one of the keys of the proposal",
+                signatures.public_key
+            )));
+        }
+        self.signatures
+            .retain(|s| s.public_key != signatures.public_key);
+        self.signatures.push(signatures);
+        Ok(())
+    }
+
+    /// Builds the signed transactions, once enough signatures were collected.
+    fn signed_transactions(&self) -> Result<Vec<SignedTransaction>, Error> {
+        let mut signers = self.valid_signatures();
+        if signers.len() < self.threshold as usize {
+            return Err(Error::CommandArgumentError(format!(
+                "The proposal has {} valid signatures, {} are required",
+                signers.len(),
+                self.threshold
+            )));
+        }
+        signers.truncate(self.threshold as usize);
+
+        let mut signed_transactions = Vec::new();
+        for (txn_index, txn) in self.transactions.iter().enumerate() {
+            let signed_txn = if self.public_keys.len() == 1 {
+                SignedTransaction::new(
+                    txn.clone(),
+                    self.public_keys[0].clone(),
+                    signers[0].1.signatures[txn_index].clone(),
+                )
+            } else {
+                let signatures = signers
+                    .iter()
+                    .map(|(key_index, signer)| (signer.signatures[txn_index].clone(), *key_index))
+                    .collect();
+                let signature = MultiEd25519Signature::new(signatures)
+                    .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+                SignedTransaction::new_multisig(txn.clone(), self.multi_public_key()?, signature)
+            };
+            signed_transactions.push(signed_txn);
+        }
+        Ok(signed_transactions)
+    }
+
+    /// Describes each transaction and the validator set resulting from them.
+    pub fn review(&self) -> ProposalReview {
+        let mut validator_set = self.validator_set.clone();
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        let mut transactions = Vec::new();
+
+        for txn in &self.transactions {
+            let description = match ScriptFunctionCall::decode(txn.payload()) {
+                Some(ScriptFunctionCall::AddValidatorAndReconfigure {
+                    validator_name,
+                    validator_address,
+                    ..
+                }) => {
+                    let member = ValidatorSetMember {
+                        name: DecryptedValidatorConfig::human_name(&validator_name),
+                        account_address: validator_address,
+                    };
+                    let description =
+                        format!("Add validator {} ({})", member.name, validator_address);
+                    validator_set.push(member.clone());
+                    added.push(member);
+                    description
+                }
+                Some(ScriptFunctionCall::RemoveValidatorAndReconfigure {
+                    validator_name,
+                    validator_address,
+                    ..
+                }) => {
+                    let member = ValidatorSetMember {
+                        name: DecryptedValidatorConfig::human_name(&validator_name),
+                        account_address: validator_address,
+                    };
+                    validator_set.retain(|m| m.account_address != validator_address);
+                    let description =
+                        format!("Remove validator {} ({})", member.name, validator_address);
+                    removed.push(member);
+                    description
+                }
+                Some(ScriptFunctionCall::CreateValidatorAccount {
+                    new_account_address,
+                    human_name,
+                    ..
+                }) => format!(
+                    "Create validator account {} ({})",
+                    DecryptedValidatorConfig::human_name(&human_name),
+                    new_account_address
+                ),
+                Some(ScriptFunctionCall::CreateValidatorOperatorAccount {
+                    new_account_address,
+                    human_name,
+                    ..
+                }) => format!(
+                    "Create validator operator account {} ({})",
+                    DecryptedValidatorConfig::human_name(&human_name),
+                    new_account_address
+                ),
+                Some(call) => format!("{:?}", call),
+                None => "Unknown transaction".into(),
+            };
+            transactions.push(TransactionReview {
+                sequence_number: txn.sequence_number(),
+                description,
+            });
+        }
+
+        ProposalReview {
+            transactions,
+            added_validators: added,
+            removed_validators: removed,
+            validator_set,
+            signers: self
+                .valid_signatures()
+                .into_iter()
+                .map(|(_, signer)| signer.public_key.clone())
+                .collect(),
+            threshold: self.threshold,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransactionReview {
+    pub sequence_number: u64,
+    pub description: String,
+}
+
+/// A human-readable summary of a proposal, including the validator set change.
+#[derive(Debug, Serialize)]
+pub struct ProposalReview {
+    pub transactions: Vec<TransactionReview>,
+    pub added_validators: Vec<ValidatorSetMember>,
+    pub removed_validators: Vec<ValidatorSetMember>,
+    /// The validator set once all transactions are executed
+    pub validator_set: Vec<ValidatorSetMember>,
+    /// The keys with valid signatures for all transactions
+    pub signers: Vec<Ed25519PublicKey>,
+    pub threshold: u8,
+}
+
+fn current_validator_set(client: &JsonRpcClientWrapper) -> Result<Vec<ValidatorSetMember>, Error> {
+    let mut members = Vec::new();
+    for info in client.validator_set(None)? {
+        let config_resource = client.validator_config(*info.account_address())?;
+        members.push(ValidatorSetMember {
+            name: DecryptedValidatorConfig::human_name(&config_resource.human_name),
+            account_address: *info.account_address(),
+        });
+    }
+    Ok(members)
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ProposeGovernanceActions {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// JSON-RPC Endpoint (e.g. http://localhost:8080)
+    #[structopt(long, required_unless = "config")]
+    json_server: Option<String>,
+    #[structopt(long, required_unless("config"))]
+    chain_id: Option<ChainId>,
+    /// A TOML file listing the actions, in order
+    #[structopt(long)]
+    actions_file: PathBuf,
+    /// The public key of the Diem Root account, or one file per key of a multi-ed25519 key
+    #[structopt(long = "public-key-file", required = true, number_of_values = 1)]
+    public_key_files: Vec<PathBuf>,
+    /// The number of signatures required with a multi-ed25519 key
+    #[structopt(long, default_value = "1")]
+    threshold: u8,
+    /// Seconds until the transactions expire, which bounds the time for review and signing
+    #[structopt(long, default_value = "604800")]
+    expiration_secs: u64,
+    /// Location to store the proposal
+    #[structopt(long)]
+    output_file: PathBuf,
+}
+
+impl ProposeGovernanceActions {
+    pub fn execute(self) -> Result<ProposalReview, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_chain_id(self.chain_id)
+            .override_json_server(&self.json_server);
+        let client = JsonRpcClientWrapper::new(config.json_server.clone());
+
+        let actions_file = self.actions_file.display().to_string();
+        let contents = fs::read_to_string(&self.actions_file)
+            .map_err(|e| Error::UnableToReadFile(actions_file.clone(), e.to_string()))?;
+        let actions: GovernanceActions = toml::from_str(&contents)
+            .map_err(|e| Error::UnableToParseFile(actions_file, e.to_string()))?;
+
+        let public_keys = self
+            .public_key_files
+            .iter()
+            .map(|path| {
+                diem_management::read_key_from_file(path)
+                    .map_err(|e| Error::UnableToReadFile(format!("{:?}", path), e))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let validator_set = current_validator_set(&client)?;
+        let mut proposal = Proposal {
+            public_keys,
+            threshold: self.threshold,
+            validator_set: validator_set.clone(),
+            transactions: Vec::new(),
+            signatures: Vec::new(),
+        };
+        proposal.multi_public_key()?;
+
+        // Sliding nonces follow the sequence numbers, as with transactions executed immediately
+        let mut seq_num = client.sequence_number(diem_root_address())?;
+        let mut members: Vec<_> = validator_set
+            .iter()
+            .map(|member| member.account_address)
+            .collect();
+        for action in actions.actions {
+            let script = script(&client, &mut members, seq_num, action)?;
+            proposal
+                .transactions
+                .push(build_raw_transaction_with_expiration(
+                    config.chain_id,
+                    diem_root_address(),
+                    seq_num,
+                    script,
+                    self.expiration_secs,
+                ));
+            seq_num += 1;
+        }
+
+        proposal.save(&self.output_file)?;
+        Ok(proposal.review())
+    }
+}
+
+/// Builds the script of the action, checking it against the validator set resulting from the
+/// previous actions.
+fn script(
+    client: &JsonRpcClientWrapper,
+    members: &mut Vec<AccountAddress>,
+    seq_num: u64,
+    action: GovernanceAction,
+) -> Result<ScriptFunction, Error> {
+    let payload = match action {
+        GovernanceAction::AddValidator { account_address } => {
+            if members.contains(&account_address) {
+                return Err(Error::CommandArgumentError(format!(
+                    "{} is already in the validator set",
+                    account_address
+                )));
+            }
+            members.push(account_address);
+            let name = client.validator_config(account_address)?.human_name;
+            transaction_builder::encode_add_validator_and_reconfigure_script_function(
+                seq_num,
+                name,
+                account_address,
+            )
+        }
+        GovernanceAction::RemoveValidator { account_address } => {
+            if !members.contains(&account_address) {
+                return Err(Error::CommandArgumentError(format!(
+                    "{} is not in the validator set",
+                    account_address
+                )));
+            }
+            members.retain(|member| member != &account_address);
+            let name = client.validator_config(account_address)?.human_name;
+            transaction_builder::encode_remove_validator_and_reconfigure_script_function(
+                seq_num,
+                name,
+                account_address,
+            )
+        }
+        GovernanceAction::CreateValidator { name, path_to_key } => {
+            let auth_key = auth_key(&path_to_key)?;
+            transaction_builder::encode_create_validator_account_script_function(
+                seq_num,
+                auth_key.derived_address(),
+                auth_key.prefix().to_vec(),
+                name.into_bytes(),
+            )
+        }
+        GovernanceAction::CreateValidatorOperator { name, path_to_key } => {
+            let auth_key = auth_key(&path_to_key)?;
+            transaction_builder::encode_create_validator_operator_account_script_function(
+                seq_num,
+                auth_key.derived_address(),
+                auth_key.prefix().to_vec(),
+                name.into_bytes(),
+            )
+        }
+    };
+    Ok(payload.into_script_function())
+}
+
+fn auth_key(path_to_key: &Path) -> Result<AuthenticationKey, Error> {
+    let key = diem_management::read_key_from_file(path_to_key)
+        .map_err(|e| Error::UnableToReadFile(format!("{:?}", path_to_key), e))?;
+    Ok(AuthenticationKey::ed25519(&key))
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ReviewProposal {
+    /// Location of the proposal
+    #[structopt(long)]
+    proposal_file: PathBuf,
+}
+
+impl ReviewProposal {
+    pub fn execute(self) -> Result<ProposalReview, Error> {
+        Ok(Proposal::load(&self.proposal_file)?.review())
+    }
+}
+
+/// Signs all transactions of a proposal with the Diem Root key of the validator storage, which
+/// holds the signer's key of a multi-ed25519 key.
+#[derive(Debug, StructOpt)]
+pub struct SignProposal {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// Location of the proposal, to which the signatures are added
+    #[structopt(long)]
+    proposal_file: PathBuf,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+}
+
+impl SignProposal {
+    pub fn execute(self) -> Result<ProposalReview, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        let mut storage = config.validator_backend();
+        let mut proposal = Proposal::load(&self.proposal_file)?;
+
+        let public_key = storage.ed25519_public_from_private(DIEM_ROOT_KEY)?;
+        let mut signatures = Vec::new();
+        for txn in &proposal.transactions {
+            let signed_txn = storage.sign(DIEM_ROOT_KEY, "sign-proposal", txn.clone())?;
+            match signed_txn.authenticator() {
+                TransactionAuthenticator::Ed25519 { signature, .. } => signatures.push(signature),
+                authenticator => {
+                    return Err(Error::UnexpectedError(format!(
+                        "Unexpected authenticator {:?}",
+                        authenticator
+                    )))
+                }
+            }
+        }
+        proposal.add_signatures(ProposalSignatures {
+            public_key,
+            signatures,
+        })?;
+
+        proposal.save(&self.proposal_file)?;
+        Ok(proposal.review())
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SubmitProposal {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// JSON-RPC Endpoint (e.g. http://localhost:8080)
+    #[structopt(long, required_unless = "config")]
+    json_server: Option<String>,
+    /// Location of the signed proposal
+    #[structopt(long)]
+    proposal_file: PathBuf,
+}
+
+impl SubmitProposal {
+    pub fn execute(self) -> Result<Vec<TransactionContext>, Error> {
+        let config = self.config.load()?.override_json_server(&self.json_server);
+        let client = JsonRpcClientWrapper::new(config.json_server);
+        let proposal = Proposal::load(&self.proposal_file)?;
+        let signed_transactions = proposal.signed_transactions()?;
+
+        // The reviewed validator set change only holds against the validator set of the proposal
+        if current_validator_set(&client)? != proposal.validator_set {
+            return Err(Error::CommandArgumentError(
+                "The validator set changed since the proposal was created".into(),
+            ));
+        }
+        if let Some(first_txn) = signed_transactions.first() {
+            let seq_num = client.sequence_number(first_txn.sender())?;
+            if seq_num != first_txn.sequence_number() {
+                return Err(Error::CommandArgumentError(format!(
+                    "The proposal starts at sequence number {}, but the account is at {}",
+                    first_txn.sequence_number(),
+                    seq_num
+                )));
+            }
+        }
+
+        let mut transaction_contexts = Vec::new();
+        for signed_txn in signed_transactions {
+            let signed_txn = signed_txn
+                .check_signature()
+                .map_err(|e| Error::UnexpectedError(e.to_string()))?
+                .into_inner();
+            transaction_contexts.push(client.submit_transaction(signed_txn)?);
+        }
+        Ok(transaction_contexts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, SigningKey, Uniform};
+    use diem_management::transaction::build_raw_transaction;
+
+    fn proposal(keys: &[Ed25519PrivateKey], threshold: u8) -> Proposal {
+        let validator = ValidatorSetMember {
+            name: "alice".into(),
+            account_address: AccountAddress::random(),
+        };
+        let script = transaction_builder::encode_remove_validator_and_reconfigure_script_function(
+            0,
+            validator.name.as_bytes().to_vec(),
+            validator.account_address,
+        )
+        .into_script_function();
+        Proposal {
+            public_keys: keys.iter().map(|key| key.public_key()).collect(),
+            threshold,
+            validator_set: vec![validator],
+            transactions: vec![build_raw_transaction(
+                ChainId::test(),
+                diem_root_address(),
+                0,
+                script,
+            )],
+            signatures: Vec::new(),
+        }
+    }
+
+    fn sign(proposal: &mut Proposal, key: &Ed25519PrivateKey) {
+        let signatures = proposal
+            .transactions
+            .iter()
+            .map(|txn| key.sign(txn))
+            .collect();
+        proposal
+            .add_signatures(ProposalSignatures {
+                public_key: key.public_key(),
+                signatures,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_review() {
+        let mut rng = rand::rngs::OsRng;
+        let key = Ed25519PrivateKey::generate(&mut rng);
+        let mut proposal = proposal(&[key], 1);
+        let review = proposal.review();
+        assert_eq!(review.removed_validators, proposal.validator_set);
+        assert!(review.added_validators.is_empty());
+        assert!(review.validator_set.is_empty());
+        assert!(review.transactions[0]
+            .description
+            .starts_with("Remove validator alice"));
+
+        // Signatures that do not match the transactions are not counted
+        let other_key = Ed25519PrivateKey::generate(&mut rng);
+        proposal.signatures.push(ProposalSignatures {
+            public_key: proposal.public_keys[0].clone(),
+            signatures: vec![other_key.sign(&proposal.transactions[0])],
+        });
+        assert!(proposal.review().signers.is_empty());
+        proposal.signed_transactions().unwrap_err();
+    }
+
+    #[test]
+    fn test_multi_ed25519() {
+        let mut rng = rand::rngs::OsRng;
+        let keys: Vec<_> = (0..3)
+            .map(|_| Ed25519PrivateKey::generate(&mut rng))
+            .collect();
+        let mut proposal = proposal(&keys, 2);
+
+        // Only keys of the proposal may sign
+        proposal
+            .add_signatures(ProposalSignatures {
+                public_key: Ed25519PrivateKey::generate(&mut rng).public_key(),
+                signatures: Vec::new(),
+            })
+            .unwrap_err();
+
+        sign(&mut proposal, &keys[2]);
+        proposal.signed_transactions().unwrap_err();
+        // Signing twice replaces the previous signatures
+        sign(&mut proposal, &keys[2]);
+        proposal.signed_transactions().unwrap_err();
+
+        sign(&mut proposal, &keys[0]);
+        let signed_txn = proposal.signed_transactions().unwrap().remove(0);
+        match signed_txn.authenticator() {
+            TransactionAuthenticator::MultiEd25519 { public_key, .. } => {
+                assert_eq!(public_key, proposal.multi_public_key().unwrap())
+            }
+            authenticator => panic!("Unexpected authenticator {:?}", authenticator),
+        }
+        signed_txn.check_signature().unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_config::config;
+use diem_global_constants::{
+    CONSENSUS_BLS_KEY, CONSENSUS_KEY, DIEM_ROOT_KEY, EXECUTION_KEY, FULLNODE_NETWORK_KEY,
+    GENESIS_WAYPOINT, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY, SAFETY_DATA,
+    SAFETY_RULES_LEASE, TREASURY_COMPLIANCE_KEY, VALIDATOR_NETWORK_ADDRESS_KEYS,
+    VALIDATOR_NETWORK_KEY, WAYPOINT,
+};
+use diem_management::{error::Error, secure_backend::SecureBackend};
+use diem_secure_storage::{ReplicaStatus, ReplicatedStorage, Storage};
+use std::convert::TryInto;
+use structopt::StructOpt;
+
+/// The keys a validator may hold in its storage.
+const VALIDATOR_KEYS: &[&str] = &[
+    CONSENSUS_BLS_KEY,
+    CONSENSUS_KEY,
+    DIEM_ROOT_KEY,
+    EXECUTION_KEY,
+    FULLNODE_NETWORK_KEY,
+    GENESIS_WAYPOINT,
+    OPERATOR_ACCOUNT,
+    OPERATOR_KEY,
+    OWNER_ACCOUNT,
+    OWNER_KEY,
+    SAFETY_DATA,
+    SAFETY_RULES_LEASE,
+    TREASURY_COMPLIANCE_KEY,
+    VALIDATOR_NETWORK_ADDRESS_KEYS,
+    VALIDATOR_NETWORK_KEY,
+    WAYPOINT,
+];
+
+#[derive(Debug, StructOpt)]
+pub struct RepairStorage {
+    /// Backend of the primary replica, in the same format as --validator-backend
+    #[structopt(long)]
+    primary_backend: SecureBackend,
+    /// Backend of a secondary replica, repeated for each secondary in order of read preference
+    #[structopt(long, required = true, number_of_values = 1)]
+    secondary_backend: Vec<SecureBackend>,
+    /// Additional keys to repair, besides the ones held by validators
+    #[structopt(long, number_of_values = 1)]
+    key: Vec<String>,
+    /// Only report the diverged keys, without repairing them
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+impl RepairStorage {
+    pub fn execute(self) -> Result<Vec<ReplicaStatus>, Error> {
+        let primary: config::SecureBackend = self.primary_backend.try_into()?;
+        let secondaries = self
+            .secondary_backend
+            .into_iter()
+            .map(|backend| backend.try_into())
+            .collect::<Result<Vec<config::SecureBackend>, _>>()?;
+        let config = config::ReplicatedConfig {
+            primary: Box::new(primary),
+            secondaries,
+        };
+        config
+            .validate()
+            .map_err(|e| Error::CommandArgumentError(e.to_string()))?;
+        let mut storage = ReplicatedStorage::new(
+            Storage::from(config.primary.as_ref()),
+            config.secondaries.iter().map(Storage::from).collect(),
+        );
+
+        let mut keys = VALIDATOR_KEYS.to_vec();
+        keys.extend(self.key.iter().map(|key| key.as_str()));
+
+        let statuses = if self.dry_run {
+            storage.check(&keys).map(|statuses| {
+                statuses
+                    .into_iter()
+                    .filter(|status| !status.diverged.is_empty())
+                    .collect()
+            })
+        } else {
+            storage.repair(&keys)
+        };
+        statuses.map_err(|e| Error::StorageUnavailable("replicated", e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helper::OperationalTool;
+    use diem_config::config::OnDiskStorageConfig;
+    use diem_secure_storage::KVStorage;
+    use diem_temppath::TempPath;
+    use diem_types::chain_id::ChainId;
+
+    fn on_disk(temp_path: &TempPath) -> config::SecureBackend {
+        let mut config = OnDiskStorageConfig::default();
+        config.path = temp_path.path().to_path_buf();
+        config.namespace = Some("validator".into());
+        config::SecureBackend::OnDiskStorage(config)
+    }
+
+    #[test]
+    fn test_repair_storage() {
+        let primary_path = TempPath::new();
+        let secondary_path = TempPath::new();
+        let primary = on_disk(&primary_path);
+        let secondary = on_disk(&secondary_path);
+        let mut storage = Storage::from(&primary);
+        storage.set(WAYPOINT, "waypoint").unwrap();
+        storage.set("custom", 5u64).unwrap();
+
+        let op_tool = OperationalTool::new("http://127.0.0.1:8080".into(), ChainId::test());
+        let secondaries = [secondary.clone()];
+        let diverged = op_tool
+            .repair_storage(&primary, &secondaries, &[], true)
+            .unwrap();
+        assert_eq!(
+            diverged,
+            vec![ReplicaStatus {
+                key: WAYPOINT.into(),
+                source: 0,
+                diverged: vec![1],
+            }]
+        );
+
+        let repaired = op_tool
+            .repair_storage(&primary, &secondaries, &["custom"], false)
+            .unwrap();
+        assert_eq!(repaired.len(), 2);
+        let storage = Storage::from(&secondary);
+        assert_eq!(storage.get::<String>(WAYPOINT).unwrap().value, "waypoint");
+        assert_eq!(storage.get::<u64>("custom").unwrap().value, 5);
+        assert!(op_tool
+            .repair_storage(&primary, &secondaries, &["custom"], true)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus_types::safety_data::SafetyData;
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_global_constants::{OWNER_ACCOUNT, SAFETY_DATA};
+use diem_management::{config::ConfigPath, error::Error, secure_backend::ValidatorBackend};
+use diem_types::account_address::AccountAddress;
+use safety_rules::audit_log::{self, AuditLogSummary};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// The safety data of a validator, as exported for migration to another storage.
+#[derive(Debug, Deserialize, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct SafetyDataSnapshot {
+    pub author: AccountAddress,
+    pub safety_data: SafetyData,
+}
+
+/// The exported file. The hash is only a check against a snapshot corrupted in transit or by a
+/// careless edit: it is not keyed, so anyone able to edit the file can also recompute it. The
+/// file must be kept as safe as the storage it was exported from, and importing it relies on the
+/// author and rollback checks rather than on the hash.
+#[derive(Debug, Deserialize, Serialize)]
+struct SafetyDataExport {
+    snapshot: SafetyDataSnapshot,
+    /// The hash of the snapshot, to detect corruption only
+    hash: HashValue,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExportSafetyData {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// Location to store the safety data
+    #[structopt(long)]
+    output_file: PathBuf,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+}
+
+impl ExportSafetyData {
+    pub fn execute(self) -> Result<SafetyDataSnapshot, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        let storage = config.validator_backend();
+
+        let snapshot = SafetyDataSnapshot {
+            author: storage.account_address(OWNER_ACCOUNT)?,
+            safety_data: storage.value(SAFETY_DATA)?,
+        };
+        let export = SafetyDataExport {
+            hash: snapshot.hash(),
+            snapshot,
+        };
+        let encoded = serde_json::to_string_pretty(&export)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        fs::write(&self.output_file, encoded)
+            .map_err(|e| Error::IO(self.output_file.display().to_string(), e))?;
+        Ok(export.snapshot)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ImportSafetyData {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// Location of safety data exported by export-safety-data
+    #[structopt(long)]
+    input_file: PathBuf,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+}
+
+impl ImportSafetyData {
+    pub fn execute(self) -> Result<SafetyDataSnapshot, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        let mut storage = config.validator_backend();
+
+        let input_file = self.input_file.display().to_string();
+        let contents = fs::read_to_string(&self.input_file)
+            .map_err(|e| Error::UnableToReadFile(input_file.clone(), e.to_string()))?;
+        let export: SafetyDataExport = serde_json::from_str(&contents)
+            .map_err(|e| Error::UnableToParseFile(input_file.clone(), e.to_string()))?;
+        if export.snapshot.hash() != export.hash {
+            return Err(Error::UnableToParseFile(
+                input_file,
+                "the safety data does not match its hash, the file is corrupted".into(),
+            ));
+        }
+        let snapshot = export.snapshot;
+
+        let owner = storage.account_address(OWNER_ACCOUNT)?;
+        if snapshot.author != owner {
+            return Err(Error::CommandArgumentError(format!(
+                "The safety data belongs to {}, but the storage belongs to {}",
+                snapshot.author, owner
+            )));
+        }
+
+        // Importing older safety data would allow the validator to sign again for rounds that it
+        // has already signed for, so only newer or identical safety data is accepted.
+        let current: Option<SafetyData> = storage.optional_value(SAFETY_DATA)?;
+        if let Some(current) = current {
+            if is_older(&snapshot.safety_data, &current) {
+                return Err(Error::CommandArgumentError(format!(
+                    "Refusing to import {}, which is older than the stored {}",
+                    snapshot.safety_data, current
+                )));
+            }
+        }
+
+        storage.set(SAFETY_DATA, snapshot.safety_data.clone())?;
+        Ok(snapshot)
+    }
+}
+
+fn is_older(safety_data: &SafetyData, other: &SafetyData) -> bool {
+    safety_data.epoch < other.epoch
+        || (safety_data.epoch == other.epoch
+            && (safety_data.last_voted_round < other.last_voted_round
+                || safety_data.preferred_round < other.preferred_round))
+}
+
+#[derive(Debug, StructOpt)]
+pub struct VerifySafetyAuditLog {
+    /// Location of the safety rules audit log
+    #[structopt(long)]
+    path: PathBuf,
+    /// The expected hash of the last record, to detect records removed from the end of the log
+    #[structopt(long)]
+    last_hash: Option<HashValue>,
+}
+
+impl VerifySafetyAuditLog {
+    pub fn execute(self) -> Result<AuditLogSummary, Error> {
+        audit_log::verify(&self.path, self.last_hash)
+            .map_err(|e| Error::UnableToParseFile(self.path.display().to_string(), e.to_string()))
+    }
+}
@@ -4,17 +4,21 @@
 use crate::{
     account_resource::SimplifiedAccountResource,
     command::{Command, CommandName},
+    proposal::ProposalReview,
+    safety_data::SafetyDataSnapshot,
     validator_config::DecryptedValidatorConfig,
     validator_set::DecryptedValidatorInfo,
     TransactionContext,
 };
 use diem_config::config;
-use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
+use diem_crypto::{ed25519::Ed25519PublicKey, x25519, HashValue};
 use diem_management::{error::Error, secure_backend::DISK};
+use diem_secure_storage::ReplicaStatus;
 use diem_types::{
     account_address::AccountAddress, chain_id::ChainId, network_address::NetworkAddress,
     waypoint::Waypoint,
 };
+use safety_rules::audit_log::AuditLogSummary;
 use structopt::StructOpt;
 
 const TOOL_NAME: &str = "diem-operational-tool";
@@ -128,6 +132,58 @@ impl OperationalTool {
         )
     }
 
+    fn safety_data_operation<T>(
+        &self,
+        file_arg: &'static str,
+        file: &str,
+        backend: &config::SecureBackend,
+        name: CommandName,
+        execute: fn(Command) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let args = format!(
+            "
+                {command}
+                --{file_arg} {file}
+                --validator-backend {backend_args}
+            ",
+            command = command(TOOL_NAME, name),
+            file_arg = file_arg,
+            file = file,
+            backend_args = backend_args(backend)?,
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        execute(command)
+    }
+
+    pub fn export_safety_data(
+        &self,
+        output_file: &str,
+        backend: &config::SecureBackend,
+    ) -> Result<SafetyDataSnapshot, Error> {
+        self.safety_data_operation(
+            "output-file",
+            output_file,
+            backend,
+            CommandName::ExportSafetyData,
+            |cmd| cmd.export_safety_data(),
+        )
+    }
+
+    pub fn import_safety_data(
+        &self,
+        input_file: &str,
+        backend: &config::SecureBackend,
+    ) -> Result<SafetyDataSnapshot, Error> {
+        self.safety_data_operation(
+            "input-file",
+            input_file,
+            backend,
+            CommandName::ImportSafetyData,
+            |cmd| cmd.import_safety_data(),
+        )
+    }
+
     fn extract_key(
         &self,
         key_name: &str,
@@ -498,6 +554,141 @@ impl OperationalTool {
         command.validator_set()
     }
 
+    pub fn verify_safety_audit_log(
+        &self,
+        path: &str,
+        last_hash: Option<HashValue>,
+    ) -> Result<AuditLogSummary, Error> {
+        let args = format!(
+            "
+                {command}
+                --path {path}
+                {last_hash}
+            ",
+            command = command(TOOL_NAME, CommandName::VerifySafetyAuditLog),
+            path = path,
+            last_hash = optional_arg("last-hash", last_hash),
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.verify_safety_audit_log()
+    }
+
+    pub fn propose_governance_actions(
+        &self,
+        actions_file: &str,
+        public_key_files: &[&str],
+        threshold: u8,
+        output_file: &str,
+    ) -> Result<ProposalReview, Error> {
+        let public_key_files: Vec<_> = public_key_files
+            .iter()
+            .map(|path| format!("--public-key-file {}", path))
+            .collect();
+        let args = format!(
+            "
+                {command}
+                --json-server {host}
+                --chain-id {chain_id}
+                --actions-file {actions_file}
+                {public_key_files}
+                --threshold {threshold}
+                --output-file {output_file}
+            ",
+            command = command(TOOL_NAME, CommandName::ProposeGovernanceActions),
+            host = self.host,
+            chain_id = self.chain_id.id(),
+            actions_file = actions_file,
+            public_key_files = public_key_files.join(" "),
+            threshold = threshold,
+            output_file = output_file,
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.propose_governance_actions()
+    }
+
+    pub fn repair_storage(
+        &self,
+        primary: &config::SecureBackend,
+        secondaries: &[config::SecureBackend],
+        keys: &[&str],
+        dry_run: bool,
+    ) -> Result<Vec<ReplicaStatus>, Error> {
+        let secondaries = secondaries
+            .iter()
+            .map(|backend| Ok(format!("--secondary-backend {}", backend_args(backend)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let keys: Vec<_> = keys.iter().map(|key| format!("--key {}", key)).collect();
+        let args = format!(
+            "
+                {command}
+                --primary-backend {primary_args}
+                {secondaries}
+                {keys}
+                {dry_run}
+            ",
+            command = command(TOOL_NAME, CommandName::RepairStorage),
+            primary_args = backend_args(primary)?,
+            secondaries = secondaries.join(" "),
+            keys = keys.join(" "),
+            dry_run = optional_flag("dry-run", dry_run),
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.repair_storage()
+    }
+
+    pub fn review_proposal(&self, proposal_file: &str) -> Result<ProposalReview, Error> {
+        let args = format!(
+            "
+                {command}
+                --proposal-file {proposal_file}
+            ",
+            command = command(TOOL_NAME, CommandName::ReviewProposal),
+            proposal_file = proposal_file,
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.review_proposal()
+    }
+
+    pub fn sign_proposal(
+        &self,
+        proposal_file: &str,
+        backend: &config::SecureBackend,
+    ) -> Result<ProposalReview, Error> {
+        let args = format!(
+            "
+                {command}
+                --proposal-file {proposal_file}
+                --validator-backend {backend_args}
+            ",
+            command = command(TOOL_NAME, CommandName::SignProposal),
+            proposal_file = proposal_file,
+            backend_args = backend_args(backend)?,
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.sign_proposal()
+    }
+
+    pub fn submit_proposal(&self, proposal_file: &str) -> Result<Vec<TransactionContext>, Error> {
+        let args = format!(
+            "
+                {command}
+                --json-server {host}
+                --proposal-file {proposal_file}
+            ",
+            command = command(TOOL_NAME, CommandName::SubmitProposal),
+            host = self.host,
+            proposal_file = proposal_file,
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.submit_proposal()
+    }
+
     fn validator_operation<T>(
         &self,
         account_address: AccountAddress,
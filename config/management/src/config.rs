@@ -128,6 +128,13 @@ impl Config {
             config::SecureBackend::InMemoryStorage => panic!("Unsupported namespace for InMemory"),
             config::SecureBackend::Vault(config) => config.namespace = Some(namespace),
             config::SecureBackend::OnDiskStorage(config) => config.namespace = Some(namespace),
+            config::SecureBackend::EncryptedOnDiskStorage(config) => {
+                config.namespace = Some(namespace)
+            }
+            config::SecureBackend::Pkcs11(config) => config.namespace = Some(namespace),
+            config::SecureBackend::Replicated(_) => {
+                panic!("Unsupported namespace for Replicated")
+            }
         };
         StorageWrapper {
             storage_name: "shared",
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Error;
-use diem_config::config::{self, GitHubConfig, OnDiskStorageConfig, Token, VaultConfig};
+use diem_config::config::{
+    self, EncryptedOnDiskStorageConfig, GitHubConfig, OnDiskStorageConfig, Pkcs11Config,
+    SecretFormat, Token, VaultConfig,
+};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
@@ -13,8 +16,10 @@ use structopt::StructOpt;
 
 pub const BACKEND: &str = "backend";
 pub const DISK: &str = "disk";
+pub const ENCRYPTED_DISK: &str = "encrypted_disk";
 pub const GITHUB: &str = "github";
 pub const MEMORY: &str = "memory";
+pub const PKCS11: &str = "pkcs11";
 pub const VAULT: &str = "vault";
 
 // Custom timeouts for vault backend operations when using the management tooling.
@@ -80,6 +85,34 @@ impl TryInto<config::SecureBackend> for SecureBackend {
                 config.namespace = self.parameters.remove("namespace");
                 config::SecureBackend::OnDiskStorage(config)
             }
+            ENCRYPTED_DISK => {
+                let path = self
+                    .parameters
+                    .remove("path")
+                    .ok_or_else(|| Error::BackendParsingError("missing path".into()))?;
+                let secret = self
+                    .parameters
+                    .remove("secret")
+                    .ok_or_else(|| Error::BackendParsingError("missing secret".into()))?;
+                let mut config = EncryptedOnDiskStorageConfig::new(
+                    PathBuf::from(path),
+                    Token::FromDisk(PathBuf::from(secret)),
+                );
+                config.set_data_dir(PathBuf::from(""));
+                config.namespace = self.parameters.remove("namespace");
+                config.migrate_from = self.parameters.remove("migrate_from").map(PathBuf::from);
+                config.secret_format = match self.parameters.remove("secret_format").as_deref() {
+                    None | Some("passphrase") => SecretFormat::Passphrase,
+                    Some("raw") => SecretFormat::Raw,
+                    Some(format) => {
+                        return Err(Error::BackendParsingError(format!(
+                            "invalid secret format: {}",
+                            format
+                        )))
+                    }
+                };
+                config::SecureBackend::EncryptedOnDiskStorage(config)
+            }
             GITHUB => {
                 let repository_owner = self
                     .parameters
@@ -103,6 +136,26 @@ impl TryInto<config::SecureBackend> for SecureBackend {
                 })
             }
             MEMORY => config::SecureBackend::InMemoryStorage,
+            PKCS11 => {
+                let module = self
+                    .parameters
+                    .remove("module")
+                    .ok_or_else(|| Error::BackendParsingError("missing module".into()))?;
+                let token_label = self
+                    .parameters
+                    .remove("token_label")
+                    .ok_or_else(|| Error::BackendParsingError("missing token label".into()))?;
+                let pin = self
+                    .parameters
+                    .remove("pin")
+                    .ok_or_else(|| Error::BackendParsingError("missing pin".into()))?;
+                config::SecureBackend::Pkcs11(Pkcs11Config {
+                    module: PathBuf::from(module),
+                    token_label,
+                    pin: Token::FromDisk(PathBuf::from(pin)),
+                    namespace: self.parameters.remove("namespace"),
+                })
+            }
             VAULT => {
                 let certificate = self.parameters.remove("ca_certificate").map(PathBuf::from);
                 let server = self
@@ -159,6 +212,13 @@ pair: "k0=v0;k1=v1;...".  The current supported formats are:
         an optional namespace: "namespace=NAMESPACE"
     InMemory: "backend=memory"
     OnDisk: "backend=disk;path=LOCAL_PATH"
+    EncryptedOnDisk: "backend=encrypted_disk;path=LOCAL_PATH;secret=PATH_TO_SECRET"
+        an optional namespace: "namespace=NAMESPACE"
+        an optional OnDisk file to migrate: "migrate_from=LOCAL_PATH"
+        an optional secret format: "secret_format=raw", defaults to passphrase whose trailing
+        whitespace is ignored
+    PKCS#11: "backend=pkcs11;module=PATH_TO_MODULE;token_label=TOKEN_LABEL;pin=PATH_TO_PIN"
+        an optional namespace: "namespace=NAMESPACE"
                 "#)
             )]
             pub $field_name: Option<SecureBackend>,
@@ -200,6 +260,30 @@ mod tests {
         assert!(storage(disk).is_err());
     }
 
+    #[test]
+    fn test_encrypted_disk() {
+        let path = diem_temppath::TempPath::new();
+        let secret = diem_temppath::TempPath::new();
+        secret.create_as_file().unwrap();
+        let mut file = File::create(secret.path()).unwrap();
+        file.write_all(b"a secret that is long enough").unwrap();
+
+        let encrypted_disk = format!(
+            "backend=encrypted_disk;path={};secret={};namespace=test",
+            path.path().to_str().unwrap(),
+            secret.path().to_str().unwrap()
+        );
+        let backend = storage(&encrypted_disk).unwrap();
+        let mut secure_storage = diem_secure_storage::Storage::from(&backend);
+        diem_secure_storage::KVStorage::set(&mut secure_storage, "key", 5).unwrap();
+
+        let encrypted_disk = format!(
+            "backend=encrypted_disk;path={}",
+            path.path().to_str().unwrap()
+        );
+        assert!(storage(&encrypted_disk).is_err());
+    }
+
     #[test]
     fn test_github() {
         let path = diem_temppath::TempPath::new();
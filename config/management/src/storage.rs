@@ -33,6 +33,22 @@ impl StorageWrapper {
             .map_err(|e| Error::StorageReadError(self.storage_name, name, e.to_string()))
     }
 
+    /// Retrieves a value, or None if it has not been set
+    pub fn optional_value<T: DeserializeOwned>(
+        &self,
+        name: &'static str,
+    ) -> Result<Option<T>, Error> {
+        match self.storage.get(name) {
+            Ok(response) => Ok(Some(response.value)),
+            Err(diem_secure_storage::Error::KeyNotSet(_)) => Ok(None),
+            Err(e) => Err(Error::StorageReadError(
+                self.storage_name,
+                name,
+                e.to_string(),
+            )),
+        }
+    }
+
     pub fn account_address(&self, name: &'static str) -> Result<AccountAddress, Error> {
         self.value(name)
     }
@@ -15,6 +15,23 @@ pub fn build_raw_transaction(
     account: AccountAddress,
     sequence_number: u64,
     script: ScriptFunction,
+) -> RawTransaction {
+    build_raw_transaction_with_expiration(
+        chain_id,
+        account,
+        sequence_number,
+        script,
+        constants::TXN_EXPIRATION_SECS,
+    )
+}
+
+/// Builds a `RawTransaction` that expires the given number of seconds from now
+pub fn build_raw_transaction_with_expiration(
+    chain_id: ChainId,
+    account: AccountAddress,
+    sequence_number: u64,
+    script: ScriptFunction,
+    expiration_secs: u64,
 ) -> RawTransaction {
     RawTransaction::new_script_function(
         account,
@@ -23,7 +40,7 @@ pub fn build_raw_transaction(
         constants::MAX_GAS_AMOUNT,
         constants::GAS_UNIT_PRICE,
         constants::GAS_CURRENCY_CODE.to_owned(),
-        TimeService::real().now_secs() + constants::TXN_EXPIRATION_SECS,
+        TimeService::real().now_secs() + expiration_secs,
         chain_id,
     )
 }
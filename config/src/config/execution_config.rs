@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::config::{Error, RootPath, SecureBackend};
+use crate::config::{Error, RemoteServiceNoise, RootPath, SecureBackend};
 use diem_types::transaction::Transaction;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -23,6 +23,9 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// The number of threads transactions within a block are executed on. Blocks are executed
+    /// sequentially when set to 1, and speculatively in parallel otherwise.
+    pub concurrency_level: u16,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +43,8 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, concurrency_level: {:?} }}",
+            self.sign_vote_proposal, self.service, self.backend, self.concurrency_level
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +60,7 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            concurrency_level: 1,
         }
     }
 }
@@ -91,9 +95,7 @@ impl ExecutionConfig {
     }
 
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
-        if let SecureBackend::OnDiskStorage(backend) = &mut self.backend {
-            backend.set_data_dir(data_dir);
-        }
+        self.backend.set_data_dir(data_dir);
     }
 }
 
@@ -116,6 +118,10 @@ pub enum ExecutionCorrectnessService {
 #[serde(deny_unknown_fields)]
 pub struct RemoteExecutionService {
     pub server_address: SocketAddr,
+    /// Authenticates and encrypts the connection to the service with a Noise IK handshake. Without
+    /// it, the service must only be reachable from trusted hosts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<RemoteServiceNoise>,
 }
 
 #[cfg(test)]
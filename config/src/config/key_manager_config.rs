@@ -12,6 +12,7 @@ const DEFAULT_JSON_RPC_ENDPOINT: &str = "https://127.0.0.1:8080";
 const DEFAULT_ROTATION_PERIOD_SECS: u64 = 604_800; // 1 week
 const DEFAULT_SLEEP_PERIOD_SECS: u64 = 600; // 10 minutes
 const DEFAULT_TXN_EXPIRATION_SECS: u64 = 3600; // 1 hour
+const DEFAULT_NETWORK_KEY_GRACE_PERIOD_SECS: u64 = 86_400; // 1 day
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -19,6 +20,13 @@ pub struct KeyManagerConfig {
     pub logger: LoggerConfig,
     pub json_rpc_endpoint: String,
     pub rotation_period_secs: u64,
+    /// The frequency by which to rotate the validator and full node network keys. Network keys
+    /// are not rotated if this is not set.
+    pub network_key_rotation_period_secs: Option<u64>,
+    /// The time after the reconfiguration adding rotated network keys to the validator set during
+    /// which the previous network keys remain advertised on-chain, so that peers keep accepting
+    /// connections using the previous keys.
+    pub network_key_grace_period_secs: u64,
     pub secure_backend: SecureBackend,
     pub sleep_period_secs: u64,
     pub txn_expiration_secs: u64,
@@ -32,6 +40,8 @@ impl Default for KeyManagerConfig {
             json_rpc_endpoint: DEFAULT_JSON_RPC_ENDPOINT.into(),
             logger: LoggerConfig::default(),
             rotation_period_secs: DEFAULT_ROTATION_PERIOD_SECS,
+            network_key_rotation_period_secs: None,
+            network_key_grace_period_secs: DEFAULT_NETWORK_KEY_GRACE_PERIOD_SECS,
             secure_backend: SecureBackend::InMemoryStorage,
             sleep_period_secs: DEFAULT_SLEEP_PERIOD_SECS,
             txn_expiration_secs: DEFAULT_TXN_EXPIRATION_SECS,
@@ -53,8 +63,6 @@ impl KeyManagerConfig {
     }
 
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
-        if let SecureBackend::OnDiskStorage(backend) = &mut self.secure_backend {
-            backend.set_data_dir(data_dir);
-        }
+        self.secure_backend.set_data_dir(data_dir);
     }
 }
@@ -5,11 +5,12 @@ use crate::{
     config::{LoggerConfig, SecureBackend},
     keys::ConfigKey,
 };
-use diem_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use diem_crypto::{ed25519::Ed25519PrivateKey, x25519, Uniform};
 use diem_types::{network_address::NetworkAddress, waypoint::Waypoint, PeerId};
 use rand::rngs::StdRng;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
     net::{SocketAddr, ToSocketAddrs},
     path::PathBuf,
 };
@@ -26,6 +27,12 @@ pub struct SafetyRulesConfig {
     // Read/Write/Connect networking operation timeout in milliseconds.
     pub network_timeout_ms: u64,
     pub enable_cached_safety_data: bool,
+    /// If set, every vote and timeout signed is recorded in an append-only, hash-chained audit
+    /// log at this path.
+    pub audit_log_path: Option<PathBuf>,
+    /// If set, only the holder of a lease in the secure storage signs, which prevents several
+    /// instances sharing a consensus key from signing concurrently.
+    pub signer_lease: Option<SignerLeaseConfig>,
 }
 
 impl Default for SafetyRulesConfig {
@@ -40,14 +47,34 @@ impl Default for SafetyRulesConfig {
             // Default value of 30 seconds for a timeout
             network_timeout_ms: 30_000,
             enable_cached_safety_data: true,
+            audit_log_path: None,
+            signer_lease: None,
         }
     }
 }
 
 impl SafetyRulesConfig {
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
-        if let SecureBackend::OnDiskStorage(backend) = &mut self.backend {
-            backend.set_data_dir(data_dir);
+        self.backend.set_data_dir(data_dir);
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignerLeaseConfig {
+    /// Identifies this instance in the lease. If not set, a random identifier is used, so after a
+    /// restart the instance waits for its previous lease to expire before signing again.
+    pub holder: Option<String>,
+    /// How long the lease is held without being renewed. It is renewed when signing, once half of
+    /// it has elapsed.
+    pub duration_secs: u64,
+}
+
+impl Default for SignerLeaseConfig {
+    fn default() -> Self {
+        Self {
+            holder: None,
+            duration_secs: 30,
         }
     }
 }
@@ -71,6 +98,10 @@ pub enum SafetyRulesService {
 #[serde(deny_unknown_fields)]
 pub struct RemoteService {
     pub server_address: NetworkAddress,
+    /// Authenticates and encrypts the connection to the service with a Noise IK handshake. Without
+    /// it, the service must only be reachable from trusted hosts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<RemoteServiceNoise>,
 }
 
 impl RemoteService {
@@ -83,6 +114,33 @@ impl RemoteService {
     }
 }
 
+/// The Noise keys of one end of a connection to a remote service. Both ends can share the public
+/// keys, but each holds its own private key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteServiceNoise {
+    /// The static key of this end of the connection.
+    pub private_key: ConfigKey<x25519::PrivateKey>,
+    /// The key of the service. Clients only connect to a service proving ownership of this key.
+    pub server_public_key: x25519::PublicKey,
+    /// The keys of the clients. The service rejects connections from any other key.
+    pub client_public_keys: HashSet<x25519::PublicKey>,
+}
+
+impl RemoteServiceNoise {
+    pub fn new(
+        private_key: x25519::PrivateKey,
+        server_public_key: x25519::PublicKey,
+        client_public_keys: HashSet<x25519::PublicKey>,
+    ) -> Self {
+        Self {
+            private_key: ConfigKey::new(private_key),
+            server_public_key,
+            client_public_keys,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct SafetyRulesTestConfig {
     pub author: PeerId,
@@ -3,14 +3,17 @@
 
 use crate::config::Error;
 use diem_secure_storage::{
-    GitHubStorage, InMemoryStorage, NamespacedStorage, OnDiskStorage, Storage, VaultStorage,
+    EncryptedOnDiskStorage, GitHubStorage, InMemoryStorage, NamespacedStorage, OnDiskStorage,
+    Pkcs11Storage, ReplicatedStorage, Storage, VaultStorage,
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    convert::TryFrom,
     fs::File,
     io::Read,
     path::{Path, PathBuf},
 };
+use zeroize::{Zeroize, Zeroizing};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -19,6 +22,26 @@ pub enum SecureBackend {
     InMemoryStorage,
     Vault(VaultConfig),
     OnDiskStorage(OnDiskStorageConfig),
+    EncryptedOnDiskStorage(EncryptedOnDiskStorageConfig),
+    Replicated(ReplicatedConfig),
+    Pkcs11(Pkcs11Config),
+}
+
+impl SecureBackend {
+    /// Sets the directory relative paths of on disk backends are resolved against.
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        match self {
+            SecureBackend::OnDiskStorage(backend) => backend.set_data_dir(data_dir),
+            SecureBackend::EncryptedOnDiskStorage(backend) => backend.set_data_dir(data_dir),
+            SecureBackend::Replicated(backend) => {
+                backend.primary.set_data_dir(data_dir.clone());
+                for secondary in &mut backend.secondaries {
+                    secondary.set_data_dir(data_dir.clone());
+                }
+            }
+            _ => (),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -86,6 +109,114 @@ pub struct OnDiskStorageConfig {
     data_dir: PathBuf,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedOnDiskStorageConfig {
+    // Required path for encrypted on disk storage
+    pub path: PathBuf,
+    /// The secret the encryption key is derived from, either a passphrase or a key file. It must
+    /// be at least 16 bytes long.
+    pub secret: Token,
+    /// How the secret is interpreted, as a passphrase by default
+    #[serde(default)]
+    pub secret_format: SecretFormat,
+    /// A namespace is an optional portion of the path to a key stored within
+    /// EncryptedOnDiskStorage. For example, a key, S, without a namespace would be available in S,
+    /// with a namespace, N, it would be in N/S.
+    pub namespace: Option<String>,
+    /// An unencrypted OnDiskStorage file whose keys are imported when the encrypted file does not
+    /// exist yet. Relative paths are resolved like `path`.
+    pub migrate_from: Option<PathBuf>,
+    #[serde(skip)]
+    data_dir: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretFormat {
+    /// Text whose trailing whitespace, such as the newline that editors and `echo` append to a
+    /// file, is not part of the secret
+    Passphrase,
+    /// Bytes used as is, e.g., the contents of a binary key file
+    Raw,
+}
+
+impl Default for SecretFormat {
+    fn default() -> Self {
+        SecretFormat::Passphrase
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pkcs11Config {
+    /// The PKCS#11 module of the token, e.g., /usr/lib/softhsm/libsofthsm2.so, this is expected
+    /// to be a full path.
+    pub module: PathBuf,
+    /// The label of the token holding the keys
+    pub token_label: String,
+    /// The user PIN of the token
+    pub pin: Token,
+    /// A namespace is an optional prefix of the labels of the objects stored within the token. For
+    /// example, a key, S, without a namespace would be labeled S, with a namespace, N, it would be
+    /// labeled N/S.
+    pub namespace: Option<String>,
+}
+
+/// Replicates all writes across several backends, see `ReplicatedStorage`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "UncheckedReplicatedConfig")]
+pub struct ReplicatedConfig {
+    /// The backend reads are served from while it is available
+    pub primary: Box<SecureBackend>,
+    /// The backends all writes are replicated to, in order of preference for reads
+    pub secondaries: Vec<SecureBackend>,
+}
+
+impl ReplicatedConfig {
+    /// ReplicatedStorage holds cryptographic keys as key/value pairs in every replica, so that
+    /// all replicas hold the same keys. Backends whose keys never leave them, i.e., Vault's
+    /// transit engine and PKCS#11 tokens, cannot be replicated this way and are rejected.
+    pub fn validate(&self) -> Result<(), Error> {
+        for backend in std::iter::once(self.primary.as_ref()).chain(&self.secondaries) {
+            let name = match backend {
+                SecureBackend::Vault(_) => "Vault",
+                SecureBackend::Pkcs11(_) => "PKCS#11",
+                SecureBackend::Replicated(config) => {
+                    config.validate()?;
+                    continue;
+                }
+                _ => continue,
+            };
+            return Err(Error::InvariantViolation(format!(
+                "{} backends hold their own cryptographic keys and cannot be replicated",
+                name
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UncheckedReplicatedConfig {
+    primary: Box<SecureBackend>,
+    secondaries: Vec<SecureBackend>,
+}
+
+impl TryFrom<UncheckedReplicatedConfig> for ReplicatedConfig {
+    type Error = Error;
+
+    fn try_from(config: UncheckedReplicatedConfig) -> Result<Self, Error> {
+        let config = ReplicatedConfig {
+            primary: config.primary,
+            secondaries: config.secondaries,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 /// Tokens can either be directly within this config or stored somewhere on disk.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -102,6 +233,23 @@ impl Token {
             Token::FromConfig(token) => Ok(token.clone()),
         }
     }
+
+    /// Reads the token as bytes, which are not required to be valid UTF-8, into a buffer that is
+    /// zeroed when dropped.
+    pub fn read_bytes(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        match self {
+            Token::FromDisk(path) => {
+                let to_error = |e| Error::IO(path.to_str().unwrap().to_string(), e);
+                let mut file = File::open(path).map_err(to_error)?;
+                let len = file.metadata().map_err(to_error)?.len();
+                // Reserve the whole file upfront, growing the buffer would leave copies behind
+                let mut contents = Zeroizing::new(Vec::with_capacity(len as usize));
+                file.read_to_end(&mut contents).map_err(to_error)?;
+                Ok(contents)
+            }
+            Token::FromConfig(token) => Ok(Zeroizing::new(token.as_bytes().to_vec())),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -140,6 +288,53 @@ impl OnDiskStorageConfig {
     }
 }
 
+impl EncryptedOnDiskStorageConfig {
+    pub fn new(path: PathBuf, secret: Token) -> Self {
+        Self {
+            path,
+            secret,
+            secret_format: SecretFormat::default(),
+            namespace: None,
+            migrate_from: None,
+            data_dir: PathBuf::from("/opt/diem/data"),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.resolve(&self.path)
+    }
+
+    /// Reads the secret. Trailing whitespace is removed from passphrases.
+    pub fn read_secret(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let mut secret = self.secret.read_bytes()?;
+        if self.secret_format == SecretFormat::Passphrase {
+            let len = secret
+                .iter()
+                .rposition(|byte| !byte.is_ascii_whitespace())
+                .map_or(0, |idx| idx + 1);
+            secret[len..].zeroize();
+            secret.truncate(len);
+        }
+        Ok(secret)
+    }
+
+    pub fn migrate_from(&self) -> Option<PathBuf> {
+        self.migrate_from.as_ref().map(|path| self.resolve(path))
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_relative() {
+            self.data_dir.join(path)
+        } else {
+            path.to_path_buf()
+        }
+    }
+}
+
 fn read_file(path: &Path) -> Result<String, Error> {
     let mut file =
         File::open(path).map_err(|e| Error::IO(path.to_str().unwrap().to_string(), e))?;
@@ -178,6 +373,38 @@ impl From<&SecureBackend> for Storage {
                     storage
                 }
             }
+            SecureBackend::EncryptedOnDiskStorage(config) => {
+                let secret = config.read_secret().expect("Unable to read secret");
+                let mut storage = EncryptedOnDiskStorage::new(config.path(), &secret)
+                    .expect("Unable to derive the storage key from the secret");
+                drop(secret);
+                if let Some(migrate_from) = config.migrate_from() {
+                    if !config.path().exists() {
+                        storage
+                            .import_on_disk(&migrate_from)
+                            .expect("Unable to migrate OnDiskStorage");
+                    }
+                }
+                let storage = Storage::from(storage);
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(NamespacedStorage::new(storage, namespace.clone()))
+                } else {
+                    storage
+                }
+            }
+            SecureBackend::Pkcs11(config) => Storage::from(Pkcs11Storage::new(
+                config.module.clone(),
+                config.token_label.clone(),
+                config.pin.read_token().expect("Unable to read pin"),
+                config.namespace.clone(),
+            )),
+            SecureBackend::Replicated(config) => {
+                config.validate().expect("Unsupported replicated backend");
+                Storage::from(ReplicatedStorage::new(
+                    Storage::from(config.primary.as_ref()),
+                    config.secondaries.iter().map(Storage::from).collect(),
+                ))
+            }
             SecureBackend::Vault(config) => Storage::from(VaultStorage::new(
                 config.server.clone(),
                 config.token.read_token().expect("Unable to read token"),
@@ -290,6 +517,50 @@ vault:
         serde_yaml::to_string(&from_disk).unwrap();
     }
 
+    #[test]
+    fn test_replicated_parsing() {
+        let text = r#"
+type: replicated
+primary:
+    type: github
+    repository_owner: diem
+    repository: secure-storage
+    token:
+        from_config: "test"
+secondaries:
+    - type: on_disk_storage
+      path: secure_storage.json
+        "#;
+
+        let mut backend: SecureBackend = serde_yaml::from_str(text).unwrap();
+        backend.set_data_dir(PathBuf::from("/data"));
+        let config = match &backend {
+            SecureBackend::Replicated(config) => config,
+            _ => panic!("Expected a replicated backend"),
+        };
+        assert!(matches!(config.primary.as_ref(), SecureBackend::GitHub(_)));
+        match &config.secondaries[..] {
+            [SecureBackend::OnDiskStorage(secondary)] => {
+                assert_eq!(secondary.path(), PathBuf::from("/data/secure_storage.json"))
+            }
+            _ => panic!("Expected an on disk secondary"),
+        }
+        serde_yaml::to_string(&backend).unwrap();
+
+        // Vault keeps its keys in its transit engine, which cannot be replicated
+        let vault = r#"
+type: replicated
+primary:
+    type: in_memory_storage
+secondaries:
+    - type: vault
+      server: "127.0.0.1:8200"
+      token:
+          from_config: "test"
+        "#;
+        serde_yaml::from_str::<SecureBackend>(vault).unwrap_err();
+    }
+
     #[test]
     fn test_token_reading() {
         let temppath = diem_temppath::TempPath::new();
@@ -303,4 +574,33 @@ vault:
         let config = Token::FromConfig("config_token".to_string());
         assert_eq!("config_token", config.read_token().unwrap());
     }
+
+    #[test]
+    fn test_secret_reading() {
+        let temppath = diem_temppath::TempPath::new();
+        temppath.create_as_file().unwrap();
+        let mut config = EncryptedOnDiskStorageConfig::new(
+            PathBuf::from("storage"),
+            Token::FromDisk(temppath.path().to_path_buf()),
+        );
+
+        // Passphrases do not include the newline added when writing the file
+        File::create(temppath.path())
+            .unwrap()
+            .write_all(b"a long enough passphrase \n")
+            .unwrap();
+        assert_eq!(
+            &config.read_secret().unwrap()[..],
+            b"a long enough passphrase"
+        );
+
+        // Raw secrets are used as is, even if they are not valid UTF-8
+        let key = [0xff, 0x00, 0x0a, 0x20, 0x0a];
+        File::create(temppath.path())
+            .unwrap()
+            .write_all(&key)
+            .unwrap();
+        config.secret_format = SecretFormat::Raw;
+        assert_eq!(&config.read_secret().unwrap()[..], &key[..]);
+    }
 }
@@ -37,6 +37,9 @@ impl Default for RocksdbConfig {
 pub struct StorageConfig {
     pub address: SocketAddr,
     pub backup_service_address: SocketAddr,
+    /// Checkpoints of the DBs can be created through the backup service under this directory,
+    /// None disables them. Relative paths are relative to the data directory.
+    pub backup_service_checkpoint_dir: Option<PathBuf>,
     pub dir: PathBuf,
     pub grpc_max_receive_len: Option<i32>,
     /// None disables pruning. The windows is in number of versions, consider system tps
@@ -55,6 +58,7 @@ impl Default for StorageConfig {
         StorageConfig {
             address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6666),
             backup_service_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6186),
+            backup_service_checkpoint_dir: None,
             dir: PathBuf::from("db"),
             grpc_max_receive_len: Some(100_000_000),
             // The prune window must at least out live a RPC request because its sub requests are
@@ -81,6 +85,12 @@ impl StorageConfig {
         }
     }
 
+    pub fn backup_service_checkpoint_dir(&self) -> Option<PathBuf> {
+        self.backup_service_checkpoint_dir
+            .as_ref()
+            .map(|dir| self.data_dir.join(dir))
+    }
+
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
         self.data_dir = data_dir;
     }
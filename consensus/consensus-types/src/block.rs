@@ -315,14 +315,13 @@ impl From<&Block> for BlockMetadata {
             block.id(),
             block.round(),
             block.timestamp_usecs(),
-            // an ordered vector of voters' account address
+            // an ordered vector of voters' account address, verified QCs carry their signatures
             block
                 .quorum_cert()
                 .ledger_info()
                 .signatures()
-                .keys()
-                .cloned()
-                .collect(),
+                .map(|signatures| signatures.keys().cloned().collect())
+                .unwrap_or_default(),
             // For nil block, we use 0x0 which is convention for nil address in move.
             block.author().unwrap_or(AccountAddress::ZERO),
         )
@@ -9,7 +9,10 @@ use crate::{
     quorum_cert::QuorumCert,
 };
 use diem_crypto::hash::HashValue;
-use diem_types::{validator_signer::ValidatorSigner, validator_verifier::ValidatorVerifier};
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures, validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+};
 use std::{collections::BTreeMap, sync::Arc};
 
 #[test]
@@ -116,8 +119,10 @@ fn test_same_qc_different_authors() {
     );
 
     let signature = signer.sign(genesis_qc.ledger_info().ledger_info());
-    let mut ledger_info_altered = genesis_qc.ledger_info().clone();
-    ledger_info_altered.add_signature(signer.author(), signature);
+    let mut signatures = genesis_qc.ledger_info().signatures().unwrap().clone();
+    signatures.insert(signer.author(), signature);
+    let ledger_info_altered =
+        LedgerInfoWithSignatures::new(genesis_qc.ledger_info().ledger_info().clone(), signatures);
     let genesis_qc_altered = QuorumCert::new(genesis_qc.vote_data().clone(), ledger_info_altered);
 
     let block_round_1_altered = Block::new_proposal(
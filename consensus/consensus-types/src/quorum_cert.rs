@@ -115,11 +115,18 @@ impl QuorumCert {
                 "Genesis QC has inconsistent commit block with certified block"
             );
             ensure!(
-                self.ledger_info().signatures().is_empty(),
+                self.ledger_info()
+                    .signatures()
+                    .map_or(false, |signatures| signatures.is_empty()),
                 "Genesis QC should not carry signatures"
             );
             return Ok(());
         }
+        // The voters of a block are recorded on chain, which requires individual signatures.
+        ensure!(
+            self.ledger_info().signatures().is_some(),
+            "QuorumCert should carry individual signatures"
+        );
         self.ledger_info()
             .verify_signatures(validator)
             .context("Fail to verify QuorumCert")?;
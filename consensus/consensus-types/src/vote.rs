@@ -3,7 +3,7 @@
 
 use crate::{common::Author, timeout::Timeout, vote_data::VoteData};
 use anyhow::{ensure, Context};
-use diem_crypto::{ed25519::Ed25519Signature, hash::CryptoHash};
+use diem_crypto::{bls12381::BLS12381Signature, ed25519::Ed25519Signature, hash::CryptoHash};
 use diem_types::{
     ledger_info::LedgerInfo, validator_signer::ValidatorSigner,
     validator_verifier::ValidatorVerifier,
@@ -28,6 +28,9 @@ pub struct Vote {
     signature: Ed25519Signature,
     /// The round signatures can be aggregated into a timeout certificate if present.
     timeout_signature: Option<Ed25519Signature>,
+    /// BLS signature of the LedgerInfo, present if the voter has a BLS key, which can be
+    /// aggregated into a single signature of the LedgerInfo.
+    bls_signature: Option<BLS12381Signature>,
 }
 
 // this is required by structured log
@@ -61,7 +64,11 @@ impl Vote {
     ) -> Self {
         ledger_info_placeholder.set_consensus_data_hash(vote_data.hash());
         let signature = validator_signer.sign(&ledger_info_placeholder);
-        Self::new_with_signature(vote_data, author, ledger_info_placeholder, signature)
+        let bls_signature = validator_signer.sign_bls(&ledger_info_placeholder);
+        let mut vote =
+            Self::new_with_signature(vote_data, author, ledger_info_placeholder, signature);
+        vote.bls_signature = bls_signature;
+        vote
     }
 
     /// Generates a new Vote using a signature over the specified ledger_info
@@ -77,9 +84,16 @@ impl Vote {
             ledger_info,
             signature,
             timeout_signature: None,
+            bls_signature: None,
         }
     }
 
+    /// Adds a BLS signature over the ledger_info, which can then be aggregated with the
+    /// signatures of other voters.
+    pub fn add_bls_signature(&mut self, signature: BLS12381Signature) {
+        self.bls_signature = Some(signature);
+    }
+
     /// Generates a round signature, which can then be used for aggregating a timeout certificate.
     /// Typically called for generating vote messages that are sent upon timeouts.
     pub fn add_timeout_signature(&mut self, signature: Ed25519Signature) {
@@ -109,6 +123,11 @@ impl Vote {
         &self.signature
     }
 
+    /// Return the BLS signature of the vote, if the voter has a BLS key
+    pub fn bls_signature(&self) -> Option<&BLS12381Signature> {
+        self.bls_signature.as_ref()
+    }
+
     /// Returns the hash of the data represent by a timeout proposal
     pub fn timeout(&self) -> Timeout {
         Timeout::new(
@@ -149,6 +168,11 @@ impl Vote {
                 .verify(self.author(), &self.timeout(), timeout_signature)
                 .context("Failed to verify Timeout Vote")?;
         }
+        if let Some(bls_signature) = &self.bls_signature {
+            validator
+                .verify_bls(self.author(), &self.ledger_info, bls_signature)
+                .context("Failed to verify BLS signature of Vote")?;
+        }
         // Let us verify the vote data as well
         self.vote_data().verify()?;
         Ok(())
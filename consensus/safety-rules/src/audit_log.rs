@@ -0,0 +1,445 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! An append-only log of every vote and timeout signed by SafetyRules. Each record commits to the
+//! hash of the previous record, so that removing, reordering or altering records breaks the hash
+//! chain, which `verify` detects. The log is stored as one JSON record per line.
+
+use crate::Error;
+use consensus_types::{common::Round, timeout::Timeout, vote::Vote};
+use diem_crypto::{ed25519::Ed25519Signature, hash::CryptoHash, HashValue};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_infallible::duration_since_epoch;
+use diem_logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// A signing operation performed by SafetyRules.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AuditEvent {
+    Vote {
+        epoch: u64,
+        round: Round,
+        /// The id of the block voted for
+        block_id: HashValue,
+        /// The hash of the signed ledger info
+        ledger_info_hash: HashValue,
+        signature: Ed25519Signature,
+    },
+    Timeout {
+        epoch: u64,
+        round: Round,
+        signature: Ed25519Signature,
+    },
+}
+
+impl AuditEvent {
+    pub fn vote(vote: &Vote) -> Self {
+        AuditEvent::Vote {
+            epoch: vote.epoch(),
+            round: vote.vote_data().proposed().round(),
+            block_id: vote.vote_data().proposed().id(),
+            ledger_info_hash: vote.ledger_info().hash(),
+            signature: vote.signature().clone(),
+        }
+    }
+
+    pub fn timeout(timeout: &Timeout, signature: &Ed25519Signature) -> Self {
+        AuditEvent::Timeout {
+            epoch: timeout.epoch(),
+            round: timeout.round(),
+            signature: signature.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct AuditLogEntry {
+    /// The position of the entry in the log, starting at 0
+    pub index: u64,
+    /// The hash of the previous entry, or zero for the first entry
+    pub previous_hash: HashValue,
+    pub timestamp_usecs: u64,
+    pub event: AuditEvent,
+}
+
+#[derive(Deserialize, Serialize)]
+struct AuditLogRecord {
+    entry: AuditLogEntry,
+    hash: HashValue,
+}
+
+impl AuditLogRecord {
+    fn parse(line: &str, line_number: usize) -> Result<Self, Error> {
+        serde_json::from_str(line).map_err(|e| {
+            Error::AuditLogError(format!("Unable to parse line {}: {}", line_number, e))
+        })
+    }
+}
+
+/// The writer of the audit log. Each record is synced to disk before `append` returns.
+pub struct AuditLog {
+    file: File,
+    next_index: u64,
+    last_hash: HashValue,
+}
+
+impl AuditLog {
+    /// Opens the audit log at the given path, creating it if it does not exist. New records are
+    /// chained to the last record of an existing log, after recovering a record torn by a crash.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let mut next_index = 0;
+        let mut last_hash = HashValue::zero();
+        if path.exists() {
+            recover_trailing_record(path)?;
+            let mut last_record = None;
+            for (line_number, line) in lines(path)? {
+                last_record = Some(AuditLogRecord::parse(&line?, line_number)?);
+            }
+            if let Some(record) = last_record {
+                next_index = record.entry.index + 1;
+                last_hash = record.hash;
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| io_error(path, e))?;
+        Ok(Self {
+            file,
+            next_index,
+            last_hash,
+        })
+    }
+
+    /// Appends the event to the log and returns the hash of the new record.
+    pub fn append(&mut self, event: AuditEvent) -> Result<HashValue, Error> {
+        let entry = AuditLogEntry {
+            index: self.next_index,
+            previous_hash: self.last_hash,
+            timestamp_usecs: duration_since_epoch().as_micros() as u64,
+            event,
+        };
+        let hash = entry.hash();
+        let mut line = serde_json::to_string(&AuditLogRecord { entry, hash })
+            .map_err(|e| Error::SerializationError(e.to_string()))?;
+        line.push('\n');
+
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|()| self.file.sync_data())
+            .map_err(|e| Error::AuditLogError(e.to_string()))?;
+        self.next_index += 1;
+        self.last_hash = hash;
+        Ok(hash)
+    }
+}
+
+/// The outcome of a successful audit log verification.
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditLogSummary {
+    pub records: u64,
+    pub votes: u64,
+    pub timeouts: u64,
+    /// The hash of the last record, which commits to the whole log
+    pub last_hash: HashValue,
+}
+
+/// Verifies the hash chain of the audit log at the given path, and that votes never go back to a
+/// previously voted round. If `expected_last_hash` is provided, the log must also end with a
+/// record of that hash, which detects records removed from the end of the log.
+pub fn verify(
+    path: &Path,
+    expected_last_hash: Option<HashValue>,
+) -> Result<AuditLogSummary, Error> {
+    let mut summary = AuditLogSummary::default();
+    let mut last_vote: Option<(u64, Round)> = None;
+
+    for (line_number, line) in lines(path)? {
+        let record = AuditLogRecord::parse(&line?, line_number)?;
+        let entry = &record.entry;
+        if entry.index != summary.records {
+            return Err(Error::AuditLogError(format!(
+                "Line {} has index {}, expected {}",
+                line_number, entry.index, summary.records
+            )));
+        }
+        if entry.previous_hash != summary.last_hash {
+            return Err(Error::AuditLogError(format!(
+                "Line {} does not follow the previous record",
+                line_number
+            )));
+        }
+        if entry.hash() != record.hash {
+            return Err(Error::AuditLogError(format!(
+                "Line {} does not match its hash",
+                line_number
+            )));
+        }
+
+        match &entry.event {
+            AuditEvent::Vote { epoch, round, .. } => {
+                if let Some((last_epoch, last_round)) = last_vote {
+                    if *epoch < last_epoch || (*epoch == last_epoch && *round <= last_round) {
+                        return Err(Error::AuditLogError(format!(
+                            "Line {} votes in epoch {} round {} after a vote in epoch {} round {}",
+                            line_number, epoch, round, last_epoch, last_round
+                        )));
+                    }
+                }
+                last_vote = Some((*epoch, *round));
+                summary.votes += 1;
+            }
+            AuditEvent::Timeout { .. } => summary.timeouts += 1,
+        }
+        summary.records += 1;
+        summary.last_hash = record.hash;
+    }
+
+    if let Some(expected_last_hash) = expected_last_hash {
+        if expected_last_hash != summary.last_hash {
+            return Err(Error::AuditLogError(format!(
+                "The last record has hash {}, expected {}",
+                summary.last_hash, expected_last_hash
+            )));
+        }
+    }
+    Ok(summary)
+}
+
+/// Every record written by `append` ends with a newline, so anything after the last newline was
+/// left by a crash during `append` or by an edit of the log. A complete record only gets its
+/// newline back, while a torn record is moved to the quarantine file at `torn_path` so that the log
+/// can be reopened and still be verified.
+fn recover_trailing_record(path: &Path) -> Result<(), Error> {
+    let contents = std::fs::read(path).map_err(|e| io_error(path, e))?;
+    let complete_len = contents
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map_or(0, |position| position + 1);
+    let trailing = &contents[complete_len..];
+    if trailing.iter().all(u8::is_ascii_whitespace) {
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(|e| io_error(path, e))?;
+    let is_complete = std::str::from_utf8(trailing).map_or(false, |line| {
+        serde_json::from_str::<AuditLogRecord>(line).is_ok()
+    });
+    if is_complete {
+        return file
+            .write_all(b"\n")
+            .and_then(|()| file.sync_data())
+            .map_err(|e| io_error(path, e));
+    }
+
+    let torn_path = torn_path(path);
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&torn_path)
+        .and_then(|mut torn_file| {
+            torn_file.write_all(trailing)?;
+            torn_file.write_all(b"\n")?;
+            torn_file.sync_data()
+        })
+        .map_err(|e| io_error(&torn_path, e))?;
+    warn!(
+        "Moved a torn record of {} bytes at the end of the audit log {} to {}",
+        trailing.len(),
+        path.display(),
+        torn_path.display()
+    );
+    file.set_len(complete_len as u64)
+        .and_then(|()| file.sync_data())
+        .map_err(|e| io_error(path, e))
+}
+
+/// The file collecting the torn records removed from the end of the audit log at the given path.
+fn torn_path(path: &Path) -> PathBuf {
+    let mut torn_path = path.as_os_str().to_owned();
+    torn_path.push(".torn");
+    torn_path.into()
+}
+
+/// Returns the non-empty lines of the file along with their (1-based) line number.
+fn lines(path: &Path) -> Result<impl Iterator<Item = (usize, Result<String, Error>)>, Error> {
+    let file = File::open(path).map_err(|e| io_error(path, e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            (
+                index + 1,
+                line.map_err(|e| Error::AuditLogError(e.to_string())),
+            )
+        })
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty())))
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> Error {
+    Error::AuditLogError(format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils, SafetyRules, TSafetyRules};
+    use diem_temppath::TempPath;
+    use diem_types::validator_signer::ValidatorSigner;
+    use std::io::Read;
+
+    #[test]
+    fn test_signing_is_audited() {
+        let signer = ValidatorSigner::from_int(0);
+        let path = TempPath::new();
+        let storage =
+            test_utils::test_storage(&signer).with_audit_log(AuditLog::open(path.path()).unwrap());
+        let mut safety_rules = SafetyRules::new(storage, false, false);
+
+        let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+        safety_rules.initialize(&proof).unwrap();
+        let a1 = test_utils::make_proposal_with_qc(1, genesis_qc, &signer, None);
+        let vote = safety_rules
+            .construct_and_sign_vote(&a1.vote_proposal)
+            .unwrap();
+        // Returning the same vote again does not sign anything
+        safety_rules
+            .construct_and_sign_vote(&a1.vote_proposal)
+            .unwrap();
+        let timeout = Timeout::new(1, 2);
+        let timeout_signature = safety_rules.sign_timeout(&timeout).unwrap();
+
+        let summary = verify(path.path(), None).unwrap();
+        assert_eq!(summary.records, 2);
+        assert_eq!(summary.votes, 1);
+        assert_eq!(summary.timeouts, 1);
+        verify(path.path(), Some(summary.last_hash)).unwrap();
+
+        let mut contents = String::new();
+        File::open(path.path())
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let events: Vec<_> = contents
+            .lines()
+            .map(|line| AuditLogRecord::parse(line, 0).unwrap().entry.event)
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                AuditEvent::vote(&vote),
+                AuditEvent::timeout(&timeout, &timeout_signature)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reopen_and_tamper() {
+        let signer = ValidatorSigner::from_int(0);
+        let path = TempPath::new();
+        let timeout = |round| {
+            let timeout = Timeout::new(1, round);
+            AuditEvent::timeout(&timeout, &signer.sign(&timeout))
+        };
+
+        let mut audit_log = AuditLog::open(path.path()).unwrap();
+        audit_log.append(timeout(1)).unwrap();
+        audit_log.append(timeout(2)).unwrap();
+        // Reopening the log continues the hash chain
+        let mut audit_log = AuditLog::open(path.path()).unwrap();
+        let last_hash = audit_log.append(timeout(3)).unwrap();
+        assert_eq!(verify(path.path(), Some(last_hash)).unwrap().records, 3);
+
+        let mut contents = String::new();
+        File::open(path.path())
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+
+        // A missing last record is only detected given the expected last hash
+        std::fs::write(path.path(), lines[..2].join("\n")).unwrap();
+        verify(path.path(), None).unwrap();
+        verify(path.path(), Some(last_hash)).unwrap_err();
+
+        // Removing, reordering or altering records breaks the chain
+        for tampered in &[
+            vec![lines[0], lines[2]],
+            vec![lines[1], lines[0], lines[2]],
+            vec![
+                lines[0],
+                &lines[1].replace("\"round\":2", "\"round\":4"),
+                lines[2],
+            ],
+        ] {
+            std::fs::write(path.path(), tampered.join("\n")).unwrap();
+            verify(path.path(), None).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_torn_record() {
+        let signer = ValidatorSigner::from_int(0);
+        let path = TempPath::new();
+        let timeout = |round| {
+            let timeout = Timeout::new(1, round);
+            AuditEvent::timeout(&timeout, &signer.sign(&timeout))
+        };
+
+        let mut audit_log = AuditLog::open(path.path()).unwrap();
+        audit_log.append(timeout(1)).unwrap();
+        audit_log.append(timeout(2)).unwrap();
+        let mut contents = std::fs::read_to_string(path.path()).unwrap();
+        let lines: Vec<_> = contents.lines().map(str::to_string).collect();
+
+        // A crash in the middle of an append leaves half of a record behind
+        let torn_record = &lines[1][..lines[1].len() / 2];
+        std::fs::write(path.path(), format!("{}\n{}", lines[0], torn_record)).unwrap();
+        verify(path.path(), None).unwrap_err();
+        let mut audit_log = AuditLog::open(path.path()).unwrap();
+        let last_hash = audit_log.append(timeout(3)).unwrap();
+        assert_eq!(verify(path.path(), Some(last_hash)).unwrap().records, 2);
+        assert_eq!(
+            std::fs::read_to_string(torn_path(path.path())).unwrap(),
+            format!("{}\n", torn_record)
+        );
+
+        // A complete record missing its newline is kept
+        contents = std::fs::read_to_string(path.path()).unwrap();
+        std::fs::write(path.path(), contents.trim_end()).unwrap();
+        let mut audit_log = AuditLog::open(path.path()).unwrap();
+        let last_hash = audit_log.append(timeout(4)).unwrap();
+        assert_eq!(verify(path.path(), Some(last_hash)).unwrap().records, 3);
+    }
+
+    #[test]
+    fn test_vote_regression() {
+        let signer = ValidatorSigner::from_int(0);
+        let path = TempPath::new();
+        let vote = |round| AuditEvent::Vote {
+            epoch: 1,
+            round,
+            block_id: HashValue::random(),
+            ledger_info_hash: HashValue::random(),
+            signature: signer.sign(&Timeout::new(1, round)),
+        };
+
+        let mut audit_log = AuditLog::open(path.path()).unwrap();
+        audit_log.append(vote(2)).unwrap();
+        audit_log.append(vote(3)).unwrap();
+        verify(path.path(), None).unwrap();
+        // The hash chain is intact, but the validator voted twice in round 3
+        audit_log.append(vote(3)).unwrap();
+        verify(path.path(), None).unwrap_err();
+    }
+}
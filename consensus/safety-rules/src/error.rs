@@ -41,6 +41,10 @@ pub enum Error {
     ValidatorNotInSet(String),
     #[error("Vote proposal missing expected signature")]
     VoteProposalSignatureNotFound,
+    #[error("Audit log error: {0}")]
+    AuditLogError(String),
+    #[error("Signer lease unavailable: {0}")]
+    SignerLeaseUnavailable(String),
 }
 
 impl From<bcs::Error> for Error {
@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+pub mod audit_log;
 mod configurable_validator_signer;
 mod consensus_state;
 mod counters;
@@ -15,6 +16,7 @@ mod remote_service;
 mod safety_rules;
 mod safety_rules_manager;
 mod serializer;
+pub mod signer_lease;
 mod t_safety_rules;
 mod thread;
 
@@ -2,16 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    audit_log::{AuditEvent, AuditLog},
     counters,
     logging::{self, LogEntry, LogEvent},
+    signer_lease::SignerLease,
     Error,
 };
 use consensus_types::{common::Author, safety_data::SafetyData};
 use diem_crypto::{
+    bls12381::BLS12381PrivateKey,
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
 };
-use diem_global_constants::{CONSENSUS_KEY, EXECUTION_KEY, OWNER_ACCOUNT, SAFETY_DATA, WAYPOINT};
+use diem_global_constants::{
+    CONSENSUS_BLS_KEY, CONSENSUS_KEY, EXECUTION_KEY, OWNER_ACCOUNT, SAFETY_DATA, WAYPOINT,
+};
 use diem_logger::prelude::*;
 use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
 use diem_types::waypoint::Waypoint;
@@ -25,10 +30,15 @@ use serde::Serialize;
 /// only ever be used by safety rules, we maintain an in-memory copy to avoid issuing reads
 /// to the internal storage if the SafetyData hasn't changed. On writes, we update the
 /// cache and internal storage.
+///
+/// If an audit log is attached, every vote and timeout signed is appended to it. If a signer
+/// lease is attached, signing requires holding the lease.
 pub struct PersistentSafetyStorage {
     enable_cached_safety_data: bool,
     cached_safety_data: Option<SafetyData>,
     internal_store: Storage,
+    audit_log: Option<AuditLog>,
+    signer_lease: Option<SignerLease>,
 }
 
 impl PersistentSafetyStorage {
@@ -56,6 +66,8 @@ impl PersistentSafetyStorage {
             enable_cached_safety_data,
             cached_safety_data: Some(safety_data),
             internal_store,
+            audit_log: None,
+            signer_lease: None,
         }
     }
 
@@ -92,9 +104,37 @@ impl PersistentSafetyStorage {
             enable_cached_safety_data,
             cached_safety_data: None,
             internal_store,
+            audit_log: None,
+            signer_lease: None,
         }
     }
 
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    pub fn with_signer_lease(mut self, signer_lease: SignerLease) -> Self {
+        self.signer_lease = Some(signer_lease);
+        self
+    }
+
+    /// Ensures that this instance holds the signer lease, if there is one.
+    pub fn ensure_signer_lease(&mut self) -> Result<(), Error> {
+        if let Some(signer_lease) = &mut self.signer_lease {
+            signer_lease.ensure(&mut self.internal_store)?;
+        }
+        Ok(())
+    }
+
+    /// Records a signing operation in the audit log, if there is one.
+    pub fn audit(&mut self, event: AuditEvent) -> Result<(), Error> {
+        if let Some(audit_log) = &mut self.audit_log {
+            audit_log.append(event)?;
+        }
+        Ok(())
+    }
+
     pub fn author(&self) -> Result<Author, Error> {
         let _timer = counters::start_timer("get", OWNER_ACCOUNT);
         Ok(self.internal_store.get(OWNER_ACCOUNT).map(|v| v.value)?)
@@ -110,6 +150,17 @@ impl PersistentSafetyStorage {
             .export_private_key_for_version(CONSENSUS_KEY, version)?)
     }
 
+    /// Returns the key for aggregate signatures, if one has been provisioned. Unlike the Ed25519
+    /// consensus key, it is stored as a value since crypto storage only supports Ed25519 keys.
+    pub fn consensus_bls_key(&self) -> Result<Option<BLS12381PrivateKey>, Error> {
+        let _timer = counters::start_timer("get", CONSENSUS_BLS_KEY);
+        match self.internal_store.get(CONSENSUS_BLS_KEY) {
+            Ok(response) => Ok(Some(response.value)),
+            Err(diem_secure_storage::Error::KeyNotSet(_)) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
     pub fn execution_public_key(&self) -> Result<Ed25519PublicKey, Error> {
         let _timer = counters::start_timer("get", EXECUTION_KEY);
         Ok(self
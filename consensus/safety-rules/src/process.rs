@@ -6,7 +6,7 @@ use crate::{
     remote_service::{self, RemoteService},
     safety_rules_manager,
 };
-use diem_config::config::{SafetyRulesConfig, SafetyRulesService};
+use diem_config::config::{RemoteServiceNoise, SafetyRulesConfig, SafetyRulesService};
 
 use std::net::SocketAddr;
 
@@ -33,6 +33,7 @@ impl Process {
                 verify_vote_proposal_signature,
                 export_consensus_key,
                 network_timeout: config.network_timeout_ms,
+                noise: service.noise.clone(),
             }),
         }
     }
@@ -45,6 +46,7 @@ impl Process {
             data.verify_vote_proposal_signature,
             data.export_consensus_key,
             data.network_timeout,
+            data.noise,
         );
     }
 }
@@ -56,18 +58,25 @@ struct ProcessData {
     export_consensus_key: bool,
     // Timeout in Seconds for network operations
     network_timeout: u64,
+    noise: Option<RemoteServiceNoise>,
 }
 
 pub struct ProcessService {
     server_addr: SocketAddr,
     network_timeout_ms: u64,
+    noise: Option<RemoteServiceNoise>,
 }
 
 impl ProcessService {
-    pub fn new(server_addr: SocketAddr, network_timeout: u64) -> Self {
+    pub fn new(
+        server_addr: SocketAddr,
+        network_timeout: u64,
+        noise: Option<RemoteServiceNoise>,
+    ) -> Self {
         Self {
             server_addr,
             network_timeout_ms: network_timeout,
+            noise,
         }
     }
 }
@@ -80,4 +89,8 @@ impl RemoteService for ProcessService {
     fn network_timeout_ms(&self) -> u64 {
         self.network_timeout_ms
     }
+
+    fn noise(&self) -> Option<&RemoteServiceNoise> {
+        self.noise.as_ref()
+    }
 }
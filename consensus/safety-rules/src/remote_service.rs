@@ -6,17 +6,27 @@ use crate::{
     serializer::{SafetyRulesInput, SerializerClient, SerializerService, TSerializerClient},
     Error, SafetyRules, TSafetyRules,
 };
+use diem_config::config::RemoteServiceNoise;
 use diem_logger::warn;
 use diem_secure_net::{NetworkClient, NetworkServer};
 use std::net::SocketAddr;
 
 pub trait RemoteService {
     fn client(&self) -> SerializerClient {
-        let network_client = NetworkClient::new(
-            "safety-rules",
-            self.server_address(),
-            self.network_timeout_ms(),
-        );
+        let network_client = match self.noise() {
+            Some(noise) => NetworkClient::new_with_noise(
+                "safety-rules",
+                self.server_address(),
+                self.network_timeout_ms(),
+                noise.private_key.private_key(),
+                noise.server_public_key,
+            ),
+            None => NetworkClient::new(
+                "safety-rules",
+                self.server_address(),
+                self.network_timeout_ms(),
+            ),
+        };
         let service = Box::new(RemoteClient::new(network_client));
         SerializerClient::new_client(service)
     }
@@ -25,6 +35,11 @@ pub trait RemoteService {
 
     /// Network Timeout in milliseconds.
     fn network_timeout_ms(&self) -> u64;
+
+    /// The Noise keys authenticating the connection, if any.
+    fn noise(&self) -> Option<&RemoteServiceNoise> {
+        None
+    }
 }
 
 pub fn execute(
@@ -33,6 +48,7 @@ pub fn execute(
     verify_vote_proposal_signature: bool,
     export_consensus_key: bool,
     network_timeout_ms: u64,
+    noise: Option<RemoteServiceNoise>,
 ) {
     let mut safety_rules = SafetyRules::new(
         storage,
@@ -44,7 +60,16 @@ pub fn execute(
     }
 
     let mut serializer_service = SerializerService::new(safety_rules);
-    let mut network_server = NetworkServer::new("safety-rules", listen_addr, network_timeout_ms);
+    let mut network_server = match noise {
+        Some(noise) => NetworkServer::new_with_noise(
+            "safety-rules",
+            listen_addr,
+            network_timeout_ms,
+            noise.private_key.private_key(),
+            noise.client_public_keys,
+        ),
+        None => NetworkServer::new("safety-rules", listen_addr, network_timeout_ms),
+    };
 
     loop {
         if let Err(e) = process_one_message(&mut network_server, &mut serializer_service) {
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    audit_log::AuditEvent,
     configurable_validator_signer::ConfigurableValidatorSigner,
     consensus_state::ConsensusState,
     counters,
@@ -22,9 +23,10 @@ use consensus_types::{
     vote_proposal::{MaybeSignedVoteProposal, VoteProposal},
 };
 use diem_crypto::{
+    bls12381::BLS12381PrivateKey,
     ed25519::{Ed25519PublicKey, Ed25519Signature},
     hash::{CryptoHash, HashValue},
-    traits::Signature,
+    traits::{PrivateKey, Signature, SigningKey},
 };
 use diem_logger::prelude::*;
 use diem_types::{
@@ -40,6 +42,8 @@ pub struct SafetyRules {
     execution_public_key: Option<Ed25519PublicKey>,
     export_consensus_key: bool,
     validator_signer: Option<ConfigurableValidatorSigner>,
+    /// Signs votes for aggregation, if the validator set has a BLS key for this validator.
+    bls_private_key: Option<BLS12381PrivateKey>,
     epoch_state: Option<EpochState>,
 }
 
@@ -65,6 +69,7 @@ impl SafetyRules {
             execution_public_key,
             export_consensus_key,
             validator_signer: None,
+            bls_private_key: None,
             epoch_state: None,
         }
     }
@@ -324,21 +329,46 @@ impl SafetyRules {
                 }
             }
         };
+        let initialize_result = initialize_result.and_then(|()| {
+            self.bls_private_key = self.bls_key_for_epoch(author, &epoch_state)?;
+            Ok(())
+        });
         initialize_result.map_err(|error| {
             info!(
                 SafetyLogSchema::new(LogEntry::KeyReconciliation, LogEvent::Error).error(&error),
             );
             self.validator_signer = None;
+            self.bls_private_key = None;
             error
         })
     }
 
+    /// Loads the BLS key if the validator set expects aggregatable signatures from this validator.
+    fn bls_key_for_epoch(
+        &self,
+        author: Author,
+        epoch_state: &EpochState,
+    ) -> Result<Option<BLS12381PrivateKey>, Error> {
+        let expected_key = match epoch_state.verifier.get_bls_public_key(&author) {
+            Some(expected_key) => expected_key,
+            None => return Ok(None),
+        };
+        match self.persistent_storage.consensus_bls_key()? {
+            Some(bls_key) if bls_key.public_key() == expected_key => Ok(Some(bls_key)),
+            _ => Err(Error::ValidatorKeyNotFound(format!(
+                "BLS key {}",
+                expected_key
+            ))),
+        }
+    }
+
     fn guarded_construct_and_sign_vote(
         &mut self,
         maybe_signed_vote_proposal: &MaybeSignedVoteProposal,
     ) -> Result<Vote, Error> {
         // Exit early if we cannot sign
         self.signer()?;
+        self.persistent_storage.ensure_signer_lease()?;
 
         let vote_proposal = &maybe_signed_vote_proposal.vote_proposal;
         let execution_signature = maybe_signed_vote_proposal.signature.as_ref();
@@ -379,16 +409,21 @@ impl SafetyRules {
         let author = self.signer()?.author();
         let ledger_info = self.construct_ledger_info(proposed_block, vote_data.hash())?;
         let signature = self.sign(&ledger_info)?;
-        let vote = Vote::new_with_signature(vote_data, author, ledger_info, signature);
+        let mut vote = Vote::new_with_signature(vote_data, author, ledger_info, signature);
+        if let Some(bls_private_key) = &self.bls_private_key {
+            vote.add_bls_signature(bls_private_key.sign(vote.ledger_info()));
+        }
 
         safety_data.last_vote = Some(vote.clone());
         self.persistent_storage.set_safety_data(safety_data)?;
+        self.persistent_storage.audit(AuditEvent::vote(&vote))?;
 
         Ok(vote)
     }
 
     fn guarded_sign_proposal(&mut self, block_data: BlockData) -> Result<Block, Error> {
         self.signer()?;
+        self.persistent_storage.ensure_signer_lease()?;
         self.verify_author(block_data.author())?;
 
         let mut safety_data = self.persistent_storage.safety_data()?;
@@ -415,6 +450,7 @@ impl SafetyRules {
 
     fn guarded_sign_timeout(&mut self, timeout: &Timeout) -> Result<Ed25519Signature, Error> {
         self.signer()?;
+        self.persistent_storage.ensure_signer_lease()?;
 
         let mut safety_data = self.persistent_storage.safety_data()?;
         self.verify_epoch(timeout.epoch(), &safety_data)?;
@@ -437,6 +473,8 @@ impl SafetyRules {
         }
 
         let signature = self.sign(timeout)?;
+        self.persistent_storage
+            .audit(AuditEvent::timeout(timeout, &signature))?;
         Ok(signature)
     }
 }
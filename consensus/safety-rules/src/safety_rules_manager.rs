@@ -2,18 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    audit_log::AuditLog,
     local_client::LocalClient,
     persistent_safety_storage::PersistentSafetyStorage,
     process::ProcessService,
     remote_service::RemoteService,
     serializer::{SerializerClient, SerializerService},
+    signer_lease::SignerLease,
     thread::ThreadService,
     SafetyRules, TSafetyRules,
 };
-use diem_config::config::{SafetyRulesConfig, SafetyRulesService};
+use diem_config::config::{
+    RemoteServiceNoise, SafetyRulesConfig, SafetyRulesService, SecureBackend, SignerLeaseConfig,
+};
+use diem_crypto::HashValue;
 use diem_infallible::RwLock;
 use diem_secure_storage::{KVStorage, Storage};
-use std::{convert::TryInto, net::SocketAddr, sync::Arc};
+use std::{convert::TryInto, net::SocketAddr, path::Path, sync::Arc};
 
 pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
     let backend = &config.backend;
@@ -22,7 +27,7 @@ pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
         panic!("Storage is not available: {:?}", error);
     }
 
-    if let Some(test_config) = &config.test {
+    let mut storage = if let Some(test_config) = &config.test {
         let author = test_config.author;
         let consensus_private_key = test_config
             .consensus_key
@@ -46,7 +51,37 @@ pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
         )
     } else {
         PersistentSafetyStorage::new(internal_storage, config.enable_cached_safety_data)
+    };
+
+    if let Some(audit_log_path) = &config.audit_log_path {
+        storage = storage
+            .with_audit_log(AuditLog::open(audit_log_path).expect("Unable to open the audit log"));
+    }
+    if let Some(signer_lease_config) = &config.signer_lease {
+        storage = storage.with_signer_lease(signer_lease(signer_lease_config, backend));
     }
+    storage
+}
+
+fn signer_lease(config: &SignerLeaseConfig, backend: &SecureBackend) -> SignerLease {
+    let holder = config
+        .holder
+        .clone()
+        .unwrap_or_else(|| HashValue::random().to_hex());
+    let signer_lease = SignerLease::new(holder, config.duration_secs);
+
+    // Several instances can only share on disk storage on the same host, where a file lock is
+    // enough to exclude each other
+    let storage_path = match backend {
+        SecureBackend::OnDiskStorage(config) => config.path(),
+        SecureBackend::EncryptedOnDiskStorage(config) => config.path(),
+        _ => return signer_lease,
+    };
+    let mut lock_path = storage_path.into_os_string();
+    lock_path.push(".signer-lock");
+    signer_lease
+        .with_lock_file(Path::new(&lock_path))
+        .expect("Unable to lock the storage for signing")
 }
 
 enum SafetyRulesWrapper {
@@ -63,7 +98,11 @@ pub struct SafetyRulesManager {
 impl SafetyRulesManager {
     pub fn new(config: &SafetyRulesConfig) -> Self {
         if let SafetyRulesService::Process(conf) = &config.service {
-            return Self::new_process(conf.server_address(), config.network_timeout_ms);
+            return Self::new_process(
+                conf.server_address(),
+                config.network_timeout_ms,
+                conf.noise.clone(),
+            );
         }
 
         let storage = storage(config);
@@ -105,8 +144,12 @@ impl SafetyRulesManager {
         }
     }
 
-    pub fn new_process(server_addr: SocketAddr, timeout_ms: u64) -> Self {
-        let process_service = ProcessService::new(server_addr, timeout_ms);
+    pub fn new_process(
+        server_addr: SocketAddr,
+        timeout_ms: u64,
+        noise: Option<RemoteServiceNoise>,
+    ) -> Self {
+        let process_service = ProcessService::new(server_addr, timeout_ms, noise);
         Self {
             internal_safety_rules: SafetyRulesWrapper::Process(process_service),
         }
@@ -0,0 +1,232 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A lease in secure storage that allows a single SafetyRules instance at a time to sign with a
+//! consensus key. An instance takes the lease before signing once the previous holder's lease
+//! expired, and renews it while signing. Backends supporting compare-and-set (e.g., Vault) reject
+//! a renewal if another instance wrote the lease since it was read. Every acquisition and renewal
+//! also reads the lease back, so that an instance overwritten by a concurrent write stops signing.
+//! On disk backends are additionally guarded by an exclusive lock on a file next to the storage.
+
+use crate::{counters, Error};
+use diem_global_constants::SAFETY_RULES_LEASE;
+use diem_logger::prelude::*;
+use diem_secure_storage::{KVStorage, Storage};
+use diem_time_service::{TimeService, TimeServiceTrait};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    path::Path,
+};
+
+/// The lease as held in secure storage.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LeaseRecord {
+    pub holder: String,
+    /// Time since Unix Epoch in seconds, after which another instance may take the lease.
+    pub expiration_secs: u64,
+}
+
+pub struct SignerLease {
+    holder: String,
+    duration_secs: u64,
+    time_service: TimeService,
+    /// The expiration of the lease, while this instance holds it
+    expiration_secs: Option<u64>,
+    /// Held for as long as this instance runs, as the lock is released when the file is closed
+    _lock_file: Option<File>,
+}
+
+impl SignerLease {
+    pub fn new(holder: String, duration_secs: u64) -> Self {
+        Self::new_with_time_service(holder, duration_secs, TimeService::real())
+    }
+
+    fn new_with_time_service(
+        holder: String,
+        duration_secs: u64,
+        time_service: TimeService,
+    ) -> Self {
+        Self {
+            holder,
+            duration_secs,
+            time_service,
+            expiration_secs: None,
+            _lock_file: None,
+        }
+    }
+
+    /// Takes an exclusive lock on the file at the given path, failing if another instance holds
+    /// it.
+    pub fn with_lock_file(mut self, path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(path)
+            .map_err(|e| Error::SignerLeaseUnavailable(format!("{}: {}", path.display(), e)))?;
+        file.try_lock_exclusive().map_err(|e| {
+            Error::SignerLeaseUnavailable(format!(
+                "{} is locked by another instance: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        self._lock_file = Some(file);
+        Ok(self)
+    }
+
+    /// Ensures that this instance holds the lease, taking or renewing it if needed.
+    pub fn ensure(&mut self, storage: &mut Storage) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        if let Some(expiration_secs) = self.expiration_secs {
+            if now.saturating_add(self.duration_secs / 2) < expiration_secs {
+                return Ok(());
+            }
+        }
+
+        let result = self.acquire(storage, now);
+        counters::set_state("signer_lease", result.is_ok() as i64);
+        if let Err(error) = &result {
+            if self.expiration_secs.take().is_some() {
+                error!(holder = self.holder, error = %error, "Lost the signer lease");
+            }
+        }
+        result
+    }
+
+    fn acquire(&mut self, storage: &mut Storage, now: u64) -> Result<(), Error> {
+        // Reading the lease also records its version for backends that support compare-and-set
+        self.check_holder(storage, now)?;
+
+        let expiration_secs = now.saturating_add(self.duration_secs);
+        storage
+            .set(
+                SAFETY_RULES_LEASE,
+                LeaseRecord {
+                    holder: self.holder.clone(),
+                    expiration_secs,
+                },
+            )
+            .map_err(|e| Error::SignerLeaseUnavailable(e.to_string()))?;
+
+        // Without compare-and-set, a concurrent acquisition may have overwritten the lease
+        match self.read(storage)? {
+            Some(lease) if lease.holder == self.holder => {
+                if self.expiration_secs.replace(expiration_secs).is_none() {
+                    info!(
+                        holder = self.holder,
+                        expiration_secs = expiration_secs,
+                        "Acquired the signer lease"
+                    );
+                }
+                Ok(())
+            }
+            _ => Err(Error::SignerLeaseUnavailable(
+                "The lease was taken by a concurrent instance".into(),
+            )),
+        }
+    }
+
+    fn check_holder(&self, storage: &Storage, now: u64) -> Result<(), Error> {
+        match self.read(storage)? {
+            Some(lease) if lease.holder != self.holder && lease.expiration_secs > now => {
+                Err(Error::SignerLeaseUnavailable(format!(
+                    "Held by {} for another {} seconds",
+                    lease.holder,
+                    lease.expiration_secs - now
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn read(&self, storage: &Storage) -> Result<Option<LeaseRecord>, Error> {
+        match storage.get(SAFETY_RULES_LEASE) {
+            Ok(response) => Ok(Some(response.value)),
+            Err(diem_secure_storage::Error::KeyNotSet(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils, SafetyRules, TSafetyRules};
+    use consensus_types::timeout::Timeout;
+    use diem_secure_storage::InMemoryStorage;
+    use diem_temppath::TempPath;
+    use diem_types::validator_signer::ValidatorSigner;
+
+    #[test]
+    fn test_lease_expiration() {
+        let time_service = TimeService::mock();
+        let mut storage = Storage::from(InMemoryStorage::new());
+        let mut first =
+            SignerLease::new_with_time_service("first".into(), 10, time_service.clone());
+        let mut second =
+            SignerLease::new_with_time_service("second".into(), 10, time_service.clone());
+        let time_service = time_service.into_mock();
+        time_service.advance_secs(1_000);
+
+        first.ensure(&mut storage).unwrap();
+        second.ensure(&mut storage).unwrap_err();
+
+        // The first instance renews the lease once half of it has elapsed
+        time_service.advance_secs(6);
+        first.ensure(&mut storage).unwrap();
+        time_service.advance_secs(6);
+        second.ensure(&mut storage).unwrap_err();
+        first.ensure(&mut storage).unwrap();
+
+        // Once the lease expires without being renewed, it can be taken by another instance
+        time_service.advance_secs(11);
+        second.ensure(&mut storage).unwrap();
+        first.ensure(&mut storage).unwrap_err();
+        assert_eq!(first.expiration_secs, None);
+    }
+
+    #[test]
+    fn test_lock_file() {
+        let path = TempPath::new();
+        let first = SignerLease::new("first".into(), 10)
+            .with_lock_file(path.path())
+            .unwrap();
+        assert!(SignerLease::new("second".into(), 10)
+            .with_lock_file(path.path())
+            .is_err());
+        drop(first);
+        SignerLease::new("second".into(), 10)
+            .with_lock_file(path.path())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_signing_requires_lease() {
+        let signer = ValidatorSigner::from_int(0);
+        let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+        let mut storage = test_utils::test_storage(&signer)
+            .with_signer_lease(SignerLease::new("first".into(), 10));
+        // Another instance takes the lease first
+        SignerLease::new("second".into(), 10)
+            .ensure(storage.internal_store())
+            .unwrap();
+
+        let mut safety_rules = SafetyRules::new(storage, false, false);
+        safety_rules.initialize(&proof).unwrap();
+        let a1 = test_utils::make_proposal_with_qc(1, genesis_qc, &signer, None);
+        assert!(matches!(
+            safety_rules.construct_and_sign_vote(&a1.vote_proposal),
+            Err(Error::SignerLeaseUnavailable(_))
+        ));
+        assert!(matches!(
+            safety_rules.sign_timeout(&Timeout::new(1, 1)),
+            Err(Error::SignerLeaseUnavailable(_))
+        ));
+        assert_eq!(
+            safety_rules.consensus_state().unwrap().last_voted_round(),
+            0
+        );
+    }
+}
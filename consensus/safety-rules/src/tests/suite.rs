@@ -7,14 +7,17 @@ use consensus_types::{
     timeout::Timeout, vote_proposal::MaybeSignedVoteProposal,
 };
 use diem_crypto::{
+    bls12381::BLS12381PrivateKey,
     ed25519::Ed25519PrivateKey,
     hash::{CryptoHash, HashValue},
+    PrivateKey, Uniform,
 };
-use diem_global_constants::CONSENSUS_KEY;
-use diem_secure_storage::CryptoStorage;
+use diem_global_constants::{CONSENSUS_BLS_KEY, CONSENSUS_KEY};
+use diem_secure_storage::{CryptoStorage, KVStorage};
 use diem_types::{
-    epoch_state::EpochState, validator_signer::ValidatorSigner,
-    validator_verifier::ValidatorVerifier,
+    epoch_state::EpochState,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
 };
 
 type Proof = test_utils::Proof;
@@ -63,6 +66,7 @@ pub fn run_test_suite(safety_rules: &Callback) {
     test_sign_proposal_with_early_preferred_round(safety_rules);
     test_uninitialized_signer(safety_rules);
     test_reconcile_key(safety_rules);
+    test_bls_vote_signature(safety_rules);
     test_validator_not_in_set(safety_rules);
     test_key_not_in_store(safety_rules);
 }
@@ -733,6 +737,83 @@ fn test_reconcile_key(_safety_rules: &Callback) {
     );
 }
 
+fn test_bls_vote_signature(_safety_rules: &Callback) {
+    // Test that votes carry a BLS signature once the validator set has a BLS key for the signer,
+    // and that the epoch can't be entered without the BLS key in storage.
+    let signer = ValidatorSigner::from_int(0);
+    let bls_key = BLS12381PrivateKey::generate_for_testing();
+    let validator_info = ValidatorConsensusInfo::new(signer.public_key(), 1)
+        .with_bls_public_key(bls_key.public_key(), &bls_key.create_proof_of_possession())
+        .unwrap();
+    let mut next_epoch_state = EpochState::empty();
+    next_epoch_state.epoch = 2;
+    next_epoch_state.verifier = ValidatorVerifier::new(
+        vec![(signer.author(), validator_info)]
+            .into_iter()
+            .collect(),
+    );
+
+    for has_bls_key in [false, true].iter() {
+        let mut storage = test_utils::test_storage(&signer);
+        if *has_bls_key {
+            storage
+                .internal_store()
+                .set(CONSENSUS_BLS_KEY, &bls_key)
+                .unwrap();
+        }
+        let mut safety_rules = Box::new(SafetyRules::new(storage, false, false));
+
+        let (mut proof, genesis_qc) = test_utils::make_genesis(&signer);
+        let round = genesis_qc.certified_block().round();
+        safety_rules.initialize(&proof).unwrap();
+
+        // The genesis validator set has no BLS keys
+        let a1 = test_utils::make_proposal_with_qc(round + 1, genesis_qc, &signer, None);
+        let vote = safety_rules.construct_and_sign_vote(&a1).unwrap();
+        assert!(vote.bls_signature().is_none());
+
+        let a2 = test_utils::make_proposal_with_parent_and_overrides(
+            vec![],
+            round + 2,
+            &a1,
+            Some(&a1),
+            &signer,
+            Some(1),
+            Some(next_epoch_state.clone()),
+            None,
+        );
+        safety_rules.construct_and_sign_vote(&a2).unwrap();
+        proof
+            .ledger_info_with_sigs
+            .push(a2.block().quorum_cert().ledger_info().clone());
+        if !has_bls_key {
+            safety_rules.initialize(&proof).unwrap_err();
+            continue;
+        }
+        safety_rules.initialize(&proof).unwrap();
+
+        let a3 = test_utils::make_proposal_with_parent_and_overrides(
+            vec![],
+            round + 3,
+            &a2,
+            Some(&a2),
+            &signer,
+            Some(2),
+            None,
+            None,
+        );
+        let vote = safety_rules.construct_and_sign_vote(&a3).unwrap();
+        next_epoch_state
+            .verifier
+            .verify_bls(
+                signer.author(),
+                vote.ledger_info(),
+                vote.bls_signature().unwrap(),
+            )
+            .unwrap();
+    }
+}
+
 // Tests for fetching a missing validator key from persistent storage.
 fn test_key_not_in_store(safety_rules: &Callback) {
     let (mut safety_rules, signer, key) = safety_rules();
@@ -2,17 +2,59 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_config::{
-    config::{NodeConfig, PersistableConfig, RemoteService, SafetyRulesService},
+    config::{
+        NodeConfig, PersistableConfig, RemoteService, RemoteServiceNoise, SafetyRulesConfig,
+        SafetyRulesService,
+    },
     utils,
 };
-use diem_types::validator_signer::ValidatorSigner;
+use diem_crypto::{x25519, Uniform};
+use diem_types::{network_address::NetworkAddress, validator_signer::ValidatorSigner};
+use rand::{rngs::StdRng, SeedableRng};
 use safety_rules::{test_utils, SafetyRulesManager};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
 
 const BINARY: &str = env!("CARGO_BIN_EXE_safety-rules");
 
 #[test]
 fn test_consensus_state() {
+    let (config, _) = process_config();
+    check_consensus_state(&config, &config);
+}
+
+#[test]
+fn test_consensus_state_with_noise() {
+    let (mut server_config, server_address) = process_config();
+    let mut client_config = server_config.clone();
+
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let server_key = x25519::PrivateKey::generate(&mut rng);
+    let client_key = x25519::PrivateKey::generate(&mut rng);
+    let server_public_key = server_key.public_key();
+    let client_public_keys: HashSet<_> = vec![client_key.public_key()].into_iter().collect();
+    server_config.service = SafetyRulesService::Process(RemoteService {
+        server_address: server_address.clone(),
+        noise: Some(RemoteServiceNoise::new(
+            server_key,
+            server_public_key,
+            client_public_keys.clone(),
+        )),
+    });
+    client_config.service = SafetyRulesService::Process(RemoteService {
+        server_address,
+        noise: Some(RemoteServiceNoise::new(
+            client_key,
+            server_public_key,
+            client_public_keys,
+        )),
+    });
+    check_consensus_state(&server_config, &client_config);
+}
+
+fn process_config() -> (SafetyRulesConfig, NetworkAddress) {
     let mut config = NodeConfig::random().consensus.safety_rules;
     let test_config = config.test.as_mut().unwrap();
     let private_key = test_config.consensus_key.as_ref().unwrap().private_key();
@@ -21,12 +63,20 @@ fn test_consensus_state() {
     test_config.waypoint = Some(waypoint);
 
     let server_port = utils::get_available_port();
-    let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port).into();
-    config.service = SafetyRulesService::Process(RemoteService { server_address });
+    let server_address: NetworkAddress =
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port).into();
+    config.service = SafetyRulesService::Process(RemoteService {
+        server_address: server_address.clone(),
+        noise: None,
+    });
+    (config, server_address)
+}
 
+/// Runs the safety-rules binary with `server_config` and queries it through `client_config`.
+fn check_consensus_state(server_config: &SafetyRulesConfig, client_config: &SafetyRulesConfig) {
     let config_path = diem_temppath::TempPath::new();
     config_path.create_as_file().unwrap();
-    config.save_config(config_path.path()).unwrap();
+    server_config.save_config(config_path.path()).unwrap();
 
     let mut command = std::process::Command::new(BINARY);
     command
@@ -36,7 +86,7 @@ fn test_consensus_state() {
         .stderr(std::process::Stdio::inherit());
     let mut child = command.spawn().unwrap();
 
-    let safety_rules_manager = SafetyRulesManager::new(&config);
+    let safety_rules_manager = SafetyRulesManager::new(client_config);
     let mut safety_rules = safety_rules_manager.client();
     let consensus_state = safety_rules.consensus_state();
 
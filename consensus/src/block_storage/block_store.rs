@@ -20,13 +20,17 @@ use consensus_types::{
     block::Block, executed_block::ExecutedBlock, quorum_cert::QuorumCert, sync_info::SyncInfo,
     timeout_certificate::TimeoutCertificate,
 };
-use diem_crypto::HashValue;
-use diem_infallible::RwLock;
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
 use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::TransactionStatus};
 use executor_types::{Error, StateComputeResult};
 use short_hex_str::AsShortHexStr;
-use std::{collections::vec_deque::VecDeque, sync::Arc, time::Duration};
+use std::{
+    collections::{vec_deque::VecDeque, HashMap},
+    sync::Arc,
+    time::Duration,
+};
 
 #[cfg(test)]
 #[path = "block_store_test.rs"]
@@ -94,6 +98,9 @@ pub struct BlockStore {
     storage: Arc<dyn PersistentLivenessStorage>,
     /// Used to ensure that any block stored will have a timestamp < the local time
     time_service: Arc<dyn TimeService>,
+    /// Ledger infos signed with an aggregate signature by the votes of their QC, keyed by the
+    /// ledger info hash. They are committed instead of the QC's individual signatures.
+    aggregated_ledger_infos: Mutex<HashMap<HashValue, LedgerInfoWithSignatures>>,
 }
 
 impl BlockStore {
@@ -176,6 +183,7 @@ impl BlockStore {
             state_computer,
             storage,
             time_service,
+            aggregated_ledger_infos: Mutex::new(HashMap::new()),
         };
         for block in blocks {
             block_store
@@ -214,6 +222,18 @@ impl BlockStore {
             .path_from_root(block_id_to_commit)
             .unwrap_or_else(Vec::new);
 
+        // The aggregate signature is a more compact proof to persist and serve to clients.
+        let finality_proof = {
+            let mut aggregated_ledger_infos = self.aggregated_ledger_infos.lock();
+            let finality_proof = aggregated_ledger_infos
+                .remove(&finality_proof.ledger_info().hash())
+                .unwrap_or(finality_proof);
+            aggregated_ledger_infos.retain(|_, ledger_info| {
+                ledger_info.ledger_info().round() > block_to_commit.round()
+            });
+            finality_proof
+        };
+
         self.state_computer
             .commit(
                 blocks_to_commit.iter().map(|b| b.id()).collect(),
@@ -282,6 +302,14 @@ impl BlockStore {
         }
     }
 
+    /// Keeps a ledger info signed with an aggregate signature, to be committed in place of the
+    /// individually signed ledger info of the same QC.
+    pub fn insert_aggregated_ledger_info(&self, ledger_info: LedgerInfoWithSignatures) {
+        self.aggregated_ledger_infos
+            .lock()
+            .insert(ledger_info.ledger_info().hash(), ledger_info);
+    }
+
     /// Execute and insert a block if it passes all validation tests.
     /// Returns the Arc to the block kept in the block store after persisting it to storage
     ///
@@ -266,7 +266,11 @@ impl BlockRetriever {
         num_blocks: u64,
     ) -> anyhow::Result<Vec<Block>> {
         let block_id = qc.certified_block().id();
-        let mut peers: Vec<&AccountAddress> = qc.ledger_info().signatures().keys().collect();
+        let mut peers: Vec<&AccountAddress> = qc
+            .ledger_info()
+            .signatures()
+            .map(|signatures| signatures.keys().collect())
+            .unwrap_or_default();
         let mut attempt = 0_u32;
         loop {
             if peers.is_empty() {
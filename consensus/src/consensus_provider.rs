@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensusdb::ConsensusDB,
     counters,
     epoch_manager::EpochManager,
     network::NetworkTask,
@@ -30,6 +31,7 @@ pub fn start_consensus(
     network_events: ConsensusNetworkEvents,
     state_sync_client: StateSyncClient,
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
+    consensus_db: Arc<ConsensusDB>,
     diem_db: Arc<dyn DbReader>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
 ) -> Runtime {
@@ -38,7 +40,7 @@ pub fn start_consensus(
         .enable_all()
         .build()
         .expect("Failed to create Tokio runtime!");
-    let storage = Arc::new(StorageWriteProxy::new(node_config, diem_db));
+    let storage = Arc::new(StorageWriteProxy::new(consensus_db, diem_db));
     let txn_manager = Arc::new(MempoolProxy::new(
         consensus_to_mempool_sender,
         node_config.consensus.mempool_poll_count,
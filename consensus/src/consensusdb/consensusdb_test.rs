@@ -65,3 +65,19 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_create_checkpoint() {
+    let tmp_dir = TempPath::new();
+    let checkpoint_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    let vote = vec![2u8, 1, 0];
+    db.save_vote(vote.clone()).unwrap();
+    db.create_checkpoint(&checkpoint_dir).unwrap();
+    db.delete_last_vote_msg().unwrap();
+
+    let checkpoint = ConsensusDB::new(&checkpoint_dir);
+    assert_eq!(checkpoint.get_last_vote().unwrap(), Some(vote));
+    assert!(db.get_last_vote().unwrap().is_none());
+}
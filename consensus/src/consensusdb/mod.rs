@@ -131,6 +131,16 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Create a consistent copy of the DB under `checkpoint_root_path`, which can be opened with
+    /// `ConsensusDB::new` like the original.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, checkpoint_root_path: P) -> Result<()> {
+        let path = checkpoint_root_path.as_ref().join("consensusdb");
+        std::fs::create_dir_all(checkpoint_root_path.as_ref())?;
+        self.db.create_checkpoint(&path)?;
+        info!("Created ConsensusDB checkpoint at {:?}", path);
+        Ok(())
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<(), DbError> {
@@ -51,6 +51,15 @@ pub static COMMITTED_BLOCKS_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the votes received that were signed by this validator's key but not sent by it.
+pub static CONFLICTING_OWN_VOTES_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_conflicting_own_votes_count",
+        "Count of the votes received that were signed by this validator's key but not sent by it."
+    )
+    .unwrap()
+});
+
 /// Count of the committed transactions since last restart.
 pub static COMMITTED_TXNS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
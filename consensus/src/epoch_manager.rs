@@ -381,9 +381,15 @@ impl EpochManager {
         let validator_set: ValidatorSet = payload
             .get()
             .expect("failed to get ValidatorSet from payload");
-        let epoch_state = EpochState {
-            epoch: payload.epoch(),
-            verifier: (&validator_set).into(),
+        // The validator set lacks the BLS keys registered in the consensus config, which the
+        // epoch state committed by the reconfiguration has.
+        let ledger_recovery_data = self.storage.recover_from_ledger();
+        let epoch_state = match ledger_recovery_data.next_epoch_state() {
+            Some(epoch_state) if epoch_state.epoch == payload.epoch() => epoch_state.clone(),
+            _ => EpochState {
+                epoch: payload.epoch(),
+                verifier: (&validator_set).into(),
+            },
         };
 
         match self.storage.start() {
@@ -40,6 +40,7 @@ pub mod consensus_provider;
 /// DiemNet interface.
 pub mod network_interface;
 
+pub use consensusdb::ConsensusDB;
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
 pub use util::config_subscription::gen_consensus_reconfig_subscription;
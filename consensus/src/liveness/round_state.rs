@@ -8,7 +8,10 @@ use crate::{
 };
 use consensus_types::{common::Round, sync_info::SyncInfo, vote::Vote};
 use diem_logger::{prelude::*, Schema};
-use diem_types::validator_verifier::ValidatorVerifier;
+use diem_types::{
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    validator_verifier::ValidatorVerifier,
+};
 use serde::Serialize;
 use std::{fmt, sync::Arc, time::Duration};
 
@@ -266,6 +269,16 @@ impl RoundState {
         }
     }
 
+    /// Aggregates the BLS signatures of the votes on `ledger_info` in the current round.
+    pub fn aggregate_ledger_info(
+        &self,
+        ledger_info: &LedgerInfo,
+        verifier: &ValidatorVerifier,
+    ) -> Option<LedgerInfoWithSignatures> {
+        self.pending_votes
+            .aggregate_ledger_info(ledger_info, verifier)
+    }
+
     pub fn record_vote(&mut self, vote: Vote) {
         if vote.vote_data().proposed().round() == self.current_round {
             self.vote_sent = Some(vote);
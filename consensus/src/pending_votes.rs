@@ -13,7 +13,7 @@ use consensus_types::{
 use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_logger::prelude::*;
 use diem_types::{
-    ledger_info::LedgerInfoWithSignatures,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures, LedgerInfoWithV0},
     validator_verifier::{ValidatorVerifier, VerifyError},
 };
 use std::{
@@ -48,7 +48,7 @@ pub struct PendingVotes {
     /// Maps LedgerInfo digest to associated signatures (contained in a partial LedgerInfoWithSignatures).
     /// This might keep multiple LedgerInfos for the current round: either due to different proposals (byzantine behavior)
     /// or due to different NIL proposals (clients can have a different view of what block to extend).
-    li_digest_to_votes: HashMap<HashValue /* LedgerInfo digest */, LedgerInfoWithSignatures>,
+    li_digest_to_votes: HashMap<HashValue /* LedgerInfo digest */, LedgerInfoWithV0>,
     /// Tracks all the signatures of the votes for the given round. In case we succeed to
     /// aggregate 2f+1 signatures a TimeoutCertificate is formed.
     maybe_partial_tc: Option<TimeoutCertificate>,
@@ -115,7 +115,7 @@ impl PendingVotes {
         // obtain the ledger info with signatures associated to the vote's ledger info
         let li_with_sig = self.li_digest_to_votes.entry(li_digest).or_insert_with(|| {
             // if the ledger info with signatures doesn't exist yet, create it
-            LedgerInfoWithSignatures::new(vote.ledger_info().clone(), BTreeMap::new())
+            LedgerInfoWithV0::new(vote.ledger_info().clone(), BTreeMap::new())
         });
 
         // add this vote to the ledger info with signatures
@@ -128,7 +128,7 @@ impl PendingVotes {
                 Ok(_) => {
                     return VoteReceptionResult::NewQuorumCertificate(Arc::new(QuorumCert::new(
                         vote.vote_data().clone(),
-                        li_with_sig.clone(),
+                        LedgerInfoWithSignatures::V0(li_with_sig.clone()),
                     )));
                 }
 
@@ -189,6 +189,42 @@ impl PendingVotes {
 
         VoteReceptionResult::VoteAdded(voting_power)
     }
+
+    /// Aggregates the BLS signatures of the votes on `ledger_info` into a single signature.
+    /// Returns None unless every validator has a BLS key and the votes carrying a BLS signature
+    /// have quorum voting power.
+    pub fn aggregate_ledger_info(
+        &self,
+        ledger_info: &LedgerInfo,
+        validator_verifier: &ValidatorVerifier,
+    ) -> Option<LedgerInfoWithSignatures> {
+        if !validator_verifier.supports_aggregate_signatures() {
+            return None;
+        }
+        let li_digest = ledger_info.hash();
+        let bls_signatures: BTreeMap<_, _> = self
+            .author_to_vote
+            .values()
+            .filter(|vote| vote.ledger_info().hash() == li_digest)
+            .filter_map(|vote| Some((vote.author(), vote.bls_signature()?.clone())))
+            .collect();
+        validator_verifier
+            .check_voting_power(bls_signatures.keys())
+            .ok()?;
+        match validator_verifier.aggregate_signatures(&bls_signatures) {
+            Ok(signature) => Some(LedgerInfoWithSignatures::new_aggregated(
+                ledger_info.clone(),
+                signature,
+            )),
+            Err(error) => {
+                error!(
+                    "BLS signatures of votes could not be aggregated: {}, ledger info: {}",
+                    error, ledger_info
+                );
+                None
+            }
+        }
+    }
 }
 
 //
@@ -233,11 +269,16 @@ impl fmt::Display for PendingVotes {
 mod tests {
     use super::{PendingVotes, VoteReceptionResult};
     use consensus_types::{vote::Vote, vote_data::VoteData};
-    use diem_crypto::HashValue;
+    use diem_crypto::{bls12381::BLS12381PrivateKey, HashValue, Uniform};
     use diem_types::{
-        block_info::BlockInfo, ledger_info::LedgerInfo,
-        validator_verifier::random_validator_verifier,
+        block_info::BlockInfo,
+        ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+        validator_signer::ValidatorSigner,
+        validator_verifier::{
+            random_validator_verifier, ValidatorConsensusInfo, ValidatorVerifier,
+        },
     };
+    use rand::{rngs::StdRng, SeedableRng};
 
     /// Creates a random ledger info for epoch 1 and round 1.
     fn random_ledger_info() -> LedgerInfo {
@@ -310,7 +351,7 @@ mod tests {
         match pending_votes.insert_vote(&vote_data_2_author_2, &validator) {
             VoteReceptionResult::NewQuorumCertificate(qc) => {
                 assert!(validator
-                    .check_voting_power(qc.ledger_info().signatures().keys())
+                    .check_voting_power(qc.ledger_info().signatures().unwrap().keys())
                     .is_ok());
             }
             _ => {
@@ -319,6 +360,78 @@ mod tests {
         };
     }
 
+    #[test]
+    /// Verify that the BLS signatures of the votes forming a QC are aggregated
+    fn test_ledger_info_aggregation() {
+        ::diem_logger::Logger::init_for_testing();
+
+        // set up 4 validators with BLS keys
+        let signers: Vec<_> = (0..4u8)
+            .map(|i| {
+                ValidatorSigner::random([i; 32]).with_bls_private_key(BLS12381PrivateKey::generate(
+                    &mut StdRng::from_seed([i; 32]),
+                ))
+            })
+            .collect();
+        let validator = ValidatorVerifier::new(
+            signers
+                .iter()
+                .map(|signer| {
+                    let validator_info = ValidatorConsensusInfo::new(signer.public_key(), 1)
+                        .with_bls_public_key(
+                            signer.bls_public_key().unwrap(),
+                            &signer.bls_proof_of_possession().unwrap(),
+                        )
+                        .unwrap();
+                    (signer.author(), validator_info)
+                })
+                .collect(),
+        );
+        let mut pending_votes = PendingVotes::new();
+
+        let li = random_ledger_info();
+        let vote_data = random_vote_data();
+        for signer in &signers[..2] {
+            let vote = Vote::new(vote_data.clone(), signer.author(), li.clone(), signer);
+            pending_votes.insert_vote(&vote, &validator);
+        }
+        // two votes are not a quorum
+        assert!(pending_votes
+            .aggregate_ledger_info(&li, &validator)
+            .is_none());
+
+        let vote = Vote::new(vote_data, signers[2].author(), li.clone(), &signers[2]);
+        match pending_votes.insert_vote(&vote, &validator) {
+            VoteReceptionResult::NewQuorumCertificate(qc) => {
+                let aggregated = pending_votes
+                    .aggregate_ledger_info(qc.ledger_info().ledger_info(), &validator)
+                    .unwrap();
+                assert!(matches!(aggregated, LedgerInfoWithSignatures::V1(_)));
+                assert_eq!(aggregated.ledger_info(), &li);
+                aggregated.verify_signatures(&validator).unwrap();
+                assert_eq!(
+                    aggregated.signers(&validator).unwrap(),
+                    qc.ledger_info().signers(&validator).unwrap()
+                );
+            }
+            _ => {
+                panic!("No QC formed.");
+            }
+        };
+
+        // validators without BLS keys don't aggregate
+        let (signers, validator) = random_validator_verifier(4, Some(2), false);
+        let mut pending_votes = PendingVotes::new();
+        let vote_data = random_vote_data();
+        for signer in &signers[..2] {
+            let vote = Vote::new(vote_data.clone(), signer.author(), li.clone(), signer);
+            pending_votes.insert_vote(&vote, &validator);
+        }
+        assert!(pending_votes
+            .aggregate_ledger_info(&li, &validator)
+            .is_none());
+    }
+
     #[test]
     /// Verify that votes are properly aggregated to TC based on their rounds
     fn test_tc_aggregation() {
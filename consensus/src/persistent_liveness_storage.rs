@@ -6,12 +6,11 @@ use anyhow::{format_err, Context, Result};
 use consensus_types::{
     block::Block, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
 };
-use diem_config::config::NodeConfig;
 use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
-    block_info::Round, epoch_change::EpochChangeProof, ledger_info::LedgerInfo,
-    transaction::Version,
+    block_info::Round, epoch_change::EpochChangeProof, epoch_state::EpochState,
+    ledger_info::LedgerInfo, transaction::Version,
 };
 use executor_types::ExecutedTrees;
 use std::{cmp::max, collections::HashSet, sync::Arc};
@@ -68,6 +67,11 @@ impl LedgerRecoveryData {
         self.storage_ledger.round()
     }
 
+    /// The epoch state started by the latest ledger info, if it ends an epoch.
+    pub fn next_epoch_state(&self) -> Option<&EpochState> {
+        self.storage_ledger.next_epoch_state()
+    }
+
     /// Finds the root (last committed block) and returns the root block, the QC to the root block
     /// and the ledger info for the root block, return an error if it can not be found.
     ///
@@ -276,8 +280,7 @@ pub struct StorageWriteProxy {
 }
 
 impl StorageWriteProxy {
-    pub fn new(config: &NodeConfig, diem_db: Arc<dyn DbReader>) -> Self {
-        let db = Arc::new(ConsensusDB::new(config.storage.dir()));
+    pub fn new(db: Arc<ConsensusDB>, diem_db: Arc<dyn DbReader>) -> Self {
         StorageWriteProxy { db, diem_db }
     }
 }
@@ -639,6 +639,10 @@ impl RoundManager {
             vote_state = vote.vote_data().proposed().executed_state_id(),
         );
 
+        if vote.author() == self.proposal_generator.author() {
+            self.check_own_vote(vote);
+        }
+
         if !vote.is_timeout() {
             // Unlike timeout votes regular votes are sent to the leaders of the next round only.
             let next_round = round + 1;
@@ -665,6 +669,12 @@ impl RoundManager {
             .insert_vote(vote, &self.epoch_state.verifier)
         {
             VoteReceptionResult::NewQuorumCertificate(qc) => {
+                if let Some(ledger_info) = self.round_state.aggregate_ledger_info(
+                    qc.ledger_info().ledger_info(),
+                    &self.epoch_state.verifier,
+                ) {
+                    self.block_store.insert_aggregated_ledger_info(ledger_info);
+                }
                 self.new_qc_aggregated(qc, vote.author()).await
             }
             VoteReceptionResult::NewTimeoutCertificate(tc) => self.new_tc_aggregated(tc).await,
@@ -672,6 +682,24 @@ impl RoundManager {
         }
     }
 
+    /// A vote signed by this validator's key is expected to be the vote it sent in this round.
+    /// Otherwise, another instance signs with the same key, which must be alerted on since it can
+    /// break safety.
+    fn check_own_vote(&self, vote: &Vote) {
+        let vote_sent = self.round_state.vote_sent();
+        let conflicting = vote_sent.as_ref().map_or(true, |vote_sent| {
+            vote_sent.ledger_info() != vote.ledger_info()
+        });
+        if conflicting {
+            counters::CONFLICTING_OWN_VOTES_COUNT.inc();
+            error!(
+                SecurityEvent::ConsensusConflictingOwnVote,
+                vote = vote,
+                vote_sent = vote_sent,
+            );
+        }
+    }
+
     async fn new_qc_aggregated(
         &mut self,
         qc: Arc<QuorumCert>,
@@ -3,6 +3,7 @@
 
 use crate::{
     block_storage::{BlockReader, BlockStore},
+    counters,
     liveness::{
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
@@ -33,11 +34,14 @@ use consensus_types::{
     sync_info::SyncInfo,
     timeout::Timeout,
     timeout_certificate::TimeoutCertificate,
+    vote::Vote,
+    vote_data::VoteData,
     vote_msg::VoteMsg,
 };
-use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, Uniform};
+use diem_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, Uniform};
 use diem_secure_storage::Storage;
 use diem_types::{
+    block_info::BlockInfo,
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     validator_signer::ValidatorSigner,
@@ -735,6 +739,48 @@ fn vote_resent_on_timeout() {
     });
 }
 
+#[test]
+/// A vote signed by the node's key that it did not send is reported as a conflicting vote.
+fn conflicting_own_vote_detected() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    // With two nodes a single vote does not form a QC, so both votes are for the current round.
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 2);
+    let node = &mut nodes[0];
+    timed_block_on(&mut runtime, async {
+        let proposal_msg = node.next_proposal().await;
+        node.round_manager
+            .process_proposal_msg(proposal_msg)
+            .await
+            .unwrap();
+        let vote_msg = node.next_vote().await;
+
+        // Another instance signs a vote for a different block in the same round
+        let vote_data = VoteData::new(
+            BlockInfo::new(1, 1, HashValue::random(), HashValue::random(), 0, 0, None),
+            vote_msg.vote().vote_data().parent().clone(),
+        );
+        let conflicting_vote = Vote::new(
+            vote_data.clone(),
+            node.signer.author(),
+            LedgerInfo::new(BlockInfo::empty(), vote_data.hash()),
+            &node.signer,
+        );
+        let conflicting_vote_msg = VoteMsg::new(conflicting_vote, vote_msg.sync_info().clone());
+
+        let count = counters::CONFLICTING_OWN_VOTES_COUNT.get();
+        node.round_manager
+            .process_vote_msg(conflicting_vote_msg)
+            .await
+            .unwrap();
+        assert_eq!(counters::CONFLICTING_OWN_VOTES_COUNT.get(), count + 1);
+
+        // The vote sent by the node is expected
+        node.round_manager.process_vote_msg(vote_msg).await.unwrap();
+        assert_eq!(counters::CONFLICTING_OWN_VOTES_COUNT.get(), count + 1);
+    });
+}
+
 #[test]
 fn sync_info_sent_on_stale_sync_info() {
     let mut runtime = consensus_runtime();
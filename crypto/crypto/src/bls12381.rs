@@ -0,0 +1,526 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides an API for BLS signatures over the BLS12-381 curve, following the
+//! [IETF draft](https://tools.ietf.org/html/draft-irtf-cfrg-bls-signature-04) in the "minimal
+//! public key size" variant: public keys live in G1 and signatures in G2.
+//!
+//! BLS signatures on the same message can be aggregated into a single signature, which verifies
+//! against the aggregate of the signers' public keys. This is only secure if every public key
+//! came with a valid [`BLS12381ProofOfPossession`], otherwise an attacker can pick a rogue public
+//! key that cancels out the keys of honest signers.
+//!
+//! # Examples
+//!
+//! ```
+//! use diem_crypto_derive::{CryptoHasher, BCSCryptoHash};
+//! use diem_crypto::{
+//!     bls12381::*,
+//!     traits::{Signature, SigningKey, Uniform},
+//! };
+//! use rand::{rngs::StdRng, SeedableRng};
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
+//! pub struct TestCryptoDocTest(String);
+//! let message = TestCryptoDocTest("Test message".to_string());
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_keys: Vec<_> = (0..3).map(|_| BLS12381PrivateKey::generate(&mut rng)).collect();
+//! let public_keys: Vec<BLS12381PublicKey> = private_keys.iter().map(|key| key.into()).collect();
+//! let signatures: Vec<_> = private_keys.iter().map(|key| key.sign(&message)).collect();
+//!
+//! let signature = BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap();
+//! let public_keys: Vec<_> = public_keys.iter().collect();
+//! assert!(signature.verify_aggregate(&message, &public_keys).is_ok());
+//! ```
+//! **Note**: The above example generates a private key using a private function intended only for
+//! testing purposes. Production code should find an alternate means for secure key generation.
+
+use crate::{
+    hash::{CryptoHash, CryptoHasher},
+    traits::*,
+};
+use anyhow::{anyhow, ensure, Result};
+use blst::{min_pk as blst_core, BLST_ERROR};
+use core::convert::TryFrom;
+use diem_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use serde::Serialize;
+use std::fmt;
+
+/// The length of the BLS12381PrivateKey
+pub const BLS12381_PRIVATE_KEY_LENGTH: usize = 32;
+/// The length of the compressed BLS12381PublicKey
+pub const BLS12381_PUBLIC_KEY_LENGTH: usize = 48;
+/// The length of the compressed BLS12381Signature
+pub const BLS12381_SIGNATURE_LENGTH: usize = 96;
+
+/// The domain separation tag of signatures, for the proof of possession scheme.
+const DST_SIGNATURE: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// The domain separation tag of proofs of possession.
+const DST_PROOF_OF_POSSESSION: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// A BLS12-381 private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct BLS12381PrivateKey(blst_core::SecretKey);
+
+#[cfg(feature = "assert-private-keys-not-cloneable")]
+static_assertions::assert_not_impl_any!(BLS12381PrivateKey: Clone);
+
+#[cfg(any(test, feature = "cloneable-private-keys"))]
+impl Clone for BLS12381PrivateKey {
+    fn clone(&self) -> Self {
+        let serialized: &[u8] = &(self.to_bytes());
+        BLS12381PrivateKey::try_from(serialized).unwrap()
+    }
+}
+
+/// A BLS12-381 public key
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct BLS12381PublicKey(blst_core::PublicKey);
+
+/// A BLS12-381 signature, either of a single signer or aggregated from several signers
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct BLS12381Signature(blst_core::Signature);
+
+/// A proof that the owner of a public key knows the corresponding private key. Proofs of
+/// possession must be verified before a public key takes part in aggregate verification.
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct BLS12381ProofOfPossession(blst_core::Signature);
+
+fn check_result(result: BLST_ERROR) -> Result<()> {
+    match result {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        error => Err(anyhow!("BLS12-381 error: {:?}", error)),
+    }
+}
+
+/// Prepends the domain separation seed of `T` to its BCS serialization, as the other signature
+/// schemes of this crate do.
+fn signing_message<T: CryptoHash + Serialize>(message: &T) -> Result<Vec<u8>, CryptoMaterialError> {
+    let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+    bcs::serialize_into(&mut bytes, &message)
+        .map_err(|_| CryptoMaterialError::SerializationError)?;
+    Ok(bytes)
+}
+
+impl BLS12381PrivateKey {
+    /// The length of the BLS12381PrivateKey
+    pub const LENGTH: usize = BLS12381_PRIVATE_KEY_LENGTH;
+
+    /// Serialize a BLS12381PrivateKey.
+    pub fn to_bytes(&self) -> [u8; BLS12381_PRIVATE_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    /// Creates a proof of possession of this key, to be published together with the public key.
+    pub fn create_proof_of_possession(&self) -> BLS12381ProofOfPossession {
+        let public_key: BLS12381PublicKey = self.into();
+        BLS12381ProofOfPossession(
+            self.0
+                .sign(&public_key.to_bytes(), DST_PROOF_OF_POSSESSION, &[]),
+        )
+    }
+
+    /// Private function aimed at minimizing code duplication between sign
+    /// methods of the SigningKey implementation. This should remain private.
+    fn sign_arbitrary_message(&self, message: &[u8]) -> BLS12381Signature {
+        BLS12381Signature(self.0.sign(message, DST_SIGNATURE, &[]))
+    }
+}
+
+impl BLS12381PublicKey {
+    /// Serialize a BLS12381PublicKey in compressed form.
+    pub fn to_bytes(&self) -> [u8; BLS12381_PUBLIC_KEY_LENGTH] {
+        self.0.compress()
+    }
+
+    /// Aggregates public keys, such that a signature aggregated from the signatures of their
+    /// owners on a message verifies against the aggregate public key. The caller must have
+    /// verified the proofs of possession of all keys.
+    pub fn aggregate(public_keys: &[&BLS12381PublicKey]) -> Result<BLS12381PublicKey> {
+        ensure!(!public_keys.is_empty(), "Cannot aggregate zero public keys");
+        let public_keys: Vec<_> = public_keys.iter().map(|key| &key.0).collect();
+        let aggregate = blst_core::AggregatePublicKey::aggregate(&public_keys, false)
+            .map_err(|error| anyhow!("BLS12-381 error: {:?}", error))?;
+        Ok(BLS12381PublicKey(aggregate.to_public_key()))
+    }
+}
+
+impl BLS12381Signature {
+    /// Serialize a BLS12381Signature in compressed form.
+    pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
+        self.0.compress()
+    }
+
+    /// Aggregates signatures into a single signature. The signatures are expected to be on the
+    /// same message, and to have been deserialized, and thus group checked, or created locally.
+    pub fn aggregate(signatures: &[&BLS12381Signature]) -> Result<BLS12381Signature> {
+        ensure!(!signatures.is_empty(), "Cannot aggregate zero signatures");
+        let signatures: Vec<_> = signatures.iter().map(|signature| &signature.0).collect();
+        let aggregate = blst_core::AggregateSignature::aggregate(&signatures, false)
+            .map_err(|error| anyhow!("BLS12-381 error: {:?}", error))?;
+        Ok(BLS12381Signature(aggregate.to_signature()))
+    }
+
+    /// Verifies an aggregate signature of the owners of `public_keys` on the same `message`. The
+    /// caller must have verified the proofs of possession of all keys.
+    pub fn verify_aggregate<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        public_keys: &[&BLS12381PublicKey],
+    ) -> Result<()> {
+        ensure!(
+            !public_keys.is_empty(),
+            "Cannot verify against zero public keys"
+        );
+        let bytes = signing_message(message)?;
+        let public_keys: Vec<_> = public_keys.iter().map(|key| &key.0).collect();
+        check_result(
+            self.0
+                .fast_aggregate_verify(false, &bytes, DST_SIGNATURE, &public_keys),
+        )
+    }
+
+    /// return a signature on an empty message (for test only)
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn dummy_signature() -> Self {
+        BLS12381PrivateKey::genesis().sign_arbitrary_message(&[])
+    }
+}
+
+impl BLS12381ProofOfPossession {
+    /// Serialize a BLS12381ProofOfPossession in compressed form.
+    pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
+        self.0.compress()
+    }
+
+    /// Verifies that this is a proof of possession of the private key of `public_key`.
+    pub fn verify(&self, public_key: &BLS12381PublicKey) -> Result<()> {
+        check_result(self.0.verify(
+            false,
+            &public_key.to_bytes(),
+            DST_PROOF_OF_POSSESSION,
+            &[],
+            &public_key.0,
+            false,
+        ))
+    }
+}
+
+///////////////////////
+// PrivateKey Traits //
+///////////////////////
+
+impl PrivateKey for BLS12381PrivateKey {
+    type PublicKeyMaterial = BLS12381PublicKey;
+}
+
+impl SigningKey for BLS12381PrivateKey {
+    type VerifyingKeyMaterial = BLS12381PublicKey;
+    type SignatureMaterial = BLS12381Signature;
+
+    fn sign<T: CryptoHash + Serialize>(&self, message: &T) -> BLS12381Signature {
+        let bytes =
+            signing_message(message).expect("Serialization of signable material should not fail.");
+        BLS12381PrivateKey::sign_arbitrary_message(&self, &bytes)
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    fn sign_arbitrary_message(&self, message: &[u8]) -> BLS12381Signature {
+        BLS12381PrivateKey::sign_arbitrary_message(self, message)
+    }
+}
+
+impl Uniform for BLS12381PrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: ::rand::RngCore + ::rand::CryptoRng,
+    {
+        let mut ikm = [0u8; 32];
+        rng.fill_bytes(&mut ikm);
+        BLS12381PrivateKey(
+            blst_core::SecretKey::key_gen(&ikm, &[])
+                .expect("Key generation should not fail with 32 bytes of key material"),
+        )
+    }
+}
+
+impl PartialEq<Self> for BLS12381PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for BLS12381PrivateKey {}
+
+impl TryFrom<&[u8]> for BLS12381PrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a BLS12381PrivateKey. This method rejects zero and out of range scalars.
+    fn try_from(bytes: &[u8]) -> std::result::Result<BLS12381PrivateKey, CryptoMaterialError> {
+        if bytes.len() != BLS12381_PRIVATE_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        blst_core::SecretKey::from_bytes(bytes)
+            .map(BLS12381PrivateKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl Length for BLS12381PrivateKey {
+    fn length(&self) -> usize {
+        Self::LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for BLS12381PrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl Genesis for BLS12381PrivateKey {
+    fn genesis() -> Self {
+        let mut buf = [0u8; BLS12381_PRIVATE_KEY_LENGTH];
+        buf[BLS12381_PRIVATE_KEY_LENGTH - 1] = 1;
+        Self::try_from(buf.as_ref()).unwrap()
+    }
+}
+
+//////////////////////
+// PublicKey Traits //
+//////////////////////
+
+impl From<&BLS12381PrivateKey> for BLS12381PublicKey {
+    fn from(private_key: &BLS12381PrivateKey) -> Self {
+        BLS12381PublicKey(private_key.0.sk_to_pk())
+    }
+}
+
+impl PublicKey for BLS12381PublicKey {
+    type PrivateKeyMaterial = BLS12381PrivateKey;
+}
+
+impl std::hash::Hash for BLS12381PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl PartialEq for BLS12381PublicKey {
+    fn eq(&self, other: &BLS12381PublicKey) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for BLS12381PublicKey {}
+
+impl VerifyingKey for BLS12381PublicKey {
+    type SigningKeyMaterial = BLS12381PrivateKey;
+    type SignatureMaterial = BLS12381Signature;
+}
+
+impl fmt::Display for BLS12381PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for BLS12381PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BLS12381PublicKey({})", self)
+    }
+}
+
+impl TryFrom<&[u8]> for BLS12381PublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a compressed BLS12381PublicKey. This method rejects the identity and points
+    /// outside of the prime order subgroup.
+    fn try_from(bytes: &[u8]) -> std::result::Result<BLS12381PublicKey, CryptoMaterialError> {
+        if bytes.len() != BLS12381_PUBLIC_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        match blst_core::PublicKey::key_validate(bytes) {
+            Ok(public_key) => Ok(BLS12381PublicKey(public_key)),
+            Err(BLST_ERROR::BLST_PK_IS_INFINITY) | Err(BLST_ERROR::BLST_POINT_NOT_IN_GROUP) => {
+                Err(CryptoMaterialError::SmallSubgroupError)
+            }
+            Err(_) => Err(CryptoMaterialError::DeserializationError),
+        }
+    }
+}
+
+impl Length for BLS12381PublicKey {
+    fn length(&self) -> usize {
+        BLS12381_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for BLS12381PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// Signature Traits //
+//////////////////////
+
+impl Signature for BLS12381Signature {
+    type VerifyingKeyMaterial = BLS12381PublicKey;
+    type SigningKeyMaterial = BLS12381PrivateKey;
+
+    fn verify<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        public_key: &BLS12381PublicKey,
+    ) -> Result<()> {
+        let bytes = signing_message(message)?;
+        self.verify_arbitrary_msg(&bytes, public_key)
+    }
+
+    /// Checks that `self` is valid for an arbitrary &[u8] `message` using `public_key`. The
+    /// signature has been group checked during deserialization.
+    fn verify_arbitrary_msg(&self, message: &[u8], public_key: &BLS12381PublicKey) -> Result<()> {
+        check_result(
+            self.0
+                .verify(false, message, DST_SIGNATURE, &[], &public_key.0, false),
+        )
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl Length for BLS12381Signature {
+    fn length(&self) -> usize {
+        BLS12381_SIGNATURE_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for BLS12381Signature {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl std::hash::Hash for BLS12381Signature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+/// Deserializes a compressed signature, rejecting points outside of the prime order subgroup.
+fn signature_from_bytes(
+    bytes: &[u8],
+) -> std::result::Result<blst_core::Signature, CryptoMaterialError> {
+    if bytes.len() != BLS12381_SIGNATURE_LENGTH {
+        return Err(CryptoMaterialError::WrongLengthError);
+    }
+    match blst_core::Signature::sig_validate(bytes, true) {
+        Ok(signature) => Ok(signature),
+        Err(BLST_ERROR::BLST_PK_IS_INFINITY) | Err(BLST_ERROR::BLST_POINT_NOT_IN_GROUP) => {
+            Err(CryptoMaterialError::SmallSubgroupError)
+        }
+        Err(_) => Err(CryptoMaterialError::DeserializationError),
+    }
+}
+
+impl TryFrom<&[u8]> for BLS12381Signature {
+    type Error = CryptoMaterialError;
+
+    fn try_from(bytes: &[u8]) -> std::result::Result<BLS12381Signature, CryptoMaterialError> {
+        signature_from_bytes(bytes).map(BLS12381Signature)
+    }
+}
+
+impl PartialEq for BLS12381Signature {
+    fn eq(&self, other: &BLS12381Signature) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for BLS12381Signature {}
+
+impl fmt::Display for BLS12381Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for BLS12381Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BLS12381Signature({})", self)
+    }
+}
+
+///////////////////////////////////
+// ProofOfPossession Traits      //
+///////////////////////////////////
+
+impl Length for BLS12381ProofOfPossession {
+    fn length(&self) -> usize {
+        BLS12381_SIGNATURE_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for BLS12381ProofOfPossession {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl TryFrom<&[u8]> for BLS12381ProofOfPossession {
+    type Error = CryptoMaterialError;
+
+    fn try_from(
+        bytes: &[u8],
+    ) -> std::result::Result<BLS12381ProofOfPossession, CryptoMaterialError> {
+        signature_from_bytes(bytes).map(BLS12381ProofOfPossession)
+    }
+}
+
+impl PartialEq for BLS12381ProofOfPossession {
+    fn eq(&self, other: &BLS12381ProofOfPossession) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for BLS12381ProofOfPossession {}
+
+impl fmt::Display for BLS12381ProofOfPossession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for BLS12381ProofOfPossession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BLS12381ProofOfPossession({})", self)
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use crate::test_utils::{self, KeyPair};
+
+/// Produces a uniformly random BLS12-381 keypair from a seed
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn keypair_strategy() -> impl Strategy<Value = KeyPair<BLS12381PrivateKey, BLS12381PublicKey>> {
+    test_utils::uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>()
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest::prelude::*;
+
+#[cfg(any(test, feature = "fuzzing"))]
+impl proptest::arbitrary::Arbitrary for BLS12381PublicKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        keypair_strategy().prop_map(|v| v.public_key).boxed()
+    }
+}
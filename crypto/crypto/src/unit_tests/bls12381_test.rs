@@ -0,0 +1,143 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bls12381::{
+        BLS12381PrivateKey, BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature,
+        BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
+    },
+    test_utils::{random_serializable_struct, uniform_keypair_strategy, TestDiemCrypto, TEST_SEED},
+    traits::*,
+    CryptoMaterialError,
+};
+
+use core::convert::TryFrom;
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+// Helper function to generate N BLS12-381 private keys.
+fn generate_keys(n: usize) -> Vec<BLS12381PrivateKey> {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    (0..n)
+        .map(|_| BLS12381PrivateKey::generate(&mut rng))
+        .collect()
+}
+
+fn message() -> TestDiemCrypto {
+    TestDiemCrypto("Test Message".to_string())
+}
+
+#[test]
+fn test_aggregate_signature() {
+    let private_keys = generate_keys(5);
+    let public_keys: Vec<BLS12381PublicKey> = private_keys.iter().map(|key| key.into()).collect();
+    let signatures: Vec<_> = private_keys
+        .iter()
+        .map(|key| key.sign(&message()))
+        .collect();
+
+    let aggregate = BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap();
+    let public_key_refs: Vec<_> = public_keys.iter().collect();
+    aggregate
+        .verify_aggregate(&message(), &public_key_refs)
+        .unwrap();
+
+    // An aggregate signature is a regular signature of the aggregate public key
+    let aggregate_public_key = BLS12381PublicKey::aggregate(&public_key_refs).unwrap();
+    aggregate.verify(&message(), &aggregate_public_key).unwrap();
+
+    // Missing, additional or different signers are detected
+    assert!(aggregate
+        .verify_aggregate(&message(), &public_key_refs[1..])
+        .is_err());
+    let partial =
+        BLS12381Signature::aggregate(&signatures[1..].iter().collect::<Vec<_>>()).unwrap();
+    assert!(partial
+        .verify_aggregate(&message(), &public_key_refs)
+        .is_err());
+    partial
+        .verify_aggregate(&message(), &public_key_refs[1..])
+        .unwrap();
+
+    let other_message = TestDiemCrypto("Other Message".to_string());
+    assert!(aggregate
+        .verify_aggregate(&other_message, &public_key_refs)
+        .is_err());
+}
+
+#[test]
+fn test_aggregate_nothing() {
+    assert!(BLS12381Signature::aggregate(&[]).is_err());
+    assert!(BLS12381PublicKey::aggregate(&[]).is_err());
+    let signature = generate_keys(1)[0].sign(&message());
+    assert!(signature.verify_aggregate(&message(), &[]).is_err());
+}
+
+#[test]
+fn test_proof_of_possession() {
+    let private_keys = generate_keys(2);
+    let public_key: BLS12381PublicKey = (&private_keys[0]).into();
+    let other_public_key: BLS12381PublicKey = (&private_keys[1]).into();
+    let pop = private_keys[0].create_proof_of_possession();
+    pop.verify(&public_key).unwrap();
+    assert!(pop.verify(&other_public_key).is_err());
+
+    // A proof of possession is not a signature on the public key, and vice versa
+    let signature = private_keys[0].sign_arbitrary_message(&public_key.to_bytes());
+    let fake_pop = BLS12381ProofOfPossession::try_from(&signature.to_bytes()[..]).unwrap();
+    assert!(fake_pop.verify(&public_key).is_err());
+
+    let pop = BLS12381ProofOfPossession::try_from(&pop.to_bytes()[..]).unwrap();
+    pop.verify(&public_key).unwrap();
+}
+
+#[test]
+fn test_invalid_encodings() {
+    assert_eq!(
+        BLS12381PublicKey::try_from(&[0u8; BLS12381_PUBLIC_KEY_LENGTH - 1][..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+    assert_eq!(
+        BLS12381Signature::try_from(&[0u8; BLS12381_SIGNATURE_LENGTH + 1][..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+
+    // The compressed identity
+    let mut identity = [0u8; BLS12381_PUBLIC_KEY_LENGTH];
+    identity[0] = 0xc0;
+    assert!(BLS12381PublicKey::try_from(&identity[..]).is_err());
+
+    // The zero scalar is not a valid private key
+    assert!(BLS12381PrivateKey::try_from(&[0u8; 32][..]).is_err());
+}
+
+proptest! {
+    #[test]
+    fn test_keys_encode(keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>()) {
+        {
+            let encoded = keypair.private_key.to_encoded_string().unwrap();
+            let decoded = BLS12381PrivateKey::from_encoded_string(&encoded);
+            prop_assert_eq!(Some(keypair.private_key), decoded.ok());
+        }
+        {
+            let encoded = keypair.public_key.to_encoded_string().unwrap();
+            let decoded = BLS12381PublicKey::from_encoded_string(&encoded);
+            prop_assert_eq!(Some(keypair.public_key), decoded.ok());
+        }
+    }
+
+    #[test]
+    fn test_sign_verify(
+        keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+        message in random_serializable_struct()
+    ) {
+        let signature = keypair.private_key.sign(&message);
+        let serialized: &[u8] = &(signature.to_bytes());
+        prop_assert_eq!(BLS12381_SIGNATURE_LENGTH, serialized.len());
+        let deserialized = BLS12381Signature::try_from(serialized).unwrap();
+        prop_assert!(keypair.public_key.verify_struct_signature(&message, &deserialized).is_ok());
+
+        let other = bcs::to_bytes(&signature).unwrap();
+        prop_assert_eq!(signature, bcs::from_bytes::<BLS12381Signature>(&other).unwrap());
+    }
+}
@@ -1,8 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use backup_service::start_backup_service;
-use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
+use backup_service::{start_backup_service, CheckpointConfig, Checkpointer};
+use consensus::{
+    consensus_provider::start_consensus, gen_consensus_reconfig_subscription, ConsensusDB,
+};
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_config::{
     config::{NetworkConfig, NodeConfig, PersistableConfig},
@@ -29,7 +31,7 @@ use std::{
     boxed::Box,
     convert::TryFrom,
     net::ToSocketAddrs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -273,11 +275,34 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         .expect("DB should open."),
     );
     let _simple_storage_service = start_storage_service_with_db(&node_config, Arc::clone(&diem_db));
+    // The consensus DB is opened ahead of consensus, so that checkpoints taken through the backup
+    // service cover it as well.
+    let consensus_db = if node_config.base.role.is_validator() {
+        Some(Arc::new(ConsensusDB::new(node_config.storage.dir())))
+    } else {
+        None
+    };
+    let mut checkpointers: Vec<Checkpointer> = vec![];
+    if let Some(consensus_db) = &consensus_db {
+        let consensus_db = Arc::clone(consensus_db);
+        checkpointers.push(Box::new(move |checkpoint_root_path: &Path| {
+            consensus_db.create_checkpoint(checkpoint_root_path)
+        }));
+    }
     let backup_service = start_backup_service(
         node_config.storage.backup_service_address,
         Arc::clone(&diem_db),
+        node_config
+            .storage
+            .backup_service_checkpoint_dir()
+            .map(|root_dir| CheckpointConfig {
+                root_dir,
+                checkpointers,
+            }),
     );
 
+    DiemVM::set_concurrency_level_once(node_config.execution.concurrency_level as usize);
+
     let genesis_waypoint = node_config.base.waypoint.genesis_waypoint();
     // if there's genesis txn and waypoint, commit it if the result matches.
     if let Some(genesis) = get_genesis_txn(&node_config) {
@@ -453,6 +478,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_network_events,
             state_sync_client,
             consensus_to_mempool_sender,
+            consensus_db.expect("The consensus DB is opened on validators."),
             diem_db,
             consensus_reconfig_events,
         ));
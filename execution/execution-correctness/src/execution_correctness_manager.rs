@@ -9,7 +9,7 @@ use crate::{
     serializer::{SerializerClient, SerializerService},
     thread::ThreadService,
 };
-use diem_config::config::{ExecutionCorrectnessService, NodeConfig};
+use diem_config::config::{ExecutionCorrectnessService, NodeConfig, RemoteServiceNoise};
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_global_constants::EXECUTION_KEY;
 use diem_infallible::Mutex;
@@ -61,6 +61,7 @@ impl ExecutionCorrectnessManager {
             return Self::new_process(
                 remote_service.server_address,
                 config.execution.network_timeout_ms,
+                remote_service.noise.clone(),
             );
         }
 
@@ -99,8 +100,12 @@ impl ExecutionCorrectnessManager {
         }
     }
 
-    pub fn new_process(server_addr: SocketAddr, network_timeout: u64) -> Self {
-        let process_service = ProcessService::new(server_addr, network_timeout);
+    pub fn new_process(
+        server_addr: SocketAddr,
+        network_timeout: u64,
+        noise: Option<RemoteServiceNoise>,
+    ) -> Self {
+        let process_service = ProcessService::new(server_addr, network_timeout, noise);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Process(process_service),
         }
@@ -5,7 +5,7 @@ use crate::{
     execution_correctness_manager,
     remote_service::{self, RemoteService},
 };
-use diem_config::config::{ExecutionCorrectnessService, NodeConfig};
+use diem_config::config::{ExecutionCorrectnessService, NodeConfig, RemoteServiceNoise};
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use std::net::SocketAddr;
 
@@ -30,15 +30,16 @@ impl Process {
 
     pub fn start(self) {
         let service = &self.config.execution.service;
-        let server_addr = match &service {
-            ExecutionCorrectnessService::Process(remote_service) => remote_service.server_address,
+        let remote_service = match &service {
+            ExecutionCorrectnessService::Process(remote_service) => remote_service,
             _ => panic!("Unexpected ExecutionCorrectness service: {:?}", service),
         };
         remote_service::execute(
             self.config.storage.address,
-            server_addr,
+            remote_service.server_address,
             self.prikey,
             self.network_timeout_ms,
+            remote_service.noise.clone(),
         );
     }
 }
@@ -46,13 +47,19 @@ impl Process {
 pub struct ProcessService {
     server_addr: SocketAddr,
     network_timeout: u64,
+    noise: Option<RemoteServiceNoise>,
 }
 
 impl ProcessService {
-    pub fn new(server_addr: SocketAddr, network_timeout: u64) -> Self {
+    pub fn new(
+        server_addr: SocketAddr,
+        network_timeout: u64,
+        noise: Option<RemoteServiceNoise>,
+    ) -> Self {
         Self {
             server_addr,
             network_timeout,
+            noise,
         }
     }
 }
@@ -64,4 +71,8 @@ impl RemoteService for ProcessService {
     fn network_timeout(&self) -> u64 {
         self.network_timeout
     }
+
+    fn noise(&self) -> Option<&RemoteServiceNoise> {
+        self.noise.as_ref()
+    }
 }
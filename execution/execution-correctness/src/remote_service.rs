@@ -4,6 +4,7 @@
 use crate::serializer::{
     ExecutionCorrectnessInput, SerializerClient, SerializerService, TSerializerClient,
 };
+use diem_config::config::RemoteServiceNoise;
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_logger::warn;
 use diem_secure_net::{NetworkClient, NetworkServer};
@@ -15,14 +16,27 @@ use storage_client::StorageClient;
 
 pub trait RemoteService {
     fn client(&self) -> SerializerClient {
-        let network_client =
-            NetworkClient::new("execution", self.server_address(), self.network_timeout());
+        let network_client = match self.noise() {
+            Some(noise) => NetworkClient::new_with_noise(
+                "execution",
+                self.server_address(),
+                self.network_timeout(),
+                noise.private_key.private_key(),
+                noise.server_public_key,
+            ),
+            None => NetworkClient::new("execution", self.server_address(), self.network_timeout()),
+        };
         let service = Box::new(RemoteClient::new(network_client));
         SerializerClient::new_client(service)
     }
 
     fn server_address(&self) -> SocketAddr;
     fn network_timeout(&self) -> u64;
+
+    /// The Noise keys authenticating the connection, if any.
+    fn noise(&self) -> Option<&RemoteServiceNoise> {
+        None
+    }
 }
 
 pub fn execute(
@@ -30,12 +44,22 @@ pub fn execute(
     listen_addr: SocketAddr,
     prikey: Option<Ed25519PrivateKey>,
     network_timeout: u64,
+    noise: Option<RemoteServiceNoise>,
 ) {
     let block_executor = Box::new(Executor::<DiemVM>::new(
         StorageClient::new(&storage_addr, network_timeout).into(),
     ));
     let mut serializer_service = SerializerService::new(block_executor, prikey);
-    let mut network_server = NetworkServer::new("execution", listen_addr, network_timeout);
+    let mut network_server = match noise {
+        Some(noise) => NetworkServer::new_with_noise(
+            "execution",
+            listen_addr,
+            network_timeout,
+            noise.private_key.private_key(),
+            noise.client_public_keys,
+        ),
+        None => NetworkServer::new("execution", listen_addr, network_timeout),
+    };
 
     loop {
         if let Err(e) = process_one_message(&mut network_server, &mut serializer_service) {
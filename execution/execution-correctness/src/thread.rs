@@ -34,7 +34,7 @@ impl ThreadService {
         let server_addr = listen_addr;
 
         let child = thread::spawn(move || {
-            remote_service::execute(storage_addr, listen_addr, prikey, network_timeout)
+            remote_service::execute(storage_addr, listen_addr, prikey, network_timeout, None)
         });
 
         Self {
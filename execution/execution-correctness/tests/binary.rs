@@ -2,24 +2,77 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_config::{
-    config::{ExecutionCorrectnessService, PersistableConfig, RemoteExecutionService},
+    config::{
+        ExecutionCorrectnessService, NodeConfig, PersistableConfig, RemoteExecutionService,
+        RemoteServiceNoise,
+    },
     utils,
 };
+use diem_crypto::{x25519, Uniform};
 use execution_correctness::ExecutionCorrectnessManager;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
 
 const BINARY: &str = env!("CARGO_BIN_EXE_execution-correctness");
+
 #[test]
 fn test_rest() {
     let (mut config, _handle, _db) = executor_test_helpers::start_storage_service();
+    set_process_service(&mut config);
+    check_reset(&config, &config);
+}
+
+#[test]
+fn test_rest_with_noise() {
+    let (mut server_config, _handle, _db) = executor_test_helpers::start_storage_service();
+    let server_address = set_process_service(&mut server_config);
+    let mut client_config = server_config.clone();
+
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let server_key = x25519::PrivateKey::generate(&mut rng);
+    let client_key = x25519::PrivateKey::generate(&mut rng);
+    let server_public_key = server_key.public_key();
+    let client_public_keys: HashSet<_> = vec![client_key.public_key()].into_iter().collect();
+    server_config.execution.service =
+        ExecutionCorrectnessService::Process(RemoteExecutionService {
+            server_address,
+            noise: Some(RemoteServiceNoise::new(
+                server_key,
+                server_public_key,
+                client_public_keys.clone(),
+            )),
+        });
+    client_config.execution.service =
+        ExecutionCorrectnessService::Process(RemoteExecutionService {
+            server_address,
+            noise: Some(RemoteServiceNoise::new(
+                client_key,
+                server_public_key,
+                client_public_keys,
+            )),
+        });
+    check_reset(&server_config, &client_config);
+}
+
+fn set_process_service(config: &mut NodeConfig) -> SocketAddr {
     let server_port = utils::get_available_port();
     let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
-    config.execution.service =
-        ExecutionCorrectnessService::Process(RemoteExecutionService { server_address });
+    config.execution.service = ExecutionCorrectnessService::Process(RemoteExecutionService {
+        server_address,
+        noise: None,
+    });
+    server_address
+}
 
+/// Runs the execution-correctness binary with `server_config` and resets it through
+/// `client_config`.
+fn check_reset(server_config: &NodeConfig, client_config: &NodeConfig) {
     let config_path = diem_temppath::TempPath::new();
     config_path.create_as_file().unwrap();
-    config.save_config(config_path.path()).unwrap();
+    server_config.save_config(config_path.path()).unwrap();
 
     let mut command = std::process::Command::new(BINARY);
     command
@@ -30,7 +83,9 @@ fn test_rest() {
     let mut child = command.spawn().unwrap();
 
     // Run a command as a client to verify the service is running
-    let res = ExecutionCorrectnessManager::new(&config).client().reset();
+    let res = ExecutionCorrectnessManager::new(client_config)
+        .client()
+        .reset();
 
     // Ensure the safety-rules subprocess is killed whether the test passes or fails.
     // Not doing this would result in a zombie process.
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use diem_vm::DiemVM;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -20,6 +21,10 @@ struct Opt {
 
     #[structopt(long, parse(from_os_str))]
     db_dir: Option<PathBuf>,
+
+    /// Number of threads transactions within a block are executed on
+    #[structopt(long, default_value = "1")]
+    concurrency_level: usize,
 }
 
 fn main() {
@@ -31,6 +36,7 @@ fn main() {
         .thread_name(|index| format!("rayon-global-{}", index))
         .build_global()
         .expect("Failed to build rayon global thread pool.");
+    DiemVM::set_concurrency_level_once(opt.concurrency_level);
 
     executor_benchmark::run_benchmark(
         opt.num_accounts,
@@ -47,6 +47,7 @@ use diem_types::{
         Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
         TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
+    validator_verifier::ValidatorVerifier,
     write_set::{WriteOp, WriteSet},
 };
 use diem_vm::VMExecutor;
@@ -392,9 +393,18 @@ where
                         .ok_or_else(|| format_err!("Configuration does not exist"))
                 })
                 .ok_or_else(|| format_err!("Association account does not exist"))??;
+            let consensus_config = account_to_state
+                .get(&on_chain_config::config_address())
+                .map(|state| state.get_consensus_config())
+                .transpose()?
+                .flatten()
+                .unwrap_or_default();
             Some(EpochState {
                 epoch: configuration.epoch(),
-                verifier: (&validator_set).into(),
+                verifier: ValidatorVerifier::from_validator_set_with_bls_keys(
+                    &validator_set,
+                    &consensus_config,
+                ),
             })
         } else {
             None
@@ -52,6 +52,10 @@ fn test_method_fuzzer() {
         &gen_request_params!(["000000000000000000000000000000dd", 0, 1]),
         "get_account_state_with_proof",
     );
+    method_fuzzer(
+        &gen_request_params!([["000000000000000000000000000000dd"], 0, 1]),
+        "get_accounts_state_with_proof",
+    );
     method_fuzzer(&gen_request_params!([]), "get_network_status");
 }
 
@@ -6,9 +6,9 @@ use crate::{
     errors::JsonRpcError,
     util::{transaction_data_view_from_transaction, vm_status_view_from_kept_vm_status},
     views::{
-        AccountStateWithProofView, AccountView, BytesView, CurrencyInfoView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView, TransactionsProofsView,
-        TransactionsWithProofsView,
+        AccountStateWithProofView, AccountStatesWithProofView, AccountView, BytesView,
+        CurrencyInfoView, EventView, EventWithProofView, MetadataView, StateProofView,
+        TransactionView, TransactionsProofsView, TransactionsWithProofsView,
     },
 };
 use anyhow::{ensure, format_err, Error, Result};
@@ -658,6 +658,44 @@ async fn get_account_state_with_proof(
     )?)
 }
 
+/// Returns the states of a list of accounts to the client, in the same order, alongside a single
+/// proof for all of them relative to the version and ledger_version specified by the client. If
+/// version or ledger_version are not specified, the latest known versions will be used.
+async fn get_accounts_state_with_proof(
+    service: JsonRpcService,
+    request: JsonRpcRequest,
+) -> Result<AccountStatesWithProofView, JsonRpcError> {
+    let raw_addresses: Vec<String> = request.parse_param(0, "account addresses")?;
+    if raw_addresses.is_empty() {
+        return Err(invalid_param(0, "account addresses"));
+    }
+    service.validate_page_size_limit(raw_addresses.len())?;
+    let addresses = raw_addresses
+        .into_iter()
+        .map(AccountAddress::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid_param(0, "account addresses"))?;
+
+    // If versions are specified by the request parameters, use them, otherwise use the defaults
+    let version = request.parse_version_param(1, "version")?;
+    let ledger_version = request.parse_version_param(2, "ledger version for proof")?;
+
+    if version > ledger_version {
+        return Err(JsonRpcError::invalid_request_with_msg(format!(
+            "version({}) should <= ledger version({})",
+            version, ledger_version
+        )));
+    }
+
+    let account_states_with_proof =
+        service
+            .db
+            .get_accounts_state_with_proof(&addresses, version, ledger_version)?;
+    Ok(AccountStatesWithProofView::try_from(
+        account_states_with_proof,
+    )?)
+}
+
 /// Returns the number of peers this node is connected to
 async fn get_network_status(service: JsonRpcService, _request: JsonRpcRequest) -> Result<u64> {
     let peers = counters::DIEM_NETWORK_PEERS
@@ -702,6 +740,13 @@ pub(crate) fn build_registry() -> RpcRegistry {
         3,
         0
     );
+    register_rpc_method!(
+        registry,
+        "get_accounts_state_with_proof",
+        get_accounts_state_with_proof,
+        3,
+        0
+    );
     register_rpc_method!(
         registry,
         "get_transactions_with_proofs",
@@ -729,6 +774,7 @@ fn invalid_param(index: usize, name: &str) -> JsonRpcError {
         "account sequence number" => "unsigned int64",
         "include_events" => "boolean",
         "account address" => "hex-encoded string",
+        "account addresses" => "non-empty list of hex-encoded strings",
         "event key" => "hex-encoded string",
         "data" => "hex-encoded string of BCS serialized Diem SignedTransaction type",
         "version" => "unsigned int64",
@@ -28,7 +28,10 @@ use diem_types::{
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    proof::{SparseMerkleProof, TransactionAccumulatorProof, TransactionInfoWithProof},
+    proof::{
+        SparseMerkleMultiProof, SparseMerkleProof, TransactionAccumulatorProof,
+        TransactionInfoWithProof,
+    },
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{SignedTransaction, Transaction, TransactionInfo, TransactionPayload},
     vm_status::StatusCode,
@@ -973,6 +976,54 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "diem_ledger_version": version
             }),
         ),
+        (
+            "get_accounts_state_with_proof: invalid account address",
+            json!({"jsonrpc": "2.0", "method": "get_accounts_state_with_proof", "params": [["e1b3d22871989e9fd9dc6814b2f4fc41", "invalid"], 1, 1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid param account addresses(params[0]): should be non-empty list of hex-encoded strings",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "get_accounts_state_with_proof: no account address",
+            json!({"jsonrpc": "2.0", "method": "get_accounts_state_with_proof", "params": [[], 1, 1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid param account addresses(params[0]): should be non-empty list of hex-encoded strings",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "get_accounts_state_with_proof: version > ledger version",
+            json!({"jsonrpc": "2.0", "method": "get_accounts_state_with_proof", "params": [["e1b3d22871989e9fd9dc6814b2f4fc41"], version, version-1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": format!("Invalid Request: version({}) should <= ledger version({})",version, version-1),
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
         (
             "id not given",
             json!({"jsonrpc": "2.0", "method": "get_metadata"}),
@@ -1508,6 +1559,44 @@ fn test_get_account_state_with_proof() {
     assert_eq!(txn_info_with_proof, *expected_txn_info_with_proof);
 }
 
+#[test]
+fn test_get_accounts_state_with_proof() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let account = get_first_account_from_mock_db(&mock_db);
+    let unknown_account = AccountAddress::random();
+
+    let received_proof = client
+        .get_accounts_state_with_proof(vec![account, unknown_account], Some(0), Some(0))
+        .unwrap()
+        .into_inner();
+    let expected_proof = get_first_state_proof_from_mock_db(&mock_db);
+    let expected_txn_info_with_proof = expected_proof.proof.transaction_info_with_proof();
+
+    //version
+    assert_eq!(received_proof.version, expected_proof.version);
+
+    // blobs
+    assert_eq!(received_proof.blobs.len(), 2);
+    let account_blob: AccountStateBlob =
+        bcs::from_bytes(received_proof.blobs[0].as_ref().unwrap()).unwrap();
+    assert_eq!(
+        Some(account_blob),
+        mock_db.get_latest_account_state(account).unwrap()
+    );
+    assert!(received_proof.blobs[1].is_none());
+
+    // proof
+    let txn_info: TransactionInfo =
+        bcs::from_bytes(&received_proof.proof.transaction_info).unwrap();
+    let li_proof: TransactionAccumulatorProof =
+        bcs::from_bytes(&received_proof.proof.ledger_info_to_transaction_info_proof).unwrap();
+    let txn_info_with_proof = TransactionInfoWithProof::new(li_proof, txn_info);
+    assert_eq!(txn_info_with_proof, *expected_txn_info_with_proof);
+    let _: SparseMerkleMultiProof<AccountStateBlob> =
+        bcs::from_bytes(&received_proof.proof.transaction_info_to_accounts_proof).unwrap();
+}
+
 #[test]
 fn test_get_state_proof() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -9,7 +9,7 @@ use diem_crypto::HashValue;
 use diem_mempool::MempoolClientSender;
 use diem_types::{
     account_address::AccountAddress,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesWithProof},
     block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::{ContractEvent, EventWithProof},
@@ -17,8 +17,9 @@ use diem_types::{
     event::EventKey,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     proof::{
-        AccumulatorConsistencyProof, AccumulatorRangeProof, SparseMerkleProof,
-        TransactionAccumulatorProof, TransactionInfoWithProof, TransactionListProof,
+        AccountStatesProof, AccumulatorConsistencyProof, AccumulatorRangeProof,
+        SparseMerkleMultiProof, SparseMerkleProof, TransactionAccumulatorProof,
+        TransactionInfoWithProof, TransactionListProof,
     },
     transaction::{
         Transaction, TransactionInfo, TransactionListWithProof, TransactionWithProof, Version,
@@ -265,6 +266,33 @@ impl DbReader for MockDiemDB {
             .clone())
     }
 
+    fn get_accounts_state_with_proof(
+        &self,
+        addresses: &[AccountAddress],
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStatesWithProof> {
+        let account_state_with_proof = self
+            .account_state_with_proof
+            .get(0)
+            .ok_or_else(|| format_err!("could not find account state"))?;
+        let blobs = addresses
+            .iter()
+            .map(|address| self.get_latest_account_state(*address))
+            .collect::<Result<_>>()?;
+        Ok(AccountStatesWithProof::new(
+            account_state_with_proof.version,
+            blobs,
+            AccountStatesProof::new(
+                account_state_with_proof
+                    .proof
+                    .transaction_info_with_proof()
+                    .clone(),
+                SparseMerkleMultiProof::new(vec![], vec![]),
+            ),
+        ))
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         unimplemented!()
     }
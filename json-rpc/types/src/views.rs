@@ -11,12 +11,12 @@ use diem_types::{
         NewEpochEvent, PreburnEvent, ReceivedMintEvent, ReceivedPaymentEvent, SentPaymentEvent,
         ToXDXExchangeRateUpdateEvent,
     },
-    account_state_blob::AccountStateWithProof,
+    account_state_blob::{AccountStateWithProof, AccountStatesWithProof},
     contract_event::ContractEvent,
     epoch_change::EpochChangeProof,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
-    proof::{AccountStateProof, AccumulatorConsistencyProof},
+    proof::{AccountStateProof, AccountStatesProof, AccumulatorConsistencyProof},
 };
 use hex::FromHex;
 use move_core_types::{
@@ -857,3 +857,62 @@ impl TryFrom<AccountStateProof> for AccountStateProofView {
         })
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AccountStatesWithProofView {
+    pub version: u64,
+    pub blobs: Vec<Option<BytesView>>,
+    pub proof: AccountStatesProofView,
+}
+
+impl TryFrom<AccountStatesWithProof> for AccountStatesWithProofView {
+    type Error = Error;
+
+    fn try_from(
+        account_states_with_proof: AccountStatesWithProof,
+    ) -> Result<AccountStatesWithProofView, Error> {
+        let blobs = account_states_with_proof
+            .blobs
+            .iter()
+            .map(|blob| {
+                blob.as_ref()
+                    .map(|account_blob| Ok(BytesView::new(bcs::to_bytes(account_blob)?)))
+                    .transpose()
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(AccountStatesWithProofView {
+            version: account_states_with_proof.version,
+            blobs,
+            proof: AccountStatesProofView::try_from(account_states_with_proof.proof)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AccountStatesProofView {
+    pub ledger_info_to_transaction_info_proof: BytesView,
+    pub transaction_info: BytesView,
+    pub transaction_info_to_accounts_proof: BytesView,
+}
+
+impl TryFrom<AccountStatesProof> for AccountStatesProofView {
+    type Error = Error;
+
+    fn try_from(account_states_proof: AccountStatesProof) -> Result<AccountStatesProofView, Error> {
+        Ok(AccountStatesProofView {
+            ledger_info_to_transaction_info_proof: BytesView::new(bcs::to_bytes(
+                account_states_proof
+                    .transaction_info_with_proof()
+                    .ledger_info_to_transaction_info_proof(),
+            )?),
+            transaction_info: BytesView::new(bcs::to_bytes(
+                account_states_proof
+                    .transaction_info_with_proof()
+                    .transaction_info(),
+            )?),
+            transaction_info_to_accounts_proof: BytesView::new(bcs::to_bytes(
+                account_states_proof.transaction_info_to_accounts_proof(),
+            )?),
+        })
+    }
+}
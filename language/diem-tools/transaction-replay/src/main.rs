@@ -6,9 +6,13 @@ use diem_transaction_replay::DiemDebugger;
 use diem_types::{account_address::AccountAddress, transaction::Version};
 use difference::Changeset;
 use move_core_types::effects::ChangeSet;
+use move_vm_runtime::gas_profiler;
 use std::{fs, path::PathBuf};
 use structopt::StructOpt;
 
+/// File in the `--profile-gas` directory holding the per-function gas table
+const GAS_PROFILE_SUMMARY_FILE: &str = "gas_profile.txt";
+
 #[derive(Debug, StructOpt)]
 struct Opt {
     /// Path to the local DiemDB file
@@ -20,6 +24,10 @@ struct Opt {
     /// If true, persist the effects of replaying transactions via `cmd` to disk in a format understood by the Move CLI
     #[structopt(short = "s", global = true)]
     save_write_sets: bool,
+    /// If set, profile the gas consumed by all Move code executed via `cmd` and save a summary and
+    /// folded stacks for flamegraph tools to this directory
+    #[structopt(long, global = true, parse(from_os_str))]
+    profile_gas: Option<PathBuf>,
     #[structopt(subcommand)] // Note that we mark a field as a subcommand
     cmd: Command,
 }
@@ -87,6 +95,10 @@ fn main() -> Result<()> {
 
     println!("Connection Succeeded");
 
+    if opt.profile_gas.is_some() {
+        gas_profiler::start();
+    }
+
     match opt.cmd {
         Command::ReplayTransactions { start, limit } => {
             println!(
@@ -199,5 +211,13 @@ fn main() -> Result<()> {
             )
         ),
     }
+
+    if let (Some(dir), Some(profile)) = (opt.profile_gas, gas_profiler::finish()) {
+        fs::create_dir_all(&dir)?;
+        let mut folded = fs::File::create(dir.join(move_cli::GAS_PROFILE_FILE))?;
+        profile.write_folded_stacks(&mut folded)?;
+        fs::write(dir.join(GAS_PROFILE_SUMMARY_FILE), profile.to_string())?;
+        println!("Gas profile saved to {:?}", dir);
+    }
     Ok(())
 }
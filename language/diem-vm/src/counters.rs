@@ -65,6 +65,16 @@ pub static TXN_VALIDATION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
 pub static TXN_GAS_USAGE: Lazy<Histogram> =
     Lazy::new(|| register_histogram!("diem_vm_txn_gas_usage", "Gas used per transaction").unwrap());
 
+/// Count the number of speculatively executed transactions that had to be re-executed, as they
+/// read state written by an earlier transaction of the same block.
+pub static SPECULATIVE_TRANSACTION_CONFLICTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_vm_speculative_transaction_conflicts",
+        "Number of speculatively executed transactions re-executed due to conflicts"
+    )
+    .unwrap()
+});
+
 /// Count the number of critical errors. This is not intended for display
 /// on a dashboard but rather for triggering alerts.
 pub static CRITICAL_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
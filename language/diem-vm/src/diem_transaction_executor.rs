@@ -11,6 +11,7 @@ use crate::{
     },
     errors::expect_only_successful_execution,
     logging::AdapterLogSchema,
+    parallel_executor::SpeculativeExecution,
     script_to_script_function,
     system_module_names::*,
     transaction_metadata::TransactionMetadata,
@@ -38,12 +39,16 @@ use move_core_types::{
 };
 use move_vm_runtime::{data_cache::RemoteCache, logging::LogContext, session::Session};
 use move_vm_types::gas_schedule::GasStatus;
+use once_cell::sync::OnceCell;
 use rayon::prelude::*;
 use std::{
     collections::HashSet,
     convert::{AsMut, AsRef},
+    iter,
 };
 
+static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
+
 pub struct DiemVM(DiemVMImpl);
 
 impl DiemVM {
@@ -51,6 +56,19 @@ impl DiemVM {
         Self(DiemVMImpl::new(state))
     }
 
+    /// Sets the number of threads blocks are executed on, for the lifetime of the process. Only
+    /// the first call has an effect. Blocks are executed sequentially unless this is set above 1.
+    pub fn set_concurrency_level_once(concurrency_level: usize) {
+        EXECUTION_CONCURRENCY_LEVEL
+            .set(std::cmp::max(concurrency_level, 1))
+            .ok();
+    }
+
+    /// Returns the number of threads blocks are executed on.
+    pub fn get_concurrency_level() -> usize {
+        EXECUTION_CONCURRENCY_LEVEL.get().copied().unwrap_or(1)
+    }
+
     pub fn internals(&self) -> DiemVMInternals {
         DiemVMInternals::new(&self.0)
     }
@@ -625,6 +643,7 @@ impl DiemVM {
         &self,
         transactions: Vec<Transaction>,
         data_cache: &mut StateViewCache,
+        concurrency_level: usize,
     ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
         let count = transactions.len();
         let mut result = vec![];
@@ -647,7 +666,18 @@ impl DiemVM {
                 .collect();
         }
 
-        for (idx, txn) in signature_verified_block.into_iter().enumerate() {
+        // With more than one thread, the transactions that may run in parallel are executed
+        // speculatively once all transactions preceding them have been executed.
+        let speculation_index = if concurrency_level > 1 {
+            SpeculativeExecution::first_index(&signature_verified_block)
+        } else {
+            None
+        };
+        let mut speculative_execution: Option<SpeculativeExecution> = None;
+
+        let mut remaining_txns = signature_verified_block.into_iter();
+        while let Some(txn) = remaining_txns.next() {
+            let idx = result.len();
             let log_context = AdapterLogSchema::new(data_cache.id(), idx);
             if should_restart {
                 let txn_output = TransactionOutput::new(
@@ -660,10 +690,29 @@ impl DiemVM {
                 debug!(log_context, "Retry after reconfiguration");
                 continue;
             };
-            let (vm_status, output, sender) =
-                self.execute_single_transaction(txn, data_cache, &log_context)?;
+
+            if speculation_index == Some(idx) {
+                let txns: Vec<_> = iter::once(&txn).chain(remaining_txns.as_slice()).collect();
+                speculative_execution = Some(SpeculativeExecution::execute(
+                    self,
+                    idx,
+                    &txns,
+                    data_cache,
+                    concurrency_level,
+                ));
+            }
+            let speculative_result = speculative_execution
+                .as_mut()
+                .and_then(|speculative_execution| speculative_execution.take_result(idx));
+            let (vm_status, output, sender) = match speculative_result {
+                Some(speculated) => speculated?,
+                None => self.execute_single_transaction(txn, data_cache, &log_context)?,
+            };
             if !output.status().is_discarded() {
                 data_cache.push_write_set(output.write_set());
+                if let Some(speculative_execution) = speculative_execution.as_mut() {
+                    speculative_execution.commit_write_set(output.write_set());
+                }
             } else {
                 match sender {
                     Some(s) => trace!(
@@ -747,10 +796,25 @@ impl DiemVM {
     pub fn execute_block_and_keep_vm_status(
         transactions: Vec<Transaction>,
         state_view: &dyn StateView,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        Self::execute_block_with_concurrency_level(
+            transactions,
+            state_view,
+            Self::get_concurrency_level(),
+        )
+    }
+
+    /// Alternate form of 'execute_block_and_keep_vm_status' that executes the block on the given
+    /// number of threads rather than the process wide concurrency level. The outputs do not
+    /// depend on the number of threads.
+    pub fn execute_block_with_concurrency_level(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+        concurrency_level: usize,
     ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
         let mut state_view_cache = StateViewCache::new(state_view);
         let vm = DiemVM::new(&state_view_cache);
-        vm.execute_block_impl(transactions, &mut state_view_cache)
+        vm.execute_block_impl(transactions, &mut state_view_cache, concurrency_level)
     }
 }
 
@@ -788,7 +852,7 @@ fn is_reconfiguration(vm_output: &TransactionOutput) -> bool {
 /// Transactions after signature checking:
 /// Waypoints and BlockPrologues are not signed and are unaffected by signature checking,
 /// but a user transaction or writeset transaction is transformed to a SignatureCheckedTransaction.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum PreprocessedTransaction {
     UserTransaction(Box<SignatureCheckedTransaction>),
     WaypointWriteSet(WriteSetPayload),
@@ -0,0 +1,176 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optimistic parallel execution of the transactions within a block.
+//!
+//! Transactions are first executed speculatively and in parallel against the same state, while
+//! recording the access paths each of them reads. They are then committed in order: a transaction
+//! whose read set does not intersect with the write sets committed since the speculation started
+//! would have observed the exact same state when executed sequentially, so its speculative output
+//! is used as is. Any other transaction depends on an earlier one and is re-executed on top of the
+//! committed state. Either way, the outputs are identical to those of sequential execution.
+
+use crate::{
+    counters::SPECULATIVE_TRANSACTION_CONFLICTS,
+    data_cache::StateViewCache,
+    diem_transaction_executor::{DiemVM, PreprocessedTransaction},
+    logging::AdapterLogSchema,
+};
+use diem_infallible::Mutex;
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::AccessPath,
+    transaction::{TransactionOutput, TransactionPayload},
+    vm_status::VMStatus,
+    write_set::WriteSet,
+};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashSet};
+
+type ExecutionResult = Result<(VMStatus, TransactionOutput, Option<String>), VMStatus>;
+
+/// The outcome of speculatively executing a transaction.
+struct Speculation {
+    result: ExecutionResult,
+    read_set: BTreeSet<AccessPath>,
+}
+
+/// The speculative outputs of a suffix of a block, starting at `first_index`.
+pub(crate) struct SpeculativeExecution {
+    first_index: usize,
+    speculations: Vec<Option<Speculation>>,
+    /// The access paths written by the transactions committed since the speculation started.
+    write_set: HashSet<AccessPath>,
+}
+
+impl SpeculativeExecution {
+    /// Returns the index from which on the transactions of a block may be executed
+    /// speculatively, if any. Transactions that may write arbitrary state (i.e., write sets) or
+    /// publish modules, which the VM caches across transactions, are always executed
+    /// sequentially, as are the transactions preceding them.
+    pub fn first_index(transactions: &[PreprocessedTransaction]) -> Option<usize> {
+        let first_index = transactions
+            .iter()
+            .rposition(|txn| !is_speculative(txn))
+            .map_or(0, |index| index + 1);
+        // Speculating on a single transaction gains nothing
+        if transactions.len() > first_index + 1 {
+            Some(first_index)
+        } else {
+            None
+        }
+    }
+
+    /// Executes the given transactions in parallel against the state of `data_cache`, using at
+    /// most `concurrency_level` threads of the global thread pool.
+    pub fn execute(
+        vm: &DiemVM,
+        first_index: usize,
+        transactions: &[&PreprocessedTransaction],
+        data_cache: &StateViewCache<'_>,
+        concurrency_level: usize,
+    ) -> Self {
+        let chunk_size = (transactions.len() + concurrency_level - 1) / concurrency_level;
+        let speculations = transactions
+            .par_iter()
+            .with_min_len(chunk_size)
+            .enumerate()
+            .map(|(offset, txn)| {
+                let read_set_view = ReadSetView::new(data_cache);
+                let log_context = AdapterLogSchema::new(data_cache.id(), first_index + offset);
+                let result = vm.execute_single_transaction(
+                    (*txn).clone(),
+                    &StateViewCache::new(&read_set_view),
+                    &log_context,
+                );
+                Some(Speculation {
+                    result,
+                    read_set: read_set_view.into_read_set(),
+                })
+            })
+            .collect();
+
+        Self {
+            first_index,
+            speculations,
+            write_set: HashSet::new(),
+        }
+    }
+
+    /// Returns the speculative result of the transaction at `index` within the block, unless it
+    /// read state written by a transaction committed since, in which case it must be re-executed.
+    pub fn take_result(&mut self, index: usize) -> Option<ExecutionResult> {
+        let speculation = self
+            .speculations
+            .get_mut(index.checked_sub(self.first_index)?)?
+            .take()?;
+        if speculation
+            .read_set
+            .iter()
+            .any(|access_path| self.write_set.contains(access_path))
+        {
+            SPECULATIVE_TRANSACTION_CONFLICTS.inc();
+            None
+        } else {
+            Some(speculation.result)
+        }
+    }
+
+    /// Records the write set of a committed transaction.
+    pub fn commit_write_set(&mut self, write_set: &WriteSet) {
+        self.write_set
+            .extend(write_set.iter().map(|(access_path, _)| access_path.clone()));
+    }
+}
+
+fn is_speculative(txn: &PreprocessedTransaction) -> bool {
+    match txn {
+        PreprocessedTransaction::UserTransaction(txn) => match txn.payload() {
+            TransactionPayload::Script(_) | TransactionPayload::ScriptFunction(_) => true,
+            TransactionPayload::Module(_) | TransactionPayload::WriteSet(_) => false,
+        },
+        PreprocessedTransaction::InvalidSignature => true,
+        PreprocessedTransaction::BlockMetadata(_)
+        | PreprocessedTransaction::WaypointWriteSet(_)
+        | PreprocessedTransaction::WriteSet(_) => false,
+    }
+}
+
+/// A `StateView` recording every access path read through it.
+struct ReadSetView<'a> {
+    data_cache: &'a StateViewCache<'a>,
+    read_set: Mutex<BTreeSet<AccessPath>>,
+}
+
+impl<'a> ReadSetView<'a> {
+    fn new(data_cache: &'a StateViewCache<'a>) -> Self {
+        Self {
+            data_cache,
+            read_set: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    fn into_read_set(self) -> BTreeSet<AccessPath> {
+        std::mem::take(&mut *self.read_set.lock())
+    }
+}
+
+impl<'a> StateView for ReadSetView<'a> {
+    fn get(&self, access_path: &AccessPath) -> anyhow::Result<Option<Vec<u8>>> {
+        self.read_set.lock().insert(access_path.clone());
+        self.data_cache.get(access_path)
+    }
+
+    fn multi_get(&self, access_paths: &[AccessPath]) -> anyhow::Result<Vec<Option<Vec<u8>>>> {
+        self.read_set.lock().extend(access_paths.iter().cloned());
+        self.data_cache.multi_get(access_paths)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.data_cache.is_genesis()
+    }
+
+    fn id(&self) -> StateViewId {
+        self.data_cache.id()
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use language_e2e_tests::account_universe::{
+    all_transactions_strategy, default_num_accounts, default_num_transactions,
+    log_balance_strategy, run_and_assert_parallel_execution, AccountUniverseGen, P2PTransferGen,
+};
+use proptest::{collection::vec, prelude::*};
+
+proptest! {
+    // These tests are pretty slow but quite comprehensive, so run a smaller number of them.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn parallel_execution_all_transactions(
+        universe in AccountUniverseGen::strategy(
+            2..default_num_accounts(),
+            log_balance_strategy(10_000_000),
+        ),
+        transactions in vec(all_transactions_strategy(1, 1_000_000), 0..default_num_transactions()),
+        concurrency_level in 2usize..8,
+    ) {
+        run_and_assert_parallel_execution(universe, transactions, concurrency_level)?;
+    }
+
+    /// With few accounts, most transactions depend on an earlier one in the block.
+    #[test]
+    fn parallel_execution_conflicting_transfers(
+        universe in AccountUniverseGen::strategy(2..4, 1_000_000u64..10_000_000),
+        transfers in vec(any_with::<P2PTransferGen>((1, 10_000)), 0..default_num_transactions()),
+        concurrency_level in 2usize..8,
+    ) {
+        run_and_assert_parallel_execution(universe, transfers, concurrency_level)?;
+    }
+}
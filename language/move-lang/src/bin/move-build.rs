@@ -5,6 +5,7 @@
 
 use move_lang::{
     command_line::{self as cli},
+    errors::{report_diagnostics, report_warnings, ErrorFormat},
     shared::Flags,
 };
 use structopt::*;
@@ -50,6 +51,10 @@ pub struct Options {
     )]
     pub emit_source_map: bool,
 
+    /// Report errors and warnings as JSON objects, one per line, instead of human readable text
+    #[structopt(long = cli::JSON_ERRORS)]
+    pub json_errors: bool,
+
     #[structopt(subcommand)]
     pub flags: Option<Flags>,
 }
@@ -61,16 +66,29 @@ pub fn main() -> anyhow::Result<()> {
         out_dir,
         no_shadow,
         emit_source_map,
+        json_errors,
         flags,
     } = Options::from_args();
+    let error_format = if json_errors {
+        ErrorFormat::Json
+    } else {
+        ErrorFormat::Human
+    };
 
     let interface_files_dir = format!("{}/generated_interface_files", out_dir);
-    let (files, compiled_units) = move_lang::move_compile_and_report(
+    let (files, result) = move_lang::move_compile_diagnostics(
         &source_files,
         &dependencies,
         Some(interface_files_dir),
         !no_shadow,
         flags.unwrap_or_else(Flags::empty),
     )?;
+    let compiled_units = match result {
+        Ok((compiled_units, warnings)) => {
+            report_warnings(files.clone(), warnings, error_format);
+            compiled_units
+        }
+        Err(diagnostics) => report_diagnostics(files, diagnostics, error_format),
+    };
     move_lang::output_compiled_units(emit_source_map, files, compiled_units, &out_dir)
 }
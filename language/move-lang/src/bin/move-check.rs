@@ -5,6 +5,7 @@
 
 use move_lang::{
     command_line::{self as cli},
+    errors::{report_diagnostics, report_warnings, ErrorFormat},
     shared::Flags,
 };
 use structopt::*;
@@ -45,6 +46,10 @@ pub struct Options {
     )]
     pub no_shadow: bool,
 
+    /// Report errors and warnings as JSON objects, one per line, instead of human readable text
+    #[structopt(long = cli::JSON_ERRORS)]
+    pub json_errors: bool,
+
     #[structopt(subcommand)]
     pub flags: Option<Flags>,
 }
@@ -55,15 +60,25 @@ pub fn main() -> anyhow::Result<()> {
         dependencies,
         out_dir,
         no_shadow,
+        json_errors,
         flags,
     } = Options::from_args();
+    let error_format = if json_errors {
+        ErrorFormat::Json
+    } else {
+        ErrorFormat::Human
+    };
 
-    let _files = move_lang::move_check_and_report(
+    let (files, result) = move_lang::move_check_diagnostics(
         &source_files,
         &dependencies,
         out_dir,
         !no_shadow,
         flags.unwrap_or_else(Flags::empty),
     )?;
+    match result {
+        Ok(warnings) => report_warnings(files, warnings, error_format),
+        Err(diagnostics) => report_diagnostics(files, diagnostics, error_format),
+    }
     Ok(())
 }
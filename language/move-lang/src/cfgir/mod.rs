@@ -15,6 +15,7 @@ mod simplify_jumps;
 pub(crate) mod translate;
 
 use crate::{
+    errors::Category,
     expansion::ast::AbilitySet,
     hlir::ast::*,
     parser::ast::{ModuleIdent, StructName, Var},
@@ -33,7 +34,9 @@ pub fn refine_inference_and_verify(
     cfg: &mut BlockCFG,
     infinite_loop_starts: &BTreeSet<Label>,
 ) {
+    compilation_env.set_category(Category::UnusedItem);
     liveness::last_usage(compilation_env, locals, cfg, infinite_loop_starts);
+    compilation_env.set_category(Category::LocalsSafety);
     let locals_states = locals::verify(
         compilation_env,
         struct_declared_abilities,
@@ -44,6 +47,7 @@ pub fn refine_inference_and_verify(
     );
 
     liveness::release_dead_refs(&locals_states, locals, cfg, infinite_loop_starts);
+    compilation_env.set_category(Category::ReferenceSafety);
     borrows::verify(compilation_env, signature, acquires, locals, cfg);
 }
 
@@ -7,6 +7,7 @@ use crate::{
         ast::{self as G, BasicBlock, BasicBlocks, BlockInfo},
         cfg::BlockCFG,
     },
+    errors::Category,
     expansion::ast::{AbilitySet, Value, Value_},
     hlir::ast::{self as H, Label},
     parser::ast::{ConstantName, FunctionName, ModuleIdent, StructName, Var},
@@ -302,6 +303,7 @@ fn constant_(
     );
     cfgir::optimize(&fake_signature, &locals, &mut cfg);
 
+    context.env.set_category(Category::Declarations);
     if blocks.len() != 1 {
         context.env.add_error(vec![(full_loc, CANNOT_FOLD)]);
         return None;
@@ -399,6 +401,7 @@ fn function_body(
 
             let (mut cfg, infinite_loop_starts, errors) =
                 BlockCFG::new(start, &mut blocks, &block_info);
+            context.env.set_category(Category::UnusedItem);
             for e in errors {
                 context.env.add_error(e);
             }
@@ -22,6 +22,8 @@ pub const SOURCE_MAP_SHORT: &str = "m";
 pub const TEST: &str = "test";
 pub const TEST_SHORT: &str = "t";
 
+pub const JSON_ERRORS: &str = "json-errors";
+
 pub fn parse_address(s: &str) -> Result<Address, String> {
     Address::parse_str(s).map_err(|msg| format!("Invalid argument to '{}': {}", SENDER, msg))
 }
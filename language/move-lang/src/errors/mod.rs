@@ -4,7 +4,7 @@
 use crate::command_line::{read_env_var, COLOR_MODE_ENV_VAR};
 use codespan::{FileId, Files, Span};
 use codespan_reporting::{
-    diagnostic::{Diagnostic, Label},
+    diagnostic::{Diagnostic as CodespanDiagnostic, Label, Severity as CodespanSeverity},
     term::{
         emit,
         termcolor::{Buffer, ColorChoice, StandardStream, WriteColor},
@@ -12,7 +12,12 @@ use codespan_reporting::{
     },
 };
 use move_ir_types::location::*;
-use std::collections::{HashMap, HashSet};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+};
 
 //**************************************************************************************************
 // Types
@@ -27,18 +32,140 @@ pub type FilesSourceText = HashMap<&'static str, String>;
 
 type FileMapping = HashMap<&'static str, FileId>;
 
+//**************************************************************************************************
+// Diagnostics
+//**************************************************************************************************
+
+/// How serious a diagnostic is. Only warnings allow compilation to proceed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+    /// An internal compiler error
+    Bug,
+}
+
+/// The kind of check a diagnostic originates from.
+///
+/// The numeric value of each category is part of its diagnostic code, which tools may match on.
+/// Values must never be changed or reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Uncategorized = 0,
+    /// Lexing and parsing
+    Syntax = 1,
+    /// Well-formedness of modules, scripts and their members, including aliases, attributes and
+    /// constants
+    Declarations = 2,
+    /// Resolution of names to modules, members, type parameters and locals
+    NameResolution = 3,
+    /// Type and ability checking
+    TypeSafety = 4,
+    /// Unused locals and assignments, and unreachable code
+    UnusedItem = 5,
+    /// Ownership of values held in locals
+    LocalsSafety = 6,
+    /// Borrowing and reference safety
+    ReferenceSafety = 7,
+    /// Generation of Move bytecode
+    CodeGeneration = 8,
+    /// Bytecode verification of the compiled units
+    BytecodeVerification = 9,
+}
+
+impl Severity {
+    fn code_prefix(self) -> char {
+        match self {
+            Severity::Warning => 'W',
+            Severity::Error => 'E',
+            Severity::Bug => 'B',
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+            Severity::Bug => write!(f, "bug"),
+        }
+    }
+}
+
+/// A diagnostic reported by the compiler. The first label is the primary label.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub category: Category,
+    pub labels: Error,
+    pub notes: Vec<String>,
+}
+
+pub type Diagnostics = Vec<Diagnostic>;
+
+impl Diagnostic {
+    pub fn new(severity: Severity, category: Category, labels: Error) -> Self {
+        assert!(!labels.is_empty());
+        Self {
+            severity,
+            category,
+            labels,
+            notes: vec![],
+        }
+    }
+
+    pub fn with_notes(mut self, notes: Vec<String>) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// Stable code identifying the severity and category of the diagnostic, e.g. `E04`.
+    pub fn code(&self) -> String {
+        format!("{}{:02}", self.severity.code_prefix(), self.category as u8)
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+}
+
+/// Wrap `errors` produced in `category` as diagnostics.
+pub fn categorize(category: Category, errors: Errors) -> Diagnostics {
+    let severity = match category {
+        Category::BytecodeVerification => Severity::Bug,
+        _ => Severity::Error,
+    };
+    errors
+        .into_iter()
+        .map(|error| Diagnostic::new(severity, category, error))
+        .collect()
+}
+
+/// Keep the errors of `diagnostics`, dropping warnings, codes and notes.
+pub fn into_errors(diagnostics: Diagnostics) -> Errors {
+    diagnostics
+        .into_iter()
+        .filter(|diag| !diag.is_warning())
+        .map(|diag| diag.labels)
+        .collect()
+}
+
+/// How diagnostics are rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Annotated source snippets, for humans
+    Human,
+    /// One JSON object per diagnostic and per line, for tools
+    Json,
+}
+
 //**************************************************************************************************
 // Reporting
 //**************************************************************************************************
 
 pub fn report_errors(files: FilesSourceText, errors: Errors) -> ! {
-    let color_choice = match read_env_var(COLOR_MODE_ENV_VAR).as_str() {
-        "NONE" => ColorChoice::Never,
-        "ANSI" => ColorChoice::AlwaysAnsi,
-        "ALWAYS" => ColorChoice::Always,
-        _ => ColorChoice::Auto,
-    };
-    let mut writer = StandardStream::stderr(color_choice);
+    let mut writer = StandardStream::stderr(color_choice());
     output_errors(&mut writer, files, errors);
     std::process::exit(1)
 }
@@ -55,17 +182,171 @@ pub fn report_errors_to_color_buffer(files: FilesSourceText, errors: Errors) ->
     writer.into_inner()
 }
 
-fn output_errors<W: WriteColor>(writer: &mut W, sources: FilesSourceText, errors: Errors) {
-    assert!(!errors.is_empty());
+/// Report `diagnostics`, which must contain at least one error, to stderr in the given format and
+/// exit.
+pub fn report_diagnostics(
+    files: FilesSourceText,
+    diagnostics: Diagnostics,
+    format: ErrorFormat,
+) -> ! {
+    assert!(diagnostics.iter().any(|diag| !diag.is_warning()));
+    write_diagnostics(files, diagnostics, format);
+    std::process::exit(1)
+}
+
+/// Report `warnings` to stderr in the given format, if there are any.
+pub fn report_warnings(files: FilesSourceText, warnings: Diagnostics, format: ErrorFormat) {
+    if !warnings.is_empty() {
+        write_diagnostics(files, warnings, format)
+    }
+}
+
+fn write_diagnostics(files: FilesSourceText, diagnostics: Diagnostics, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Human => {
+            let mut writer = StandardStream::stderr(color_choice());
+            output_diagnostics(&mut writer, files, diagnostics);
+        }
+        ErrorFormat::Json => {
+            let stderr = std::io::stderr();
+            output_json_diagnostics(&mut stderr.lock(), files, diagnostics).unwrap();
+        }
+    }
+}
+
+pub fn report_diagnostics_to_buffer(
+    files: FilesSourceText,
+    diagnostics: Diagnostics,
+    format: ErrorFormat,
+) -> Vec<u8> {
+    match format {
+        ErrorFormat::Human => {
+            let mut writer = Buffer::no_color();
+            output_diagnostics(&mut writer, files, diagnostics);
+            writer.into_inner()
+        }
+        ErrorFormat::Json => {
+            let mut buffer = vec![];
+            output_json_diagnostics(&mut buffer, files, diagnostics).unwrap();
+            buffer
+        }
+    }
+}
+
+/// Render `diagnostics` as annotated source snippets without their codes, the way
+/// `report_errors_to_buffer` renders errors, e.g. for baselines of tests.
+pub fn report_diagnostics_to_buffer_without_codes(
+    files: FilesSourceText,
+    diagnostics: Diagnostics,
+) -> Vec<u8> {
+    let mut writer = Buffer::no_color();
+    render_diagnostics(&mut writer, files, diagnostics, false);
+    writer.into_inner()
+}
+
+fn color_choice() -> ColorChoice {
+    match read_env_var(COLOR_MODE_ENV_VAR).as_str() {
+        "NONE" => ColorChoice::Never,
+        "ANSI" => ColorChoice::AlwaysAnsi,
+        "ALWAYS" => ColorChoice::Always,
+        _ => ColorChoice::Auto,
+    }
+}
+
+fn make_files(sources: FilesSourceText) -> (Files<String>, FileMapping) {
     let mut files = Files::new();
     let mut file_mapping = HashMap::new();
     for (fname, source) in sources.into_iter() {
         let id = files.add(fname, source);
         file_mapping.insert(fname, id);
     }
+    (files, file_mapping)
+}
+
+fn output_errors<W: WriteColor>(writer: &mut W, sources: FilesSourceText, errors: Errors) {
+    assert!(!errors.is_empty());
+    let (files, file_mapping) = make_files(sources);
     render_errors(writer, &files, &file_mapping, errors);
 }
 
+fn output_diagnostics<W: WriteColor>(
+    writer: &mut W,
+    sources: FilesSourceText,
+    diagnostics: Diagnostics,
+) {
+    render_diagnostics(writer, sources, diagnostics, true)
+}
+
+fn render_diagnostics<W: WriteColor>(
+    writer: &mut W,
+    sources: FilesSourceText,
+    diagnostics: Diagnostics,
+    with_codes: bool,
+) {
+    let (files, file_mapping) = make_files(sources);
+    for diag in dedup_diagnostics(diagnostics) {
+        let code = diag.code();
+        let severity = match diag.severity {
+            Severity::Warning => CodespanSeverity::Warning,
+            Severity::Error => CodespanSeverity::Error,
+            Severity::Bug => CodespanSeverity::Bug,
+        };
+        let mut rendered =
+            render_diagnostic(&files, &file_mapping, severity, diag.labels).with_notes(diag.notes);
+        if with_codes {
+            rendered = rendered.with_code(code);
+        }
+        emit(writer, &Config::default(), &files, &rendered).unwrap()
+    }
+}
+
+fn output_json_diagnostics<W: Write>(
+    writer: &mut W,
+    sources: FilesSourceText,
+    diagnostics: Diagnostics,
+) -> std::io::Result<()> {
+    let (files, file_mapping) = make_files(sources);
+    let json_label = |(loc, msg): &(Loc, String)| {
+        let (id, span) = convert_loc(&files, &file_mapping, *loc);
+        let start = files.location(id, span.start()).unwrap();
+        let end = files.location(id, span.end()).unwrap();
+        json!({
+            "file": loc.file(),
+            "start": {
+                "line": start.line.0 + 1,
+                "column": start.column.0 + 1,
+            },
+            "end": {
+                "line": end.line.0 + 1,
+                "column": end.column.0 + 1,
+            },
+            "message": msg,
+        })
+    };
+    for diag in dedup_diagnostics(diagnostics) {
+        let (primary, secondary) = diag.labels.split_first().unwrap();
+        let value = json!({
+            "code": diag.code(),
+            "severity": diag.severity.to_string(),
+            "primary": json_label(primary),
+            "secondary": secondary.iter().map(json_label).collect::<Vec<_>>(),
+            "notes": diag.notes,
+        });
+        writeln!(writer, "{}", value)?;
+    }
+    Ok(())
+}
+
+/// Sort diagnostics by the location of their primary label and drop duplicates.
+fn dedup_diagnostics(mut diagnostics: Diagnostics) -> Diagnostics {
+    diagnostics.sort_by(|d1, d2| d1.labels[0].0.cmp(&d2.labels[0].0));
+    let mut seen: HashSet<HashableError> = HashSet::new();
+    diagnostics
+        .into_iter()
+        .filter(|diag| seen.insert(hashable_error(&diag.labels)))
+        .collect()
+}
+
 fn hashable_error(error: &ErrorSlice) -> HashableError {
     error
         .iter()
@@ -112,14 +393,27 @@ fn convert_loc(files: &Files<String>, file_mapping: &FileMapping, loc: Loc) -> (
     (id, Span::new(begin_index, end_index))
 }
 
-fn render_error(files: &Files<String>, file_mapping: &FileMapping, mut error: Error) -> Diagnostic {
+fn render_error(
+    files: &Files<String>,
+    file_mapping: &FileMapping,
+    error: Error,
+) -> CodespanDiagnostic {
+    render_diagnostic(files, file_mapping, CodespanSeverity::Error, error)
+}
+
+fn render_diagnostic(
+    files: &Files<String>,
+    file_mapping: &FileMapping,
+    severity: CodespanSeverity,
+    mut error: Error,
+) -> CodespanDiagnostic {
     let mk_lbl = |err: (Loc, String)| -> Label {
         let (id, span) = convert_loc(files, file_mapping, err.0);
         Label::new(id, span, err.1)
     };
     let err = error.remove(0);
     // TODO message with each error msg
-    let mut diag = Diagnostic::new_error("", mk_lbl(err));
+    let mut diag = CodespanDiagnostic::new(severity, "", mk_lbl(err));
     diag = diag.with_secondary_labels(error.into_iter().map(mk_lbl));
     diag
 }
@@ -1773,6 +1773,7 @@ fn check_unused_locals(
         .expect("ICE Signature should always be defined when checking a function body");
     let mut unused = BTreeSet::new();
     let mut errors = Vec::new();
+    let mut warnings = Vec::new();
     // report unused locals
     for (v, _) in locals
         .key_cloned_iter()
@@ -1783,24 +1784,29 @@ fn check_unused_locals(
             DisplayVar::Orig(vstr) => vstr,
         };
         let loc = v.loc();
-        let msg = if signature.is_parameter(&v) {
-            format!(
+        if signature.is_parameter(&v) {
+            // the parameter stays in the signature, so it does not affect the generated code
+            let msg = format!(
                 "Unused parameter '{0}'. Consider removing or prefixing with an underscore: '_{0}'",
                 vstr
-            )
+            );
+            warnings.push((loc, msg));
         } else {
             // unused local variable; mark for removal
             unused.insert(v);
-            format!(
+            let msg = format!(
                 "Unused local '{0}'. Consider removing or prefixing with an underscore: '_{0}'",
                 vstr
-            )
-        };
-        errors.push((loc, msg));
+            );
+            errors.push((loc, msg));
+        }
     }
     for error in errors {
         context.env.add_error(vec![error]);
     }
+    for warning in warnings {
+        context.env.add_warning(vec![warning]);
+    }
     for v in &unused {
         locals.remove(v);
     }
@@ -108,23 +108,43 @@ pub fn move_check(
     sources_shadow_deps: bool,
     flags: Flags,
 ) -> anyhow::Result<(FilesSourceText, Result<(), Errors>)> {
+    let (files, result) = move_check_diagnostics(
+        targets,
+        deps,
+        interface_files_dir_opt,
+        sources_shadow_deps,
+        flags,
+    )?;
+    Ok((files, result.map(|_warnings| ()).map_err(into_errors)))
+}
+
+/// Similar to move_check but reports diagnostics, which carry a code and a severity. On success,
+/// returns the warnings reported while checking.
+pub fn move_check_diagnostics(
+    targets: &[String],
+    deps: &[String],
+    interface_files_dir_opt: Option<String>,
+    sources_shadow_deps: bool,
+    flags: Flags,
+) -> anyhow::Result<(FilesSourceText, Result<Diagnostics, Diagnostics>)> {
     let mut compilation_env = CompilationEnv::new(flags);
     let (files, pprog_and_comments_res) =
         move_parse(targets, deps, interface_files_dir_opt, sources_shadow_deps)?;
     let (_comments, pprog) = match pprog_and_comments_res {
-        Err(errors) => return Ok((files, Err(errors))),
+        Err(errors) => return Ok((files, Err(categorize(Category::Syntax, errors)))),
         Ok(res) => res,
     };
 
-    let result = match move_continue_up_to(
+    let result = match run(
         &mut compilation_env,
         None,
         PassResult::Parser(pprog),
         Pass::CFGIR,
+        |_, _| (),
     ) {
-        Ok(PassResult::CFGIR(_)) => Ok(()),
+        Ok(PassResult::CFGIR(_)) => Ok(compilation_env.take_warnings()),
         Ok(_) => unreachable!(),
-        Err(errors) => Err(errors),
+        Err(diagnostics) => Err(diagnostics),
     };
     Ok((files, result))
 }
@@ -163,23 +183,49 @@ pub fn move_compile(
     sources_shadow_deps: bool,
     flags: Flags,
 ) -> anyhow::Result<(FilesSourceText, Result<Vec<CompiledUnit>, Errors>)> {
+    let (files, result) = move_compile_diagnostics(
+        targets,
+        deps,
+        interface_files_dir_opt,
+        sources_shadow_deps,
+        flags,
+    )?;
+    Ok((
+        files,
+        result.map(|(units, _warnings)| units).map_err(into_errors),
+    ))
+}
+
+/// Similar to move_compile but reports diagnostics, which carry a code and a severity. On success,
+/// returns the warnings reported while compiling along with the compiled units.
+pub fn move_compile_diagnostics(
+    targets: &[String],
+    deps: &[String],
+    interface_files_dir_opt: Option<String>,
+    sources_shadow_deps: bool,
+    flags: Flags,
+) -> anyhow::Result<(
+    FilesSourceText,
+    Result<(Vec<CompiledUnit>, Diagnostics), Diagnostics>,
+)> {
     let mut compilation_env = CompilationEnv::new(flags);
     let (files, pprog_and_comments_res) =
         move_parse(targets, deps, interface_files_dir_opt, sources_shadow_deps)?;
     let (_comments, pprog) = match pprog_and_comments_res {
-        Err(errors) => return Ok((files, Err(errors))),
+        Err(errors) => return Ok((files, Err(categorize(Category::Syntax, errors)))),
         Ok(res) => res,
     };
 
-    let result = match move_continue_up_to(
+    let result = match run(
         &mut compilation_env,
         None,
         PassResult::Parser(pprog),
         Pass::Compilation,
+        |_, _| (),
     ) {
-        Ok(PassResult::Compilation(units)) => Ok(units),
+        Ok(PassResult::Compilation(units)) => Ok((units, compilation_env.take_warnings())),
         Ok(_) => unreachable!(),
-        Err(errors) => Err(errors),
+        Err(diagnostics) => Err(diagnostics),
     };
     Ok((files, result))
 }
@@ -284,8 +330,9 @@ pub fn move_construct_pre_compiled_lib(
             compiled = Some(units.clone())
         }
     };
-    let result = run(compilation_env, None, start, Pass::Compilation, save_result).map(|_| {
-        FullyCompiledProgram {
+    let result = run(compilation_env, None, start, Pass::Compilation, save_result)
+        .map_err(into_errors)
+        .map(|_| FullyCompiledProgram {
             parser: parser.unwrap(),
             expansion: expansion.unwrap(),
             naming: naming.unwrap(),
@@ -293,8 +340,7 @@ pub fn move_construct_pre_compiled_lib(
             hlir: hlir.unwrap(),
             cfgir: cfgir.unwrap(),
             compiled: compiled.unwrap(),
-        }
-    });
+        });
 
     Ok((files, result))
 }
@@ -307,7 +353,7 @@ pub fn move_continue_up_to(
     pass: PassResult,
     until: Pass,
 ) -> Result<PassResult, Errors> {
-    run(compilation_env, pre_compiled_lib, pass, until, |_, _| ())
+    run(compilation_env, pre_compiled_lib, pass, until, |_, _| ()).map_err(into_errors)
 }
 
 //**************************************************************************************************
@@ -586,7 +632,7 @@ fn run(
     cur: PassResult,
     until: Pass,
     mut result_check: impl FnMut(&PassResult, &CompilationEnv),
-) -> Result<PassResult, Errors> {
+) -> Result<PassResult, Diagnostics> {
     result_check(&cur, compilation_env);
     if cur.equivalent_pass() >= until {
         return Ok(cur);
@@ -594,6 +640,7 @@ fn run(
 
     match cur {
         PassResult::Parser(prog) => {
+            compilation_env.set_category(Category::Declarations);
             let eprog = expansion::translate::program(compilation_env, pre_compiled_lib, prog);
             run(
                 compilation_env,
@@ -604,6 +651,7 @@ fn run(
             )
         }
         PassResult::Expansion(eprog) => {
+            compilation_env.set_category(Category::NameResolution);
             let nprog = naming::translate::program(compilation_env, pre_compiled_lib, eprog);
             run(
                 compilation_env,
@@ -614,8 +662,9 @@ fn run(
             )
         }
         PassResult::Naming(nprog) => {
+            compilation_env.set_category(Category::TypeSafety);
             let tprog = typing::translate::program(compilation_env, pre_compiled_lib, nprog);
-            compilation_env.check_errors()?;
+            compilation_env.check_diagnostics()?;
             run(
                 compilation_env,
                 pre_compiled_lib,
@@ -625,6 +674,7 @@ fn run(
             )
         }
         PassResult::Typing(tprog) => {
+            compilation_env.set_category(Category::UnusedItem);
             let hprog = hlir::translate::program(compilation_env, pre_compiled_lib, tprog);
             run(
                 compilation_env,
@@ -636,7 +686,7 @@ fn run(
         }
         PassResult::HLIR(hprog) => {
             let cprog = cfgir::translate::program(compilation_env, pre_compiled_lib, hprog);
-            compilation_env.check_errors()?;
+            compilation_env.check_diagnostics()?;
             run(
                 compilation_env,
                 pre_compiled_lib,
@@ -646,9 +696,10 @@ fn run(
             )
         }
         PassResult::CFGIR(cprog) => {
+            compilation_env.set_category(Category::CodeGeneration);
             let compiled_units =
                 to_bytecode::translate::program(compilation_env, pre_compiled_lib, cprog);
-            compilation_env.check_errors()?;
+            compilation_env.check_diagnostics()?;
             assert!(until == Pass::Compilation);
             run(
                 compilation_env,
@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{command_line as cli, errors::Errors};
+use crate::{
+    command_line as cli,
+    errors::{into_errors, Category, Diagnostic, Diagnostics, Errors, Severity},
+};
 use fallible::copy_from_slice::copy_slice_to_vec;
 use move_ir_types::location::*;
 use petgraph::{algo::astar as petgraph_astar, graphmap::DiGraphMap};
@@ -205,7 +208,9 @@ pub fn shortest_cycle<'a, T: Ord + Hash>(
 
 pub struct CompilationEnv {
     flags: Flags,
-    errors: Errors,
+    diagnostics: Diagnostics,
+    /// Category of the diagnostics reported by the check currently running
+    category: Category,
     // TODO(tzakian): Remove the global counter and use this counter instead
     // pub counter: u64,
 }
@@ -214,35 +219,73 @@ impl CompilationEnv {
     pub fn new(flags: Flags) -> Self {
         Self {
             flags,
-            errors: Vec::new(),
+            diagnostics: Vec::new(),
+            category: Category::Uncategorized,
         }
     }
 
+    /// Set the category of the diagnostics reported from now on.
+    pub fn set_category(&mut self, category: Category) {
+        self.category = category
+    }
+
     pub fn add_error(&mut self, e: Vec<(Loc, impl Into<String>)>) {
-        self.errors
-            .push(e.into_iter().map(|(loc, msg)| (loc, msg.into())).collect())
+        self.add_diagnostic(Severity::Error, e)
     }
 
     pub fn add_errors(&mut self, es: Errors) {
-        self.errors.extend(es)
+        for e in es {
+            self.add_error(e)
+        }
+    }
+
+    /// Report a diagnostic that does not prevent compilation from succeeding.
+    pub fn add_warning(&mut self, e: Vec<(Loc, impl Into<String>)>) {
+        self.add_diagnostic(Severity::Warning, e)
+    }
+
+    fn add_diagnostic(&mut self, severity: Severity, e: Vec<(Loc, impl Into<String>)>) {
+        self.diagnostics.push(Diagnostic::new(
+            severity,
+            self.category,
+            e.into_iter().map(|(loc, msg)| (loc, msg.into())).collect(),
+        ))
     }
 
     pub fn has_errors(&self) -> bool {
-        !self.errors.is_empty()
+        self.count_errors() > 0
     }
 
     pub fn count_errors(&self) -> usize {
-        self.errors.len()
+        self.diagnostics
+            .iter()
+            .filter(|diag| !diag.is_warning())
+            .count()
     }
 
     pub fn check_errors(&mut self) -> Result<(), Errors> {
+        self.check_diagnostics().map_err(into_errors)
+    }
+
+    /// Like `check_errors`, but keeps the categories of the errors and returns any warnings along
+    /// with them.
+    pub fn check_diagnostics(&mut self) -> Result<(), Diagnostics> {
         if self.has_errors() {
-            Err(std::mem::take(&mut self.errors))
+            Err(std::mem::take(&mut self.diagnostics))
         } else {
             Ok(())
         }
     }
 
+    /// Remove and return the warnings reported so far.
+    pub fn take_warnings(&mut self) -> Diagnostics {
+        let (warnings, errors): (Diagnostics, Diagnostics) = std::mem::take(&mut self.diagnostics)
+            .into_iter()
+            .partition(|diag| diag.is_warning());
+        self.diagnostics = errors;
+        warnings
+    }
+
     pub fn flags(&self) -> &Flags {
         &self.flags
     }
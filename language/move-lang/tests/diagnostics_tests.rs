@@ -0,0 +1,123 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_lang::{
+    errors::{report_diagnostics_to_buffer, Category, Diagnostics, ErrorFormat, Severity},
+    move_check_diagnostics, move_compile,
+    shared::Flags,
+};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn check(source: &str) -> (NamedTempFile, Vec<u8>, Diagnostics) {
+    let mut file = tempfile::Builder::new().suffix(".move").tempfile().unwrap();
+    file.write_all(source.as_bytes()).unwrap();
+    let targets = vec![file.path().to_str().unwrap().to_string()];
+    let (files, result) =
+        move_check_diagnostics(&targets, &[], None, false, Flags::empty()).unwrap();
+    let diagnostics = result.unwrap_err();
+    let json = report_diagnostics_to_buffer(files, diagnostics.clone(), ErrorFormat::Json);
+    (file, json, diagnostics)
+}
+
+fn json_lines(json: &[u8]) -> Vec<serde_json::Value> {
+    std::str::from_utf8(json)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn syntax_error_code() {
+    let (_file, json, diagnostics) = check("module 0x1::M { fun f( }");
+    assert!(diagnostics
+        .iter()
+        .all(|diag| diag.category == Category::Syntax && diag.severity == Severity::Error));
+    let values = json_lines(&json);
+    assert_eq!(values[0]["code"], "E01");
+    assert_eq!(values[0]["severity"], "error");
+}
+
+#[test]
+fn type_error_json() {
+    let source = "module 0x1::M {\n    fun f(): u64 {\n        true\n    }\n}\n";
+    let (file, json, diagnostics) = check(source);
+    assert!(diagnostics.iter().all(|diag| diag.code() == "E04"));
+
+    let values = json_lines(&json);
+    assert!(!values.is_empty());
+    let value = &values[0];
+    assert_eq!(value["code"], "E04");
+    assert_eq!(value["severity"], "error");
+    assert_eq!(value["primary"]["file"], file.path().to_str().unwrap());
+    assert_eq!(value["primary"]["start"]["line"], 3);
+    assert_eq!(value["primary"]["start"]["column"], 9);
+    assert!(!value["secondary"].as_array().unwrap().is_empty());
+    assert!(value["notes"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn reference_safety_code() {
+    let source = "module 0x1::M {
+    fun f() {
+        let x = 0;
+        let r = &mut x;
+        let s = &mut x;
+        *r = 1;
+        *s = 2;
+    }
+}
+";
+    let (_file, _json, diagnostics) = check(source);
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics
+        .iter()
+        .all(|diag| diag.category == Category::ReferenceSafety && diag.code() == "E07"));
+}
+
+#[test]
+fn unused_parameter_warning() {
+    let source = "module 0x1::M {\n    fun f(x: u64) {}\n}\n";
+    let mut file = tempfile::Builder::new().suffix(".move").tempfile().unwrap();
+    file.write_all(source.as_bytes()).unwrap();
+    let targets = vec![file.path().to_str().unwrap().to_string()];
+
+    // The warning does not fail the compilation
+    let (files, result) =
+        move_check_diagnostics(&targets, &[], None, false, Flags::empty()).unwrap();
+    let warnings = result.unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].code(), "W05");
+    let values = json_lines(&report_diagnostics_to_buffer(
+        files,
+        warnings,
+        ErrorFormat::Json,
+    ));
+    assert_eq!(values[0]["severity"], "warning");
+    assert_eq!(values[0]["primary"]["start"]["line"], 2);
+    move_compile(&targets, &[], None, false, Flags::empty())
+        .unwrap()
+        .1
+        .unwrap();
+
+    // Along with an error found later, the warning is reported with it
+    let source = "module 0x1::M {
+    fun f(y: u64) {
+        let x = 0;
+        let r = &mut x;
+        let s = &mut x;
+        *r = 1;
+        *s = 2;
+    }
+}
+";
+    let (_file, _json, diagnostics) = check(source);
+    assert!(diagnostics
+        .iter()
+        .any(|diag| diag.severity == Severity::Warning && diag.code() == "W05"));
+    assert!(diagnostics
+        .iter()
+        .any(|diag| diag.severity == Severity::Error && diag.code() == "E07"));
+}
@@ -1,7 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use move_lang::{command_line::read_bool_env_var, move_compile, shared::Flags};
+use move_lang::{
+    command_line::read_bool_env_var,
+    errors::{report_diagnostics_to_buffer_without_codes, Category, Diagnostic, Severity},
+    move_compile_diagnostics,
+    shared::Flags,
+};
 use move_lang_test_utils::*;
 use std::{fs, path::Path};
 
@@ -50,14 +55,22 @@ fn move_check_testsuite(path: &Path) -> datatest_stable::Result<()> {
     let exp_path = path.with_extension(EXP_EXT);
     let out_path = path.with_extension(OUT_EXT);
 
-    let (files, units_or_errors) = move_compile(&targets, &deps, None, false, Flags::empty())?;
-    let errors = match units_or_errors {
-        Err(errors) => errors,
-        Ok(units) => move_lang::compiled_unit::verify_units(units).1,
+    let (files, units_or_diagnostics) =
+        move_compile_diagnostics(&targets, &deps, None, false, Flags::empty())?;
+    let diagnostics = match units_or_diagnostics {
+        Err(diagnostics) => diagnostics,
+        Ok((units, mut warnings)) => {
+            let errors = move_lang::compiled_unit::verify_units(units).1;
+            warnings.extend(errors.into_iter().map(|error| {
+                Diagnostic::new(Severity::Error, Category::BytecodeVerification, error)
+            }));
+            warnings
+        }
     };
-    let has_errors = !errors.is_empty();
+    // warnings are part of the baseline, even if compilation succeeded
+    let has_errors = !diagnostics.is_empty();
     let error_buffer = if has_errors {
-        move_lang::errors::report_errors_to_buffer(files, errors)
+        report_diagnostics_to_buffer_without_codes(files, diagnostics)
     } else {
         vec![]
     };
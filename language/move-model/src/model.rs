@@ -2045,6 +2045,11 @@ impl<'env> StructEnv<'env> {
         self.module_env.get_id().qualified(self.get_id())
     }
 
+    /// Gets the definition index of this struct.
+    pub fn get_def_idx(&self) -> StructDefinitionIndex {
+        self.data.def_idx
+    }
+
     /// Determines whether this struct is native.
     pub fn is_native(&self) -> bool {
         let def = self.module_env.data.module.struct_def_at(self.data.def_idx);
@@ -5,23 +5,34 @@
 
 use anyhow::anyhow;
 
-use std::{collections::BTreeMap, fs, option::Option::None};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    option::Option::None,
+    path::{Path, PathBuf},
+};
 
 use codespan::{ByteIndex, ColumnIndex, LineIndex, Location, Span};
 use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
 use itertools::Itertools;
 use log::{debug, info, warn};
-use num::BigInt;
+use num::{bigint::Sign, BigInt};
 use pretty::RcDoc;
 use regex::Regex;
 
+use move_core_types::{account_address::AccountAddress, language_storage::StructTag};
 use move_model::{
     code_writer::CodeWriter,
     model::{FunId, GlobalEnv, Loc, ModuleId, StructId},
     ty::{PrimitiveType, Type},
 };
 
-use crate::prover_task_runner::{ProverTaskRunner, RunBoogieWithSeeds};
+use crate::{
+    counterexample::{
+        Counterexample, CounterexampleResource, CounterexampleValue, ExpectedOutcome,
+    },
+    prover_task_runner::{ProverTaskRunner, RunBoogieWithSeeds},
+};
 // DEBUG
 // use backtrace::Backtrace;
 use crate::options::{BoogieOptions, VectorTheory};
@@ -31,8 +42,11 @@ use move_model::{
     model::{NodeId, QualifiedId},
 };
 use once_cell::sync::Lazy;
-use std::num::ParseIntError;
-use vm::file_format::FunctionDefinitionIndex;
+use std::{convert::TryFrom, num::ParseIntError};
+use vm::{
+    access::ModuleAccess,
+    file_format::{FunctionDefinitionIndex, StructDefinitionIndex},
+};
 
 /// A type alias for the way how we use crate `pretty`'s document type. `pretty` is a
 /// Wadler-style pretty printer. Our simple usage doesn't require any lifetime management.
@@ -90,6 +104,8 @@ pub enum TraceEntry {
     Result(QualifiedId<FunId>, usize, ModelValue),
     Abort(QualifiedId<FunId>, ModelValue),
     Exp(NodeId, ModelValue),
+    GlobalAddress(QualifiedId<StructId>, ModelValue),
+    Global(QualifiedId<StructId>, ModelValue),
 }
 
 impl<'env> BoogieWrapper<'env> {
@@ -191,8 +207,12 @@ impl<'env> BoogieWrapper<'env> {
         debug!("writing boogie log to {}", boogie_log_file);
         fs::write(&boogie_log_file, &all_output)?;
 
-        for error in &errors {
-            self.add_error(error);
+        for (idx, error) in errors.iter().enumerate() {
+            let counterexample_file = match &self.options.counterexample_dir {
+                Some(dir) => self.write_counterexample(Path::new(dir), idx, error)?,
+                None => None,
+            };
+            self.add_error(error, counterexample_file);
         }
 
         if !log_file_existed && !self.options.keep_artifacts {
@@ -203,7 +223,7 @@ impl<'env> BoogieWrapper<'env> {
     }
 
     /// Helper to add a boogie error as a codespan Diagnostic.
-    fn add_error(&self, error: &BoogieError) {
+    fn add_error(&self, error: &BoogieError, counterexample_file: Option<PathBuf>) {
         // Create the error
         let label = Label::new(error.loc.file_id(), error.loc.span(), "");
         let mut diag = Diagnostic::new(Severity::Error, error.message.clone(), label);
@@ -303,9 +323,210 @@ impl<'env> BoogieWrapper<'env> {
             }
             diag = diag.with_notes(display);
         }
+        if let Some(file) = counterexample_file {
+            diag.notes
+                .push(format!("counterexample written to `{}`", file.display()));
+        }
         self.env.add_diag(diag);
     }
 
+    /// Writes the counterexample of the error, if it has one, as a JSON file into `dir`. Returns
+    /// the path of the file.
+    fn write_counterexample(
+        &self,
+        dir: &Path,
+        idx: usize,
+        error: &BoogieError,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let counterexample = match self.extract_counterexample(error) {
+            Some(counterexample) => counterexample,
+            None => return Ok(None),
+        };
+        fs::create_dir_all(dir)?;
+        let file = dir.join(format!(
+            "{}_{}_{}.json",
+            counterexample.module.name(),
+            counterexample.function,
+            idx
+        ));
+        debug!("writing counterexample to {}", file.display());
+        fs::write(&file, serde_json::to_string_pretty(&counterexample)?)?;
+        Ok(Some(file))
+    }
+
+    /// Reconstructs the inputs of the verified function from the model and execution trace of
+    /// an assertion failure. Returns `None` if the error has no model or the inputs cannot be
+    /// represented, e.g. because the function is generic.
+    fn extract_counterexample(&self, error: &BoogieError) -> Option<Counterexample> {
+        use TraceEntry::*;
+        if !error.kind.is_from_verification() {
+            return None;
+        }
+        let model = error.model.as_ref()?;
+        // The trace starts at the entry of the verified function.
+        let fun_id = error.execution_trace.iter().find_map(|entry| match entry {
+            AtLocation(loc) => self
+                .env
+                .get_enclosing_function(loc)
+                .map(|fun_env| fun_env.get_qualified_id()),
+            Temporary(fun, ..) | Result(fun, ..) | Abort(fun, _) => Some(*fun),
+            _ => None,
+        })?;
+        let fun_env = self.env.get_function(fun_id);
+        if !fun_env.get_type_parameters().is_empty() {
+            warn!(
+                "[boogie model] no counterexample for generic function `{}`",
+                fun_env.get_full_name_str()
+            );
+            return None;
+        }
+        let fun_target = self
+            .targets
+            .get_target(&fun_env, &FunctionVariant::Baseline);
+        let param_count = fun_target.get_parameter_count();
+
+        let mut params = BTreeMap::new();
+        let mut results = BTreeMap::new();
+        let mut globals = vec![];
+        let mut seen_globals = BTreeSet::new();
+        let mut global_addr = None;
+        let mut abort = None;
+        for entry in &error.execution_trace {
+            match entry {
+                // Parameters are tracked on entry, later entries stem from recursive calls.
+                Temporary(fun, idx, value) if *fun == fun_id && *idx < param_count => {
+                    params.entry(*idx).or_insert_with(|| {
+                        value.concrete_value(self, model, fun_target.get_local_type(*idx))
+                    });
+                }
+                // Results of recursive calls come first, the last one is from the outermost call.
+                Result(fun, idx, value) if *fun == fun_id => {
+                    results.insert(
+                        *idx,
+                        value.concrete_value(self, model, fun_target.get_return_type(*idx)),
+                    );
+                }
+                Abort(_, value) => {
+                    abort = Some(value);
+                    break;
+                }
+                GlobalAddress(_, value) => {
+                    global_addr = value.extract_address();
+                }
+                Global(mem, value) => {
+                    if let Some(address) = global_addr.take() {
+                        if seen_globals.insert((*mem, address)) {
+                            globals.push(self.extract_resource(model, *mem, address, value)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let args = (0..param_count)
+            .map(|idx| {
+                params
+                    .remove(&idx)
+                    .flatten()
+                    .or_else(|| self.zero_value(fun_target.get_local_type(idx)))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let signers = args
+            .iter()
+            .filter_map(|arg| match arg {
+                CounterexampleValue::Signer(addr) => Some(*addr),
+                _ => None,
+            })
+            .collect();
+        let expected = match abort {
+            Some(code) => match code.extract_i128()? {
+                -1 => ExpectedOutcome::ExecutionFailure,
+                code => ExpectedOutcome::Abort(u64::try_from(code).ok()?),
+            },
+            None => ExpectedOutcome::Return(
+                (0..fun_target.get_return_count())
+                    .map(|idx| results.remove(&idx).flatten())
+                    .collect(),
+            ),
+        };
+        let location = match self.env.get_file_and_location(&error.loc) {
+            Some((file, pos)) => format!(
+                "{}:{}:{}",
+                file,
+                pos.line.0.saturating_add(1),
+                pos.column.0.saturating_add(1)
+            ),
+            None => "<unknown>".to_string(),
+        };
+        Some(Counterexample {
+            module: fun_env.module_env.get_verified_module().self_id(),
+            function: fun_env.get_identifier(),
+            message: error.message.clone(),
+            location,
+            args,
+            signers,
+            globals,
+            expected,
+        })
+    }
+
+    /// Reconstructs a resource in global memory from its model value.
+    fn extract_resource(
+        &self,
+        model: &Model,
+        mem: QualifiedId<StructId>,
+        address: AccountAddress,
+        value: &ModelValue,
+    ) -> Option<CounterexampleResource> {
+        let struct_env = self.env.get_struct(mem);
+        let ty = Type::Struct(mem.module_id, mem.id, vec![]);
+        let struct_tag = StructTag {
+            address: *struct_env.module_env.self_address(),
+            module: struct_env.module_env.get_identifier(),
+            name: struct_env.get_identifier(),
+            type_params: vec![],
+        };
+        Some(CounterexampleResource {
+            address,
+            struct_tag,
+            value: value
+                .concrete_value(self, model, &ty)
+                .or_else(|| self.zero_value(&ty))?,
+        })
+    }
+
+    /// Returns the zero value of the type, which is used for inputs the model leaves
+    /// undetermined: their value does not influence the outcome of the verification condition.
+    fn zero_value(&self, ty: &Type) -> Option<CounterexampleValue> {
+        Some(match ty {
+            Type::Primitive(PrimitiveType::U8) => CounterexampleValue::U8(0),
+            Type::Primitive(PrimitiveType::U64) => CounterexampleValue::U64(0),
+            Type::Primitive(PrimitiveType::U128) => CounterexampleValue::U128(0),
+            Type::Primitive(PrimitiveType::Bool) => CounterexampleValue::Bool(false),
+            Type::Primitive(PrimitiveType::Address) => {
+                CounterexampleValue::Address(AccountAddress::ZERO)
+            }
+            Type::Primitive(PrimitiveType::Signer) => {
+                CounterexampleValue::Signer(AccountAddress::ZERO)
+            }
+            Type::Vector(_) => CounterexampleValue::Vector(vec![]),
+            Type::Struct(module_id, struct_id, params) => {
+                let struct_env = self.env.get_module(*module_id).into_struct(*struct_id);
+                let fields = struct_env
+                    .get_fields()
+                    .map(|f| {
+                        let name = f.get_name().display(self.env.symbol_pool()).to_string();
+                        Some((name, self.zero_value(&f.get_type().instantiate(params))?))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                CounterexampleValue::Struct(fields)
+            }
+            Type::Reference(_, bt) => return self.zero_value(bt),
+            _ => return None,
+        })
+    }
+
     fn get_abbreviated_source(&self, node_id: NodeId) -> String {
         let loc = self.env.get_node_loc(node_id);
         let res = if let Ok(src) = self.env.get_source(&loc) {
@@ -495,6 +716,16 @@ impl<'env> BoogieWrapper<'env> {
                 let value = self.extract_value(value)?;
                 Ok(TraceEntry::Exp(node_id, value))
             }
+            "track_global_addr" => {
+                let mem = self.extract_struct(args)?;
+                let value = self.extract_value(value)?;
+                Ok(TraceEntry::GlobalAddress(mem, value))
+            }
+            "track_global" => {
+                let mem = self.extract_struct(args)?;
+                let value = self.extract_value(value)?;
+                Ok(TraceEntry::Global(mem, value))
+            }
             _ => Err(ModelParseError::new(&format!(
                 "unrecognized augmented trace entry `{}`",
                 name
@@ -539,6 +770,20 @@ impl<'env> BoogieWrapper<'env> {
         Err(ModelParseError("invalid function id".to_string()))
     }
 
+    fn extract_struct(&self, args: &str) -> Result<QualifiedId<StructId>, ModelParseError> {
+        let elems = args.split(',').collect_vec();
+        if elems.len() == 2 {
+            let module_idx = elems[0].parse::<usize>()?;
+            index_range_check(self.env.get_module_count())(module_idx)?;
+            let module_env = self.env.get_module(ModuleId::new(module_idx));
+            let struct_idx = elems[1].parse::<u16>()?;
+            index_range_check(module_env.get_struct_count())(struct_idx as usize)?;
+            let struct_id = module_env.get_struct_id(StructDefinitionIndex::new(struct_idx));
+            return Ok(module_env.get_id().qualified(struct_id));
+        }
+        Err(ModelParseError("invalid struct id".to_string()))
+    }
+
     fn extract_fun_and_index(
         &self,
         args: &str,
@@ -869,6 +1114,18 @@ impl ModelValue {
         }
     }
 
+    /// Extract an address from a literal.
+    fn extract_address(&self) -> Option<AccountAddress> {
+        let (sign, bytes) =
+            BigInt::parse_bytes(&self.extract_literal()?.clone().into_bytes(), 10)?.to_bytes_be();
+        if sign == Sign::Minus || bytes.len() > AccountAddress::LENGTH {
+            return None;
+        }
+        let mut addr = [0u8; AccountAddress::LENGTH];
+        addr[AccountAddress::LENGTH - bytes.len()..].copy_from_slice(&bytes);
+        Some(AccountAddress::new(addr))
+    }
+
     /// Extract a literal.
     fn extract_literal(&self) -> Option<&String> {
         if let ModelValue::Literal(s) = self {
@@ -943,6 +1200,75 @@ impl ModelValue {
         }
     }
 
+    /// Extracts the concrete value of the given type, or `None` if the model leaves it
+    /// undetermined. Undetermined elements of vectors and structs are set to the zero value of
+    /// their type.
+    pub fn concrete_value(
+        &self,
+        wrapper: &BoogieWrapper,
+        model: &Model,
+        ty: &Type,
+    ) -> Option<CounterexampleValue> {
+        if self.extract_list("Error").is_some() {
+            // This is an undefined value
+            return None;
+        }
+        let element = |values: &ModelValueVector, idx: usize, ty: &Type| {
+            values
+                .values
+                .get(&idx)
+                .unwrap_or(&values.default)
+                .extract_box()
+                .concrete_value(wrapper, model, ty)
+                .or_else(|| wrapper.zero_value(ty))
+        };
+        match ty {
+            Type::Primitive(PrimitiveType::U8) => Some(CounterexampleValue::U8(
+                self.extract_literal()?.parse::<u8>().ok()?,
+            )),
+            Type::Primitive(PrimitiveType::U64) => Some(CounterexampleValue::U64(
+                self.extract_literal()?.parse::<u64>().ok()?,
+            )),
+            Type::Primitive(PrimitiveType::U128) => Some(CounterexampleValue::U128(
+                self.extract_literal()?.parse::<u128>().ok()?,
+            )),
+            Type::Primitive(PrimitiveType::Bool) => Some(CounterexampleValue::Bool(
+                self.extract_literal()?.parse::<bool>().ok()?,
+            )),
+            Type::Primitive(PrimitiveType::Address) => {
+                Some(CounterexampleValue::Address(self.extract_address()?))
+            }
+            Type::Primitive(PrimitiveType::Signer) => {
+                Some(CounterexampleValue::Signer(self.extract_address()?))
+            }
+            Type::Vector(param) => {
+                let values = self.extract_vector(model)?;
+                let elems = (0..values.size)
+                    .map(|idx| element(&values, idx, param))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(CounterexampleValue::Vector(elems))
+            }
+            Type::Struct(module_id, struct_id, params) => {
+                let struct_env = wrapper.env.get_module(*module_id).into_struct(*struct_id);
+                let values = self.extract_vector(model)?;
+                let fields = struct_env
+                    .get_fields()
+                    .enumerate()
+                    .map(|(idx, f)| {
+                        let name = f.get_name().display(wrapper.env.symbol_pool()).to_string();
+                        Some((
+                            name,
+                            element(&values, idx, &f.get_type().instantiate(params))?,
+                        ))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(CounterexampleValue::Struct(fields))
+            }
+            Type::Reference(_, bt) => self.concrete_value(wrapper, model, &*bt),
+            _ => None,
+        }
+    }
+
     /// Pretty prints the body of a struct or vector, enclosed in braces.
     pub fn pretty_vec_or_struct_body(entries: Vec<PrettyDoc>) -> PrettyDoc {
         PrettyDoc::text("{")
@@ -3,7 +3,7 @@
 
 //! This module translates the bytecode of a module to Boogie code.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use itertools::Itertools;
 #[allow(unused_imports)]
@@ -20,7 +20,7 @@ use bytecode::{
 use move_model::{
     code_writer::CodeWriter,
     emit, emitln,
-    model::{GlobalEnv, ModuleEnv, StructEnv, TypeParameter},
+    model::{FunId, GlobalEnv, ModuleEnv, QualifiedId, StructEnv, StructId, TypeParameter},
     pragmas::{ADDITION_OVERFLOW_UNCHECKED_PRAGMA, SEED_PRAGMA, TIMEOUT_PRAGMA},
     ty::{PrimitiveType, Type},
 };
@@ -46,6 +46,7 @@ use move_model::{
     ast::TempIndex,
     model::{Loc, NodeId},
 };
+use num::BigUint;
 
 pub struct BoogieTranslator<'env> {
     env: &'env GlobalEnv,
@@ -392,6 +393,9 @@ impl<'env> ModuleTranslator<'env> {
         // Initial assumptions
         if variant.is_verified() {
             self.translate_verify_entry_assumptions(fun_target);
+            if self.options.counterexample_dir.is_some() {
+                self.track_globals(fun_target);
+            }
         }
 
         // Generate bytecode
@@ -480,6 +484,119 @@ impl<'env> ModuleTranslator<'env> {
         }
     }
 
+    /// Generates tracking of the resources the function may access at the addresses known on
+    /// entry, so the initial global state can be made part of counterexamples.
+    fn track_globals(&self, fun_target: &FunctionTarget<'_>) {
+        let env = fun_target.global_env();
+        let (memory, constants) = self.collect_accessed_globals(fun_target);
+        let addresses = (0..fun_target.get_parameter_count())
+            .filter_map(|i| {
+                let ty = fun_target.get_local_type(i);
+                let base_ty = ty.skip_reference();
+                if !matches!(
+                    base_ty,
+                    Type::Primitive(PrimitiveType::Address)
+                        | Type::Primitive(PrimitiveType::Signer)
+                ) {
+                    None
+                } else if ty.is_reference() {
+                    Some(format!(
+                        "$Unbox{}($Dereference($t{}))",
+                        boogie_type_suffix(base_ty),
+                        i
+                    ))
+                } else {
+                    Some(format!("$t{}", i))
+                }
+            })
+            .chain(constants.iter().map(|addr| addr.to_string()))
+            .collect_vec();
+        if memory.is_empty() || addresses.is_empty() {
+            return;
+        }
+        emitln!(
+            self.writer,
+            "\n// global memory tracking for counterexamples"
+        );
+        let type_args = boogie_type_value_array(env, &[]);
+        for mem in memory {
+            let struct_env = env.get_struct(mem);
+            let memory_name = boogie_resource_memory_name(env, mem, &None);
+            let track_args = format!("{},{}", mem.module_id.to_usize(), struct_env.get_def_idx());
+            for addr in &addresses {
+                emitln!(self.writer, "$$t_addr := {};", addr);
+                emitln!(
+                    self.writer,
+                    "if ($ResourceExists({}, {}, $$t_addr)) {{",
+                    memory_name,
+                    type_args
+                );
+                self.writer.indent();
+                emitln!(
+                    self.writer,
+                    "$$t_vec := $ResourceValue({}, {}, $$t_addr);",
+                    memory_name,
+                    type_args
+                );
+                emitln!(
+                    self.writer,
+                    "assume {{:print \"$track_global_addr({}):\", $$t_addr}} $$t_addr == $$t_addr;",
+                    track_args
+                );
+                emitln!(
+                    self.writer,
+                    "assume {{:print \"$track_global({}):\", $$t_vec}} $$t_vec == $$t_vec;",
+                    track_args
+                );
+                self.writer.unindent();
+                emitln!(self.writer, "}");
+            }
+        }
+    }
+
+    /// Collects the memory of non-generic resources the function and its callees may access,
+    /// together with the address constants appearing in their code.
+    fn collect_accessed_globals(
+        &self,
+        fun_target: &FunctionTarget<'_>,
+    ) -> (BTreeSet<QualifiedId<StructId>>, BTreeSet<BigUint>) {
+        use Bytecode::*;
+        let mut memory = BTreeSet::new();
+        let mut addresses = BTreeSet::new();
+        let mut visited: BTreeSet<QualifiedId<FunId>> = BTreeSet::new();
+        let mut todo = vec![fun_target.func_env.get_qualified_id()];
+        while let Some(fun_id) = todo.pop() {
+            if !visited.insert(fun_id) {
+                continue;
+            }
+            let data = match self.targets.get_data(&fun_id, &FunctionVariant::Baseline) {
+                Some(data) => data,
+                None => continue,
+            };
+            for bc in &data.code {
+                match bc {
+                    Load(_, _, Constant::Address(addr)) => {
+                        addresses.insert(addr.clone());
+                    }
+                    Call(_, _, Operation::Function(mid, fid, _), ..) => {
+                        todo.push(mid.qualified(*fid))
+                    }
+                    Call(_, _, Operation::MoveTo(mid, sid, inst), ..)
+                    | Call(_, _, Operation::MoveFrom(mid, sid, inst), ..)
+                    | Call(_, _, Operation::Exists(mid, sid, inst), ..)
+                    | Call(_, _, Operation::BorrowGlobal(mid, sid, inst), ..)
+                    | Call(_, _, Operation::GetGlobal(mid, sid, inst), ..)
+                        if inst.is_empty() =>
+                    {
+                        memory.insert(mid.qualified(*sid));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        (memory, addresses)
+    }
+
     /// Translates one bytecode instruction.
     fn translate_bytecode(
         &'env self,
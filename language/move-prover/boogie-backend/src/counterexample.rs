@@ -0,0 +1,91 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Counterexamples of failed verification conditions, represented as concrete inputs of the
+//! verified function. A counterexample can be replayed in the Move VM to check whether the
+//! execution it describes is real or an artifact of the specification (e.g. a missing invariant).
+
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag},
+    value::{MoveStruct, MoveValue},
+};
+use serde::{Deserialize, Serialize};
+
+/// A concrete Move value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterexampleValue {
+    U8(u8),
+    U64(u64),
+    U128(u128),
+    Bool(bool),
+    Address(AccountAddress),
+    Signer(AccountAddress),
+    Vector(Vec<CounterexampleValue>),
+    /// The fields of a struct in declaration order, with their names.
+    Struct(Vec<(String, CounterexampleValue)>),
+}
+
+impl CounterexampleValue {
+    /// Converts this value into the value representation of the VM.
+    pub fn to_move_value(&self) -> MoveValue {
+        use CounterexampleValue::*;
+        match self {
+            U8(v) => MoveValue::U8(*v),
+            U64(v) => MoveValue::U64(*v),
+            U128(v) => MoveValue::U128(*v),
+            Bool(v) => MoveValue::Bool(*v),
+            Address(a) => MoveValue::Address(*a),
+            Signer(a) => MoveValue::Signer(*a),
+            Vector(elems) => MoveValue::Vector(elems.iter().map(|e| e.to_move_value()).collect()),
+            Struct(fields) => MoveValue::Struct(MoveStruct::new(
+                fields.iter().map(|(_, v)| v.to_move_value()).collect(),
+            )),
+        }
+    }
+}
+
+/// A resource stored in global memory when the verified function is entered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterexampleResource {
+    pub address: AccountAddress,
+    pub struct_tag: StructTag,
+    pub value: CounterexampleValue,
+}
+
+/// The outcome of the execution described by a counterexample.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpectedOutcome {
+    /// The function aborts with the given code.
+    Abort(u64),
+    /// The function aborts because of an execution failure, like an arithmetic overflow or a
+    /// missing resource.
+    ExecutionFailure,
+    /// The function returns. Return values the model leaves undetermined are `None`.
+    Return(Vec<Option<CounterexampleValue>>),
+}
+
+/// A counterexample of a verification condition of a function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counterexample {
+    /// The module of the verified function.
+    pub module: ModuleId,
+    /// The name of the verified function.
+    pub function: Identifier,
+    /// The message of the prover error.
+    pub message: String,
+    /// The location of the prover error, as `file:line:column`.
+    pub location: String,
+    /// The arguments the function is called with. Arguments the model leaves undetermined are
+    /// set to the zero value of their type.
+    pub args: Vec<CounterexampleValue>,
+    /// The addresses of the signer arguments, in order.
+    pub signers: Vec<AccountAddress>,
+    /// The resources the function and its callees can access at the addresses passed as
+    /// arguments or appearing as constants in the code. Resources of generic structs are not
+    /// included.
+    pub globals: Vec<CounterexampleResource>,
+    /// What happens when the function is executed with the above inputs, according to the model.
+    pub expected: ExpectedOutcome,
+}
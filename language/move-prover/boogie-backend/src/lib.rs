@@ -26,6 +26,7 @@ pub const MULTISET_ARRAY_THEORY: &[u8] = include_bytes!("prelude/multiset-array-
 mod boogie_helpers;
 pub mod boogie_wrapper;
 pub mod bytecode_translator;
+pub mod counterexample;
 pub mod options;
 pub mod prelude_template_helpers;
 pub mod prover_task_runner;
@@ -98,6 +98,9 @@ pub struct BoogieOptions {
     pub vector_theory: VectorTheory,
     /// Whether to generate a z3 trace file and where to put it.
     pub z3_trace_file: Option<String>,
+    /// Directory where counterexamples of failed verification conditions are written to, as
+    /// concrete inputs which can be replayed in the Move VM.
+    pub counterexample_dir: Option<String>,
 }
 
 impl Default for BoogieOptions {
@@ -131,6 +134,7 @@ impl Default for BoogieOptions {
             sequential_task: false,
             vector_theory: VectorTheory::BoogieArray,
             z3_trace_file: None,
+            counterexample_dir: None,
         }
     }
 }
@@ -0,0 +1,78 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::Result;
+use boogie_backend::counterexample::Counterexample;
+use move_prover_replay::{Package, Verdict};
+use std::fs;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "move-prover-replay",
+    about = "Replays counterexamples of the Move prover in the Move VM"
+)]
+struct Args {
+    /// Counterexample files, as written by `move-prover --counterexamples`
+    #[structopt(long = "counterexample", short = "c", required = true)]
+    counterexamples: Vec<String>,
+    /// Move source files or directories the counterexamples were produced for
+    #[structopt(required = true)]
+    sources: Vec<String>,
+    /// Move source files or directories the sources depend on
+    #[structopt(long = "dependency", short = "d")]
+    dependencies: Vec<String>,
+    /// Print the instructions executed during replay
+    #[structopt(long = "trace", short = "t")]
+    trace: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::from_args();
+    let package = Package::compile(&args.sources, &args.dependencies)?;
+
+    let mut spurious = 0;
+    for file in &args.counterexamples {
+        let counterexample: Counterexample = serde_json::from_str(&fs::read_to_string(file)?)?;
+        println!(
+            "{}: {}::{} ({} at {})",
+            file,
+            counterexample.module,
+            counterexample.function,
+            counterexample.message,
+            counterexample.location
+        );
+        let replay = package.replay(&counterexample, args.trace)?;
+        for step in &replay.trace {
+            println!(
+                "    {:indent$}{}::{} pc {}{}",
+                "",
+                step.frame
+                    .module_id
+                    .as_ref()
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| "<script>".to_string()),
+                step.frame.function_name,
+                step.frame.pc,
+                step.location
+                    .as_ref()
+                    .map(|loc| format!(" at {}", loc))
+                    .unwrap_or_default(),
+                indent = 2 * step.depth.saturating_sub(1)
+            );
+        }
+        match replay.verdict {
+            Verdict::Confirmed => println!("  confirmed: the VM {}", replay.outcome),
+            Verdict::Spurious(reason) => {
+                spurious += 1;
+                println!("  spurious: {}", reason)
+            }
+        }
+    }
+    if spurious > 0 {
+        std::process::exit(1)
+    }
+    Ok(())
+}
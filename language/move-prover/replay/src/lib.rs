@@ -0,0 +1,253 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Replays counterexamples of the Move prover, as written by `move-prover --counterexamples`, in
+//! the Move VM. A replay confirms a counterexample if the VM reproduces the outcome the prover's
+//! model predicts, and flags it as spurious otherwise. Spurious counterexamples usually point to
+//! a specification which is too weak, e.g. a missing invariant about the initial state.
+//!
+//! Note that the VM does not evaluate specifications: for a counterexample of a function which
+//! returns, the replay checks that the function returns the values of the model, not that those
+//! values violate the post-condition.
+
+use anyhow::{bail, format_err, Result};
+use boogie_backend::counterexample::{Counterexample, ExpectedOutcome};
+use bytecode_source_map::source_map::SourceMap;
+use move_core_types::{
+    language_storage::ModuleId,
+    value::MoveValue,
+    vm_status::{StatusCode, StatusType},
+};
+use move_ir_types::location::Loc;
+use move_lang::{
+    compiled_unit::CompiledUnit, errors::FilesSourceText, find_move_filenames, shared::Flags,
+};
+use move_vm_runtime::{
+    debug_hook::{self, DebugHook, ExecutionState, FrameInfo},
+    logging::NoContextLog,
+    move_vm::MoveVM,
+};
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas_schedule::GasStatus;
+use std::{cell::RefCell, collections::BTreeMap, fmt, rc::Rc};
+use vm::{access::ModuleAccess, errors::VMError, file_format::CompiledModule};
+
+/// The compiled Move code counterexamples are replayed against.
+pub struct Package {
+    files: FilesSourceText,
+    modules: BTreeMap<ModuleId, (CompiledModule, SourceMap<Loc>)>,
+}
+
+/// What happened when the function of a counterexample was executed in the VM.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The function aborted with the given code.
+    Abort(u64),
+    /// Execution failed with the given status, e.g. because of an arithmetic error.
+    ExecutionFailure(StatusCode),
+    /// The function returned the given BCS serialized values.
+    Return(Vec<Vec<u8>>),
+}
+
+/// Whether the VM agrees with the prover's model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The VM reproduces the outcome of the counterexample.
+    Confirmed,
+    /// The VM does not reproduce the outcome of the counterexample, for the given reason.
+    Spurious(String),
+}
+
+/// An instruction executed during replay.
+#[derive(Clone, Debug)]
+pub struct TraceStep {
+    /// Number of frames on the call stack.
+    pub depth: usize,
+    /// The frame executing the instruction.
+    pub frame: FrameInfo,
+    /// The source location of the instruction as `file:line`, if known.
+    pub location: Option<String>,
+}
+
+/// The result of replaying a counterexample.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub outcome: Outcome,
+    pub verdict: Verdict,
+    /// The executed instructions, empty if tracing was not requested.
+    pub trace: Vec<TraceStep>,
+}
+
+/// Records the frames the interpreter executes.
+struct Tracer(Rc<RefCell<Vec<(usize, FrameInfo)>>>);
+
+impl DebugHook for Tracer {
+    fn on_instruction(&mut self, state: &ExecutionState) {
+        self.0
+            .borrow_mut()
+            .push((state.depth(), state.current_frame()));
+    }
+}
+
+impl Package {
+    /// Compiles the Move sources in `targets` and `deps`, which may be files or directories. All
+    /// compiled modules are published before replay, so `deps` must contain all modules the
+    /// targets depend on.
+    pub fn compile(targets: &[String], deps: &[String]) -> Result<Self> {
+        let mut sources = find_move_filenames(targets, true)?;
+        sources.extend(find_move_filenames(deps, true)?);
+        sources.sort();
+        sources.dedup();
+        let (files, units) =
+            move_lang::move_compile_and_report(&sources, &[], None, false, Flags::empty())?;
+        let modules = units
+            .into_iter()
+            .filter_map(|unit| match unit {
+                CompiledUnit::Module {
+                    module, source_map, ..
+                } => Some((module.self_id(), (module, source_map))),
+                CompiledUnit::Script { .. } => None,
+            })
+            .collect();
+        Ok(Self { files, modules })
+    }
+
+    /// Executes the function of the counterexample on its inputs, recording the executed
+    /// instructions if `trace` is set.
+    pub fn replay(&self, counterexample: &Counterexample, trace: bool) -> Result<Replay> {
+        if !self.modules.contains_key(&counterexample.module) {
+            bail!("module {} not found", counterexample.module);
+        }
+        let mut storage = InMemoryStorage::new();
+        for (id, (module, _)) in &self.modules {
+            let mut bytes = vec![];
+            module.serialize(&mut bytes)?;
+            storage.publish_or_overwrite_module(id.clone(), bytes);
+        }
+        for resource in &counterexample.globals {
+            storage.publish_or_overwrite_resource(
+                resource.address,
+                resource.struct_tag.clone(),
+                serialize(&resource.value.to_move_value())?,
+            );
+        }
+        let args = counterexample
+            .args
+            .iter()
+            .map(|arg| serialize(&arg.to_move_value()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let vm = MoveVM::new();
+        let mut session = vm.new_session(&storage);
+        let steps = Rc::new(RefCell::new(vec![]));
+        if trace {
+            debug_hook::install(Box::new(Tracer(steps.clone())));
+        }
+        let result = session.execute_function(
+            &counterexample.module,
+            counterexample.function.as_ident_str(),
+            vec![],
+            args,
+            &mut GasStatus::new_unmetered(),
+            &NoContextLog::new(),
+        );
+        if trace {
+            debug_hook::uninstall();
+        }
+
+        let outcome = match result {
+            Ok(values) => Outcome::Return(values),
+            Err(err) => failure_outcome(err)?,
+        };
+        let verdict = check_outcome(&counterexample.expected, &outcome)?;
+        let trace = steps
+            .borrow()
+            .iter()
+            .map(|(depth, frame)| TraceStep {
+                depth: *depth,
+                location: self.location(frame),
+                frame: frame.clone(),
+            })
+            .collect();
+        Ok(Replay {
+            outcome,
+            verdict,
+            trace,
+        })
+    }
+
+    /// Renders the source location of the instruction executed by `frame`.
+    fn location(&self, frame: &FrameInfo) -> Option<String> {
+        let (_, source_map) = self.modules.get(frame.module_id.as_ref()?)?;
+        let loc = source_map
+            .get_code_location(frame.function_index, frame.pc)
+            .ok()?;
+        let source = self.files.get(loc.file())?;
+        let offset = loc.span().start().to_usize();
+        let line = source.get(..offset)?.matches('\n').count() + 1;
+        Some(format!("{}:{}", loc.file(), line))
+    }
+}
+
+fn serialize(value: &MoveValue) -> Result<Vec<u8>> {
+    value
+        .simple_serialize()
+        .ok_or_else(|| format_err!("cannot serialize value {:?}", value))
+}
+
+/// Classifies an error of the VM. Errors which are not caused by the execution itself, e.g. an
+/// argument which cannot be passed to the function, make replay impossible.
+fn failure_outcome(err: VMError) -> Result<Outcome> {
+    match (err.major_status(), err.status_type()) {
+        (StatusCode::ABORTED, _) => Ok(Outcome::Abort(err.sub_status().unwrap_or_default())),
+        (status, StatusType::Execution) => Ok(Outcome::ExecutionFailure(status)),
+        _ => bail!("cannot replay counterexample: {:?}", err),
+    }
+}
+
+fn check_outcome(expected: &ExpectedOutcome, actual: &Outcome) -> Result<Verdict> {
+    Ok(match (expected, actual) {
+        (ExpectedOutcome::Abort(expected_code), Outcome::Abort(code)) if expected_code == code => {
+            Verdict::Confirmed
+        }
+        (ExpectedOutcome::ExecutionFailure, Outcome::ExecutionFailure(_)) => Verdict::Confirmed,
+        (ExpectedOutcome::Return(expected_values), Outcome::Return(values)) => {
+            for (idx, (expected_value, value)) in expected_values.iter().zip(values).enumerate() {
+                if let Some(expected_value) = expected_value {
+                    if &serialize(&expected_value.to_move_value())? != value {
+                        return Ok(Verdict::Spurious(format!(
+                            "expected return value #{} to be {:?}, but the VM returned {:?}",
+                            idx, expected_value, value
+                        )));
+                    }
+                }
+            }
+            Verdict::Confirmed
+        }
+        (expected, actual) => Verdict::Spurious(format!(
+            "expected {}, but the VM {}",
+            describe_expected(expected),
+            actual
+        )),
+    })
+}
+
+fn describe_expected(expected: &ExpectedOutcome) -> String {
+    match expected {
+        ExpectedOutcome::Abort(code) => format!("an abort with code 0x{:X}", code),
+        ExpectedOutcome::ExecutionFailure => "an execution failure".to_string(),
+        ExpectedOutcome::Return(_) => "a return".to_string(),
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Abort(code) => write!(f, "aborted with code 0x{:X}", code),
+            Outcome::ExecutionFailure(status) => write!(f, "failed with {:?}", status),
+            Outcome::Return(values) => write!(f, "returned {} value(s)", values.len()),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use boogie_backend::counterexample::{
+    Counterexample, CounterexampleResource, CounterexampleValue, ExpectedOutcome,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag},
+    vm_status::StatusCode,
+};
+use move_prover_replay::{Outcome, Package, Verdict};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const COUNTER: &str = "module 0x2::Counter {
+    struct Counter has key { value: u64 }
+
+    public fun bump(addr: address): u64 acquires Counter {
+        let c = borrow_global_mut<Counter>(addr);
+        if (c.value == 10) abort 7;
+        c.value = c.value + 1;
+        c.value
+    }
+}
+";
+
+fn compile() -> (NamedTempFile, Package) {
+    let mut file = tempfile::Builder::new().suffix(".move").tempfile().unwrap();
+    file.write_all(COUNTER.as_bytes()).unwrap();
+    let package = Package::compile(&[file.path().to_str().unwrap().to_string()], &[]).unwrap();
+    (file, package)
+}
+
+fn counterexample(counter: Option<u64>, expected: ExpectedOutcome) -> Counterexample {
+    let module = ModuleId::new(
+        AccountAddress::from_hex_literal("0x2").unwrap(),
+        Identifier::new("Counter").unwrap(),
+    );
+    let addr = AccountAddress::from_hex_literal("0x5").unwrap();
+    let globals = counter
+        .map(|value| CounterexampleResource {
+            address: addr,
+            struct_tag: StructTag {
+                address: *module.address(),
+                module: module.name().to_owned(),
+                name: Identifier::new("Counter").unwrap(),
+                type_params: vec![],
+            },
+            value: CounterexampleValue::Struct(vec![(
+                "value".to_string(),
+                CounterexampleValue::U64(value),
+            )]),
+        })
+        .into_iter()
+        .collect();
+    Counterexample {
+        module,
+        function: Identifier::new("bump").unwrap(),
+        message: "post-condition does not hold".to_string(),
+        location: "counter.move:4:5".to_string(),
+        args: vec![CounterexampleValue::Address(addr)],
+        signers: vec![],
+        globals,
+        expected,
+    }
+}
+
+#[test]
+fn abort_confirmed_with_trace() {
+    let (file, package) = compile();
+    let replay = package
+        .replay(&counterexample(Some(10), ExpectedOutcome::Abort(7)), true)
+        .unwrap();
+    assert_eq!(replay.outcome, Outcome::Abort(7));
+    assert_eq!(replay.verdict, Verdict::Confirmed);
+    assert!(!replay.trace.is_empty());
+    assert!(replay
+        .trace
+        .iter()
+        .all(|step| step.frame.function_name == "bump"));
+    let location = replay.trace[0].location.as_ref().unwrap();
+    assert!(location.starts_with(file.path().to_str().unwrap()));
+}
+
+#[test]
+fn abort_spurious() {
+    let (_file, package) = compile();
+    let replay = package
+        .replay(&counterexample(Some(3), ExpectedOutcome::Abort(7)), false)
+        .unwrap();
+    assert!(matches!(replay.outcome, Outcome::Return(_)));
+    assert!(matches!(replay.verdict, Verdict::Spurious(_)));
+    assert!(replay.trace.is_empty());
+}
+
+#[test]
+fn return_values() {
+    let (_file, package) = compile();
+    let expected = |value| ExpectedOutcome::Return(vec![Some(CounterexampleValue::U64(value))]);
+    let replay = package
+        .replay(&counterexample(Some(3), expected(4)), false)
+        .unwrap();
+    assert_eq!(replay.verdict, Verdict::Confirmed);
+    let replay = package
+        .replay(&counterexample(Some(3), expected(5)), false)
+        .unwrap();
+    assert!(matches!(replay.verdict, Verdict::Spurious(_)));
+}
+
+#[test]
+fn missing_resource() {
+    let (_file, package) = compile();
+    let replay = package
+        .replay(
+            &counterexample(None, ExpectedOutcome::ExecutionFailure),
+            false,
+        )
+        .unwrap();
+    assert_eq!(
+        replay.outcome,
+        Outcome::ExecutionFailure(StatusCode::MISSING_DATA)
+    );
+    assert_eq!(replay.verdict, Verdict::Confirmed);
+}
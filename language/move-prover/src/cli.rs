@@ -409,6 +409,14 @@ impl Options {
                     and generate a z3 trace file for analysis. The file will be stored \
                     at FUNCTION_NAME.z3log.")
             )
+            .arg(
+                Arg::with_name("counterexamples")
+                    .long("counterexamples")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("writes counterexamples of prover errors as concrete inputs into DIR, \
+                    which can be replayed in the Move VM with `move-prover-replay`")
+            )
             .after_help("More options available via `--config file` or `--config-str str`. \
             Use `--print-config` to see format and current values. \
             See `move-prover/src/cli.rs::Option` for documentation.");
@@ -588,6 +596,11 @@ impl Options {
             options.backend.z3_trace_file = Some(format!("{}.z3log", fun_name));
         }
 
+        if matches.is_present("counterexamples") {
+            options.backend.counterexample_dir =
+                Some(matches.value_of("counterexamples").unwrap().to_string());
+        }
+
         options.backend.derive_options();
 
         if matches.is_present("print-config") {
@@ -0,0 +1,108 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::compiler::{as_module, compile_units};
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasCost, GasUnits},
+    identifier::Identifier,
+    language_storage::ModuleId,
+};
+use move_vm_runtime::{gas_profiler, logging::NoContextLog, move_vm::MoveVM};
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas_schedule::{zero_cost_schedule, GasStatus};
+
+const TEST_ADDR: AccountAddress = AccountAddress::new([42; AccountAddress::LENGTH]);
+
+#[test]
+fn gas_is_attributed_to_functions() {
+    let code = format!(
+        r#"
+        module 0x{}::M {{
+            fun foo() {{
+                bar();
+                bar();
+            }}
+
+            fun bar(): u64 {{
+                1 + 2
+            }}
+        }}
+    "#,
+        TEST_ADDR
+    );
+
+    let mut units = compile_units(&code).unwrap();
+    let m = as_module(units.pop().unwrap());
+    let mut blob = vec![];
+    m.serialize(&mut blob).unwrap();
+
+    let mut storage = InMemoryStorage::new();
+    let module_id = ModuleId::new(TEST_ADDR, Identifier::new("M").unwrap());
+    storage.publish_or_overwrite_module(module_id.clone(), blob);
+
+    // Charge the same, non-zero amount for every instruction.
+    let mut cost_table = zero_cost_schedule();
+    for cost in cost_table.instruction_table.iter_mut() {
+        *cost = GasCost::new(1, 1);
+    }
+    let mut gas_status = GasStatus::new(&cost_table, GasUnits::new(1_000_000));
+    let gas_before = gas_status.remaining_internal_gas().get();
+
+    let vm = MoveVM::new();
+    let mut sess = vm.new_session(&storage);
+    let context = NoContextLog::new();
+
+    gas_profiler::start();
+    sess.execute_function(
+        &module_id,
+        &Identifier::new("foo").unwrap(),
+        vec![],
+        vec![],
+        &mut gas_status,
+        &context,
+    )
+    .unwrap();
+    let profile = gas_profiler::finish().unwrap();
+    assert!(!gas_profiler::is_active());
+
+    let gas_used = gas_before - gas_status.remaining_internal_gas().get();
+    assert!(gas_used > 0);
+    assert_eq!(profile.total_gas(), gas_used);
+
+    let foo_name = format!("0x{}::M::foo", TEST_ADDR);
+    let bar_name = format!("0x{}::M::bar", TEST_ADDR);
+    let functions = profile.functions();
+    assert_eq!(functions.len(), 2);
+
+    let (name, foo) = &functions[0];
+    assert_eq!(*name, foo_name);
+    assert_eq!(foo.calls, 1);
+    assert_eq!(foo.total_gas, gas_used);
+
+    let (name, bar) = &functions[1];
+    assert_eq!(*name, bar_name);
+    assert_eq!(bar.calls, 2);
+    assert_eq!(bar.self_gas, bar.total_gas);
+    assert_eq!(foo.self_gas + bar.self_gas, gas_used);
+
+    let nested_stack = format!("{};{}", foo_name, bar_name);
+    assert_eq!(profile.folded_stacks()[&nested_stack], bar.self_gas);
+
+    let instruction_gas: u64 = profile
+        .instructions()
+        .into_iter()
+        .map(|(_, _, instr)| instr.gas)
+        .sum();
+    assert_eq!(instruction_gas, gas_used);
+
+    let mut folded = vec![];
+    profile.write_folded_stacks(&mut folded).unwrap();
+    assert_eq!(String::from_utf8(folded).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn nothing_is_recorded_without_profiling() {
+    assert!(!gas_profiler::is_active());
+    assert!(gas_profiler::finish().is_none());
+}
@@ -4,6 +4,7 @@
 mod bad_entry_point_tests;
 mod bad_storage_tests;
 mod function_arg_tests;
+mod gas_profiler_tests;
 mod loader_tests;
 mod mutated_accounts_tests;
 mod return_value_tests;
@@ -0,0 +1,179 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hook to observe and suspend the interpreter, used to build debuggers on top of the VM.
+//!
+//! A `DebugHook` installed on a thread is invoked before every instruction the interpreter
+//! executes on that thread. The hook receives an `ExecutionState` that describes the call stack
+//! and can render the locals of each frame. Execution is suspended until the hook returns, which
+//! is how debuggers implement breakpoints and stepping.
+
+use crate::{interpreter::Frame, loader::Loader};
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use move_vm_types::{
+    loaded_data::runtime_types::Type,
+    values::{self, Reference, Value},
+};
+use std::cell::RefCell;
+use vm::file_format::{CodeOffset, FunctionDefinitionIndex, SignatureToken};
+
+thread_local! {
+    static ACTIVE_HOOK: RefCell<Option<Box<dyn DebugHook>>> = RefCell::new(None);
+}
+
+/// Observes the execution of the interpreter one instruction at a time.
+pub trait DebugHook {
+    /// Called before the instruction at the current `pc` of the innermost frame executes.
+    fn on_instruction(&mut self, state: &ExecutionState);
+}
+
+/// Install `hook` for all Move execution on the current thread, replacing any installed hook.
+pub fn install(hook: Box<dyn DebugHook>) {
+    ACTIVE_HOOK.with(|active| *active.borrow_mut() = Some(hook));
+}
+
+/// Remove the hook installed on the current thread, if any, and return it.
+pub fn uninstall() -> Option<Box<dyn DebugHook>> {
+    take_active()
+}
+
+pub(crate) fn take_active() -> Option<Box<dyn DebugHook>> {
+    ACTIVE_HOOK.with(|active| active.borrow_mut().take())
+}
+
+pub(crate) fn restore_active(hook: Box<dyn DebugHook>) {
+    ACTIVE_HOOK.with(|active| *active.borrow_mut() = Some(hook));
+}
+
+/// Description of a frame on the call stack.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrameInfo {
+    /// The module defining the function, or `None` for a script.
+    pub module_id: Option<ModuleId>,
+    /// Name of the function.
+    pub function_name: String,
+    /// Index of the function definition in its module, always 0 for a script.
+    pub function_index: FunctionDefinitionIndex,
+    /// Offset of the instruction being executed, or of the call for frames that are not the
+    /// innermost.
+    pub pc: CodeOffset,
+}
+
+/// Content of a local variable of a frame.
+#[derive(Clone, Debug)]
+pub struct LocalInfo {
+    /// Index of the local in the frame. Parameters come first.
+    pub index: usize,
+    /// Type of the value, or of the referenced value for references. `None` if it could not be
+    /// resolved.
+    pub type_tag: Option<TypeTag>,
+    /// Whether the local holds a reference.
+    pub is_reference: bool,
+    /// BCS serialization of the value, or of the referenced value for references, according to
+    /// `type_tag`.
+    pub bytes: Option<Vec<u8>>,
+    /// Untyped rendering of the value, `None` if the local is not set or has been moved.
+    pub debug_string: Option<String>,
+}
+
+/// Read only view on the interpreter given to a `DebugHook`.
+pub struct ExecutionState<'a> {
+    loader: &'a Loader,
+    callers: &'a [Frame],
+    current: &'a Frame,
+}
+
+impl<'a> ExecutionState<'a> {
+    pub(crate) fn new(loader: &'a Loader, callers: &'a [Frame], current: &'a Frame) -> Self {
+        Self {
+            loader,
+            callers,
+            current,
+        }
+    }
+
+    /// Number of frames on the call stack, including the innermost frame.
+    pub fn depth(&self) -> usize {
+        self.callers.len() + 1
+    }
+
+    /// Description of the innermost frame.
+    pub fn current_frame(&self) -> FrameInfo {
+        frame_info(self.current)
+    }
+
+    /// Description of all frames on the call stack, outermost first.
+    pub fn frames(&self) -> Vec<FrameInfo> {
+        self.callers
+            .iter()
+            .chain(std::iter::once(self.current))
+            .map(frame_info)
+            .collect()
+    }
+
+    /// Locals of the frame at `frame_idx`, as indexed in `frames`.
+    pub fn locals(&self, frame_idx: usize) -> Vec<LocalInfo> {
+        let frame = match self.callers.get(frame_idx) {
+            Some(frame) => frame,
+            None if frame_idx == self.callers.len() => self.current,
+            None => return vec![],
+        };
+        let resolver = frame.function().get_resolver(self.loader);
+        frame
+            .function()
+            .local_types()
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, tok)| {
+                let is_reference = matches!(
+                    tok,
+                    SignatureToken::Reference(_) | SignatureToken::MutableReference(_)
+                );
+                let ty = resolver
+                    .make_type(tok)
+                    .and_then(|ty| ty.subst(frame.ty_args()))
+                    .ok()
+                    .map(|ty| match ty {
+                        Type::Reference(inner) | Type::MutableReference(inner) => *inner,
+                        ty => ty,
+                    });
+                let value = frame.locals().copy_loc(index).ok();
+                let value = if is_reference {
+                    value
+                        .and_then(|v| v.value_as::<Reference>().ok())
+                        .and_then(|r| r.read_ref().ok())
+                } else {
+                    value
+                };
+                LocalInfo {
+                    index,
+                    type_tag: ty
+                        .as_ref()
+                        .and_then(|ty| self.loader.type_to_type_tag(ty).ok()),
+                    is_reference,
+                    bytes: self.serialize(value.as_ref(), ty.as_ref()),
+                    debug_string: value.as_ref().and_then(|v| {
+                        let mut s = String::new();
+                        values::debug::print_value(&mut s, v).ok().map(|_| s)
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    fn serialize(&self, value: Option<&Value>, ty: Option<&Type>) -> Option<Vec<u8>> {
+        let layout = self.loader.type_to_type_layout(ty?).ok()?;
+        value?.simple_serialize(&layout)
+    }
+}
+
+fn frame_info(frame: &Frame) -> FrameInfo {
+    let function = frame.function();
+    FrameInfo {
+        module_id: function.module_id().cloned(),
+        function_name: function.name().to_string(),
+        function_index: function.index(),
+        pc: frame.pc(),
+    }
+}
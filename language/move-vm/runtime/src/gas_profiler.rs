@@ -0,0 +1,308 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Opt-in attribution of gas to Move functions and instructions.
+//!
+//! Profiling is enabled per thread: after a call to `start`, every invocation of the interpreter
+//! on the current thread records how much gas was charged while executing each instruction and
+//! each function frame. Calling `finish` stops profiling and returns the accumulated
+//! `GasProfile`, which can be rendered as folded stacks (the input format of `flamegraph.pl` and
+//! `inferno`) or as a per-function table.
+//!
+//! All amounts are reported in internal gas units, i.e. before the gas unit scaling factor of the
+//! `GasSchedule` is applied. Executions with an unmetered `GasStatus` do not charge anything and
+//! therefore show up with zero gas.
+
+use move_core_types::gas_schedule::GasAlgebra;
+use move_vm_types::gas_schedule::GasStatus;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt, io,
+};
+use vm::file_format::Bytecode;
+
+thread_local! {
+    static ACTIVE_PROFILER: RefCell<Option<GasProfiler>> = RefCell::new(None);
+}
+
+/// Start profiling all Move execution on the current thread.
+///
+/// Any profile previously collected on this thread and not yet returned by `finish` is dropped.
+pub fn start() {
+    ACTIVE_PROFILER.with(|profiler| *profiler.borrow_mut() = Some(GasProfiler::new()));
+}
+
+/// Stop profiling on the current thread and return the collected profile, or `None` if profiling
+/// was not started.
+pub fn finish() -> Option<GasProfile> {
+    take_active().map(|profiler| profiler.profile)
+}
+
+/// Return `true` if gas profiling is enabled on the current thread.
+pub fn is_active() -> bool {
+    ACTIVE_PROFILER.with(|profiler| profiler.borrow().is_some())
+}
+
+pub(crate) fn take_active() -> Option<GasProfiler> {
+    ACTIVE_PROFILER.with(|profiler| profiler.borrow_mut().take())
+}
+
+pub(crate) fn restore_active(profiler: GasProfiler) {
+    ACTIVE_PROFILER.with(|active| *active.borrow_mut() = Some(profiler));
+}
+
+/// Gas attributed to a single function.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FunctionGas {
+    /// Number of times the function was called.
+    pub calls: u64,
+    /// Gas charged while the function was on top of the call stack.
+    pub self_gas: u64,
+    /// Gas charged while the function was anywhere on the call stack.
+    pub total_gas: u64,
+}
+
+/// Gas attributed to a single instruction of a function.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InstructionGas {
+    /// The instruction as it appears in the bytecode.
+    pub instruction: String,
+    /// Number of times the instruction was executed.
+    pub count: u64,
+    /// Gas charged by all executions of the instruction.
+    pub gas: u64,
+}
+
+/// Gas usage collected over one or more executions of the interpreter.
+#[derive(Clone, Debug, Default)]
+pub struct GasProfile {
+    /// Self gas keyed by the `;`-separated call stack it was charged in.
+    folded_stacks: BTreeMap<String, u64>,
+    /// Call count and self gas per function.
+    functions: BTreeMap<String, FunctionGas>,
+    /// Per instruction gas, keyed by function and code offset.
+    instructions: BTreeMap<String, BTreeMap<u16, InstructionGas>>,
+}
+
+impl GasProfile {
+    /// Total gas charged over all profiled executions.
+    pub fn total_gas(&self) -> u64 {
+        self.folded_stacks.values().sum()
+    }
+
+    /// Self gas keyed by call stack, with frames separated by `;`.
+    pub fn folded_stacks(&self) -> &BTreeMap<String, u64> {
+        &self.folded_stacks
+    }
+
+    /// Write the profile in the folded stack format understood by flamegraph tools.
+    pub fn write_folded_stacks<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        for (stack, gas) in &self.folded_stacks {
+            writeln!(w, "{} {}", stack, gas)?;
+        }
+        Ok(())
+    }
+
+    /// Per-function gas, sorted by decreasing total gas.
+    pub fn functions(&self) -> Vec<(&str, FunctionGas)> {
+        let mut totals: BTreeMap<&str, u64> = BTreeMap::new();
+        for (stack, gas) in &self.folded_stacks {
+            // Recursive functions must only be charged once per stack.
+            let frames: BTreeSet<&str> = stack.split(';').collect();
+            for frame in frames {
+                *totals.entry(frame).or_insert(0) += gas;
+            }
+        }
+        let mut functions: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(name, gas)| {
+                let total_gas = totals.get(name.as_str()).copied().unwrap_or(0);
+                (name.as_str(), FunctionGas { total_gas, ..*gas })
+            })
+            .collect();
+        functions.sort_by(|(n1, g1), (n2, g2)| g2.total_gas.cmp(&g1.total_gas).then(n1.cmp(n2)));
+        functions
+    }
+
+    /// Per-instruction gas as `(function, code offset, gas)`, sorted by decreasing gas.
+    pub fn instructions(&self) -> Vec<(&str, u16, &InstructionGas)> {
+        let mut instructions: Vec<_> = self
+            .instructions
+            .iter()
+            .flat_map(|(name, instrs)| {
+                instrs
+                    .iter()
+                    .map(move |(pc, gas)| (name.as_str(), *pc, gas))
+            })
+            .collect();
+        instructions.sort_by(|(n1, pc1, g1), (n2, pc2, g2)| {
+            g2.gas.cmp(&g1.gas).then(n1.cmp(n2)).then(pc1.cmp(pc2))
+        });
+        instructions
+    }
+}
+
+/// Number of instructions listed when a `GasProfile` is displayed.
+const MAX_DISPLAYED_INSTRUCTIONS: usize = 20;
+
+impl fmt::Display for GasProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Total gas: {}", self.total_gas())?;
+        writeln!(f)?;
+        writeln!(f, "{:>12} {:>12} {:>8}  Function", "Total", "Self", "Calls")?;
+        for (name, gas) in self.functions() {
+            writeln!(
+                f,
+                "{:>12} {:>12} {:>8}  {}",
+                gas.total_gas, gas.self_gas, gas.calls, name
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:>12} {:>8}  Instruction", "Gas", "Count")?;
+        for (name, pc, gas) in self
+            .instructions()
+            .into_iter()
+            .take(MAX_DISPLAYED_INSTRUCTIONS)
+        {
+            writeln!(
+                f,
+                "{:>12} {:>8}  {}[{}] {}",
+                gas.gas, gas.count, name, pc, gas.instruction
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects a `GasProfile` while the interpreter runs.
+///
+/// The profiler takes a checkpoint of the remaining gas whenever execution moves to a new
+/// instruction or function. The gas charged since the previous checkpoint is attributed to the
+/// instruction and call stack that were current at that checkpoint.
+pub(crate) struct GasProfiler {
+    profile: GasProfile,
+    /// Names of the functions on the call stack, innermost last.
+    frames: Vec<String>,
+    /// `frames` joined with `;`, cached since it is needed at every checkpoint.
+    stack: String,
+    /// Code offset of the instruction gas is currently charged to, if any.
+    current_instruction: Option<u16>,
+    /// Remaining gas at the last checkpoint.
+    last_remaining: u64,
+}
+
+impl GasProfiler {
+    fn new() -> Self {
+        Self {
+            profile: GasProfile::default(),
+            frames: vec![],
+            stack: String::new(),
+            current_instruction: None,
+            last_remaining: 0,
+        }
+    }
+
+    /// Begin profiling a new invocation of the interpreter.
+    pub(crate) fn start_execution(&mut self, gas_status: &GasStatus) {
+        self.frames.clear();
+        self.stack.clear();
+        self.current_instruction = None;
+        self.last_remaining = gas_status.remaining_internal_gas().get();
+    }
+
+    /// Attribute any outstanding gas and end the current invocation of the interpreter.
+    pub(crate) fn finish_execution(&mut self, gas_status: &GasStatus) {
+        self.checkpoint(gas_status);
+        self.frames.clear();
+        self.stack.clear();
+        self.current_instruction = None;
+    }
+
+    /// Record a call to `name`, which becomes the innermost frame.
+    pub(crate) fn enter_function(&mut self, name: String, gas_status: &GasStatus) {
+        self.checkpoint(gas_status);
+        self.current_instruction = None;
+        self.profile
+            .functions
+            .entry(name.clone())
+            .or_insert_with(FunctionGas::default)
+            .calls += 1;
+        if !self.stack.is_empty() {
+            self.stack.push(';');
+        }
+        self.stack.push_str(&name);
+        self.frames.push(name);
+    }
+
+    /// Record a return from the innermost frame.
+    pub(crate) fn exit_function(&mut self, gas_status: &GasStatus) {
+        self.checkpoint(gas_status);
+        self.current_instruction = None;
+        if let Some(name) = self.frames.pop() {
+            let len = self.stack.len() - name.len();
+            self.stack.truncate(len.saturating_sub(1));
+        }
+    }
+
+    /// Record that `instr` at offset `pc` of the innermost frame is about to execute.
+    pub(crate) fn instruction(&mut self, pc: u16, instr: &Bytecode, gas_status: &GasStatus) {
+        self.checkpoint(gas_status);
+        if let Some(function) = self.frames.last() {
+            let instrs = match self.profile.instructions.get_mut(function) {
+                Some(instrs) => instrs,
+                None => self
+                    .profile
+                    .instructions
+                    .entry(function.clone())
+                    .or_insert_with(BTreeMap::new),
+            };
+            instrs
+                .entry(pc)
+                .or_insert_with(|| InstructionGas {
+                    instruction: format!("{:?}", instr),
+                    count: 0,
+                    gas: 0,
+                })
+                .count += 1;
+        }
+        self.current_instruction = Some(pc);
+    }
+
+    /// Attribute the gas charged since the previous checkpoint to the current call stack and
+    /// instruction.
+    fn checkpoint(&mut self, gas_status: &GasStatus) {
+        let remaining = gas_status.remaining_internal_gas().get();
+        let charged = self.last_remaining.saturating_sub(remaining);
+        self.last_remaining = remaining;
+        if charged == 0 {
+            return;
+        }
+        let function = match self.frames.last() {
+            Some(function) => function,
+            None => return,
+        };
+        match self.profile.folded_stacks.get_mut(&self.stack) {
+            Some(gas) => *gas += charged,
+            None => {
+                self.profile
+                    .folded_stacks
+                    .insert(self.stack.clone(), charged);
+            }
+        }
+        if let Some(gas) = self.profile.functions.get_mut(function) {
+            gas.self_gas += charged;
+        }
+        if let Some(pc) = &self.current_instruction {
+            if let Some(instr) = self
+                .profile
+                .instructions
+                .get_mut(function)
+                .and_then(|instrs| instrs.get_mut(pc))
+            {
+                instr.gas += charged;
+            }
+        }
+    }
+}
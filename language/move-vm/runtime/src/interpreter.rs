@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    debug_hook::{self, DebugHook, ExecutionState},
+    gas_profiler::{self, GasProfiler},
     loader::{Function, Loader, Resolver},
     logging::LogContext,
     native_functions::FunctionContext,
@@ -65,6 +67,10 @@ pub(crate) struct Interpreter<L: LogContext> {
     call_stack: CallStack,
     // Logger to report information to clients
     log_context: L,
+    /// Collects gas usage when gas profiling is enabled on the current thread.
+    gas_profiler: Option<GasProfiler>,
+    /// Inspects execution when a debug hook is installed on the current thread.
+    debug_hook: Option<Box<dyn DebugHook>>,
 }
 
 impl<L: LogContext> Interpreter<L> {
@@ -82,7 +88,20 @@ impl<L: LogContext> Interpreter<L> {
         // We count the intrinsic cost of the transaction here, since that needs to also cover the
         // setup of the function.
         let mut interp = Self::new(log_context.clone());
-        interp.execute(loader, data_store, gas_status, function, ty_args, args)
+        interp.gas_profiler = gas_profiler::take_active();
+        if let Some(profiler) = &mut interp.gas_profiler {
+            profiler.start_execution(gas_status);
+        }
+        interp.debug_hook = debug_hook::take_active();
+        let result = interp.execute(loader, data_store, gas_status, function, ty_args, args);
+        if let Some(mut profiler) = interp.gas_profiler.take() {
+            profiler.finish_execution(gas_status);
+            gas_profiler::restore_active(profiler);
+        }
+        if let Some(hook) = interp.debug_hook.take() {
+            debug_hook::restore_active(hook);
+        }
+        result
     }
 
     /// Create a new instance of an `Interpreter` in the context of a transaction with a
@@ -92,6 +111,8 @@ impl<L: LogContext> Interpreter<L> {
             operand_stack: Stack::new(),
             call_stack: CallStack::new(),
             log_context,
+            gas_profiler: None,
+            debug_hook: None,
         }
     }
 
@@ -134,6 +155,9 @@ impl<L: LogContext> Interpreter<L> {
                 .map_err(|e| self.set_location(e))?;
         }
 
+        if let Some(profiler) = &mut self.gas_profiler {
+            profiler.enter_function(function.pretty_string(), gas_status);
+        }
         let mut current_frame = Frame::new(function, ty_args, locals);
         loop {
             let resolver = current_frame.resolver(loader);
@@ -142,6 +166,9 @@ impl<L: LogContext> Interpreter<L> {
                 .map_err(|err| self.maybe_core_dump(err, &current_frame))?;
             match exit_code {
                 ExitCode::Return => {
+                    if let Some(profiler) = &mut self.gas_profiler {
+                        profiler.exit_function(gas_status);
+                    }
                     if let Some(frame) = self.call_stack.pop() {
                         current_frame = frame;
                         current_frame.pc += 1; // advance past the Call instruction in the caller
@@ -173,6 +200,9 @@ impl<L: LogContext> Interpreter<L> {
                         let err = set_err_info!(frame, err);
                         self.maybe_core_dump(err, &frame)
                     })?;
+                    if let Some(profiler) = &mut self.gas_profiler {
+                        profiler.enter_function(frame.function.pretty_string(), gas_status);
+                    }
                     current_frame = frame;
                 }
                 ExitCode::CallGeneric(idx) => {
@@ -206,6 +236,9 @@ impl<L: LogContext> Interpreter<L> {
                         let err = set_err_info!(frame, err);
                         self.maybe_core_dump(err, &frame)
                     })?;
+                    if let Some(profiler) = &mut self.gas_profiler {
+                        profiler.enter_function(frame.function.pretty_string(), gas_status);
+                    }
                     current_frame = frame;
                 }
             }
@@ -240,8 +273,12 @@ impl<L: LogContext> Interpreter<L> {
         function: Arc<Function>,
         ty_args: Vec<Type>,
     ) -> VMResult<()> {
+        if let Some(profiler) = &mut self.gas_profiler {
+            profiler.enter_function(function.pretty_string(), gas_status);
+        }
         // Note: refactor if native functions push a frame on the stack
-        self.call_native_impl(resolver, data_store, gas_status, function.clone(), ty_args)
+        let result = self
+            .call_native_impl(resolver, data_store, gas_status, function.clone(), ty_args)
             .map_err(|e| match function.module_id() {
                 Some(id) => e
                     .at_code_offset(function.index(), 0)
@@ -253,7 +290,11 @@ impl<L: LogContext> Interpreter<L> {
                         );
                     self.set_location(err)
                 }
-            })
+            });
+        if let Some(profiler) = &mut self.gas_profiler {
+            profiler.exit_function(gas_status);
+        }
+        result
     }
 
     fn call_native_impl(
@@ -643,7 +684,7 @@ impl CallStack {
 /// A `Frame` is the execution context for a function. It holds the locals of the function and
 /// the function itself.
 #[derive(Debug)]
-struct Frame {
+pub(crate) struct Frame {
     pc: u16,
     locals: Locals,
     function: Arc<Function>,
@@ -704,6 +745,14 @@ impl Frame {
                     &resolver,
                     &interpreter
                 );
+                if let Some(profiler) = &mut interpreter.gas_profiler {
+                    profiler.instruction(self.pc, instruction, gas_status);
+                }
+                if let Some(hook) = &mut interpreter.debug_hook {
+                    let state =
+                        ExecutionState::new(resolver.loader(), &interpreter.call_stack.0, self);
+                    hook.on_instruction(&state);
+                }
 
                 fail_point!("move_vm::interpreter_loop", |_| {
                     Err(
@@ -1125,10 +1174,22 @@ impl Frame {
         }
     }
 
-    fn ty_args(&self) -> &[Type] {
+    pub(crate) fn ty_args(&self) -> &[Type] {
         &self.ty_args
     }
 
+    pub(crate) fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub(crate) fn locals(&self) -> &Locals {
+        &self.locals
+    }
+
+    pub(crate) fn function(&self) -> &Function {
+        &self.function
+    }
+
     fn resolver<'a>(&self, loader: &'a Loader) -> Resolver<'a> {
         self.function.get_resolver(loader)
     }
@@ -13,6 +13,8 @@
 extern crate mirai_annotations;
 
 pub mod data_cache;
+pub mod debug_hook;
+pub mod gas_profiler;
 mod interpreter;
 mod loader;
 pub mod logging;
@@ -1144,6 +1144,19 @@ impl<'a> Resolver<'a> {
         self.loader.type_to_type_layout(ty)
     }
 
+    // Translate a `SignatureToken` of the binary to a `Type`, as needed for instance to inspect
+    // the locals of a function.
+    pub(crate) fn make_type(&self, tok: &SignatureToken) -> PartialVMResult<Type> {
+        match &self.binary {
+            BinaryType::Module(module) => self
+                .loader
+                .module_cache
+                .lock()
+                .make_type(module.module(), tok),
+            BinaryType::Script(script) => script.make_type(tok),
+        }
+    }
+
     pub(crate) fn loader(&self) -> &Loader {
         &self.loader
     }
@@ -1513,6 +1526,36 @@ impl Script {
     fn function_instantiation_at(&self, idx: u16) -> &FunctionInstantiation {
         &self.function_instantiations[idx as usize]
     }
+
+    // Translate a `SignatureToken` of the script to a `Type`, using the struct handles resolved
+    // when the script was loaded
+    fn make_type(&self, tok: &SignatureToken) -> PartialVMResult<Type> {
+        let res = match tok {
+            SignatureToken::Bool => Type::Bool,
+            SignatureToken::U8 => Type::U8,
+            SignatureToken::U64 => Type::U64,
+            SignatureToken::U128 => Type::U128,
+            SignatureToken::Address => Type::Address,
+            SignatureToken::Signer => Type::Signer,
+            SignatureToken::TypeParameter(idx) => Type::TyParam(*idx as usize),
+            SignatureToken::Vector(inner_tok) => Type::Vector(Box::new(self.make_type(inner_tok)?)),
+            SignatureToken::Reference(inner_tok) => {
+                Type::Reference(Box::new(self.make_type(inner_tok)?))
+            }
+            SignatureToken::MutableReference(inner_tok) => {
+                Type::MutableReference(Box::new(self.make_type(inner_tok)?))
+            }
+            SignatureToken::Struct(sh_idx) => Type::Struct(self.struct_refs[sh_idx.0 as usize]),
+            SignatureToken::StructInstantiation(sh_idx, tys) => {
+                let type_parameters = tys
+                    .iter()
+                    .map(|tok| self.make_type(tok))
+                    .collect::<PartialVMResult<_>>()?;
+                Type::StructInstantiation(self.struct_refs[sh_idx.0 as usize], type_parameters)
+            }
+        };
+        Ok(res)
+    }
 }
 
 // A simple wrapper for the "owner" of the function (Module or Script)
@@ -1620,6 +1663,10 @@ impl Function {
         self.locals.len()
     }
 
+    pub(crate) fn local_types(&self) -> &Signature {
+        &self.locals
+    }
+
     pub(crate) fn arg_count(&self) -> usize {
         self.parameters.len()
     }
@@ -76,6 +76,11 @@ impl<'a> GasStatus<'a> {
             .to_external_units(self.gas_left)
     }
 
+    /// Return the gas left in internal units, before any scaling is applied.
+    pub fn remaining_internal_gas(&self) -> InternalGasUnits<GasCarrier> {
+        self.gas_left
+    }
+
     /// Charge a given amount of gas and fail if not enough gas units are left.
     pub fn deduct_gas(&mut self, amount: InternalGasUnits<GasCarrier>) -> PartialVMResult<()> {
         if !self.charge {
@@ -398,6 +398,27 @@ pub fn run_and_assert_universe(
     assert_accounts_match(&universe, &executor)
 }
 
+/// Run these transactions both sequentially and on the given number of threads, and verify that
+/// the outputs are identical.
+pub fn run_and_assert_parallel_execution(
+    universe: AccountUniverseGen,
+    transaction_gens: Vec<impl AUTransactionGen + Clone>,
+    concurrency_level: usize,
+) -> Result<(), TestCaseError> {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let mut universe = universe.setup(&mut executor);
+    let transactions: Vec<_> = transaction_gens
+        .iter()
+        .map(|transaction_gen| transaction_gen.clone().apply(&mut universe).0)
+        .collect();
+
+    let sequential_outputs = executor.execute_block_with_concurrency_level(transactions.clone(), 1);
+    let parallel_outputs =
+        executor.execute_block_with_concurrency_level(transactions, concurrency_level);
+    prop_assert_eq!(sequential_outputs, parallel_outputs);
+    Ok(())
+}
+
 /// Verify that the account information in the universe matches the information in the executor.
 pub fn assert_accounts_match(
     universe: &AccountUniverse,
@@ -244,6 +244,23 @@ impl FakeExecutor {
         )
     }
 
+    /// Alternate form of 'execute_block_and_keep_vm_status' that executes the block on the given
+    /// number of threads.
+    pub fn execute_block_with_concurrency_level(
+        &self,
+        txn_block: Vec<SignedTransaction>,
+        concurrency_level: usize,
+    ) -> Result<Vec<(VMStatus, TransactionOutput)>, VMStatus> {
+        DiemVM::execute_block_with_concurrency_level(
+            txn_block
+                .into_iter()
+                .map(Transaction::UserTransaction)
+                .collect(),
+            &self.data_store,
+            concurrency_level,
+        )
+    }
+
     /// Executes the transaction as a singleton block and applies the resulting write set to the
     /// data store. Panics if execution fails
     pub fn execute_and_apply(&mut self, transaction: SignedTransaction) -> TransactionOutput {
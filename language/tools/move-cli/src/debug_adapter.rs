@@ -0,0 +1,660 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A source-level debugger for Move speaking the Debug Adapter Protocol (DAP).
+//!
+//! The adapter hooks into the interpreter through `move_vm_runtime::debug_hook`, maps bytecode
+//! offsets to source lines with the source maps produced by the compiler, and exchanges DAP
+//! messages with an editor over a pair of streams (stdin/stdout for `move run --debug`).
+//!
+//! Supported requests: `initialize`, `launch`/`attach` (with `stopOnEntry`), `setBreakpoints`
+//! (line breakpoints), `configurationDone`, `threads`, `stackTrace`, `scopes`, `variables`,
+//! `continue`, `next`, `stepIn`, `stepOut`, `pause` and `disconnect`. Move execution is single
+//! threaded, so there is exactly one thread.
+
+use anyhow::{anyhow, Result};
+use bytecode_source_map::source_map::SourceMap;
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use move_ir_types::location::Loc;
+use move_lang::{compiled_unit::CompiledUnit, errors::FilesSourceText};
+use move_vm_runtime::debug_hook::{self, DebugHook, ExecutionState, FrameInfo};
+use serde_json::{json, Value};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+};
+
+/// Id of the only thread reported to the client.
+const THREAD_ID: u64 = 1;
+
+/// Renders a value of the given type from its BCS serialization.
+pub type ValueRenderer = Box<dyn Fn(&TypeTag, &[u8]) -> Option<String>>;
+
+//**************************************************************************************************
+// Protocol
+//**************************************************************************************************
+
+/// Read one DAP message, returning `None` at the end of the stream.
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix("Content-Length:") {
+            content_length = Some(len.trim().parse::<usize>()?);
+        }
+    }
+    let len = content_length.ok_or_else(|| anyhow!("Missing Content-Length header"))?;
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Write one DAP message.
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// Read DAP messages from `reader` on a separate thread so that the interpreter can poll for
+/// requests, such as `pause`, while it runs.
+pub fn spawn_reader<R: BufRead + Send + 'static>(mut reader: R) -> Receiver<Value> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+//**************************************************************************************************
+// Source mapping
+//**************************************************************************************************
+
+/// A line in a source file.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line: u64,
+}
+
+#[derive(Debug)]
+struct FunctionLines {
+    path: PathBuf,
+    /// Line of the code starting at each offset, up to the next offset in the map.
+    lines: BTreeMap<CodeOffset, u64>,
+    /// Source names of the parameters followed by the locals.
+    local_names: Vec<String>,
+}
+
+/// Maps bytecode offsets to source lines for all functions compiled from source.
+#[derive(Debug, Default)]
+pub struct SourceIndex {
+    functions: BTreeMap<(Option<ModuleId>, FunctionDefinitionIndex), FunctionLines>,
+    /// Lines with code, per file.
+    lines: BTreeMap<PathBuf, BTreeSet<u64>>,
+}
+
+impl SourceIndex {
+    pub fn new(files: &FilesSourceText, units: &[CompiledUnit]) -> Self {
+        let line_starts: BTreeMap<&str, Vec<usize>> = files
+            .iter()
+            .map(|(name, text)| {
+                let starts = std::iter::once(0)
+                    .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+                    .collect();
+                (*name, starts)
+            })
+            .collect();
+        let line_of = |loc: &Loc| -> Option<u64> {
+            let starts = line_starts.get(loc.file())?;
+            let offset = loc.span().start().to_usize();
+            let line = match starts.binary_search(&offset) {
+                Ok(idx) => idx + 1,
+                Err(idx) => idx,
+            };
+            Some(line as u64)
+        };
+
+        let mut index = Self::default();
+        for unit in units {
+            let (module_id, function_count, source_map) = match unit {
+                CompiledUnit::Module {
+                    module, source_map, ..
+                } => (
+                    Some(module.self_id()),
+                    module.function_defs().len(),
+                    source_map,
+                ),
+                CompiledUnit::Script { source_map, .. } => (None, 1, source_map),
+            };
+            for idx in 0..function_count {
+                let fdef_idx = FunctionDefinitionIndex(idx as u16);
+                if let Some(lines) = Self::function_lines(source_map, fdef_idx, &line_of) {
+                    index
+                        .lines
+                        .entry(lines.path.clone())
+                        .or_insert_with(BTreeSet::new)
+                        .extend(lines.lines.values());
+                    index.functions.insert((module_id.clone(), fdef_idx), lines);
+                }
+            }
+        }
+        index
+    }
+
+    fn function_lines(
+        source_map: &SourceMap<Loc>,
+        fdef_idx: FunctionDefinitionIndex,
+        line_of: &impl Fn(&Loc) -> Option<u64>,
+    ) -> Option<FunctionLines> {
+        let function_map = source_map.get_function_source_map(fdef_idx).ok()?;
+        let path = canonical_path(function_map.decl_location.file());
+        let lines = function_map
+            .code_map
+            .iter()
+            .filter_map(|(offset, loc)| Some((*offset, line_of(loc)?)))
+            .collect();
+        let local_names = function_map
+            .parameters
+            .iter()
+            .chain(function_map.locals.iter())
+            .map(|(name, _)| name.clone())
+            .collect();
+        Some(FunctionLines {
+            path,
+            lines,
+            local_names,
+        })
+    }
+
+    fn function(&self, frame: &FrameInfo) -> Option<&FunctionLines> {
+        self.functions
+            .get(&(frame.module_id.clone(), frame.function_index))
+    }
+
+    /// Source line of the instruction a frame is executing, if known.
+    pub fn location(&self, frame: &FrameInfo) -> Option<SourceLocation> {
+        let function = self.function(frame)?;
+        let (_, line) = function.lines.range(..=frame.pc).next_back()?;
+        Some(SourceLocation {
+            path: function.path.clone(),
+            line: *line,
+        })
+    }
+
+    /// Source name of local `idx` of a frame, if known.
+    pub fn local_name(&self, frame: &FrameInfo, idx: usize) -> Option<&str> {
+        self.function(frame)?
+            .local_names
+            .get(idx)
+            .map(|name| name.as_str())
+    }
+
+    /// Whether code was compiled from `line` of the file at `path`.
+    pub fn has_line(&self, path: &Path, line: u64) -> bool {
+        self.lines
+            .get(path)
+            .map_or(false, |lines| lines.contains(&line))
+    }
+}
+
+fn canonical_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn function_display_name(frame: &FrameInfo) -> String {
+    match &frame.module_id {
+        Some(id) => format!("{}::{}", id, frame.function_name),
+        None => format!("script::{}", frame.function_name),
+    }
+}
+
+//**************************************************************************************************
+// Adapter
+//**************************************************************************************************
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum StepMode {
+    /// Run until a breakpoint is hit.
+    Continue,
+    /// Stop at the next line, in any frame.
+    StepIn,
+    /// Stop at the next line in the frame at `depth` or in one of its callers.
+    StepOver { depth: usize },
+    /// Stop at the next line in a caller of the frame at `depth`.
+    StepOut { depth: usize },
+    /// Stop as soon as possible.
+    Pause,
+}
+
+/// What the execution should do after a request has been handled.
+#[derive(Debug, Eq, PartialEq)]
+enum Action {
+    /// Keep processing requests.
+    Wait,
+    /// Resume or start execution.
+    Resume,
+}
+
+pub struct DebugAdapter<W: Write> {
+    output: W,
+    requests: Receiver<Value>,
+    sources: SourceIndex,
+    render: ValueRenderer,
+    seq: u64,
+    breakpoints: BTreeMap<PathBuf, BTreeSet<u64>>,
+    mode: StepMode,
+    /// Location and frame depth of the last instruction that had a source location.
+    last_location: Option<(usize, SourceLocation)>,
+    /// Location and frame depth where the current step started.
+    step_start: Option<(usize, SourceLocation)>,
+    configured: bool,
+    disconnected: bool,
+}
+
+impl<W: Write> DebugAdapter<W> {
+    pub fn new(
+        output: W,
+        requests: Receiver<Value>,
+        sources: SourceIndex,
+        render: ValueRenderer,
+    ) -> Self {
+        Self {
+            output,
+            requests,
+            sources,
+            render,
+            seq: 0,
+            breakpoints: BTreeMap::new(),
+            mode: StepMode::Continue,
+            last_location: None,
+            step_start: None,
+            configured: false,
+            disconnected: false,
+        }
+    }
+
+    /// Process requests until the client is done configuring the session.
+    pub fn wait_for_configuration(&mut self) -> Result<()> {
+        while !self.configured && !self.disconnected {
+            match self.requests.recv() {
+                Ok(request) => {
+                    self.handle_request(&request, None)?;
+                }
+                Err(_) => self.disconnected = true,
+            }
+        }
+        Ok(())
+    }
+
+    /// Report the outcome of the execution and process requests until the client disconnects.
+    pub fn finish(&mut self, outcome: &str, exit_code: i64) -> Result<()> {
+        if self.disconnected {
+            return Ok(());
+        }
+        self.send_event(
+            "output",
+            json!({ "category": "console", "output": format!("{}\n", outcome) }),
+        )?;
+        self.send_event("exited", json!({ "exitCode": exit_code }))?;
+        self.send_event("terminated", json!({}))?;
+        while !self.disconnected {
+            match self.requests.recv() {
+                Ok(request) => {
+                    self.handle_request(&request, None)?;
+                }
+                Err(_) => self.disconnected = true,
+            }
+        }
+        Ok(())
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        self.seq += 1;
+        self.seq
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        let message = json!({
+            "seq": self.next_seq(),
+            "type": "event",
+            "event": event,
+            "body": body,
+        });
+        write_message(&mut self.output, &message)
+    }
+
+    fn send_response(&mut self, request: &Value, result: Result<Value, String>) -> io::Result<()> {
+        let mut message = json!({
+            "seq": self.next_seq(),
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => message["body"] = body,
+            Err(error) => message["message"] = Value::String(error),
+        }
+        write_message(&mut self.output, &message)
+    }
+
+    fn handle_request(
+        &mut self,
+        request: &Value,
+        state: Option<&ExecutionState>,
+    ) -> Result<Action> {
+        let args = &request["arguments"];
+        let depth = state.map_or(0, |state| state.depth());
+        let (result, action) = match request["command"].as_str().unwrap_or_default() {
+            "initialize" => (
+                Ok(json!({ "supportsConfigurationDoneRequest": true })),
+                Action::Wait,
+            ),
+            "launch" | "attach" => {
+                if args["stopOnEntry"].as_bool().unwrap_or(false) {
+                    self.mode = StepMode::StepIn;
+                }
+                (Ok(json!({})), Action::Wait)
+            }
+            "setBreakpoints" => (Ok(self.set_breakpoints(args)), Action::Wait),
+            "setExceptionBreakpoints" => (Ok(json!({})), Action::Wait),
+            "configurationDone" => {
+                self.configured = true;
+                (Ok(json!({})), Action::Resume)
+            }
+            "threads" => (
+                Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+                Action::Wait,
+            ),
+            "stackTrace" => (
+                state
+                    .map(|state| self.stack_trace(state))
+                    .ok_or_else(|| "Not stopped".to_string()),
+                Action::Wait,
+            ),
+            "scopes" => {
+                // Each frame has a single scope; its reference is the frame index plus one since
+                // zero means "no children" in the protocol.
+                let frame_id = args["frameId"].as_u64().unwrap_or_default();
+                (
+                    Ok(json!({ "scopes": [{
+                        "name": "Locals",
+                        "variablesReference": frame_id + 1,
+                        "expensive": false,
+                    }]})),
+                    Action::Wait,
+                )
+            }
+            "variables" => (
+                match (state, args["variablesReference"].as_u64()) {
+                    (Some(state), Some(reference)) if reference > 0 => {
+                        Ok(self.variables(state, (reference - 1) as usize))
+                    }
+                    _ => Ok(json!({ "variables": [] })),
+                },
+                Action::Wait,
+            ),
+            "continue" => {
+                self.mode = StepMode::Continue;
+                (Ok(json!({ "allThreadsContinued": true })), Action::Resume)
+            }
+            "next" => {
+                self.mode = StepMode::StepOver { depth };
+                (Ok(json!({})), Action::Resume)
+            }
+            "stepIn" => {
+                self.mode = StepMode::StepIn;
+                (Ok(json!({})), Action::Resume)
+            }
+            "stepOut" => {
+                self.mode = StepMode::StepOut { depth };
+                (Ok(json!({})), Action::Resume)
+            }
+            "pause" => {
+                self.mode = StepMode::Pause;
+                (Ok(json!({})), Action::Wait)
+            }
+            "disconnect" | "terminate" => {
+                self.disconnected = true;
+                (Ok(json!({})), Action::Resume)
+            }
+            command => (
+                Err(format!("Unsupported request: {}", command)),
+                Action::Wait,
+            ),
+        };
+        self.send_response(request, result)?;
+        if request["command"] == "initialize" {
+            self.send_event("initialized", json!({}))?;
+        }
+        Ok(action)
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        let path = match args["source"]["path"].as_str() {
+            Some(path) => canonical_path(path),
+            None => return json!({ "breakpoints": [] }),
+        };
+        let lines: Vec<u64> = args["breakpoints"]
+            .as_array()
+            .map(|breakpoints| {
+                breakpoints
+                    .iter()
+                    .filter_map(|bp| bp["line"].as_u64())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let breakpoints: Vec<_> = lines
+            .iter()
+            .map(|line| json!({ "verified": self.sources.has_line(&path, *line), "line": line }))
+            .collect();
+        self.breakpoints.insert(path, lines.into_iter().collect());
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn stack_trace(&self, state: &ExecutionState) -> Value {
+        let frames = state.frames();
+        let stack_frames: Vec<_> = frames
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(idx, frame)| {
+                let mut stack_frame = json!({
+                    "id": idx,
+                    "name": function_display_name(frame),
+                    "line": 0,
+                    "column": 0,
+                });
+                match self.sources.location(frame) {
+                    Some(location) => {
+                        stack_frame["source"] = json!({ "path": location.path });
+                        stack_frame["line"] = json!(location.line);
+                        stack_frame["column"] = json!(1);
+                    }
+                    None => stack_frame["presentationHint"] = json!("subtle"),
+                }
+                stack_frame
+            })
+            .collect();
+        json!({ "stackFrames": stack_frames, "totalFrames": frames.len() })
+    }
+
+    fn variables(&self, state: &ExecutionState, frame_idx: usize) -> Value {
+        let frame = match state.frames().into_iter().nth(frame_idx) {
+            Some(frame) => frame,
+            None => return json!({ "variables": [] }),
+        };
+        let variables: Vec<_> = state
+            .locals(frame_idx)
+            .into_iter()
+            .map(|local| {
+                let name = self
+                    .sources
+                    .local_name(&frame, local.index)
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("local_{}", local.index));
+                let rendered = match (&local.type_tag, &local.bytes) {
+                    (Some(type_tag), Some(bytes)) => (self.render)(type_tag, bytes),
+                    _ => None,
+                };
+                let value = rendered
+                    .or(local.debug_string)
+                    .unwrap_or_else(|| "<unavailable>".to_string());
+                let ty = match (&local.type_tag, local.is_reference) {
+                    (Some(type_tag), true) => format!("&{}", type_tag),
+                    (Some(type_tag), false) => type_tag.to_string(),
+                    (None, _) => "?".to_string(),
+                };
+                json!({
+                    "name": name,
+                    "value": value,
+                    "type": ty,
+                    "variablesReference": 0,
+                })
+            })
+            .collect();
+        json!({ "variables": variables })
+    }
+
+    /// Process requests that arrived while running, without blocking.
+    fn poll_requests(&mut self, state: &ExecutionState) -> Result<()> {
+        loop {
+            match self.requests.try_recv() {
+                Ok(request) => {
+                    self.handle_request(&request, Some(state))?;
+                }
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Report that execution stopped and process requests until it is resumed.
+    fn stop(&mut self, reason: &str, state: &ExecutionState) -> Result<()> {
+        self.send_event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+        while !self.disconnected {
+            match self.requests.recv() {
+                Ok(request) => {
+                    if self.handle_request(&request, Some(state))? == Action::Resume {
+                        self.step_start = self.last_location.clone();
+                        return Ok(());
+                    }
+                }
+                Err(_) => self.disconnected = true,
+            }
+        }
+        Ok(())
+    }
+
+    fn stop_reason(&self, depth: usize, location: &SourceLocation) -> Option<&'static str> {
+        let at_step_start = self
+            .step_start
+            .as_ref()
+            .map_or(false, |(start_depth, start)| {
+                *start_depth == depth && start == location
+            });
+        if self
+            .breakpoints
+            .get(&location.path)
+            .map_or(false, |lines| lines.contains(&location.line))
+        {
+            return Some("breakpoint");
+        }
+        match self.mode {
+            StepMode::Continue => None,
+            StepMode::StepIn if !at_step_start => Some("step"),
+            StepMode::StepOver { depth: d } if depth <= d && !at_step_start => Some("step"),
+            StepMode::StepOut { depth: d } if depth < d => Some("step"),
+            StepMode::Pause => Some("pause"),
+            _ => None,
+        }
+    }
+
+    fn on_instruction_impl(&mut self, state: &ExecutionState) -> Result<()> {
+        self.poll_requests(state)?;
+        if self.disconnected {
+            return Ok(());
+        }
+        let location = match self.sources.location(&state.current_frame()) {
+            Some(location) => location,
+            // Code without source, e.g. from the standard library, cannot be stopped in.
+            None => return Ok(()),
+        };
+        let depth = state.depth();
+        let current = Some((depth, location));
+        if self.last_location == current {
+            // Only stop when execution moves to a new line.
+            return Ok(());
+        }
+        self.last_location = current;
+        let location = &self.last_location.as_ref().unwrap().1;
+        if let Some(reason) = self.stop_reason(depth, location) {
+            self.stop(reason, state)?;
+        }
+        Ok(())
+    }
+}
+
+/// Install `adapter` on the current thread while `execute` runs, then report the outcome of the
+/// execution to the client.
+pub fn debug_session<W, T, E, F>(mut adapter: DebugAdapter<W>, execute: F) -> Result<Result<T, E>>
+where
+    W: Write + 'static,
+    E: Debug,
+    F: FnOnce() -> Result<T, E>,
+{
+    adapter.wait_for_configuration()?;
+    let adapter = Rc::new(RefCell::new(adapter));
+    debug_hook::install(Box::new(SharedAdapter(adapter.clone())));
+    let result = execute();
+    debug_hook::uninstall();
+    let (outcome, exit_code) = match &result {
+        Ok(_) => ("Execution succeeded".to_string(), 0),
+        Err(err) => (format!("Execution failed: {:?}", err), 1),
+    };
+    adapter.borrow_mut().finish(&outcome, exit_code)?;
+    Ok(result)
+}
+
+/// Handle to an adapter shared between the interpreter and `debug_session`.
+struct SharedAdapter<W: Write>(Rc<RefCell<DebugAdapter<W>>>);
+
+impl<W: Write> DebugHook for SharedAdapter<W> {
+    fn on_instruction(&mut self, state: &ExecutionState) {
+        let mut adapter = self.0.borrow_mut();
+        if adapter.on_instruction_impl(state).is_err() {
+            // The client is unreachable, let execution run to completion.
+            adapter.disconnected = true;
+        }
+    }
+}
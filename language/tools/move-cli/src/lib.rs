@@ -28,6 +28,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
+pub mod debug_adapter;
 pub mod package;
 pub mod test;
 
@@ -40,6 +41,9 @@ pub const DEFAULT_SOURCE_DIR: &str = "src";
 /// Default directory where Move packages live under build_dir
 pub const DEFAULT_PACKAGE_DIR: &str = "package";
 
+/// File under build_dir where folded gas stacks are written by `run --profile-gas`
+pub const GAS_PROFILE_FILE: &str = "gas_profile.folded";
+
 /// Default dependency inclusion mode
 pub const DEFAULT_DEP_MODE: &str = "stdlib";
 
@@ -4,6 +4,7 @@
 use errmapgen::ErrorMapping;
 
 use move_cli::{
+    debug_adapter::{self, DebugAdapter, SourceIndex},
     package::{parse_mode_from_string, Mode},
     *,
 };
@@ -18,7 +19,7 @@ use move_core_types::{
     vm_status::{AbortLocation, StatusCode, VMStatus},
 };
 use move_lang::{self, compiled_unit::CompiledUnit, shared::Flags, MOVE_COMPILED_EXTENSION};
-use move_vm_runtime::{logging::NoContextLog, move_vm::MoveVM};
+use move_vm_runtime::{gas_profiler, logging::NoContextLog, move_vm::MoveVM};
 use move_vm_types::gas_schedule::GasStatus;
 use vm::{
     access::ModuleAccess,
@@ -29,10 +30,12 @@ use vm::{
 };
 
 use anyhow::{anyhow, bail, Result};
+use resource_viewer::MoveValueAnnotator;
 use std::{
     collections::BTreeMap,
     ffi::OsStr,
     fs,
+    io::{self, BufReader, Write},
     path::{Path, PathBuf},
 };
 use structopt::StructOpt;
@@ -139,6 +142,16 @@ pub enum Command {
         /// deleted resources) will NOT be committed to disk.
         #[structopt(long = "dry-run", short = "n")]
         dry_run: bool,
+        /// If set, record the gas consumed by each function and instruction, print a summary (to
+        /// stderr under `--debug`) and write folded stacks for flamegraph tools to
+        /// `gas_profile.folded` in the build directory. Gas metering is enabled with the maximum
+        /// budget if no `gas-budget` is specified.
+        #[structopt(long = "profile-gas")]
+        profile_gas: bool,
+        /// If set, run under a source-level debugger speaking the Debug Adapter Protocol on
+        /// stdin/stdout. Source locations are available for the script and the modules in `src`.
+        #[structopt(long = "debug")]
+        debug: bool,
     },
 
     /// Run expected value tests using the given batch file
@@ -175,6 +188,10 @@ impl Move {
         Path::new(&self.build_dir).join(DEFAULT_PACKAGE_DIR)
     }
 
+    fn get_gas_profile_path(&self) -> PathBuf {
+        Path::new(&self.build_dir).join(GAS_PROFILE_FILE)
+    }
+
     /// This collects only the compiled modules from dependent libraries. The modules
     /// created via the "publish" command should already sit in the storage based on
     /// current implementation.
@@ -347,6 +364,8 @@ fn run(
     vm_type_args: Vec<TypeTag>,
     gas_budget: Option<u64>,
     dry_run: bool,
+    gas_profile_path: Option<&Path>,
+    debug_state: Option<OnDiskStateView>,
     verbose: bool,
 ) -> Result<()> {
     fn compile_script(
@@ -423,29 +442,41 @@ move run` must be applied to a module inside `storage/`",
     let vm_args: Vec<Vec<u8>> = convert_txn_args(&txn_args);
 
     let vm = MoveVM::new();
+    let gas_budget = match (gas_budget, gas_profile_path) {
+        (None, Some(_)) => Some(max_gas_budget() - 1),
+        _ => gas_budget,
+    };
     let mut gas_status = get_gas_status(gas_budget)?;
     let log_context = NoContextLog::new();
     let mut session = vm.new_session(&state);
 
+    if gas_profile_path.is_some() {
+        gas_profiler::start();
+    }
+
     let script_type_parameters = vec![];
     let script_parameters = vec![];
-    let res = match script_name_opt {
+    let script_function = match script_name_opt {
         Some(script_name) => {
             // script fun. parse module, extract script ID to pass to VM
             let module = CompiledModule::deserialize(&bytecode)
                 .map_err(|e| anyhow!("Error deserializing module: {:?}", e))?;
-            session
-                .execute_script_function(
-                    &module.self_id(),
-                    &IdentStr::new(script_name)?,
-                    vm_type_args.clone(),
-                    vm_args,
-                    signer_addresses.clone(),
-                    &mut gas_status,
-                    &log_context,
-                )
-                .map(|_| ())
+            Some((module.self_id(), IdentStr::new(script_name)?))
         }
+        None => None,
+    };
+    let execute = || match &script_function {
+        Some((module_id, script_name)) => session
+            .execute_script_function(
+                module_id,
+                script_name,
+                vm_type_args.clone(),
+                vm_args,
+                signer_addresses.clone(),
+                &mut gas_status,
+                &log_context,
+            )
+            .map(|_| ()),
         None => session.execute_script(
             bytecode.to_vec(),
             vm_type_args.clone(),
@@ -455,6 +486,48 @@ move run` must be applied to a module inside `storage/`",
             &log_context,
         ),
     };
+    let is_debugging = debug_state.is_some();
+    let res = match debug_state {
+        Some(debug_state) => {
+            let sources = compile_debug_sources(&state, script_file)?;
+            let render = Box::new(move |type_tag: &TypeTag, blob: &[u8]| {
+                MoveValueAnnotator::new_no_stdlib(&debug_state)
+                    .view_value(type_tag, blob)
+                    .ok()
+                    .map(|value| value.to_string())
+            });
+            let requests = debug_adapter::spawn_reader(BufReader::new(io::stdin()));
+            let adapter = DebugAdapter::new(io::stdout(), requests, sources, render);
+            debug_adapter::debug_session(adapter, execute)?
+        }
+        None => execute(),
+    };
+
+    if let (Some(path), Some(profile)) = (gas_profile_path, gas_profiler::finish()) {
+        let mut file = fs::File::create(path)?;
+        profile.write_folded_stacks(&mut file)?;
+        // stdout carries the debug protocol when debugging, so report on stderr instead
+        let mut out: Box<dyn Write> = if is_debugging {
+            Box::new(io::stderr())
+        } else {
+            Box::new(io::stdout())
+        };
+        write!(out, "{}", profile)?;
+        writeln!(out, "Wrote folded gas stacks to {:?}", path)?;
+    }
+
+    if is_debugging {
+        // stdout carries the debug protocol and the outcome was already reported to the client
+        let (changeset, events) = match res {
+            Ok(()) => session.finish().map_err(|e| e.into_vm_status())?,
+            Err(err) => bail!("Execution failed: {:?}", err.into_vm_status()),
+        };
+        return if dry_run {
+            Ok(())
+        } else {
+            maybe_commit_effects(true, changeset, events, &state)
+        };
+    }
 
     if let Err(err) = res {
         explain_execution_error(
@@ -475,12 +548,40 @@ move run` must be applied to a module inside `storage/`",
     }
 }
 
+/// Compile the script and the modules under `src` to map the code executed by `run --debug` back to
+/// source.
+fn compile_debug_sources(state: &OnDiskStateView, script_file: &str) -> Result<SourceIndex> {
+    let mut targets = vec![];
+    let script_path = Path::new(script_file);
+    if !Move::is_bytecode_file(script_path) && !script_path.starts_with(DEFAULT_SOURCE_DIR) {
+        targets.push(script_file.to_string());
+    }
+    if Path::new(DEFAULT_SOURCE_DIR).exists() {
+        targets.push(DEFAULT_SOURCE_DIR.to_string());
+    }
+    let (files, units) = move_lang::move_compile(
+        &targets,
+        &[state.interface_files_dir()?],
+        None,
+        true,
+        Flags::empty(),
+    )?;
+    let units = units.map_err(|_| anyhow!("Unable to compile {:?} for debugging", targets))?;
+    Ok(SourceIndex::new(&files, &units))
+}
+
+/// The largest gas budget that does not overflow once converted to internal gas units.
+fn max_gas_budget() -> u64 {
+    let gas_schedule = &vm_genesis::genesis_gas_schedule::INITIAL_GAS_SCHEDULE;
+    u64::MAX
+        .checked_div(gas_schedule.gas_constants.gas_unit_scaling_factor)
+        .unwrap()
+}
+
 fn get_gas_status(gas_budget: Option<u64>) -> Result<GasStatus<'static>> {
     let gas_status = if let Some(gas_budget) = gas_budget {
         let gas_schedule = &vm_genesis::genesis_gas_schedule::INITIAL_GAS_SCHEDULE;
-        let max_gas_budget = u64::MAX
-            .checked_div(gas_schedule.gas_constants.gas_unit_scaling_factor)
-            .unwrap();
+        let max_gas_budget = max_gas_budget();
         if gas_budget >= max_gas_budget {
             bail!("Gas budget set too high; maximum is {}", max_gas_budget)
         }
@@ -1010,8 +1111,21 @@ fn main() -> Result<()> {
             type_args,
             gas_budget,
             dry_run,
+            profile_gas,
+            debug,
         } => {
             let state = move_args.prepare_state(true)?;
+            let debug_state = if *debug {
+                // a separate view of the storage used to render values in the debugger
+                Some(move_args.prepare_state(false)?)
+            } else {
+                None
+            };
+            let gas_profile_path = if *profile_gas {
+                Some(move_args.get_gas_profile_path())
+            } else {
+                None
+            };
             run(
                 state,
                 script_file,
@@ -1021,7 +1135,9 @@ fn main() -> Result<()> {
                 type_args.to_vec(),
                 *gas_budget,
                 *dry_run,
-                move_args.verbose,
+                gas_profile_path.as_deref(),
+                debug_state,
+                move_args.verbose && !*debug,
             )
         }
         Command::Test {
@@ -1,9 +1,18 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use move_cli::test;
-
-use std::path::PathBuf;
+use diem_temppath::TempPath;
+use move_cli::{
+    debug_adapter::{read_message, write_message},
+    test,
+};
+use serde_json::json;
+use std::{
+    fs,
+    io::Cursor,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
 
 pub const CLI_BINARY_PATH: [&str; 6] = ["..", "..", "..", "target", "debug", "move"];
 pub const CLI_METATEST_PATH: [&str; 3] = ["tests", "metatests", "args.txt"];
@@ -28,3 +37,44 @@ fn run_metatest() {
     // without coverage
     assert!(test::run_all(&path_metatest, &path_cli_binary, false).is_ok());
 }
+
+#[test]
+fn debug_keeps_gas_profile_off_the_protocol_stream() {
+    let cli_binary = PathBuf::from(get_cli_binary_path()).canonicalize().unwrap();
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    fs::write(dir.path().join("script.move"), "script { fun main() {} }").unwrap();
+
+    let mut child = Command::new(cli_binary)
+        .current_dir(dir.path())
+        .args(&["run", "--debug", "--profile-gas", "script.move"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let commands = ["initialize", "launch", "configurationDone"];
+    for (seq, command) in commands.iter().enumerate() {
+        let request =
+            json!({ "seq": seq + 1, "type": "request", "command": command, "arguments": {} });
+        write_message(&mut stdin, &request).unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stdout.contains("Wrote folded gas stacks"));
+    assert!(stderr.contains("Wrote folded gas stacks"));
+
+    let mut cursor = Cursor::new(stdout);
+    let mut events = vec![];
+    while let Some(message) = read_message(&mut cursor).unwrap() {
+        if message["type"] == "event" {
+            events.push(message["event"].as_str().unwrap().to_string());
+        }
+    }
+    assert_eq!(events.last().map(String::as_str), Some("terminated"));
+}
@@ -0,0 +1,126 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_cli::debug_adapter::{
+    debug_session, read_message, write_message, DebugAdapter, SourceIndex,
+};
+use serde_json::{json, Value};
+use std::{
+    cell::RefCell,
+    io::{self, Cursor, Write},
+    rc::Rc,
+    sync::mpsc,
+};
+
+/// A writer whose content can be inspected after it was moved into the adapter.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn read_all(buffer: &SharedBuffer) -> Vec<Value> {
+    let mut cursor = Cursor::new(buffer.0.borrow().clone());
+    let mut messages = vec![];
+    while let Some(message) = read_message(&mut cursor).unwrap() {
+        messages.push(message);
+    }
+    messages
+}
+
+fn request(seq: u64, command: &str, arguments: Value) -> Value {
+    json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments })
+}
+
+#[test]
+fn message_round_trip() {
+    let message = json!({ "seq": 1, "type": "request", "command": "threads" });
+    let mut buffer = vec![];
+    write_message(&mut buffer, &message).unwrap();
+    write_message(&mut buffer, &message).unwrap();
+    assert!(buffer.starts_with(b"Content-Length: "));
+
+    let mut cursor = Cursor::new(buffer);
+    assert_eq!(read_message(&mut cursor).unwrap(), Some(message.clone()));
+    assert_eq!(read_message(&mut cursor).unwrap(), Some(message));
+    assert_eq!(read_message(&mut cursor).unwrap(), None);
+}
+
+#[test]
+fn session_lifecycle() {
+    let (sender, receiver) = mpsc::channel();
+    sender
+        .send(request(1, "initialize", json!({ "adapterID": "move" })))
+        .unwrap();
+    sender.send(request(2, "launch", json!({}))).unwrap();
+    sender
+        .send(request(
+            3,
+            "setBreakpoints",
+            json!({ "source": { "path": "missing.move" }, "breakpoints": [{ "line": 3 }] }),
+        ))
+        .unwrap();
+    sender
+        .send(request(4, "configurationDone", json!({})))
+        .unwrap();
+    sender.send(request(5, "disconnect", json!({}))).unwrap();
+
+    let output = SharedBuffer::default();
+    let adapter = DebugAdapter::new(
+        output.clone(),
+        receiver,
+        SourceIndex::default(),
+        Box::new(|_, _| None),
+    );
+    let result = debug_session(adapter, || Err::<(), _>("aborted")).unwrap();
+    assert_eq!(result, Err("aborted"));
+
+    let messages = read_all(&output);
+    let responses: Vec<_> = messages
+        .iter()
+        .filter(|m| m["type"] == "response")
+        .map(|m| {
+            (
+                m["command"].as_str().unwrap(),
+                m["success"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        responses,
+        vec![
+            ("initialize", true),
+            ("launch", true),
+            ("setBreakpoints", true),
+            ("configurationDone", true),
+            ("disconnect", true),
+        ]
+    );
+    let events: Vec<_> = messages
+        .iter()
+        .filter(|m| m["type"] == "event")
+        .map(|m| m["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        vec!["initialized", "output", "exited", "terminated"]
+    );
+
+    let set_breakpoints = messages
+        .iter()
+        .find(|m| m["command"] == "setBreakpoints")
+        .unwrap();
+    assert_eq!(
+        set_breakpoints["body"]["breakpoints"],
+        json!([{ "verified": false, "line": 3 }])
+    );
+    let exited = messages.iter().find(|m| m["event"] == "exited").unwrap();
+    assert_eq!(exited["body"]["exitCode"], 1);
+}
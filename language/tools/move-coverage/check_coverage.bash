@@ -61,6 +61,10 @@ echo "> cargo run --bin source-coverage -- -t trace.mvcov -b ../../move-lang/bui
 echo "---------------------------------------------------------------------------"
 echo "You can can also get a finer-grained coverage summary for each function by running:"
 echo "> cargo run --bin coverage-summaries -- -t trace.mvcov -s ../../diem-framework/releases/artifacts/current/stdlib.mv"
+echo "---------------------------------------------------------------------------"
+echo "You can also produce lcov (-f lcov), Cobertura (-f cobertura) or HTML (-f html) reports of"
+echo "line and branch coverage. Repeat -t to merge coverage maps from several runs:"
+echo "> cargo run --bin coverage-report -- -t trace.mvcov -b ../../move-lang/build/modules -s ../../move-lang -f html -o coverage_html"
 echo "==========================================================================="
 
 unset MOVE_VM_TRACE
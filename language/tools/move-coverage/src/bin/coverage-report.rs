@@ -0,0 +1,148 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::{bail, format_err, Result};
+use bytecode_source_map::utils::{remap_owned_loc_to_loc, source_map_from_file, OwnedLoc};
+use move_coverage::{coverage_map::CoverageMap, report::CoverageReport};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use structopt::StructOpt;
+use vm::{access::ModuleAccess, file_format::CompiledModule};
+
+#[derive(Debug)]
+enum ReportFormat {
+    Lcov,
+    Cobertura,
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "lcov" => Ok(ReportFormat::Lcov),
+            "cobertura" => Ok(ReportFormat::Cobertura),
+            "html" => Ok(ReportFormat::Html),
+            _ => bail!(
+                "Unknown report format {}, expected lcov, cobertura or html",
+                s
+            ),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Coverage Report",
+    about = "Creates line and branch coverage reports of Move sources in lcov, Cobertura or HTML \
+             format"
+)]
+struct Args {
+    /// The paths to the coverage maps or trace files. Coverage from all of them is merged.
+    #[structopt(long = "input-trace-path", short = "t", required = true)]
+    pub input_trace_paths: Vec<String>,
+    /// Whether the passed-in files are raw trace files or serialized coverage maps
+    #[structopt(long = "is-raw-trace", short = "r")]
+    pub is_raw_trace_file: bool,
+    /// The paths to module binaries, or to directories containing them. The source map of each
+    /// module must be next to it, with the `mvsm` extension.
+    #[structopt(long = "module-path", short = "b", required = true)]
+    pub module_paths: Vec<String>,
+    /// The directory relative source file names in the source maps are resolved against
+    #[structopt(long = "source-root", short = "s", default_value = ".")]
+    pub source_root: String,
+    /// The format of the report: lcov, cobertura or html
+    #[structopt(long = "format", short = "f", default_value = "lcov")]
+    pub format: ReportFormat,
+    /// Path of the report. Printed to stdout if not present, except for html reports which are
+    /// written to this directory and require it.
+    #[structopt(long = "output-path", short = "o")]
+    pub output_path: Option<String>,
+}
+
+fn get_module_files(module_paths: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for module_path in module_paths {
+        let path = Path::new(module_path);
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let entry_path = entry?.path();
+                if entry_path.extension().map_or(false, |ext| ext == "mv") {
+                    files.push(entry_path);
+                }
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn main() -> Result<()> {
+    let args = Args::from_args();
+    let source_map_extension = "mvsm";
+
+    let mut coverage_map = CoverageMap {
+        exec_maps: BTreeMap::new(),
+    };
+    for input_trace_path in &args.input_trace_paths {
+        coverage_map.merge(if args.is_raw_trace_file {
+            CoverageMap::from_trace_file(input_trace_path)
+        } else {
+            CoverageMap::from_binary_file(input_trace_path)
+        });
+    }
+    let unified_exec_map = coverage_map.to_unified_exec_map();
+
+    let mut report = CoverageReport::new(&args.source_root);
+    let mut seen_modules = BTreeSet::new();
+    for module_file in get_module_files(&args.module_paths)? {
+        let bytecode_bytes = fs::read(&module_file)?;
+        let compiled_module = CompiledModule::deserialize(&bytecode_bytes)
+            .map_err(|e| format_err!("Module {:?} can't be deserialized: {:?}", module_file, e))?;
+        // The same module may be found in several directories, e.g. a build and a release.
+        if !seen_modules.insert(compiled_module.self_id()) {
+            continue;
+        }
+        let source_map_file = module_file.with_extension(source_map_extension);
+        let source_map = match source_map_from_file::<OwnedLoc>(&source_map_file) {
+            Ok(source_map) => remap_owned_loc_to_loc(source_map),
+            Err(_) => {
+                eprintln!(
+                    "Skipping module {:?}: no source map found at {:?}",
+                    module_file, source_map_file
+                );
+                continue;
+            }
+        };
+        report.add_module(&compiled_module, &source_map, &unified_exec_map)?;
+    }
+
+    match args.format {
+        ReportFormat::Html => match &args.output_path {
+            Some(output_path) => report.write_html(Path::new(output_path))?,
+            None => bail!("An output directory is required for html reports"),
+        },
+        ReportFormat::Lcov | ReportFormat::Cobertura => {
+            let mut report_writer: Box<dyn Write> = match &args.output_path {
+                Some(x) => Box::new(File::create(Path::new(x))?),
+                None => Box::new(io::stdout()),
+            };
+            if let ReportFormat::Lcov = args.format {
+                report.write_lcov(&mut report_writer)?;
+            } else {
+                report.write_cobertura(&mut report_writer)?;
+            }
+        }
+    }
+    Ok(())
+}
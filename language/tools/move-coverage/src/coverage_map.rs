@@ -121,6 +121,19 @@ impl CoverageMap {
         exec_entry.insert(module_addr, module_name, func_name, pc);
     }
 
+    /// Add the executions recorded in `another`, e.g. the coverage map of a different test run, to
+    /// this map. Counts of executions sharing the same id are summed.
+    pub fn merge(&mut self, another: CoverageMap) {
+        for (exec_id, exec_map) in another.exec_maps {
+            match self.exec_maps.get_mut(&exec_id) {
+                Some(existing) => existing.merge(exec_map),
+                None => {
+                    self.exec_maps.insert(exec_id, exec_map);
+                }
+            }
+        }
+    }
+
     pub fn to_unified_exec_map(&self) -> ExecCoverageMap {
         let mut unified_map = ExecCoverageMap::new(String::new());
         for (_, exec_map) in self.exec_maps.iter() {
@@ -164,12 +177,16 @@ impl ModuleCoverageMap {
         self.insert_multi(func_name, pc, 1);
     }
 
+    /// Add the execution counts of `another` to this map.
     pub fn merge(&mut self, another: ModuleCoverageMap) {
         for (key, val) in another.function_maps {
-            self.function_maps
+            let func_entry = self
+                .function_maps
                 .entry(key)
-                .or_insert_with(FunctionCoverage::new)
-                .extend(val);
+                .or_insert_with(FunctionCoverage::new);
+            for (pc, count) in val {
+                *func_entry.entry(pc).or_insert(0) += count;
+            }
         }
     }
 
@@ -211,6 +228,16 @@ impl ExecCoverageMap {
         self.insert_multi(module_addr, module_name, func_name, pc, 1);
     }
 
+    /// Add the execution counts of `another` to this map.
+    pub fn merge(&mut self, another: ExecCoverageMap) {
+        for ((module_addr, module_name), module_map) in another.module_maps {
+            self.module_maps
+                .entry((module_addr, module_name.clone()))
+                .or_insert_with(|| ModuleCoverageMap::new(module_addr, module_name))
+                .merge(module_map);
+        }
+    }
+
     pub fn into_coverage_map_with_modules(
         self,
         modules: BTreeMap<AccountAddress, BTreeMap<Identifier, (String, CompiledModule)>>,
@@ -345,3 +372,48 @@ pub fn output_map_to_file<M: Serialize, P: AsRef<Path>>(file_name: P, data: &M)
     file.write_all(&bytes)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Identifier {
+        Identifier::new(name).unwrap()
+    }
+
+    fn run(pcs: &[u64]) -> CoverageMap {
+        let mut coverage_map = CoverageMap {
+            exec_maps: BTreeMap::new(),
+        };
+        for pc in pcs {
+            coverage_map.insert("exec", AccountAddress::ZERO, ident("M"), ident("f"), *pc);
+        }
+        coverage_map
+    }
+
+    #[test]
+    fn merging_runs_sums_counts() {
+        let mut coverage_map = run(&[0, 1, 1]);
+        coverage_map.merge(run(&[1, 2]));
+
+        let unified_map = coverage_map.to_unified_exec_map();
+        let function_coverage = unified_map.module_maps[&(AccountAddress::ZERO, ident("M"))]
+            .get_function_coverage(&ident("f"))
+            .unwrap();
+        let expected: FunctionCoverage = vec![(0, 1), (1, 3), (2, 1)].into_iter().collect();
+        assert_eq!(function_coverage, &expected);
+    }
+
+    #[test]
+    fn merging_module_maps_sums_counts() {
+        let mut module_map = ModuleCoverageMap::new(AccountAddress::ZERO, ident("M"));
+        module_map.insert_multi(ident("f"), 0, 2);
+        let mut another = ModuleCoverageMap::new(AccountAddress::ZERO, ident("M"));
+        another.insert_multi(ident("f"), 0, 3);
+        another.insert_multi(ident("g"), 0, 1);
+        module_map.merge(another);
+
+        assert_eq!(module_map.function_maps[&ident("f")][&0], 5);
+        assert_eq!(module_map.function_maps[&ident("g")][&0], 1);
+    }
+}
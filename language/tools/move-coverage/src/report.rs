@@ -0,0 +1,789 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Line and branch coverage of Move source files, exported in the lcov, Cobertura and HTML
+//! formats.
+//!
+//! Coverage maps record how many times each instruction was executed but not which way branches
+//! went. The number of times a branch outcome was taken is exact when the target instruction can
+//! only be reached through that branch, and otherwise bounded by the number of executions of both
+//! the branch and its target.
+
+use crate::coverage_map::{ExecCoverageMap, FunctionCoverage};
+use anyhow::{format_err, Result};
+use bytecode_source_map::source_map::SourceMap;
+use move_ir_types::location::Loc;
+use std::{
+    cmp,
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex},
+    CompiledModule,
+};
+
+/// Coverage of a function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionLineCoverage {
+    /// Name of the function, qualified by its module.
+    pub name: String,
+    /// Line of the function declaration.
+    pub line: u32,
+    /// Number of times the function was called.
+    pub hits: u64,
+}
+
+/// Coverage of a conditional branch instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BranchCoverage {
+    /// Line of the branch instruction.
+    pub line: u32,
+    /// Name of the function containing the branch, qualified by its module.
+    pub function: String,
+    /// Code offset of the branch instruction in the function.
+    pub offset: CodeOffset,
+    /// Number of times the jump and the fall through outcomes were taken, in that order, or `None`
+    /// if the branch instruction was never executed.
+    pub taken: Option<[u64; 2]>,
+}
+
+impl BranchCoverage {
+    /// Number of outcomes of the branch that were taken at least once.
+    pub fn outcomes_hit(&self) -> u64 {
+        self.taken
+            .map(|taken| taken.iter().filter(|count| **count > 0).count() as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// Coverage of a single source file.
+#[derive(Clone, Debug, Default)]
+pub struct FileCoverage {
+    /// Hit count of each line holding code, keyed by line number starting at 1.
+    pub lines: BTreeMap<u32, u64>,
+    pub functions: Vec<FunctionLineCoverage>,
+    pub branches: Vec<BranchCoverage>,
+}
+
+/// Aggregated counts over one or more files.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CoverageTotals {
+    pub lines_found: u64,
+    pub lines_hit: u64,
+    pub branches_found: u64,
+    pub branches_hit: u64,
+    pub functions_found: u64,
+    pub functions_hit: u64,
+}
+
+impl FileCoverage {
+    pub fn totals(&self) -> CoverageTotals {
+        CoverageTotals {
+            lines_found: self.lines.len() as u64,
+            lines_hit: self.lines.values().filter(|hits| **hits > 0).count() as u64,
+            branches_found: 2 * self.branches.len() as u64,
+            branches_hit: self.branches.iter().map(|b| b.outcomes_hit()).sum(),
+            functions_found: self.functions.len() as u64,
+            functions_hit: self.functions.iter().filter(|f| f.hits > 0).count() as u64,
+        }
+    }
+
+    /// Branches starting at `line`, as `(outcomes hit, outcomes found)`.
+    fn branches_at(&self, line: u32) -> Option<(u64, u64)> {
+        let branches: Vec<_> = self.branches.iter().filter(|b| b.line == line).collect();
+        if branches.is_empty() {
+            return None;
+        }
+        Some((
+            branches.iter().map(|b| b.outcomes_hit()).sum(),
+            2 * branches.len() as u64,
+        ))
+    }
+}
+
+impl CoverageTotals {
+    pub fn add(&mut self, other: &CoverageTotals) {
+        self.lines_found += other.lines_found;
+        self.lines_hit += other.lines_hit;
+        self.branches_found += other.branches_found;
+        self.branches_hit += other.branches_hit;
+        self.functions_found += other.functions_found;
+        self.functions_hit += other.functions_hit;
+    }
+
+    pub fn line_rate(&self) -> f64 {
+        rate(self.lines_hit, self.lines_found)
+    }
+
+    pub fn branch_rate(&self) -> f64 {
+        rate(self.branches_hit, self.branches_found)
+    }
+
+    pub fn function_rate(&self) -> f64 {
+        rate(self.functions_hit, self.functions_found)
+    }
+}
+
+fn rate(hit: u64, found: u64) -> f64 {
+    if found == 0 {
+        1.0
+    } else {
+        hit as f64 / found as f64
+    }
+}
+
+/// Text of a source file along with the byte offset at which each line starts.
+struct SourceFile {
+    text: String,
+    line_starts: Vec<u32>,
+}
+
+impl SourceFile {
+    fn new(text: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                text.char_indices()
+                    .filter(|(_, c)| *c == '\n')
+                    .map(|(idx, _)| idx as u32 + 1),
+            )
+            .collect();
+        Self { text, line_starts }
+    }
+
+    /// Line number, starting at 1, of the byte at `offset`.
+    fn line(&self, offset: u32) -> u32 {
+        match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx as u32 + 1,
+            Err(idx) => idx as u32,
+        }
+    }
+}
+
+/// Source coverage of a set of modules, keyed by source file.
+pub struct CoverageReport {
+    /// Directory the relative file names recorded in source maps are resolved against.
+    source_root: PathBuf,
+    sources: BTreeMap<String, SourceFile>,
+    files: BTreeMap<String, FileCoverage>,
+}
+
+impl CoverageReport {
+    pub fn new<P: AsRef<Path>>(source_root: P) -> Self {
+        Self {
+            source_root: source_root.as_ref().to_path_buf(),
+            sources: BTreeMap::new(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Coverage keyed by the file names recorded in the source maps.
+    pub fn files(&self) -> &BTreeMap<String, FileCoverage> {
+        &self.files
+    }
+
+    pub fn totals(&self) -> CoverageTotals {
+        let mut totals = CoverageTotals::default();
+        for file in self.files.values() {
+            totals.add(&file.totals());
+        }
+        totals
+    }
+
+    /// Add the coverage of `module`, whose source locations are described by `source_map`, as
+    /// recorded in `coverage_map`. Each module must only be added once.
+    pub fn add_module(
+        &mut self,
+        module: &CompiledModule,
+        source_map: &SourceMap<Loc>,
+        coverage_map: &ExecCoverageMap,
+    ) -> Result<()> {
+        let module_id = module.self_id();
+        let module_map = coverage_map
+            .module_maps
+            .get(&(*module_id.address(), module_id.name().to_owned()));
+        let no_coverage = FunctionCoverage::new();
+
+        for (function_def_idx, function_def) in module.function_defs().iter().enumerate() {
+            let code = match &function_def.code {
+                // Native functions have no source lines to cover.
+                None => continue,
+                Some(code_unit) => &code_unit.code,
+            };
+            let fn_handle = module.function_handle_at(function_def.function);
+            let fn_name = module.identifier_at(fn_handle.name);
+            let qualified_name = format!("{}::{}", module_id.name(), fn_name);
+            let function_map = source_map
+                .get_function_source_map(FunctionDefinitionIndex(function_def_idx as u16))?;
+            let function_coverage = module_map
+                .and_then(|module_map| module_map.get_function_coverage(fn_name))
+                .unwrap_or(&no_coverage);
+            let count = |offset: CodeOffset| {
+                function_coverage
+                    .get(&(offset as u64))
+                    .copied()
+                    .unwrap_or(0)
+            };
+
+            let file_name = function_map.decl_location.file();
+            self.load_source(file_name)?;
+            let source = &self.sources[file_name];
+            let file = self
+                .files
+                .entry(file_name.to_string())
+                .or_insert_with(FileCoverage::default);
+
+            let decl_line = source.line(function_map.decl_location.span().start().0);
+            file.functions.push(FunctionLineCoverage {
+                name: qualified_name.clone(),
+                line: decl_line,
+                hits: count(0),
+            });
+            record_line(file, decl_line, count(0));
+
+            let outcomes = branch_outcomes(code, &count);
+            for offset in 0..code.len() {
+                let offset = offset as CodeOffset;
+                let loc = match function_map.get_code_location(offset) {
+                    Some(loc) if loc.file() == file_name => loc,
+                    _ => continue,
+                };
+                let line = source.line(loc.span().start().0);
+                record_line(file, line, count(offset));
+
+                if let Some(taken) = outcomes.get(&offset) {
+                    file.branches.push(BranchCoverage {
+                        line,
+                        function: qualified_name.clone(),
+                        offset,
+                        taken: *taken,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn load_source(&mut self, file_name: &str) -> Result<()> {
+        if !self.sources.contains_key(file_name) {
+            let path = self.resolve(file_name);
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format_err!("Unable to read source file {:?}: {}", path, e))?;
+            self.sources
+                .insert(file_name.to_string(), SourceFile::new(text));
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, file_name: &str) -> PathBuf {
+        self.source_root.join(file_name)
+    }
+
+    /// Write the report in the lcov tracefile format.
+    pub fn write_lcov<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for (file_name, file) in &self.files {
+            let totals = file.totals();
+            writeln!(w, "TN:")?;
+            writeln!(w, "SF:{}", self.resolve(file_name).display())?;
+            for function in &file.functions {
+                writeln!(w, "FN:{},{}", function.line, function.name)?;
+            }
+            for function in &file.functions {
+                writeln!(w, "FNDA:{},{}", function.hits, function.name)?;
+            }
+            writeln!(w, "FNF:{}", totals.functions_found)?;
+            writeln!(w, "FNH:{}", totals.functions_hit)?;
+            for (block, branch) in file.branches.iter().enumerate() {
+                for outcome in 0..2 {
+                    match branch.taken {
+                        Some(taken) => writeln!(
+                            w,
+                            "BRDA:{},{},{},{}",
+                            branch.line, block, outcome, taken[outcome]
+                        )?,
+                        None => writeln!(w, "BRDA:{},{},{},-", branch.line, block, outcome)?,
+                    }
+                }
+            }
+            writeln!(w, "BRF:{}", totals.branches_found)?;
+            writeln!(w, "BRH:{}", totals.branches_hit)?;
+            for (line, hits) in &file.lines {
+                writeln!(w, "DA:{},{}", line, hits)?;
+            }
+            writeln!(w, "LF:{}", totals.lines_found)?;
+            writeln!(w, "LH:{}", totals.lines_hit)?;
+            writeln!(w, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Write the report in the Cobertura XML format, with one package per source directory and
+    /// one class per source file.
+    pub fn write_cobertura<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut packages: BTreeMap<String, Vec<(&String, &FileCoverage)>> = BTreeMap::new();
+        for (file_name, file) in &self.files {
+            let package = Path::new(file_name)
+                .parent()
+                .map(|dir| dir.display().to_string())
+                .filter(|dir| !dir.is_empty())
+                .unwrap_or_else(|| ".".to_string());
+            packages.entry(package).or_default().push((file_name, file));
+        }
+        let totals = self.totals();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or(0);
+
+        writeln!(w, r#"<?xml version="1.0" ?>"#)?;
+        writeln!(
+            w,
+            r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+        )?;
+        writeln!(
+            w,
+            r#"<coverage line-rate="{:.4}" branch-rate="{:.4}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="0.1" timestamp="{}">"#,
+            totals.line_rate(),
+            totals.branch_rate(),
+            totals.lines_hit,
+            totals.lines_found,
+            totals.branches_hit,
+            totals.branches_found,
+            timestamp
+        )?;
+        writeln!(w, "  <sources>")?;
+        writeln!(
+            w,
+            "    <source>{}</source>",
+            escape(&self.source_root.display().to_string())
+        )?;
+        writeln!(w, "  </sources>")?;
+        writeln!(w, "  <packages>")?;
+        for (package, files) in packages {
+            let mut package_totals = CoverageTotals::default();
+            for (_, file) in &files {
+                package_totals.add(&file.totals());
+            }
+            writeln!(
+                w,
+                r#"    <package name="{}" line-rate="{:.4}" branch-rate="{:.4}" complexity="0">"#,
+                escape(&package),
+                package_totals.line_rate(),
+                package_totals.branch_rate()
+            )?;
+            writeln!(w, "      <classes>")?;
+            for (file_name, file) in files {
+                let file_totals = file.totals();
+                let class_name = Path::new(file_name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_else(|| file_name.clone());
+                writeln!(
+                    w,
+                    r#"        <class name="{}" filename="{}" line-rate="{:.4}" branch-rate="{:.4}" complexity="0">"#,
+                    escape(&class_name),
+                    escape(file_name),
+                    file_totals.line_rate(),
+                    file_totals.branch_rate()
+                )?;
+                writeln!(w, "          <methods/>")?;
+                writeln!(w, "          <lines>")?;
+                for (line, hits) in &file.lines {
+                    match file.branches_at(*line) {
+                        None => writeln!(
+                            w,
+                            r#"            <line number="{}" hits="{}" branch="false"/>"#,
+                            line, hits
+                        )?,
+                        Some((hit, found)) => writeln!(
+                            w,
+                            r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{:.0}% ({}/{})"/>"#,
+                            line,
+                            hits,
+                            rate(hit, found) * 100.0,
+                            hit,
+                            found
+                        )?,
+                    }
+                }
+                writeln!(w, "          </lines>")?;
+                writeln!(w, "        </class>")?;
+            }
+            writeln!(w, "      </classes>")?;
+            writeln!(w, "    </package>")?;
+        }
+        writeln!(w, "  </packages>")?;
+        writeln!(w, "</coverage>")?;
+        Ok(())
+    }
+
+    /// Write a static HTML report to `output_dir`: an `index.html` summarizing all files, and one
+    /// page per source file with the hit count of every line.
+    pub fn write_html(&self, output_dir: &Path) -> Result<()> {
+        fs::create_dir_all(output_dir)?;
+
+        let mut index = File::create(output_dir.join("index.html"))?;
+        write_html_header(&mut index, "Move Coverage")?;
+        writeln!(index, "<h1>Move Coverage</h1>")?;
+        writeln!(
+            index,
+            "<table class=\"summary\"><tr><th>File</th><th>Lines</th><th>Branches</th>\
+             <th>Functions</th></tr>"
+        )?;
+        for (file_name, file) in &self.files {
+            write_summary_row(
+                &mut index,
+                &format!(
+                    "<a href=\"{}\">{}</a>",
+                    html_page_name(file_name),
+                    escape(file_name)
+                ),
+                &file.totals(),
+            )?;
+        }
+        write_summary_row(&mut index, "<b>Total</b>", &self.totals())?;
+        writeln!(index, "</table>")?;
+        writeln!(index, "</body></html>")?;
+
+        for (file_name, file) in &self.files {
+            let mut page = File::create(output_dir.join(html_page_name(file_name)))?;
+            write_html_header(&mut page, file_name)?;
+            writeln!(
+                page,
+                "<h1><a href=\"index.html\">Move Coverage</a> - {}</h1>",
+                escape(file_name)
+            )?;
+            writeln!(
+                page,
+                "<table class=\"summary\"><tr><th>File</th><th>Lines</th><th>Branches</th>\
+                 <th>Functions</th></tr>"
+            )?;
+            write_summary_row(&mut page, &escape(file_name), &file.totals())?;
+            writeln!(page, "</table>")?;
+
+            let source = self
+                .sources
+                .get(file_name)
+                .ok_or_else(|| format_err!("Missing source text for {}", file_name))?;
+            writeln!(page, "<table class=\"source\">")?;
+            for (idx, text) in source.text.lines().enumerate() {
+                let line = idx as u32 + 1;
+                let (class, hits) = match file.lines.get(&line) {
+                    None => ("", String::new()),
+                    Some(0) => ("uncovered", "0".to_string()),
+                    Some(hits) => ("covered", hits.to_string()),
+                };
+                let branches = file
+                    .branches_at(line)
+                    .map(|(hit, found)| format!("{}/{}", hit, found))
+                    .unwrap_or_default();
+                writeln!(
+                    page,
+                    "<tr class=\"{}\"><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                     <td class=\"num\">{}</td><td><pre>{}</pre></td></tr>",
+                    class,
+                    line,
+                    hits,
+                    branches,
+                    escape(text)
+                )?;
+            }
+            writeln!(page, "</table>")?;
+            writeln!(page, "</body></html>")?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of times the jump and the fall through outcomes of each conditional branch in `code` were
+/// taken, keyed by the offset of the branch, given the number of times each instruction was
+/// executed. The outcomes of branches which were never executed are `None`.
+fn branch_outcomes<F: Fn(CodeOffset) -> u64>(
+    code: &[Bytecode],
+    count: F,
+) -> BTreeMap<CodeOffset, Option<[u64; 2]>> {
+    // The entry point counts as a predecessor of the first instruction.
+    let mut predecessors = vec![0usize; code.len()];
+    predecessors[0] += 1;
+    for offset in 0..code.len() {
+        for successor in Bytecode::get_successors(offset as CodeOffset, code) {
+            predecessors[successor as usize] += 1;
+        }
+    }
+
+    let mut outcomes = BTreeMap::new();
+    for (offset, instr) in code.iter().enumerate() {
+        let offset = offset as CodeOffset;
+        if let Bytecode::BrTrue(target) | Bytecode::BrFalse(target) = instr {
+            let executed = count(offset);
+            let taken = |dest: CodeOffset| {
+                if predecessors.get(dest as usize) == Some(&1) {
+                    count(dest)
+                } else {
+                    cmp::min(executed, count(dest))
+                }
+            };
+            let taken = if executed == 0 {
+                None
+            } else {
+                Some([taken(*target), taken(offset + 1)])
+            };
+            outcomes.insert(offset, taken);
+        }
+    }
+    outcomes
+}
+
+/// Record that `line` holds code executed `hits` times. Lines holding several instructions report
+/// the most executed one.
+fn record_line(file: &mut FileCoverage, line: u32, hits: u64) {
+    let entry = file.lines.entry(line).or_insert(0);
+    *entry = cmp::max(*entry, hits);
+}
+
+fn html_page_name(file_name: &str) -> String {
+    let name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.html", name)
+}
+
+fn write_html_header<W: Write>(w: &mut W, title: &str) -> io::Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html><head><meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>{}</title>", escape(title))?;
+    writeln!(
+        w,
+        "<style>\
+         body {{ font-family: sans-serif; }} \
+         table {{ border-collapse: collapse; }} \
+         .summary td, .summary th {{ padding: 2px 12px; border: 1px solid #ccc; }} \
+         .source pre {{ margin: 0; }} \
+         .source td {{ padding: 0 6px; }} \
+         .num {{ text-align: right; color: #666; }} \
+         .covered {{ background-color: #dfd; }} \
+         .uncovered {{ background-color: #fdd; }}\
+         </style>"
+    )?;
+    writeln!(w, "</head><body>")
+}
+
+fn write_summary_row<W: Write>(w: &mut W, name: &str, totals: &CoverageTotals) -> io::Result<()> {
+    writeln!(
+        w,
+        "<tr><td>{}</td><td>{:.2}% ({}/{})</td><td>{:.2}% ({}/{})</td><td>{:.2}% ({}/{})</td></tr>",
+        name,
+        totals.line_rate() * 100.0,
+        totals.lines_hit,
+        totals.lines_found,
+        totals.branch_rate() * 100.0,
+        totals.branches_hit,
+        totals.branches_found,
+        totals.function_rate() * 100.0,
+        totals.functions_hit,
+        totals.functions_found
+    )
+}
+
+/// Escape `text` for inclusion in HTML and XML documents.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+
+    fn sample_report() -> CoverageReport {
+        let file = FileCoverage {
+            lines: vec![(1, 2), (2, 2), (3, 0), (5, 0), (6, 0)]
+                .into_iter()
+                .collect(),
+            functions: vec![
+                FunctionLineCoverage {
+                    name: "M::f".to_string(),
+                    line: 1,
+                    hits: 2,
+                },
+                FunctionLineCoverage {
+                    name: "M::g".to_string(),
+                    line: 5,
+                    hits: 0,
+                },
+            ],
+            branches: vec![
+                BranchCoverage {
+                    line: 2,
+                    function: "M::f".to_string(),
+                    offset: 1,
+                    taken: Some([2, 0]),
+                },
+                BranchCoverage {
+                    line: 6,
+                    function: "M::g".to_string(),
+                    offset: 1,
+                    taken: None,
+                },
+            ],
+        };
+        let text = "fun f() {\n  if (x < y) {\n  }\n}\nfun g() {\n  if (b) {}\n}\n";
+        let mut report = CoverageReport::new("/root");
+        report.files.insert("sources/M.move".to_string(), file);
+        report.sources.insert(
+            "sources/M.move".to_string(),
+            SourceFile::new(text.to_string()),
+        );
+        report
+    }
+
+    #[test]
+    fn lcov() {
+        let mut output = vec![];
+        sample_report().write_lcov(&mut output).unwrap();
+        let expected = "\
+TN:
+SF:/root/sources/M.move
+FN:1,M::f
+FN:5,M::g
+FNDA:2,M::f
+FNDA:0,M::g
+FNF:2
+FNH:1
+BRDA:2,0,0,2
+BRDA:2,0,1,0
+BRDA:6,1,0,-
+BRDA:6,1,1,-
+BRF:4
+BRH:1
+DA:1,2
+DA:2,2
+DA:3,0
+DA:5,0
+DA:6,0
+LF:5
+LH:2
+end_of_record
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn cobertura() {
+        let mut output = vec![];
+        sample_report().write_cobertura(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // the timestamp is the only part of the output depending on the time of the run
+        let start = output.find("timestamp=\"").unwrap() + "timestamp=\"".len();
+        let end = start + output[start..].find('"').unwrap();
+        let output = format!("{}0{}", &output[..start], &output[end..]);
+        let expected = r#"<?xml version="1.0" ?>
+<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+<coverage line-rate="0.4000" branch-rate="0.2500" lines-covered="2" lines-valid="5" branches-covered="1" branches-valid="4" complexity="0" version="0.1" timestamp="0">
+  <sources>
+    <source>/root</source>
+  </sources>
+  <packages>
+    <package name="sources" line-rate="0.4000" branch-rate="0.2500" complexity="0">
+      <classes>
+        <class name="M" filename="sources/M.move" line-rate="0.4000" branch-rate="0.2500" complexity="0">
+          <methods/>
+          <lines>
+            <line number="1" hits="2" branch="false"/>
+            <line number="2" hits="2" branch="true" condition-coverage="50% (1/2)"/>
+            <line number="3" hits="0" branch="false"/>
+            <line number="5" hits="0" branch="false"/>
+            <line number="6" hits="0" branch="true" condition-coverage="0% (0/2)"/>
+          </lines>
+        </class>
+      </classes>
+    </package>
+  </packages>
+</coverage>
+"#;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn html() {
+        let dir = TempPath::new();
+        sample_report().write_html(dir.path()).unwrap();
+
+        let index = fs::read_to_string(dir.path().join("index.html")).unwrap();
+        assert!(index.contains(
+            "<tr><td><a href=\"sources_M_move.html\">sources/M.move</a></td>\
+             <td>40.00% (2/5)</td><td>25.00% (1/4)</td><td>50.00% (1/2)</td></tr>"
+        ));
+
+        let page = fs::read_to_string(dir.path().join("sources_M_move.html")).unwrap();
+        assert!(page.contains(
+            "<tr class=\"covered\"><td class=\"num\">2</td><td class=\"num\">2</td>\
+             <td class=\"num\">1/2</td><td><pre>  if (x &lt; y) {</pre></td></tr>"
+        ));
+        assert!(page.contains(
+            "<tr class=\"uncovered\"><td class=\"num\">6</td><td class=\"num\">0</td>\
+             <td class=\"num\">0/2</td><td><pre>  if (b) {}</pre></td></tr>"
+        ));
+        assert!(page.contains(
+            "<tr class=\"\"><td class=\"num\">4</td><td class=\"num\"></td>\
+             <td class=\"num\"></td><td><pre>}</pre></td></tr>"
+        ));
+    }
+
+    #[test]
+    fn branch_outcomes_inferred_from_targets() {
+        let counts =
+            |executions: &'static [u64]| move |offset: CodeOffset| executions[offset as usize];
+
+        // Both outcomes lead to instructions only reachable through the branch.
+        let code = vec![
+            Bytecode::CopyLoc(0),
+            Bytecode::BrFalse(4),
+            Bytecode::LdU64(1),
+            Bytecode::Branch(5),
+            Bytecode::LdU64(2),
+            Bytecode::Pop,
+            Bytecode::Ret,
+        ];
+        let outcomes = branch_outcomes(&code, counts(&[3, 3, 1, 1, 2, 3, 3]));
+        assert_eq!(outcomes, vec![(1, Some([2, 1]))].into_iter().collect());
+        let outcomes = branch_outcomes(&code, counts(&[0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(outcomes, vec![(1, None)].into_iter().collect());
+
+        // The jump target is also reached by falling through, so the jump count is only bounded by
+        // the executions of the branch.
+        let code = vec![
+            Bytecode::CopyLoc(0),
+            Bytecode::BrTrue(4),
+            Bytecode::LdU64(0),
+            Bytecode::Pop,
+            Bytecode::Ret,
+        ];
+        let outcomes = branch_outcomes(&code, counts(&[3, 3, 1, 1, 3]));
+        assert_eq!(outcomes, vec![(1, Some([3, 1]))].into_iter().collect());
+    }
+}
@@ -10,8 +10,9 @@ use super::{
 use crate::{
     error::WaitForTransactionError,
     views::{
-        AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, EventWithProofView,
-        MetadataView, StateProofView, TransactionView, TransactionsWithProofsView,
+        AccountStateWithProofView, AccountStatesWithProofView, AccountView, CurrencyInfoView,
+        EventView, EventWithProofView, MetadataView, StateProofView, TransactionView,
+        TransactionsWithProofsView,
     },
     Error, Result, Retry, State,
 };
@@ -231,6 +232,20 @@ impl Client {
         .await
     }
 
+    pub async fn get_accounts_state_with_proof(
+        &self,
+        addresses: Vec<AccountAddress>,
+        from_version: Option<u64>,
+        to_version: Option<u64>,
+    ) -> Result<Response<AccountStatesWithProofView>> {
+        self.send(MethodRequest::get_accounts_state_with_proof(
+            addresses,
+            from_version,
+            to_version,
+        ))
+        .await
+    }
+
     pub async fn get_transactions_with_proofs(
         &self,
         start_version: u64,
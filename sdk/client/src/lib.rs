@@ -67,6 +67,7 @@ pub enum Method {
     //
     GetStateProof,
     GetAccountStateWithProof,
+    GetAccountsStateWithProof,
     GetTransactionsWithProofs,
     GetEventsWithProofs,
 }
@@ -25,6 +25,7 @@ pub enum MethodRequest {
     //
     GetStateProof((u64,)),
     GetAccountStateWithProof(AccountAddress, Option<u64>, Option<u64>),
+    GetAccountsStateWithProof(Vec<AccountAddress>, Option<u64>, Option<u64>),
     GetTransactionsWithProofs(u64, u64),
     GetEventsWithProofs(String, u64, u64),
 }
@@ -95,6 +96,14 @@ impl MethodRequest {
         Self::GetAccountStateWithProof(address, from_version, to_version)
     }
 
+    pub fn get_accounts_state_with_proof(
+        addresses: Vec<AccountAddress>,
+        from_version: Option<u64>,
+        to_version: Option<u64>,
+    ) -> Self {
+        Self::GetAccountsStateWithProof(addresses, from_version, to_version)
+    }
+
     pub fn get_transactions_with_proofs(start_version: u64, limit: u64) -> Self {
         Self::GetTransactionsWithProofs(start_version, limit)
     }
@@ -116,6 +125,7 @@ impl MethodRequest {
             MethodRequest::GetNetworkStatus(_) => Method::GetNetworkStatus,
             MethodRequest::GetStateProof(_) => Method::GetStateProof,
             MethodRequest::GetAccountStateWithProof(_, _, _) => Method::GetAccountStateWithProof,
+            MethodRequest::GetAccountsStateWithProof(_, _, _) => Method::GetAccountsStateWithProof,
             MethodRequest::GetTransactionsWithProofs(_, _) => Method::GetTransactionsWithProofs,
             MethodRequest::GetEventsWithProofs(_, _, _) => Method::GetEventsWithProofs,
         }
@@ -4,8 +4,8 @@
 use super::Method;
 use crate::{
     views::{
-        AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, MetadataView,
-        StateProofView, TransactionView,
+        AccountStateWithProofView, AccountStatesWithProofView, AccountView, CurrencyInfoView,
+        EventView, MetadataView, StateProofView, TransactionView,
     },
     Error, State,
 };
@@ -55,6 +55,7 @@ pub enum MethodResponse {
 
     GetStateProof(StateProofView),
     GetAccountStateWithProof(AccountStateWithProofView),
+    GetAccountsStateWithProof(AccountStatesWithProofView),
     GetTransactionsWithProofs,
     GetEventsWithProofs,
 }
@@ -83,6 +84,9 @@ impl MethodResponse {
             Method::GetAccountStateWithProof => {
                 MethodResponse::GetAccountStateWithProof(serde_json::from_value(json)?)
             }
+            Method::GetAccountsStateWithProof => {
+                MethodResponse::GetAccountsStateWithProof(serde_json::from_value(json)?)
+            }
             Method::GetTransactionsWithProofs => MethodResponse::GetTransactionsWithProofs,
             Method::GetEventsWithProofs => MethodResponse::GetEventsWithProofs,
         };
@@ -103,6 +107,7 @@ impl MethodResponse {
             MethodResponse::GetNetworkStatus(_) => Method::GetNetworkStatus,
             MethodResponse::GetStateProof(_) => Method::GetStateProof,
             MethodResponse::GetAccountStateWithProof(_) => Method::GetAccountStateWithProof,
+            MethodResponse::GetAccountsStateWithProof(_) => Method::GetAccountsStateWithProof,
             MethodResponse::GetTransactionsWithProofs => Method::GetTransactionsWithProofs,
             MethodResponse::GetEventsWithProofs => Method::GetEventsWithProofs,
         }
@@ -17,11 +17,15 @@ static COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
 /// Metric counter keys.
 const CHECK_KEYS: &str = "check_keys";
 const CONSENSUS_KEY: &str = "consensus_key";
+const NETWORK_KEYS: &str = "network_keys";
 
 /// Metric counter states.
 pub const KEYS_STILL_FRESH: &[&str] = &[CHECK_KEYS, "keys_still_fresh"];
 pub const LIVENESS_ERROR_ENCOUNTERED: &[&str] = &[CHECK_KEYS, "liveness_error_encountered"];
+pub const NETWORK_KEYS_ROTATED_IN_STORAGE: &[&str] = &[NETWORK_KEYS, "rotated_in_storage"];
 pub const ROTATED_IN_STORAGE: &[&str] = &[CONSENSUS_KEY, "rotated_in_storage"];
+pub const SUBMITTED_NETWORK_KEY_TRANSACTION: &[&str] =
+    &[NETWORK_KEYS, "submitted_rotation_transaction"];
 pub const SUBMITTED_ROTATION_TRANSACTION: &[&str] =
     &[CONSENSUS_KEY, "submitted_rotation_transaction"];
 pub const WAITING_ON_RECONFIGURATION: &[&str] = &[CHECK_KEYS, "waiting_on_reconfiguration"];
@@ -34,7 +38,9 @@ pub fn initialize_all_metric_counters() {
     let metric_counter_states = &[
         KEYS_STILL_FRESH,
         LIVENESS_ERROR_ENCOUNTERED,
+        NETWORK_KEYS_ROTATED_IN_STORAGE,
         ROTATED_IN_STORAGE,
+        SUBMITTED_NETWORK_KEY_TRANSACTION,
         SUBMITTED_ROTATION_TRANSACTION,
         WAITING_ON_RECONFIGURATION,
         WAITING_ON_TRANSACTION_EXECUTION,
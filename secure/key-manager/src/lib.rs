@@ -1,11 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! The purpose of KeyManager is to rotate consensus key (and eventually the network key). It is not
-//! responsible for generating the first key and fails if the stores have not been properly setup.
-//! During rotation, it first updates the local store, then submits a transaction to rotate to the
-//! new key. After some period of time and upon restarts of the process, it will evaluate the
-//! current status of the system including:
+//! The purpose of KeyManager is to rotate the consensus key and, if enabled, the validator and full
+//! node network keys. It is not responsible for generating the first key and fails if the stores
+//! have not been properly setup. During rotation, it first updates the local store, then submits a
+//! transaction to rotate to the new key. After some period of time and upon restarts of the
+//! process, it will evaluate the current status of the system including:
 //! * last rotation time, and rotate if it is too long ago
 //! * if the latest key in the store matches the latest key in the ValidatorConfig, upon mismatch
 //! it will try to submit a transaction to update the ValidatorConfig to the current key in the
@@ -14,6 +14,13 @@
 //! evaluates the current time from the last reconfiguration and logs that delta with greater
 //! levels of severity depending on the delta.
 //!
+//! Network keys are rotated in two steps, as peers only accept connections from the network keys
+//! they find in the ValidatorSet (e.g., via simple-onchain-discovery), and a node only picks up a
+//! rotated network key upon restart. First, the new keys are registered in the ValidatorConfig
+//! alongside the previous keys, so that peers accept either key once the ValidatorSet has been
+//! updated. Then, once the new keys are part of the ValidatorSet and a grace period has passed
+//! since that reconfiguration, the previous keys are removed from the ValidatorConfig.
+//!
 //! KeyManager talks to Diem via the DiemInterface that may either be a direct link into
 //! `DiemDB`/`Executor`, JSON-RPC, or some other concoction.
 //! KeyManager talks to its own storage through the `DiemSecureStorage::Storage trait.
@@ -21,23 +28,29 @@
 
 use crate::{
     counters::{
-        KEYS_STILL_FRESH, LIVENESS_ERROR_ENCOUNTERED, ROTATED_IN_STORAGE,
-        SUBMITTED_ROTATION_TRANSACTION, UNEXPECTED_ERROR_ENCOUNTERED, WAITING_ON_RECONFIGURATION,
-        WAITING_ON_TRANSACTION_EXECUTION,
+        KEYS_STILL_FRESH, LIVENESS_ERROR_ENCOUNTERED, NETWORK_KEYS_ROTATED_IN_STORAGE,
+        ROTATED_IN_STORAGE, SUBMITTED_NETWORK_KEY_TRANSACTION, SUBMITTED_ROTATION_TRANSACTION,
+        UNEXPECTED_ERROR_ENCOUNTERED, WAITING_ON_RECONFIGURATION, WAITING_ON_TRANSACTION_EXECUTION,
     },
     diem_interface::DiemInterface,
     logging::{LogEntry, LogEvent, LogSchema},
 };
-use diem_crypto::ed25519::Ed25519PublicKey;
-use diem_global_constants::{CONSENSUS_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT};
+use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
+use diem_global_constants::{
+    CONSENSUS_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT,
+    VALIDATOR_NETWORK_KEY,
+};
 use diem_logger::prelude::*;
+use diem_network_address_encryption::Encryptor;
 use diem_secure_storage::{CryptoStorage, KVStorage};
 use diem_time_service::{TimeService, TimeServiceTrait};
 use diem_types::{
     account_address::AccountAddress,
     account_config::XUS_NAME,
     chain_id::ChainId,
+    network_address::NetworkAddress,
     transaction::{RawTransaction, SignedTransaction, Transaction},
+    validator_config::ValidatorConfig,
 };
 use std::time::Duration;
 use thiserror::Error;
@@ -65,6 +78,14 @@ pub enum Action {
     WaitForReconfiguration,
     /// Storage and the blockchain are inconsistent, wait for rotation transaction execution.
     WaitForTransactionExecution,
+    /// Sufficient time has passed for another network key rotation (network keys are stale).
+    FullNetworkKeyRotation,
+    /// Storage and the blockchain network keys are inconsistent, submit a new network key
+    /// rotation transaction.
+    SubmitNetworkKeyRotationTransaction,
+    /// The new network keys are part of the validator set and the grace period has passed since
+    /// the reconfiguration, stop advertising the previous network keys.
+    RetirePreviousNetworkKeys,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -82,6 +103,8 @@ pub enum Error {
     LivenessError(u64, u64),
     #[error("Unable to retrieve the account address: {0}, storage error: {1}")]
     MissingAccountAddress(String, String),
+    #[error("Unable to read the network addresses: {0}")]
+    NetworkAddressError(String),
     #[error("Storage error: {0}")]
     StorageError(String),
     #[error("ValidatorInfo not found in ValidatorConfig: {0}")]
@@ -108,6 +131,12 @@ impl From<bcs::Error> for Error {
     }
 }
 
+impl From<diem_network_address_encryption::Error> for Error {
+    fn from(error: diem_network_address_encryption::Error) -> Self {
+        Error::NetworkAddressError(error.to_string())
+    }
+}
+
 impl From<diem_secure_storage::Error> for Error {
     fn from(error: diem_secure_storage::Error) -> Self {
         Error::StorageError(error.to_string())
@@ -123,6 +152,14 @@ pub struct KeyManager<LI, S> {
     sleep_period_secs: u64,    // The amount of time to sleep between key management checks
     txn_expiration_secs: u64,  // The time after which a rotation transaction expires
     chain_id: ChainId,
+    network_key_rotation: Option<NetworkKeyRotation>,
+}
+
+/// The configuration required to rotate the validator and full node network keys.
+struct NetworkKeyRotation {
+    encryptor: Encryptor, // Decrypts and encrypts the validator network addresses
+    rotation_period_secs: u64, // The frequency by which to rotate the network keys
+    grace_period_secs: u64, // The time during which the previous network keys remain on-chain
 }
 
 impl<LI, S> KeyManager<LI, S>
@@ -148,9 +185,26 @@ where
             sleep_period_secs,
             txn_expiration_secs,
             chain_id,
+            network_key_rotation: None,
         }
     }
 
+    /// Enables the rotation of the validator and full node network keys. The encryptor must hold
+    /// the keys used to encrypt the validator network addresses registered on-chain.
+    pub fn with_network_key_rotation(
+        mut self,
+        encryptor: Encryptor,
+        rotation_period_secs: u64,
+        grace_period_secs: u64,
+    ) -> Self {
+        self.network_key_rotation = Some(NetworkKeyRotation {
+            encryptor,
+            rotation_period_secs,
+            grace_period_secs,
+        });
+        self
+    }
+
     /// Begins execution of the key manager by running an infinite loop where the key manager will
     /// periodically wake up, verify the state of the validator keys (e.g., the consensus key), and
     /// initiate a key rotation when required. If something goes wrong that we can't handle, an
@@ -284,17 +338,118 @@ where
             self.chain_id,
         );
 
+        self.sign_and_submit_transaction(txn)?;
+
+        info!(LogSchema::new(LogEntry::TransactionSubmitted).event(LogEvent::Success));
+        counters::increment_metric_counter(SUBMITTED_ROTATION_TRANSACTION);
+
+        Ok(consensus_key)
+    }
+
+    pub fn last_network_key_rotation(&self) -> Result<u64, Error> {
+        let validator_rotation = self
+            .storage
+            .get_public_key(VALIDATOR_NETWORK_KEY)?
+            .last_update;
+        let fullnode_rotation = self
+            .storage
+            .get_public_key(FULLNODE_NETWORK_KEY)?
+            .last_update;
+        Ok(std::cmp::min(validator_rotation, fullnode_rotation))
+    }
+
+    pub fn rotate_network_keys(&mut self) -> Result<(), Error> {
+        info!(LogSchema::new(LogEntry::NetworkKeysRotatedInStorage).event(LogEvent::Pending));
+        self.storage.rotate_key(VALIDATOR_NETWORK_KEY)?;
+        self.storage.rotate_key(FULLNODE_NETWORK_KEY)?;
+        let (validator_network_key, _) = self.network_key_from_storage(VALIDATOR_NETWORK_KEY)?;
+        info!(LogSchema::new(LogEntry::NetworkKeysRotatedInStorage)
+            .event(LogEvent::Success)
+            .network_key(&validator_network_key));
+        counters::increment_metric_counter(NETWORK_KEYS_ROTATED_IN_STORAGE);
+
+        self.submit_network_key_transaction(true)
+    }
+
+    /// Submits a transaction registering the network keys held in secure storage on-chain. Every
+    /// network address registered on-chain with a different key is registered again with the key
+    /// in storage. If `retain_previous_keys` is set, the addresses with the previous keys remain
+    /// registered, otherwise they are removed.
+    pub fn submit_network_key_transaction(
+        &mut self,
+        retain_previous_keys: bool,
+    ) -> Result<(), Error> {
+        info!(LogSchema::new(LogEntry::TransactionSubmitted).event(LogEvent::Pending));
+
+        let encryptor = &self
+            .network_key_rotation
+            .as_ref()
+            .ok_or_else(|| Error::UnknownError("Network key rotation is not enabled".into()))?
+            .encryptor;
+        let operator_account = self.get_account_from_storage(OPERATOR_ACCOUNT)?;
+        let seq_id = self.diem.retrieve_sequence_number(operator_account)?;
+        let expiration = self.time_service.now_secs() + self.txn_expiration_secs;
+
+        // Update the network addresses as registered on-chain with the keys in storage
+        let owner_account = self.get_account_from_storage(OWNER_ACCOUNT)?;
+        let validator_config = self.diem.retrieve_validator_config(owner_account)?;
+        let (validator_addresses, fullnode_addresses) =
+            network_addresses(encryptor, owner_account, &validator_config)?;
+        let (validator_network_key, _) = self.network_key_from_storage(VALIDATOR_NETWORK_KEY)?;
+        let (fullnode_network_key, _) = self.network_key_from_storage(FULLNODE_NETWORK_KEY)?;
+        let validator_addresses = advertise_network_key(
+            &validator_addresses,
+            &validator_network_key,
+            retain_previous_keys,
+        );
+        let fullnode_addresses = advertise_network_key(
+            &fullnode_addresses,
+            &fullnode_network_key,
+            retain_previous_keys,
+        );
+
+        // The transaction reconfigures, so the validator config will be updated by the next
+        // sequence number.
+        let validator_addresses =
+            encryptor.encrypt(&validator_addresses, owner_account, seq_id + 1)?;
+        let fullnode_addresses = bcs::to_bytes(&fullnode_addresses)?;
+
+        let txn = build_rotation_transaction(
+            owner_account,
+            operator_account,
+            seq_id,
+            &validator_config.consensus_public_key,
+            validator_addresses,
+            fullnode_addresses,
+            expiration,
+            self.chain_id,
+        );
+        self.sign_and_submit_transaction(txn)?;
+
+        info!(LogSchema::new(LogEntry::TransactionSubmitted).event(LogEvent::Success));
+        counters::increment_metric_counter(SUBMITTED_NETWORK_KEY_TRANSACTION);
+
+        Ok(())
+    }
+
+    fn sign_and_submit_transaction(&self, txn: RawTransaction) -> Result<(), Error> {
         let operator_pubkey = self.storage.get_public_key(OPERATOR_KEY)?.public_key;
         let txn_signature = self.storage.sign(OPERATOR_KEY, &txn)?;
         let signed_txn = SignedTransaction::new(txn, operator_pubkey, txn_signature);
 
         self.diem
-            .submit_transaction(Transaction::UserTransaction(signed_txn))?;
-
-        info!(LogSchema::new(LogEntry::TransactionSubmitted).event(LogEvent::Success));
-        counters::increment_metric_counter(SUBMITTED_ROTATION_TRANSACTION);
+            .submit_transaction(Transaction::UserTransaction(signed_txn))
+    }
 
-        Ok(consensus_key)
+    /// Returns the x25519 network key held in storage under the given name, along with the time
+    /// it was last rotated.
+    fn network_key_from_storage(&self, key_name: &str) -> Result<(x25519::PublicKey, u64), Error> {
+        let response = self.storage.get_public_key(key_name)?;
+        let network_key = x25519::PublicKey::from_ed25519_public_bytes(
+            &response.public_key.to_bytes(),
+        )
+        .map_err(|e| Error::StorageError(format!("Invalid network key {}: {}", key_name, e)))?;
+        Ok((network_key, response.last_update))
     }
 
     /// Ensures that the diem_timestamp() value registered on-chain is strictly monotonically
@@ -346,6 +501,72 @@ where
 
         if last_rotation + self.rotation_period_secs <= self.time_service.now_secs() {
             Ok(Action::FullKeyRotation)
+        } else {
+            self.evaluate_network_status()
+        }
+    }
+
+    /// Evaluates the current status of the network keys by comparing the keys held in secure
+    /// storage to the network addresses registered on-chain. This is a no-op unless network key
+    /// rotation is enabled.
+    pub fn evaluate_network_status(&self) -> Result<Action, Error> {
+        let network_key_rotation = match &self.network_key_rotation {
+            Some(network_key_rotation) => network_key_rotation,
+            None => return Ok(Action::NoAction),
+        };
+        let now = self.time_service.now_secs();
+
+        // Compare the network addresses in the validator config to the validator set
+        let owner_account = self.get_account_from_storage(OWNER_ACCOUNT)?;
+        let validator_config = self.diem.retrieve_validator_config(owner_account)?;
+        let validator_info = self.diem.retrieve_validator_info(owner_account)?;
+        if &validator_config != validator_info.config() {
+            return Ok(Action::WaitForReconfiguration);
+        }
+
+        let (validator_addresses, fullnode_addresses) = network_addresses(
+            &network_key_rotation.encryptor,
+            owner_account,
+            &validator_config,
+        )?;
+        let mut previous_keys_registered = false;
+        for (key_name, addresses) in &[
+            (VALIDATOR_NETWORK_KEY, validator_addresses),
+            (FULLNODE_NETWORK_KEY, fullnode_addresses),
+        ] {
+            let (storage_key, last_update) = self.network_key_from_storage(key_name)?;
+            let config_keys: Vec<_> = addresses
+                .iter()
+                .filter_map(NetworkAddress::find_noise_proto)
+                .collect();
+
+            // Compare the validator config to secure storage
+            if !config_keys.contains(&storage_key) {
+                return if last_update + self.txn_expiration_secs <= now {
+                    Ok(Action::SubmitNetworkKeyRotationTransaction)
+                } else {
+                    Ok(Action::WaitForTransactionExecution)
+                };
+            }
+
+            // Check if the previous keys are still registered after a rotation
+            previous_keys_registered |= config_keys.iter().any(|key| key != &storage_key);
+        }
+
+        // The validator set holds the new keys, so the grace period runs from the last
+        // reconfiguration rather than from the rotation, which may have taken a while to execute.
+        if previous_keys_registered {
+            let grace_period_end =
+                self.last_reconfiguration()? + network_key_rotation.grace_period_secs;
+            return if grace_period_end <= self.diem_timestamp()? {
+                Ok(Action::RetirePreviousNetworkKeys)
+            } else {
+                Ok(Action::NoAction)
+            };
+        }
+
+        if self.last_network_key_rotation()? + network_key_rotation.rotation_period_secs <= now {
+            Ok(Action::FullNetworkKeyRotation)
         } else {
             Ok(Action::NoAction)
         }
@@ -375,6 +596,23 @@ where
                 warn!(LogSchema::new(LogEntry::WaitForTransactionExecution));
                 counters::increment_metric_counter(WAITING_ON_TRANSACTION_EXECUTION);
             }
+            Action::FullNetworkKeyRotation => {
+                info!(LogSchema::new(LogEntry::FullNetworkKeyRotation).event(LogEvent::Pending));
+                self.rotate_network_keys()?;
+                info!(LogSchema::new(LogEntry::FullNetworkKeyRotation).event(LogEvent::Success));
+            }
+            Action::SubmitNetworkKeyRotationTransaction => {
+                info!(LogSchema::new(LogEntry::NetworkKeyTransactionResubmission)
+                    .event(LogEvent::Pending));
+                self.submit_network_key_transaction(true)?;
+                info!(LogSchema::new(LogEntry::NetworkKeyTransactionResubmission)
+                    .event(LogEvent::Success));
+            }
+            Action::RetirePreviousNetworkKeys => {
+                info!(LogSchema::new(LogEntry::RetirePreviousNetworkKeys).event(LogEvent::Pending));
+                self.submit_network_key_transaction(false)?;
+                info!(LogSchema::new(LogEntry::RetirePreviousNetworkKeys).event(LogEvent::Success));
+            }
         };
 
         Ok(())
@@ -388,6 +626,51 @@ where
     }
 }
 
+/// Returns the validator and full node network addresses registered in the given validator config.
+fn network_addresses(
+    encryptor: &Encryptor,
+    owner_account: AccountAddress,
+    validator_config: &ValidatorConfig,
+) -> Result<(Vec<NetworkAddress>, Vec<NetworkAddress>), Error> {
+    let validator_addresses =
+        encryptor.decrypt(&validator_config.validator_network_addresses, owner_account)?;
+    let fullnode_addresses = validator_config
+        .fullnode_network_addresses()
+        .map_err(|e| Error::NetworkAddressError(e.to_string()))?;
+    Ok((validator_addresses, fullnode_addresses))
+}
+
+/// Returns the given network addresses updated to use the given network key: every address using
+/// a different key is replaced by the same address using the new key. If `retain_previous_keys` is
+/// set, the addresses using a different key are kept after the updated addresses.
+fn advertise_network_key(
+    addresses: &[NetworkAddress],
+    network_key: &x25519::PublicKey,
+    retain_previous_keys: bool,
+) -> Vec<NetworkAddress> {
+    let mut updated_addresses: Vec<NetworkAddress> = Vec::new();
+    let mut previous_addresses = Vec::new();
+    for address in addresses {
+        let updated_address = match address.find_noise_proto() {
+            Some(previous_key) if &previous_key != network_key => {
+                previous_addresses.push(address.clone());
+                let mut updated_address = address.clone();
+                updated_address.rotate_noise_public_key(&previous_key, network_key);
+                updated_address
+            }
+            _ => address.clone(),
+        };
+        if !updated_addresses.contains(&updated_address) {
+            updated_addresses.push(updated_address);
+        }
+    }
+
+    if retain_previous_keys {
+        updated_addresses.extend(previous_addresses);
+    }
+    updated_addresses
+}
+
 pub fn build_rotation_transaction(
     owner_address: AccountAddress,
     operator_address: AccountAddress,
@@ -13,6 +13,7 @@ use diem_key_manager::{
     Error, KeyManager,
 };
 use diem_logger::info;
+use diem_network_address_encryption::Encryptor;
 use diem_secure_push_metrics::MetricsPusher;
 use diem_secure_storage::Storage;
 use diem_time_service::TimeService;
@@ -72,6 +73,16 @@ fn create_and_execute_key_manager(key_manager_config: KeyManagerConfig) -> Resul
         key_manager_config.txn_expiration_secs,
         key_manager_config.chain_id,
     );
+    if let Some(rotation_period_secs) = key_manager_config.network_key_rotation_period_secs {
+        let encryptor_storage: Storage = (&key_manager_config.secure_backend)
+            .try_into()
+            .expect("Unable to initialize storage");
+        key_manager = key_manager.with_network_key_rotation(
+            Encryptor::new(encryptor_storage),
+            rotation_period_secs,
+            key_manager_config.network_key_grace_period_secs,
+        );
+    }
 
     info!(LogSchema::new(LogEntry::Initialized)
         .event(LogEvent::Success)
@@ -2,20 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    diem_interface::JsonRpcDiemInterface, Action, DiemInterface, Error, KeyManager, GAS_UNIT_PRICE,
-    MAX_GAS_AMOUNT,
+    advertise_network_key, diem_interface::JsonRpcDiemInterface, Action, DiemInterface, Error,
+    KeyManager, GAS_UNIT_PRICE, MAX_GAS_AMOUNT,
 };
 use anyhow::Result;
 use diem_config::{
-    config::{KeyManagerConfig, NodeConfig},
+    config::{KeyManagerConfig, NodeConfig, Peer, PeerRole, HANDSHAKE_VERSION},
     utils,
     utils::get_genesis_txn,
 };
-use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
+use diem_crypto::{ed25519::Ed25519PrivateKey, x25519, HashValue, PrivateKey, Uniform};
 use diem_global_constants::{
-    CONSENSUS_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY,
+    CONSENSUS_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY,
+    VALIDATOR_NETWORK_KEY,
 };
-use diem_secure_storage::{InMemoryStorage, KVStorage};
+use diem_network_address_encryption::Encryptor;
+use diem_secure_storage::{CryptoStorage, InMemoryStorage, KVStorage, Storage};
 use diem_time_service::{MockTimeService, TimeService, TimeServiceTrait};
 use diem_types::{
     account_address::AccountAddress,
@@ -26,6 +28,7 @@ use diem_types::{
     block_metadata::{BlockMetadata, DiemBlockResource},
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     mempool_status::{MempoolStatus, MempoolStatusCode},
+    network_address::NetworkAddress,
     on_chain_config::{ConfigurationResource, ValidatorSet},
     transaction::{RawTransaction, Transaction},
     validator_config::ValidatorConfig,
@@ -37,7 +40,13 @@ use executor::Executor;
 use executor_types::BlockExecutor;
 use futures::{channel::mpsc::channel, StreamExt};
 use rand::{rngs::StdRng, SeedableRng};
-use std::{cell::RefCell, collections::BTreeMap, convert::TryFrom, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    convert::{TryFrom, TryInto},
+    str::FromStr,
+    sync::Arc,
+};
 use storage_interface::{DbReader, DbReaderWriter};
 use tokio::runtime::Runtime;
 use vm_validator::{
@@ -45,6 +54,8 @@ use vm_validator::{
 };
 
 const TXN_EXPIRATION_SECS: u64 = 100;
+const NETWORK_KEY_ROTATION_PERIOD_SECS: u64 = 86_400;
+const NETWORK_KEY_GRACE_PERIOD_SECS: u64 = 7_200;
 
 struct Node<T: DiemInterface> {
     executor: Executor<DiemVM>,
@@ -124,6 +135,32 @@ impl<T: DiemInterface> Node<T> {
             .unwrap()
             .value
     }
+
+    fn get_network_key_from_storage(&mut self, key_name: &str) -> x25519::PublicKey {
+        let public_key = self.get_key_from_storage(key_name).public_key();
+        x25519::PublicKey::from_ed25519_public_bytes(&public_key.to_bytes()).unwrap()
+    }
+
+    // Returns the keys accepted by peers for this node on the validator and full node networks,
+    // as derived from the validator set by simple-onchain-discovery.
+    fn get_accepted_network_keys(
+        &mut self,
+    ) -> (HashSet<x25519::PublicKey>, HashSet<x25519::PublicKey>) {
+        let owner_account = self.get_account_from_storage(OWNER_ACCOUNT);
+        let validator_config = self
+            .diem
+            .retrieve_validator_info(owner_account)
+            .unwrap()
+            .into_config();
+        let validator_addresses = Encryptor::for_testing()
+            .decrypt(&validator_config.validator_network_addresses, owner_account)
+            .unwrap();
+        let fullnode_addresses = validator_config.fullnode_network_addresses().unwrap();
+        (
+            Peer::from_addrs(PeerRole::Validator, validator_addresses).keys,
+            Peer::from_addrs(PeerRole::ValidatorFullNode, fullnode_addresses).keys,
+        )
+    }
 }
 
 /// The struct below is a DiemInterface wrapper that exposes several additional methods to better
@@ -368,6 +405,16 @@ fn setup_node<T: DiemInterface + Clone>(
     Node::new(executor, diem_test_harness, key_manager, time.into_mock())
 }
 
+// Enables network key rotation on the key manager of the given node.
+fn enable_network_key_rotation<T: DiemInterface>(mut node: Node<T>) -> Node<T> {
+    node.key_manager = node.key_manager.with_network_key_rotation(
+        Encryptor::for_testing(),
+        NETWORK_KEY_ROTATION_PERIOD_SECS,
+        NETWORK_KEY_GRACE_PERIOD_SECS,
+    );
+    node
+}
+
 // Creates and returns a secure storage implementation (based on an in memory storage engine) for
 // testing. As part of the initialization, the consensus key is created.
 fn setup_secure_storage(config: &NodeConfig, time: TimeService) -> InMemoryStorage {
@@ -397,6 +444,20 @@ fn setup_secure_storage(config: &NodeConfig, time: TimeService) -> InMemoryStora
         .set(crate::CONSENSUS_KEY, consensus_prikey)
         .unwrap();
 
+    // Initialize the network keys in storage
+    let genesis_storage: Storage = (&config
+        .validator_network
+        .as_ref()
+        .unwrap()
+        .identity_from_storage()
+        .backend)
+        .try_into()
+        .unwrap();
+    for key_name in &[VALIDATOR_NETWORK_KEY, FULLNODE_NETWORK_KEY] {
+        let network_prikey = genesis_storage.export_private_key(key_name).unwrap();
+        sec_storage.set(key_name, network_prikey).unwrap();
+    }
+
     sec_storage
 }
 
@@ -871,3 +932,215 @@ fn verify_missing_data<T: DiemInterface>(mut node: Node<T>) {
         ),
     }
 }
+
+#[test]
+// This tests that the key manager rotates the network keys on schedule, and that the previous
+// network keys remain accepted by peers until the new keys are part of the validator set and the
+// grace period has passed.
+fn test_network_key_rotation() {
+    // Test the mock diem interface implementation
+    let node = setup_node_using_test_mocks();
+    verify_network_key_rotation(enable_network_key_rotation(node));
+
+    // Test the json diem interface implementation
+    let (node, _runtime) = setup_node_using_json_rpc();
+    verify_network_key_rotation(enable_network_key_rotation(node));
+}
+
+fn verify_network_key_rotation<T: DiemInterface>(mut node: Node<T>) {
+    // Verify correct initial state (i.e., peers accept the network keys in storage)
+    let validator_key = node.get_network_key_from_storage(VALIDATOR_NETWORK_KEY);
+    let fullnode_key = node.get_network_key_from_storage(FULLNODE_NETWORK_KEY);
+    assert_eq!(
+        (key_set(&[validator_key]), key_set(&[fullnode_key])),
+        node.get_accepted_network_keys()
+    );
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::NoAction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Verify network key rotation required after enough time
+    node.time.advance_secs(NETWORK_KEY_ROTATION_PERIOD_SECS);
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::FullNetworkKeyRotation,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Verify a single execution iteration will rotate the network keys and wait for the rotation
+    // transaction to be executed
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    let new_validator_key = node.get_network_key_from_storage(VALIDATOR_NETWORK_KEY);
+    let new_fullnode_key = node.get_network_key_from_storage(FULLNODE_NETWORK_KEY);
+    assert_ne!(validator_key, new_validator_key);
+    assert_ne!(fullnode_key, new_fullnode_key);
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::WaitForTransactionExecution,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Execute the rotation and verify peers accept both the previous and the new network keys,
+    // while the consensus key is unchanged
+    node.execute_and_commit(node.diem.take_all_transactions());
+    assert_eq!(
+        (
+            key_set(&[validator_key, new_validator_key]),
+            key_set(&[fullnode_key, new_fullnode_key])
+        ),
+        node.get_accepted_network_keys()
+    );
+    node.key_manager.compare_storage_to_config().unwrap();
+    node.key_manager.compare_info_to_config().unwrap();
+
+    // Verify the previous network keys remain registered during the grace period
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::NoAction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Verify the previous network keys are removed once the grace period has passed
+    node.time.advance_secs(NETWORK_KEY_GRACE_PERIOD_SECS);
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::RetirePreviousNetworkKeys,
+        node.key_manager.evaluate_status().unwrap()
+    );
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    node.execute_and_commit(node.diem.take_all_transactions());
+    assert_eq!(
+        (key_set(&[new_validator_key]), key_set(&[new_fullnode_key])),
+        node.get_accepted_network_keys()
+    );
+    assert_eq!(
+        Action::NoAction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+}
+
+#[test]
+// This tests the key manager's ability to detect network keys in storage that are not registered
+// on-chain, and to resubmit the network key rotation transaction once it has expired.
+fn test_network_key_storage_blockchain_mismatch() {
+    // Test the mock diem interface implementation
+    let node = setup_node_using_test_mocks();
+    verify_network_key_storage_blockchain_mismatch(enable_network_key_rotation(node));
+
+    // Test the json diem interface implementation
+    let (node, _runtime) = setup_node_using_json_rpc();
+    verify_network_key_storage_blockchain_mismatch(enable_network_key_rotation(node));
+}
+
+fn verify_network_key_storage_blockchain_mismatch<T: DiemInterface>(mut node: Node<T>) {
+    let (_, key_manager_config) = get_test_configs();
+    let validator_key = node.get_network_key_from_storage(VALIDATOR_NETWORK_KEY);
+
+    // Rotate the network keys locally in storage but drop the rotation transaction
+    node.update_diem_timestamp();
+    node.key_manager.rotate_network_keys().unwrap();
+    node.diem.take_all_transactions();
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::WaitForTransactionExecution,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Verify the transaction is resubmitted once expired, and that executing it registers the
+    // new network keys alongside the previous ones
+    node.time
+        .advance_secs(key_manager_config.txn_expiration_secs);
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::SubmitNetworkKeyRotationTransaction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    node.execute_and_commit(node.diem.take_all_transactions());
+    let new_validator_key = node.get_network_key_from_storage(VALIDATOR_NETWORK_KEY);
+    let (validator_keys, _) = node.get_accepted_network_keys();
+    assert_eq!(key_set(&[validator_key, new_validator_key]), validator_keys);
+}
+
+#[test]
+// This tests that the previous network keys remain registered for the whole grace period after
+// the new keys are added to the validator set, even if the reconfiguration is delayed beyond the
+// grace period.
+fn test_network_key_delayed_reconfiguration() {
+    let node = setup_node_using_test_mocks();
+    verify_network_key_delayed_reconfiguration(enable_network_key_rotation(node));
+}
+
+fn verify_network_key_delayed_reconfiguration<T: DiemInterface>(mut node: Node<T>) {
+    let validator_key = node.get_network_key_from_storage(VALIDATOR_NETWORK_KEY);
+
+    // Rotate the network keys but hold off the rotation transaction beyond the grace period
+    node.update_diem_timestamp();
+    node.key_manager.rotate_network_keys().unwrap();
+    node.diem.take_all_transactions();
+    node.time.advance_secs(NETWORK_KEY_GRACE_PERIOD_SECS);
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::SubmitNetworkKeyRotationTransaction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+    node.update_diem_timestamp();
+    node.key_manager.execute_once().unwrap();
+    node.execute_and_commit(node.diem.take_all_transactions());
+    let new_validator_key = node.get_network_key_from_storage(VALIDATOR_NETWORK_KEY);
+    let (validator_keys, _) = node.get_accepted_network_keys();
+    assert_eq!(key_set(&[validator_key, new_validator_key]), validator_keys);
+
+    // Verify the previous network keys are not retired right after the reconfiguration
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::NoAction,
+        node.key_manager.evaluate_status().unwrap()
+    );
+
+    // Verify the previous network keys are retired once the grace period has passed since the
+    // reconfiguration
+    node.time.advance_secs(NETWORK_KEY_GRACE_PERIOD_SECS);
+    node.update_diem_timestamp();
+    assert_eq!(
+        Action::RetirePreviousNetworkKeys,
+        node.key_manager.evaluate_status().unwrap()
+    );
+}
+
+#[test]
+fn test_advertise_network_key() {
+    let mut rng = StdRng::from_seed([45u8; 32]);
+    let previous_key = x25519::PrivateKey::generate(&mut rng).public_key();
+    let network_key = x25519::PrivateKey::generate(&mut rng).public_key();
+    let address = NetworkAddress::from_str("/ip4/127.0.0.1/tcp/6180").unwrap();
+    let previous_address = address
+        .clone()
+        .append_prod_protos(previous_key, HANDSHAKE_VERSION);
+    let new_address = address.append_prod_protos(network_key, HANDSHAKE_VERSION);
+
+    // Addresses using a different key are updated, and optionally retained
+    assert_eq!(
+        vec![new_address.clone(), previous_address.clone()],
+        advertise_network_key(&[previous_address.clone()], &network_key, true)
+    );
+    assert_eq!(
+        vec![new_address.clone()],
+        advertise_network_key(&[previous_address.clone()], &network_key, false)
+    );
+
+    // Addresses already using the network key are not duplicated
+    assert_eq!(
+        vec![new_address.clone()],
+        advertise_network_key(&[new_address, previous_address], &network_key, false)
+    );
+}
+
+fn key_set(keys: &[x25519::PublicKey]) -> HashSet<x25519::PublicKey> {
+    keys.iter().cloned().collect()
+}
@@ -14,12 +14,23 @@
 //!
 //! Internally both the client and server leverage a NetworkStream that communications in blocks
 //! where a block is a length prefixed array of bytes.
-
+//!
+//! Optionally, the client and server can authenticate each other and encrypt all blocks by
+//! performing a Noise IK handshake upon connecting. The client pins the static x25519 key of the
+//! server and the server only accepts clients whose static key it trusts. The Noise nonces ensure
+//! that tampered, replayed or reordered blocks are rejected.
+
+use diem_crypto::{
+    noise::{self, NoiseSession},
+    x25519,
+};
 use diem_logger::{info, trace, warn, Schema};
 use diem_secure_push_metrics::{register_int_counter_vec, IntCounterVec};
 use once_cell::sync::Lazy;
 use serde::Serialize;
 use std::{
+    collections::HashSet,
+    convert::TryFrom,
     io::{Read, Write},
     net::{Shutdown, SocketAddr, TcpListener, TcpStream},
     thread, time,
@@ -55,6 +66,7 @@ enum LogEvent {
     ConnectionAttempt,
     ConnectionSuccessful,
     ConnectionFailed,
+    HandshakeFailed,
     DisconnectedPeerOnRead,
     DisconnectedPeerOnWrite,
     Shutdown,
@@ -138,12 +150,30 @@ pub enum Error {
     AlreadyShutdown,
     #[error("Found data that is too large to decode: {0}")]
     DataTooLarge(usize),
+    #[error("Decrypted message has an invalid length")]
+    InvalidMessageLength,
     #[error("Internal network error:")]
     NetworkError(#[from] std::io::Error),
     #[error("No active stream")]
     NoActiveStream,
+    #[error("Noise error: {0}")]
+    NoiseError(#[from] noise::NoiseError),
     #[error("Remote stream cleanly closed")]
     RemoteStreamClosed,
+    #[error("Remote peer is not trusted: {0}")]
+    UntrustedPeer(x25519::PublicKey),
+}
+
+/// The Noise configuration of a client: its static key and the pinned key of the server.
+struct ClientNoise {
+    config: noise::NoiseConfig,
+    server_public_key: x25519::PublicKey,
+}
+
+/// The Noise configuration of a server: its static key and the keys of the trusted clients.
+struct ServerNoise {
+    config: noise::NoiseConfig,
+    trusted_peers: HashSet<x25519::PublicKey>,
 }
 
 pub struct NetworkClient {
@@ -152,6 +182,7 @@ pub struct NetworkClient {
     stream: Option<NetworkStream>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    noise: Option<ClientNoise>,
 }
 
 impl NetworkClient {
@@ -161,6 +192,25 @@ impl NetworkClient {
             server,
             stream: None,
             timeout_ms,
+            noise: None,
+        }
+    }
+
+    /// Creates a client that authenticates with `private_key` and encrypts all messages. It only
+    /// talks to a server that proves ownership of `server_public_key`.
+    pub fn new_with_noise(
+        service: &'static str,
+        server: SocketAddr,
+        timeout_ms: u64,
+        private_key: x25519::PrivateKey,
+        server_public_key: x25519::PublicKey,
+    ) -> Self {
+        Self {
+            noise: Some(ClientNoise {
+                config: noise::NoiseConfig::new(private_key),
+                server_public_key,
+            }),
+            ..Self::new(service, server, timeout_ms)
         }
     }
 
@@ -255,7 +305,25 @@ impl NetworkClient {
 
             let stream = stream?;
             stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, self.server, self.timeout_ms));
+            let mut stream = NetworkStream::new(stream, self.server, self.timeout_ms);
+            if let Some(noise) = &self.noise {
+                if let Err(err) = stream.initiate_handshake(
+                    &noise.config,
+                    self.service.as_bytes(),
+                    noise.server_public_key,
+                ) {
+                    self.increment_counter(Method::Connect, MethodResult::Failure);
+                    warn!(SecureNetLogSchema::new(
+                        self.service,
+                        NetworkMode::Client,
+                        LogEvent::HandshakeFailed,
+                    )
+                    .error(&err)
+                    .remote_peer(&self.server));
+                    return Err(err);
+                }
+            }
+            self.stream = Some(stream);
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -275,6 +343,7 @@ pub struct NetworkServer {
     stream: Option<NetworkStream>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    noise: Option<ServerNoise>,
 }
 
 impl NetworkServer {
@@ -285,6 +354,25 @@ impl NetworkServer {
             listener: Some(listener.unwrap()),
             stream: None,
             timeout_ms,
+            noise: None,
+        }
+    }
+
+    /// Creates a server that authenticates with `private_key` and encrypts all messages. Clients
+    /// that do not prove ownership of one of the `trusted_peers` keys are disconnected.
+    pub fn new_with_noise(
+        service: &'static str,
+        listen: SocketAddr,
+        timeout_ms: u64,
+        private_key: x25519::PrivateKey,
+        trusted_peers: HashSet<x25519::PublicKey>,
+    ) -> Self {
+        Self {
+            noise: Some(ServerNoise {
+                config: noise::NoiseConfig::new(private_key),
+                trusted_peers,
+            }),
+            ..Self::new(service, listen, timeout_ms)
         }
     }
 
@@ -388,6 +476,26 @@ impl NetworkServer {
                 }
             };
 
+            stream.set_nodelay(true)?;
+            let mut stream = NetworkStream::new(stream, stream_addr, self.timeout_ms);
+            if let Some(noise) = &self.noise {
+                if let Err(err) = stream.respond_to_handshake(
+                    &noise.config,
+                    self.service.as_bytes(),
+                    &noise.trusted_peers,
+                ) {
+                    self.increment_counter(Method::Connect, MethodResult::Failure);
+                    warn!(SecureNetLogSchema::new(
+                        self.service,
+                        NetworkMode::Server,
+                        LogEvent::HandshakeFailed,
+                    )
+                    .error(&err)
+                    .remote_peer(&stream_addr));
+                    return Err(err);
+                }
+            }
+
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -396,19 +504,23 @@ impl NetworkServer {
             )
             .remote_peer(&stream_addr));
 
-            stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, stream_addr, self.timeout_ms));
+            self.stream = Some(stream);
         }
 
         self.stream.as_mut().ok_or(Error::NoActiveStream)
     }
 }
 
+/// The largest plaintext that fits into a single Noise message.
+const MAX_NOISE_PLAINTEXT: usize = noise::MAX_SIZE_NOISE_MSG - noise::AES_GCM_TAGLEN;
+
 struct NetworkStream {
     stream: TcpStream,
     remote: SocketAddr,
     buffer: Vec<u8>,
     temp_buffer: [u8; 1024],
+    /// Encrypts and decrypts blocks once a Noise handshake completed.
+    session: Option<NoiseSession>,
 }
 
 impl NetworkStream {
@@ -423,11 +535,76 @@ impl NetworkStream {
             remote,
             buffer: Vec::new(),
             temp_buffer: [0; 1024],
+            session: None,
         }
     }
 
+    /// Performs the initiator side of a Noise IK handshake with a responder owning
+    /// `remote_public_key`. All following blocks are encrypted.
+    pub fn initiate_handshake(
+        &mut self,
+        config: &noise::NoiseConfig,
+        prologue: &[u8],
+        remote_public_key: x25519::PublicKey,
+    ) -> Result<(), Error> {
+        let mut rng = rand::rngs::OsRng;
+        let mut init_msg = vec![0; noise::handshake_init_msg_len(0)];
+        let state = config.initiate_connection(
+            &mut rng,
+            prologue,
+            remote_public_key,
+            None,
+            &mut init_msg,
+        )?;
+        self.write_block(&init_msg)?;
+        let resp_msg = self.read_block()?;
+        let (_, session) = config.finalize_connection(state, &resp_msg)?;
+        self.session = Some(session);
+        Ok(())
+    }
+
+    /// Performs the responder side of a Noise IK handshake, rejecting initiators whose static key
+    /// is not in `trusted_peers`. All following blocks are encrypted.
+    pub fn respond_to_handshake(
+        &mut self,
+        config: &noise::NoiseConfig,
+        prologue: &[u8],
+        trusted_peers: &HashSet<x25519::PublicKey>,
+    ) -> Result<(), Error> {
+        let init_msg = self.read_block()?;
+        let (remote_public_key, state, _) =
+            config.parse_client_init_message(prologue, &init_msg)?;
+        if !trusted_peers.contains(&remote_public_key) {
+            return Err(Error::UntrustedPeer(remote_public_key));
+        }
+        let mut rng = rand::rngs::OsRng;
+        let mut resp_msg = vec![0; noise::handshake_resp_msg_len(0)];
+        let session = config.respond_to_client(&mut rng, state, None, &mut resp_msg)?;
+        self.write_block(&resp_msg)?;
+        self.session = Some(session);
+        Ok(())
+    }
+
     /// Blocking read until able to successfully read an entire message
     pub fn read(&mut self) -> Result<Vec<u8>, Error> {
+        let block = self.read_block()?;
+        match &mut self.session {
+            Some(session) => decrypt(session, block),
+            None => Ok(block),
+        }
+    }
+
+    /// Blocking write until able to successfully send an entire message
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        if let Some(session) = &mut self.session {
+            let block = encrypt(session, data)?;
+            return self.write_block(&block);
+        }
+        self.write_block(data)
+    }
+
+    /// Blocking read until able to successfully read an entire block
+    fn read_block(&mut self) -> Result<Vec<u8>, Error> {
         let result = self.read_buffer();
         if !result.is_empty() {
             return Ok(result);
@@ -455,8 +632,8 @@ impl NetworkStream {
         Ok(self.stream.shutdown(Shutdown::Both)?)
     }
 
-    /// Blocking write until able to successfully send an entire message
-    pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+    /// Blocking write until able to successfully send an entire block
+    fn write_block(&mut self, data: &[u8]) -> Result<(), Error> {
         let u32_max = u32::max_value() as usize;
         if u32_max <= data.len() {
             return Err(Error::DataTooLarge(data.len()));
@@ -510,15 +687,75 @@ impl NetworkStream {
     }
 }
 
+/// Encrypts a message into a block. Noise messages are limited in size, so the message, prefixed
+/// with its length, is split into chunks that are encrypted one after the other.
+fn encrypt(session: &mut NoiseSession, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let data_len = u32::try_from(data.len()).map_err(|_| Error::DataTooLarge(data.len()))?;
+    let mut plaintext = data_len.to_le_bytes().to_vec();
+    plaintext.extend_from_slice(data);
+
+    let mut block = Vec::with_capacity(noise::encrypted_len(plaintext.len()));
+    for chunk in plaintext.chunks_mut(MAX_NOISE_PLAINTEXT) {
+        let auth_tag = session.write_message_in_place(chunk)?;
+        block.extend_from_slice(chunk);
+        block.extend_from_slice(&auth_tag);
+    }
+    Ok(block)
+}
+
+/// Decrypts a block produced by `encrypt`. The authenticated length prefix guarantees that
+/// trailing chunks have not been dropped.
+fn decrypt(session: &mut NoiseSession, mut block: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut plaintext = Vec::with_capacity(block.len());
+    for chunk in block.chunks_mut(noise::MAX_SIZE_NOISE_MSG) {
+        plaintext.extend_from_slice(session.read_message_in_place(chunk)?);
+    }
+
+    if plaintext.len() < 4 {
+        return Err(Error::InvalidMessageLength);
+    }
+    let mut u32_bytes = [0; 4];
+    u32_bytes.copy_from_slice(&plaintext[..4]);
+    if u32::from_le_bytes(u32_bytes) as usize != plaintext.len() - 4 {
+        return Err(Error::InvalidMessageLength);
+    }
+    Ok(plaintext.split_off(4))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use diem_config::utils;
+    use diem_crypto::Uniform;
+    use rand::rngs::OsRng;
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
     /// Read, Write, Connect timeout in milliseconds.
     const TIMEOUT: u64 = 5_000;
 
+    /// A server that only trusts the client key it returns, along with its own public key.
+    fn noise_server() -> (
+        SocketAddr,
+        NetworkServer,
+        x25519::PrivateKey,
+        x25519::PublicKey,
+    ) {
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let server_key = x25519::PrivateKey::generate(&mut OsRng);
+        let server_public_key = server_key.public_key();
+        let client_key = x25519::PrivateKey::generate(&mut OsRng);
+        let trusted_peers = vec![client_key.public_key()].into_iter().collect();
+        let server =
+            NetworkServer::new_with_noise("test", server_addr, TIMEOUT, server_key, trusted_peers);
+        (server_addr, server, client_key, server_public_key)
+    }
+
+    fn raw_stream(server_addr: SocketAddr) -> NetworkStream {
+        let stream = TcpStream::connect(server_addr).unwrap();
+        NetworkStream::new(stream, server_addr, TIMEOUT)
+    }
+
     #[test]
     fn test_ping() {
         let server_port = utils::get_available_port();
@@ -652,4 +889,152 @@ mod test {
         let result2 = server2.read().unwrap();
         assert_eq!(data2, result2);
     }
+
+    #[test]
+    fn test_noise_ping() {
+        let (server_addr, mut server, client_key, server_public_key) = noise_server();
+        let mut client = NetworkClient::new_with_noise(
+            "test",
+            server_addr,
+            TIMEOUT,
+            client_key,
+            server_public_key,
+        );
+
+        // Messages larger than a single Noise message are split into chunks
+        let data = vec![0, 1, 2, 3];
+        let large_data: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+        let client_data = (data.clone(), large_data.clone());
+        let client = thread::spawn(move || {
+            client.write(&client_data.0).unwrap();
+            client.write(&client_data.1).unwrap();
+            client.read().unwrap()
+        });
+
+        assert_eq!(data, server.read().unwrap());
+        assert_eq!(large_data, server.read().unwrap());
+        let data = vec![4, 5, 6, 7];
+        server.write(&data).unwrap();
+        assert_eq!(data, client.join().unwrap());
+    }
+
+    #[test]
+    fn test_noise_untrusted_client() {
+        let (server_addr, mut server, _client_key, server_public_key) = noise_server();
+        let untrusted_key = x25519::PrivateKey::generate(&mut OsRng);
+        let untrusted_public_key = untrusted_key.public_key();
+        let mut client = NetworkClient::new_with_noise(
+            "test",
+            server_addr,
+            TIMEOUT,
+            untrusted_key,
+            server_public_key,
+        );
+        let client = thread::spawn(move || client.write(&[0, 1, 2, 3]));
+
+        match server.read().unwrap_err() {
+            Error::UntrustedPeer(key) => assert_eq!(key, untrusted_public_key),
+            err => panic!("Unexpected error: {}", err),
+        }
+        client.join().unwrap().unwrap_err();
+    }
+
+    #[test]
+    fn test_noise_unknown_server() {
+        let (server_addr, mut server, client_key, _server_public_key) = noise_server();
+        let unknown_public_key = x25519::PrivateKey::generate(&mut OsRng).public_key();
+        let mut client = NetworkClient::new_with_noise(
+            "test",
+            server_addr,
+            TIMEOUT,
+            client_key,
+            unknown_public_key,
+        );
+        let client = thread::spawn(move || client.write(&[0, 1, 2, 3]));
+
+        assert!(matches!(server.read().unwrap_err(), Error::NoiseError(_)));
+        client.join().unwrap().unwrap_err();
+    }
+
+    #[test]
+    fn test_noise_tampered_message() {
+        let (server_addr, mut server, client_key, server_public_key) = noise_server();
+        let attacker = thread::spawn(move || {
+            let mut stream = raw_stream(server_addr);
+            let config = noise::NoiseConfig::new(client_key);
+            stream
+                .initiate_handshake(&config, b"test", server_public_key)
+                .unwrap();
+            let mut block = encrypt(stream.session.as_mut().unwrap(), &[0, 1, 2, 3]).unwrap();
+            block[5] ^= 1;
+            stream.write_block(&block).unwrap();
+        });
+
+        assert!(matches!(
+            server.read().unwrap_err(),
+            Error::NoiseError(noise::NoiseError::Decrypt)
+        ));
+        attacker.join().unwrap();
+    }
+
+    #[test]
+    fn test_noise_replayed_message() {
+        let (server_addr, mut server, client_key, server_public_key) = noise_server();
+        let data = vec![0, 1, 2, 3];
+        let attacker_data = data.clone();
+        let attacker = thread::spawn(move || {
+            let mut stream = raw_stream(server_addr);
+            let config = noise::NoiseConfig::new(client_key);
+            stream
+                .initiate_handshake(&config, b"test", server_public_key)
+                .unwrap();
+            let block = encrypt(stream.session.as_mut().unwrap(), &attacker_data).unwrap();
+            stream.write_block(&block).unwrap();
+            stream.write_block(&block).unwrap();
+        });
+
+        assert_eq!(data, server.read().unwrap());
+        assert!(matches!(
+            server.read().unwrap_err(),
+            Error::NoiseError(noise::NoiseError::Decrypt)
+        ));
+        attacker.join().unwrap();
+    }
+
+    #[test]
+    fn test_noise_replayed_connection() {
+        let (server_addr, mut server, client_key, server_public_key) = noise_server();
+        let data = vec![0, 1, 2, 3];
+        let attacker_data = data.clone();
+        let attacker = thread::spawn(move || {
+            // Record a legitimate connection
+            let mut stream = raw_stream(server_addr);
+            let config = noise::NoiseConfig::new(client_key);
+            let mut init_msg = vec![0; noise::handshake_init_msg_len(0)];
+            let state = config
+                .initiate_connection(&mut OsRng, b"test", server_public_key, None, &mut init_msg)
+                .unwrap();
+            stream.write_block(&init_msg).unwrap();
+            let resp_msg = stream.read_block().unwrap();
+            let (_, mut session) = config.finalize_connection(state, &resp_msg).unwrap();
+            let block = encrypt(&mut session, &attacker_data).unwrap();
+            stream.write_block(&block).unwrap();
+            stream.shutdown().unwrap();
+
+            // Replay it without knowing the client key
+            let mut stream = raw_stream(server_addr);
+            stream.write_block(&init_msg).unwrap();
+            stream.read_block().unwrap();
+            stream.write_block(&block).unwrap();
+        });
+
+        assert_eq!(data, server.read().unwrap());
+        server.read().unwrap_err();
+        // The replayed handshake completes, but the session keys are fresh
+        assert!(matches!(
+            server.read().unwrap_err(),
+            Error::NoiseError(noise::NoiseError::Decrypt)
+        ));
+        attacker.join().unwrap();
+    }
 }
@@ -0,0 +1,237 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    from_base64, to_base64, CryptoKVStorage, Error, GetResponse, KVStorage, OnDiskStorage,
+};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use diem_crypto::hkdf::Hkdf;
+use diem_temppath::TempPath;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use fs2::FileExt;
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroizing;
+
+/// The version of the file format, authenticated together with the encrypted data.
+const VERSION: u32 = 1;
+/// Binds the derived keys to this storage.
+const HKDF_INFO: &[u8] = b"DIEM_ENCRYPTED_ON_DISK_STORAGE";
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const SALT_SIZE: usize = 32;
+
+/// EncryptedOnDiskStorage is a key value store that, like OnDiskStorage, is persisted to a single
+/// file on the local filesystem, but encrypts that file with AES-256-GCM. A secret, e.g. a
+/// passphrase or the contents of a key file, is turned into a pseudorandom key with HKDF-SHA256
+/// when the storage is created and is not kept afterwards. The encryption key is then expanded
+/// from that pseudorandom key and a random salt that is regenerated on every write. The secret
+/// must be at least 16 bytes long and should have high entropy, HKDF does not slow down guessing
+/// attacks on weak passphrases.
+///
+/// Writes replace the file atomically, and all accesses hold an advisory lock on a sidecar
+/// `.lock` file, so multiple processes can safely share the same storage. This offers no
+/// permission checks and holds key material in memory, so Vault remains the recommended backend
+/// for production.
+pub struct EncryptedOnDiskStorage {
+    file_path: PathBuf,
+    lock_path: PathBuf,
+    /// The pseudorandom key extracted from the secret
+    prk: Zeroizing<Vec<u8>>,
+    time_service: TimeService,
+}
+
+/// The contents of the storage file.
+#[derive(Deserialize, Serialize)]
+struct EncryptedFile {
+    version: u32,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    salt: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    nonce: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedOnDiskStorage {
+    pub fn new(file_path: PathBuf, secret: &[u8]) -> Result<Self, Error> {
+        let prk = Hkdf::<Sha256>::extract(None, secret)
+            .map_err(|e| Error::EncryptionError(format!("Invalid secret: {}", e)))?;
+        let mut lock_path = file_path.clone().into_os_string();
+        lock_path.push(".lock");
+        Ok(Self {
+            file_path,
+            lock_path: lock_path.into(),
+            prk: Zeroizing::new(prk),
+            time_service: TimeService::real(),
+        })
+    }
+
+    /// Imports all keys of an unencrypted OnDiskStorage file, keeping their last update times, and
+    /// returns the number of imported keys. Nothing is imported if any of the keys already exists.
+    /// The unencrypted file is left untouched and should be deleted once the migration succeeded.
+    pub fn import_on_disk(&mut self, on_disk_path: &Path) -> Result<usize, Error> {
+        if !on_disk_path.exists() {
+            return Err(Error::InternalError(format!(
+                "OnDiskStorage file does not exist: {:?}",
+                on_disk_path
+            )));
+        }
+        let imported = OnDiskStorage::new(on_disk_path.to_path_buf()).read()?;
+
+        let _lock = self.lock(true)?;
+        let mut data = self.read()?;
+        if let Some(key) = imported.keys().find(|key| data.contains_key(*key)) {
+            return Err(Error::KeyAlreadyExists(key.clone()));
+        }
+        let num_keys = imported.len();
+        data.extend(imported);
+        self.write(&data)?;
+        Ok(num_keys)
+    }
+
+    /// Acquires the lock on the storage file, which is released when the returned file is dropped.
+    fn lock(&self, exclusive: bool) -> Result<File, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&self.lock_path)?;
+        if exclusive {
+            file.lock_exclusive()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(file)
+    }
+
+    /// Reads and decrypts the storage file. The caller must hold the lock.
+    fn read(&self) -> Result<HashMap<String, Value>, Error> {
+        let contents = match fs::read(&self.file_path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let file: EncryptedFile = serde_json::from_slice(&contents)?;
+        if file.version != VERSION {
+            return Err(Error::EncryptionError(format!(
+                "Unsupported file version: {}",
+                file.version
+            )));
+        }
+        if file.nonce.len() != NONCE_SIZE {
+            return Err(Error::EncryptionError("Invalid nonce".into()));
+        }
+
+        let cipher = self.cipher(&file.salt)?;
+        let plaintext = cipher
+            .decrypt(
+                GenericArray::from_slice(&file.nonce),
+                Payload {
+                    msg: &file.ciphertext,
+                    aad: &VERSION.to_le_bytes(),
+                },
+            )
+            .map_err(|_| {
+                Error::EncryptionError(
+                    "Unable to decrypt storage, either the secret is wrong or the file is corrupted"
+                        .into(),
+                )
+            })?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Encrypts and atomically replaces the storage file. The caller must hold the exclusive lock.
+    fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
+        let plaintext = serde_json::to_vec(data)?;
+        let mut salt = vec![0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = vec![0; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = self.cipher(&salt)?;
+        let ciphertext = cipher
+            .encrypt(
+                GenericArray::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &VERSION.to_le_bytes(),
+                },
+            )
+            .map_err(|_| Error::EncryptionError("Unable to encrypt storage".into()))?;
+        let contents = serde_json::to_vec(&EncryptedFile {
+            version: VERSION,
+            salt,
+            nonce,
+            ciphertext,
+        })?;
+
+        // The parent will be empty when only a filename is supplied. Therefore use the current
+        // working directory provided by PathBuf::new().
+        let file_dir = self
+            .file_path
+            .parent()
+            .map_or(PathBuf::new(), |p| p.to_path_buf());
+        let temp_path = TempPath::new_with_temp_dir(file_dir);
+        let mut file = File::create(temp_path.path())?;
+        file.write_all(&contents)?;
+        file.sync_all()?;
+        fs::rename(temp_path.path(), &self.file_path)?;
+        Ok(())
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<Aes256Gcm, Error> {
+        let info = [HKDF_INFO, salt].concat();
+        let key = Hkdf::<Sha256>::expand(&self.prk, Some(&info), KEY_SIZE)
+            .map(Zeroizing::new)
+            .map_err(|e| Error::EncryptionError(format!("Unable to derive key: {}", e)))?;
+        Ok(Aes256Gcm::new(GenericArray::from_slice(&key)))
+    }
+}
+
+impl KVStorage for EncryptedOnDiskStorage {
+    /// The storage is available if it can be decrypted with the secret.
+    fn available(&self) -> Result<(), Error> {
+        let _lock = self.lock(false)?;
+        self.read().map(|_| ())
+    }
+
+    fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
+        let mut data = {
+            let _lock = self.lock(false)?;
+            self.read()?
+        };
+        data.remove(key)
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))
+            .and_then(|value| serde_json::from_value(value).map_err(|e| e.into()))
+    }
+
+    fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let _lock = self.lock(true)?;
+        let mut data = self.read()?;
+        data.insert(
+            key.to_string(),
+            serde_json::to_value(&GetResponse::new(value, now))?,
+        );
+        self.write(&data)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        let _lock = self.lock(true)?;
+        self.write(&HashMap::new())
+    }
+}
+
+impl CryptoKVStorage for EncryptedOnDiskStorage {}
@@ -7,6 +7,8 @@ use thiserror::Error;
 
 #[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
     #[error("Entropy error: {0}")]
     EntropyError(String),
     #[error("Internal error: {0}")]
@@ -21,6 +23,8 @@ pub enum Error {
     SerializationError(String),
     #[error("Key version not found, key name: {0}, version: {1}")]
     KeyVersionNotFound(String, String),
+    #[error("Key cannot be exported: {0}")]
+    KeyNotExportable(String),
 }
 
 impl From<base64::DecodeError> for Error {
@@ -63,6 +67,18 @@ impl From<diem_vault_client::Error> for Error {
     }
 }
 
+impl From<pkcs11::errors::Error> for Error {
+    fn from(error: pkcs11::errors::Error) -> Self {
+        match error {
+            pkcs11::errors::Error::Pkcs11(pkcs11::types::CKR_PIN_INCORRECT)
+            | pkcs11::errors::Error::Pkcs11(pkcs11::types::CKR_USER_NOT_LOGGED_IN) => {
+                Self::PermissionDenied
+            }
+            _ => Self::InternalError(format!("{}", error)),
+        }
+    }
+}
+
 impl From<diem_github_client::Error> for Error {
     fn from(error: diem_github_client::Error) -> Self {
         match error {
@@ -5,26 +5,32 @@
 
 mod crypto_kv_storage;
 mod crypto_storage;
+mod encrypted_on_disk;
 mod error;
 mod github;
 mod in_memory;
 mod kv_storage;
 mod namespaced_storage;
 mod on_disk;
+mod pkcs11;
 mod policy;
+mod replicated;
 mod storage;
 mod vault;
 
 pub use crate::{
     crypto_kv_storage::CryptoKVStorage,
     crypto_storage::{CryptoStorage, PublicKeyResponse},
+    encrypted_on_disk::EncryptedOnDiskStorage,
     error::Error,
     github::GitHubStorage,
     in_memory::InMemoryStorage,
     kv_storage::{GetResponse, KVStorage},
     namespaced_storage::NamespacedStorage,
     on_disk::OnDiskStorage,
+    pkcs11::Pkcs11Storage,
     policy::{Capability, Identity, Permission, Policy},
+    replicated::{ReplicaStatus, ReplicatedStorage},
     storage::Storage,
     vault::VaultStorage,
 };
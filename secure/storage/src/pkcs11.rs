@@ -0,0 +1,604 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CryptoStorage, Error, GetResponse, KVStorage, PublicKeyResponse};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+    PrivateKey,
+};
+use diem_infallible::Mutex;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use once_cell::sync::Lazy;
+use pkcs11::{
+    types::{
+        CKA_APPLICATION, CKA_CLASS, CKA_EC_PARAMS, CKA_EC_POINT, CKA_EXTRACTABLE, CKA_ID,
+        CKA_KEY_TYPE, CKA_LABEL, CKA_MODIFIABLE, CKA_PRIVATE, CKA_SENSITIVE, CKA_SIGN, CKA_TOKEN,
+        CKA_VALUE, CKA_VERIFY, CKF_OS_LOCKING_OK, CKF_RW_SESSION, CKF_SERIAL_SESSION, CKO_DATA,
+        CKO_PRIVATE_KEY, CKO_PUBLIC_KEY, CKR_USER_ALREADY_LOGGED_IN, CKU_USER, CK_ATTRIBUTE,
+        CK_ATTRIBUTE_TYPE, CK_BBOOL, CK_C_INITIALIZE_ARGS, CK_FALSE, CK_KEY_TYPE, CK_MECHANISM,
+        CK_MECHANISM_TYPE, CK_OBJECT_CLASS, CK_OBJECT_HANDLE, CK_SESSION_HANDLE, CK_TRUE, CK_ULONG,
+        CK_VOID_PTR,
+    },
+    Ctx,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    ptr,
+    sync::Arc,
+};
+
+/// The CKA_APPLICATION of the data objects holding key/value pairs.
+const APPLICATION: &[u8] = b"diem";
+/// The DER encoded object identifier of Ed25519, 1.3.101.112, used as CKA_EC_PARAMS.
+const ED25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+/// The DER header of the octet string holding an Ed25519 public key in CKA_EC_POINT.
+const EC_POINT_HEADER: &[u8] = &[0x04, 0x20];
+const FIND_BATCH_SIZE: CK_ULONG = 64;
+
+// Ed25519 was introduced in PKCS#11 v3.0, which the bindings predate
+const CKK_EC_EDWARDS: CK_KEY_TYPE = 0x0000_0040;
+const CKM_EC_EDWARDS_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x0000_1055;
+const CKM_EDDSA: CK_MECHANISM_TYPE = 0x0000_1057;
+
+/// A PKCS#11 module can only be initialized once per process, so all storages share it.
+static MODULES: Lazy<Mutex<HashMap<PathBuf, Arc<Ctx>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Pkcs11Storage holds Ed25519 keys in a hardware security module, or any other token, accessed
+/// through a PKCS#11 module (e.g., SoftHSM for testing). Keys are generated within the token and
+/// by default cannot be exported: signing happens within the token.
+///
+/// Each version of a key is a key pair labeled with the name of the key and its version, e.g.,
+/// `consensus@0`, and identified by its creation time. Rotations create the next version and
+/// destroy all but the previous one. Key/value pairs are held as data objects labeled with their
+/// key. A namespace prefixes all labels with the namespace followed by "/".
+pub struct Pkcs11Storage {
+    module: PathBuf,
+    token_label: String,
+    pin: String,
+    namespace: Option<String>,
+    exportable_keys: bool,
+    session: Mutex<Option<Session>>,
+    time_service: TimeService,
+}
+
+/// A logged in session, opened on first use and reopened if it is lost (e.g., the token was
+/// removed).
+struct Session {
+    ctx: Arc<Ctx>,
+    handle: CK_SESSION_HANDLE,
+}
+
+/// A version of a key held within the token.
+struct KeyVersion {
+    version: u32,
+    private_key: CK_OBJECT_HANDLE,
+    public_key: CK_OBJECT_HANDLE,
+}
+
+impl Pkcs11Storage {
+    pub fn new(
+        module: PathBuf,
+        token_label: String,
+        pin: String,
+        namespace: Option<String>,
+    ) -> Self {
+        Self {
+            module,
+            token_label,
+            pin,
+            namespace,
+            exportable_keys: false,
+            session: Mutex::new(None),
+            time_service: TimeService::real(),
+        }
+    }
+
+    /// Allows exporting the private keys created or imported from now on. This defeats the purpose
+    /// of a hardware token and is only intended for testing.
+    pub fn with_exportable_keys(mut self) -> Self {
+        self.exportable_keys = true;
+        self
+    }
+
+    fn open_session(&self) -> Result<Session, Error> {
+        let ctx = module(&self.module)?;
+        let slot = ctx
+            .get_slot_list(true)?
+            .into_iter()
+            .find(|slot| {
+                ctx.get_token_info(*slot).map_or(false, |info| {
+                    String::from_utf8_lossy(&info.label).trim_end() == self.token_label
+                })
+            })
+            .ok_or_else(|| {
+                Error::InternalError(format!("PKCS#11 token not found: {}", self.token_label))
+            })?;
+
+        let handle = ctx.open_session(slot, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)?;
+        match ctx.login(handle, CKU_USER, Some(&self.pin)) {
+            // The login state is shared by all sessions of the process
+            Ok(()) | Err(pkcs11::errors::Error::Pkcs11(CKR_USER_ALREADY_LOGGED_IN)) => (),
+            Err(e) => {
+                let _ = ctx.close_session(handle);
+                return Err(e.into());
+            }
+        }
+        Ok(Session { ctx, handle })
+    }
+
+    /// Runs an operation within the session, which serializes all accesses to the token.
+    fn with_session<T, F>(&self, operation: F) -> Result<T, Error>
+    where
+        F: FnOnce(&Ctx, CK_SESSION_HANDLE) -> Result<T, Error>,
+    {
+        let mut session = self.session.lock();
+        if session.is_none() {
+            *session = Some(self.open_session()?);
+        }
+
+        let (ctx, handle) = match session.as_ref() {
+            Some(session) => (session.ctx.clone(), session.handle),
+            None => unreachable!("The session was just opened"),
+        };
+        let result = operation(&ctx, handle);
+        if result.is_err() && ctx.get_session_info(handle).is_err() {
+            *session = None;
+        }
+        result
+    }
+
+    fn label(&self, name: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}/{}", namespace, name),
+            None => name.into(),
+        }
+    }
+
+    fn key_label(&self, name: &str, version: u32) -> String {
+        format!("{}@{}", self.label(name), version)
+    }
+
+    /// Returns the versions of a key, from the oldest to the most recent one.
+    fn key_versions(
+        &self,
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        name: &str,
+    ) -> Result<Vec<KeyVersion>, Error> {
+        let prefix = format!("{}@", self.label(name));
+        let template = [
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_PRIVATE_KEY),
+            CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_EC_EDWARDS),
+        ];
+
+        let mut versions = Vec::new();
+        for private_key in find(ctx, session, &template)? {
+            let label = attribute(ctx, session, private_key, CKA_LABEL)?;
+            let label = String::from_utf8_lossy(&label);
+            let version = match label
+                .strip_prefix(&prefix)
+                .and_then(|version| version.parse().ok())
+            {
+                Some(version) => version,
+                None => continue,
+            };
+            let public_key = find_object(ctx, session, CKO_PUBLIC_KEY, label.as_bytes())?
+                .ok_or_else(|| Error::InternalError(format!("Missing public key: {}", label)))?;
+            versions.push(KeyVersion {
+                version,
+                private_key,
+                public_key,
+            });
+        }
+        versions.sort_by_key(|version| version.version);
+        Ok(versions)
+    }
+
+    fn current_version(
+        &self,
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        name: &str,
+    ) -> Result<KeyVersion, Error> {
+        self.key_versions(ctx, session, name)?
+            .pop()
+            .ok_or_else(|| Error::KeyNotSet(self.label(name)))
+    }
+
+    fn find_version(
+        &self,
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        name: &str,
+        version: &Ed25519PublicKey,
+    ) -> Result<KeyVersion, Error> {
+        for key_version in self.key_versions(ctx, session, name)? {
+            if &public_key(ctx, session, key_version.public_key)? == version {
+                return Ok(key_version);
+            }
+        }
+        Err(Error::KeyVersionNotFound(name.into(), version.to_string()))
+    }
+
+    /// Generates a new version of a key within the token.
+    fn generate_key(
+        &self,
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        name: &str,
+        version: u32,
+    ) -> Result<Ed25519PublicKey, Error> {
+        let label = self.key_label(name, version);
+        let id = self.time_service.now_secs().to_be_bytes();
+        let (sensitive, extractable) = self.key_protection();
+        let public_template = [
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+            CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+            CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(ED25519_PARAMS),
+        ];
+        let private_template = [
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+            CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+            CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&sensitive),
+            CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&extractable),
+        ];
+
+        let (public_key_handle, _) = ctx.generate_key_pair(
+            session,
+            &mechanism(CKM_EC_EDWARDS_KEY_PAIR_GEN),
+            &public_template,
+            &private_template,
+        )?;
+        public_key(ctx, session, public_key_handle)
+    }
+
+    /// Imports a key as the given version of a key.
+    fn import_key(
+        &self,
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        name: &str,
+        version: u32,
+        key: &Ed25519PrivateKey,
+    ) -> Result<(), Error> {
+        let label = self.key_label(name, version);
+        let id = self.time_service.now_secs().to_be_bytes();
+        let (sensitive, extractable) = self.key_protection();
+        let ec_point = [EC_POINT_HEADER, &key.public_key().to_bytes()].concat();
+        let private_key = key.to_bytes();
+        let public_template = [
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_PUBLIC_KEY),
+            CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_EC_EDWARDS),
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+            CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+            CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(ED25519_PARAMS),
+            CK_ATTRIBUTE::new(CKA_EC_POINT).with_bytes(&ec_point),
+        ];
+        let private_template = [
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_PRIVATE_KEY),
+            CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_EC_EDWARDS),
+            CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+            CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+            CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&CK_TRUE),
+            CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&sensitive),
+            CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&extractable),
+            CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(ED25519_PARAMS),
+            CK_ATTRIBUTE::new(CKA_VALUE).with_bytes(&private_key),
+        ];
+
+        ctx.create_object(session, &public_template)?;
+        ctx.create_object(session, &private_template)?;
+        Ok(())
+    }
+
+    /// Returns the CKA_SENSITIVE and CKA_EXTRACTABLE values of new private keys.
+    fn key_protection(&self) -> (CK_BBOOL, CK_BBOOL) {
+        if self.exportable_keys {
+            (CK_FALSE, CK_TRUE)
+        } else {
+            (CK_TRUE, CK_FALSE)
+        }
+    }
+
+    fn export_key(
+        &self,
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        name: &str,
+        key_version: KeyVersion,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        let extractable = attribute(ctx, session, key_version.private_key, CKA_EXTRACTABLE)?;
+        if extractable != [CK_TRUE] {
+            return Err(Error::KeyNotExportable(self.label(name)));
+        }
+        let private_key = attribute(ctx, session, key_version.private_key, CKA_VALUE)?;
+        Ed25519PrivateKey::try_from(private_key.as_slice())
+            .map_err(|e| Error::InternalError(e.to_string()))
+    }
+
+    fn sign_with_key<T: CryptoHash + Serialize>(
+        ctx: &Ctx,
+        session: CK_SESSION_HANDLE,
+        key_version: KeyVersion,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let mut bytes = <T::Hasher as diem_crypto::hash::CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut bytes, &message).map_err(|e| {
+            Error::InternalError(format!(
+                "Serialization of signable material should not fail, yet returned Error:{}",
+                e
+            ))
+        })?;
+
+        ctx.sign_init(session, &mechanism(CKM_EDDSA), key_version.private_key)?;
+        let signature = ctx.sign(session, &bytes)?;
+        Ed25519Signature::try_from(signature.as_slice())
+            .map_err(|e| Error::InternalError(e.to_string()))
+    }
+}
+
+impl Drop for Pkcs11Storage {
+    fn drop(&mut self) {
+        if let Some(session) = self.session.lock().take() {
+            let _ = session.ctx.close_session(session.handle);
+        }
+    }
+}
+
+impl KVStorage for Pkcs11Storage {
+    fn available(&self) -> Result<(), Error> {
+        self.with_session(|ctx, session| {
+            ctx.get_session_info(session)?;
+            Ok(())
+        })
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        let label = self.label(key);
+        self.with_session(|ctx, session| {
+            let object = find_object(ctx, session, CKO_DATA, label.as_bytes())?
+                .ok_or_else(|| Error::KeyNotSet(key.into()))?;
+            let value = attribute(ctx, session, object, CKA_VALUE)?;
+            serde_json::from_slice(&value).map_err(|e| e.into())
+        })
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        let label = self.label(key);
+        let value = serde_json::to_vec(&GetResponse::new(value, self.time_service.now_secs()))?;
+        self.with_session(|ctx, session| {
+            if let Some(object) = find_object(ctx, session, CKO_DATA, label.as_bytes())? {
+                let template = [CK_ATTRIBUTE::new(CKA_VALUE).with_bytes(&value)];
+                ctx.set_attribute_value(session, object, &template)?;
+            } else {
+                let template = [
+                    CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_DATA),
+                    CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+                    CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+                    CK_ATTRIBUTE::new(CKA_MODIFIABLE).with_bool(&CK_TRUE),
+                    CK_ATTRIBUTE::new(CKA_APPLICATION).with_bytes(APPLICATION),
+                    CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+                    CK_ATTRIBUTE::new(CKA_VALUE).with_bytes(&value),
+                ];
+                ctx.create_object(session, &template)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Destroys all objects within the namespace, or the whole token without a namespace.
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        let prefix = self.label("");
+        self.with_session(|ctx, session| {
+            let template = [CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE)];
+            for object in find(ctx, session, &template)? {
+                if attribute(ctx, session, object, CKA_LABEL)?.starts_with(prefix.as_bytes()) {
+                    ctx.destroy_object(session, object)?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl CryptoStorage for Pkcs11Storage {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.with_session(|ctx, session| {
+            if !self.key_versions(ctx, session, name)?.is_empty() {
+                return Err(Error::KeyAlreadyExists(self.label(name)));
+            }
+            self.generate_key(ctx, session, name, 0)
+        })
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.with_session(|ctx, session| {
+            let key_version = self.current_version(ctx, session, name)?;
+            self.export_key(ctx, session, name, key_version)
+        })
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.with_session(|ctx, session| {
+            let key_version = self.find_version(ctx, session, name, &version)?;
+            self.export_key(ctx, session, name, key_version)
+        })
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.with_session(|ctx, session| {
+            if !self.key_versions(ctx, session, name)?.is_empty() {
+                return Err(Error::KeyAlreadyExists(self.label(name)));
+            }
+            self.import_key(ctx, session, name, 0, &key)
+        })
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.with_session(|ctx, session| {
+            let key_version = self.current_version(ctx, session, name)?;
+            let id = attribute(ctx, session, key_version.public_key, CKA_ID)?;
+            Ok(PublicKeyResponse {
+                last_update: <[u8; 8]>::try_from(id.as_slice()).map_or(0, u64::from_be_bytes),
+                public_key: public_key(ctx, session, key_version.public_key)?,
+            })
+        })
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.with_session(|ctx, session| {
+            let mut versions = self.key_versions(ctx, session, name)?;
+            versions.pop();
+            let previous = versions
+                .pop()
+                .ok_or_else(|| Error::KeyVersionNotFound(name.into(), "previous version".into()))?;
+            public_key(ctx, session, previous.public_key)
+        })
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.with_session(|ctx, session| {
+            let mut versions = self.key_versions(ctx, session, name)?;
+            let current = versions
+                .pop()
+                .ok_or_else(|| Error::KeyNotSet(self.label(name)))?;
+            let public_key = self.generate_key(ctx, session, name, current.version + 1)?;
+
+            // Retain the previous version only, once the new version exists
+            for version in versions {
+                ctx.destroy_object(session, version.private_key)?;
+                ctx.destroy_object(session, version.public_key)?;
+            }
+            Ok(public_key)
+        })
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.with_session(|ctx, session| {
+            let key_version = self.current_version(ctx, session, name)?;
+            Self::sign_with_key(ctx, session, key_version, message)
+        })
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.with_session(|ctx, session| {
+            let key_version = self.find_version(ctx, session, name, &version)?;
+            Self::sign_with_key(ctx, session, key_version, message)
+        })
+    }
+}
+
+/// Loads and initializes a PKCS#11 module, unless it already was.
+fn module(path: &Path) -> Result<Arc<Ctx>, Error> {
+    let mut modules = MODULES.lock();
+    if let Some(ctx) = modules.get(path) {
+        return Ok(ctx.clone());
+    }
+
+    let mut ctx = Ctx::new(path)?;
+    let mut args = CK_C_INITIALIZE_ARGS::new();
+    args.flags = CKF_OS_LOCKING_OK;
+    ctx.initialize(Some(args))?;
+    let ctx = Arc::new(ctx);
+    modules.insert(path.to_path_buf(), ctx.clone());
+    Ok(ctx)
+}
+
+fn mechanism(mechanism: CK_MECHANISM_TYPE) -> CK_MECHANISM {
+    CK_MECHANISM {
+        mechanism,
+        pParameter: ptr::null_mut(),
+        ulParameterLen: 0,
+    }
+}
+
+fn find(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    template: &[CK_ATTRIBUTE],
+) -> Result<Vec<CK_OBJECT_HANDLE>, Error> {
+    ctx.find_objects_init(session, template)?;
+    let mut objects = Vec::new();
+    let result = loop {
+        match ctx.find_objects(session, FIND_BATCH_SIZE) {
+            Ok(batch) if batch.is_empty() => break Ok(()),
+            Ok(batch) => objects.extend(batch),
+            Err(e) => break Err(e),
+        }
+    };
+    ctx.find_objects_final(session)?;
+    result?;
+    Ok(objects)
+}
+
+fn find_object(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    class: CK_OBJECT_CLASS,
+    label: &[u8],
+) -> Result<Option<CK_OBJECT_HANDLE>, Error> {
+    let mut template = vec![
+        CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label),
+    ];
+    if class == CKO_DATA {
+        template.push(CK_ATTRIBUTE::new(CKA_APPLICATION).with_bytes(APPLICATION));
+    }
+    Ok(find(ctx, session, &template)?.into_iter().next())
+}
+
+/// Reads an attribute, first querying its length and then its value.
+fn attribute(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    object: CK_OBJECT_HANDLE,
+    attribute_type: CK_ATTRIBUTE_TYPE,
+) -> Result<Vec<u8>, Error> {
+    let mut template = vec![CK_ATTRIBUTE::new(attribute_type)];
+    ctx.get_attribute_value(session, object, &mut template)?;
+    let mut buffer = vec![0u8; template[0].ulValueLen as usize];
+    // The token writes the value into the buffer, which must hence be passed as mutable
+    template[0].pValue = buffer.as_mut_ptr() as CK_VOID_PTR;
+    template[0].ulValueLen = buffer.len() as CK_ULONG;
+    ctx.get_attribute_value(session, object, &mut template)?;
+    buffer.truncate(template[0].ulValueLen as usize);
+    Ok(buffer)
+}
+
+/// Reads an Ed25519 public key, which tokens either hold raw or as a DER octet string.
+fn public_key(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    object: CK_OBJECT_HANDLE,
+) -> Result<Ed25519PublicKey, Error> {
+    let ec_point = attribute(ctx, session, object, CKA_EC_POINT)?;
+    let bytes = ec_point.strip_prefix(EC_POINT_HEADER).unwrap_or(&ec_point);
+    Ed25519PublicKey::try_from(bytes).map_err(|e| Error::InternalError(e.to_string()))
+}
@@ -0,0 +1,300 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    crypto_kv_storage::get_previous_version_name, CryptoKVStorage, Error, GetResponse, KVStorage,
+    Storage,
+};
+use diem_infallible::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeSet,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+/// How often writes compare the keys in use across the replicas.
+const DIVERGENCE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// ReplicatedStorage writes through to a primary and one or more secondary storages, so that a
+/// single unavailable backend (e.g., a Vault outage) does not take down its users.
+///
+/// Writes are applied to every replica and succeed as long as one up-to-date replica accepted
+/// them; a replica that failed a write is considered stale and no longer serves reads until it is
+/// repaired. Reads are served by the first up-to-date replica, in order of preference, that reads
+/// successfully. A replica that failed a read is only tried after the others until it serves a
+/// read or accepts a write again.
+///
+/// Reads do not compare replicas. Instead, writes compare the keys read or written so far across
+/// all replicas, at most once per `DIVERGENCE_CHECK_INTERVAL`, and mark the replicas holding a
+/// value other than the most recent one as stale. The stale and failed read flags are only held in
+/// memory and are reset on restart, until the next divergence check finds the stale replicas
+/// again; replicas should hence be repaired, e.g., with the operational tool, before restarting.
+///
+/// Cryptographic keys are held as key/value pairs in each replica (see `CryptoKVStorage`), so that
+/// every replica holds the same key versions. Backends holding their own keys, i.e., Vault and
+/// PKCS#11, are hence rejected as replicas by the config.
+pub struct ReplicatedStorage {
+    replicas: Vec<Replica>,
+    /// The keys read or written so far, which divergence checks compare across replicas
+    keys: Mutex<BTreeSet<String>>,
+    divergence_check_interval: Duration,
+    last_divergence_check: Option<Instant>,
+}
+
+struct Replica {
+    storage: Storage,
+    /// Set when a write to this replica failed or its data diverged, i.e., its data may be out of
+    /// date. Cleared by `repair`.
+    stale: bool,
+    /// Set when a read from this replica failed, i.e., it is likely unavailable.
+    failed_read: AtomicBool,
+}
+
+/// The state of a key across the replicas of a ReplicatedStorage.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReplicaStatus {
+    pub key: String,
+    /// The replica holding the value all replicas should agree on.
+    pub source: usize,
+    /// The replicas whose value differs from, or is missing compared to, the source.
+    pub diverged: Vec<usize>,
+}
+
+impl ReplicatedStorage {
+    /// Creates a ReplicatedStorage. Reads prefer the primary, then the secondaries in order.
+    pub fn new(primary: Storage, secondaries: Vec<Storage>) -> Self {
+        let replicas = std::iter::once(primary)
+            .chain(secondaries)
+            .map(|storage| Replica {
+                storage,
+                stale: false,
+                failed_read: AtomicBool::new(false),
+            })
+            .collect();
+        Self {
+            replicas,
+            keys: Mutex::new(BTreeSet::new()),
+            divergence_check_interval: DIVERGENCE_CHECK_INTERVAL,
+            last_divergence_check: None,
+        }
+    }
+
+    /// Overrides how often writes compare the keys in use across the replicas.
+    pub fn with_divergence_check_interval(mut self, interval: Duration) -> Self {
+        self.divergence_check_interval = interval;
+        self
+    }
+
+    /// Compares the given keys, and the previous versions of cryptographic keys, across all
+    /// replicas. Keys that are not set in any replica are omitted. Every replica must be
+    /// available.
+    pub fn check(&self, keys: &[&str]) -> Result<Vec<ReplicaStatus>, Error> {
+        let mut statuses = Vec::new();
+        for key in keys {
+            for key in &[key.to_string(), get_previous_version_name(key)] {
+                if let Some(status) = self.check_key(key)? {
+                    statuses.push(status);
+                }
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// Copies the value of every diverged key from its source to all replicas and returns the
+    /// repaired keys. All replicas are considered up to date afterwards. The source is the most
+    /// recently updated replica that did not miss a write, and amongst equally recent ones, the
+    /// replica preferred for reads.
+    pub fn repair(&mut self, keys: &[&str]) -> Result<Vec<ReplicaStatus>, Error> {
+        let statuses: Vec<_> = self
+            .check(keys)?
+            .into_iter()
+            .filter(|status| !status.diverged.is_empty())
+            .collect();
+
+        for status in &statuses {
+            let value = self.replicas[status.source]
+                .storage
+                .get::<Value>(&status.key)?
+                .value;
+            // Rewrite the source as well, so that all replicas share the same last update
+            for replica in &mut self.replicas {
+                replica.storage.set(&status.key, &value)?;
+            }
+        }
+
+        for replica in &mut self.replicas {
+            replica.stale = false;
+        }
+        Ok(statuses)
+    }
+
+    fn check_key(&self, key: &str) -> Result<Option<ReplicaStatus>, Error> {
+        let mut responses = Vec::new();
+        for replica in &self.replicas {
+            match replica.storage.get::<Value>(key) {
+                Ok(response) => responses.push(Some(response)),
+                Err(Error::KeyNotSet(_)) => responses.push(None),
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Prefer up-to-date replicas, then the most recent value, then the first replica
+        let rank = |index: usize, response: &GetResponse<Value>| {
+            (!self.replicas[index].stale, response.last_update)
+        };
+        let mut source: Option<(usize, &GetResponse<Value>)> = None;
+        for (index, response) in responses.iter().enumerate() {
+            if let Some(response) = response {
+                match source {
+                    Some((source, newest)) if rank(source, newest) >= rank(index, response) => (),
+                    _ => source = Some((index, response)),
+                }
+            }
+        }
+
+        Ok(source.map(|(source, newest)| ReplicaStatus {
+            key: key.into(),
+            source,
+            diverged: responses
+                .iter()
+                .enumerate()
+                .filter(|(_, response)| {
+                    response
+                        .as_ref()
+                        .map_or(true, |response| response.value != newest.value)
+                })
+                .map(|(index, _)| index)
+                .collect(),
+        }))
+    }
+
+    /// Compares the keys in use across the replicas if the last comparison is older than the
+    /// interval, and marks the replicas that diverged as stale. Failures are only logged, as the
+    /// write that triggered the comparison succeeded.
+    fn check_divergence(&mut self) {
+        if let Some(last_divergence_check) = self.last_divergence_check {
+            if last_divergence_check.elapsed() < self.divergence_check_interval {
+                return;
+            }
+        }
+        self.last_divergence_check = Some(Instant::now());
+
+        let keys: Vec<_> = self.keys.lock().iter().cloned().collect();
+        let keys: Vec<_> = keys.iter().map(String::as_str).collect();
+        match self.check(&keys) {
+            Ok(statuses) => {
+                for status in statuses {
+                    for index in status.diverged {
+                        diem_logger::warn!("Replica {} diverged for {}", index, status.key);
+                        self.replicas[index].stale = true;
+                    }
+                }
+            }
+            Err(e) => diem_logger::warn!("Unable to compare the replicas: {}", e),
+        }
+    }
+
+    fn read(&self, key: &str) -> Result<GetResponse<Value>, Error> {
+        {
+            let mut keys = self.keys.lock();
+            if !keys.contains(key) {
+                keys.insert(key.into());
+            }
+        }
+
+        // Up-to-date replicas in order of preference, the ones that failed a read last
+        let mut replicas: Vec<_> = self
+            .replicas
+            .iter()
+            .enumerate()
+            .filter(|(_, replica)| !replica.stale)
+            .collect();
+        replicas.sort_by_key(|(_, replica)| replica.failed_read.load(Ordering::Relaxed));
+
+        let mut error = None;
+        for (index, replica) in replicas {
+            let response = replica.storage.get::<Value>(key);
+            match response {
+                Ok(_) | Err(Error::KeyNotSet(_)) => {
+                    replica.failed_read.store(false, Ordering::Relaxed);
+                    return response;
+                }
+                Err(e) => {
+                    diem_logger::warn!("Unable to read {} from replica {}: {}", key, index, e);
+                    replica.failed_read.store(true, Ordering::Relaxed);
+                    error = Some(e);
+                }
+            }
+        }
+        Err(error
+            .unwrap_or_else(|| Error::InternalError("No up-to-date replica is available".into())))
+    }
+}
+
+impl KVStorage for ReplicatedStorage {
+    fn available(&self) -> Result<(), Error> {
+        let mut error = None;
+        for replica in self.replicas.iter().filter(|replica| !replica.stale) {
+            match replica.storage.available() {
+                Ok(()) => return Ok(()),
+                Err(e) => error = Some(e),
+            }
+        }
+        Err(error
+            .unwrap_or_else(|| Error::InternalError("No up-to-date replica is available".into())))
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        let response = self.read(key)?;
+        Ok(GetResponse::new(
+            serde_json::from_value(response.value)?,
+            response.last_update,
+        ))
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        let mut written = false;
+        let mut error = None;
+
+        for (index, replica) in self.replicas.iter_mut().enumerate() {
+            match replica.storage.set(key, &value) {
+                // Writes to stale replicas are still applied, but cannot be relied upon
+                Ok(()) => {
+                    replica.failed_read.store(false, Ordering::Relaxed);
+                    written |= !replica.stale;
+                }
+                Err(e) => {
+                    diem_logger::warn!("Unable to write {} to replica {}: {}", key, index, e);
+                    replica.stale = true;
+                    error = Some(e);
+                }
+            }
+        }
+
+        if written {
+            self.keys.lock().insert(key.into());
+            self.check_divergence();
+            Ok(())
+        } else {
+            Err(error.unwrap_or_else(|| {
+                Error::InternalError("No up-to-date replica is available".into())
+            }))
+        }
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        for replica in &mut self.replicas {
+            replica.storage.reset_and_clear()?;
+            replica.stale = false;
+            replica.failed_read.store(false, Ordering::Relaxed);
+        }
+        self.keys.lock().clear();
+        Ok(())
+    }
+}
+
+impl CryptoKVStorage for ReplicatedStorage {}
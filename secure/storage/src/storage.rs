@@ -1,8 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    CryptoStorage, Error, GetResponse, GitHubStorage, InMemoryStorage, KVStorage,
-    NamespacedStorage, OnDiskStorage, PublicKeyResponse, VaultStorage,
+    CryptoStorage, EncryptedOnDiskStorage, Error, GetResponse, GitHubStorage, InMemoryStorage,
+    KVStorage, NamespacedStorage, OnDiskStorage, Pkcs11Storage, PublicKeyResponse,
+    ReplicatedStorage, VaultStorage,
 };
 use diem_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
 use enum_dispatch::enum_dispatch;
@@ -21,4 +22,7 @@ pub enum Storage {
     InMemoryStorage(InMemoryStorage),
     NamespacedStorage(NamespacedStorage),
     OnDiskStorage(OnDiskStorage),
+    EncryptedOnDiskStorage(EncryptedOnDiskStorage),
+    ReplicatedStorage(ReplicatedStorage),
+    Pkcs11Storage(Pkcs11Storage),
 }
@@ -0,0 +1,131 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{tests::suite, EncryptedOnDiskStorage, Error, KVStorage, OnDiskStorage, Storage};
+use diem_temppath::TempPath;
+use std::{fs, thread};
+
+const SECRET: &[u8] = b"a passphrase that is long enough";
+
+#[test]
+fn encrypted_on_disk() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(EncryptedOnDiskStorage::new(path_buf, SECRET).unwrap());
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+#[test]
+fn encrypted_on_disk_is_encrypted() {
+    let temp_path = TempPath::new();
+    let mut storage = EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), SECRET).unwrap();
+    storage.set("consensus", "plaintext value").unwrap();
+
+    let contents = fs::read_to_string(temp_path.path()).unwrap();
+    assert!(!contents.contains("consensus"));
+    assert!(!contents.contains("plaintext value"));
+
+    // Every write uses a fresh salt and nonce
+    storage.set("consensus", "plaintext value").unwrap();
+    assert_ne!(contents, fs::read_to_string(temp_path.path()).unwrap());
+}
+
+#[test]
+fn encrypted_on_disk_wrong_secret() {
+    let temp_path = TempPath::new();
+    let path_buf = temp_path.path().to_path_buf();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), SECRET).unwrap();
+    storage.set("key", 5u64).unwrap();
+    storage.available().unwrap();
+
+    let storage = EncryptedOnDiskStorage::new(path_buf, b"another long enough secret").unwrap();
+    assert!(matches!(
+        storage.available(),
+        Err(Error::EncryptionError(_))
+    ));
+    assert!(matches!(
+        storage.get::<u64>("key"),
+        Err(Error::EncryptionError(_))
+    ));
+}
+
+#[test]
+fn encrypted_on_disk_short_secret() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    assert!(matches!(
+        EncryptedOnDiskStorage::new(path_buf, b"short"),
+        Err(Error::EncryptionError(_))
+    ));
+}
+
+#[test]
+fn encrypted_on_disk_tampered() {
+    let temp_path = TempPath::new();
+    let path_buf = temp_path.path().to_path_buf();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), SECRET).unwrap();
+    storage.set("key", 5u64).unwrap();
+
+    let mut file: serde_json::Value =
+        serde_json::from_slice(&fs::read(&path_buf).unwrap()).unwrap();
+    let mut ciphertext = base64::decode(file["ciphertext"].as_str().unwrap()).unwrap();
+    ciphertext[0] ^= 1;
+    file["ciphertext"] = base64::encode(ciphertext).into();
+    fs::write(&path_buf, serde_json::to_vec(&file).unwrap()).unwrap();
+
+    assert!(matches!(
+        storage.get::<u64>("key"),
+        Err(Error::EncryptionError(_))
+    ));
+}
+
+#[test]
+fn encrypted_on_disk_concurrent_writers() {
+    let temp_path = TempPath::new();
+    let path_buf = temp_path.path().to_path_buf();
+
+    // Each writer uses its own instance, as separate processes would
+    let writers: Vec<_> = (0..4)
+        .map(|writer| {
+            let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), SECRET).unwrap();
+            thread::spawn(move || {
+                for i in 0..10u64 {
+                    storage.set(&format!("{}-{}", writer, i), i).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let storage = EncryptedOnDiskStorage::new(path_buf, SECRET).unwrap();
+    for writer in 0..4 {
+        for i in 0..10u64 {
+            let key = format!("{}-{}", writer, i);
+            assert_eq!(storage.get::<u64>(&key).unwrap().value, i);
+        }
+    }
+}
+
+#[test]
+fn encrypted_on_disk_import() {
+    let on_disk_path = TempPath::new();
+    let mut on_disk = OnDiskStorage::new(on_disk_path.path().to_path_buf());
+    on_disk.set("key", 5u64).unwrap();
+    on_disk.set("other_key", "value".to_string()).unwrap();
+    let last_update = on_disk.get::<u64>("key").unwrap().last_update;
+
+    let temp_path = TempPath::new();
+    let mut storage = EncryptedOnDiskStorage::new(temp_path.path().to_path_buf(), SECRET).unwrap();
+    assert_eq!(storage.import_on_disk(on_disk_path.path()).unwrap(), 2);
+
+    let response = storage.get::<u64>("key").unwrap();
+    assert_eq!(response.value, 5);
+    assert_eq!(response.last_update, last_update);
+    assert_eq!(storage.get::<String>("other_key").unwrap().value, "value");
+
+    // Importing again would overwrite existing keys
+    assert!(matches!(
+        storage.import_on_disk(on_disk_path.path()),
+        Err(Error::KeyAlreadyExists(_))
+    ));
+}
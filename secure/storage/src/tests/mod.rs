@@ -1,8 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod encrypted_on_disk;
 mod github;
 mod in_memory;
 mod on_disk;
+mod pkcs11;
+mod replicated;
 mod suite;
 mod vault;
@@ -0,0 +1,194 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{tests::suite, CryptoStorage, Error, KVStorage, Pkcs11Storage, Storage};
+use diem_crypto::{test_utils::TestDiemCrypto, Signature};
+use diem_temppath::TempPath;
+use std::{env, fs, path::PathBuf, process::Command};
+
+/// Where SoftHSM installs its PKCS#11 module, unless overridden by SOFTHSM2_MODULE.
+const MODULE_PATHS: &[&str] = &[
+    "/usr/lib/softhsm/libsofthsm2.so",
+    "/usr/lib/x86_64-linux-gnu/softhsm/libsofthsm2.so",
+    "/usr/local/lib/softhsm/libsofthsm2.so",
+    "/opt/homebrew/lib/softhsm/libsofthsm2.so",
+];
+const TOKEN_LABEL: &str = "diem";
+const PIN: &str = "1234";
+const CRYPTO_NAME: &str = "consensus";
+
+/// A SoftHSM token, initialized within a temporary directory.
+struct SoftHsm {
+    module: PathBuf,
+    _directory: TempPath,
+}
+
+impl SoftHsm {
+    /// Initializes a token, or returns None if SoftHSM is not installed. As SoftHSM reads its
+    /// configuration when it is first loaded, this may only be called once per process.
+    fn initialize() -> Option<Self> {
+        let module = env::var_os("SOFTHSM2_MODULE")
+            .map(PathBuf::from)
+            .or_else(|| {
+                MODULE_PATHS
+                    .iter()
+                    .map(PathBuf::from)
+                    .find(|path| path.exists())
+            })?;
+
+        let directory = TempPath::new();
+        directory.create_as_dir().ok()?;
+        let token_dir = directory.path().join("tokens");
+        fs::create_dir(&token_dir).ok()?;
+        let config = directory.path().join("softhsm2.conf");
+        fs::write(
+            &config,
+            format!(
+                "directories.tokendir = {}\nobjectstore.backend = file\n",
+                token_dir.display()
+            ),
+        )
+        .ok()?;
+        env::set_var("SOFTHSM2_CONF", &config);
+
+        let status = Command::new("softhsm2-util")
+            .args(&["--init-token", "--free", "--label", TOKEN_LABEL])
+            .args(&["--pin", PIN, "--so-pin", PIN])
+            .status()
+            .ok()?;
+        if !status.success() {
+            return None;
+        }
+
+        Some(Self {
+            module,
+            _directory: directory,
+        })
+    }
+
+    fn storage(&self, namespace: Option<&str>) -> Pkcs11Storage {
+        Pkcs11Storage::new(
+            self.module.clone(),
+            TOKEN_LABEL.into(),
+            PIN.into(),
+            namespace.map(|namespace| namespace.into()),
+        )
+    }
+}
+
+/// Runs all tests against a single SoftHSM token, as it can only be initialized once.
+#[test]
+fn pkcs11() {
+    let softhsm = match SoftHsm::initialize() {
+        Some(softhsm) => softhsm,
+        None => return,
+    };
+
+    // This must run first, as the token remains logged in once any storage logged in
+    test_invalid_pin(&softhsm);
+
+    let mut storage = Storage::from(softhsm.storage(None).with_exportable_keys());
+    suite::execute_all_storage_tests(&mut storage);
+
+    let tests: &[fn(&SoftHsm)] = &[test_key_versions, test_namespaces, test_non_exportable_keys];
+    for test in tests {
+        test(&softhsm);
+        storage.reset_and_clear().unwrap();
+    }
+}
+
+fn test_invalid_pin(softhsm: &SoftHsm) {
+    let storage = Pkcs11Storage::new(
+        softhsm.module.clone(),
+        TOKEN_LABEL.into(),
+        "4321".into(),
+        None,
+    );
+    assert_eq!(storage.available().unwrap_err(), Error::PermissionDenied);
+
+    let storage = Pkcs11Storage::new(softhsm.module.clone(), "unknown".into(), PIN.into(), None);
+    storage.available().unwrap_err();
+}
+
+/// Key versions are held by the token and survive reopening it.
+fn test_key_versions(softhsm: &SoftHsm) {
+    let mut storage = softhsm.storage(None);
+    let first = storage.create_key(CRYPTO_NAME).unwrap();
+    let second = storage.rotate_key(CRYPTO_NAME).unwrap();
+    let third = storage.rotate_key(CRYPTO_NAME).unwrap();
+    assert_eq!(
+        storage.create_key(CRYPTO_NAME).unwrap_err(),
+        Error::KeyAlreadyExists(CRYPTO_NAME.into())
+    );
+
+    let storage = softhsm.storage(None);
+    assert_eq!(
+        storage.get_public_key(CRYPTO_NAME).unwrap().public_key,
+        third
+    );
+    assert_eq!(
+        storage
+            .get_public_key_previous_version(CRYPTO_NAME)
+            .unwrap(),
+        second
+    );
+
+    // Only the previous version is retained
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = storage
+        .sign_using_version(CRYPTO_NAME, second.clone(), &message)
+        .unwrap();
+    signature.verify(&message, &second).unwrap();
+    assert_eq!(
+        storage
+            .sign_using_version(CRYPTO_NAME, first.clone(), &message)
+            .unwrap_err(),
+        Error::KeyVersionNotFound(CRYPTO_NAME.into(), first.to_string())
+    );
+}
+
+fn test_namespaces(softhsm: &SoftHsm) {
+    let mut storage_0 = softhsm.storage(Some("ns0"));
+    let mut storage_1 = softhsm.storage(Some("ns1"));
+    storage_0.set("key", 0u64).unwrap();
+    storage_1.set("key", 1u64).unwrap();
+    assert_eq!(storage_0.get::<u64>("key").unwrap().value, 0);
+    assert_eq!(storage_1.get::<u64>("key").unwrap().value, 1);
+
+    let public_key = storage_0.create_key(CRYPTO_NAME).unwrap();
+    storage_1.get_public_key(CRYPTO_NAME).unwrap_err();
+    assert_ne!(storage_1.create_key(CRYPTO_NAME).unwrap(), public_key);
+
+    // Resetting a namespace leaves the others untouched
+    storage_0.reset_and_clear().unwrap();
+    storage_0.get::<u64>("key").unwrap_err();
+    assert_eq!(storage_1.get::<u64>("key").unwrap().value, 1);
+}
+
+/// By default, keys never leave the token, yet can be used for signing.
+fn test_non_exportable_keys(softhsm: &SoftHsm) {
+    let mut storage = softhsm.storage(None);
+    let public_key = storage.create_key(CRYPTO_NAME).unwrap();
+    assert_eq!(
+        storage.export_private_key(CRYPTO_NAME).unwrap_err(),
+        Error::KeyNotExportable(CRYPTO_NAME.into())
+    );
+    assert_eq!(
+        storage
+            .export_private_key_for_version(CRYPTO_NAME, public_key.clone())
+            .unwrap_err(),
+        Error::KeyNotExportable(CRYPTO_NAME.into())
+    );
+
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = storage.sign(CRYPTO_NAME, &message).unwrap();
+    signature.verify(&message, &public_key).unwrap();
+
+    let rotated_public_key = storage.rotate_key(CRYPTO_NAME).unwrap();
+    let signature = storage.sign(CRYPTO_NAME, &message).unwrap();
+    signature.verify(&message, &rotated_public_key).unwrap();
+    let signature = storage
+        .sign_using_version(CRYPTO_NAME, public_key.clone(), &message)
+        .unwrap();
+    signature.verify(&message, &public_key).unwrap();
+}
@@ -0,0 +1,164 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    tests::suite, CryptoStorage, GetResponse, InMemoryStorage, KVStorage, OnDiskStorage,
+    ReplicaStatus, ReplicatedStorage, Storage,
+};
+use diem_temppath::TempPath;
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+const KEY: &str = "safety_data";
+
+fn on_disk(temp_path: &TempPath) -> Storage {
+    Storage::from(OnDiskStorage::new(temp_path.path().to_path_buf()))
+}
+
+/// Writes a single key to an on disk replica with the given last update.
+fn write_replica(path: &Path, value: u64, last_update: u64) {
+    let mut data = HashMap::new();
+    data.insert(KEY, GetResponse::new(value, last_update));
+    fs::write(path, serde_json::to_vec(&data).unwrap()).unwrap();
+}
+
+#[test]
+fn replicated() {
+    let secondary = TempPath::new();
+    let mut storage = Storage::from(ReplicatedStorage::new(
+        Storage::from(InMemoryStorage::new()),
+        vec![on_disk(&secondary)],
+    ));
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+#[test]
+fn replicated_failover() {
+    let primary = TempPath::new();
+    let secondary = TempPath::new();
+    let mut storage = ReplicatedStorage::new(on_disk(&primary), vec![on_disk(&secondary)]);
+    storage.set(KEY, 1u64).unwrap();
+    let contents = fs::read(primary.path()).unwrap();
+
+    // A replica that failed a read is tried last, until it accepts a write
+    fs::write(primary.path(), b"unavailable").unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 1);
+    write_replica(primary.path(), 0, 0);
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 1);
+    storage.set(KEY, 1u64).unwrap();
+    write_replica(primary.path(), 0, 0);
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 0);
+    fs::write(primary.path(), &contents).unwrap();
+
+    // An unreadable primary neither blocks writes nor reads
+    fs::write(primary.path(), b"unavailable").unwrap();
+    storage.set(KEY, 2u64).unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 2);
+    storage.available().unwrap();
+
+    // Once back, the primary does not serve its stale value until it is repaired
+    fs::write(primary.path(), contents).unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 2);
+    let repaired = storage.repair(&[KEY]).unwrap();
+    assert_eq!(
+        repaired,
+        vec![ReplicaStatus {
+            key: KEY.into(),
+            source: 1,
+            diverged: vec![0],
+        }]
+    );
+    let replica = OnDiskStorage::new(primary.path().to_path_buf());
+    assert_eq!(replica.get::<u64>(KEY).unwrap().value, 2);
+
+    // Writes fail once no up-to-date replica accepts them
+    fs::write(secondary.path(), b"unavailable").unwrap();
+    storage.set(KEY, 3u64).unwrap();
+    fs::write(primary.path(), b"unavailable").unwrap();
+    storage.set(KEY, 4u64).unwrap_err();
+    storage.get::<u64>(KEY).unwrap_err();
+}
+
+#[test]
+fn replicated_divergence() {
+    let primary = TempPath::new();
+    let secondary = TempPath::new();
+    primary.create_as_file().unwrap();
+    secondary.create_as_file().unwrap();
+
+    // Reads are served by the primary alone, while the most recent value is the source
+    write_replica(primary.path(), 1, 10);
+    write_replica(secondary.path(), 2, 20);
+    let mut storage = ReplicatedStorage::new(on_disk(&primary), vec![on_disk(&secondary)]);
+    assert_eq!(storage.get::<u64>(KEY).unwrap(), GetResponse::new(1, 10));
+    assert_eq!(
+        storage.check(&[KEY]).unwrap(),
+        vec![ReplicaStatus {
+            key: KEY.into(),
+            source: 1,
+            diverged: vec![0],
+        }]
+    );
+
+    // Writes compare the keys in use, and the diverged primary stops serving reads
+    storage.set("other", 0u64).unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap(), GetResponse::new(2, 20));
+
+    // Further writes only compare again once the interval elapsed
+    write_replica(primary.path(), 2, 20);
+    let mut storage = ReplicatedStorage::new(on_disk(&primary), vec![on_disk(&secondary)])
+        .with_divergence_check_interval(Duration::from_secs(3600));
+    assert_eq!(storage.get::<u64>(KEY).unwrap(), GetResponse::new(2, 20));
+    storage.set("other", 0u64).unwrap();
+    write_replica(primary.path(), 1, 10);
+    storage.set("other", 1u64).unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap(), GetResponse::new(1, 10));
+
+    // Repairing resolves values updated within the same second in favour of the primary
+    write_replica(secondary.path(), 2, 10);
+    storage.repair(&[KEY]).unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 1);
+    let statuses = storage.check(&[KEY]).unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert!(statuses[0].diverged.is_empty());
+    assert!(storage.repair(&[KEY]).unwrap().is_empty());
+
+    // A key missing from a replica has diverged as well
+    write_replica(primary.path(), 1, 10);
+    fs::write(secondary.path(), b"").unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 1);
+    assert_eq!(storage.check(&[KEY]).unwrap()[0].diverged, vec![1]);
+    assert!(storage.check(&["unknown"]).unwrap().is_empty());
+}
+
+#[test]
+fn replicated_key_versions() {
+    let primary = TempPath::new();
+    let secondary = TempPath::new();
+    let mut storage = ReplicatedStorage::new(on_disk(&primary), vec![on_disk(&secondary)]);
+    let key_name = "consensus";
+    let first = storage.create_key(key_name).unwrap();
+    let second = storage.rotate_key(key_name).unwrap();
+
+    // Every replica holds both versions
+    let replica = OnDiskStorage::new(secondary.path().to_path_buf());
+    assert_eq!(replica.get_public_key(key_name).unwrap().public_key, second);
+    assert_eq!(
+        replica.get_public_key_previous_version(key_name).unwrap(),
+        first
+    );
+
+    // Repairing a new replica copies both versions as well
+    let replacement = TempPath::new();
+    let mut storage = ReplicatedStorage::new(on_disk(&primary), vec![on_disk(&replacement)]);
+    let repaired = storage.repair(&[key_name]).unwrap();
+    assert_eq!(repaired.len(), 2);
+    let replica = OnDiskStorage::new(replacement.path().to_path_buf());
+    assert_eq!(replica.get_public_key(key_name).unwrap().public_key, second);
+    assert_eq!(
+        replica.get_public_key_previous_version(key_name).unwrap(),
+        first
+    );
+    storage
+        .export_private_key_for_version(key_name, first)
+        .unwrap();
+}
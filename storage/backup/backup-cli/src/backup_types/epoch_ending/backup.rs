@@ -6,8 +6,9 @@ use crate::{
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
-        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
-        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        backup_service_client::BackupServiceClient, codec::Encoder,
+        read_record_bytes::ReadRecordBytes, should_cut_chunk, storage_ext::BackupStorageExt,
+        GlobalBackupOptions,
     },
 };
 use anyhow::{anyhow, ensure, Result};
@@ -34,6 +35,7 @@ pub struct EpochEndingBackupController {
     start_epoch: u64,
     end_epoch: u64,
     max_chunk_size: usize,
+    encoder: Arc<Encoder>,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
 }
@@ -41,7 +43,7 @@ pub struct EpochEndingBackupController {
 impl EpochEndingBackupController {
     pub fn new(
         opt: EpochEndingBackupOpt,
-        global_opt: GlobalBackupOpt,
+        global_opt: GlobalBackupOptions,
         client: Arc<BackupServiceClient>,
         storage: Arc<dyn BackupStorage>,
     ) -> Self {
@@ -49,6 +51,7 @@ impl EpochEndingBackupController {
             start_epoch: opt.start_epoch,
             end_epoch: opt.end_epoch,
             max_chunk_size: global_opt.max_chunk_size,
+            encoder: global_opt.encoder,
             client,
             storage,
         }
@@ -155,12 +158,15 @@ impl EpochEndingBackupController {
         first_epoch: u64,
         last_epoch: u64,
     ) -> Result<EpochEndingChunk> {
-        let (chunk_handle, mut chunk_file) = self
+        let chunk_handle = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_epoch))
+            .write_encoded(
+                backup_handle,
+                &Self::chunk_name(first_epoch),
+                chunk_bytes,
+                &self.encoder,
+            )
             .await?;
-        chunk_file.write_all(&chunk_bytes).await?;
-        chunk_file.shutdown().await?;
         Ok(EpochEndingChunk {
             first_epoch,
             last_epoch,
@@ -182,6 +188,7 @@ impl EpochEndingBackupController {
             last_epoch,
             waypoints,
             chunks,
+            codec: self.encoder.codec().clone(),
         };
         let (manifest_handle, mut manifest_file) = self
             .storage
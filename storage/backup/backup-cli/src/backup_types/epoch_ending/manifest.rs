@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::FileHandle;
+use crate::{storage::FileHandle, utils::codec::Codec};
 use anyhow::{ensure, Result};
 use diem_types::waypoint::Waypoint;
 use serde::{Deserialize, Serialize};
@@ -23,6 +23,9 @@ pub struct EpochEndingBackup {
     pub last_epoch: u64,
     pub waypoints: Vec<Waypoint>,
     pub chunks: Vec<EpochEndingChunk>,
+    /// How the chunk files are encoded.
+    #[serde(default)]
+    pub codec: Codec,
 }
 
 impl EpochEndingBackup {
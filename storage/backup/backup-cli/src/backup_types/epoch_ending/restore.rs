@@ -9,7 +9,10 @@ use crate::{
     },
     storage::{BackupStorage, FileHandle, FileHandleRef},
     utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX,
+        codec::{Codec, Decoder},
+        read_record_bytes::ReadRecordBytes,
+        storage_ext::BackupStorageExt,
+        stream::StreamX,
         GlobalRestoreOptions, RestoreRunMode,
     },
 };
@@ -37,6 +40,7 @@ pub struct EpochEndingRestoreController {
     manifest_handle: FileHandle,
     target_version: Version,
     trusted_waypoints: Arc<HashMap<Version, Waypoint>>,
+    decoder: Arc<Decoder>,
 }
 
 impl EpochEndingRestoreController {
@@ -51,6 +55,7 @@ impl EpochEndingRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             trusted_waypoints: global_opt.trusted_waypoints,
+            decoder: global_opt.decoder,
         }
     }
 
@@ -94,7 +99,9 @@ impl EpochEndingRestoreController {
                 break;
             }
 
-            let lis = self.read_chunk(&chunk.ledger_infos).await?;
+            let lis = self
+                .read_chunk(&chunk.ledger_infos, &manifest.codec)
+                .await?;
             ensure!(
                 chunk.first_epoch + lis.len() as u64 == chunk.last_epoch + 1,
                 "Number of items in chunks doesn't match that in manifest. \
@@ -160,8 +167,12 @@ impl EpochEndingRestoreController {
     async fn read_chunk(
         &self,
         file_handle: &FileHandleRef,
+        codec: &Codec,
     ) -> Result<Vec<LedgerInfoWithSignatures>> {
-        let mut file = self.storage.open_for_read(file_handle).await?;
+        let mut file = self
+            .storage
+            .open_for_read_decoded(file_handle, codec, &self.decoder)
+            .await?;
         let mut chunk = vec![];
 
         while let Some(record_bytes) = file.read_record_bytes().await? {
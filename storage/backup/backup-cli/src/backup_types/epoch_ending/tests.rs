@@ -10,8 +10,11 @@ use crate::{
     },
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
-        backup_service_client::BackupServiceClient, test_utils::tmp_db_with_random_content,
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        backup_service_client::BackupServiceClient,
+        codec::{Encoder, EncryptionKeyOpt},
+        test_utils::tmp_db_with_random_content,
+        ConcurrentDownloadsOpt, GlobalBackupOptions, GlobalRestoreOpt, RocksdbOpt,
+        TrustedWaypointOpt,
     },
 };
 use backup_service::start_backup_service;
@@ -48,6 +51,7 @@ fn end_to_end() {
     let rt = start_backup_service(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
         src_db,
+        None,
     );
     let client = Arc::new(BackupServiceClient::new(format!(
         "http://localhost:{}",
@@ -63,8 +67,9 @@ fn end_to_end() {
                     start_epoch: 0,
                     end_epoch: latest_epoch,
                 },
-                GlobalBackupOpt {
+                GlobalBackupOptions {
                     max_chunk_size: 1024,
+                    encoder: Arc::new(Encoder::default()),
                 },
                 client,
                 Arc::clone(&store),
@@ -83,6 +88,7 @@ fn end_to_end() {
                 trusted_waypoints: TrustedWaypointOpt::default(),
                 rocksdb_opt: RocksdbOpt::default(),
                 concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                encryption_key: EncryptionKeyOpt::default(),
             }
             .try_into()
             .unwrap(),
@@ -198,8 +204,9 @@ async fn test_trusted_waypoints_impl(
                 start_epoch: start as u64,
                 end_epoch: std::cmp::min(start + 2, lis.len()) as u64,
             },
-            GlobalBackupOpt {
+            GlobalBackupOptions {
                 max_chunk_size: 1024,
+                encoder: Arc::new(Encoder::default()),
             },
             client.clone(),
             Arc::clone(&store),
@@ -220,6 +227,7 @@ async fn test_trusted_waypoints_impl(
             trusted_waypoints: TrustedWaypointOpt::default(),
             rocksdb_opt: RocksdbOpt::default(),
             concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            encryption_key: EncryptionKeyOpt::default(),
         }
         .try_into()
         .unwrap(),
@@ -240,6 +248,7 @@ async fn test_trusted_waypoints_impl(
             },
             rocksdb_opt: RocksdbOpt::default(),
             concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            encryption_key: EncryptionKeyOpt::default(),
         }
         .try_into()
         .unwrap(),
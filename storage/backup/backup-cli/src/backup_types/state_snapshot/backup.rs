@@ -6,8 +6,9 @@ use crate::{
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
-        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
-        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        backup_service_client::BackupServiceClient, codec::Encoder,
+        read_record_bytes::ReadRecordBytes, should_cut_chunk, storage_ext::BackupStorageExt,
+        GlobalBackupOptions,
     },
 };
 use anyhow::{anyhow, Result};
@@ -21,7 +22,7 @@ use diem_types::{
 use once_cell::sync::Lazy;
 use std::{convert::TryInto, str::FromStr, sync::Arc};
 use structopt::StructOpt;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 #[derive(StructOpt)]
 pub struct StateSnapshotBackupOpt {
@@ -35,6 +36,7 @@ pub struct StateSnapshotBackupOpt {
 pub struct StateSnapshotBackupController {
     version: Version,
     max_chunk_size: usize,
+    encoder: Arc<Encoder>,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
 }
@@ -42,13 +44,14 @@ pub struct StateSnapshotBackupController {
 impl StateSnapshotBackupController {
     pub fn new(
         opt: StateSnapshotBackupOpt,
-        global_opt: GlobalBackupOpt,
+        global_opt: GlobalBackupOptions,
         client: Arc<BackupServiceClient>,
         storage: Arc<dyn BackupStorage>,
     ) -> Self {
         Self {
             version: opt.version,
             max_chunk_size: global_opt.max_chunk_size,
+            encoder: global_opt.encoder,
             client,
             storage,
         }
@@ -168,25 +171,31 @@ impl StateSnapshotBackupController {
         first_key: HashValue,
         last_key: HashValue,
     ) -> Result<StateSnapshotChunk> {
-        let (chunk_handle, mut chunk_file) = self
+        let chunk_handle = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_idx))
+            .write_encoded(
+                backup_handle,
+                &Self::chunk_name(first_idx),
+                chunk_bytes,
+                &self.encoder,
+            )
+            .await?;
+
+        let mut proof_bytes = Vec::new();
+        self.client
+            .get_account_range_proof(last_key, self.version)
+            .await?
+            .read_to_end(&mut proof_bytes)
             .await?;
-        chunk_file.write_all(&chunk_bytes).await?;
-        chunk_file.shutdown().await?;
-        let (proof_handle, mut proof_file) = self
+        let proof_handle = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_proof_name(first_idx, last_idx))
+            .write_encoded(
+                backup_handle,
+                &Self::chunk_proof_name(first_idx, last_idx),
+                &proof_bytes,
+                &self.encoder,
+            )
             .await?;
-        tokio::io::copy(
-            &mut self
-                .client
-                .get_account_range_proof(last_key, self.version)
-                .await?,
-            &mut proof_file,
-        )
-        .await?;
-        proof_file.shutdown().await?;
 
         Ok(StateSnapshotChunk {
             first_idx,
@@ -207,18 +216,22 @@ impl StateSnapshotBackupController {
         let (txn_info, _): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
             bcs::from_bytes(&proof_bytes)?;
 
-        let (proof_handle, mut proof_file) = self
+        let proof_handle = self
             .storage
-            .create_for_write(&backup_handle, Self::proof_name())
+            .write_encoded(
+                &backup_handle,
+                Self::proof_name(),
+                &proof_bytes,
+                &self.encoder,
+            )
             .await?;
-        proof_file.write_all(&proof_bytes).await?;
-        proof_file.shutdown().await?;
 
         let manifest = StateSnapshotBackup {
             version: self.version,
             root_hash: txn_info.transaction_info().state_root_hash(),
             chunks,
             proof: proof_handle,
+            codec: self.encoder.codec().clone(),
         };
 
         let (manifest_handle, mut manifest_file) = self
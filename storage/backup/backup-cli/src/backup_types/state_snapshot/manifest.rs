@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::FileHandle;
+use crate::{storage::FileHandle, utils::codec::Codec};
 use diem_crypto::HashValue;
 use diem_types::transaction::Version;
 use serde::{Deserialize, Serialize};
@@ -46,4 +46,7 @@ pub struct StateSnapshotBackup {
     /// `EpochStateBackup` recovered prior to this to the DB; Requiring it to be in the same epoch
     /// limits the requirement on such `EpochStateBackup` to no older than the same epoch.
     pub proof: FileHandle,
+    /// How the chunk and proof files are encoded.
+    #[serde(default)]
+    pub codec: Codec,
 }
@@ -16,8 +16,10 @@ use crate::{
     },
     storage::{BackupStorage, FileHandle},
     utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, GlobalRestoreOptions,
-        RestoreRunMode,
+        codec::{Codec, Decoder},
+        read_record_bytes::ReadRecordBytes,
+        storage_ext::BackupStorageExt,
+        GlobalRestoreOptions, RestoreRunMode,
     },
 };
 use anyhow::{anyhow, ensure, Result};
@@ -48,6 +50,7 @@ pub struct StateSnapshotRestoreController {
     /// nothing will be done, otherwise, this has no effect.
     target_version: Version,
     epoch_history: Option<Arc<EpochHistory>>,
+    decoder: Arc<Decoder>,
 }
 
 impl StateSnapshotRestoreController {
@@ -64,6 +67,7 @@ impl StateSnapshotRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             epoch_history,
+            decoder: global_opt.decoder,
         }
     }
 
@@ -95,8 +99,10 @@ impl StateSnapshotRestoreController {
 
         let manifest: StateSnapshotBackup =
             self.storage.load_json_file(&self.manifest_handle).await?;
-        let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
-            self.storage.load_bcs_file(&manifest.proof).await?;
+        let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) = self
+            .storage
+            .load_bcs_file_decoded(&manifest.proof, &manifest.codec, &self.decoder)
+            .await?;
         txn_info_with_proof.verify(li.ledger_info(), manifest.version)?;
         ensure!(
             txn_info_with_proof.transaction_info().state_root_hash() == manifest.root_hash,
@@ -130,8 +136,13 @@ impl StateSnapshotRestoreController {
         ver_gauge.set(self.version as i64);
         tgt_leaf_idx.set(manifest.chunks.last().map_or(0, |c| c.last_idx as i64));
         for chunk in manifest.chunks {
-            let blobs = self.read_account_state_chunk(chunk.blobs).await?;
-            let proof = self.storage.load_bcs_file(&chunk.proof).await?;
+            let blobs = self
+                .read_account_state_chunk(chunk.blobs, &manifest.codec)
+                .await?;
+            let proof = self
+                .storage
+                .load_bcs_file_decoded(&chunk.proof, &manifest.codec, &self.decoder)
+                .await?;
 
             receiver.add_chunk(blobs, proof)?;
             leaf_idx.set(chunk.last_idx as i64);
@@ -144,8 +155,12 @@ impl StateSnapshotRestoreController {
     async fn read_account_state_chunk(
         &self,
         file_handle: FileHandle,
+        codec: &Codec,
     ) -> Result<Vec<(HashValue, AccountStateBlob)>> {
-        let mut file = self.storage.open_for_read(&file_handle).await?;
+        let mut file = self
+            .storage
+            .open_for_read_decoded(&file_handle, codec, &self.decoder)
+            .await?;
 
         let mut chunk = vec![];
 
@@ -9,8 +9,10 @@ use crate::{
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
         backup_service_client::BackupServiceClient,
+        codec::{Encoder, EncryptionKeyOpt},
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        ConcurrentDownloadsOpt, GlobalBackupOptions, GlobalRestoreOpt, RocksdbOpt,
+        TrustedWaypointOpt,
     },
 };
 use diem_config::config::RocksdbConfig;
@@ -44,8 +46,9 @@ fn end_to_end() {
         .block_on(
             StateSnapshotBackupController::new(
                 StateSnapshotBackupOpt { version },
-                GlobalBackupOpt {
+                GlobalBackupOptions {
                     max_chunk_size: 500,
+                    encoder: Arc::new(Encoder::default()),
                 },
                 client,
                 Arc::clone(&store),
@@ -67,6 +70,7 @@ fn end_to_end() {
                 trusted_waypoints: TrustedWaypointOpt::default(),
                 rocksdb_opt: RocksdbOpt::default(),
                 concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                encryption_key: EncryptionKeyOpt::default(),
             }
             .try_into()
             .unwrap(),
@@ -14,9 +14,11 @@ use crate::{
     },
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
-        backup_service_client::BackupServiceClient, test_utils::start_local_backup_service,
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, GlobalRestoreOptions,
-        RocksdbOpt, TrustedWaypointOpt,
+        backup_service_client::BackupServiceClient,
+        codec::{CodecOpt, EncryptionKey, EncryptionKeyOpt},
+        test_utils::start_local_backup_service,
+        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalBackupOptions, GlobalRestoreOpt,
+        GlobalRestoreOptions, RocksdbOpt, TrustedWaypointOpt,
     },
 };
 use diem_config::config::RocksdbConfig;
@@ -75,10 +77,24 @@ fn test_end_to_end_impl(d: TestData) {
     )));
     let num_txns_to_backup = d.target_ver - d.txn_start_ver + 1;
 
+    // Backups are compressed and encrypted, with the key given in a file.
+    let key_file = TempPath::new();
+    std::fs::write(key_file.path(), EncryptionKey::generate().to_hex()).unwrap();
+    let encryption_key_opt = EncryptionKeyOpt {
+        encryption_key_file: Some(key_file.path().to_path_buf()),
+        ..EncryptionKeyOpt::default()
+    };
+
     // Backup
-    let global_backup_opt = GlobalBackupOpt {
+    let global_backup_opt: GlobalBackupOptions = GlobalBackupOpt {
         max_chunk_size: 2048,
-    };
+        codec: CodecOpt {
+            zstd_level: Some(1),
+            encryption_key: encryption_key_opt.clone(),
+        },
+    }
+    .try_into()
+    .unwrap();
     let state_snapshot_manifest = d.state_snapshot_ver.map(|version| {
         rt.block_on(
             StateSnapshotBackupController::new(
@@ -114,6 +130,7 @@ fn test_end_to_end_impl(d: TestData) {
         trusted_waypoints: TrustedWaypointOpt::default(),
         rocksdb_opt: RocksdbOpt::default(),
         concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        encryption_key: encryption_key_opt,
     }
     .try_into()
     .unwrap();
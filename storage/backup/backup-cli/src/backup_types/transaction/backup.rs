@@ -6,8 +6,9 @@ use crate::{
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
-        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
-        should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        backup_service_client::BackupServiceClient, codec::Encoder,
+        read_record_bytes::ReadRecordBytes, should_cut_chunk, storage_ext::BackupStorageExt,
+        GlobalBackupOptions,
     },
 };
 use anyhow::{anyhow, Result};
@@ -16,7 +17,7 @@ use diem_types::transaction::Version;
 use once_cell::sync::Lazy;
 use std::{convert::TryInto, str::FromStr, sync::Arc};
 use structopt::StructOpt;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 #[derive(StructOpt)]
 pub struct TransactionBackupOpt {
@@ -31,6 +32,7 @@ pub struct TransactionBackupController {
     start_version: u64,
     num_transactions: usize,
     max_chunk_size: usize,
+    encoder: Arc<Encoder>,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
 }
@@ -38,7 +40,7 @@ pub struct TransactionBackupController {
 impl TransactionBackupController {
     pub fn new(
         opt: TransactionBackupOpt,
-        global_opt: GlobalBackupOpt,
+        global_opt: GlobalBackupOptions,
         client: Arc<BackupServiceClient>,
         storage: Arc<dyn BackupStorage>,
     ) -> Self {
@@ -46,6 +48,7 @@ impl TransactionBackupController {
             start_version: opt.start_version,
             num_transactions: opt.num_transactions,
             max_chunk_size: global_opt.max_chunk_size,
+            encoder: global_opt.encoder,
             client,
             storage,
         }
@@ -148,29 +151,31 @@ impl TransactionBackupController {
         first_version: u64,
         last_version: u64,
     ) -> Result<TransactionChunk> {
-        let (proof_handle, mut proof_file) = self
+        let mut proof_bytes = Vec::new();
+        self.client
+            .get_transaction_range_proof(first_version, last_version)
+            .await?
+            .read_to_end(&mut proof_bytes)
+            .await?;
+        let proof_handle = self
             .storage
-            .create_for_write(
+            .write_encoded(
                 backup_handle,
                 &Self::chunk_proof_name(first_version, last_version),
+                &proof_bytes,
+                &self.encoder,
             )
             .await?;
-        tokio::io::copy(
-            &mut self
-                .client
-                .get_transaction_range_proof(first_version, last_version)
-                .await?,
-            &mut proof_file,
-        )
-        .await?;
-        proof_file.shutdown().await?;
-
-        let (chunk_handle, mut chunk_file) = self
+
+        let chunk_handle = self
             .storage
-            .create_for_write(backup_handle, &Self::chunk_name(first_version))
+            .write_encoded(
+                backup_handle,
+                &Self::chunk_name(first_version),
+                chunk_bytes,
+                &self.encoder,
+            )
             .await?;
-        chunk_file.write_all(&chunk_bytes).await?;
-        chunk_file.shutdown().await?;
 
         Ok(TransactionChunk {
             first_version,
@@ -191,6 +196,7 @@ impl TransactionBackupController {
             first_version,
             last_version,
             chunks,
+            codec: self.encoder.codec().clone(),
         };
         let (manifest_handle, mut manifest_file) = self
             .storage
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::FileHandle;
+use crate::{storage::FileHandle, utils::codec::Codec};
 use anyhow::{ensure, Result};
 use diem_types::transaction::Version;
 use serde::{Deserialize, Serialize};
@@ -30,6 +30,9 @@ pub struct TransactionBackup {
     pub first_version: Version,
     pub last_version: Version,
     pub chunks: Vec<TransactionChunk>,
+    /// How the chunk and proof files are encoded.
+    #[serde(default)]
+    pub codec: Codec,
 }
 
 impl TransactionBackup {
@@ -12,7 +12,10 @@ use crate::{
     },
     storage::{BackupStorage, FileHandle},
     utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX,
+        codec::{Codec, Decoder},
+        read_record_bytes::ReadRecordBytes,
+        storage_ext::BackupStorageExt,
+        stream::StreamX,
         GlobalRestoreOptions, RestoreRunMode,
     },
 };
@@ -34,7 +37,6 @@ use std::{
 };
 use storage_interface::DbReaderWriter;
 use structopt::StructOpt;
-use tokio::io::BufReader;
 
 #[derive(StructOpt)]
 pub struct TransactionRestoreOpt {
@@ -62,6 +64,7 @@ pub struct TransactionRestoreController {
     target_version: Version,
     replay_from_version: Version,
     epoch_history: Option<Arc<EpochHistory>>,
+    decoder: Arc<Decoder>,
     state: State,
 }
 
@@ -77,10 +80,14 @@ struct LoadedChunk {
 impl LoadedChunk {
     async fn load(
         manifest: TransactionChunk,
+        codec: &Codec,
+        decoder: &Decoder,
         storage: &Arc<dyn BackupStorage>,
         epoch_history: Option<&Arc<EpochHistory>>,
     ) -> Result<Self> {
-        let mut file = BufReader::new(storage.open_for_read(&manifest.transactions).await?);
+        let mut file = storage
+            .open_for_read_decoded(&manifest.transactions, codec, decoder)
+            .await?;
         let mut txns = Vec::new();
         let mut txn_infos = Vec::new();
         let mut event_vecs = Vec::new();
@@ -100,10 +107,11 @@ impl LoadedChunk {
             txns.len(),
         );
 
-        let (range_proof, ledger_info) = storage
-            .load_bcs_file::<(TransactionAccumulatorRangeProof, LedgerInfoWithSignatures)>(
-                &manifest.proof,
-            )
+        let (range_proof, ledger_info): (
+            TransactionAccumulatorRangeProof,
+            LedgerInfoWithSignatures,
+        ) = storage
+            .load_bcs_file_decoded(&manifest.proof, codec, decoder)
             .await?;
         if let Some(epoch_history) = epoch_history {
             epoch_history.verify_ledger_info(&ledger_info)?;
@@ -147,6 +155,7 @@ impl TransactionRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             epoch_history,
+            decoder: global_opt.decoder,
             state: State::default(),
         }
     }
@@ -182,6 +191,8 @@ impl TransactionRestoreController {
             loaded_chunks.push(
                 LoadedChunk::load(
                     chunk_manifest.clone(),
+                    &manifest.codec,
+                    &self.decoder,
                     &self.storage,
                     self.epoch_history.as_ref(),
                 )
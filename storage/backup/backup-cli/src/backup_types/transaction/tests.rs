@@ -9,8 +9,10 @@ use crate::{
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
         backup_service_client::BackupServiceClient,
+        codec::{Encoder, EncryptionKeyOpt},
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        ConcurrentDownloadsOpt, GlobalBackupOptions, GlobalRestoreOpt, RocksdbOpt,
+        TrustedWaypointOpt,
     },
 };
 use diem_config::config::RocksdbConfig;
@@ -64,7 +66,10 @@ fn end_to_end() {
                     start_version: first_ver_to_backup,
                     num_transactions: num_txns_to_backup,
                 },
-                GlobalBackupOpt { max_chunk_size },
+                GlobalBackupOptions {
+                    max_chunk_size,
+                    encoder: Arc::new(Encoder::default()),
+                },
                 client,
                 Arc::clone(&store),
             )
@@ -85,6 +90,7 @@ fn end_to_end() {
                 trusted_waypoints: TrustedWaypointOpt::default(),
                 rocksdb_opt: RocksdbOpt::default(),
                 concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                encryption_key: EncryptionKeyOpt::default(),
             }
             .try_into()
             .unwrap(),
@@ -6,7 +6,7 @@ use backup_cli::{
     coordinators::verify::VerifyCoordinator,
     metadata::cache::MetadataCacheOpt,
     storage::StorageOpt,
-    utils::{ConcurrentDownloadsOpt, TrustedWaypointOpt},
+    utils::{codec::EncryptionKeyOpt, ConcurrentDownloadsOpt, TrustedWaypointOpt},
 };
 use diem_logger::{prelude::*, Level, Logger};
 use diem_secure_push_metrics::MetricsPusher;
@@ -22,6 +22,8 @@ struct Opt {
     storage: StorageOpt,
     #[structopt(flatten)]
     concurrent_downloads: ConcurrentDownloadsOpt,
+    #[structopt(flatten)]
+    encryption_key_opt: EncryptionKeyOpt,
 }
 
 #[tokio::main]
@@ -42,6 +44,7 @@ async fn main_impl() -> Result<()> {
         opt.metadata_cache_opt,
         opt.trusted_waypoints_opt,
         opt.concurrent_downloads.get(),
+        opt.encryption_key_opt,
     )?
     .run()
     .await
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{convert::TryInto, path::PathBuf, sync::Arc};
 
 use anyhow::Result;
 use structopt::StructOpt;
@@ -12,12 +12,15 @@ use backup_cli::{
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        prune::{PruneCoordinator, PruneCoordinatorOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
         backup_service_client::{BackupServiceClient, BackupServiceClientOpt},
-        ConcurrentDownloadsOpt, GlobalBackupOpt,
+        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalBackupOptions,
     },
 };
 use diem_logger::{prelude::*, Level, Logger};
@@ -38,6 +41,16 @@ enum OneShotCommand {
     Query(OneShotQueryType),
     #[structopt(about = "Do a one shot backup.")]
     Backup(OneShotBackupOpt),
+    #[structopt(
+        about = "Create a consistent checkpoint of the ledger and consensus DBs of the local Diem \
+        node, via the backup service within it, without stopping the node."
+    )]
+    Checkpoint(OneShotCheckpointOpt),
+    #[structopt(
+        about = "Delete the backups not needed under the retention policy, and compact the \
+        metadata files in the storage into a single one."
+    )]
+    Prune(OneShotPruneOpt),
 }
 
 #[derive(StructOpt)]
@@ -81,6 +94,33 @@ struct OneShotBackupOpt {
     backup_type: BackupType,
 }
 
+#[derive(StructOpt)]
+struct OneShotCheckpointOpt {
+    #[structopt(flatten)]
+    client: BackupServiceClientOpt,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Directory to create the checkpoint in, relative to the checkpoint directory \
+        configured on the node (storage.backup_service_checkpoint_dir), without \"..\". Must not \
+        contain any DB yet. Once created, a node can be started with it as its storage directory."
+    )]
+    path: PathBuf,
+}
+
+#[derive(StructOpt)]
+struct OneShotPruneOpt {
+    #[structopt(flatten)]
+    coordinator: PruneCoordinatorOpt,
+
+    #[structopt(flatten)]
+    concurrent_downloads: ConcurrentDownloadsOpt,
+
+    #[structopt(subcommand)]
+    storage: StorageOpt,
+}
+
 #[derive(StructOpt)]
 enum BackupType {
     EpochEnding {
@@ -160,7 +200,7 @@ async fn main_impl() -> Result<()> {
             },
             OneShotCommand::Backup(opt) => {
                 let client = Arc::new(BackupServiceClient::new_with_opt(opt.client));
-                let global_opt = opt.global;
+                let global_opt: GlobalBackupOptions = opt.global.try_into()?;
 
                 match opt.backup_type {
                     BackupType::EpochEnding { opt, storage } => {
@@ -195,12 +235,27 @@ async fn main_impl() -> Result<()> {
                     }
                 }
             }
+            OneShotCommand::Checkpoint(opt) => {
+                BackupServiceClient::new_with_opt(opt.client)
+                    .create_checkpoint(&opt.path)
+                    .await?;
+                println!("Checkpoint created at {}.", opt.path.display())
+            }
+            OneShotCommand::Prune(opt) => {
+                PruneCoordinator::new(
+                    opt.coordinator,
+                    opt.storage.init_storage().await?,
+                    opt.concurrent_downloads.get(),
+                )
+                .run()
+                .await?;
+            }
         },
         Command::Coordinator(coordinator_cmd) => match coordinator_cmd {
             CoordinatorCommand::Run(opt) => {
                 BackupCoordinator::new(
                     opt.coordinator,
-                    opt.global,
+                    opt.global.try_into()?,
                     Arc::new(BackupServiceClient::new_with_opt(opt.client)),
                     opt.storage.init_storage().await?,
                 )
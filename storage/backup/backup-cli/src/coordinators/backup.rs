@@ -7,26 +7,29 @@ use crate::{
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
+    coordinators::checkpoint::BackupCheckpoint,
     metadata,
-    metadata::cache::MetadataCacheOpt,
+    metadata::{cache::MetadataCacheOpt, view::BackupStorageState},
     metrics::backup::{
-        EPOCH_ENDING_EPOCH, HEARTBEAT_TS, STATE_SNAPSHOT_VERSION, TRANSACTION_VERSION,
+        EPOCH_ENDING_EPOCH, HEARTBEAT_TS, STATE_SNAPSHOT_TS, STATE_SNAPSHOT_VERSION,
+        TRANSACTION_LAG_ALERT, TRANSACTION_LAG_SECS, TRANSACTION_LAG_VERSIONS, TRANSACTION_VERSION,
     },
     storage::BackupStorage,
     utils::{
         backup_service_client::BackupServiceClient, unix_timestamp_sec, ConcurrentDownloadsOpt,
-        GlobalBackupOpt,
+        GlobalBackupOptions,
     },
 };
 use anyhow::{anyhow, ensure, Result};
+use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_types::transaction::Version;
 use diemdb::backup::backup_handler::DbState;
 use futures::{stream, Future, StreamExt};
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::VecDeque, fmt::Debug, path::PathBuf, sync::Arc};
 use structopt::StructOpt;
 use tokio::{
-    sync::watch,
+    sync::{watch, Mutex as AsyncMutex},
     time::{interval, Duration},
 };
 use tokio_stream::wrappers::IntervalStream;
@@ -48,6 +51,32 @@ pub struct BackupCoordinatorOpt {
     pub transaction_batch_size: usize,
     #[structopt(flatten)]
     pub concurernt_downloads: ConcurrentDownloadsOpt,
+    #[structopt(
+        long,
+        help = "Back up committed transactions without waiting for a full batch once the oldest \
+        of them has waited this many seconds, bounding how far the backup lags behind the DB."
+    )]
+    pub max_transaction_lag_secs: Option<u64>,
+    #[structopt(
+        long,
+        help = "Also take a state snapshot at the latest backed up transaction once this many \
+        seconds have passed since the last one, without waiting for --state-snapshot-interval \
+        versions."
+    )]
+    pub state_snapshot_interval_secs: Option<u64>,
+    #[structopt(
+        long,
+        help = "Raise the transaction lag alert metric when the oldest committed transaction not \
+        yet backed up has waited more than this many seconds."
+    )]
+    pub lag_alert_threshold_secs: Option<u64>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Local file to durably record the backup progress in, so that the transaction lag \
+        and the time based state snapshot schedule are kept across restarts."
+    )]
+    pub checkpoint_file: Option<PathBuf>,
 }
 
 impl BackupCoordinatorOpt {
@@ -63,6 +92,10 @@ impl BackupCoordinatorOpt {
              that's not yet in a transaction backup, resulting in replaying all transactions \
              at restore time."
         );
+        ensure!(
+            self.state_snapshot_interval_secs != Some(0),
+            "State snapshot interval in seconds must be greater than 0."
+        );
         Ok(())
     }
 }
@@ -70,17 +103,24 @@ impl BackupCoordinatorOpt {
 pub struct BackupCoordinator {
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
-    global_opt: GlobalBackupOpt,
+    global_opt: GlobalBackupOptions,
     metadata_cache_opt: MetadataCacheOpt,
     state_snapshot_interval: usize,
     transaction_batch_size: usize,
     concurrent_downloads: usize,
+    max_transaction_lag_secs: Option<u64>,
+    state_snapshot_interval_secs: Option<u64>,
+    lag_alert_threshold_secs: Option<u64>,
+    checkpoint_file: Option<PathBuf>,
+    checkpoint: AsyncMutex<BackupCheckpoint>,
+    transaction_lag: Mutex<TransactionLag>,
+    start_ts: i64,
 }
 
 impl BackupCoordinator {
     pub fn new(
         opt: BackupCoordinatorOpt,
-        global_opt: GlobalBackupOpt,
+        global_opt: GlobalBackupOptions,
         client: Arc<BackupServiceClient>,
         storage: Arc<dyn BackupStorage>,
     ) -> Self {
@@ -93,6 +133,13 @@ impl BackupCoordinator {
             state_snapshot_interval: opt.state_snapshot_interval,
             transaction_batch_size: opt.transaction_batch_size,
             concurrent_downloads: opt.concurernt_downloads.get(),
+            max_transaction_lag_secs: opt.max_transaction_lag_secs,
+            state_snapshot_interval_secs: opt.state_snapshot_interval_secs,
+            lag_alert_threshold_secs: opt.lag_alert_threshold_secs,
+            checkpoint_file: opt.checkpoint_file,
+            checkpoint: AsyncMutex::new(BackupCheckpoint::default()),
+            transaction_lag: Mutex::new(TransactionLag::new(None, None)),
+            start_ts: unix_timestamp_sec(),
         }
     }
     pub async fn run(&self) -> Result<()> {
@@ -104,6 +151,19 @@ impl BackupCoordinator {
         )
         .await?
         .get_storage_state();
+        let checkpoint = match &self.checkpoint_file {
+            Some(path) => BackupCheckpoint::load(path).await?,
+            None => BackupCheckpoint::default(),
+        };
+        let checkpoint = reconcile_checkpoint(checkpoint, &backup_state);
+        if let Some(ts) = checkpoint.latest_state_snapshot_ts {
+            STATE_SNAPSHOT_TS.set(ts);
+        }
+        *self.transaction_lag.lock() = TransactionLag::new(
+            checkpoint.latest_transaction_version,
+            checkpoint.latest_transaction_ts,
+        );
+        *self.checkpoint.lock().await = checkpoint;
 
         // On new DbState retrieved:
         // `watch_db_state` informs `backup_epoch_endings` via channel 1,
@@ -164,13 +224,14 @@ impl BackupCoordinator {
         match self.client.get_db_state().await {
             Ok(s) => {
                 HEARTBEAT_TS.set(unix_timestamp_sec());
-                if s.is_none() {
-                    warn!("DB not bootstrapped.");
-                } else {
+                if let Some(db_state) = s {
+                    self.update_transaction_lag(db_state.committed_version);
                     db_state_broadcast
                         .send(s)
                         .map_err(|e| anyhow!("Receivers should not be cancelled: {}", e))
                         .unwrap()
+                } else {
+                    warn!("DB not bootstrapped.");
                 }
             }
             Err(e) => warn!(
@@ -180,6 +241,41 @@ impl BackupCoordinator {
         };
     }
 
+    fn update_transaction_lag(&self, committed_version: Version) {
+        let now = unix_timestamp_sec();
+        let mut lag = self.transaction_lag.lock();
+        lag.observe(committed_version, now);
+        let lag_secs = lag.pending_since().map_or(0, |ts| now - ts);
+        TRANSACTION_LAG_VERSIONS.set(lag.num_pending(committed_version) as i64);
+        TRANSACTION_LAG_SECS.set(lag_secs);
+
+        let alert = self
+            .lag_alert_threshold_secs
+            .map_or(false, |threshold| lag_secs > threshold as i64);
+        if alert && TRANSACTION_LAG_ALERT.get() == 0 {
+            warn!(
+                lag_secs = lag_secs,
+                "Transaction backup lags behind the DB more than the threshold."
+            );
+        }
+        TRANSACTION_LAG_ALERT.set(alert as i64);
+    }
+
+    /// Updates the checkpoint, saving it to the checkpoint file if there is one. Failing to save
+    /// is not fatal, since the backup storage is the source of truth of the progress.
+    async fn update_checkpoint(&self, update: impl FnOnce(&mut BackupCheckpoint)) {
+        let mut checkpoint = self.checkpoint.lock().await;
+        update(&mut checkpoint);
+        if let Some(path) = &self.checkpoint_file {
+            if let Err(e) = checkpoint.save(path).await {
+                warn!(
+                    error = ?e,
+                    "Failed saving backup checkpoint. Will retry on the next update."
+                );
+            }
+        }
+    }
+
     async fn backup_epoch_endings(
         &self,
         mut last_epoch_ending_epoch_in_backup: Option<u64>,
@@ -208,7 +304,9 @@ impl BackupCoordinator {
             )
             .run()
             .await?;
-            last_epoch_ending_epoch_in_backup = Some(last)
+            last_epoch_ending_epoch_in_backup = Some(last);
+            self.update_checkpoint(|c| c.latest_epoch_ending_epoch = Some(last))
+                .await;
         }
 
         downstream_db_state_broadcaster
@@ -232,14 +330,21 @@ impl BackupCoordinator {
             self.state_snapshot_interval,
         );
 
-        if db_state.committed_version < next_snapshot_version {
+        let snapshot_version = if db_state.committed_version >= next_snapshot_version {
+            next_snapshot_version
+        } else if let Some(version) = self
+            .state_snapshot_due(last_snapshot_version_in_backup)
+            .await
+        {
+            version
+        } else {
             // wait for the next db_state update
             return Ok(last_snapshot_version_in_backup);
-        }
+        };
 
         StateSnapshotBackupController::new(
             StateSnapshotBackupOpt {
-                version: next_snapshot_version,
+                version: snapshot_version,
             },
             self.global_opt.clone(),
             Arc::clone(&self.client),
@@ -248,7 +353,34 @@ impl BackupCoordinator {
         .run()
         .await?;
 
-        Ok(Some(next_snapshot_version))
+        let now = unix_timestamp_sec();
+        STATE_SNAPSHOT_TS.set(now);
+        self.update_checkpoint(|c| {
+            c.latest_state_snapshot_version = Some(snapshot_version);
+            c.latest_state_snapshot_ts = Some(now);
+        })
+        .await;
+        Ok(Some(snapshot_version))
+    }
+
+    /// The version of the state snapshot due by the time based schedule, if any.
+    async fn state_snapshot_due(
+        &self,
+        last_snapshot_version_in_backup: Option<Version>,
+    ) -> Option<Version> {
+        let interval_secs = self.state_snapshot_interval_secs? as i64;
+        let checkpoint = self.checkpoint.lock().await;
+        let version = get_time_based_snapshot(
+            last_snapshot_version_in_backup,
+            checkpoint.latest_transaction_version,
+        )?;
+        // Without a recorded snapshot time, the schedule starts when the coordinator starts.
+        let last_snapshot_ts = checkpoint.latest_state_snapshot_ts.unwrap_or(self.start_ts);
+        if unix_timestamp_sec() - last_snapshot_ts >= interval_secs {
+            Some(version)
+        } else {
+            None
+        }
     }
 
     async fn backup_transactions(
@@ -260,14 +392,19 @@ impl BackupCoordinator {
             if let Some(version) = last_transaction_version_in_backup {
                 TRANSACTION_VERSION.set(version as i64);
             }
-            let (first, last) = get_batch_range(
+            let (first, mut last) = get_batch_range(
                 last_transaction_version_in_backup,
                 self.transaction_batch_size,
             );
 
             if db_state.committed_version < last {
-                // wait for the next db_state update
-                return Ok(last_transaction_version_in_backup);
+                if db_state.committed_version < first || !self.transaction_lag_exceeded() {
+                    // wait for the next db_state update
+                    return Ok(last_transaction_version_in_backup);
+                }
+                // Back up what's committed without waiting for the batch to fill up, the next
+                // batch realigns to the batch size.
+                last = db_state.committed_version;
             }
 
             TransactionBackupController::new(
@@ -283,9 +420,27 @@ impl BackupCoordinator {
             .await?;
 
             last_transaction_version_in_backup = Some(last);
+            self.transaction_lag.lock().backed_up(last);
+            let now = unix_timestamp_sec();
+            self.update_checkpoint(|c| {
+                c.latest_transaction_version = Some(last);
+                c.latest_transaction_ts = Some(now);
+            })
+            .await;
         }
     }
 
+    fn transaction_lag_exceeded(&self) -> bool {
+        let max_lag_secs = match self.max_transaction_lag_secs {
+            Some(max_lag_secs) => max_lag_secs as i64,
+            None => return false,
+        };
+        self.transaction_lag
+            .lock()
+            .pending_since()
+            .map_or(false, |ts| unix_timestamp_sec() - ts >= max_lag_secs)
+    }
+
     fn backup_work_stream<'a, S, W, Fut>(
         &'a self,
         initial_state: S,
@@ -329,6 +484,89 @@ where
 {
 }
 
+/// Tracks committed transactions not yet backed up, to measure how long the oldest of them has
+/// been waiting.
+#[derive(Debug)]
+struct TransactionLag {
+    backed_up_version: Option<Version>,
+    /// Committed versions in ascending order, each with the time it was first seen. An entry
+    /// covers the versions after the previous one, up to its own.
+    pending: VecDeque<(Version, i64)>,
+    /// When the transactions found pending on the first observation are assumed to be committed.
+    initial_ts: Option<i64>,
+}
+
+impl TransactionLag {
+    /// Transactions found pending on the first observation are assumed to be waiting since the
+    /// latest transaction backup, if known, or they are assumed to be just committed.
+    fn new(backed_up_version: Option<Version>, latest_backup_ts: Option<i64>) -> Self {
+        Self {
+            backed_up_version,
+            pending: VecDeque::new(),
+            initial_ts: latest_backup_ts,
+        }
+    }
+
+    fn observe(&mut self, committed_version: Version, ts: i64) {
+        let first_seen_ts = self.initial_ts.take().map_or(ts, |t| std::cmp::min(t, ts));
+        let last_known = self
+            .pending
+            .back()
+            .map(|(v, _)| *v)
+            .or(self.backed_up_version);
+        if last_known.map_or(true, |v| committed_version > v) {
+            self.pending.push_back((committed_version, first_seen_ts));
+        }
+    }
+
+    fn backed_up(&mut self, version: Version) {
+        self.backed_up_version = Some(version);
+        while self.pending.front().map_or(false, |(v, _)| *v <= version) {
+            self.pending.pop_front();
+        }
+    }
+
+    fn pending_since(&self) -> Option<i64> {
+        self.pending.front().map(|(_, ts)| *ts)
+    }
+
+    fn num_pending(&self, committed_version: Version) -> u64 {
+        match self.backed_up_version {
+            Some(v) => committed_version.saturating_sub(v),
+            None => committed_version + 1,
+        }
+    }
+}
+
+/// The backup storage is the source of truth of the progress. Timestamps in the checkpoint are
+/// kept only where it agrees with the backup storage.
+fn reconcile_checkpoint(
+    checkpoint: BackupCheckpoint,
+    backup_state: &BackupStorageState,
+) -> BackupCheckpoint {
+    if checkpoint.latest_epoch_ending_epoch > backup_state.latest_epoch_ending_epoch
+        || checkpoint.latest_state_snapshot_version > backup_state.latest_state_snapshot_version
+        || checkpoint.latest_transaction_version > backup_state.latest_transaction_version
+    {
+        warn!(
+            checkpoint = ?checkpoint,
+            backup_storage_state = %backup_state,
+            "Checkpoint is ahead of the backup storage, backups might have been deleted."
+        );
+    }
+    BackupCheckpoint {
+        latest_epoch_ending_epoch: backup_state.latest_epoch_ending_epoch,
+        latest_state_snapshot_version: backup_state.latest_state_snapshot_version,
+        latest_state_snapshot_ts: checkpoint.latest_state_snapshot_ts.filter(|_| {
+            checkpoint.latest_state_snapshot_version == backup_state.latest_state_snapshot_version
+        }),
+        latest_transaction_version: backup_state.latest_transaction_version,
+        latest_transaction_ts: checkpoint.latest_transaction_ts.filter(|_| {
+            checkpoint.latest_transaction_version == backup_state.latest_transaction_version
+        }),
+    }
+}
+
 fn get_batch_range(last_in_backup: Option<u64>, batch_size: usize) -> (u64, u64) {
     // say, 7 is already in backup, and we target batches of size 10, we will return (8, 10) in this
     // case, so 8, 9, 10 will be in this batch, and next time the backup worker will pass in 10,
@@ -341,6 +579,20 @@ fn get_batch_range(last_in_backup: Option<u64>, batch_size: usize) -> (u64, u64)
     })
 }
 
+/// A time based snapshot is taken at the end of the latest transaction batch in the backup, so that
+/// a restore from it replays whole transaction backups, like from a version based snapshot.
+fn get_time_based_snapshot(
+    last_snapshot_in_backup: Option<u64>,
+    last_transaction_in_backup: Option<u64>,
+) -> Option<u64> {
+    let version = last_transaction_in_backup?;
+    if last_snapshot_in_backup.map_or(false, |v| v >= version) {
+        None
+    } else {
+        Some(version)
+    }
+}
+
 fn get_next_snapshot(last_in_backup: Option<u64>, db_state: DbState, interval: usize) -> u64 {
     // We don't try to guarantee snapshots are taken at each applicable interval: when the backup
     // progress can't keep up with the ledger growth, we favor timeliness over completeness.
@@ -359,7 +611,16 @@ fn get_next_snapshot(last_in_backup: Option<u64>, db_state: DbState, interval: u
 
 #[cfg(test)]
 mod tests {
-    use crate::coordinators::backup::{get_batch_range, get_next_snapshot};
+    use crate::{
+        coordinators::{
+            backup::{
+                get_batch_range, get_next_snapshot, get_time_based_snapshot, reconcile_checkpoint,
+                TransactionLag,
+            },
+            checkpoint::BackupCheckpoint,
+        },
+        metadata::view::BackupStorageState,
+    };
     use diemdb::backup::backup_handler::DbState;
 
     #[test]
@@ -386,4 +647,89 @@ mod tests {
         assert_eq!(get_next_snapshot(Some(0), _state(250), 100), 200);
         assert_eq!(get_next_snapshot(Some(200), _state(250), 100), 300);
     }
+
+    #[test]
+    fn test_get_time_based_snapshot() {
+        assert_eq!(get_time_based_snapshot(None, None), None);
+        assert_eq!(get_time_based_snapshot(None, Some(0)), Some(0));
+        assert_eq!(get_time_based_snapshot(Some(0), Some(0)), None);
+        assert_eq!(get_time_based_snapshot(Some(0), Some(157)), Some(157));
+        assert_eq!(get_time_based_snapshot(Some(200), Some(157)), None);
+    }
+
+    #[test]
+    fn test_transaction_lag() {
+        let mut lag = TransactionLag::new(Some(9), None);
+        lag.observe(9, 100);
+        assert_eq!(lag.pending_since(), None);
+        assert_eq!(lag.num_pending(9), 0);
+
+        lag.observe(20, 101);
+        lag.observe(20, 102);
+        lag.observe(30, 103);
+        assert_eq!(lag.pending_since(), Some(101));
+        assert_eq!(lag.num_pending(30), 21);
+
+        // Versions up to 20 were first seen at 101, the rest at 103.
+        lag.backed_up(15);
+        assert_eq!(lag.pending_since(), Some(101));
+        lag.backed_up(20);
+        assert_eq!(lag.pending_since(), Some(103));
+        lag.backed_up(30);
+        assert_eq!(lag.pending_since(), None);
+        assert_eq!(lag.num_pending(30), 0);
+
+        // Transactions pending on the first observation are assumed to be waiting since the
+        // latest backup.
+        let mut lag = TransactionLag::new(Some(9), Some(90));
+        lag.observe(20, 100);
+        lag.observe(30, 101);
+        assert_eq!(lag.pending_since(), Some(90));
+        lag.backed_up(20);
+        assert_eq!(lag.pending_since(), Some(101));
+
+        let mut lag = TransactionLag::new(None, None);
+        assert_eq!(lag.num_pending(0), 1);
+        lag.observe(0, 100);
+        assert_eq!(lag.pending_since(), Some(100));
+    }
+
+    #[test]
+    fn test_reconcile_checkpoint() {
+        let checkpoint = BackupCheckpoint {
+            latest_epoch_ending_epoch: Some(1),
+            latest_state_snapshot_version: Some(100),
+            latest_state_snapshot_ts: Some(1000),
+            latest_transaction_version: Some(150),
+            latest_transaction_ts: Some(1500),
+        };
+        let backup_state = BackupStorageState {
+            latest_epoch_ending_epoch: Some(2),
+            latest_state_snapshot_version: Some(100),
+            latest_transaction_version: Some(200),
+        };
+        // Progress comes from the backup storage, the transaction backup time is unknown since
+        // the checkpoint fell behind.
+        assert_eq!(
+            reconcile_checkpoint(checkpoint, &backup_state),
+            BackupCheckpoint {
+                latest_epoch_ending_epoch: Some(2),
+                latest_state_snapshot_version: Some(100),
+                latest_state_snapshot_ts: Some(1000),
+                latest_transaction_version: Some(200),
+                latest_transaction_ts: None,
+            }
+        );
+        assert_eq!(
+            reconcile_checkpoint(
+                checkpoint,
+                &BackupStorageState {
+                    latest_epoch_ending_epoch: None,
+                    latest_state_snapshot_version: None,
+                    latest_transaction_version: None,
+                }
+            ),
+            BackupCheckpoint::default()
+        );
+    }
 }
@@ -0,0 +1,60 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::utils::error_notes::ErrorNotes;
+use anyhow::Result;
+use diem_types::transaction::Version;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::{
+    fs::{rename, File, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+/// Progress of the backup coordinator, recorded after each backup is fully saved to the backup
+/// storage. Timestamps are in seconds since the Unix epoch, taken when the backups finished.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BackupCheckpoint {
+    pub latest_epoch_ending_epoch: Option<u64>,
+    pub latest_state_snapshot_version: Option<Version>,
+    pub latest_state_snapshot_ts: Option<i64>,
+    pub latest_transaction_version: Option<Version>,
+    pub latest_transaction_ts: Option<i64>,
+}
+
+impl BackupCheckpoint {
+    /// Returns the default, empty checkpoint if the file doesn't exist.
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = tokio::fs::read(path).await.err_notes(path)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Replaces the file atomically, syncing it to disk before returning.
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = tmp_path(path);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp_path)
+            .await
+            .err_notes(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(self)?).await?;
+        file.sync_all().await?;
+        rename(&tmp_path, path).await.err_notes(path)?;
+        // Make the rename durable as well.
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir).await.err_notes(dir)?.sync_all().await?;
+        }
+        Ok(())
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    tmp_path.into()
+}
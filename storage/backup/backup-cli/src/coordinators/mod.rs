@@ -2,5 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup;
+pub mod checkpoint;
+pub mod prune;
 pub mod restore;
 pub mod verify;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,193 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        state_snapshot::manifest::StateSnapshotBackup, transaction::manifest::TransactionBackup,
+    },
+    metadata,
+    metadata::{
+        cache::MetadataCacheOpt,
+        view::{PrunedBackups, RetentionPolicy},
+    },
+    metrics::prune::{
+        PRUNE_COORDINATOR_FAIL_TS, PRUNE_COORDINATOR_START_TS, PRUNE_COORDINATOR_SUCC_TS,
+        PRUNE_NUM_DELETED_FILES,
+    },
+    storage::{BackupStorage, FileHandle, ShellSafeName},
+    utils::{storage_ext::BackupStorageExt, stream::StreamX, unix_timestamp_sec},
+};
+use anyhow::Result;
+use diem_logger::prelude::*;
+use rand::random;
+use std::{convert::TryInto, sync::Arc};
+use structopt::StructOpt;
+use tokio_stream::StreamExt;
+
+#[derive(StructOpt)]
+pub struct PruneCoordinatorOpt {
+    #[structopt(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+
+    #[structopt(
+        long,
+        help = "Keep the latest N state snapshots, deleting older ones together with the \
+        transaction backups ending before the oldest kept one. Epoch ending backups are always \
+        kept. If not set, nothing is deleted and the metadata files are only compacted."
+    )]
+    pub num_state_snapshots_to_keep: Option<usize>,
+
+    #[structopt(
+        long,
+        help = "Only log what would be deleted, without deleting anything."
+    )]
+    pub dry_run: bool,
+}
+
+/// Deletes backups not needed under the retention policy, and compacts the metadata files into a
+/// single one.
+pub struct PruneCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    metadata_cache_opt: MetadataCacheOpt,
+    retention_policy: Option<RetentionPolicy>,
+    dry_run: bool,
+    concurrent_downloads: usize,
+}
+
+impl PruneCoordinator {
+    pub fn new(
+        opt: PruneCoordinatorOpt,
+        storage: Arc<dyn BackupStorage>,
+        concurrent_downloads: usize,
+    ) -> Self {
+        Self {
+            storage,
+            metadata_cache_opt: opt.metadata_cache_opt,
+            retention_policy: opt
+                .num_state_snapshots_to_keep
+                .map(|num_state_snapshots_to_keep| RetentionPolicy {
+                    num_state_snapshots_to_keep,
+                }),
+            dry_run: opt.dry_run,
+            concurrent_downloads,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("Prune coordinator started.");
+        PRUNE_COORDINATOR_START_TS.set(unix_timestamp_sec());
+
+        let ret = self.run_impl().await;
+
+        if let Err(e) = &ret {
+            error!(
+                error = ?e,
+                "Prune coordinator failed."
+            );
+            PRUNE_COORDINATOR_FAIL_TS.set(unix_timestamp_sec());
+        } else {
+            info!("Prune coordinator exiting with success.");
+            PRUNE_COORDINATOR_SUCC_TS.set(unix_timestamp_sec());
+        }
+
+        ret
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let mut metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?;
+        let pruned = match &self.retention_policy {
+            Some(policy) => metadata_view.prune(policy)?,
+            None => PrunedBackups::default(),
+        };
+        let metadata_files = metadata_view.metadata_files().to_vec();
+        if pruned.is_empty() && metadata_files.len() <= 1 {
+            info!("Nothing to prune or compact.");
+            return Ok(());
+        }
+
+        let mut backup_files = Vec::new();
+        for backup in &pruned.state_snapshot_backups {
+            let manifest: StateSnapshotBackup =
+                self.storage.load_json_file(&backup.manifest).await?;
+            for chunk in manifest.chunks {
+                backup_files.push(chunk.blobs);
+                backup_files.push(chunk.proof);
+            }
+            backup_files.push(manifest.proof);
+            backup_files.push(backup.manifest.clone());
+        }
+        for backup in &pruned.transaction_backups {
+            let manifest: TransactionBackup = self.storage.load_json_file(&backup.manifest).await?;
+            for chunk in manifest.chunks {
+                backup_files.push(chunk.transactions);
+                backup_files.push(chunk.proof);
+            }
+            backup_files.push(backup.manifest.clone());
+        }
+        info!(
+            "Planned to prune {} state snapshot backups and {} transaction backups ({} files), \
+            and to compact {} metadata files.",
+            pruned.state_snapshot_backups.len(),
+            pruned.transaction_backups.len(),
+            backup_files.len(),
+            metadata_files.len(),
+        );
+
+        if self.dry_run {
+            for file_handle in metadata_files.iter().chain(backup_files.iter()) {
+                info!("Would delete {}.", file_handle);
+            }
+            info!("This is a dry run.");
+            return Ok(());
+        }
+
+        // The kept entries are saved before any metadata file is deleted, and the pruned backups
+        // are deleted only after no metadata file refers to them, so an interrupted run never
+        // leaves behind metadata referring to missing files, only unreferenced backup files.
+        PRUNE_NUM_DELETED_FILES.set(0);
+        self.storage
+            .save_metadata_lines(
+                &Self::compacted_metadata_file_name()?,
+                &metadata_view.to_text_lines()?,
+            )
+            .await?;
+        self.delete_files(metadata_files).await?;
+        self.delete_files(backup_files).await?;
+
+        Ok(())
+    }
+}
+
+impl PruneCoordinator {
+    /// Compacted metadata files get unique names, since the metadata cache expects the content of
+    /// a file to never change.
+    fn compacted_metadata_file_name() -> Result<ShellSafeName> {
+        format!(
+            "compacted_{}.{:04x}.meta",
+            unix_timestamp_sec(),
+            random::<u16>()
+        )
+        .try_into()
+    }
+
+    async fn delete_files(&self, file_handles: Vec<FileHandle>) -> Result<()> {
+        let futs = file_handles.into_iter().map(|file_handle| async move {
+            self.storage.delete_file(&file_handle).await?;
+            PRUNE_NUM_DELETED_FILES.inc();
+            Ok(())
+        });
+        futures::stream::iter(futs)
+            .buffered_x(
+                self.concurrent_downloads * 2, /* buffer size */
+                self.concurrent_downloads,     /* concurrency */
+            )
+            .collect::<Result<Vec<_>>>()
+            .await?;
+        Ok(())
+    }
+}
@@ -15,10 +15,10 @@ use crate::{
     storage::BackupStorage,
     utils::{unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode},
 };
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Result};
 use diem_logger::prelude::*;
 use diem_types::transaction::Version;
-use std::sync::Arc;
+use std::{cmp::max, sync::Arc};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -110,6 +110,16 @@ impl RestoreCoordinator {
                 txn_resume_point
             );
         }
+        let first_version_needed = max(replay_transactions_from_version, txn_resume_point);
+        if let Some(b) = transactions.first() {
+            ensure!(
+                b.first_version <= first_version_needed,
+                "Transaction backups start from version {}, can't restore from version {}, \
+                older backups might have been pruned.",
+                b.first_version,
+                first_version_needed,
+            );
+        }
 
         let epoch_history = Arc::new(
             EpochHistoryRestoreController::new(
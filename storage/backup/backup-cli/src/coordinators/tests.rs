@@ -0,0 +1,384 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+        transaction::manifest::{TransactionBackup, TransactionChunk},
+    },
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        checkpoint::BackupCheckpoint,
+        prune::{PruneCoordinator, PruneCoordinatorOpt},
+    },
+    metadata::{cache, cache::MetadataCacheOpt, Metadata},
+    storage::{local_fs::LocalFs, BackupStorage, FileHandle},
+    utils::{
+        backup_service_client::BackupServiceClient,
+        codec::{Codec, Encoder},
+        test_utils::{start_local_backup_service, tmp_db_empty},
+        GlobalBackupOptions,
+    },
+};
+use diem_crypto::HashValue;
+use diem_proptest_helpers::ValueGenerator;
+use diem_temppath::TempPath;
+use diem_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionToCommit, Version},
+};
+use diemdb::{test_helper::arb_blocks_to_commit, DiemDB};
+use std::{convert::TryInto, path::Path, sync::Arc};
+use storage_interface::{DbReader, DbWriter};
+use structopt::StructOpt;
+use tokio::{
+    io::AsyncWriteExt,
+    runtime::Runtime,
+    time::{sleep, timeout, Duration},
+};
+
+async fn write_file(
+    store: &Arc<dyn BackupStorage>,
+    backup_handle: &str,
+    name: &str,
+    content: &[u8],
+) -> FileHandle {
+    let (file_handle, mut file) = store
+        .create_for_write(backup_handle, &name.parse().unwrap())
+        .await
+        .unwrap();
+    file.write_all(content).await.unwrap();
+    file.shutdown().await.unwrap();
+    file_handle
+}
+
+async fn save_metadata(store: &Arc<dyn BackupStorage>, metadata: Metadata) {
+    store
+        .save_metadata_line(&metadata.name(), &metadata.to_text_line().unwrap())
+        .await
+        .unwrap();
+}
+
+/// Fakes a state snapshot backup, returning all its files.
+async fn backup_state_snapshot(
+    store: &Arc<dyn BackupStorage>,
+    version: Version,
+) -> Vec<FileHandle> {
+    let backup_handle = store
+        .create_backup(&format!("state_ver_{}", version).try_into().unwrap())
+        .await
+        .unwrap();
+    let blobs = write_file(store, &backup_handle, "0-.chunk", b"blobs").await;
+    let chunk_proof = write_file(store, &backup_handle, "0-.proof", b"chunk proof").await;
+    let proof = write_file(store, &backup_handle, "state.proof", b"proof").await;
+    let manifest = StateSnapshotBackup {
+        version,
+        root_hash: HashValue::zero(),
+        chunks: vec![StateSnapshotChunk {
+            first_idx: 0,
+            last_idx: 0,
+            first_key: HashValue::zero(),
+            last_key: HashValue::zero(),
+            blobs: blobs.clone(),
+            proof: chunk_proof.clone(),
+        }],
+        proof: proof.clone(),
+        codec: Codec::default(),
+    };
+    let manifest_handle = write_file(
+        store,
+        &backup_handle,
+        "state.manifest",
+        &serde_json::to_vec(&manifest).unwrap(),
+    )
+    .await;
+    save_metadata(
+        store,
+        Metadata::new_state_snapshot_backup(version, manifest_handle.clone()),
+    )
+    .await;
+
+    vec![blobs, chunk_proof, proof, manifest_handle]
+}
+
+/// Fakes a transaction backup, returning all its files.
+async fn backup_transactions(
+    store: &Arc<dyn BackupStorage>,
+    first_version: Version,
+    last_version: Version,
+) -> Vec<FileHandle> {
+    let backup_handle = store
+        .create_backup(
+            &format!("transaction_{}-", first_version)
+                .try_into()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let transactions = write_file(store, &backup_handle, "0-.chunk", b"transactions").await;
+    let proof = write_file(store, &backup_handle, "0-.proof", b"proof").await;
+    let manifest = TransactionBackup {
+        first_version,
+        last_version,
+        chunks: vec![TransactionChunk {
+            first_version,
+            last_version,
+            transactions: transactions.clone(),
+            proof: proof.clone(),
+        }],
+        codec: Codec::default(),
+    };
+    let manifest_handle = write_file(
+        store,
+        &backup_handle,
+        "transaction.manifest",
+        &serde_json::to_vec(&manifest).unwrap(),
+    )
+    .await;
+    save_metadata(
+        store,
+        Metadata::new_transaction_backup(first_version, last_version, manifest_handle.clone()),
+    )
+    .await;
+
+    vec![transactions, proof, manifest_handle]
+}
+
+async fn num_existing(store: &Arc<dyn BackupStorage>, files: &[FileHandle]) -> usize {
+    let mut num = 0;
+    for file_handle in files {
+        if store.open_for_read(file_handle).await.is_ok() {
+            num += 1;
+        }
+    }
+    num
+}
+
+#[test]
+fn test_prune() {
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let cache_dir = TempPath::new();
+    let cache_dir_str = cache_dir.path().to_str().unwrap();
+    let rt = Runtime::new().unwrap();
+
+    let (pruned_files, kept_files) = rt.block_on(async {
+        let mut pruned_files = backup_state_snapshot(&store, 50).await;
+        pruned_files.extend(backup_transactions(&store, 0, 99).await);
+        let mut kept_files = backup_state_snapshot(&store, 150).await;
+        kept_files.extend(backup_transactions(&store, 100, 199).await);
+        kept_files.extend(backup_transactions(&store, 200, 299).await);
+        (pruned_files, kept_files)
+    });
+
+    let prune = |extra_args: &[&str]| {
+        let mut args = vec![
+            "exe",
+            "--metadata-cache-dir",
+            cache_dir_str,
+            "--num-state-snapshots-to-keep",
+            "1",
+        ];
+        args.extend(extra_args);
+        rt.block_on(
+            PruneCoordinator::new(
+                PruneCoordinatorOpt::from_iter(args),
+                Arc::clone(&store),
+                4, /* concurrent_downloads */
+            )
+            .run(),
+        )
+        .unwrap();
+    };
+
+    // A dry run deletes nothing.
+    prune(&["--dry-run"]);
+    assert_eq!(rt.block_on(store.list_metadata_files()).unwrap().len(), 5);
+    assert_eq!(
+        rt.block_on(num_existing(&store, &pruned_files)),
+        pruned_files.len()
+    );
+
+    prune(&[]);
+    assert_eq!(rt.block_on(store.list_metadata_files()).unwrap().len(), 1);
+    assert_eq!(rt.block_on(num_existing(&store, &pruned_files)), 0);
+    assert_eq!(
+        rt.block_on(num_existing(&store, &kept_files)),
+        kept_files.len()
+    );
+
+    let view = rt
+        .block_on(cache::sync_and_load(
+            &MetadataCacheOpt::from_iter(vec!["exe", "--metadata-cache-dir", cache_dir_str]),
+            Arc::clone(&store),
+            4, /* concurrent_downloads */
+        ))
+        .unwrap();
+    assert_eq!(
+        view.select_state_snapshot(Version::max_value())
+            .unwrap()
+            .map(|s| s.version),
+        Some(150)
+    );
+    assert_eq!(
+        view.select_transaction_backups(Version::max_value())
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Nothing left to prune or compact.
+    prune(&[]);
+    assert_eq!(rt.block_on(store.list_metadata_files()).unwrap().len(), 1);
+}
+
+fn commit_blocks(
+    db: &DiemDB,
+    blocks: &[(Vec<TransactionToCommit>, LedgerInfoWithSignatures)],
+) -> Version {
+    let mut next_ver = db.get_latest_version().map_or(0, |v| v + 1);
+    for (txns_to_commit, ledger_info_with_sigs) in blocks {
+        db.save_transactions(txns_to_commit, next_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        next_ver += txns_to_commit.len() as Version;
+    }
+    next_ver - 1
+}
+
+async fn wait_for_checkpoint(
+    path: &Path,
+    pred: impl Fn(&BackupCheckpoint) -> bool,
+) -> BackupCheckpoint {
+    loop {
+        let checkpoint = BackupCheckpoint::load(path).await.unwrap();
+        if pred(&checkpoint) {
+            return checkpoint;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[test]
+fn test_continuous_backup() {
+    let (_db_dir, db) = tmp_db_empty();
+    let blocks = ValueGenerator::new().generate(arb_blocks_to_commit());
+    let (first_blocks, more_blocks) = blocks.split_at((blocks.len() / 2).max(1));
+    let first_version = commit_blocks(&db, first_blocks);
+
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let cache_dir = TempPath::new();
+    let cache_dir_str = cache_dir.path().to_str().unwrap();
+    let checkpoint_file = TempPath::new();
+    let checkpoint_path = checkpoint_file.path();
+    let (rt, port) = start_local_backup_service(Arc::clone(&db));
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let coordinator = || {
+        BackupCoordinator::new(
+            BackupCoordinatorOpt::from_iter(vec![
+                "exe",
+                "--metadata-cache-dir",
+                cache_dir_str,
+                // Batches and version based snapshots are never full in this test, backups are
+                // driven by the lag and the time based schedule.
+                "--state-snapshot-interval",
+                "1000000",
+                "--transaction-batch-size",
+                "1000000",
+                "--max-transaction-lag-secs",
+                "1",
+                "--state-snapshot-interval-secs",
+                "1",
+                "--checkpoint-file",
+                checkpoint_path.to_str().unwrap(),
+            ]),
+            GlobalBackupOptions {
+                max_chunk_size: 1 << 20,
+                encoder: Arc::new(Encoder::default()),
+            },
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+    };
+    let caught_up = |version: Version| {
+        move |c: &BackupCheckpoint| {
+            c.latest_transaction_version == Some(version)
+                && c.latest_state_snapshot_version == Some(version)
+        }
+    };
+
+    // Newly committed transactions are backed up while the coordinator runs.
+    let coordinator1 = coordinator();
+    let (last_version, checkpoint) = rt.block_on(async {
+        let run_and_check = async {
+            wait_for_checkpoint(checkpoint_path, caught_up(first_version)).await;
+            let last_version = commit_blocks(&db, more_blocks);
+            (
+                last_version,
+                wait_for_checkpoint(checkpoint_path, caught_up(last_version)).await,
+            )
+        };
+        timeout(Duration::from_secs(60), async {
+            tokio::select! {
+                res = coordinator1.run() => panic!("Coordinator exited: {:?}", res),
+                ret = run_and_check => ret,
+            }
+        })
+        .await
+        .expect("Backup not caught up in time.")
+    });
+    assert!(checkpoint.latest_state_snapshot_ts.is_some());
+    assert!(checkpoint.latest_transaction_ts.is_some());
+
+    let load_metadata = || {
+        rt.block_on(cache::sync_and_load(
+            &MetadataCacheOpt::from_iter(vec!["exe", "--metadata-cache-dir", cache_dir_str]),
+            Arc::clone(&store),
+            4, /* concurrent_downloads */
+        ))
+        .unwrap()
+    };
+    let num_metadata_files = load_metadata().metadata_files().len();
+    let transaction_backups = load_metadata()
+        .select_transaction_backups(Version::max_value())
+        .unwrap();
+    assert_eq!(transaction_backups.first().unwrap().first_version, 0);
+    assert_eq!(
+        transaction_backups.last().unwrap().last_version,
+        last_version
+    );
+    // Time based snapshots are taken at the end of a transaction backup, not at whatever version
+    // happened to be committed.
+    let metadata = load_metadata();
+    let mut snapshot = metadata
+        .select_state_snapshot(Version::max_value())
+        .unwrap();
+    while let Some(s) = snapshot {
+        assert!(transaction_backups
+            .iter()
+            .any(|t| t.last_version == s.version));
+        snapshot = match s.version.checked_sub(1) {
+            Some(version) => metadata.select_state_snapshot(version).unwrap(),
+            None => None,
+        };
+    }
+
+    // A restarted coordinator resumes from where it was, without backing anything up again.
+    let coordinator2 = coordinator();
+    rt.block_on(async {
+        assert!(timeout(Duration::from_secs(3), coordinator2.run())
+            .await
+            .is_err());
+    });
+    assert_eq!(
+        rt.block_on(BackupCheckpoint::load(checkpoint_path))
+            .unwrap(),
+        checkpoint
+    );
+    assert_eq!(load_metadata().metadata_files().len(), num_metadata_files);
+}
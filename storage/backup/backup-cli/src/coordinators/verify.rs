@@ -13,18 +13,22 @@ use crate::{
         VERIFY_COORDINATOR_FAIL_TS, VERIFY_COORDINATOR_START_TS, VERIFY_COORDINATOR_SUCC_TS,
     },
     storage::BackupStorage,
-    utils::{unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode, TrustedWaypointOpt},
+    utils::{
+        codec::{Decoder, EncryptionKeyOpt},
+        unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode, TrustedWaypointOpt,
+    },
 };
 use anyhow::Result;
 use diem_logger::prelude::*;
 use diem_types::transaction::Version;
-use std::sync::Arc;
+use std::{convert::TryInto, sync::Arc};
 
 pub struct VerifyCoordinator {
     storage: Arc<dyn BackupStorage>,
     metadata_cache_opt: MetadataCacheOpt,
     trusted_waypoints_opt: TrustedWaypointOpt,
     concurrent_downloads: usize,
+    decoder: Arc<Decoder>,
 }
 
 impl VerifyCoordinator {
@@ -33,12 +37,14 @@ impl VerifyCoordinator {
         metadata_cache_opt: MetadataCacheOpt,
         trusted_waypoints_opt: TrustedWaypointOpt,
         concurrent_downloads: usize,
+        encryption_key_opt: EncryptionKeyOpt,
     ) -> Result<Self> {
         Ok(Self {
             storage,
             metadata_cache_opt,
             trusted_waypoints_opt,
             concurrent_downloads,
+            decoder: Arc::new(encryption_key_opt.try_into()?),
         })
     }
 
@@ -79,6 +85,7 @@ impl VerifyCoordinator {
             trusted_waypoints: Arc::new(self.trusted_waypoints_opt.verify()?),
             run_mode: Arc::new(RestoreRunMode::Verify),
             concurrent_downloads: self.concurrent_downloads,
+            decoder: self.decoder,
         };
 
         let epoch_history = Arc::new(
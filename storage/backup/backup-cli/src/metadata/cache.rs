@@ -147,7 +147,9 @@ pub async fn sync_and_load(
 
     // Load metadata from synced cache files.
     let mut metadata_vec = Vec::new();
+    let mut metadata_files = Vec::new();
     for h in new_remote_hashes.into_iter().chain(up_to_date_local_hashes) {
+        metadata_files.push(remote_file_handle_by_hash[h].clone());
         let cached_file = cache_dir.join(&*h);
         metadata_vec.extend(
             OpenOptions::new()
@@ -165,7 +167,7 @@ pub async fn sync_and_load(
         "Metadata cache loaded in {:.2} seconds.",
         timer.elapsed().as_secs_f64()
     );
-    Ok(metadata_vec.into())
+    Ok(MetadataView::new(metadata_vec, metadata_files))
 }
 
 trait FileHandleHash {
@@ -4,6 +4,9 @@
 pub mod cache;
 pub mod view;
 
+#[cfg(test)]
+mod tests;
+
 use crate::storage::{FileHandle, ShellSafeName, TextLine};
 use anyhow::Result;
 use diem_types::transaction::Version;
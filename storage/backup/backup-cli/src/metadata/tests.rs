@@ -0,0 +1,191 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metadata::{
+    view::{MetadataView, RetentionPolicy},
+    Metadata,
+};
+use diem_types::transaction::Version;
+
+fn state_snapshot(version: Version) -> Metadata {
+    Metadata::new_state_snapshot_backup(version, format!("state_snapshot_{}", version))
+}
+
+fn transaction(first_version: Version, last_version: Version) -> Metadata {
+    Metadata::new_transaction_backup(
+        first_version,
+        last_version,
+        format!("transaction_{}-{}", first_version, last_version),
+    )
+}
+
+fn epoch_ending(first_epoch: u64, last_epoch: u64, last_version: Version) -> Metadata {
+    Metadata::new_epoch_ending_backup(
+        first_epoch,
+        last_epoch,
+        0,
+        last_version,
+        format!("epoch_ending_{}-{}", first_epoch, last_epoch),
+    )
+}
+
+#[test]
+fn test_prune() {
+    let mut view = MetadataView::new(
+        vec![
+            epoch_ending(0, 1, 120),
+            epoch_ending(2, 3, 220),
+            state_snapshot(50),
+            state_snapshot(150),
+            state_snapshot(250),
+            transaction(0, 99),
+            transaction(100, 199),
+            transaction(200, 299),
+            transaction(300, 399),
+        ],
+        Vec::new(),
+    );
+
+    assert!(view
+        .prune(&RetentionPolicy {
+            num_state_snapshots_to_keep: 0
+        })
+        .is_err());
+
+    let policy = RetentionPolicy {
+        num_state_snapshots_to_keep: 2,
+    };
+    let pruned = view.prune(&policy).unwrap();
+    assert_eq!(
+        pruned
+            .state_snapshot_backups
+            .iter()
+            .map(|s| s.version)
+            .collect::<Vec<_>>(),
+        vec![50],
+    );
+    assert_eq!(
+        pruned
+            .transaction_backups
+            .iter()
+            .map(|t| (t.first_version, t.last_version))
+            .collect::<Vec<_>>(),
+        vec![(0, 99)],
+    );
+
+    // What's kept can still be restored from, to any version since the oldest kept snapshot.
+    assert_eq!(
+        view.select_state_snapshot(149).unwrap().map(|s| s.version),
+        None
+    );
+    assert_eq!(
+        view.select_state_snapshot(150).unwrap().map(|s| s.version),
+        Some(150)
+    );
+    assert_eq!(
+        view.select_transaction_backups(Version::max_value())
+            .unwrap()
+            .iter()
+            .map(|t| t.first_version)
+            .collect::<Vec<_>>(),
+        vec![100, 200, 300],
+    );
+    assert_eq!(
+        view.select_epoch_ending_backups(Version::max_value())
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Nothing more to prune under the same policy.
+    assert!(view.prune(&policy).unwrap().is_empty());
+}
+
+#[test]
+fn test_prune_keeps_latest_transaction_backup() {
+    let mut view = MetadataView::new(
+        vec![
+            state_snapshot(50),
+            state_snapshot(500),
+            transaction(0, 99),
+            transaction(100, 199),
+        ],
+        Vec::new(),
+    );
+    let pruned = view
+        .prune(&RetentionPolicy {
+            num_state_snapshots_to_keep: 1,
+        })
+        .unwrap();
+    assert_eq!(pruned.state_snapshot_backups.len(), 1);
+    assert_eq!(
+        pruned
+            .transaction_backups
+            .iter()
+            .map(|t| t.first_version)
+            .collect::<Vec<_>>(),
+        vec![0],
+    );
+    assert_eq!(
+        view.get_storage_state().latest_transaction_version,
+        Some(199)
+    );
+
+    // Without any state snapshot, all transactions are needed.
+    let mut view = MetadataView::new(vec![transaction(0, 99), transaction(100, 199)], Vec::new());
+    assert!(view
+        .prune(&RetentionPolicy {
+            num_state_snapshots_to_keep: 1,
+        })
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_duplicated_entries() {
+    // As if compaction was interrupted before the old metadata files were deleted.
+    let view = MetadataView::new(
+        vec![
+            epoch_ending(0, 1, 120),
+            state_snapshot(50),
+            transaction(0, 99),
+            transaction(100, 199),
+            epoch_ending(0, 1, 120),
+            state_snapshot(50),
+            transaction(0, 99),
+        ],
+        Vec::new(),
+    );
+    assert_eq!(
+        view.select_transaction_backups(Version::max_value())
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let lines = view.to_text_lines().unwrap();
+    assert_eq!(lines.len(), 4);
+    let reloaded = MetadataView::new(
+        lines
+            .iter()
+            .map(|l| serde_json::from_str(l.as_ref()).unwrap())
+            .collect(),
+        Vec::new(),
+    );
+    assert_eq!(reloaded.to_text_lines().unwrap(), lines);
+}
+
+#[test]
+fn test_select_transaction_backups_not_from_genesis() {
+    let view = MetadataView::new(
+        vec![transaction(100, 199), transaction(200, 299)],
+        Vec::new(),
+    );
+    assert_eq!(view.select_transaction_backups(250).unwrap().len(), 2);
+
+    let view = MetadataView::new(
+        vec![transaction(100, 199), transaction(300, 399)],
+        Vec::new(),
+    );
+    assert!(view.select_transaction_backups(350).is_err());
+}
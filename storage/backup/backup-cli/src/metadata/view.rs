@@ -1,8 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::metadata::{
-    EpochEndingBackupMeta, Metadata, StateSnapshotBackupMeta, TransactionBackupMeta,
+use crate::{
+    metadata::{EpochEndingBackupMeta, Metadata, StateSnapshotBackupMeta, TransactionBackupMeta},
+    storage::{FileHandle, TextLine},
 };
 use anyhow::{anyhow, ensure, Result};
 use diem_types::transaction::Version;
@@ -13,9 +14,113 @@ pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
     state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
     transaction_backups: Vec<TransactionBackupMeta>,
+    /// The metadata files the entries are loaded from.
+    metadata_files: Vec<FileHandle>,
 }
 
 impl MetadataView {
+    pub(crate) fn new(metadata_vec: Vec<Metadata>, metadata_files: Vec<FileHandle>) -> Self {
+        let mut epoch_ending_backups = Vec::new();
+        let mut state_snapshot_backups = Vec::new();
+        let mut transaction_backups = Vec::new();
+
+        for meta in metadata_vec {
+            match meta {
+                Metadata::EpochEndingBackup(e) => epoch_ending_backups.push(e),
+                Metadata::StateSnapshotBackup(s) => state_snapshot_backups.push(s),
+                Metadata::TransactionBackup(t) => transaction_backups.push(t),
+            }
+        }
+
+        // The same entry shows up in more than one file if compaction was interrupted after
+        // saving the compacted file but before deleting the old ones.
+        epoch_ending_backups.sort();
+        epoch_ending_backups.dedup();
+        state_snapshot_backups.sort();
+        state_snapshot_backups.dedup();
+        transaction_backups.sort();
+        transaction_backups.dedup();
+
+        Self {
+            epoch_ending_backups,
+            state_snapshot_backups,
+            transaction_backups,
+            metadata_files,
+        }
+    }
+
+    pub fn metadata_files(&self) -> &[FileHandle] {
+        &self.metadata_files
+    }
+
+    /// All entries, one per line, to be saved in a single metadata file when compacting.
+    pub fn to_text_lines(&self) -> Result<Vec<TextLine>> {
+        self.epoch_ending_backups
+            .iter()
+            .cloned()
+            .map(Metadata::EpochEndingBackup)
+            .chain(
+                self.state_snapshot_backups
+                    .iter()
+                    .cloned()
+                    .map(Metadata::StateSnapshotBackup),
+            )
+            .chain(
+                self.transaction_backups
+                    .iter()
+                    .cloned()
+                    .map(Metadata::TransactionBackup),
+            )
+            .map(|m| m.to_text_line())
+            .collect()
+    }
+
+    /// Removes the backups not needed under `policy` from the view, and returns them.
+    pub fn prune(&mut self, policy: &RetentionPolicy) -> Result<PrunedBackups> {
+        ensure!(
+            policy.num_state_snapshots_to_keep > 0,
+            "At least one state snapshot must be kept.",
+        );
+
+        // Without a state snapshot, restoring replays all transactions from genesis.
+        let oldest_kept_snapshot_version = match self
+            .state_snapshot_backups
+            .iter()
+            .rev()
+            .take(policy.num_state_snapshots_to_keep)
+            .last()
+        {
+            Some(s) => s.version,
+            None => return Ok(PrunedBackups::default()),
+        };
+        // The latest transaction backup is always kept, otherwise the backup coordinator would
+        // start over from genesis.
+        let latest_transaction_version = self
+            .transaction_backups
+            .iter()
+            .map(|t| t.last_version)
+            .max()
+            .unwrap_or(0);
+
+        let (state_snapshot_backups, kept) = self
+            .state_snapshot_backups
+            .drain(..)
+            .partition(|s| s.version < oldest_kept_snapshot_version);
+        self.state_snapshot_backups = kept;
+        // Keep the transactions since the oldest kept state snapshot, including the one at its
+        // version, so restoring to any version since then needs nothing pruned.
+        let (transaction_backups, kept) = self.transaction_backups.drain(..).partition(|t| {
+            t.last_version < oldest_kept_snapshot_version
+                && t.last_version < latest_transaction_version
+        });
+        self.transaction_backups = kept;
+
+        Ok(PrunedBackups {
+            state_snapshot_backups,
+            transaction_backups,
+        })
+    }
+
     pub fn get_storage_state(&self) -> BackupStorageState {
         let latest_epoch_ending_epoch =
             self.epoch_ending_backups.iter().map(|e| e.last_epoch).max();
@@ -52,23 +157,26 @@ impl MetadataView {
         target_version: Version,
     ) -> Result<Vec<TransactionBackupMeta>> {
         // This can be more flexible, but for now we assume and check backups are continuous in
-        // range (which is always true when we backup from a single backup coordinator)
-        let mut next_ver = 0;
+        // range (which is always true when we backup from a single backup coordinator). They
+        // don't have to start from genesis, since older ones can be pruned.
+        let mut next_ver = None;
         let mut res = Vec::new();
         for backup in self.transaction_backups.iter().sorted() {
             if backup.first_version > target_version {
                 break;
             }
-            ensure!(
-                backup.first_version == next_ver,
-                "Transactioon backup ranges not continuous, expecting version {}, got {}.",
-                next_ver,
-                backup.first_version,
-            );
+            if let Some(next_ver) = next_ver {
+                ensure!(
+                    backup.first_version == next_ver,
+                    "Transactioon backup ranges not continuous, expecting version {}, got {}.",
+                    next_ver,
+                    backup.first_version,
+                );
+            }
 
             res.push(backup.clone());
 
-            next_ver = backup.last_version + 1;
+            next_ver = Some(backup.last_version + 1);
         }
 
         Ok(res)
@@ -102,25 +210,24 @@ impl MetadataView {
     }
 }
 
-impl From<Vec<Metadata>> for MetadataView {
-    fn from(metadata_vec: Vec<Metadata>) -> Self {
-        let mut epoch_ending_backups = Vec::new();
-        let mut state_snapshot_backups = Vec::new();
-        let mut transaction_backups = Vec::new();
+/// Decides which backups are kept when pruning. Epoch ending backups are always kept, since they
+/// are small and all of them are needed to verify any other backup.
+pub struct RetentionPolicy {
+    /// Number of the latest state snapshots to keep. Transaction backups are kept back to the
+    /// oldest of them.
+    pub num_state_snapshots_to_keep: usize,
+}
 
-        for meta in metadata_vec {
-            match meta {
-                Metadata::EpochEndingBackup(e) => epoch_ending_backups.push(e),
-                Metadata::StateSnapshotBackup(s) => state_snapshot_backups.push(s),
-                Metadata::TransactionBackup(t) => transaction_backups.push(t),
-            }
-        }
+/// Backups not needed under a `RetentionPolicy`, see `MetadataView::prune()`.
+#[derive(Default)]
+pub struct PrunedBackups {
+    pub state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
+    pub transaction_backups: Vec<TransactionBackupMeta>,
+}
 
-        Self {
-            epoch_ending_backups,
-            state_snapshot_backups,
-            transaction_backups,
-        }
+impl PrunedBackups {
+    pub fn is_empty(&self) -> bool {
+        self.state_snapshot_backups.is_empty() && self.transaction_backups.is_empty()
     }
 }
 
@@ -35,3 +35,35 @@ pub static TRANSACTION_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static STATE_SNAPSHOT_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_coordinator_state_snapshot_timestamp_s",
+        "Timestamp when the latest state snapshot was taken."
+    )
+    .unwrap()
+});
+
+pub static TRANSACTION_LAG_VERSIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_coordinator_transaction_lag_versions",
+        "Number of committed transactions not yet backed up."
+    )
+    .unwrap()
+});
+
+pub static TRANSACTION_LAG_SECS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_coordinator_transaction_lag_s",
+        "Seconds the oldest committed transaction not yet backed up has been waiting."
+    )
+    .unwrap()
+});
+
+pub static TRANSACTION_LAG_ALERT: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_coordinator_transaction_lag_alert",
+        "1 if the transaction lag exceeds the configured threshold, 0 otherwise."
+    )
+    .unwrap()
+});
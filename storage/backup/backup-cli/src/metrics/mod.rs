@@ -3,5 +3,6 @@
 
 pub mod backup;
 pub mod metadata;
+pub mod prune;
 pub mod restore;
 pub mod verify;
@@ -0,0 +1,37 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_secure_push_metrics::{register_int_gauge, IntGauge};
+use once_cell::sync::Lazy;
+
+pub static PRUNE_NUM_DELETED_FILES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_prune_num_deleted_files",
+        "Number of backup and metadata files deleted by the prune coordinator."
+    )
+    .unwrap()
+});
+
+pub static PRUNE_COORDINATOR_START_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_prune_coordinator_start_timestamp_s",
+        "Timestamp when the prune coordinator starts."
+    )
+    .unwrap()
+});
+
+pub static PRUNE_COORDINATOR_SUCC_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_prune_coordinator_succeed_timestamp_s",
+        "Timestamp when the prune coordinator succeeds."
+    )
+    .unwrap()
+});
+
+pub static PRUNE_COORDINATOR_FAIL_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_prune_coordinator_fail_timestamp_s",
+        "Timestamp when the prune coordinator fails."
+    )
+    .unwrap()
+});
@@ -59,14 +59,20 @@ pub struct Commands {
     ///     $FILE_NAME
     /// expected stdout to stream out bytes of the file.
     pub open_for_read: String,
-    /// Command line to save a line of metadata
+    /// Command line to save a line of metadata, or a file of lines when compacting metadata.
     /// input env vars:
     ///     $FILE_NAME
-    /// stdin will be fed with a line of text with a trailing newline.
+    /// stdin will be fed with lines of text, each with a trailing newline.
     pub save_metadata_line: String,
     /// Command line to list all existing metadata file handles.
     /// expected stdout to stream out lines of file handles.
     pub list_metadata_files: String,
+    /// Command line to delete a file, used when pruning backups and compacting metadata; only
+    /// those fail if it's not configured.
+    /// input env vars:
+    ///     $FILE_HANDLE, of a backup file or a metadata file
+    #[serde(default)]
+    pub delete_file: Option<String>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -18,7 +18,7 @@ use crate::{
     },
     utils::error_notes::ErrorNotes,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -114,6 +114,11 @@ impl BackupStorage for CommandAdapter {
     }
 
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.save_metadata_lines(name, std::slice::from_ref(content))
+            .await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
         let mut child = self
             .cmd(
                 &self.config.commands.save_metadata_line,
@@ -121,9 +126,10 @@ impl BackupStorage for CommandAdapter {
             )
             .spawn()?;
 
+        let content = lines.iter().map(AsRef::<str>::as_ref).collect::<String>();
         child
             .stdin()
-            .write_all(content.as_ref().as_bytes())
+            .write_all(content.as_bytes())
             .await
             .err_notes(name)?;
         child.join().await?;
@@ -143,4 +149,18 @@ impl BackupStorage for CommandAdapter {
             .err_notes((file!(), line!(), &buf))?;
         Ok(buf.lines().map(str::to_string).collect())
     }
+
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let cmd = self
+            .config
+            .commands
+            .delete_file
+            .as_ref()
+            .ok_or_else(|| anyhow!("Command delete_file is not configured."))?;
+        self.cmd(cmd, vec![EnvVar::file_handle(file_handle.to_string())])
+            .spawn()?
+            .join()
+            .await
+            .err_notes(file_handle)
+    }
 }
@@ -5,8 +5,8 @@ use super::*;
 use crate::storage::{
     command_adapter::config::Commands,
     test_util::{
-        arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
-        test_write_and_read_impl,
+        arb_backups, arb_metadata_files, test_compact_metadata_files_impl, test_delete_files_impl,
+        test_save_and_list_metadata_files_impl, test_write_and_read_impl,
     },
 };
 use diem_temppath::TempPath;
@@ -29,6 +29,7 @@ fn get_store(tmpdir: &TempPath) -> Box<dyn BackupStorage> {
                 open_for_read = 'cat "$FOLDER/$FILE_HANDLE"'
                 save_metadata_line= 'cd "$FOLDER" && mkdir -p metadata && cd metadata && cat > $FILE_NAME'
                 list_metadata_files = 'cd "$FOLDER" && (test -d metadata && cd metadata && ls -1 || exec) | while read f; do echo metadata/$f; done'
+                delete_file = 'rm "$FOLDER/$FILE_HANDLE"'
             "#, tmpdir.path().to_str().unwrap()),
     ).unwrap();
 
@@ -57,6 +58,22 @@ proptest! {
         let tmpdir = TempPath::new();
         block_on(test_save_and_list_metadata_files_impl(get_store(&tmpdir), input));
     }
+
+    #[test]
+    fn test_delete_files(
+        backups in arb_backups()
+    ) {
+        let tmpdir = TempPath::new();
+        block_on(test_delete_files_impl(get_store(&tmpdir), backups));
+    }
+
+    #[test]
+    fn test_compact_metadata_files(
+        input in arb_metadata_files(),
+    ) {
+        let tmpdir = TempPath::new();
+        block_on(test_compact_metadata_files_impl(get_store(&tmpdir), input));
+    }
 }
 
 fn dummy_store(cmd: &str) -> CommandAdapter {
@@ -67,6 +84,7 @@ fn dummy_store(cmd: &str) -> CommandAdapter {
             open_for_read: cmd.to_string(),
             save_metadata_line: cmd.to_string(),
             list_metadata_files: cmd.to_string(),
+            delete_file: Some(cmd.to_string()),
         },
         env_vars: Vec::new(),
     })
@@ -113,6 +131,9 @@ async fn assert_commands_error(cmd: &str) {
 
     // list_metadata_files
     assert!(store.list_metadata_files().await.is_err());
+
+    // delete_file
+    assert!(store.delete_file(&handle).await.is_err());
 }
 
 async fn assert_commands_okay(cmd: &str) {
@@ -148,7 +169,10 @@ async fn assert_commands_okay(cmd: &str) {
         .unwrap();
 
     // list_metadata_files
-    assert_eq!(store.list_metadata_files().await.unwrap(), vec!["okay"])
+    assert_eq!(store.list_metadata_files().await.unwrap(), vec!["okay"]);
+
+    // delete_file
+    store.delete_file(&handle).await.unwrap();
 }
 
 #[test]
@@ -15,7 +15,7 @@ use async_trait::async_trait;
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use tokio::{
-    fs::{create_dir, create_dir_all, read_dir, OpenOptions},
+    fs::{create_dir, create_dir_all, read_dir, remove_dir, remove_file, OpenOptions},
     io::{AsyncRead, AsyncWrite, AsyncWriteExt},
 };
 
@@ -92,6 +92,11 @@ impl BackupStorage for LocalFs {
     }
 
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.save_metadata_lines(name, std::slice::from_ref(content))
+            .await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
         let dir = self.metadata_dir();
         create_dir_all(&dir).await.err_notes(name)?; // in case not yet created
 
@@ -102,9 +107,8 @@ impl BackupStorage for LocalFs {
             .open(&path)
             .await
             .err_notes(&path)?;
-        file.write_all(content.as_ref().as_bytes())
-            .await
-            .err_notes(&path)?;
+        let content = lines.iter().map(AsRef::<str>::as_ref).collect::<String>();
+        file.write_all(content.as_bytes()).await.err_notes(&path)?;
 
         Ok(())
     }
@@ -122,4 +126,18 @@ impl BackupStorage for LocalFs {
         }
         Ok(res)
     }
+
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let path = self.dir.join(file_handle);
+        remove_file(&path).await.err_notes(&path)?;
+
+        // Remove the backup folder together with its last file. This fails harmlessly while other
+        // files are left in it, or if a concurrent call has removed it.
+        if let Some(backup_dir) = path.parent() {
+            if backup_dir != self.dir && backup_dir != self.metadata_dir() {
+                let _ = remove_dir(backup_dir).await;
+            }
+        }
+        Ok(())
+    }
 }
@@ -3,8 +3,8 @@
 
 use super::*;
 use crate::storage::test_util::{
-    arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
-    test_write_and_read_impl,
+    arb_backups, arb_metadata_files, test_compact_metadata_files_impl, test_delete_files_impl,
+    test_save_and_list_metadata_files_impl, test_write_and_read_impl,
 };
 use diem_temppath::TempPath;
 use proptest::prelude::*;
@@ -36,4 +36,30 @@ proptest! {
         let rt = Runtime::new().unwrap();
         rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
     }
+
+    #[test]
+    fn test_delete_files(
+        backups in arb_backups()
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = LocalFs::new(tmpdir.path().to_path_buf());
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_delete_files_impl(Box::new(store), backups));
+        // Backup folders are removed together with their last files.
+        prop_assert_eq!(std::fs::read_dir(tmpdir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_compact_metadata_files(
+        input in arb_metadata_files(),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = LocalFs::new(tmpdir.path().to_path_buf());
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_compact_metadata_files_impl(Box::new(store), input));
+    }
 }
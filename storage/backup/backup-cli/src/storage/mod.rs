@@ -160,6 +160,10 @@ pub trait BackupStorage: Send + Sync {
     /// is straightforward and acceptable.
     /// See `list_metadata_files`.
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()>;
+    /// Asks to save a metadata file holding multiple entries, one per line, which is how the
+    /// metadata gets compacted: entries of many metadata files are saved into a single new one
+    /// before the old ones are deleted. Otherwise the same with `save_metadata_line`.
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()>;
     /// The backup system always asks for all metadata files and cache and build index on top of
     /// the content of them. This means:
     ///   1. The storage is free to reorganise the metadata files, like combining multiple ones to
@@ -167,6 +171,9 @@ pub trait BackupStorage: Send + Sync {
     ///   2. But the cache does expect the content stays the same for a file handle, so when
     /// reorganising metadata files, give them new unique names.
     async fn list_metadata_files(&self) -> Result<Vec<FileHandle>>;
+    /// Deletes a file, either one returned by `create_for_write()` or a metadata file returned by
+    /// `list_metadata_files()`. Backups are pruned and metadata files are compacted through this.
+    async fn delete_file(&self, file_handle: &FileHandleRef) -> Result<()>;
 }
 
 #[derive(StructOpt)]
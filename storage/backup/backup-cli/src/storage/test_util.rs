@@ -11,7 +11,7 @@ use proptest::{
     collection::{hash_map, vec},
     prelude::*,
 };
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, str::FromStr};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 fn to_file_name(backup_name: &str, file_name: &str) -> String {
@@ -47,6 +47,39 @@ pub async fn test_write_and_read_impl(
     }
 }
 
+pub async fn test_delete_files_impl(
+    store: Box<dyn BackupStorage>,
+    backups: HashMap<ShellSafeName, HashMap<ShellSafeName, Vec<u8>>>,
+) {
+    let mut handles = Vec::new();
+    for (backup_name, files) in &backups {
+        let backup_handle = store.create_backup(backup_name).await.unwrap();
+        for (name, content) in files {
+            let (handle, mut file) = store.create_for_write(&backup_handle, name).await.unwrap();
+            file.write_all(content).await.unwrap();
+            file.shutdown().await.unwrap();
+            handles.push(handle);
+        }
+    }
+
+    for handle in &handles {
+        store.delete_file(handle).await.unwrap();
+    }
+
+    for handle in &handles {
+        let mut buf = Vec::new();
+        let res: Result<usize> = async {
+            Ok(store
+                .open_for_read(handle)
+                .await?
+                .read_to_end(&mut buf)
+                .await?)
+        }
+        .await;
+        assert!(res.is_err());
+    }
+}
+
 pub fn arb_backups(
 ) -> impl Strategy<Value = HashMap<ShellSafeName, HashMap<ShellSafeName, Vec<u8>>>> {
     hash_map(
@@ -97,6 +130,47 @@ pub async fn test_save_and_list_metadata_files_impl(
     assert_eq!(read_back, expected)
 }
 
+pub async fn test_compact_metadata_files_impl(
+    store: Box<dyn BackupStorage>,
+    input: Vec<(ShellSafeName, TextLine)>,
+) {
+    for (name, content) in &input {
+        store.save_metadata_line(name, &content).await.unwrap();
+    }
+    let old_files = store.list_metadata_files().await.unwrap();
+
+    let compacted_name = ShellSafeName::from_str("compacted-0.meta").unwrap();
+    let lines = input
+        .into_iter()
+        .map(|(_name, content)| content)
+        .sorted()
+        .collect::<Vec<_>>();
+    store
+        .save_metadata_lines(&compacted_name, &lines)
+        .await
+        .unwrap();
+    for file_handle in &old_files {
+        store.delete_file(file_handle).await.unwrap();
+    }
+
+    let files = store.list_metadata_files().await.unwrap();
+    assert_eq!(files.len(), 1);
+    let mut buf = String::new();
+    store
+        .open_for_read(&files[0])
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    let read_back = buf
+        .lines()
+        .map(TextLine::new)
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(read_back, lines);
+}
+
 pub fn arb_metadata_files() -> impl Strategy<Value = Vec<(ShellSafeName, TextLine)>> {
     hash_map(any::<ShellSafeName>(), any::<TextLine>(), 0..10)
         .prop_map(HashMap::into_iter)
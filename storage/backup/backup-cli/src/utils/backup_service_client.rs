@@ -7,6 +7,7 @@ use diem_crypto::HashValue;
 use diem_types::transaction::Version;
 use diemdb::backup::backup_handler::DbState;
 use futures::TryStreamExt;
+use std::path::Path;
 use structopt::StructOpt;
 use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
@@ -120,4 +121,19 @@ impl BackupServiceClient {
         ))
         .await
     }
+
+    /// Creates a checkpoint of the node's DBs under `checkpoint_root_path`, which is relative to
+    /// the checkpoint directory configured on the node.
+    pub async fn create_checkpoint(&self, checkpoint_root_path: &Path) -> Result<()> {
+        let url = format!("{}/checkpoint", self.address);
+        self.client
+            .post(&url)
+            .query(&[("path", checkpoint_root_path)])
+            .send()
+            .await
+            .err_notes(&url)?
+            .error_for_status()
+            .err_notes(&url)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,436 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional compression and encryption of the chunk and proof files of a backup, applied before
+//! they reach the backup storage, so neither the storage nor the tools it shells out to see the
+//! plain content. Manifests stay plain and record the `Codec` of the files they refer to, which is
+//! all the restore side needs besides the key.
+
+use crate::storage::FileHandleRef;
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use anyhow::{anyhow, ensure, Result};
+use diem_config::config;
+use diem_crypto::HashValue;
+use diem_management::secure_backend::SecureBackend;
+use diem_secure_storage::{KVStorage, Storage};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::{TryFrom, TryInto},
+    fs,
+    path::PathBuf,
+};
+use structopt::StructOpt;
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+/// Authenticated together with every encrypted file, followed by the file handle, binding the
+/// ciphertext to this format and to the file it was written to.
+const AAD_PREFIX: &[u8] = b"DIEM_BACKUP_FILE_V1";
+/// Prefixed to the key when hashing it into its ID, so the ID can't be mistaken for another hash.
+const KEY_ID_SALT: &[u8] = b"DIEM_BACKUP_ENCRYPTION_KEY_ID";
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Zstd,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encryption {
+    /// Each file is a random 96-bit nonce followed by the AES-256-GCM ciphertext, which
+    /// authenticates the file handle as well, so a file can't be passed off as another one.
+    Aes256Gcm {
+        /// Identifies the key without revealing it, see `EncryptionKey::id()`.
+        key_id: String,
+    },
+}
+
+/// How the chunk and proof files referred to by a manifest are encoded. Files are compressed
+/// before being encrypted. Manifests of backups taken before encoding was supported lack it, and
+/// default to plain files.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Codec {
+    pub compression: Option<Compression>,
+    pub encryption: Option<Encryption>,
+}
+
+/// A 256-bit AES key, formatted as hex in key files and secure storage.
+pub struct EncryptionKey([u8; KEY_SIZE]);
+
+impl EncryptionKey {
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = hex::decode(hex.trim())?;
+        ensure!(
+            bytes.len() == KEY_SIZE,
+            "Encryption key must be {} bytes, got {}.",
+            KEY_SIZE,
+            bytes.len(),
+        );
+        let mut key = [0; KEY_SIZE];
+        key.copy_from_slice(&bytes);
+        Ok(Self(key))
+    }
+
+    pub fn generate() -> Self {
+        let mut key = [0; KEY_SIZE];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    /// The first 4 bytes of a hash of the key in hex, recorded in manifests so a wrong key is
+    /// reported as such instead of as corrupted files.
+    pub fn id(&self) -> String {
+        let mut salted = KEY_ID_SALT.to_vec();
+        salted.extend_from_slice(&self.0);
+        HashValue::sha3_256_of(&salted).to_hex()[..8].to_string()
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(GenericArray::from_slice(&self.0))
+    }
+}
+
+/// The additional authenticated data of the file at `file_handle`.
+fn aad(file_handle: &FileHandleRef) -> Vec<u8> {
+    let mut aad = AAD_PREFIX.to_vec();
+    aad.extend_from_slice(file_handle.as_bytes());
+    aad
+}
+
+#[derive(Clone, StructOpt)]
+pub struct EncryptionKeyOpt {
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with = "encryption-key-backend",
+        help = "File holding the hex encoded 256-bit key to encrypt backups with, or to decrypt \
+        them with when restoring or verifying."
+    )]
+    pub encryption_key_file: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Secure storage holding the hex encoded 256-bit key to encrypt backups with, or to \
+        decrypt them with when restoring or verifying. Formatted like the backends of the \
+        operational tool, e.g. \"backend=vault;server=URL;token=PATH_TO_TOKEN\"."
+    )]
+    pub encryption_key_backend: Option<SecureBackend>,
+
+    #[structopt(
+        long,
+        default_value = "backup_encryption_key",
+        help = "Name of the key in the secure storage given by --encryption-key-backend."
+    )]
+    pub encryption_key_name: String,
+}
+
+impl EncryptionKeyOpt {
+    pub fn load(&self) -> Result<Option<EncryptionKey>> {
+        let hex = if let Some(path) = &self.encryption_key_file {
+            fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read encryption key file {:?}: {}", path, e))?
+        } else if let Some(backend) = &self.encryption_key_backend {
+            let backend: config::SecureBackend = backend.clone().try_into()?;
+            Storage::from(&backend)
+                .get::<String>(&self.encryption_key_name)
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to read encryption key {} from secure storage: {}",
+                        self.encryption_key_name,
+                        e,
+                    )
+                })?
+                .value
+        } else {
+            return Ok(None);
+        };
+        EncryptionKey::from_hex(&hex).map(Some)
+    }
+}
+
+impl Default for EncryptionKeyOpt {
+    fn default() -> Self {
+        Self::from_iter(vec!["exe"])
+    }
+}
+
+#[derive(Clone, Default, StructOpt)]
+pub struct CodecOpt {
+    #[structopt(
+        long,
+        help = "Compress backups with zstd at this level (1-21), before encrypting them if \
+        encryption is enabled."
+    )]
+    pub zstd_level: Option<i32>,
+
+    #[structopt(flatten)]
+    pub encryption_key: EncryptionKeyOpt,
+}
+
+/// Encodes the files of new backups.
+pub struct Encoder {
+    codec: Codec,
+    zstd_level: i32,
+    key: Option<EncryptionKey>,
+}
+
+impl Encoder {
+    pub fn new(zstd_level: Option<i32>, key: Option<EncryptionKey>) -> Self {
+        Self {
+            codec: Codec {
+                compression: zstd_level.map(|_| Compression::Zstd),
+                encryption: key
+                    .as_ref()
+                    .map(|key| Encryption::Aes256Gcm { key_id: key.id() }),
+            },
+            zstd_level: zstd_level.unwrap_or(0),
+            key,
+        }
+    }
+
+    /// The codec to record in the manifests.
+    pub fn codec(&self) -> &Codec {
+        &self.codec
+    }
+
+    /// Encodes the content of the file at `file_handle`.
+    pub fn encode(&self, file_handle: &FileHandleRef, data: &[u8]) -> Result<Vec<u8>> {
+        let compressed;
+        let data = match self.codec.compression {
+            Some(Compression::Zstd) => {
+                compressed = zstd::stream::encode_all(data, self.zstd_level)?;
+                &compressed[..]
+            }
+            None => data,
+        };
+        match &self.key {
+            Some(key) => {
+                let mut nonce = [0; NONCE_SIZE];
+                OsRng.fill_bytes(&mut nonce);
+                let ciphertext = key
+                    .cipher()
+                    .encrypt(
+                        GenericArray::from_slice(&nonce),
+                        Payload {
+                            msg: data,
+                            aad: &aad(file_handle),
+                        },
+                    )
+                    .map_err(|_| anyhow!("Failed to encrypt."))?;
+                let mut encrypted = nonce.to_vec();
+                encrypted.extend(ciphertext);
+                Ok(encrypted)
+            }
+            None => Ok(data.to_vec()),
+        }
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl TryFrom<CodecOpt> for Encoder {
+    type Error = anyhow::Error;
+
+    fn try_from(opt: CodecOpt) -> Result<Self> {
+        if let Some(level) = opt.zstd_level {
+            ensure!(
+                (1..=21).contains(&level),
+                "--zstd-level must be between 1 and 21, got {}.",
+                level,
+            );
+        }
+        Ok(Self::new(opt.zstd_level, opt.encryption_key.load()?))
+    }
+}
+
+/// Decodes the files of existing backups, according to the codec recorded in their manifests.
+#[derive(Default)]
+pub struct Decoder {
+    key: Option<EncryptionKey>,
+}
+
+impl Decoder {
+    pub fn new(key: Option<EncryptionKey>) -> Self {
+        Self { key }
+    }
+
+    /// Decodes the content of the file at `file_handle`.
+    pub fn decode(
+        &self,
+        codec: &Codec,
+        file_handle: &FileHandleRef,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let data = self.decrypt(codec, file_handle, data)?;
+        match codec.compression {
+            Some(Compression::Zstd) => Ok(zstd::stream::decode_all(data.as_slice())?),
+            None => Ok(data),
+        }
+    }
+
+    /// Undoes the encryption of the codec, leaving the data compressed if it is.
+    pub fn decrypt(
+        &self,
+        codec: &Codec,
+        file_handle: &FileHandleRef,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        match &codec.encryption {
+            Some(Encryption::Aes256Gcm { key_id }) => {
+                let key = self.key.as_ref().ok_or_else(|| {
+                    anyhow!(
+                        "Backup is encrypted with key {}, but no encryption key is provided.",
+                        key_id,
+                    )
+                })?;
+                ensure!(
+                    key.id() == *key_id,
+                    "Backup is encrypted with key {}, but key {} is provided.",
+                    key_id,
+                    key.id(),
+                );
+                ensure!(data.len() >= NONCE_SIZE, "Encrypted file is truncated.");
+                let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+                key.cipher()
+                    .decrypt(
+                        GenericArray::from_slice(nonce),
+                        Payload {
+                            msg: ciphertext,
+                            aad: &aad(file_handle),
+                        },
+                    )
+                    .map_err(|_| {
+                        anyhow!(
+                            "Failed to decrypt {}, the file is corrupted or belongs to another \
+                             file handle.",
+                            file_handle,
+                        )
+                    })
+            }
+            None => Ok(data),
+        }
+    }
+}
+
+impl TryFrom<EncryptionKeyOpt> for Decoder {
+    type Error = anyhow::Error;
+
+    fn try_from(opt: EncryptionKeyOpt) -> Result<Self> {
+        Ok(Self::new(opt.load()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const FILE_HANDLE: &str = "backup.0001/chunk";
+
+    fn roundtrip(zstd_level: Option<i32>, encrypt: bool, data: &[u8]) {
+        let key = EncryptionKey::generate();
+        let encoder = Encoder::new(
+            zstd_level,
+            if encrypt {
+                Some(EncryptionKey::from_hex(&key.to_hex()).unwrap())
+            } else {
+                None
+            },
+        );
+        let encoded = encoder.encode(FILE_HANDLE, data).unwrap();
+        if encrypt {
+            assert_ne!(encoded.as_slice(), data);
+        }
+
+        let decoder = Decoder::new(Some(key));
+        assert_eq!(
+            decoder
+                .decode(encoder.codec(), FILE_HANDLE, encoded)
+                .unwrap()
+                .as_slice(),
+            data
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(
+            zstd_level in proptest::option::of(1..=3),
+            encrypt in any::<bool>(),
+            data in proptest::collection::vec(any::<u8>(), 0..4096),
+        ) {
+            roundtrip(zstd_level, encrypt, &data);
+        }
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let encoder = Encoder::new(None, Some(EncryptionKey::generate()));
+        let encoded = encoder.encode(FILE_HANDLE, b"abc").unwrap();
+
+        assert!(Decoder::default()
+            .decode(encoder.codec(), FILE_HANDLE, encoded.clone())
+            .is_err());
+        assert!(Decoder::new(Some(EncryptionKey::generate()))
+            .decode(encoder.codec(), FILE_HANDLE, encoded)
+            .is_err());
+    }
+
+    #[test]
+    fn test_tampered() {
+        let key = EncryptionKey::generate();
+        let decoder = Decoder::new(Some(EncryptionKey::from_hex(&key.to_hex()).unwrap()));
+        let encoder = Encoder::new(Some(1), Some(key));
+        let mut encoded = encoder.encode(FILE_HANDLE, b"abc").unwrap();
+
+        // A file moved to another file handle doesn't decrypt
+        assert!(decoder
+            .decode(encoder.codec(), "backup.0001/other_chunk", encoded.clone())
+            .is_err());
+        *encoded.last_mut().unwrap() ^= 1;
+        assert!(decoder
+            .decode(encoder.codec(), FILE_HANDLE, encoded.clone())
+            .is_err());
+        encoded.truncate(NONCE_SIZE - 1);
+        assert!(decoder
+            .decode(encoder.codec(), FILE_HANDLE, encoded)
+            .is_err());
+    }
+
+    #[test]
+    fn test_zstd_level() {
+        let opt = |zstd_level| CodecOpt {
+            zstd_level,
+            ..CodecOpt::default()
+        };
+        for level in &[None, Some(1), Some(21)] {
+            assert!(Encoder::try_from(opt(*level)).is_ok());
+        }
+        for level in &[Some(-1), Some(0), Some(22)] {
+            assert!(Encoder::try_from(opt(*level)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_key_from_hex() {
+        let key = EncryptionKey::generate();
+        let parsed = EncryptionKey::from_hex(&format!("{}\n", key.to_hex())).unwrap();
+        assert_eq!(parsed.id(), key.id());
+
+        assert!(EncryptionKey::from_hex("abcd").is_err());
+        assert!(EncryptionKey::from_hex("not hex").is_err());
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup_service_client;
+pub mod codec;
 pub(crate) mod error_notes;
 pub mod read_record_bytes;
 pub mod storage_ext;
@@ -10,6 +11,7 @@ pub(crate) mod stream;
 #[cfg(test)]
 pub mod test_utils;
 
+use crate::utils::codec::{CodecOpt, Decoder, Encoder, EncryptionKeyOpt};
 use anyhow::{anyhow, Result};
 use diem_config::config::RocksdbConfig;
 use diem_crypto::HashValue;
@@ -19,7 +21,7 @@ use diem_types::{account_state_blob::AccountStateBlob, transaction::Version, way
 use diemdb::{backup::restore_handler::RestoreHandler, DiemDB, GetRestoreHandler};
 use std::{
     collections::HashMap,
-    convert::TryFrom,
+    convert::{TryFrom, TryInto},
     mem::size_of,
     path::{Path, PathBuf},
     sync::Arc,
@@ -36,6 +38,26 @@ pub struct GlobalBackupOpt {
         help = "Maximum chunk file size in bytes."
     )]
     pub max_chunk_size: usize,
+
+    #[structopt(flatten)]
+    pub codec: CodecOpt,
+}
+
+#[derive(Clone)]
+pub struct GlobalBackupOptions {
+    pub max_chunk_size: usize,
+    pub encoder: Arc<Encoder>,
+}
+
+impl TryFrom<GlobalBackupOpt> for GlobalBackupOptions {
+    type Error = anyhow::Error;
+
+    fn try_from(opt: GlobalBackupOpt) -> Result<Self> {
+        Ok(Self {
+            max_chunk_size: opt.max_chunk_size,
+            encoder: Arc::new(opt.codec.try_into()?),
+        })
+    }
 }
 
 #[derive(Clone, StructOpt)]
@@ -92,6 +114,9 @@ pub struct GlobalRestoreOpt {
 
     #[structopt(flatten)]
     pub concurernt_downloads: ConcurrentDownloadsOpt,
+
+    #[structopt(flatten)]
+    pub encryption_key: EncryptionKeyOpt,
 }
 
 pub enum RestoreRunMode {
@@ -146,6 +171,7 @@ pub struct GlobalRestoreOptions {
     pub trusted_waypoints: Arc<HashMap<Version, Waypoint>>,
     pub run_mode: Arc<RestoreRunMode>,
     pub concurrent_downloads: usize,
+    pub decoder: Arc<Decoder>,
 }
 
 impl TryFrom<GlobalRestoreOpt> for GlobalRestoreOptions {
@@ -171,6 +197,7 @@ impl TryFrom<GlobalRestoreOpt> for GlobalRestoreOptions {
             trusted_waypoints: Arc::new(opt.trusted_waypoints.verify()?),
             run_mode: Arc::new(run_mode),
             concurrent_downloads,
+            decoder: Arc::new(opt.encryption_key.try_into()?),
         })
     }
 }
@@ -1,19 +1,49 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::storage::{BackupHandle, BackupStorage, FileHandleRef};
+use crate::{
+    storage::{
+        BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+    },
+    utils::codec::{Codec, Compression, Decoder, Encoder},
+};
 use anyhow::Result;
+use async_compression::tokio::bufread::ZstdDecoder;
 use async_trait::async_trait;
 use rand::random;
 use serde::de::DeserializeOwned;
-use std::{convert::TryInto, sync::Arc};
-use tokio::io::AsyncReadExt;
+use std::{convert::TryInto, io::Cursor, sync::Arc};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 
 #[async_trait]
 pub trait BackupStorageExt {
     async fn read_all(&self, file_handle: &FileHandleRef) -> Result<Vec<u8>>;
     async fn load_json_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
     async fn load_bcs_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T>;
+    /// Opens a file encoded with `codec`, which is recorded in the manifest referring to it. The
+    /// file is decompressed as it is read, but an encrypted file is read and decrypted as a whole
+    /// first, since its authentication tag covers all of it.
+    async fn open_for_read_decoded(
+        &self,
+        file_handle: &FileHandleRef,
+        codec: &Codec,
+        decoder: &Decoder,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
+    async fn load_bcs_file_decoded<T: DeserializeOwned>(
+        &self,
+        file_handle: &FileHandleRef,
+        codec: &Codec,
+        decoder: &Decoder,
+    ) -> Result<T>;
+    /// Creates a file holding `bytes` encoded by `encoder`, whose codec must be recorded in the
+    /// manifest referring to the file.
+    async fn write_encoded(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        bytes: &[u8],
+        encoder: &Encoder,
+    ) -> Result<FileHandle>;
     /// Adds a random suffix ".XXXX" to the backup name, so a retry won't pass a same backup name to
     /// the storage.
     async fn create_backup_with_random_suffix(&self, name: &str) -> Result<BackupHandle>;
@@ -36,8 +66,107 @@ impl BackupStorageExt for Arc<dyn BackupStorage> {
         Ok(serde_json::from_slice(&self.read_all(&file_handle).await?)?)
     }
 
+    async fn open_for_read_decoded(
+        &self,
+        file_handle: &FileHandleRef,
+        codec: &Codec,
+        decoder: &Decoder,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let file: Box<dyn AsyncRead + Send + Unpin> = if codec.encryption.is_some() {
+            let bytes = decoder.decrypt(codec, file_handle, self.read_all(file_handle).await?)?;
+            Box::new(Cursor::new(bytes))
+        } else {
+            self.open_for_read(file_handle).await?
+        };
+        let file = BufReader::new(file);
+        Ok(match codec.compression {
+            Some(Compression::Zstd) => Box::new(ZstdDecoder::new(file)),
+            None => Box::new(file),
+        })
+    }
+
+    async fn load_bcs_file_decoded<T: DeserializeOwned>(
+        &self,
+        file_handle: &FileHandleRef,
+        codec: &Codec,
+        decoder: &Decoder,
+    ) -> Result<T> {
+        let mut file = self
+            .open_for_read_decoded(file_handle, codec, decoder)
+            .await?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).await?;
+        Ok(bcs::from_bytes(&bytes)?)
+    }
+
+    async fn write_encoded(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+        bytes: &[u8],
+        encoder: &Encoder,
+    ) -> Result<FileHandle> {
+        // The file handle is only known once the file is created, and is authenticated along with
+        // the encrypted content
+        let (file_handle, mut file) = self.create_for_write(backup_handle, name).await?;
+        let encoded = encoder.encode(&file_handle, bytes)?;
+        file.write_all(&encoded).await?;
+        file.shutdown().await?;
+        Ok(file_handle)
+    }
+
     async fn create_backup_with_random_suffix(&self, name: &str) -> Result<BackupHandle> {
         self.create_backup(&format!("{}.{:04x}", name, random::<u16>()).try_into()?)
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage::local_fs::LocalFs, utils::codec::EncryptionKey};
+    use diem_temppath::TempPath;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn test_write_and_open_decoded() {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(tmpdir.path().to_path_buf()));
+        // Larger than the read buffers, so the file is decompressed in several steps
+        let data: Vec<u8> = (0..100_000u32)
+            .flat_map(|i| i.to_be_bytes().to_vec())
+            .collect();
+
+        Runtime::new().unwrap().block_on(async {
+            let backup_handle = store
+                .create_backup_with_random_suffix("test")
+                .await
+                .unwrap();
+            for zstd_level in &[None, Some(3)] {
+                for encrypt in &[false, true] {
+                    let key = EncryptionKey::generate();
+                    let decoder =
+                        Decoder::new(Some(EncryptionKey::from_hex(&key.to_hex()).unwrap()));
+                    let encoder =
+                        Encoder::new(*zstd_level, if *encrypt { Some(key) } else { None });
+                    let name = format!("zstd_{}_encrypt_{}", zstd_level.unwrap_or(0), encrypt)
+                        .parse()
+                        .unwrap();
+                    let file_handle = store
+                        .write_encoded(&backup_handle, &name, &data, &encoder)
+                        .await
+                        .unwrap();
+
+                    let mut file = store
+                        .open_for_read_decoded(&file_handle, encoder.codec(), &decoder)
+                        .await
+                        .unwrap();
+                    let mut decoded = Vec::new();
+                    file.read_to_end(&mut decoded).await.unwrap();
+                    assert_eq!(decoded, data);
+                }
+            }
+        })
+    }
+}
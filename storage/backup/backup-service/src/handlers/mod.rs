@@ -7,9 +7,15 @@ use crate::handlers::utils::{
     handle_rejection, reply_with_async_channel_writer, reply_with_bcs_bytes,
     send_size_prefixed_bcs_bytes, unwrap_or_500, LATENCY_HISTOGRAM,
 };
+use anyhow::Result;
 use diem_crypto::hash::HashValue;
 use diem_types::transaction::Version;
 use diemdb::backup::backup_handler::BackupHandler;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use warp::{filters::BoxedFilter, reply::Reply, Filter};
 
 static DB_STATE: &str = "db_state";
@@ -19,8 +25,17 @@ static STATE_ROOT_PROOF: &str = "state_root_proof";
 static EPOCH_ENDING_LEDGER_INFOS: &str = "epoch_ending_ledger_infos";
 static TRANSACTIONS: &str = "transactions";
 static TRANSACTION_RANGE_PROOF: &str = "transaction_range_proof";
+static CHECKPOINT: &str = "checkpoint";
+
+#[derive(Deserialize)]
+struct CheckpointParams {
+    path: PathBuf,
+}
 
-pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Reply,)> {
+pub(crate) fn get_routes(
+    backup_handler: BackupHandler,
+    checkpointer: Option<Arc<dyn Fn(&Path) -> Result<()> + Send + Sync>>,
+) -> BoxedFilter<(impl Reply,)> {
     // GET db_state
     let bh = backup_handler.clone();
     let db_state = warp::path::end()
@@ -117,9 +132,24 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .or(warp::path(TRANSACTIONS).and(transactions))
         .or(warp::path(TRANSACTION_RANGE_PROOF).and(transaction_range_proof));
 
-    // Serve all routes for GET only.
+    // POST checkpoint?path=<path_relative_to_checkpoint_root_dir>
+    let checkpoint = warp::path::end()
+        .and(warp::query::<CheckpointParams>())
+        .map(move |params: CheckpointParams| -> Box<dyn Reply> {
+            match &checkpointer {
+                Some(checkpointer) => unwrap_or_500(
+                    checkpointer(&params.path).and_then(|()| reply_with_bcs_bytes(CHECKPOINT, &())),
+                ),
+                // Checkpoints are disabled unless configured.
+                None => Box::new(warp::http::StatusCode::FORBIDDEN),
+            }
+        })
+        .recover(handle_rejection);
+
+    // Serve all routes for GET only, except for the checkpoint, which modifies the local disk.
     warp::get()
         .and(routes)
+        .or(warp::path(CHECKPOINT).and(warp::post()).and(checkpoint))
         .with(warp::log::custom(|info| {
             let endpoint = info.path().split('/').nth(1).unwrap_or("-");
             LATENCY_HISTOGRAM
@@ -4,14 +4,51 @@
 mod handlers;
 
 use crate::handlers::get_routes;
+use anyhow::{ensure, Result};
 use diem_logger::prelude::*;
 use diemdb::DiemDB;
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
 use tokio::runtime::{Builder, Runtime};
 
-pub fn start_backup_service(address: SocketAddr, db: Arc<DiemDB>) -> Runtime {
+/// Creates a checkpoint of a DB other than the ledger DB (e.g. the consensus DB of a validator)
+/// under the given checkpoint root path, so that checkpoints cover all DBs of the node.
+pub type Checkpointer = Box<dyn Fn(&Path) -> Result<()> + Send + Sync>;
+
+/// Enables creating checkpoints through the backup service, which is off unless configured.
+pub struct CheckpointConfig {
+    /// Checkpoints are only created under this directory, at paths relative to it given by the
+    /// clients.
+    pub root_dir: PathBuf,
+    pub checkpointers: Vec<Checkpointer>,
+}
+
+pub fn start_backup_service(
+    address: SocketAddr,
+    db: Arc<DiemDB>,
+    checkpoint_config: Option<CheckpointConfig>,
+) -> Runtime {
     let backup_handler = db.get_backup_handler();
-    let routes = get_routes(backup_handler);
+    let checkpointer = checkpoint_config.map(|config| {
+        Arc::new(move |path: &Path| {
+            let checkpoint_root_path = resolve_checkpoint_path(&config.root_dir, path)?;
+            // The DBs are not checkpointed atomically, the ledger DB is checkpointed last since
+            // blocks are saved to the consensus DB before being committed to the ledger DB, and
+            // are pruned from the former right after. Should the ledger DB still end up ahead of
+            // all the blocks in the consensus DB, consensus recovers from the ledger DB alone on
+            // startup and syncs up from there.
+            std::fs::create_dir_all(&checkpoint_root_path)?;
+            config
+                .checkpointers
+                .iter()
+                .try_for_each(|checkpointer| checkpointer(&checkpoint_root_path))?;
+            db.create_checkpoint(&checkpoint_root_path)
+        }) as Arc<dyn Fn(&Path) -> Result<()> + Send + Sync>
+    });
+    let routes = get_routes(backup_handler, checkpointer);
 
     let runtime = Builder::new_multi_thread()
         .thread_name("backup")
@@ -33,13 +70,27 @@ pub fn start_backup_service(address: SocketAddr, db: Arc<DiemDB>) -> Runtime {
     runtime
 }
 
+/// Only plain relative paths are accepted, so that clients can't have checkpoints created outside
+/// of the checkpoint root directory.
+fn resolve_checkpoint_path(root_dir: &Path, path: &Path) -> Result<PathBuf> {
+    ensure!(
+        path.components().next().is_some()
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_))),
+        "Checkpoint path must be relative to the checkpoint root directory, without \"..\": {:?}",
+        path,
+    );
+    Ok(root_dir.join(path))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use diem_config::utils::get_available_port;
     use diem_crypto::hash::HashValue;
     use diem_temppath::TempPath;
-    use reqwest::blocking::get;
+    use reqwest::blocking::{get, Client};
     use std::net::{IpAddr, Ipv4Addr};
 
     /// 404 - endpoint not found
@@ -52,7 +103,11 @@ mod tests {
         let tmpdir = TempPath::new();
         let db = Arc::new(DiemDB::new_for_test(&tmpdir));
         let port = get_available_port();
-        let _rt = start_backup_service(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), db);
+        let _rt = start_backup_service(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            db,
+            None,
+        );
 
         // Endpoint doesn't exist.
         let resp = get(&format!("http://127.0.0.1:{}/", port)).unwrap();
@@ -93,5 +148,78 @@ mod tests {
         assert_eq!(resp.status(), 200);
         assert_eq!(resp.content_length(), None);
         assert!(resp.bytes().is_err());
+
+        // Checkpoints are disabled.
+        let resp = Client::new()
+            .post(&format!("http://127.0.0.1:{}/checkpoint", port))
+            .query(&[("path", "checkpoint")])
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[test]
+    fn checkpoint() {
+        let tmpdir = TempPath::new();
+        let checkpoint_root_dir = TempPath::new();
+        let db = Arc::new(DiemDB::new_for_test(&tmpdir));
+        let port = get_available_port();
+        let checkpointer: Checkpointer = Box::new(|checkpoint_root_path: &Path| {
+            std::fs::write(checkpoint_root_path.join("other"), b"other")?;
+            Ok(())
+        });
+        let _rt = start_backup_service(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            db,
+            Some(CheckpointConfig {
+                root_dir: checkpoint_root_dir.path().to_path_buf(),
+                checkpointers: vec![checkpointer],
+            }),
+        );
+        let url = format!("http://127.0.0.1:{}/checkpoint", port);
+        let client = Client::new();
+
+        // Checkpoints are only created on POST, under the checkpoint root directory.
+        let resp = get(&url).unwrap();
+        assert_eq!(resp.status(), 405);
+        let resp = client.post(&url).send().unwrap();
+        assert_eq!(resp.status(), 400);
+        for path in &[
+            tmpdir.path().to_str().unwrap(),
+            "../checkpoint",
+            "a/../../b",
+            "",
+        ] {
+            let resp = client.post(&url).query(&[("path", path)]).send().unwrap();
+            assert_eq!(resp.status(), 500);
+        }
+        assert!(!checkpoint_root_dir.path().exists());
+
+        let resp = client
+            .post(&url)
+            .query(&[("path", "checkpoint")])
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let checkpoint_path = checkpoint_root_dir.path().join("checkpoint");
+        DiemDB::open(
+            &checkpoint_path,
+            true, /* readonly */
+            None, /* pruner */
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read(checkpoint_path.join("other")).unwrap(),
+            b"other"
+        );
+
+        // The checkpoint already exists.
+        let resp = client
+            .post(&url)
+            .query(&[("path", "checkpoint")])
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 500);
     }
 }
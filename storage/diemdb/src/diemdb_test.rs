@@ -375,6 +375,20 @@ fn verify_committed_transactions(
                 .verify(ledger_info, cur_ver, *addr)
                 .unwrap();
         }
+        let (addresses, expected_blobs): (Vec<_>, Vec<_>) = txn_to_commit
+            .account_states()
+            .iter()
+            .map(|(addr, blob)| (*addr, Some(blob.clone())))
+            .unzip();
+        if !addresses.is_empty() {
+            let account_states_with_proof = db
+                .get_accounts_state_with_proof(&addresses, cur_ver, ledger_version)
+                .unwrap();
+            assert_eq!(account_states_with_proof.blobs, expected_blobs);
+            account_states_with_proof
+                .verify(ledger_info, cur_ver, &addresses)
+                .unwrap();
+        }
 
         cur_ver += 1;
     }
@@ -389,6 +403,47 @@ fn verify_committed_transactions(
     );
 }
 
+fn test_catch_up_with_primary_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let secondary_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let secondary = DiemDB::open_as_secondary(
+        tmp_dir.path(),
+        secondary_dir.path(),
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert!(secondary.ledger_store.get_latest_ledger_info().is_err());
+
+    let num_batches = input.len();
+    let mut cur_ver = 0;
+    for (batch_idx, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+
+        secondary.try_catch_up_with_primary().unwrap();
+        assert_eq!(
+            secondary.ledger_store.get_latest_ledger_info().unwrap(),
+            *ledger_info_with_sigs
+        );
+        verify_committed_transactions(
+            &secondary,
+            &txns_to_commit,
+            cur_ver,
+            ledger_info_with_sigs,
+            batch_idx + 1 == num_batches, /* is_latest */
+        );
+
+        cur_ver += txns_to_commit.len() as u64;
+    }
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
@@ -401,6 +456,11 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_catch_up_with_primary(input in arb_blocks_to_commit()) {
+        test_catch_up_with_primary_impl(input);
+    }
 }
 
 #[test]
@@ -485,6 +545,32 @@ fn test_get_latest_tree_state() {
     );
 }
 
+#[test]
+fn test_create_checkpoint() {
+    let tmp_dir = TempPath::new();
+    let checkpoint_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let txn_info = TransactionInfo::new(
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+        0,
+        KeptVMStatus::Executed,
+    );
+    put_transaction_info(&db, 0, &txn_info);
+    db.create_checkpoint(&checkpoint_dir).unwrap();
+    // Writes after the checkpoint is created are not reflected in it
+    put_transaction_info(&db, 1, &txn_info);
+    assert_eq!(db.get_latest_tree_state().unwrap().num_transactions, 2);
+
+    let checkpoint = DiemDB::new_for_test(&checkpoint_dir);
+    assert_eq!(
+        checkpoint.get_latest_tree_state().unwrap(),
+        TreeState::new(1, vec![txn_info.hash()], txn_info.state_root_hash())
+    );
+}
+
 fn put_transaction_info(db: &DiemDB, version: Version, txn_info: &TransactionInfo) {
     let mut cs = ChangeSet::new();
     db.ledger_store
@@ -0,0 +1,743 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module checks the integrity of the data persisted in DiemDB: it recomputes the
+//! transaction accumulator from the transaction infos, checks the transactions, events and state
+//! trees against the hashes each transaction info commits to, and cross-checks the indices
+//! against the data they index, in both directions.
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    event_store::EventStore,
+    ledger_store::LedgerStore,
+    schema::{
+        epoch_by_version::EpochByVersionSchema, event::EventSchema, event_by_key::EventByKeySchema,
+        event_by_version::EventByVersionSchema, ledger_info::LedgerInfoSchema,
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+        transaction_info::TransactionInfoSchema,
+    },
+    state_store::StateStore,
+};
+use anyhow::{ensure, Result};
+use diem_crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
+    HashValue,
+};
+use diem_types::{
+    account_address::AccountAddress,
+    contract_event::ContractEvent,
+    event::EventKey,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use schemadb::{schema::Schema, ReadOptions, DB};
+use std::{collections::BTreeMap, fmt, ops::RangeInclusive, sync::Arc};
+
+/// An inconsistency found in the DB, identifying the records involved.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Inconsistency {
+    /// A record is missing, or failed to be read or decoded.
+    Unreadable { record: String, error: String },
+    /// The persisted transaction accumulator differs from the one recomputed from the
+    /// transaction infos.
+    TransactionAccumulator {
+        version: Version,
+        persisted: HashValue,
+        recomputed: HashValue,
+    },
+    /// A ledger info commits to another transaction accumulator than the one recomputed from the
+    /// transaction infos.
+    LedgerInfo {
+        epoch: u64,
+        version: Version,
+        in_ledger_info: HashValue,
+        recomputed: HashValue,
+    },
+    /// A transaction differs from the one its transaction info commits to.
+    Transaction {
+        version: Version,
+        in_transaction_info: HashValue,
+        recomputed: HashValue,
+    },
+    /// The events of a transaction differ from the ones its transaction info commits to.
+    Events {
+        version: Version,
+        in_transaction_info: HashValue,
+        recomputed: HashValue,
+    },
+    /// The state tree at a version differs from the one its transaction info commits to, or is
+    /// missing while the state trees of earlier versions are retained.
+    StateTree {
+        version: Version,
+        in_transaction_info: HashValue,
+        in_state_tree: Option<HashValue>,
+    },
+    /// The `transaction_by_account` index does not point to a user transaction.
+    TransactionByAccount {
+        version: Version,
+        sender: AccountAddress,
+        sequence_number: u64,
+        indexed_version: Option<Version>,
+    },
+    /// The `event_by_key` index does not point to an event.
+    EventByKey {
+        version: Version,
+        index: u64,
+        key: EventKey,
+        sequence_number: u64,
+        indexed: Option<(Version, u64)>,
+    },
+    /// The `event_by_version` index does not point to an event.
+    EventByVersion {
+        version: Version,
+        index: u64,
+        key: EventKey,
+        sequence_number: u64,
+        indexed_index: Option<u64>,
+    },
+    /// The `epoch_by_version` index differs from the epoch ending ledger infos.
+    EpochByVersion {
+        version: Version,
+        ledger_info_epoch: Option<u64>,
+        indexed_epoch: Option<u64>,
+    },
+    /// A `transaction_by_account` entry points to a version that does not hold the user
+    /// transaction it indexes.
+    OrphanTransactionByAccount {
+        sender: AccountAddress,
+        sequence_number: u64,
+        version: Version,
+    },
+    /// An `event_by_key` entry points to a (version, index) that does not hold the event it
+    /// indexes.
+    OrphanEventByKey {
+        key: EventKey,
+        sequence_number: u64,
+        version: Version,
+        index: u64,
+    },
+    /// An `epoch_by_version` entry holds an epoch whose ledger info does not end that epoch at
+    /// its version.
+    OrphanEpochByVersion { version: Version, epoch: u64 },
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Inconsistency::Unreadable { record, error } => {
+                write!(f, "{} is unreadable: {}", record, error)
+            }
+            Inconsistency::TransactionAccumulator {
+                version,
+                persisted,
+                recomputed,
+            } => write!(
+                f,
+                "Transaction accumulator root hash at version {} is {:x}, recomputed {:x}",
+                version, persisted, recomputed,
+            ),
+            Inconsistency::LedgerInfo {
+                epoch,
+                version,
+                in_ledger_info,
+                recomputed,
+            } => write!(
+                f,
+                "LedgerInfo of epoch {} at version {} commits to transaction accumulator root \
+                 hash {:x}, recomputed {:x}",
+                epoch, version, in_ledger_info, recomputed,
+            ),
+            Inconsistency::Transaction {
+                version,
+                in_transaction_info,
+                recomputed,
+            } => write!(
+                f,
+                "Transaction at version {} hashes to {:x}, its TransactionInfo commits to {:x}",
+                version, recomputed, in_transaction_info,
+            ),
+            Inconsistency::Events {
+                version,
+                in_transaction_info,
+                recomputed,
+            } => write!(
+                f,
+                "Events at version {} have root hash {:x}, their TransactionInfo commits to {:x}",
+                version, recomputed, in_transaction_info,
+            ),
+            Inconsistency::StateTree {
+                version,
+                in_transaction_info,
+                in_state_tree,
+            } => match in_state_tree {
+                Some(in_state_tree) => write!(
+                    f,
+                    "State tree at version {} has root hash {:x}, its TransactionInfo commits to \
+                     {:x}",
+                    version, in_state_tree, in_transaction_info,
+                ),
+                None => write!(
+                    f,
+                    "State tree at version {} is missing while earlier ones are retained",
+                    version,
+                ),
+            },
+            Inconsistency::TransactionByAccount {
+                version,
+                sender,
+                sequence_number,
+                indexed_version,
+            } => write!(
+                f,
+                "transaction_by_account index of ({}, {}) points to version {:?} instead of {}",
+                sender, sequence_number, indexed_version, version,
+            ),
+            Inconsistency::EventByKey {
+                version,
+                index,
+                key,
+                sequence_number,
+                indexed,
+            } => write!(
+                f,
+                "event_by_key index of ({}, {}) points to (version, index) {:?} instead of \
+                 ({}, {})",
+                key, sequence_number, indexed, version, index,
+            ),
+            Inconsistency::EventByVersion {
+                version,
+                index,
+                key,
+                sequence_number,
+                indexed_index,
+            } => write!(
+                f,
+                "event_by_version index of ({}, {}, {}) points to index {:?} instead of {}",
+                key, version, sequence_number, indexed_index, index,
+            ),
+            Inconsistency::EpochByVersion {
+                version,
+                ledger_info_epoch,
+                indexed_epoch,
+            } => write!(
+                f,
+                "epoch_by_version index at version {} holds epoch {:?}, the LedgerInfos end epoch \
+                 {:?}",
+                version, indexed_epoch, ledger_info_epoch,
+            ),
+            Inconsistency::OrphanTransactionByAccount {
+                sender,
+                sequence_number,
+                version,
+            } => write!(
+                f,
+                "transaction_by_account index of ({}, {}) points to version {}, which holds \
+                 another transaction",
+                sender, sequence_number, version,
+            ),
+            Inconsistency::OrphanEventByKey {
+                key,
+                sequence_number,
+                version,
+                index,
+            } => write!(
+                f,
+                "event_by_key index of ({}, {}) points to (version, index) ({}, {}), which holds \
+                 another event",
+                key, sequence_number, version, index,
+            ),
+            Inconsistency::OrphanEpochByVersion { version, epoch } => write!(
+                f,
+                "epoch_by_version index at version {} holds epoch {}, whose LedgerInfo does not \
+                 end the epoch at that version",
+                version, epoch,
+            ),
+        }
+    }
+}
+
+/// `Fsck` checks the integrity of the data persisted in DiemDB.
+pub struct Fsck {
+    db: Arc<DB>,
+    ledger_store: Arc<LedgerStore>,
+    state_store: Arc<StateStore>,
+    event_store: Arc<EventStore>,
+}
+
+impl Fsck {
+    pub(crate) fn new(
+        db: Arc<DB>,
+        ledger_store: Arc<LedgerStore>,
+        state_store: Arc<StateStore>,
+        event_store: Arc<EventStore>,
+    ) -> Self {
+        Self {
+            db,
+            ledger_store,
+            state_store,
+            event_store,
+        }
+    }
+
+    /// Checks the versions in `[start_version, end_version]`, and returns the inconsistencies
+    /// found. The transaction accumulator is recomputed on top of its persisted frozen subtrees
+    /// at `start_version`, failing if those cannot be read.
+    ///
+    /// State trees are only checked from the first version at which one is found, as the ones
+    /// of earlier versions may have been pruned.
+    ///
+    /// The indices are also checked the other way around: their entries pointing into the range
+    /// must point to the records they index. As `transaction_by_account` and `event_by_key` are
+    /// not keyed by version, they are scanned whole.
+    pub fn check(
+        &self,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<Inconsistency>> {
+        ensure!(
+            start_version <= end_version,
+            "Invalid version range [{}, {}].",
+            start_version,
+            end_version,
+        );
+
+        let mut inconsistencies = vec![];
+        let ledger_infos = self.get_ledger_infos(start_version, end_version, &mut inconsistencies);
+        let mut accumulator = Some(InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.ledger_store.get_frozen_subtree_hashes(start_version)?,
+            start_version,
+        )?);
+        let mut state_tree_retained = false;
+
+        for version in start_version..=end_version {
+            let txn_info = read(
+                || format!("TransactionInfo at version {}", version),
+                self.db.get::<TransactionInfoSchema>(&version),
+                &mut inconsistencies,
+            );
+
+            // The accumulator can't be recomputed past a missing transaction info.
+            accumulator = match (accumulator, &txn_info) {
+                (Some(accumulator), Some(txn_info)) => Some(accumulator.append(&[txn_info.hash()])),
+                _ => None,
+            };
+            let ledger_infos_at_version = ledger_infos.get(&version).map_or(&[][..], Vec::as_slice);
+            if let Some(accumulator) = &accumulator {
+                self.check_accumulator(
+                    version,
+                    accumulator.root_hash(),
+                    ledger_infos_at_version,
+                    &mut inconsistencies,
+                );
+            }
+            self.check_epoch_by_version(version, ledger_infos_at_version, &mut inconsistencies);
+            self.check_transaction(version, txn_info.as_ref(), &mut inconsistencies);
+            self.check_events(version, txn_info.as_ref(), &mut inconsistencies);
+            if let Some(txn_info) = &txn_info {
+                self.check_state_tree(
+                    version,
+                    txn_info,
+                    &mut state_tree_retained,
+                    &mut inconsistencies,
+                );
+            }
+        }
+
+        let versions = start_version..=end_version;
+        self.check_transaction_by_account_entries(&versions, &mut inconsistencies);
+        self.check_event_by_key_entries(&versions, &mut inconsistencies);
+        self.check_epoch_by_version_entries(&versions, &mut inconsistencies);
+
+        Ok(inconsistencies)
+    }
+
+    /// Returns the ledger infos within the version range, by version. There is one per epoch.
+    fn get_ledger_infos(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) -> BTreeMap<Version, Vec<LedgerInfoWithSignatures>> {
+        let mut ledger_infos: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        let latest_epoch = match self.ledger_store.get_latest_ledger_info_option() {
+            Some(ledger_info) => ledger_info.ledger_info().epoch(),
+            None => return ledger_infos,
+        };
+        for epoch in 0..=latest_epoch {
+            if let Some(ledger_info) = read(
+                || format!("LedgerInfo of epoch {}", epoch),
+                self.db.get::<LedgerInfoSchema>(&epoch),
+                inconsistencies,
+            ) {
+                let version = ledger_info.ledger_info().version();
+                if version >= start_version && version <= end_version {
+                    ledger_infos.entry(version).or_default().push(ledger_info);
+                }
+            }
+        }
+        ledger_infos
+    }
+
+    fn check_accumulator(
+        &self,
+        version: Version,
+        recomputed: HashValue,
+        ledger_infos: &[LedgerInfoWithSignatures],
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        match self.ledger_store.get_root_hash(version) {
+            Ok(persisted) if persisted != recomputed => {
+                inconsistencies.push(Inconsistency::TransactionAccumulator {
+                    version,
+                    persisted,
+                    recomputed,
+                })
+            }
+            Ok(_) => (),
+            Err(e) => inconsistencies.push(unreadable(
+                format!("Transaction accumulator at version {}", version),
+                e,
+            )),
+        }
+
+        for ledger_info in ledger_infos
+            .iter()
+            .map(LedgerInfoWithSignatures::ledger_info)
+        {
+            if ledger_info.transaction_accumulator_hash() != recomputed {
+                inconsistencies.push(Inconsistency::LedgerInfo {
+                    epoch: ledger_info.epoch(),
+                    version,
+                    in_ledger_info: ledger_info.transaction_accumulator_hash(),
+                    recomputed,
+                });
+            }
+        }
+    }
+
+    fn check_epoch_by_version(
+        &self,
+        version: Version,
+        ledger_infos: &[LedgerInfoWithSignatures],
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let ledger_info_epoch = ledger_infos
+            .iter()
+            .map(LedgerInfoWithSignatures::ledger_info)
+            .find(|ledger_info| ledger_info.ends_epoch())
+            .map(|ledger_info| ledger_info.epoch());
+        match self.db.get::<EpochByVersionSchema>(&version) {
+            Ok(indexed_epoch) if indexed_epoch != ledger_info_epoch => {
+                inconsistencies.push(Inconsistency::EpochByVersion {
+                    version,
+                    ledger_info_epoch,
+                    indexed_epoch,
+                })
+            }
+            Ok(_) => (),
+            Err(e) => inconsistencies.push(unreadable(
+                format!("epoch_by_version index at version {}", version),
+                e,
+            )),
+        }
+    }
+
+    fn check_transaction(
+        &self,
+        version: Version,
+        txn_info: Option<&TransactionInfo>,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let txn = match read(
+            || format!("Transaction at version {}", version),
+            self.db.get::<TransactionSchema>(&version),
+            inconsistencies,
+        ) {
+            Some(txn) => txn,
+            None => return,
+        };
+
+        if let Some(txn_info) = txn_info {
+            let recomputed = txn.hash();
+            if recomputed != txn_info.transaction_hash() {
+                inconsistencies.push(Inconsistency::Transaction {
+                    version,
+                    in_transaction_info: txn_info.transaction_hash(),
+                    recomputed,
+                });
+            }
+        }
+
+        if let Transaction::UserTransaction(txn) = txn {
+            let (sender, sequence_number) = (txn.sender(), txn.sequence_number());
+            match self
+                .db
+                .get::<TransactionByAccountSchema>(&(sender, sequence_number))
+            {
+                Ok(indexed_version) if indexed_version != Some(version) => {
+                    inconsistencies.push(Inconsistency::TransactionByAccount {
+                        version,
+                        sender,
+                        sequence_number,
+                        indexed_version,
+                    })
+                }
+                Ok(_) => (),
+                Err(e) => inconsistencies.push(unreadable(
+                    format!(
+                        "transaction_by_account index of ({}, {})",
+                        sender, sequence_number
+                    ),
+                    e,
+                )),
+            }
+        }
+    }
+
+    fn check_events(
+        &self,
+        version: Version,
+        txn_info: Option<&TransactionInfo>,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let events = match self.event_store.get_events_by_version(version) {
+            Ok(events) => events,
+            Err(e) => {
+                inconsistencies.push(unreadable(format!("Events at version {}", version), e));
+                return;
+            }
+        };
+
+        if let Some(txn_info) = txn_info {
+            let event_hashes: Vec<_> = events.iter().map(ContractEvent::hash).collect();
+            let recomputed =
+                InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                    .root_hash();
+            if recomputed != txn_info.event_root_hash() {
+                inconsistencies.push(Inconsistency::Events {
+                    version,
+                    in_transaction_info: txn_info.event_root_hash(),
+                    recomputed,
+                });
+            }
+        }
+
+        for (index, event) in events.iter().enumerate() {
+            let index = index as u64;
+            let (key, sequence_number) = (*event.key(), event.sequence_number());
+            match self.db.get::<EventByKeySchema>(&(key, sequence_number)) {
+                Ok(indexed) if indexed != Some((version, index)) => {
+                    inconsistencies.push(Inconsistency::EventByKey {
+                        version,
+                        index,
+                        key,
+                        sequence_number,
+                        indexed,
+                    })
+                }
+                Ok(_) => (),
+                Err(e) => inconsistencies.push(unreadable(
+                    format!("event_by_key index of ({}, {})", key, sequence_number),
+                    e,
+                )),
+            }
+            match self
+                .db
+                .get::<EventByVersionSchema>(&(key, version, sequence_number))
+            {
+                Ok(indexed_index) if indexed_index != Some(index) => {
+                    inconsistencies.push(Inconsistency::EventByVersion {
+                        version,
+                        index,
+                        key,
+                        sequence_number,
+                        indexed_index,
+                    })
+                }
+                Ok(_) => (),
+                Err(e) => inconsistencies.push(unreadable(
+                    format!(
+                        "event_by_version index of ({}, {}, {})",
+                        key, version, sequence_number
+                    ),
+                    e,
+                )),
+            }
+        }
+    }
+
+    fn check_transaction_by_account_entries(
+        &self,
+        versions: &RangeInclusive<Version>,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let entries = self.index_entries::<TransactionByAccountSchema>(
+            "transaction_by_account index",
+            versions,
+            |_, version| *version,
+            inconsistencies,
+        );
+        for ((sender, sequence_number), version) in entries {
+            match self.db.get::<TransactionSchema>(&version) {
+                Ok(Some(Transaction::UserTransaction(txn)))
+                    if txn.sender() == sender && txn.sequence_number() == sequence_number => {}
+                Ok(_) => inconsistencies.push(Inconsistency::OrphanTransactionByAccount {
+                    sender,
+                    sequence_number,
+                    version,
+                }),
+                Err(e) => inconsistencies
+                    .push(unreadable(format!("Transaction at version {}", version), e)),
+            }
+        }
+    }
+
+    fn check_event_by_key_entries(
+        &self,
+        versions: &RangeInclusive<Version>,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let entries = self.index_entries::<EventByKeySchema>(
+            "event_by_key index",
+            versions,
+            |_, (version, _)| *version,
+            inconsistencies,
+        );
+        for ((key, sequence_number), (version, index)) in entries {
+            match self.db.get::<EventSchema>(&(version, index)) {
+                Ok(Some(event))
+                    if *event.key() == key && event.sequence_number() == sequence_number => {}
+                Ok(_) => inconsistencies.push(Inconsistency::OrphanEventByKey {
+                    key,
+                    sequence_number,
+                    version,
+                    index,
+                }),
+                Err(e) => inconsistencies.push(unreadable(
+                    format!("Event at (version, index) ({}, {})", version, index),
+                    e,
+                )),
+            }
+        }
+    }
+
+    fn check_epoch_by_version_entries(
+        &self,
+        versions: &RangeInclusive<Version>,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let entries = self.index_entries::<EpochByVersionSchema>(
+            "epoch_by_version index",
+            versions,
+            |version, _| *version,
+            inconsistencies,
+        );
+        for (version, epoch) in entries {
+            match self.db.get::<LedgerInfoSchema>(&epoch) {
+                Ok(Some(ledger_info))
+                    if ledger_info.ledger_info().version() == version
+                        && ledger_info.ledger_info().ends_epoch() => {}
+                Ok(_) => {
+                    inconsistencies.push(Inconsistency::OrphanEpochByVersion { version, epoch })
+                }
+                Err(e) => {
+                    inconsistencies.push(unreadable(format!("LedgerInfo of epoch {}", epoch), e))
+                }
+            }
+        }
+    }
+
+    /// Returns the entries of an index which point into the version range, or records the index
+    /// as unreadable.
+    fn index_entries<S: Schema>(
+        &self,
+        index: &str,
+        versions: &RangeInclusive<Version>,
+        version_of: impl Fn(&S::Key, &S::Value) -> Version,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) -> Vec<(S::Key, S::Value)> {
+        let entries = self
+            .db
+            .iter::<S>(ReadOptions::default())
+            .and_then(|mut iter| {
+                iter.seek_to_first();
+                iter.filter(|entry| {
+                    entry.as_ref().map_or(true, |(key, value)| {
+                        versions.contains(&version_of(key, value))
+                    })
+                })
+                .collect()
+            });
+        match entries {
+            Ok(entries) => entries,
+            Err(e) => {
+                inconsistencies.push(unreadable(index.to_string(), e));
+                vec![]
+            }
+        }
+    }
+
+    fn check_state_tree(
+        &self,
+        version: Version,
+        txn_info: &TransactionInfo,
+        state_tree_retained: &mut bool,
+        inconsistencies: &mut Vec<Inconsistency>,
+    ) {
+        let in_transaction_info = txn_info.state_root_hash();
+        match self.state_store.get_root_hash_option(version) {
+            Ok(Some(in_state_tree)) => {
+                *state_tree_retained = true;
+                if in_state_tree != in_transaction_info {
+                    inconsistencies.push(Inconsistency::StateTree {
+                        version,
+                        in_transaction_info,
+                        in_state_tree: Some(in_state_tree),
+                    });
+                }
+            }
+            Ok(None) if *state_tree_retained => inconsistencies.push(Inconsistency::StateTree {
+                version,
+                in_transaction_info,
+                in_state_tree: None,
+            }),
+            Ok(None) => (),
+            Err(e) => inconsistencies.push(unreadable(
+                format!("State tree root at version {}", version),
+                e,
+            )),
+        }
+    }
+}
+
+fn unreadable(record: String, error: anyhow::Error) -> Inconsistency {
+    Inconsistency::Unreadable {
+        record,
+        error: format!("{:#}", error),
+    }
+}
+
+/// Returns the record read, or records it as unreadable if it is missing or failed to be read.
+fn read<T>(
+    record: impl FnOnce() -> String,
+    res: Result<Option<T>>,
+    inconsistencies: &mut Vec<Inconsistency>,
+) -> Option<T> {
+    match res {
+        Ok(Some(value)) => Some(value),
+        Ok(None) => {
+            inconsistencies.push(Inconsistency::Unreadable {
+                record: record(),
+                error: "Missing.".to_string(),
+            });
+            None
+        }
+        Err(e) => {
+            inconsistencies.push(unreadable(record(), e));
+            None
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{test_helper::arb_blocks_to_commit, DiemDB};
+use diem_temppath::TempPath;
+use proptest::prelude::*;
+use schemadb::SchemaBatch;
+use storage_interface::DbWriter;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_fsck(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(&txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let latest_version = cur_ver - 1;
+
+        let fsck = db.get_fsck();
+        prop_assert!(fsck.check(0, latest_version).unwrap().is_empty());
+        prop_assert!(fsck.check(latest_version / 2, latest_version).unwrap().is_empty());
+        prop_assert!(fsck.check(1, 0).is_err());
+
+        // The first ledger info always ends epoch 0.
+        let epoch_ending_version = input[0].1.ledger_info().version();
+        let txn = input[0].0[0].transaction().as_signed_user_txn().unwrap();
+        let txn_info = db
+            .db
+            .get::<TransactionInfoSchema>(&latest_version)
+            .unwrap()
+            .unwrap();
+        let corrupted_txn_info = TransactionInfo::new(
+            txn_info.transaction_hash(),
+            txn_info.state_root_hash(),
+            txn_info.event_root_hash(),
+            txn_info.gas_used() + 1,
+            txn_info.status().clone(),
+        );
+        let mut batch = SchemaBatch::new();
+        batch
+            .delete::<EpochByVersionSchema>(&epoch_ending_version)
+            .unwrap();
+        batch
+            .delete::<TransactionByAccountSchema>(&(txn.sender(), txn.sequence_number()))
+            .unwrap();
+        batch
+            .put::<TransactionInfoSchema>(&latest_version, &corrupted_txn_info)
+            .unwrap();
+        // Index entries pointing to records that do not exist
+        let orphan_sender = AccountAddress::random();
+        let orphan_event_key = EventKey::random();
+        batch
+            .put::<TransactionByAccountSchema>(&(orphan_sender, 0), &0)
+            .unwrap();
+        batch
+            .put::<EventByKeySchema>(&(orphan_event_key, 0), &(latest_version, 0))
+            .unwrap();
+        db.db.write_schemas(batch).unwrap();
+
+        let persisted = db.ledger_store.get_root_hash(latest_version).unwrap();
+        let recomputed = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            db.ledger_store.get_frozen_subtree_hashes(latest_version).unwrap(),
+            latest_version,
+        )
+        .unwrap()
+        .append(&[corrupted_txn_info.hash()])
+        .root_hash();
+        let latest_ledger_info = input.last().unwrap().1.ledger_info();
+        let expected = vec![
+            Inconsistency::EpochByVersion {
+                version: epoch_ending_version,
+                ledger_info_epoch: Some(0),
+                indexed_epoch: None,
+            },
+            Inconsistency::TransactionByAccount {
+                version: 0,
+                sender: txn.sender(),
+                sequence_number: txn.sequence_number(),
+                indexed_version: None,
+            },
+            Inconsistency::TransactionAccumulator {
+                version: latest_version,
+                persisted,
+                recomputed,
+            },
+            Inconsistency::LedgerInfo {
+                epoch: latest_ledger_info.epoch(),
+                version: latest_version,
+                in_ledger_info: persisted,
+                recomputed,
+            },
+            Inconsistency::OrphanTransactionByAccount {
+                sender: orphan_sender,
+                sequence_number: 0,
+                version: 0,
+            },
+            Inconsistency::OrphanEventByKey {
+                key: orphan_event_key,
+                sequence_number: 0,
+                version: latest_version,
+                index: 0,
+            },
+        ];
+
+        let inconsistencies = fsck.check(0, latest_version).unwrap();
+        prop_assert_eq!(inconsistencies.len(), expected.len());
+        for inconsistency in &expected {
+            prop_assert!(inconsistencies.contains(inconsistency));
+        }
+
+        // Orphans are only reported within the checked range
+        if latest_version > 0 {
+            prop_assert!(!fsck
+                .check(1, latest_version)
+                .unwrap()
+                .contains(&expected[4]));
+        }
+    }
+}
@@ -47,16 +47,8 @@ pub(crate) struct LedgerStore {
 impl LedgerStore {
     pub fn new(db: Arc<DB>) -> Self {
         // Upon restart, read the latest ledger info and signatures and cache them in memory.
-        let ledger_info = {
-            let mut iter = db
-                .iter::<LedgerInfoSchema>(ReadOptions::default())
-                .expect("Constructing iterator should work.");
-            iter.seek_to_last();
-            iter.next()
-                .transpose()
-                .expect("Reading latest ledger info from DB should work.")
-                .map(|kv| kv.1)
-        };
+        let ledger_info = Self::read_latest_ledger_info(&db)
+            .expect("Reading latest ledger info from DB should work.");
 
         Self {
             db,
@@ -64,6 +56,20 @@ impl LedgerStore {
         }
     }
 
+    fn read_latest_ledger_info(db: &DB) -> Result<Option<LedgerInfoWithSignatures>> {
+        let mut iter = db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_last();
+        Ok(iter.next().transpose()?.map(|kv| kv.1))
+    }
+
+    /// Re-reads the latest ledger info and signatures from the DB, for when they were persisted
+    /// by another instance, i.e. the primary of a DB opened as secondary.
+    pub fn refresh_latest_ledger_info(&self) -> Result<()> {
+        let ledger_info = Self::read_latest_ledger_info(&self.db)?;
+        self.latest_ledger_info.store(Arc::new(ledger_info));
+        Ok(())
+    }
+
     pub fn get_epoch(&self, version: Version) -> Result<u64> {
         let mut iter = self
             .db
@@ -17,6 +17,7 @@ pub mod test_helper;
 
 pub mod backup;
 pub mod errors;
+pub mod fsck;
 pub mod metrics;
 pub mod schema;
 
@@ -41,6 +42,7 @@ use crate::{
     change_set::{ChangeSet, SealedChangeSet},
     errors::DiemDbError,
     event_store::EventStore,
+    fsck::Fsck,
     ledger_counters::LedgerCounters,
     ledger_store::LedgerStore,
     metrics::{
@@ -61,14 +63,14 @@ use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesWithProof},
     contract_event::{ContractEvent, EventWithProof},
     epoch_change::EpochChangeProof,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionListProof,
+        AccountStateProof, AccountStatesProof, AccumulatorConsistencyProof, EventProof,
+        SparseMerkleProof, TransactionListProof,
     },
     transaction::{
         TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
@@ -311,6 +313,13 @@ impl DiemDB {
         ))
     }
 
+    /// Makes a DB opened as secondary reflect the transactions committed by the primary since it
+    /// was opened or last caught up.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()?;
+        self.ledger_store.refresh_latest_ledger_info()
+    }
+
     /// This opens db in non-readonly mode, without the pruner.
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn new_for_test<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
@@ -328,6 +337,21 @@ impl DiemDB {
         update_rocksdb_properties(&self.db)
     }
 
+    /// Creates a consistent copy of the DB under `checkpoint_root_path`, which can be opened with
+    /// `DiemDB::open` like the original, while the DB keeps serving reads and writes.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, checkpoint_root_path: P) -> Result<()> {
+        let instant = Instant::now();
+        let path = checkpoint_root_path.as_ref().join("diemdb");
+        std::fs::create_dir_all(checkpoint_root_path.as_ref())?;
+        self.db.create_checkpoint(&path)?;
+        info!(
+            path = path,
+            time_ms = %instant.elapsed().as_millis(),
+            "Created DiemDB checkpoint.",
+        );
+        Ok(())
+    }
+
     /// Returns ledger infos reflecting epoch bumps starting with the given epoch. If there are no
     /// more than `MAX_NUM_EPOCH_ENDING_LEDGER_INFO` results, this function returns all of them,
     /// otherwise the first `MAX_NUM_EPOCH_ENDING_LEDGER_INFO` results are returned and a flag
@@ -428,6 +452,16 @@ impl DiemDB {
         )
     }
 
+    /// Gets an instance of `Fsck` to check the integrity of the DB.
+    pub fn get_fsck(&self) -> Fsck {
+        Fsck::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.ledger_store),
+            Arc::clone(&self.state_store),
+            Arc::clone(&self.event_store),
+        )
+    }
+
     // ================================== Private APIs ==================================
     fn get_events_with_proof_by_event_key(
         &self,
@@ -836,6 +870,44 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_accounts_state_with_proof(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<AccountStatesWithProof> {
+        gauged_api("get_accounts_state_with_proof", || {
+            ensure!(!addresses.is_empty(), "No account address is specified.");
+            ensure!(
+                version <= ledger_version,
+                "The queried version {} should be equal to or older than ledger version {}.",
+                version,
+                ledger_version
+            );
+            {
+                let latest_version = self.get_latest_version()?;
+                ensure!(
+                    ledger_version <= latest_version,
+                    "ledger_version specified {} is greater than committed version {}.",
+                    ledger_version,
+                    latest_version
+                );
+            }
+
+            let txn_info_with_proof = self
+                .ledger_store
+                .get_transaction_info_with_proof(version, ledger_version)?;
+            let (account_state_blobs, sparse_merkle_multi_proof) = self
+                .state_store
+                .get_accounts_state_with_proof_by_version(addresses, version)?;
+            Ok(AccountStatesWithProof::new(
+                version,
+                account_state_blobs,
+                AccountStatesProof::new(txn_info_with_proof, sparse_merkle_multi_proof),
+            ))
+        })
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
@@ -21,7 +21,7 @@ use diem_jellyfish_merkle::{
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
-    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    proof::{SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof},
     transaction::Version,
 };
 use schemadb::{SchemaBatch, DB};
@@ -53,6 +53,19 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)
     }
 
+    /// Get the account state blobs of a list of accounts, with a single proof for all of them.
+    pub fn get_accounts_state_with_proof_by_version(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+    ) -> Result<(
+        Vec<Option<AccountStateBlob>>,
+        SparseMerkleMultiProof<AccountStateBlob>,
+    )> {
+        let keys: Vec<_> = addresses.iter().map(|address| address.hash()).collect();
+        JellyfishMerkleTree::new(self).get_with_multi_proof(&keys, version)
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
@@ -81,7 +94,7 @@ impl StateStore {
             .collect::<Vec<_>>();
 
         let (new_root_hash_vec, tree_update_batch) =
-            JellyfishMerkleTree::new(self).put_value_sets(blob_sets, first_version)?;
+            JellyfishMerkleTree::new(self).batch_put_value_sets(blob_sets, first_version)?;
 
         let num_versions = new_root_hash_vec.len();
         assert_eq!(num_versions, tree_update_batch.node_stats.len());
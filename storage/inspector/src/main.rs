@@ -3,16 +3,23 @@
 
 #![forbid(unsafe_code)]
 
+mod query;
+
 use anyhow::Result;
 use diem_config::config::RocksdbConfig;
 use diem_framework_releases::name_for_script;
 use diem_logger::info;
 use diemdb::DiemDB;
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+};
 use storage_interface::DbReader;
 
 use diem_types::{
     account_address::AccountAddress, account_config::AccountResource, account_state::AccountState,
+    ledger_info::LedgerInfoWithSignatures,
 };
 use std::convert::TryFrom;
 use structopt::StructOpt;
@@ -39,6 +46,27 @@ enum Command {
     },
     #[structopt(name = "list-accounts")]
     ListAccounts,
+    /// Check the integrity of the DB, exiting with an error if any inconsistency is found.
+    #[structopt(name = "fsck")]
+    Fsck {
+        #[structopt(long, default_value = "0")]
+        start_version: u64,
+        /// Defaults to the latest version.
+        #[structopt(long)]
+        end_version: Option<u64>,
+    },
+    /// Export the transactions, events or resources matching a query, e.g.
+    /// "events where type = 0x1::DiemAccount::SentPaymentEvent limit 100".
+    #[structopt(name = "query")]
+    Query {
+        query: query::Query,
+        /// "jsonl" or "csv".
+        #[structopt(long, default_value = "jsonl")]
+        format: query::Format,
+        /// Defaults to stdout.
+        #[structopt(long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
 }
 
 /// Print out latest information stored in the DB.
@@ -56,7 +84,14 @@ fn print_head(db: &DiemDB) -> Result<()> {
         si.latest_ledger_info.ledger_info()
     );
 
-    info!("Signatures: {:?}", si.latest_ledger_info.signatures());
+    match &si.latest_ledger_info {
+        LedgerInfoWithSignatures::V0(ledger_info) => {
+            info!("Signatures: {:?}", ledger_info.signatures())
+        }
+        LedgerInfoWithSignatures::V1(ledger_info) => {
+            info!("Aggregate signature: {:?}", ledger_info.signature())
+        }
+    }
 
     info!("Current EpochState: {}", si.get_epoch_state());
 
@@ -152,6 +187,42 @@ fn list_accounts(db: &DiemDB) {
     info!("Total Accounts: {}", num_account);
 }
 
+fn fsck(db: &DiemDB, start_version: u64, end_version: Option<u64>) {
+    let end_version = end_version.unwrap_or_else(|| {
+        db.get_latest_version()
+            .expect("Unable to get latest version")
+    });
+    info!(
+        "Checking versions [{}, {}] of the DB.",
+        start_version, end_version
+    );
+    let inconsistencies = db
+        .get_fsck()
+        .check(start_version, end_version)
+        .expect("Unable to check the DB");
+    for inconsistency in &inconsistencies {
+        println!("{}", inconsistency);
+    }
+    if !inconsistencies.is_empty() {
+        println!("Found {} inconsistencies.", inconsistencies.len());
+        std::process::exit(1);
+    }
+    info!("No inconsistency found.");
+}
+
+fn run_query(db: &DiemDB, query: &query::Query, format: query::Format, output: Option<PathBuf>) {
+    let num_rows = match output {
+        Some(path) => query.run(
+            db,
+            format,
+            BufWriter::new(File::create(path).expect("Unable to create output file")),
+        ),
+        None => query.run(db, format, io::stdout().lock()),
+    }
+    .expect("Unable to run query");
+    info!("Exported {} rows.", num_rows);
+}
+
 fn main() {
     ::diem_logger::DiemLogger::builder().build();
 
@@ -190,6 +261,19 @@ fn main() {
             Command::ListAccounts => {
                 list_accounts(&db);
             }
+            Command::Fsck {
+                start_version,
+                end_version,
+            } => {
+                fsck(&db, start_version, end_version);
+            }
+            Command::Query {
+                query,
+                format,
+                output,
+            } => {
+                run_query(&db, &query, format, output);
+            }
         }
     } else {
         print_head(&db).expect("Unable to read information from DB");
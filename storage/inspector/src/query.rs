@@ -0,0 +1,906 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A small query language to export transactions, events and resources from a DB, for loading
+//! them into analytics databases. A query looks like
+//!
+//! ```text
+//! transactions where sender = 0x1 and status = executed and version >= 100 limit 10
+//! ```
+//!
+//! It starts with the target, `transactions`, `events` or `resources`, optionally followed by
+//! conditions joined by `and`, and optionally ends with a limit on the number of rows. A condition
+//! is a field, an operator and a value, separated by whitespace. `version` can be compared with
+//! `=`, `!=`, `<`, `<=`, `>` and `>=`, other fields only with `=` and `!=`. Fields by target:
+//!
+//!   * transactions: `version`, `sender`, `script_function` and `status`. A script function is
+//!     `[address::][module::]function`, matching on the given parts; legacy scripts match on their
+//!     names. A status is `executed`, `out_of_gas`, `move_abort`, `execution_failure` or
+//!     `miscellaneous_error`.
+//!   * events: `version`, `key` and `type`, a Move type tag like
+//!     `0x1::DiemAccount::SentPaymentEvent`.
+//!   * resources: `address`, which is required, `version`, which defaults to the latest version and
+//!     can only be compared with `=`, and `type`.
+//!
+//! Transactions with `sender =` are read through the account index and events with `key =`
+//! through the event index, other queries scan the version range.
+//!
+//! Event data and resources are decoded with the resource viewer, which knows about the types
+//! defined in the Diem framework.
+
+use anyhow::{bail, ensure, format_err, Error, Result};
+use diem_framework_releases::name_for_script;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_state::AccountState,
+    contract_event::ContractEvent,
+    event::EventKey,
+    transaction::{Transaction, TransactionInfo, TransactionPayload, Version},
+    vm_status::KeptVMStatus,
+};
+use move_core_types::{language_storage::TypeTag, parser::parse_type_tag};
+use resource_viewer::{MoveValueAnnotator, NullStateView};
+use serde::Serialize;
+use std::{
+    cmp::{max, min},
+    convert::TryFrom,
+    fmt,
+    io::Write,
+    str::FromStr,
+};
+use storage_interface::{DbReader, Order};
+
+/// Max number of transactions or events read from the DB at a time.
+const BATCH_SIZE: u64 = 1000;
+
+const STATUSES: &[&str] = &[
+    "executed",
+    "out_of_gas",
+    "move_abort",
+    "execution_failure",
+    "miscellaneous_error",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Transactions,
+    Events,
+    Resources,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn eval<T: PartialOrd>(self, lhs: &T, rhs: &T) -> bool {
+        match self {
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+            Op::Lt => lhs < rhs,
+            Op::Le => lhs <= rhs,
+            Op::Gt => lhs > rhs,
+            Op::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "=" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            _ => bail!("Unknown operator: {}", s),
+        })
+    }
+}
+
+/// Name of the code a user transaction runs: a script function, or a legacy script known to the
+/// Diem framework. Also used as a pattern, where the address and the module are optional.
+#[derive(Clone, Debug, PartialEq)]
+struct ScriptName {
+    address: Option<AccountAddress>,
+    module: Option<String>,
+    function: String,
+}
+
+impl ScriptName {
+    fn of(payload: &TransactionPayload) -> Option<Self> {
+        match payload {
+            TransactionPayload::ScriptFunction(f) => Some(Self {
+                address: Some(*f.module().address()),
+                module: Some(f.module().name().to_string()),
+                function: f.function().to_string(),
+            }),
+            TransactionPayload::Script(s) => name_for_script(s.code()).ok().map(|name| Self {
+                address: None,
+                module: None,
+                function: name,
+            }),
+            TransactionPayload::WriteSet(_) | TransactionPayload::Module(_) => None,
+        }
+    }
+
+    fn matches(&self, pattern: &ScriptName) -> bool {
+        self.function == pattern.function
+            && (pattern.module.is_none() || self.module == pattern.module)
+            && (pattern.address.is_none() || self.address == pattern.address)
+    }
+}
+
+impl FromStr for ScriptName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.rsplit("::");
+        let function = parts
+            .next()
+            .filter(|f| !f.is_empty())
+            .ok_or_else(|| format_err!("Bad script function: {}", s))?
+            .to_string();
+        let module = parts.next().map(str::to_string);
+        let address = parts.next().map(parse_address).transpose()?;
+        ensure!(parts.next().is_none(), "Bad script function: {}", s);
+        Ok(Self {
+            address,
+            module,
+            function,
+        })
+    }
+}
+
+impl fmt::Display for ScriptName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(address) = &self.address {
+            write!(f, "0x{}::", address.short_str_lossless())?;
+        }
+        if let Some(module) = &self.module {
+            write!(f, "{}::", module)?;
+        }
+        write!(f, "{}", self.function)
+    }
+}
+
+fn parse_address(s: &str) -> Result<AccountAddress> {
+    Ok(if s.starts_with("0x") {
+        AccountAddress::from_hex_literal(s)?
+    } else {
+        AccountAddress::from_hex(s)?
+    })
+}
+
+fn status_name(status: &KeptVMStatus) -> &'static str {
+    match status {
+        KeptVMStatus::Executed => "executed",
+        KeptVMStatus::OutOfGas => "out_of_gas",
+        KeptVMStatus::MoveAbort(..) => "move_abort",
+        KeptVMStatus::ExecutionFailure { .. } => "execution_failure",
+        KeptVMStatus::MiscellaneousError => "miscellaneous_error",
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    Version(Op, Version),
+    Sender(Op, AccountAddress),
+    ScriptFunction(Op, ScriptName),
+    Status(Op, String),
+    Key(Op, EventKey),
+    Type(Op, TypeTag),
+    Address(Op, AccountAddress),
+}
+
+impl Condition {
+    fn parse(target: Target, field: &str, op: Op, value: &str) -> Result<Self> {
+        let condition = match (target, field) {
+            (_, "version") => Condition::Version(op, value.parse()?),
+            (Target::Transactions, "sender") => Condition::Sender(op, parse_address(value)?),
+            (Target::Transactions, "script_function") => {
+                Condition::ScriptFunction(op, value.parse()?)
+            }
+            (Target::Transactions, "status") => {
+                ensure!(STATUSES.contains(&value), "Unknown status: {}", value);
+                Condition::Status(op, value.to_string())
+            }
+            (Target::Events, "key") => Condition::Key(
+                op,
+                value
+                    .parse()
+                    .map_err(|_| format_err!("Bad event key: {}", value))?,
+            ),
+            (Target::Events, "type") | (Target::Resources, "type") => {
+                Condition::Type(op, parse_type_tag(value)?)
+            }
+            (Target::Resources, "address") => Condition::Address(op, parse_address(value)?),
+            _ => bail!("Unknown field for {:?}: {}", target, field),
+        };
+        if !matches!(condition, Condition::Version(..)) {
+            ensure!(
+                op == Op::Eq || op == Op::Ne,
+                "Field {} can only be compared with = or !=.",
+                field,
+            );
+        }
+        Ok(condition)
+    }
+}
+
+/// A parsed query, see the module documentation for the syntax.
+#[derive(Debug, PartialEq)]
+pub struct Query {
+    target: Target,
+    conditions: Vec<Condition>,
+    limit: Option<usize>,
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut tokens = s.split_whitespace().peekable();
+        let target = match tokens.next() {
+            Some("transactions") => Target::Transactions,
+            Some("events") => Target::Events,
+            Some("resources") => Target::Resources,
+            Some(t) => bail!("Unknown target: {}", t),
+            None => bail!("Empty query."),
+        };
+
+        let mut conditions = Vec::new();
+        if tokens.peek() == Some(&"where") {
+            tokens.next();
+            loop {
+                let (field, op, value) = match (tokens.next(), tokens.next(), tokens.next()) {
+                    (Some(field), Some(op), Some(value)) => (field, op.parse()?, value),
+                    _ => bail!("Incomplete condition."),
+                };
+                conditions.push(Condition::parse(target, field, op, value)?);
+                if tokens.peek() == Some(&"and") {
+                    tokens.next();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let limit = match tokens.next() {
+            Some("limit") => Some(
+                tokens
+                    .next()
+                    .ok_or_else(|| format_err!("Missing limit."))?
+                    .parse()?,
+            ),
+            Some(t) => bail!("Unexpected token: {}", t),
+            None => None,
+        };
+        if let Some(t) = tokens.next() {
+            bail!("Unexpected token: {}", t);
+        }
+
+        if target == Target::Resources {
+            ensure!(
+                conditions
+                    .iter()
+                    .any(|c| matches!(c, Condition::Address(Op::Eq, _))),
+                "Querying resources requires an address, e.g. \"where address = 0x1\".",
+            );
+            ensure!(
+                conditions
+                    .iter()
+                    .all(|c| !matches!(c, Condition::Version(op, _) if *op != Op::Eq)),
+                "Resources are queried at a single version, only \"version =\" is supported.",
+            );
+        }
+
+        Ok(Self {
+            target,
+            conditions,
+            limit,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    Jsonl,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "jsonl" => Format::Jsonl,
+            "csv" => Format::Csv,
+            _ => bail!("Unknown format: {}, expecting jsonl or csv.", s),
+        })
+    }
+}
+
+enum RowWriter<W: Write> {
+    Jsonl(W),
+    Csv(csv::Writer<W>),
+}
+
+impl<W: Write> RowWriter<W> {
+    fn new(format: Format, out: W) -> Self {
+        match format {
+            Format::Jsonl => RowWriter::Jsonl(out),
+            Format::Csv => RowWriter::Csv(csv::Writer::from_writer(out)),
+        }
+    }
+
+    fn write<R: Serialize>(&mut self, row: &R) -> Result<()> {
+        match self {
+            RowWriter::Jsonl(out) => {
+                serde_json::to_writer(&mut *out, row)?;
+                writeln!(out)?;
+            }
+            RowWriter::Csv(out) => out.serialize(row)?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            RowWriter::Jsonl(out) => out.flush()?,
+            RowWriter::Csv(out) => out.flush()?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct TransactionRow {
+    version: Version,
+    #[serde(rename = "type")]
+    type_: &'static str,
+    sender: Option<String>,
+    sequence_number: Option<u64>,
+    script_function: Option<String>,
+    status: &'static str,
+    gas_used: u64,
+}
+
+#[derive(Serialize)]
+struct EventRow {
+    version: Version,
+    key: String,
+    sequence_number: u64,
+    #[serde(rename = "type")]
+    type_: String,
+    /// Decoded if the type is known to the resource viewer, hex encoded otherwise.
+    data: String,
+}
+
+#[derive(Serialize)]
+struct ResourceRow {
+    version: Version,
+    address: String,
+    #[serde(rename = "type")]
+    type_: String,
+    /// Decoded if the type is known to the resource viewer, hex encoded otherwise.
+    value: String,
+}
+
+impl Query {
+    /// Runs the query against `db`, writing a row to `out` for each match. Returns the number of
+    /// rows written.
+    pub fn run(&self, db: &dyn DbReader, format: Format, out: impl Write) -> Result<usize> {
+        let mut writer = RowWriter::new(format, out);
+        let latest_version = db.get_latest_version()?;
+        let num_rows = match self.target {
+            Target::Transactions => self.query_transactions(db, latest_version, &mut writer)?,
+            Target::Events => self.query_events(db, latest_version, &mut writer)?,
+            Target::Resources => self.query_resources(db, latest_version, &mut writer)?,
+        };
+        writer.flush()?;
+        Ok(num_rows)
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(usize::max_value())
+    }
+
+    /// The version range to scan, narrowed down by the conditions on the version.
+    fn version_range(&self, latest_version: Version) -> (Version, Version) {
+        let (mut first, mut last) = (0, latest_version);
+        for condition in &self.conditions {
+            if let Condition::Version(op, v) = condition {
+                match op {
+                    Op::Eq => {
+                        first = max(first, *v);
+                        last = min(last, *v);
+                    }
+                    Op::Ge => first = max(first, *v),
+                    Op::Gt => first = max(first, v.saturating_add(1)),
+                    Op::Le => last = min(last, *v),
+                    Op::Lt if *v == 0 => return (1, 0),
+                    Op::Lt => last = min(last, v - 1),
+                    Op::Ne => (),
+                }
+            }
+        }
+        (first, last)
+    }
+
+    fn version_matches(&self, version: Version) -> bool {
+        self.conditions.iter().all(|c| match c {
+            Condition::Version(op, v) => op.eval(&version, v),
+            _ => true,
+        })
+    }
+
+    /// Calls `f` with each transaction in the version range, its info and its events if
+    /// `fetch_events`, until it returns false. Only the transactions of the sender are read if it
+    /// is pinned by a condition.
+    fn scan_transactions<F>(
+        &self,
+        db: &dyn DbReader,
+        latest_version: Version,
+        fetch_events: bool,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Version, &Transaction, &TransactionInfo, &[ContractEvent]) -> Result<bool>,
+    {
+        let (first, last) = self.version_range(latest_version);
+        let sender = self.conditions.iter().find_map(|c| match c {
+            Condition::Sender(Op::Eq, s) => Some(*s),
+            _ => None,
+        });
+        if let Some(sender) = sender {
+            // Sequence numbers increase with versions, stop at the first one past the range
+            for seq_num in 0.. {
+                let txn =
+                    match db.get_txn_by_account(sender, seq_num, latest_version, fetch_events)? {
+                        Some(txn) if txn.version <= last => txn,
+                        _ => break,
+                    };
+                if txn.version < first {
+                    continue;
+                }
+                let events = txn.events.unwrap_or_default();
+                if !f(
+                    txn.version,
+                    &txn.transaction,
+                    txn.proof.transaction_info(),
+                    &events,
+                )? {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        let mut version = first;
+        while version <= last {
+            let batch_size = min(BATCH_SIZE, last - version + 1);
+            let txn_list =
+                db.get_transactions(version, batch_size, latest_version, fetch_events)?;
+            ensure!(
+                txn_list.transactions.len() as u64 == batch_size,
+                "Expecting {} transactions from version {}, got {}.",
+                batch_size,
+                version,
+                txn_list.transactions.len(),
+            );
+            let events = txn_list.events.unwrap_or_default();
+            for (i, (txn, txn_info)) in txn_list
+                .transactions
+                .iter()
+                .zip(txn_list.proof.transaction_infos())
+                .enumerate()
+            {
+                let txn_events = events.get(i).map_or(&[][..], Vec::as_slice);
+                if !f(version + i as u64, txn, txn_info, txn_events)? {
+                    return Ok(());
+                }
+            }
+            version += batch_size;
+        }
+        Ok(())
+    }
+
+    fn query_transactions<W: Write>(
+        &self,
+        db: &dyn DbReader,
+        latest_version: Version,
+        writer: &mut RowWriter<W>,
+    ) -> Result<usize> {
+        let mut num_rows = 0;
+        self.scan_transactions(db, latest_version, false, |version, txn, txn_info, _| {
+            if num_rows >= self.limit() {
+                return Ok(false);
+            }
+            let user_txn = txn.as_signed_user_txn().ok();
+            let sender = user_txn.map(|t| t.sender());
+            let script_function = user_txn.and_then(|t| ScriptName::of(t.payload()));
+            let status = status_name(txn_info.status());
+            let matches = self.conditions.iter().all(|c| match c {
+                Condition::Version(op, v) => op.eval(&version, v),
+                Condition::Sender(op, s) => op.eval(&sender, &Some(*s)),
+                Condition::ScriptFunction(op, pattern) => {
+                    let is_match = script_function
+                        .as_ref()
+                        .map_or(false, |f| f.matches(pattern));
+                    (*op == Op::Eq) == is_match
+                }
+                Condition::Status(op, s) => op.eval(&status, &s.as_str()),
+                _ => true,
+            });
+            if matches {
+                writer.write(&TransactionRow {
+                    version,
+                    type_: match txn {
+                        Transaction::UserTransaction(_) => "user",
+                        Transaction::GenesisTransaction(_) => "genesis",
+                        Transaction::BlockMetadata(_) => "block_metadata",
+                    },
+                    sender: sender.map(|s| s.to_hex()),
+                    sequence_number: user_txn.map(|t| t.sequence_number()),
+                    script_function: script_function.map(|f| f.to_string()),
+                    status,
+                    gas_used: txn_info.gas_used(),
+                })?;
+                num_rows += 1;
+            }
+            Ok(true)
+        })?;
+        Ok(num_rows)
+    }
+
+    fn query_events<W: Write>(
+        &self,
+        db: &dyn DbReader,
+        latest_version: Version,
+        writer: &mut RowWriter<W>,
+    ) -> Result<usize> {
+        let view = NullStateView::default();
+        let annotator = MoveValueAnnotator::new(&view);
+        let mut num_rows = 0;
+        // Writes the event if it matches, returns whether to keep reading events
+        let mut visit = |version: Version, event: &ContractEvent| -> Result<bool> {
+            if num_rows >= self.limit() {
+                return Ok(false);
+            }
+            let matches = self.version_matches(version)
+                && self.conditions.iter().all(|c| match c {
+                    Condition::Key(op, k) => op.eval(event.key(), k),
+                    Condition::Type(op, t) => op.eval(event.type_tag(), t),
+                    _ => true,
+                });
+            if matches {
+                writer.write(&EventRow {
+                    version,
+                    key: event.key().to_string(),
+                    sequence_number: event.sequence_number(),
+                    type_: event.type_tag().to_string(),
+                    data: annotator
+                        .view_contract_event(event)
+                        .map_or_else(|_| hex::encode(event.event_data()), |v| v.to_string()),
+                })?;
+                num_rows += 1;
+            }
+            Ok(num_rows < self.limit())
+        };
+
+        let key = self.conditions.iter().find_map(|c| match c {
+            Condition::Key(Op::Eq, k) => Some(k),
+            _ => None,
+        });
+        match key {
+            Some(key) => self.scan_events_by_key(db, latest_version, key, visit)?,
+            None => self.scan_transactions(db, latest_version, true, |version, _, _, events| {
+                for event in events {
+                    if !visit(version, event)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            })?,
+        }
+        Ok(num_rows)
+    }
+
+    /// Calls `f` with each event of `key` in the version range, in order, until it returns false.
+    fn scan_events_by_key<F>(
+        &self,
+        db: &dyn DbReader,
+        latest_version: Version,
+        key: &EventKey,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Version, &ContractEvent) -> Result<bool>,
+    {
+        let (first, last) = self.version_range(latest_version);
+        let mut seq_num = 0;
+        loop {
+            let events = db.get_events(key, seq_num, Order::Ascending, BATCH_SIZE)?;
+            for (version, event) in &events {
+                if *version > last {
+                    return Ok(());
+                }
+                if *version >= first && !f(*version, event)? {
+                    return Ok(());
+                }
+            }
+            if (events.len() as u64) < BATCH_SIZE {
+                return Ok(());
+            }
+            seq_num += BATCH_SIZE;
+        }
+    }
+
+    fn query_resources<W: Write>(
+        &self,
+        db: &dyn DbReader,
+        latest_version: Version,
+        writer: &mut RowWriter<W>,
+    ) -> Result<usize> {
+        let mut address = None;
+        let mut version = latest_version;
+        for condition in &self.conditions {
+            match condition {
+                Condition::Address(Op::Eq, a) => address = Some(*a),
+                Condition::Version(_, v) => version = *v,
+                _ => (),
+            }
+        }
+        let address = address.expect("Checked when parsing.");
+        ensure!(
+            version <= latest_version,
+            "Version {} is beyond the latest version {}.",
+            version,
+            latest_version,
+        );
+
+        let blob = match db
+            .get_account_state_with_proof_by_version(address, version)?
+            .0
+        {
+            Some(blob) => blob,
+            None => return Ok(0),
+        };
+        let view = NullStateView::default();
+        let annotator = MoveValueAnnotator::new(&view);
+        let mut num_rows = 0;
+        for (path, value) in AccountState::try_from(&blob)?.iter() {
+            if num_rows >= self.limit() {
+                break;
+            }
+            let tag = match AccessPath::new(address, path.clone()).get_struct_tag() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let type_tag = TypeTag::Struct(tag.clone());
+            let matches = self.conditions.iter().all(|c| match c {
+                Condition::Address(op, a) => op.eval(&address, a),
+                Condition::Type(op, t) => op.eval(&type_tag, t),
+                _ => true,
+            });
+            if matches {
+                writer.write(&ResourceRow {
+                    version,
+                    address: address.to_hex(),
+                    type_: type_tag.to_string(),
+                    value: annotator
+                        .view_resource(&tag, value)
+                        .map_or_else(|_| hex::encode(value), |v| v.to_string()),
+                })?;
+                num_rows += 1;
+            }
+        }
+        Ok(num_rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use executor_test_helpers::integration_test_impl::test_execution_with_storage_impl;
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+
+    fn core_code_address() -> AccountAddress {
+        AccountAddress::from_hex_literal("0x1").unwrap()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query: Query = "transactions where sender = 0x1 and version >= 10 and \
+            script_function = PaymentScripts::peer_to_peer_with_metadata limit 5"
+            .parse()
+            .unwrap();
+        assert_eq!(query.target, Target::Transactions);
+        assert_eq!(query.limit, Some(5));
+        assert_eq!(
+            query.conditions,
+            vec![
+                Condition::Sender(Op::Eq, core_code_address()),
+                Condition::Version(Op::Ge, 10),
+                Condition::ScriptFunction(
+                    Op::Eq,
+                    ScriptName {
+                        address: None,
+                        module: Some("PaymentScripts".to_string()),
+                        function: "peer_to_peer_with_metadata".to_string(),
+                    }
+                ),
+            ]
+        );
+        assert_eq!(query.version_range(100), (10, 100));
+
+        let query: Query = "events where type = 0x1::DiemAccount::SentPaymentEvent"
+            .parse()
+            .unwrap();
+        assert_eq!(query.target, Target::Events);
+        assert_eq!(query.limit, None);
+
+        let query: Query = "resources where address = 0x1 and version = 3"
+            .parse()
+            .unwrap();
+        assert_eq!(query.version_range(100), (3, 3));
+    }
+
+    #[test]
+    fn test_parse_bad_query() {
+        for query in &[
+            "",
+            "blocks",
+            "transactions where",
+            "transactions where sender =",
+            "transactions where sender < 0x1",
+            "transactions where key = 0x1",
+            "transactions where status = failed",
+            "transactions limit",
+            "transactions limit 1 2",
+            "events where version ~ 1",
+            "resources",
+            "resources where address = 0x1 and version > 1",
+        ] {
+            assert!(query.parse::<Query>().is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_script_name() {
+        let name = ScriptName {
+            address: Some(core_code_address()),
+            module: Some("PaymentScripts".to_string()),
+            function: "peer_to_peer_with_metadata".to_string(),
+        };
+        assert_eq!(
+            name.to_string(),
+            "0x1::PaymentScripts::peer_to_peer_with_metadata"
+        );
+        for pattern in &[
+            "peer_to_peer_with_metadata",
+            "PaymentScripts::peer_to_peer_with_metadata",
+            "0x1::PaymentScripts::peer_to_peer_with_metadata",
+            "1::PaymentScripts::peer_to_peer_with_metadata",
+        ] {
+            assert!(name.matches(&pattern.parse().unwrap()), "{}", pattern);
+        }
+        for pattern in &[
+            "peer_to_peer",
+            "AccountAdministrationScripts::peer_to_peer_with_metadata",
+            "0x2::PaymentScripts::peer_to_peer_with_metadata",
+        ] {
+            assert!(!name.matches(&pattern.parse().unwrap()), "{}", pattern);
+        }
+        assert!("a::b::c::d".parse::<ScriptName>().is_err());
+    }
+
+    /// Runs `query` against `db`, returning the rows as JSON values.
+    fn run_query(db: &dyn DbReader, query: &str) -> Vec<Value> {
+        let mut out = Vec::new();
+        let num_rows = query
+            .parse::<Query>()
+            .unwrap()
+            .run(db, Format::Jsonl, &mut out)
+            .unwrap();
+        let rows: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), num_rows);
+        rows
+    }
+
+    /// Values of `field` in `rows`, skipping nulls.
+    fn distinct(rows: &[Value], field: &str) -> BTreeSet<String> {
+        rows.iter()
+            .filter_map(|row| row[field].as_str().map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_query_transactions() {
+        let db = test_execution_with_storage_impl();
+        let all_txns = run_query(&*db, "transactions");
+        assert_eq!(all_txns.len() as u64, db.get_latest_version().unwrap() + 1);
+
+        // Reading through the account index gives the same rows as scanning
+        let senders = distinct(&all_txns, "sender");
+        assert!(senders.len() > 1);
+        for sender in &senders {
+            let expected: Vec<_> = all_txns
+                .iter()
+                .filter(|row| row["sender"] == sender.as_str())
+                .cloned()
+                .collect();
+            let query = format!("transactions where sender = {}", sender);
+            assert_eq!(run_query(&*db, &query), expected);
+
+            let last = expected.last().unwrap();
+            let query = format!(
+                "transactions where sender = {} and version >= {} limit 1",
+                sender, last["version"]
+            );
+            assert_eq!(run_query(&*db, &query), vec![last.clone()]);
+        }
+
+        let expected: Vec<_> = all_txns
+            .iter()
+            .filter(|row| row["status"] == "executed")
+            .cloned()
+            .collect();
+        assert_eq!(
+            run_query(&*db, "transactions where status = executed"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_query_events() {
+        let db = test_execution_with_storage_impl();
+        let all_events = run_query(&*db, "events");
+
+        // Reading through the event index gives the same rows as scanning
+        let keys = distinct(&all_events, "key");
+        assert!(keys.len() > 1);
+        for key in &keys {
+            let expected: Vec<_> = all_events
+                .iter()
+                .filter(|row| row["key"] == key.as_str())
+                .cloned()
+                .collect();
+            let query = format!("events where key = {}", key);
+            assert_eq!(run_query(&*db, &query), expected);
+
+            let version = expected.last().unwrap()["version"].as_u64().unwrap();
+            let query = format!("events where key = {} and version < {}", key, version);
+            assert_eq!(
+                run_query(&*db, &query),
+                expected
+                    .iter()
+                    .filter(|row| row["version"].as_u64().unwrap() < version)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        let rows = run_query(
+            &*db,
+            "events where type = 0x1::DiemAccount::SentPaymentEvent limit 2",
+        );
+        assert_eq!(rows.len(), 2);
+    }
+}
@@ -4,14 +4,14 @@
 use super::*;
 use crate::test_helper::{
     arb_existent_kvs_and_nonexistent_keys, arb_kv_pair_with_distinct_last_nibble,
-    arb_tree_with_index, test_get_range_proof, test_get_with_proof,
+    arb_tree_with_index, test_get_range_proof, test_get_with_multi_proof, test_get_with_proof,
     test_get_with_proof_with_distinct_last_nibble, ValueBlob,
 };
 use diem_crypto::HashValue;
 use diem_nibble::Nibble;
 use diem_types::transaction::PRE_GENESIS_VERSION;
 use mock_tree_store::MockTreeStore;
-use proptest::prelude::*;
+use proptest::{collection::vec, prelude::*, sample::Index};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::collections::HashMap;
 
@@ -539,6 +539,99 @@ fn test_put_value_sets() {
     }
 }
 
+/// Applies `value_sets` on top of a tree holding `existing_kvs` both serially and in batch, and
+/// checks that both produce the same root hashes and updates.
+fn assert_batch_put_value_sets_matches_serial(
+    existing_kvs: Vec<(HashValue, ValueBlob)>,
+    value_sets: Vec<Vec<(HashValue, ValueBlob)>>,
+    min_parallel_value_set_size: usize,
+) {
+    let db = MockTreeStore::default();
+    let tree = JellyfishMerkleTree::new(&db);
+    let mut first_version = 0;
+    if !existing_kvs.is_empty() {
+        let (_root_hash, batch) = tree.put_value_set(existing_kvs, first_version).unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+        first_version += 1;
+    }
+
+    let expected = tree
+        .put_value_sets(value_sets.clone(), first_version)
+        .unwrap();
+    let actual = tree
+        .batch_put_value_sets_impl(value_sets, first_version, min_parallel_value_set_size)
+        .unwrap();
+    assert_eq!(actual, expected);
+}
+
+/// Generates the key-value pairs of an existing tree along with value sets updating it, which
+/// both update existing keys and insert new ones.
+fn arb_existing_kvs_and_value_sets() -> impl Strategy<
+    Value = (
+        Vec<(HashValue, ValueBlob)>,
+        Vec<Vec<(HashValue, ValueBlob)>>,
+    ),
+> {
+    (
+        vec(any::<(HashValue, ValueBlob)>(), 0..200),
+        vec(
+            vec(any::<(bool, Index, HashValue, ValueBlob)>(), 1..200),
+            1..5,
+        ),
+    )
+        .prop_map(|(existing_kvs, updates)| {
+            let value_sets = updates
+                .into_iter()
+                .map(|value_set| {
+                    value_set
+                        .into_iter()
+                        .map(|(is_update, index, key, value)| {
+                            if is_update && !existing_kvs.is_empty() {
+                                (index.get(&existing_kvs).0, value)
+                            } else {
+                                (key, value)
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            (existing_kvs, value_sets)
+        })
+}
+
+#[test]
+fn test_batch_put_value_sets() {
+    let seed: &[_] = &[1, 2, 3, 4];
+    let mut actual_seed = [0u8; 32];
+    actual_seed[..seed.len()].copy_from_slice(&seed);
+    let mut rng: StdRng = StdRng::from_seed(actual_seed);
+
+    let mut random_kvs = |num_keys: usize| -> Vec<(HashValue, ValueBlob)> {
+        (0..num_keys)
+            .map(|_| {
+                (
+                    HashValue::random_with_rng(&mut rng),
+                    ValueBlob::from(HashValue::random_with_rng(&mut rng).to_vec()),
+                )
+            })
+            .collect()
+    };
+    let existing_kvs = random_kvs(1000);
+    let mut value_sets = vec![random_kvs(1000), random_kvs(10)];
+    // Update existing keys, some of them twice within the same version
+    let mut updates = random_kvs(MIN_PARALLEL_VALUE_SET_SIZE);
+    for (i, (key, _value)) in updates.iter_mut().enumerate() {
+        *key = existing_kvs[i % 200].0;
+    }
+    value_sets.push(updates);
+
+    assert_batch_put_value_sets_matches_serial(
+        existing_kvs,
+        value_sets,
+        MIN_PARALLEL_VALUE_SET_SIZE,
+    );
+}
+
 fn many_keys_get_proof_and_verify_tree_root(seed: &[u8], num_keys: usize) {
     assert!(seed.len() < 32);
     let mut actual_seed = [0u8; 32];
@@ -637,6 +730,11 @@ proptest! {
         test_get_with_proof((existent_kvs, nonexistent_keys))
     }
 
+    #[test]
+    fn proptest_get_with_multi_proof((existent_kvs, nonexistent_keys) in arb_existent_kvs_and_nonexistent_keys::<ValueBlob>(1000, 100)) {
+        test_get_with_multi_proof((existent_kvs, nonexistent_keys))
+    }
+
     #[test]
     fn proptest_get_with_proof_with_distinct_last_nibble((kv1, kv2) in arb_kv_pair_with_distinct_last_nibble::<ValueBlob>()) {
         test_get_with_proof_with_distinct_last_nibble((kv1, kv2))
@@ -646,4 +744,9 @@ proptest! {
     fn proptest_get_range_proof((btree, n) in arb_tree_with_index::<ValueBlob>(1000)) {
         test_get_range_proof((btree, n))
     }
+
+    #[test]
+    fn proptest_batch_put_value_sets((existing_kvs, value_sets) in arb_existing_kvs_and_value_sets()) {
+        assert_batch_put_value_sets_matches_serial(existing_kvs, value_sets, 1);
+    }
 }
@@ -6,10 +6,10 @@
 //! This module implements [`JellyfishMerkleTree`] backed by storage module. The tree itself doesn't
 //! persist anything, but realizes the logic of R/W only. The write path will produce all the
 //! intermediate results in a batch for storage layer to commit and the read path will return
-//! results directly. The public APIs are only [`new`], [`put_value_sets`], [`put_value_set`] and
-//! [`get_with_proof`]. After each put with a `value_set` based on a known version, the tree will
-//! return a new root hash with a [`TreeUpdateBatch`] containing all the new nodes and indices of
-//! stale nodes.
+//! results directly. The public APIs are only [`new`], [`put_value_sets`], [`put_value_set`],
+//! [`batch_put_value_sets`], [`get_with_proof`] and [`get_with_multi_proof`]. After each put with
+//! a `value_set` based on a known version, the tree will return a new root hash with a
+//! [`TreeUpdateBatch`] containing all the new nodes and indices of stale nodes.
 //!
 //! A Jellyfish Merkle Tree itself logically is a 256-bit sparse Merkle tree with an optimization
 //! that any subtree containing 0 or 1 leaf node will be replaced by that leaf node or a placeholder
@@ -63,7 +63,9 @@
 //! [`new`]: struct.JellyfishMerkleTree.html#method.new
 //! [`put_value_sets`]: struct.JellyfishMerkleTree.html#method.put_value_sets
 //! [`put_value_set`]: struct.JellyfishMerkleTree.html#method.put_value_set
+//! [`batch_put_value_sets`]: struct.JellyfishMerkleTree.html#method.batch_put_value_sets
 //! [`get_with_proof`]: struct.JellyfishMerkleTree.html#method.get_with_proof
+//! [`get_with_multi_proof`]: struct.JellyfishMerkleTree.html#method.get_with_multi_proof
 //! [`TreeUpdateBatch`]: struct.TreeUpdateBatch.html
 //! [`InternalNode`]: node_type/struct.InternalNode.html
 //! [`LeafNode`]: node_type/struct.LeafNode.html
@@ -85,7 +87,7 @@ use anyhow::{bail, ensure, format_err, Result};
 use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_nibble::Nibble;
 use diem_types::{
-    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    proof::{SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof},
     transaction::Version,
 };
 use nibble_path::{skip_common_prefix, NibbleIterator, NibblePath};
@@ -94,13 +96,14 @@ use node_type::{Child, Children, InternalNode, LeafNode, Node, NodeKey};
 use proptest::arbitrary::Arbitrary;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
+use rayon::prelude::*;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     collections::{BTreeMap, BTreeSet},
     marker::PhantomData,
 };
 use thiserror::Error;
-use tree_cache::TreeCache;
+use tree_cache::{SubtreeUpdate, TreeCache};
 
 #[derive(Error, Debug)]
 #[error("Missing state root node at version {version}, probably pruned.")]
@@ -111,6 +114,12 @@ pub struct MissingRootError {
 /// The hardcoded maximum height of a [`JellyfishMerkleTree`] in nibbles.
 pub const ROOT_NIBBLE_HEIGHT: usize = HashValue::LENGTH * 2;
 
+/// The minimum size of a value set for
+/// [`batch_put_value_sets`](struct.JellyfishMerkleTree.html#method.batch_put_value_sets) to update
+/// the subtrees below the root in parallel. Smaller value sets are applied serially, as they would
+/// not amortize the cost of scheduling the subtree updates.
+const MIN_PARALLEL_VALUE_SET_SIZE: usize = 256;
+
 /// `TreeReader` defines the interface between
 /// [`JellyfishMerkleTree`](struct.JellyfishMerkleTree.html)
 /// and underlying storage holding nodes.
@@ -288,6 +297,167 @@ where
         Ok(tree_cache.into())
     }
 
+    /// Alternate form of [`put_value_sets`](struct.JellyfishMerkleTree.html#method.put_value_sets)
+    /// for large value sets. The keys of each value set are partitioned by their first nibble, the
+    /// subtrees below the root are updated in parallel and the results are merged at the root.
+    /// The returned root hashes and batch are identical to those of `put_value_sets`.
+    pub fn batch_put_value_sets(
+        &self,
+        value_sets: Vec<Vec<(HashValue, V)>>,
+        first_version: Version,
+    ) -> Result<(Vec<HashValue>, TreeUpdateBatch<V>)>
+    where
+        R: Sync,
+        V: Send + Sync,
+    {
+        self.batch_put_value_sets_impl(value_sets, first_version, MIN_PARALLEL_VALUE_SET_SIZE)
+    }
+
+    fn batch_put_value_sets_impl(
+        &self,
+        value_sets: Vec<Vec<(HashValue, V)>>,
+        first_version: Version,
+        min_parallel_value_set_size: usize,
+    ) -> Result<(Vec<HashValue>, TreeUpdateBatch<V>)>
+    where
+        R: Sync,
+        V: Send + Sync,
+    {
+        let mut tree_cache = TreeCache::new(self.reader, first_version)?;
+        for (idx, value_set) in value_sets.into_iter().enumerate() {
+            assert!(
+                !value_set.is_empty(),
+                "Transactions that output empty write set should not be included.",
+            );
+            let version = first_version + idx as u64;
+            if value_set.len() >= min_parallel_value_set_size {
+                Self::batch_put(value_set, version, &mut tree_cache)?;
+            } else {
+                value_set
+                    .into_iter()
+                    .try_for_each(|(key, value)| Self::put(key, value, version, &mut tree_cache))?;
+            }
+            // Freezes the current cache to make all contents in the current cache immutable.
+            tree_cache.freeze();
+        }
+
+        Ok(tree_cache.into())
+    }
+
+    /// Applies a value set to the subtrees below the root in parallel. Inserting a key into an
+    /// internal root node only updates the subtree at the key's first nibble, so doing so for the
+    /// keys of each subtree in their original order, then updating the root once, produces the
+    /// same nodes as inserting all keys one by one.
+    fn batch_put(
+        value_set: Vec<(HashValue, V)>,
+        version: Version,
+        tree_cache: &mut TreeCache<R, V>,
+    ) -> Result<()>
+    where
+        R: Sync,
+        V: Send + Sync,
+    {
+        let root_node_key = tree_cache.get_root_node_key().clone();
+        let root_node = match tree_cache.get_node(&root_node_key)? {
+            Node::Internal(internal_node) => internal_node,
+            // Null and leaf roots are replaced by the first keys inserted, so the subtrees only
+            // become independent of each other once the root is an internal node.
+            Node::Leaf(_) | Node::Null => {
+                return value_set
+                    .into_iter()
+                    .try_for_each(|(key, value)| Self::put(key, value, version, tree_cache));
+            }
+        };
+
+        let mut partitions: Vec<Vec<(HashValue, V)>> = (0..16).map(|_| Vec::new()).collect();
+        for (key, value) in value_set {
+            partitions[u8::from(key.get_nibble(0)) as usize].push((key, value));
+        }
+
+        let updates = {
+            let parent_cache: &TreeCache<R, V> = tree_cache;
+            partitions
+                .into_par_iter()
+                .enumerate()
+                .filter(|(_, partition)| !partition.is_empty())
+                .map(|(index, partition)| {
+                    Self::put_subtree(
+                        &root_node_key,
+                        &root_node,
+                        Nibble::from(index as u8),
+                        partition,
+                        version,
+                        TreeCache::new_subtree(parent_cache),
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        // Replace the root the same way inserting the keys one by one would have.
+        tree_cache.delete_node(&root_node_key, false /* is_leaf */);
+        let mut children: Children = root_node.into();
+        for (child_index, child, update) in updates {
+            children.insert(child_index, child);
+            tree_cache.merge_subtree_update(update)?;
+        }
+        let mut new_root_node_key = root_node_key;
+        new_root_node_key.set_version(version);
+        tree_cache.put_node(
+            new_root_node_key.clone(),
+            InternalNode::new(children).into(),
+        )?;
+        tree_cache.set_root_node_key(new_root_node_key);
+        Ok(())
+    }
+
+    /// Inserts the given keys, which all share `child_index` as their first nibble, into the
+    /// subtree at that index below the root. Returns the root's new child along with the nodes put
+    /// and deleted.
+    fn put_subtree(
+        root_node_key: &NodeKey,
+        root_node: &InternalNode,
+        child_index: Nibble,
+        value_set: Vec<(HashValue, V)>,
+        version: Version,
+        mut subtree_cache: TreeCache<R, V>,
+    ) -> Result<(Nibble, Child, SubtreeUpdate<V>)> {
+        let mut child_version = root_node.child(child_index).map(|child| child.version);
+        let mut new_child = None;
+        for (key, value) in value_set {
+            let nibble_path = NibblePath::new(key.to_vec());
+            let mut nibble_iter = nibble_path.nibbles();
+            // The root consumes the first nibble, as in `insert_at_internal_node`.
+            nibble_iter.next().expect("Ran out of nibbles");
+            let (_, new_child_node) = match child_version {
+                Some(child_version) => Self::insert_at(
+                    root_node_key.gen_child_node_key(child_version, child_index),
+                    version,
+                    &mut nibble_iter,
+                    value,
+                    &mut subtree_cache,
+                )?,
+                None => Self::create_leaf_node(
+                    root_node_key.gen_child_node_key(version, child_index),
+                    &nibble_iter,
+                    value,
+                    &mut subtree_cache,
+                )?,
+            };
+            child_version = Some(version);
+            new_child = Some(Child::new(
+                new_child_node.hash(),
+                version,
+                new_child_node.is_leaf(),
+            ));
+        }
+
+        Ok((
+            child_index,
+            new_child.expect("Subtrees are only updated with non-empty value sets."),
+            subtree_cache.into_subtree_update(),
+        ))
+    }
+
     fn put(
         key: HashValue,
         value: V,
@@ -606,6 +776,25 @@ where
         bail!("Jellyfish Merkle tree has cyclic graph inside.");
     }
 
+    /// Returns the values (if applicable) of a list of keys, in the same order, and a single
+    /// merkle proof for all of them.
+    pub fn get_with_multi_proof(
+        &self,
+        keys: &[HashValue],
+        version: Version,
+    ) -> Result<(Vec<Option<V>>, SparseMerkleMultiProof<V>)> {
+        let (values, proofs): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .map(|key| {
+                let (value, proof) = self.get_with_proof(*key, version)?;
+                Ok((value, (*key, proof)))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        Ok((values, SparseMerkleMultiProof::from_proofs(proofs)?))
+    }
+
     /// Gets the proof that shows a list of keys up to `rightmost_key_to_prove` exist at `version`.
     pub fn get_range_proof(
         &self,
@@ -110,6 +110,40 @@ pub fn test_get_with_proof<V: crate::TestValue>(
     test_nonexistent_keys_impl(&tree, version, &nonexistent_keys);
 }
 
+pub fn test_get_with_multi_proof<V: crate::TestValue>(
+    (existent_kvs, nonexistent_keys): (HashMap<HashValue, V>, Vec<HashValue>),
+) {
+    let (db, version) = init_mock_db(&existent_kvs);
+    let tree = JellyfishMerkleTree::new(&db);
+    let root_hash = tree.get_root_hash(version).unwrap();
+
+    let keys: Vec<_> = existent_kvs
+        .keys()
+        .chain(nonexistent_keys.iter())
+        .cloned()
+        .collect();
+    let (values, proof) = tree.get_with_multi_proof(&keys, version).unwrap();
+    for (key, value) in keys.iter().zip(values.iter()) {
+        assert_eq!(value.as_ref(), existent_kvs.get(key));
+    }
+    let elements: Vec<_> = keys
+        .iter()
+        .cloned()
+        .zip(values.iter().map(Option::as_ref))
+        .collect();
+    assert!(proof.verify(root_hash, &elements).is_ok());
+
+    // The multi-proof is never larger than the individual proofs together.
+    let num_siblings: usize = keys
+        .iter()
+        .map(|key| {
+            let (_, proof) = tree.get_with_proof(*key, version).unwrap();
+            proof.siblings().len()
+        })
+        .sum();
+    assert!(proof.siblings().len() <= num_siblings);
+}
+
 pub fn arb_kv_pair_with_distinct_last_nibble<V: crate::TestValue>(
 ) -> impl Strategy<Value = ((HashValue, V), (HashValue, V))> {
     (
@@ -131,10 +131,21 @@ pub struct TreeCache<'a, R, V> {
     /// The immutable part of this cache, which will be committed to the underlying storage.
     frozen_cache: FrozenTreeCache<V>,
 
+    /// The cache this cache covers a subtree of, if any. See `TreeCache::new_subtree`.
+    parent: Option<&'a TreeCache<'a, R, V>>,
+
     /// The underlying persistent storage.
     reader: &'a R,
 }
 
+/// The nodes a subtree cache put and deleted, to be merged back into its parent.
+pub struct SubtreeUpdate<V> {
+    node_cache: HashMap<NodeKey, Node<V>>,
+    num_new_leaves: usize,
+    stale_node_index_cache: HashSet<NodeKey>,
+    num_stale_leaves: usize,
+}
+
 impl<'a, R, V> TreeCache<'a, R, V>
 where
     R: 'a + TreeReader<V>,
@@ -169,6 +180,7 @@ where
             node_cache,
             stale_node_index_cache: HashSet::new(),
             frozen_cache: FrozenTreeCache::new(),
+            parent: None,
             root_node_key,
             next_version,
             reader,
@@ -177,12 +189,34 @@ where
         })
     }
 
+    /// Constructs a cache for updating one subtree below the root of `parent` at the upcoming
+    /// version, independently of the other subtrees. Nodes are read through `parent`, while the
+    /// nodes put and deleted are held by the new cache until they are merged back into `parent`
+    /// via `TreeCache::merge_subtree_update`. As a node deleted from the subtree is stale unless
+    /// the subtree cache itself holds it, this must be called before anything below the root of
+    /// `parent` is updated at the upcoming version.
+    pub fn new_subtree(parent: &'a TreeCache<'a, R, V>) -> Self {
+        Self {
+            node_cache: HashMap::new(),
+            stale_node_index_cache: HashSet::new(),
+            frozen_cache: FrozenTreeCache::new(),
+            parent: Some(parent),
+            root_node_key: parent.root_node_key.clone(),
+            next_version: parent.next_version,
+            reader: parent.reader,
+            num_stale_leaves: 0,
+            num_new_leaves: 0,
+        }
+    }
+
     /// Gets a node with given node key. If it doesn't exist in node cache, read from `reader`.
     pub fn get_node(&self, node_key: &NodeKey) -> Result<Node<V>> {
         Ok(if let Some(node) = self.node_cache.get(node_key) {
             node.clone()
         } else if let Some(node) = self.frozen_cache.node_cache.get(node_key) {
             node.clone()
+        } else if let Some(parent) = self.parent {
+            parent.get_node(node_key)?
         } else {
             DIEM_JELLYFISH_STORAGE_READS.inc();
             self.reader.get_node(node_key)?
@@ -228,6 +262,38 @@ where
         }
     }
 
+    /// Returns the nodes this subtree cache put and deleted.
+    pub fn into_subtree_update(self) -> SubtreeUpdate<V> {
+        SubtreeUpdate {
+            node_cache: self.node_cache,
+            num_new_leaves: self.num_new_leaves,
+            stale_node_index_cache: self.stale_node_index_cache,
+            num_stale_leaves: self.num_stale_leaves,
+        }
+    }
+
+    /// Merges the nodes a subtree cache put and deleted, as if they were put and deleted through
+    /// this cache.
+    pub fn merge_subtree_update(&mut self, update: SubtreeUpdate<V>) -> Result<()> {
+        for (node_key, node) in update.node_cache {
+            match self.node_cache.entry(node_key) {
+                Entry::Vacant(o) => {
+                    o.insert(node);
+                }
+                Entry::Occupied(o) => {
+                    bail!("Node with key {:?} already exists in NodeBatch", o.key())
+                }
+            }
+        }
+        self.num_new_leaves += update.num_new_leaves;
+        for node_key in update.stale_node_index_cache {
+            let is_new_entry = self.stale_node_index_cache.insert(node_key);
+            assert!(is_new_entry, "Node gets stale twice unexpectedly.");
+        }
+        self.num_stale_leaves += update.num_stale_leaves;
+        Ok(())
+    }
+
     /// Freezes all the contents in cache to be immutable and clear `node_cache`.
     pub fn freeze(&mut self) {
         let root_node_key = self.get_root_node_key();
@@ -0,0 +1,55 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use once_cell::sync::Lazy;
+
+/// Latest version the read replica caught up to
+pub static VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_read_replica_version",
+        "Latest version the read replica caught up to"
+    )
+    .unwrap()
+});
+
+/// Time elapsed between the timestamp of the latest block the read replica caught up to and its
+/// latest catch up, in milliseconds. This is the staleness of the chain as seen by the replica,
+/// which also grows when the chain itself makes no progress.
+pub static LATEST_BLOCK_AGE_MS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_read_replica_latest_block_age_ms",
+        "Time elapsed between the timestamp of the latest block the read replica caught up to \
+         and its latest catch up, in milliseconds"
+    )
+    .unwrap()
+});
+
+/// Time elapsed since the latest successful catch up with the primary, in milliseconds, as of the
+/// latest attempt to catch up
+pub static TIME_SINCE_LAST_CATCH_UP_MS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_read_replica_time_since_last_catch_up_ms",
+        "Time elapsed since the latest successful catch up with the primary, in milliseconds"
+    )
+    .unwrap()
+});
+
+/// Number of versions the primary committed beyond the latest version of the read replica, as
+/// reported by the JSON-RPC of the node
+pub static VERSION_LAG: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_read_replica_version_lag",
+        "Number of versions the primary committed beyond the latest version of the read replica"
+    )
+    .unwrap()
+});
+
+/// Cumulative number of failures to catch up with the primary
+pub static CATCH_UP_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_read_replica_catch_up_failures",
+        "Cumulative number of failures to catch up with the primary"
+    )
+    .unwrap()
+});
@@ -0,0 +1,174 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! A read replica of a node's DiemDB, opened as a RocksDB secondary instance alongside the node.
+//! It periodically catches up with the node, which keeps the DB open as primary, so that reads
+//! can be served from other processes on the same host without competing with the node itself.
+
+mod counters;
+
+use anyhow::Result;
+use diem_client::BlockingClient;
+use diem_config::config::RocksdbConfig;
+use diem_logger::prelude::*;
+use diemdb::DiemDB;
+use std::{
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use storage_interface::DbReader;
+
+#[derive(Debug)]
+pub struct ReadReplica {
+    db: Arc<DiemDB>,
+    sender: Mutex<mpsc::Sender<()>>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl ReadReplica {
+    /// Opens the DB under `db_root_path` as secondary, keeping the files of the secondary instance
+    /// under `secondary_path`, and catches up with the primary every `catch_up_interval`. If the
+    /// JSON-RPC endpoint of the node is given, the replica also reports how many versions it lags
+    /// behind the node after each catch up.
+    pub fn open<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        secondary_path: P,
+        rocksdb_config: RocksdbConfig,
+        catch_up_interval: Duration,
+        node_json_rpc_url: Option<String>,
+    ) -> Result<Self> {
+        let db = Arc::new(DiemDB::open_as_secondary(
+            db_root_path,
+            secondary_path,
+            rocksdb_config,
+        )?);
+        catch_up(&db)?;
+        let mut last_catch_up = Instant::now();
+        counters::TIME_SINCE_LAST_CATCH_UP_MS.set(0);
+        let node = node_json_rpc_url.map(BlockingClient::new);
+
+        let (sender, receiver) = mpsc::channel();
+        let replica_db = Arc::clone(&db);
+        let join_handle = Some(thread::spawn(move || loop {
+            match receiver.recv_timeout(catch_up_interval) {
+                Ok(_) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+            match catch_up(&replica_db) {
+                Ok(()) => last_catch_up = Instant::now(),
+                Err(e) => {
+                    counters::CATCH_UP_FAILURES.inc();
+                    warn!(error = ?e, "Catching up with the primary failed.");
+                }
+            }
+            counters::TIME_SINCE_LAST_CATCH_UP_MS.set(last_catch_up.elapsed().as_millis() as i64);
+            if let Some(node) = &node {
+                if let Err(e) = report_version_lag(node, &replica_db) {
+                    debug!(error = ?e, "Fetching the latest version of the node failed.");
+                }
+            }
+        }));
+        info!("Opened read replica.");
+
+        Ok(Self {
+            db,
+            sender: Mutex::new(sender),
+            join_handle,
+        })
+    }
+
+    /// The DB of the read replica, which reflects the primary as of the latest catch up.
+    pub fn db(&self) -> Arc<DiemDB> {
+        Arc::clone(&self.db)
+    }
+}
+
+impl Drop for ReadReplica {
+    fn drop(&mut self) {
+        // Notify the catch up thread to exit
+        self.sender.lock().unwrap().send(()).unwrap();
+        self.join_handle
+            .take()
+            .expect("Catch up thread must exist.")
+            .join()
+            .expect("Catch up thread should join peacefully.");
+    }
+}
+
+fn catch_up(db: &DiemDB) -> Result<()> {
+    db.try_catch_up_with_primary()?;
+    let (version, timestamp_usecs) = db.get_latest_commit_metadata()?;
+    let now_usecs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros() as u64;
+    counters::VERSION.set(version as i64);
+    counters::LATEST_BLOCK_AGE_MS.set((now_usecs.saturating_sub(timestamp_usecs) / 1000) as i64);
+    Ok(())
+}
+
+/// Compares the latest version of the node with that of the replica, which is read after the
+/// node's so that a node committing in between does not make the replica look ahead of it.
+fn report_version_lag(node: &BlockingClient, db: &DiemDB) -> Result<()> {
+    let node_version = node.get_metadata()?.into_inner().version;
+    let (version, _) = db.get_latest_commit_metadata()?;
+    counters::VERSION_LAG.set(node_version.saturating_sub(version) as i64);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+    use diemdb::test_helper::arb_blocks_to_commit;
+    use proptest::prelude::*;
+    use std::time::Instant;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1))]
+
+        #[test]
+        fn test_read_replica(input in arb_blocks_to_commit()) {
+            let tmp_dir = TempPath::new();
+            let secondary_dir = TempPath::new();
+            let primary = DiemDB::new_for_test(&tmp_dir);
+
+            // The replica can only be opened once the DB is bootstrapped
+            ReadReplica::open(
+                tmp_dir.path(),
+                secondary_dir.path(),
+                RocksdbConfig::default(),
+                Duration::from_millis(10),
+                None,
+            )
+            .unwrap_err();
+
+            let mut cur_ver = 0;
+            let mut replica = None;
+            for (txns_to_commit, ledger_info_with_sigs) in &input {
+                primary
+                    .save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                    .unwrap();
+                cur_ver += txns_to_commit.len() as u64;
+
+                let replica = replica.get_or_insert_with(|| {
+                    ReadReplica::open(
+                        tmp_dir.path(),
+                        secondary_dir.path(),
+                        RocksdbConfig::default(),
+                        Duration::from_millis(10),
+                        None,
+                    )
+                    .unwrap()
+                });
+                let deadline = Instant::now() + Duration::from_secs(10);
+                while replica.db().get_latest_ledger_info().unwrap() != *ledger_info_with_sigs {
+                    assert!(Instant::now() < deadline, "Read replica did not catch up.");
+                    thread::sleep(Duration::from_millis(10));
+                }
+            }
+        }
+    }
+}
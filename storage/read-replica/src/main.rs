@@ -0,0 +1,138 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::{format_err, Result};
+use diem_config::config::{JsonRpcConfig, RocksdbConfig, RoleType};
+use diem_logger::{prelude::*, Level, Logger};
+use diem_metrics::metric_server;
+use diem_read_replica::ReadReplica;
+use diem_types::{
+    account_config::diem_root_address, account_state::AccountState, chain_id::ChainId,
+};
+use std::{convert::TryFrom, net::SocketAddr, path::PathBuf, thread, time::Duration};
+use storage_interface::DbReader;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    about = "Serves JSON-RPC from a read-only secondary instance of the DB of a Diem node running \
+    on the same host."
+)]
+struct Opt {
+    /// Storage directory of the node, i.e. the parent of its `diemdb` directory
+    #[structopt(long, parse(from_os_str))]
+    db_dir: PathBuf,
+
+    /// Directory to keep the files of the secondary instance in, e.g. its logs
+    #[structopt(long, parse(from_os_str))]
+    secondary_dir: PathBuf,
+
+    /// Address to serve JSON-RPC on
+    #[structopt(long, default_value = "127.0.0.1:8081")]
+    address: SocketAddr,
+
+    /// Max number of requests in a JSON-RPC batch, defaults to that of a node
+    #[structopt(long)]
+    batch_size_limit: Option<u16>,
+
+    /// Max number of items in a page of JSON-RPC results, defaults to that of a node
+    #[structopt(long)]
+    page_size_limit: Option<u16>,
+
+    /// Max size of a JSON-RPC request in bytes, defaults to that of a node
+    #[structopt(long)]
+    content_length_limit: Option<usize>,
+
+    /// Certificate to serve JSON-RPC over TLS with
+    #[structopt(long, requires = "tls-key-path")]
+    tls_cert_path: Option<String>,
+
+    /// Private key of the TLS certificate
+    #[structopt(long, requires = "tls-cert-path")]
+    tls_key_path: Option<String>,
+
+    /// Address to serve metrics on
+    #[structopt(long, default_value = "0.0.0.0")]
+    metrics_address: String,
+
+    /// Port to serve metrics on, which must differ from the metrics ports of the node
+    #[structopt(long, default_value = "9103")]
+    metrics_port: u16,
+
+    /// Interval between catching up with the node, in milliseconds
+    #[structopt(long, default_value = "1000")]
+    catch_up_interval_ms: u64,
+
+    /// JSON-RPC endpoint of the node, to export how many versions the replica lags behind it
+    #[structopt(long)]
+    node_json_rpc_url: Option<String>,
+}
+
+impl Opt {
+    fn json_rpc_config(&self) -> JsonRpcConfig {
+        let default = JsonRpcConfig::default();
+        JsonRpcConfig {
+            address: self.address,
+            batch_size_limit: self.batch_size_limit.unwrap_or(default.batch_size_limit),
+            page_size_limit: self.page_size_limit.unwrap_or(default.page_size_limit),
+            content_length_limit: self
+                .content_length_limit
+                .unwrap_or(default.content_length_limit),
+            tls_cert_path: self.tls_cert_path.clone(),
+            tls_key_path: self.tls_key_path.clone(),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    Logger::new().level(Level::Info).read_env().init();
+    let opt = Opt::from_args();
+
+    let replica = ReadReplica::open(
+        &opt.db_dir,
+        &opt.secondary_dir,
+        RocksdbConfig::default(),
+        Duration::from_millis(opt.catch_up_interval_ms),
+        opt.node_json_rpc_url.clone(),
+    )?;
+    let db = replica.db();
+    let chain_id = fetch_chain_id(&*db)?;
+
+    let metrics_address = opt.metrics_address.clone();
+    let metrics_port = opt.metrics_port;
+    thread::spawn(move || metric_server::start_server(metrics_address, metrics_port, false));
+
+    // The replica has no mempool: transactions must be submitted to the node, and submitting them
+    // to the replica fails.
+    let (mp_sender, _) = futures::channel::mpsc::channel(0);
+    let json_rpc_config = opt.json_rpc_config();
+    let _json_rpc = diem_json_rpc::bootstrap(
+        json_rpc_config.address,
+        json_rpc_config.batch_size_limit,
+        json_rpc_config.page_size_limit,
+        json_rpc_config.content_length_limit,
+        &json_rpc_config.tls_cert_path,
+        &json_rpc_config.tls_key_path,
+        db,
+        mp_sender,
+        RoleType::FullNode,
+        chain_id,
+    );
+    info!(address = %json_rpc_config.address, "Serving JSON-RPC from the read replica.");
+
+    loop {
+        thread::park();
+    }
+}
+
+fn fetch_chain_id(db: &dyn DbReader) -> Result<ChainId> {
+    let blob = db
+        .get_latest_account_state(diem_root_address())?
+        .ok_or_else(|| format_err!("Missing Diem root account state."))?;
+    Ok(AccountState::try_from(&blob)?
+        .get_chain_id_resource()?
+        .ok_or_else(|| format_err!("Missing chain ID resource."))?
+        .chain_id())
+}
@@ -248,6 +248,13 @@ impl DB {
         DB::open_cf_as_secondary(db_opts, primary_path, secondary_path, name, column_families)
     }
 
+    /// Makes a DB opened as secondary reflect the writes made to the primary since it was opened
+    /// or last caught up.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.inner.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
     fn open_cf(
         db_opts: &rocksdb::Options,
         path: impl AsRef<Path>,
@@ -453,6 +460,14 @@ impl DB {
                 )
             })
     }
+
+    /// Creates a consistent, openable copy of the DB at `path`, which must not exist yet. SST
+    /// files are hard linked rather than copied when `path` is on the same file system, making
+    /// this cheap even for a large DB that is being written to.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
+        Ok(())
+    }
 }
 
 /// For now we always use synchronous writes. This makes sure that once the operation returns
@@ -349,6 +349,34 @@ fn test_open_as_secondary() {
         db_sec.get::<TestSchema1>(&TestField(0)).unwrap(),
         Some(TestField(0)),
     );
+
+    db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+    assert_eq!(db_sec.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+    db_sec.try_catch_up_with_primary().unwrap();
+    assert_eq!(
+        db_sec.get::<TestSchema1>(&TestField(1)).unwrap(),
+        Some(TestField(1)),
+    );
+}
+
+#[test]
+fn test_checkpoint() {
+    let tmpdir = diem_temppath::TempPath::new();
+    let checkpoint = diem_temppath::TempPath::new();
+    {
+        let db = open_db(&tmpdir);
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.create_checkpoint(&checkpoint).unwrap();
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+    }
+    {
+        let db = open_db(&checkpoint);
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+    }
 }
 
 #[test]
@@ -7,7 +7,7 @@ use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesWithProof},
     contract_event::{ContractEvent, EventWithProof},
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
@@ -285,6 +285,17 @@ pub trait DbReader: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountStateWithProof>;
 
+    /// Returns the account states corresponding to the given version and account addresses, in
+    /// the same order, with a single proof based on `ledger_version`.
+    fn get_accounts_state_with_proof(
+        &self,
+        _addresses: &[AccountAddress],
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStatesWithProof> {
+        unimplemented!()
+    }
+
     // Gets an account state by account address, out of the ledger state indicated by the state
     // Merkle tree root with a sparse merkle proof proving state tree root.
     // See [`DiemDB::get_account_state_with_proof_by_version`].
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_crypto::{
+    bls12381::{BLS12381PrivateKey, BLS12381PublicKey},
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
     traits::{SigningKey, Uniform},
@@ -35,6 +36,11 @@ fn trace_crypto_values(tracer: &mut Tracer, samples: &mut Samples) -> Result<()>
     tracer.trace_value(samples, &signature)?;
     tracer.trace_value::<MultiEd25519PublicKey>(samples, &public_key.into())?;
     tracer.trace_value::<MultiEd25519Signature>(samples, &signature.into())?;
+
+    let bls_private_key = BLS12381PrivateKey::generate(&mut rng);
+    let bls_public_key: BLS12381PublicKey = (&bls_private_key).into();
+    tracer.trace_value(samples, &bls_public_key)?;
+    tracer.trace_value(samples, &bls_private_key.sign(&message))?;
     Ok(())
 }
 
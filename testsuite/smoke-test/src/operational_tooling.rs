@@ -19,8 +19,8 @@ use diem_crypto::{
     HashValue, PrivateKey, Uniform,
 };
 use diem_global_constants::{
-    CONSENSUS_KEY, GENESIS_WAYPOINT, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT, OWNER_KEY,
-    VALIDATOR_NETWORK_ADDRESS_KEYS, VALIDATOR_NETWORK_KEY, WAYPOINT,
+    CONSENSUS_KEY, DIEM_ROOT_KEY, GENESIS_WAYPOINT, OPERATOR_ACCOUNT, OPERATOR_KEY, OWNER_ACCOUNT,
+    OWNER_KEY, VALIDATOR_NETWORK_ADDRESS_KEYS, VALIDATOR_NETWORK_KEY, WAYPOINT,
 };
 use diem_key_manager::diem_interface::DiemInterface;
 use diem_management::storage::to_x25519;
@@ -326,6 +326,78 @@ fn test_fullnode_network_key_rotation() {
     assert_eq!(new_network_key, info_network_key);
 }
 
+#[test]
+fn test_governance_proposal() {
+    let num_nodes = 4;
+    let (env, op_tool, backend, _) = launch_swarm_with_op_tool_and_backend(num_nodes, 0);
+    let diem_backend = load_diem_root_storage(&env.validator_swarm, 0);
+    let diem_storage: Storage = (&diem_backend).try_into().unwrap();
+    let diem_root_key = diem_storage
+        .get_public_key(DIEM_ROOT_KEY)
+        .unwrap()
+        .public_key;
+    let key_path = write_key_to_file(&diem_root_key, &env, write_key_to_file_hex_format);
+
+    // Propose to remove the last validator
+    let removed_backend = load_backend_storage(&env.validator_swarm, num_nodes - 1);
+    let removed_storage: Storage = (&removed_backend).try_into().unwrap();
+    let removed_account: AccountAddress = removed_storage.get(OWNER_ACCOUNT).unwrap().value;
+    let (_, node_config_path) = load_node_config(&env.validator_swarm, 0);
+    let actions_file = node_config_path.with_file_name("ACTIONS");
+    let proposal_file = node_config_path.with_file_name("PROPOSAL");
+    fs::write(
+        &actions_file,
+        format!(
+            "[[actions]]\ntype = \"remove_validator\"\naccount_address = \"{}\"\n",
+            removed_account
+        ),
+    )
+    .unwrap();
+    let proposal_file = proposal_file.to_str().unwrap();
+    let review = op_tool
+        .propose_governance_actions(
+            actions_file.to_str().unwrap(),
+            &[key_path.to_str().unwrap()],
+            1,
+            proposal_file,
+        )
+        .unwrap();
+    assert_eq!(1, review.removed_validators.len());
+    assert_eq!(
+        removed_account,
+        review.removed_validators[0].account_address
+    );
+    assert_eq!(num_nodes - 1, review.validator_set.len());
+    assert!(review.signers.is_empty());
+
+    // The proposal can neither be submitted unsigned nor signed with another key
+    op_tool.submit_proposal(proposal_file).unwrap_err();
+    op_tool.sign_proposal(proposal_file, &backend).unwrap_err();
+
+    let review = op_tool.sign_proposal(proposal_file, &diem_backend).unwrap();
+    assert_eq!(vec![diem_root_key], review.signers);
+    assert_eq!(
+        review.signers,
+        op_tool.review_proposal(proposal_file).unwrap().signers
+    );
+    let txn_ctxs = op_tool.submit_proposal(proposal_file).unwrap();
+    let client = env.get_validator_client(0, None);
+    for txn_ctx in &txn_ctxs {
+        client
+            .wait_for_transaction(txn_ctx.address, txn_ctx.sequence_number)
+            .unwrap();
+    }
+
+    let validator_set_infos = op_tool.validator_set(None, &backend).unwrap();
+    assert_eq!(num_nodes - 1, validator_set_infos.len());
+    assert!(validator_set_infos
+        .iter()
+        .all(|info| info.account_address != removed_account));
+
+    // The proposal was built against the previous validator set
+    op_tool.submit_proposal(proposal_file).unwrap_err();
+}
+
 #[test]
 fn test_network_key_rotation() {
     let num_nodes = 4;
@@ -514,6 +586,56 @@ fn test_print_account() {
     assert_eq!(storage_operator_account, op_tool_operator_account);
 }
 
+#[test]
+fn test_safety_data_export_import() {
+    let (env, op_tool, backend, _storage) = launch_swarm_with_op_tool_and_backend(2, 0);
+    let (_, node_config_path) = load_node_config(&env.validator_swarm, 0);
+    let old_file = node_config_path.with_file_name("OLD_SAFETY_DATA");
+    let new_file = node_config_path.with_file_name("NEW_SAFETY_DATA");
+
+    // Export the safety data twice, until the validator has voted in between
+    let old = op_tool
+        .export_safety_data(old_file.to_str().unwrap(), &backend)
+        .unwrap();
+    let mut new = op_tool
+        .export_safety_data(new_file.to_str().unwrap(), &backend)
+        .unwrap();
+    for _ in 0..50 {
+        if new.safety_data.last_voted_round > old.safety_data.last_voted_round {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        new = op_tool
+            .export_safety_data(new_file.to_str().unwrap(), &backend)
+            .unwrap();
+    }
+    assert!(new.safety_data.last_voted_round > old.safety_data.last_voted_round);
+
+    // Older safety data is refused, while the latest is accepted
+    op_tool
+        .import_safety_data(old_file.to_str().unwrap(), &backend)
+        .unwrap_err();
+    let imported = op_tool
+        .import_safety_data(new_file.to_str().unwrap(), &backend)
+        .unwrap();
+    assert_eq!(new.safety_data, imported.safety_data);
+
+    // Safety data of another validator is refused
+    let other_backend = load_backend_storage(&env.validator_swarm, 1);
+    op_tool
+        .import_safety_data(new_file.to_str().unwrap(), &other_backend)
+        .unwrap_err();
+
+    // Safety data edited after the export is refused
+    let contents = fs::read_to_string(&new_file).unwrap();
+    let edited = contents.replacen("\"epoch\": ", "\"epoch\": 1", 1);
+    assert_ne!(contents, edited);
+    fs::write(&new_file, edited).unwrap();
+    op_tool
+        .import_safety_data(new_file.to_str().unwrap(), &backend)
+        .unwrap_err();
+}
+
 #[test]
 fn test_print_key() {
     let (_env, op_tool, backend, storage) = launch_swarm_with_op_tool_and_backend(1, 0);
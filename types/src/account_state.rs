@@ -12,8 +12,8 @@ use crate::{
     block_metadata::DiemBlockResource,
     diem_timestamp::DiemTimestampResource,
     on_chain_config::{
-        ConfigurationResource, DiemVersion, OnChainConfig, RegisteredCurrencies,
-        VMPublishingOption, ValidatorSet,
+        ConfigurationResource, DiemVersion, OnChainConfig, OnChainConsensusConfig,
+        RegisteredCurrencies, VMPublishingOption, ValidatorSet,
     },
     validator_config::{ValidatorConfigResource, ValidatorOperatorConfigResource},
 };
@@ -178,6 +178,14 @@ impl AccountState {
             .map_err(Into::into)
     }
 
+    pub fn get_consensus_config(&self) -> Result<Option<OnChainConsensusConfig>> {
+        self.0
+            .get(&OnChainConsensusConfig::CONFIG_ID.access_path().path)
+            .map(|bytes| OnChainConsensusConfig::deserialize_into_config(bytes))
+            .transpose()
+            .map_err(Into::into)
+    }
+
     pub fn get_registered_currency_info_resources(&self) -> Result<Vec<CurrencyInfoResource>> {
         let currencies: Option<RegisteredCurrencies> = self.get_config()?;
         match currencies {
@@ -6,7 +6,7 @@ use crate::{
     account_config::{AccountResource, BalanceResource},
     account_state::AccountState,
     ledger_info::LedgerInfo,
-    proof::AccountStateProof,
+    proof::{AccountStateProof, AccountStatesProof},
     transaction::Version,
 };
 use anyhow::{anyhow, ensure, Error, Result};
@@ -184,6 +184,64 @@ impl AccountStateWithProof {
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStatesWithProof {
+    /// The transaction version at which these account states are seen.
+    pub version: Version,
+    /// Blob values representing the account states, in the order the accounts were requested
+    /// in. If a value is not set, it means the account does not exist.
+    pub blobs: Vec<Option<AccountStateBlob>>,
+    /// The proof the client can use to authenticate all the values at once.
+    pub proof: AccountStatesProof,
+}
+
+impl AccountStatesWithProof {
+    /// Constructor.
+    pub fn new(
+        version: Version,
+        blobs: Vec<Option<AccountStateBlob>>,
+        proof: AccountStatesProof,
+    ) -> Self {
+        Self {
+            version,
+            blobs,
+            proof,
+        }
+    }
+
+    /// Verifies the account state blobs with the proof, both carried by `self`.
+    ///
+    /// The same is ensured as by `AccountStateWithProof::verify`, for the account of each address
+    /// in `addresses` and the blob at the same position in `self.blobs`.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        version: Version,
+        addresses: &[AccountAddress],
+    ) -> Result<()> {
+        ensure!(
+            self.version == version,
+            "State version ({}) is not expected ({}).",
+            self.version,
+            version,
+        );
+        ensure!(
+            self.blobs.len() == addresses.len(),
+            "Number of account states ({}) does not match number of addresses ({}).",
+            self.blobs.len(),
+            addresses.len(),
+        );
+
+        let accounts: Vec<_> = addresses
+            .iter()
+            .map(|address| address.hash())
+            .zip(self.blobs.iter().map(Option::as_ref))
+            .collect();
+        self.proof.verify(ledger_info, version, &accounts)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +269,11 @@ mod tests {
         fn account_state_with_proof_bcs_roundtrip(account_state_with_proof in any::<AccountStateWithProof>()) {
             assert_canonical_encode_decode(account_state_with_proof);
         }
+
+        #[test]
+        fn account_states_with_proof_bcs_roundtrip(account_states_with_proof in any::<AccountStatesWithProof>()) {
+            assert_canonical_encode_decode(account_states_with_proof);
+        }
     }
 
     #[test]
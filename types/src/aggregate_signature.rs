@@ -0,0 +1,33 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_bitvec::BitVec;
+use diem_crypto::bls12381::BLS12381Signature;
+use serde::{Deserialize, Serialize};
+
+/// A single BLS signature aggregated from the signatures of several validators on the same
+/// message. The signers are identified by their position in the validator set of the epoch,
+/// ordered by account address, so that a quorum certificate does not grow with the number of
+/// signers. Use `ValidatorVerifier::aggregate_signatures` to create one.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AggregateSignature {
+    validator_bitmask: BitVec,
+    signature: BLS12381Signature,
+}
+
+impl AggregateSignature {
+    pub fn new(validator_bitmask: BitVec, signature: BLS12381Signature) -> Self {
+        Self {
+            validator_bitmask,
+            signature,
+        }
+    }
+
+    pub fn validator_bitmask(&self) -> &BitVec {
+        &self.validator_bitmask
+    }
+
+    pub fn signature(&self) -> &BLS12381Signature {
+        &self.signature
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::{
     account_address::AccountAddress,
+    aggregate_signature::AggregateSignature,
     block_info::{BlockInfo, Round},
     epoch_state::EpochState,
     on_chain_config::ValidatorSet,
@@ -17,7 +18,6 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fmt::{Display, Formatter},
-    ops::{Deref, DerefMut},
 };
 
 /// This structure serves a dual purpose.
@@ -133,12 +133,14 @@ impl LedgerInfo {
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum LedgerInfoWithSignatures {
     V0(LedgerInfoWithV0),
+    V1(LedgerInfoWithV1),
 }
 
 impl Display for LedgerInfoWithSignatures {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
             LedgerInfoWithSignatures::V0(ledger) => write!(f, "{}", ledger),
+            LedgerInfoWithSignatures::V1(ledger) => write!(f, "{}", ledger),
         }
     }
 }
@@ -152,30 +154,56 @@ impl LedgerInfoWithSignatures {
         LedgerInfoWithSignatures::V0(LedgerInfoWithV0::new(ledger_info, signatures))
     }
 
+    pub fn new_aggregated(ledger_info: LedgerInfo, signature: AggregateSignature) -> Self {
+        LedgerInfoWithSignatures::V1(LedgerInfoWithV1::new(ledger_info, signature))
+    }
+
     pub fn genesis(genesis_state_root_hash: HashValue, validator_set: ValidatorSet) -> Self {
         LedgerInfoWithSignatures::V0(LedgerInfoWithV0::genesis(
             genesis_state_root_hash,
             validator_set,
         ))
     }
-}
 
-// Temporary hack to avoid massive changes, it won't work when new variant comes and needs proper
-// dispatch at that time.
-impl Deref for LedgerInfoWithSignatures {
-    type Target = LedgerInfoWithV0;
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        match self {
+            LedgerInfoWithSignatures::V0(ledger) => ledger.ledger_info(),
+            LedgerInfoWithSignatures::V1(ledger) => ledger.ledger_info(),
+        }
+    }
 
-    fn deref(&self) -> &LedgerInfoWithV0 {
-        match &self {
-            LedgerInfoWithSignatures::V0(ledger) => ledger,
+    /// Returns the individual signatures of the validators, which aggregate signatures don't
+    /// retain. Use `signers` to get the validators that signed either way.
+    pub fn signatures(&self) -> Option<&BTreeMap<AccountAddress, Ed25519Signature>> {
+        match self {
+            LedgerInfoWithSignatures::V0(ledger) => Some(ledger.signatures()),
+            LedgerInfoWithSignatures::V1(_) => None,
         }
     }
-}
 
-impl DerefMut for LedgerInfoWithSignatures {
-    fn deref_mut(&mut self) -> &mut LedgerInfoWithV0 {
+    /// Returns the validators that signed, which requires the validator set of the epoch for
+    /// aggregate signatures.
+    pub fn signers(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> ::std::result::Result<Vec<AccountAddress>, VerifyError> {
         match self {
-            LedgerInfoWithSignatures::V0(ref mut ledger) => ledger,
+            LedgerInfoWithSignatures::V0(ledger) => {
+                Ok(ledger.signatures().keys().copied().collect())
+            }
+            LedgerInfoWithSignatures::V1(ledger) => {
+                validator.get_signers(ledger.signature().validator_bitmask())
+            }
+        }
+    }
+
+    pub fn verify_signatures(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> ::std::result::Result<(), VerifyError> {
+        match self {
+            LedgerInfoWithSignatures::V0(ledger) => ledger.verify_signatures(validator),
+            LedgerInfoWithSignatures::V1(ledger) => ledger.verify_signatures(validator),
         }
     }
 }
@@ -248,6 +276,44 @@ impl LedgerInfoWithV0 {
     }
 }
 
+/// A `LedgerInfo` with a single BLS signature aggregated from the signatures of the validators,
+/// which keeps the size of ledger infos independent of the number of signers.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LedgerInfoWithV1 {
+    ledger_info: LedgerInfo,
+    signature: AggregateSignature,
+}
+
+impl Display for LedgerInfoWithV1 {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.ledger_info)
+    }
+}
+
+impl LedgerInfoWithV1 {
+    pub fn new(ledger_info: LedgerInfo, signature: AggregateSignature) -> Self {
+        LedgerInfoWithV1 {
+            ledger_info,
+            signature,
+        }
+    }
+
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        &self.ledger_info
+    }
+
+    pub fn signature(&self) -> &AggregateSignature {
+        &self.signature
+    }
+
+    pub fn verify_signatures(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> ::std::result::Result<(), VerifyError> {
+        validator.verify_aggregate_signature(self.ledger_info(), self.signature())
+    }
+}
+
 //
 // Arbitrary implementation of LedgerInfoWithV0 (for fuzzing)
 //
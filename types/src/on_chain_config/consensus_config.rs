@@ -0,0 +1,66 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{account_address::AccountAddress, on_chain_config::OnChainConfig};
+use anyhow::{format_err, Result};
+use diem_crypto::bls12381::{BLS12381ProofOfPossession, BLS12381PublicKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The consensus configuration, published on chain as the BCS encoded payload of the
+/// `DiemConsensusConfig` config and updated by DiemRoot. The payload is initialized empty, which
+/// stands for the default configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum OnChainConsensusConfig {
+    V1(ConsensusConfigV1),
+}
+
+/// The BLS keys registered by validators to sign ledger infos with aggregate signatures.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ConsensusConfigV1 {
+    pub validator_bls_keys: BTreeMap<AccountAddress, ValidatorBlsKey>,
+}
+
+/// A BLS public key with the proof that its owner knows the private key, which is checked before
+/// the key is used to verify aggregate signatures.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ValidatorBlsKey {
+    pub public_key: BLS12381PublicKey,
+    pub proof_of_possession: BLS12381ProofOfPossession,
+}
+
+impl OnChainConsensusConfig {
+    pub fn validator_bls_keys(&self) -> &BTreeMap<AccountAddress, ValidatorBlsKey> {
+        match self {
+            OnChainConsensusConfig::V1(config) => &config.validator_bls_keys,
+        }
+    }
+}
+
+impl Default for OnChainConsensusConfig {
+    fn default() -> Self {
+        OnChainConsensusConfig::V1(ConsensusConfigV1::default())
+    }
+}
+
+impl OnChainConfig for OnChainConsensusConfig {
+    const IDENTIFIER: &'static str = "DiemConsensusConfig";
+
+    fn deserialize_into_config(bytes: &[u8]) -> Result<Self> {
+        let payload: Vec<u8> = bcs::from_bytes(&bytes).map_err(|e| {
+            format_err!(
+                "Failed first round of deserialization for OnChainConsensusConfig: {}",
+                e
+            )
+        })?;
+        if payload.is_empty() {
+            return Ok(Self::default());
+        }
+        bcs::from_bytes(&payload).map_err(|e| {
+            format_err!(
+                "Failed second round of deserialization for OnChainConsensusConfig: {}",
+                e
+            )
+        })
+    }
+}
@@ -16,6 +16,7 @@ use move_core_types::{
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{collections::HashMap, fmt, sync::Arc};
 
+mod consensus_config;
 mod diem_version;
 mod registered_currencies;
 mod validator_set;
@@ -23,6 +24,7 @@ mod vm_config;
 mod vm_publishing_option;
 
 pub use self::{
+    consensus_config::{ConsensusConfigV1, OnChainConsensusConfig, ValidatorBlsKey},
     diem_version::{DiemVersion, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2},
     registered_currencies::RegisteredCurrencies,
     validator_set::ValidatorSet,
@@ -25,7 +25,7 @@ use diem_crypto::{
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
-use std::marker::PhantomData;
+use std::{iter::Peekable, marker::PhantomData, slice::Iter};
 
 /// A proof that can be used authenticate an element in an accumulator given trusted root hash. For
 /// example, both `LedgerInfoToTransactionInfoProof` and `TransactionInfoToEventProof` can be
@@ -265,6 +265,254 @@ where
     }
 }
 
+/// A proof that can be used to authenticate a set of elements in a Sparse Merkle Tree given
+/// trusted root hash. It is equivalent to one `SparseMerkleProof` per element, except that the
+/// siblings the individual proofs have in common, or which can be computed from other elements,
+/// are only included once or not at all.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SparseMerkleMultiProof<V> {
+    /// The subtrees the elements end up in, ordered by key. Each of them is identified by its
+    /// depth and, like in `SparseMerkleProof`, either the only leaf in it or `None` if it is empty.
+    /// Elements ending up in the same subtree share the same entry.
+    subtrees: Vec<(u16, Option<SparseMerkleLeafNode>)>,
+
+    /// The siblings of the paths from the root to the subtrees which are not on any of these
+    /// paths themselves, in the order they are met by a depth-first, left-to-right traversal of
+    /// the paths.
+    siblings: Vec<HashValue>,
+
+    phantom: PhantomData<V>,
+}
+
+impl<V> SparseMerkleMultiProof<V>
+where
+    V: CryptoHash,
+{
+    /// Constructs a new `SparseMerkleMultiProof` using a list of subtrees and a list of siblings.
+    pub fn new(
+        subtrees: Vec<(u16, Option<SparseMerkleLeafNode>)>,
+        siblings: Vec<HashValue>,
+    ) -> Self {
+        SparseMerkleMultiProof {
+            subtrees,
+            siblings,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Combines the proofs of individual keys against the same tree into a single proof. The keys
+    /// may be given in any order and more than once.
+    pub fn from_proofs(mut proofs: Vec<(HashValue, SparseMerkleProof<V>)>) -> Result<Self> {
+        ensure!(
+            !proofs.is_empty(),
+            "Cannot combine an empty list of proofs."
+        );
+        proofs.sort_by_key(|(key, _)| *key);
+        proofs.dedup_by_key(|(key, _)| *key);
+
+        let mut subtrees = vec![];
+        let mut siblings = vec![];
+        Self::combine(&proofs, 0, &mut subtrees, &mut siblings)?;
+        Ok(Self::new(subtrees, siblings))
+    }
+
+    fn combine(
+        proofs: &[(HashValue, SparseMerkleProof<V>)],
+        depth: usize,
+        subtrees: &mut Vec<(u16, Option<SparseMerkleLeafNode>)>,
+        siblings: &mut Vec<HashValue>,
+    ) -> Result<()> {
+        let (_, first_proof) = &proofs[0];
+        let len = first_proof.siblings.len();
+        ensure!(
+            len <= HashValue::LENGTH_IN_BITS,
+            "Sparse Merkle Tree proof has more than {} ({}) siblings.",
+            HashValue::LENGTH_IN_BITS,
+            len,
+        );
+        if len == depth {
+            ensure!(
+                proofs
+                    .iter()
+                    .all(|(_, proof)| proof.siblings.len() == depth
+                        && proof.leaf == first_proof.leaf),
+                "Proofs of keys ending up in the same subtree do not match.",
+            );
+            subtrees.push((depth as u16, first_proof.leaf));
+            return Ok(());
+        }
+        ensure!(
+            len > depth,
+            "Proofs of keys sharing a prefix of {} bits end at different depths.",
+            depth,
+        );
+
+        let split = proofs
+            .iter()
+            .position(|(key, _)| key.bit(depth))
+            .unwrap_or_else(|| proofs.len());
+        let (left, right) = proofs.split_at(split);
+        if left.is_empty() || right.is_empty() {
+            siblings.push(first_proof.siblings[len - 1 - depth]);
+        }
+        if !left.is_empty() {
+            Self::combine(left, depth + 1, subtrees, siblings)?;
+        }
+        if !right.is_empty() {
+            Self::combine(right, depth + 1, subtrees, siblings)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the list of subtrees in this proof.
+    pub fn subtrees(&self) -> &[(u16, Option<SparseMerkleLeafNode>)] {
+        &self.subtrees
+    }
+
+    /// Returns the list of siblings in this proof.
+    pub fn siblings(&self) -> &[HashValue] {
+        &self.siblings
+    }
+
+    /// Verifies every element in `elements`, as `SparseMerkleProof::verify` does for a single
+    /// one: if the value of an element is present, that the element exists in the Sparse Merkle
+    /// Tree, otherwise that its key doesn't exist in the tree. The elements may be given in any
+    /// order and a key more than once, as long as it is with the same value.
+    pub fn verify(
+        &self,
+        expected_root_hash: HashValue,
+        elements: &[(HashValue, Option<&V>)],
+    ) -> Result<()> {
+        ensure!(!elements.is_empty(), "No element to verify.");
+        let mut elements = elements.to_vec();
+        elements.sort_by_key(|(key, _)| *key);
+        for window in elements.windows(2) {
+            let ((key, value), (other_key, other_value)) = (window[0], window[1]);
+            ensure!(
+                key != other_key || value.map(|v| v.hash()) == other_value.map(|v| v.hash()),
+                "Key {:x} is verified with different values.",
+                key,
+            );
+        }
+        elements.dedup_by_key(|(key, _)| *key);
+
+        let mut subtrees = self.subtrees.iter().peekable();
+        let mut siblings = self.siblings.iter();
+        let actual_root_hash = Self::compute_root_hash(&elements, 0, &mut subtrees, &mut siblings)?;
+        ensure!(
+            subtrees.next().is_none() && siblings.next().is_none(),
+            "Sparse Merkle Tree multi-proof has unused subtrees or siblings.",
+        );
+        ensure!(
+            actual_root_hash == expected_root_hash,
+            "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+            actual_root_hash,
+            expected_root_hash,
+        );
+
+        Ok(())
+    }
+
+    fn compute_root_hash(
+        elements: &[(HashValue, Option<&V>)],
+        depth: usize,
+        subtrees: &mut Peekable<Iter<'_, (u16, Option<SparseMerkleLeafNode>)>>,
+        siblings: &mut Iter<'_, HashValue>,
+    ) -> Result<HashValue> {
+        let subtree_depth = match subtrees.peek() {
+            Some((subtree_depth, _)) => *subtree_depth as usize,
+            None => bail!("Sparse Merkle Tree multi-proof has too few subtrees."),
+        };
+        if subtree_depth == depth {
+            let (_, leaf) = subtrees.next().expect("Must exist.");
+            for (key, value) in elements {
+                Self::verify_subtree(*key, *value, depth, *leaf)?;
+            }
+            return Ok(leaf.map_or(*SPARSE_MERKLE_PLACEHOLDER_HASH, |leaf| leaf.hash()));
+        }
+        ensure!(
+            subtree_depth > depth && depth < HashValue::LENGTH_IN_BITS,
+            "Sparse Merkle Tree multi-proof has a subtree at an unexpected depth ({}).",
+            subtree_depth,
+        );
+
+        let split = elements
+            .iter()
+            .position(|(key, _)| key.bit(depth))
+            .unwrap_or_else(|| elements.len());
+        let (left, right) = elements.split_at(split);
+        let (left_hash, right_hash) = if left.is_empty() || right.is_empty() {
+            let sibling = *siblings.next().ok_or_else(|| {
+                format_err!("Sparse Merkle Tree multi-proof has too few siblings.")
+            })?;
+            if left.is_empty() {
+                (
+                    sibling,
+                    Self::compute_root_hash(right, depth + 1, subtrees, siblings)?,
+                )
+            } else {
+                (
+                    Self::compute_root_hash(left, depth + 1, subtrees, siblings)?,
+                    sibling,
+                )
+            }
+        } else {
+            (
+                Self::compute_root_hash(left, depth + 1, subtrees, siblings)?,
+                Self::compute_root_hash(right, depth + 1, subtrees, siblings)?,
+            )
+        };
+        Ok(SparseMerkleInternalNode::new(left_hash, right_hash).hash())
+    }
+
+    /// Verifies a single element against the subtree at `depth` it ends up in.
+    fn verify_subtree(
+        key: HashValue,
+        value: Option<&V>,
+        depth: usize,
+        leaf: Option<SparseMerkleLeafNode>,
+    ) -> Result<()> {
+        match (value, leaf) {
+            (Some(value), Some(leaf)) => {
+                ensure!(
+                    key == leaf.key,
+                    "Keys do not match. Key in proof: {:x}. Expected key: {:x}.",
+                    leaf.key,
+                    key
+                );
+                let hash = value.hash();
+                ensure!(
+                    hash == leaf.value_hash,
+                    "Value hashes do not match. Value hash in proof: {:x}. \
+                     Expected value hash: {:x}",
+                    leaf.value_hash,
+                    hash,
+                );
+            }
+            (Some(_value), None) => bail!(
+                "Expected inclusion proof of key {:x}. Found non-inclusion proof.",
+                key
+            ),
+            (None, Some(leaf)) => {
+                ensure!(
+                    key != leaf.key,
+                    "Expected non-inclusion proof of key {:x}, but key exists in proof.",
+                    key
+                );
+                ensure!(
+                    key.common_prefix_bits_len(leaf.key) >= depth,
+                    "Key {:x} would not have ended up in the subtree where the provided key in \
+                     proof is the only existing key, if it existed. So this is not a valid \
+                     non-inclusion proof.",
+                    key
+                );
+            }
+            (None, None) => (),
+        }
+        Ok(())
+    }
+}
+
 /// A proof that can be used to show that two Merkle accumulators are consistent -- the big one can
 /// be obtained by appending certain leaves to the small one. For example, at some point in time a
 /// client knows that the root hash of the ledger at version 10 is `old_root` (it could be a
@@ -619,6 +867,64 @@ impl AccountStateProof {
     }
 }
 
+/// The complete proof used to authenticate the states of a set of accounts. This structure
+/// consists of the `AccumulatorProof` from `LedgerInfo` to `TransactionInfo`, the
+/// `TransactionInfo` object and the `SparseMerkleMultiProof` from state root to the accounts.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStatesProof {
+    transaction_info_with_proof: TransactionInfoWithProof,
+
+    /// The sparse merkle multi-proof from state root to the account states.
+    transaction_info_to_accounts_proof: SparseMerkleMultiProof<AccountStateBlob>,
+}
+
+impl AccountStatesProof {
+    /// Constructs a new `AccountStatesProof` using given `transaction_info_with_proof` and
+    /// `transaction_info_to_accounts_proof`.
+    pub fn new(
+        transaction_info_with_proof: TransactionInfoWithProof,
+        transaction_info_to_accounts_proof: SparseMerkleMultiProof<AccountStateBlob>,
+    ) -> Self {
+        AccountStatesProof {
+            transaction_info_with_proof,
+            transaction_info_to_accounts_proof,
+        }
+    }
+
+    /// Returns the `transaction_info_with_proof` object in this proof.
+    pub fn transaction_info_with_proof(&self) -> &TransactionInfoWithProof {
+        &self.transaction_info_with_proof
+    }
+
+    /// Returns the `transaction_info_to_accounts_proof` object in this proof.
+    pub fn transaction_info_to_accounts_proof(&self) -> &SparseMerkleMultiProof<AccountStateBlob> {
+        &self.transaction_info_to_accounts_proof
+    }
+
+    /// Verifies that the states of a set of accounts at version `state_version` are correct using
+    /// the provided proof. Each account is given by the hash of its address and, if we expect it
+    /// to exist, its account state blob.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        state_version: Version,
+        accounts: &[(HashValue, Option<&AccountStateBlob>)],
+    ) -> Result<()> {
+        self.transaction_info_to_accounts_proof.verify(
+            self.transaction_info_with_proof
+                .transaction_info
+                .state_root_hash(),
+            accounts,
+        )?;
+
+        self.transaction_info_with_proof
+            .verify(ledger_info, state_version)?;
+
+        Ok(())
+    }
+}
+
 /// The complete proof used to authenticate a contract event. This structure consists of the
 /// `AccumulatorProof` from `LedgerInfo` to `TransactionInfo`, the `TransactionInfo` object and the
 /// `AccumulatorProof` from event accumulator root to the event.
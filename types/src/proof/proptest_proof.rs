@@ -6,7 +6,8 @@
 
 use crate::proof::{
     definition::MAX_ACCUMULATOR_PROOF_DEPTH, AccumulatorConsistencyProof, AccumulatorProof,
-    AccumulatorRangeProof, SparseMerkleLeafNode, SparseMerkleProof, SparseMerkleRangeProof,
+    AccumulatorRangeProof, SparseMerkleLeafNode, SparseMerkleMultiProof, SparseMerkleProof,
+    SparseMerkleRangeProof,
 };
 use diem_crypto::{
     hash::{
@@ -102,6 +103,23 @@ where
     }
 }
 
+impl<V> Arbitrary for SparseMerkleMultiProof<V>
+where
+    V: std::fmt::Debug + CryptoHash,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        (
+            vec((0..=256u16, any::<Option<SparseMerkleLeafNode>>()), 1..10),
+            vec(arb_sparse_merkle_sibling(), 0..256),
+        )
+            .prop_map(|(subtrees, siblings)| SparseMerkleMultiProof::new(subtrees, siblings))
+            .boxed()
+    }
+}
+
 impl Arbitrary for AccumulatorConsistencyProof {
     type Parameters = ();
     type Strategy = BoxedStrategy<Self>;
@@ -26,6 +26,7 @@ use diem_crypto::{
 };
 
 type SparseMerkleProof = crate::proof::SparseMerkleProof<AccountStateBlob>;
+type SparseMerkleMultiProof = crate::proof::SparseMerkleMultiProof<AccountStateBlob>;
 
 #[test]
 fn test_verify_empty_accumulator() {
@@ -246,6 +247,129 @@ fn test_verify_three_element_sparse_merkle() {
     }
 }
 
+#[test]
+fn test_verify_sparse_merkle_multi_proof() {
+    // The same tree as in `test_verify_three_element_sparse_merkle`.
+    let key1 = b"hello".test_only_hash();
+    let key2 = b"world".test_only_hash();
+    let key3 = b"!".test_only_hash();
+    let non_existing_key1 = b"abc".test_only_hash();
+    let non_existing_key2 = b"def".test_only_hash();
+
+    let blob1 = AccountStateBlob::from(b"1".to_vec());
+    let blob2 = AccountStateBlob::from(b"2".to_vec());
+    let blob3 = AccountStateBlob::from(b"3".to_vec());
+
+    let leaf1 = SparseMerkleLeafNode::new(key1, blob1.hash());
+    let leaf2 = SparseMerkleLeafNode::new(key2, blob2.hash());
+    let leaf3 = SparseMerkleLeafNode::new(key3, blob3.hash());
+    let internal_b_hash = SparseMerkleInternalNode::new(leaf2.hash(), leaf3.hash()).hash();
+    let internal_a_hash = SparseMerkleInternalNode::new(leaf1.hash(), internal_b_hash).hash();
+    let root_hash =
+        SparseMerkleInternalNode::new(internal_a_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH).hash();
+
+    let proof1 = SparseMerkleProof::new(
+        Some(leaf1),
+        vec![internal_b_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+    );
+    let proof2 = SparseMerkleProof::new(
+        Some(leaf2),
+        vec![leaf3.hash(), leaf1.hash(), *SPARSE_MERKLE_PLACEHOLDER_HASH],
+    );
+    let proof3 = SparseMerkleProof::new(
+        Some(leaf3),
+        vec![leaf2.hash(), leaf1.hash(), *SPARSE_MERKLE_PLACEHOLDER_HASH],
+    );
+    let non_existing_proof2 = SparseMerkleProof::new(None, vec![internal_a_hash]);
+
+    {
+        // Every sibling can be computed from the other elements.
+        let proof = SparseMerkleMultiProof::from_proofs(vec![
+            (non_existing_key2, non_existing_proof2.clone()),
+            (key3, proof3),
+            (key1, proof1.clone()),
+            (non_existing_key1, proof1.clone()),
+            (key2, proof2.clone()),
+            (key1, proof1.clone()),
+        ])
+        .unwrap();
+        assert_eq!(
+            proof.subtrees(),
+            &[
+                (2, Some(leaf1)),
+                (3, Some(leaf2)),
+                (3, Some(leaf3)),
+                (1, None)
+            ]
+        );
+        assert!(proof.siblings().is_empty());
+
+        let elements = [
+            (key1, Some(&blob1)),
+            (key2, Some(&blob2)),
+            (key3, Some(&blob3)),
+            (non_existing_key1, None),
+            (non_existing_key2, None),
+        ];
+        assert!(proof.verify(root_hash, &elements).is_ok());
+        // The order of the elements does not matter, and neither does repeating one.
+        let mut reordered = elements.to_vec();
+        reordered.reverse();
+        reordered.push((key2, Some(&blob2)));
+        assert!(proof.verify(root_hash, &reordered).is_ok());
+        // Trying to show that a key has two values.
+        reordered.push((key2, Some(&blob3)));
+        assert!(proof.verify(root_hash, &reordered).is_err());
+        // Trying to show that a key has another value.
+        let mut tampered = elements;
+        tampered[1] = (key2, Some(&blob1));
+        assert!(proof.verify(root_hash, &tampered).is_err());
+        // Trying to show that a key doesn't exist.
+        tampered[1] = (key2, None);
+        assert!(proof.verify(root_hash, &tampered).is_err());
+        // The proof can't be used for a subset of the elements.
+        assert!(proof.verify(root_hash, &elements[..4]).is_err());
+        assert!(proof.verify(root_hash, &[]).is_err());
+        // Trying to verify against another root.
+        assert!(proof.verify(internal_a_hash, &elements).is_err());
+    }
+
+    {
+        // The siblings shared by both keys are only included once.
+        let proof = SparseMerkleMultiProof::from_proofs(vec![
+            (key2, proof2),
+            (non_existing_key2, non_existing_proof2),
+        ])
+        .unwrap();
+        assert_eq!(proof.subtrees(), &[(3, Some(leaf2)), (1, None)]);
+        assert_eq!(proof.siblings(), &[leaf1.hash(), leaf3.hash()]);
+        assert!(proof
+            .verify(
+                root_hash,
+                &[(key2, Some(&blob2)), (non_existing_key2, None)]
+            )
+            .is_ok());
+        // This proof can't be used to show that non_existing_key1 doesn't exist.
+        assert!(proof
+            .verify(
+                root_hash,
+                &[(non_existing_key1, None), (non_existing_key2, None)]
+            )
+            .is_err());
+    }
+
+    // Proofs of keys ending up in the same subtree must match.
+    assert!(SparseMerkleMultiProof::from_proofs(vec![
+        (key1, proof1),
+        (
+            non_existing_key1,
+            SparseMerkleProof::new(None, vec![internal_b_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH]),
+        ),
+    ])
+    .is_err());
+    assert!(SparseMerkleMultiProof::from_proofs(vec![]).is_err());
+}
+
 #[test]
 fn test_verify_transaction() {
     //            root
@@ -332,6 +332,16 @@ impl RawTransaction {
     pub fn sender(&self) -> AccountAddress {
         self.sender
     }
+
+    /// Return the sequence number of this transaction.
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    /// Return the payload of this transaction.
+    pub fn payload(&self) -> &TransactionPayload {
+        &self.payload
+    }
 }
 
 /// Different kinds of transactions.
@@ -7,13 +7,22 @@ use crate::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_config::XUS_NAME,
+    block_info::BlockInfo,
     chain_id::ChainId,
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     transaction::{
         ChangeSet, RawTransaction, Script, TransactionArgument, TransactionPayload, WriteSetPayload,
     },
+    validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
 use bcs::to_bytes;
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    HashValue,
+};
+use std::{collections::BTreeMap, convert::TryFrom};
 
 #[test]
 fn test_access_path_canonical_serialization_example() {
@@ -262,3 +271,103 @@ fn get_common_write_set() -> WriteSet {
     .freeze()
     .unwrap()
 }
+
+#[test]
+fn test_epoch_state_canonical_serialization_example() {
+    let input = get_common_epoch_state();
+
+    let expected_output: Vec<u8> = vec![
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xCA, 0x82, 0x0B, 0xF9, 0x30, 0x5E,
+        0xB9, 0x7D, 0x0D, 0x78, 0x4F, 0x71, 0xB3, 0x95, 0x54, 0x57, 0x20, 0xD7, 0x5A, 0x98, 0x01,
+        0x82, 0xB1, 0x0A, 0xB7, 0xD5, 0x4B, 0xFE, 0xD3, 0xC9, 0x64, 0x07, 0x3A, 0x0E, 0xE1, 0x72,
+        0xF3, 0xDA, 0xA6, 0x23, 0x25, 0xAF, 0x02, 0x1A, 0x68, 0xF7, 0x07, 0x51, 0x1A, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    let actual_output = to_bytes(&input).unwrap();
+    assert_eq!(expected_output, actual_output);
+}
+
+#[test]
+fn test_ledger_info_with_signatures_canonical_serialization_example() {
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(
+            1,
+            10,
+            HashValue::new([0x01; HashValue::LENGTH]),
+            HashValue::new([0x02; HashValue::LENGTH]),
+            100,
+            1_000_000,
+            Some(get_common_epoch_state()),
+        ),
+        HashValue::new([0x03; HashValue::LENGTH]),
+    );
+    let mut signatures = BTreeMap::new();
+    signatures.insert(get_common_validator_address(), get_common_signature());
+    let input = LedgerInfoWithSignatures::new(ledger_info, signatures);
+
+    let expected_output: Vec<u8> = vec![
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x20, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x20, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02,
+        0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02,
+        0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x40, 0x42, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x01, 0xCA, 0x82, 0x0B, 0xF9, 0x30, 0x5E, 0xB9, 0x7D, 0x0D, 0x78, 0x4F,
+        0x71, 0xB3, 0x95, 0x54, 0x57, 0x20, 0xD7, 0x5A, 0x98, 0x01, 0x82, 0xB1, 0x0A, 0xB7, 0xD5,
+        0x4B, 0xFE, 0xD3, 0xC9, 0x64, 0x07, 0x3A, 0x0E, 0xE1, 0x72, 0xF3, 0xDA, 0xA6, 0x23, 0x25,
+        0xAF, 0x02, 0x1A, 0x68, 0xF7, 0x07, 0x51, 0x1A, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x20, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03,
+        0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03,
+        0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x01, 0xCA, 0x82, 0x0B, 0xF9, 0x30, 0x5E, 0xB9, 0x7D,
+        0x0D, 0x78, 0x4F, 0x71, 0xB3, 0x95, 0x54, 0x57, 0x40, 0xE5, 0x56, 0x43, 0x00, 0xC3, 0x60,
+        0xAC, 0x72, 0x90, 0x86, 0xE2, 0xCC, 0x80, 0x6E, 0x82, 0x8A, 0x84, 0x87, 0x7F, 0x1E, 0xB8,
+        0xE5, 0xD9, 0x74, 0xD8, 0x73, 0xE0, 0x65, 0x22, 0x49, 0x01, 0x55, 0x5F, 0xB8, 0x82, 0x15,
+        0x90, 0xA3, 0x3B, 0xAC, 0xC6, 0x1E, 0x39, 0x70, 0x1C, 0xF9, 0xB4, 0x6B, 0xD2, 0x5B, 0xF5,
+        0xF0, 0x59, 0x5B, 0xBE, 0x24, 0x65, 0x51, 0x41, 0x43, 0x8E, 0x7A, 0x10, 0x0B,
+    ];
+
+    let actual_output = to_bytes(&input).unwrap();
+    assert_eq!(expected_output, actual_output);
+}
+
+fn get_common_validator_address() -> AccountAddress {
+    AccountAddress::new([
+        0xca, 0x82, 0x0b, 0xf9, 0x30, 0x5e, 0xb9, 0x7d, 0x0d, 0x78, 0x4f, 0x71, 0xb3, 0x95, 0x54,
+        0x57,
+    ])
+}
+
+fn get_common_epoch_state() -> EpochState {
+    // The public key of the first test vector of RFC 8032
+    let public_key =
+        Ed25519PublicKey::try_from(
+            &hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap()[..],
+        )
+        .unwrap();
+    let mut address_to_validator_info = BTreeMap::new();
+    address_to_validator_info.insert(
+        get_common_validator_address(),
+        ValidatorConsensusInfo::new(public_key, 1 /* voting_power */),
+    );
+    EpochState {
+        epoch: 2,
+        verifier: ValidatorVerifier::new(address_to_validator_info),
+    }
+}
+
+fn get_common_signature() -> Ed25519Signature {
+    // The signature of the first test vector of RFC 8032
+    Ed25519Signature::try_from(
+        &hex::decode(
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+             5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        )
+        .unwrap()[..],
+    )
+    .unwrap()
+}
@@ -13,12 +13,15 @@ use crate::{
     validator_verifier::{random_validator_verifier, ValidatorConsensusInfo, ValidatorVerifier},
     waypoint::Waypoint,
 };
-use diem_crypto::{ed25519::Ed25519Signature, hash::HashValue};
+use diem_crypto::{
+    bls12381::BLS12381PrivateKey, ed25519::Ed25519Signature, hash::HashValue, Uniform,
+};
 use proptest::{
     collection::{size_range, vec, SizeRange},
     prelude::*,
     sample::Index,
 };
+use rand::{rngs::StdRng, SeedableRng};
 use std::{collections::BTreeMap, convert::TryFrom};
 
 // hack strategy to generate a length from `impl Into<SizeRange>`
@@ -510,3 +513,129 @@ proptest! {
             .expect_err("Expected stale change, got valid change");
     }
 }
+
+/// Like `into_epoch_state`, with the BLS keys of the signers.
+fn into_bls_epoch_state(epoch: u64, signers: &[ValidatorSigner]) -> EpochState {
+    EpochState {
+        epoch,
+        verifier: ValidatorVerifier::new(
+            signers
+                .iter()
+                .map(|signer| {
+                    (
+                        signer.author(),
+                        ValidatorConsensusInfo::new(signer.public_key(), 1 /* voting power */)
+                            .with_bls_public_key(
+                                signer.bls_public_key().unwrap(),
+                                &signer.bls_proof_of_possession().unwrap(),
+                            )
+                            .unwrap(),
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Create an aggregate signature of all signers on the `LedgerInfo`.
+fn aggregate_sign_ledger_info(
+    epoch_state: &EpochState,
+    signers: &[ValidatorSigner],
+    ledger_info: LedgerInfo,
+) -> LedgerInfoWithSignatures {
+    let signatures = signers
+        .iter()
+        .map(|s| (s.author(), s.sign_bls(&ledger_info).unwrap()))
+        .collect();
+    let signature = epoch_state
+        .verifier
+        .aggregate_signatures(&signatures)
+        .unwrap();
+    LedgerInfoWithSignatures::new_aggregated(ledger_info, signature)
+}
+
+#[test]
+fn test_ratchet_with_aggregate_signatures() {
+    let vsets: Vec<Vec<ValidatorSigner>> = (0..3u8)
+        .map(|epoch| {
+            (0..4u8)
+                .map(|i| {
+                    let seed = [epoch * 4 + i; 32];
+                    ValidatorSigner::random(seed).with_bls_private_key(
+                        BLS12381PrivateKey::generate(&mut StdRng::from_seed(seed)),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    let epoch_states: Vec<_> = vsets
+        .iter()
+        .enumerate()
+        .map(|(epoch, signers)| into_bls_epoch_state(epoch as u64 + 1, signers))
+        .collect();
+
+    // Epoch 1 is trusted, epochs 1 and 2 end with an aggregate signed epoch change
+    let trusted_state =
+        TrustedState::try_from(&new_mock_ledger_info(0, 0, Some(epoch_states[0].clone()))).unwrap();
+    let epoch_change_lis: Vec<_> = (0..2)
+        .map(|i| {
+            aggregate_sign_ledger_info(
+                &epoch_states[i],
+                &vsets[i],
+                new_mock_ledger_info(
+                    i as u64 + 1,
+                    i as u64 + 1,
+                    Some(epoch_states[i + 1].clone()),
+                ),
+            )
+        })
+        .collect();
+    let latest_li = aggregate_sign_ledger_info(
+        &epoch_states[2],
+        &vsets[2],
+        new_mock_ledger_info(3, 5, None),
+    );
+
+    // The BLS keys of the next epochs are part of the serialized epoch change proof
+    let change_proof = EpochChangeProof::new(epoch_change_lis.clone(), false /* more */);
+    let change_proof: EpochChangeProof =
+        bcs::from_bytes(&bcs::to_bytes(&change_proof).unwrap()).unwrap();
+    match trusted_state
+        .verify_and_ratchet(&latest_li, &change_proof)
+        .unwrap()
+    {
+        TrustedStateChange::Epoch {
+            new_state,
+            latest_epoch_change_li,
+        } => {
+            assert_eq!(new_state.latest_version(), 5);
+            assert_eq!(latest_epoch_change_li, &epoch_change_lis[1]);
+        }
+        _ => panic!("Unexpected ratchet result"),
+    };
+
+    // A ledger info signed by the validators of the wrong epoch is rejected
+    let bad_li = aggregate_sign_ledger_info(
+        &epoch_states[1],
+        &vsets[1],
+        new_mock_ledger_info(3, 5, None),
+    );
+    trusted_state
+        .verify_and_ratchet(&bad_li, &change_proof)
+        .expect_err("Should reject a ledger info signed by the previous epoch");
+
+    // An aggregate signature without a quorum of signers is rejected
+    let ledger_info = new_mock_ledger_info(3, 5, None);
+    let signatures = vsets[2][..2]
+        .iter()
+        .map(|s| (s.author(), s.sign_bls(&ledger_info).unwrap()))
+        .collect();
+    let signature = epoch_states[2]
+        .verifier
+        .aggregate_signatures(&signatures)
+        .unwrap();
+    let bad_li = LedgerInfoWithSignatures::new_aggregated(ledger_info, signature);
+    trusted_state
+        .verify_and_ratchet(&bad_li, &change_proof)
+        .expect_err("Should reject an aggregate signature without quorum");
+}
@@ -3,6 +3,9 @@
 
 use crate::account_address::AccountAddress;
 use diem_crypto::{
+    bls12381::{
+        BLS12381PrivateKey, BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature,
+    },
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
     test_utils::TEST_SEED,
@@ -20,6 +23,7 @@ use std::convert::TryFrom;
 pub struct ValidatorSigner {
     author: AccountAddress,
     private_key: Ed25519PrivateKey,
+    bls_private_key: Option<BLS12381PrivateKey>,
 }
 
 impl ValidatorSigner {
@@ -27,14 +31,28 @@ impl ValidatorSigner {
         ValidatorSigner {
             author,
             private_key,
+            bls_private_key: None,
         }
     }
 
+    /// Adds a BLS key, which signs messages whose signatures are aggregated.
+    pub fn with_bls_private_key(mut self, bls_private_key: BLS12381PrivateKey) -> Self {
+        self.bls_private_key = Some(bls_private_key);
+        self
+    }
+
     /// Constructs a signature for `message` using `private_key`.
     pub fn sign<T: Serialize + CryptoHash>(&self, message: &T) -> Ed25519Signature {
         self.private_key.sign(message)
     }
 
+    /// Constructs a BLS signature for `message` if this signer has a BLS key.
+    pub fn sign_bls<T: Serialize + CryptoHash>(&self, message: &T) -> Option<BLS12381Signature> {
+        self.bls_private_key
+            .as_ref()
+            .map(|bls_private_key| bls_private_key.sign(message))
+    }
+
     /// Returns the author associated with this signer.
     pub fn author(&self) -> AccountAddress {
         self.author
@@ -45,6 +63,18 @@ impl ValidatorSigner {
         self.private_key.public_key()
     }
 
+    /// Returns the BLS public key associated with this signer, if it has one.
+    pub fn bls_public_key(&self) -> Option<BLS12381PublicKey> {
+        self.bls_private_key.as_ref().map(|key| key.public_key())
+    }
+
+    /// Returns a proof of possession of the BLS key of this signer, if it has one.
+    pub fn bls_proof_of_possession(&self) -> Option<BLS12381ProofOfPossession> {
+        self.bls_private_key
+            .as_ref()
+            .map(|key| key.create_proof_of_possession())
+    }
+
     /// Returns the private key associated with this signer. Only available for testing purposes.
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn private_key(&self) -> &Ed25519PrivateKey {
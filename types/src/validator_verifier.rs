@@ -1,14 +1,20 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{account_address::AccountAddress, on_chain_config::ValidatorSet};
+use crate::{
+    account_address::AccountAddress,
+    aggregate_signature::AggregateSignature,
+    on_chain_config::{OnChainConsensusConfig, ValidatorSet},
+};
+use diem_bitvec::BitVec;
 use diem_crypto::{
+    bls12381::{BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature},
     ed25519::{Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
     Signature, VerifyingKey,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt};
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
 use thiserror::Error;
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -43,6 +49,16 @@ pub enum VerifyError {
     #[error("Signature is invalid")]
     /// The signature does not match the hash.
     InvalidSignature,
+    #[error("Author has no BLS public key")]
+    /// The author of a BLS signature has not registered a BLS public key.
+    MissingBlsPublicKey,
+    #[error("Proof of possession is invalid")]
+    /// The proof of possession does not match the BLS public key.
+    InvalidProofOfPossession,
+    #[error("Validator bitmask is invalid")]
+    /// The bitmask of an aggregate signature refers to validators beyond the validator set, or
+    /// the validator set is too large to be represented by a bitmask.
+    InvalidBitmask,
 }
 
 /// Helper struct to manage validator information for validation
@@ -51,6 +67,9 @@ pub enum VerifyError {
 pub struct ValidatorConsensusInfo {
     public_key: Ed25519PublicKey,
     voting_power: u64,
+    /// The key for aggregate signatures, only set once its proof of possession has been verified.
+    #[cfg_attr(any(test, feature = "fuzzing"), proptest(value = "None"))]
+    bls_public_key: Option<BLS12381PublicKey>,
 }
 
 impl ValidatorConsensusInfo {
@@ -58,8 +77,23 @@ impl ValidatorConsensusInfo {
         ValidatorConsensusInfo {
             public_key,
             voting_power,
+            bls_public_key: None,
         }
     }
+
+    /// Adds the key for aggregate signatures. The proof of possession is required since aggregate
+    /// verification is only sound for keys whose owner knows the private key.
+    pub fn with_bls_public_key(
+        mut self,
+        bls_public_key: BLS12381PublicKey,
+        proof_of_possession: &BLS12381ProofOfPossession,
+    ) -> std::result::Result<Self, VerifyError> {
+        proof_of_possession
+            .verify(&bls_public_key)
+            .map_err(|_| VerifyError::InvalidProofOfPossession)?;
+        self.bls_public_key = Some(bls_public_key);
+        Ok(self)
+    }
 }
 
 /// Supports validation of signatures for known authors with individual voting powers. This struct
@@ -202,6 +236,102 @@ impl ValidatorVerifier {
         Ok(())
     }
 
+    /// Verify the correctness of a BLS signature of a message by a known author.
+    pub fn verify_bls<T: Serialize + CryptoHash>(
+        &self,
+        author: AccountAddress,
+        message: &T,
+        signature: &BLS12381Signature,
+    ) -> std::result::Result<(), VerifyError> {
+        let validator_info = self
+            .address_to_validator_info
+            .get(&author)
+            .ok_or(VerifyError::UnknownAuthor)?;
+        let public_key = validator_info
+            .bls_public_key
+            .as_ref()
+            .ok_or(VerifyError::MissingBlsPublicKey)?;
+        signature
+            .verify(message, public_key)
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
+
+    /// Returns true if every validator has a BLS public key, which is required to verify
+    /// aggregate signatures of arbitrary quorums.
+    pub fn supports_aggregate_signatures(&self) -> bool {
+        !self.is_empty()
+            && self.len() <= usize::from(u8::MAX) + 1
+            && self
+                .address_to_validator_info
+                .values()
+                .all(|validator_info| validator_info.bls_public_key.is_some())
+    }
+
+    /// Aggregates the BLS signatures of known authors on the same message. The individual
+    /// signatures are expected to have been verified already.
+    pub fn aggregate_signatures(
+        &self,
+        signatures: &BTreeMap<AccountAddress, BLS12381Signature>,
+    ) -> std::result::Result<AggregateSignature, VerifyError> {
+        let mut validator_bitmask = BitVec::default();
+        for author in signatures.keys() {
+            let index = self
+                .address_to_validator_info
+                .keys()
+                .position(|address| address == author)
+                .ok_or(VerifyError::UnknownAuthor)?;
+            let index = u8::try_from(index).map_err(|_| VerifyError::InvalidBitmask)?;
+            validator_bitmask.set(index);
+        }
+        let signatures: Vec<_> = signatures.values().collect();
+        let signature =
+            BLS12381Signature::aggregate(&signatures).map_err(|_| VerifyError::InvalidSignature)?;
+        Ok(AggregateSignature::new(validator_bitmask, signature))
+    }
+
+    /// Returns the authors of an aggregate signature, in the order of the validator set.
+    pub fn get_signers(
+        &self,
+        validator_bitmask: &BitVec,
+    ) -> std::result::Result<Vec<AccountAddress>, VerifyError> {
+        if let Some(last_set_bit) = validator_bitmask.last_set_bit() {
+            if usize::from(last_set_bit) >= self.len() {
+                return Err(VerifyError::InvalidBitmask);
+            }
+        }
+        Ok(self
+            .get_ordered_account_addresses_iter()
+            .take(usize::from(u8::MAX) + 1)
+            .enumerate()
+            .filter(|(index, _)| validator_bitmask.is_set(*index as u8))
+            .map(|(_, address)| address)
+            .collect())
+    }
+
+    /// This function will successfully return when the authors of an aggregate signature have at
+    /// least quorum voting power and the signature matches the aggregate of their BLS public keys.
+    pub fn verify_aggregate_signature<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        aggregate_signature: &AggregateSignature,
+    ) -> std::result::Result<(), VerifyError> {
+        let signers = self.get_signers(aggregate_signature.validator_bitmask())?;
+        self.check_voting_power(signers.iter())?;
+        let public_keys = signers
+            .iter()
+            .map(|author| {
+                self.address_to_validator_info
+                    .get(author)
+                    .and_then(|validator_info| validator_info.bls_public_key.as_ref())
+                    .ok_or(VerifyError::MissingBlsPublicKey)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        aggregate_signature
+            .signature()
+            .verify_aggregate(message, &public_keys)
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
+
     /// Ensure there are not more than the maximum expected signatures (all possible signatures).
     fn check_num_of_signatures(
         &self,
@@ -248,6 +378,13 @@ impl ValidatorVerifier {
             .map(|validator_info| validator_info.public_key.clone())
     }
 
+    /// Returns the BLS public key for this address, if it registered one.
+    pub fn get_bls_public_key(&self, author: &AccountAddress) -> Option<BLS12381PublicKey> {
+        self.address_to_validator_info
+            .get(&author)
+            .and_then(|validator_info| validator_info.bls_public_key.clone())
+    }
+
     /// Returns the voting power for this address.
     pub fn get_voting_power(&self, author: &AccountAddress) -> Option<u64> {
         self.address_to_validator_info
@@ -316,6 +453,29 @@ impl From<&ValidatorSet> for ValidatorVerifier {
     }
 }
 
+impl ValidatorVerifier {
+    /// Builds the verifier of a validator set with the BLS keys registered in the consensus
+    /// config. Keys of validators outside of the set or with an invalid proof of possession are
+    /// left out, so these validators can't take part in aggregate signatures.
+    pub fn from_validator_set_with_bls_keys(
+        validator_set: &ValidatorSet,
+        consensus_config: &OnChainConsensusConfig,
+    ) -> Self {
+        let mut verifier = ValidatorVerifier::from(validator_set);
+        for (author, bls_key) in consensus_config.validator_bls_keys() {
+            if let Some(validator_info) = verifier.address_to_validator_info.get_mut(author) {
+                if let Ok(with_bls_key) = validator_info
+                    .clone()
+                    .with_bls_public_key(bls_key.public_key.clone(), &bls_key.proof_of_possession)
+                {
+                    *validator_info = with_bls_key;
+                }
+            }
+        }
+        verifier
+    }
+}
+
 #[cfg(any(test, feature = "fuzzing"))]
 impl From<&ValidatorVerifier> for ValidatorSet {
     fn from(verifier: &ValidatorVerifier) -> Self {
@@ -377,7 +537,12 @@ pub fn random_validator_verifier(
 mod tests {
     use super::*;
     use crate::validator_signer::ValidatorSigner;
-    use diem_crypto::test_utils::{TestDiemCrypto, TEST_SEED};
+    use diem_crypto::{
+        bls12381::BLS12381PrivateKey,
+        test_utils::{TestDiemCrypto, TEST_SEED},
+        Uniform,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
     use std::collections::BTreeMap;
 
     #[test]
@@ -654,4 +819,145 @@ mod tests {
             Err(VerifyError::UnknownAuthor)
         );
     }
+
+    #[test]
+    fn test_aggregate_signatures() {
+        const NUM_SIGNERS: u8 = 4;
+        let validator_signers: Vec<ValidatorSigner> = (0..NUM_SIGNERS)
+            .map(|i| {
+                let mut rng = StdRng::from_seed([i; 32]);
+                ValidatorSigner::random([i; 32])
+                    .with_bls_private_key(BLS12381PrivateKey::generate(&mut rng))
+            })
+            .collect();
+        let dummy_struct = TestDiemCrypto("Hello, World".to_string());
+        let author_to_validator_info: BTreeMap<_, _> = validator_signers
+            .iter()
+            .map(|signer| {
+                (
+                    signer.author(),
+                    ValidatorConsensusInfo::new(signer.public_key(), 1)
+                        .with_bls_public_key(
+                            signer.bls_public_key().unwrap(),
+                            &signer.bls_proof_of_possession().unwrap(),
+                        )
+                        .unwrap(),
+                )
+            })
+            .collect();
+        let validator_verifier = ValidatorVerifier::new(author_to_validator_info.clone());
+        assert!(validator_verifier.supports_aggregate_signatures());
+
+        // BLS keys are serialized, so deserialized verifiers can check aggregate signatures
+        let deserialized: ValidatorVerifier =
+            bcs::from_bytes(&bcs::to_bytes(&validator_verifier).unwrap()).unwrap();
+        assert_eq!(deserialized, validator_verifier);
+
+        // A BLS key is only accepted with its own proof of possession
+        let signer = &validator_signers[0];
+        assert_eq!(
+            ValidatorConsensusInfo::new(signer.public_key(), 1).with_bls_public_key(
+                signer.bls_public_key().unwrap(),
+                &validator_signers[1].bls_proof_of_possession().unwrap(),
+            ),
+            Err(VerifyError::InvalidProofOfPossession)
+        );
+
+        let sign = |signers: &[ValidatorSigner]| -> BTreeMap<_, _> {
+            signers
+                .iter()
+                .map(|signer| (signer.author(), signer.sign_bls(&dummy_struct).unwrap()))
+                .collect()
+        };
+
+        // Each signature can be verified individually
+        for (author, signature) in sign(&validator_signers) {
+            assert_eq!(
+                validator_verifier.verify_bls(author, &dummy_struct, &signature),
+                Ok(())
+            );
+        }
+
+        // A quorum of 3 signers verifies and the signers are recovered from the bitmask
+        let aggregate = validator_verifier
+            .aggregate_signatures(&sign(&validator_signers[1..]))
+            .unwrap();
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(&dummy_struct, &aggregate),
+            Ok(())
+        );
+        let mut expected_signers: Vec<_> = validator_signers[1..]
+            .iter()
+            .map(|signer| signer.author())
+            .collect();
+        expected_signers.sort();
+        assert_eq!(
+            validator_verifier.get_signers(aggregate.validator_bitmask()),
+            Ok(expected_signers)
+        );
+
+        // Another message fails
+        let other_struct = TestDiemCrypto("Hello, Diem".to_string());
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(&other_struct, &aggregate),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // Claiming a signer who did not sign fails
+        let mut validator_bitmask = aggregate.validator_bitmask().clone();
+        for i in 0..NUM_SIGNERS {
+            validator_bitmask.set(i);
+        }
+        let forged = AggregateSignature::new(validator_bitmask, aggregate.signature().clone());
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(&dummy_struct, &forged),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // Less than a quorum fails
+        let aggregate = validator_verifier
+            .aggregate_signatures(&sign(&validator_signers[2..]))
+            .unwrap();
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(&dummy_struct, &aggregate),
+            Err(VerifyError::TooLittleVotingPower {
+                voting_power: 2,
+                quorum_voting_power: 3
+            })
+        );
+
+        // Signers beyond the validator set fail
+        let mut validator_bitmask = BitVec::default();
+        validator_bitmask.set(NUM_SIGNERS);
+        let forged = AggregateSignature::new(validator_bitmask, aggregate.signature().clone());
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(&dummy_struct, &forged),
+            Err(VerifyError::InvalidBitmask)
+        );
+
+        // Unknown signers cannot be aggregated
+        let unknown_signer = ValidatorSigner::random([NUM_SIGNERS; 32])
+            .with_bls_private_key(BLS12381PrivateKey::generate_for_testing());
+        assert_eq!(
+            validator_verifier.aggregate_signatures(&sign(std::slice::from_ref(&unknown_signer))),
+            Err(VerifyError::UnknownAuthor)
+        );
+
+        // Validators without a BLS key cannot take part in aggregate signatures
+        let mut author_to_validator_info = author_to_validator_info;
+        let signer = &validator_signers[0];
+        author_to_validator_info.insert(
+            signer.author(),
+            ValidatorConsensusInfo::new(signer.public_key(), 1),
+        );
+        let validator_verifier = ValidatorVerifier::new(author_to_validator_info);
+        assert!(!validator_verifier.supports_aggregate_signatures());
+        let aggregate = validator_verifier
+            .aggregate_signatures(&sign(&validator_signers))
+            .unwrap();
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(&dummy_struct, &aggregate),
+            Err(VerifyError::MissingBlsPublicKey)
+        );
+    }
 }